@@ -0,0 +1,243 @@
+//! Minimal end-to-end certificate proof workflow:
+//! - initialize the DLOG committer keys;
+//! - generate a test proof (and its vk) with the built-in test circuit;
+//! - build a CommitmentTree out of the sidechains described by a JSON input,
+//!   using the vk of the test circuit as certificate verification key;
+//! - compute the public inputs of a certificate proof for each sidechain;
+//! - verify the test proofs singly and in batch;
+//! - emit all the artifacts as hex strings.
+//!
+//! Run it with:
+//! ```bash
+//! cargo run --release --example certificate_proof [path/to/input.json]
+//! ```
+//! If no input is given, `examples/certificate_proof.json` is used.
+//!
+//! Please note: the test circuit just proves knowledge of two field elements `c`, `d`,
+//! so its proofs can't be bound to the certificate inputs computed above.
+
+use cctp_primitives::{
+    commitment_tree::CommitmentTree,
+    proving_system::{
+        init::{get_g1_key_hash, get_supported_max_segment_size, G1_UNIVERSAL_PARAMS},
+        init_dlog_keys,
+        verifier::{
+            batch_verifier::ZendooBatchVerifier, certificate::CertificateProofUserInputs,
+            verify_zendoo_proof_prepared, PreparedZendooVk, UserInputs,
+        },
+        ProvingSystem, ZendooProof, ZendooVerifierKey,
+    },
+    type_mapping::{Error, FieldElement, MC_PK_SIZE},
+    utils::{compute_sc_id, data_structures::BackwardTransfer, serialization::serialize_to_buffer},
+};
+use proof_systems::darlin::tests::simple_marlin::generate_test_data;
+use serde_json::Value;
+use std::{convert::TryInto, fs};
+
+const DEFAULT_INPUT: &str = include_str!("certificate_proof.json");
+
+const SEGMENT_SIZE: usize = 1 << 9;
+
+const NUM_PROOFS: usize = 2;
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn from_hex<const N: usize>(value: &Value, field: &str) -> Result<[u8; N], Error> {
+    let hex = value[field]
+        .as_str()
+        .ok_or(format!("Missing hex string \"{}\"", field))?;
+    if hex.len() != 2 * N || !hex.is_ascii() {
+        Err(format!("\"{}\" must be {} hex encoded bytes", field, N))?
+    }
+    let bytes = (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(bytes.try_into().unwrap())
+}
+
+fn get_u64(value: &Value, field: &str) -> Result<u64, Error> {
+    Ok(value[field]
+        .as_u64()
+        .ok_or(format!("Missing integer \"{}\"", field))?)
+}
+
+fn get_u32(value: &Value, field: &str) -> Result<u32, Error> {
+    Ok(get_u64(value, field)?.try_into()?)
+}
+
+fn get_array<'a>(value: &'a Value, field: &str) -> Result<&'a Vec<Value>, Error> {
+    Ok(value[field]
+        .as_array()
+        .ok_or(format!("Missing array \"{}\"", field))?)
+}
+
+fn fe_to_hex(fe: &FieldElement) -> Result<String, Error> {
+    Ok(to_hex(&serialize_to_buffer(fe, None)?))
+}
+
+/// Build a CommitmentTree with the sidechains in `input`, returning it along with the
+/// ids of the sidechains.
+fn build_commitment_tree(
+    input: &Value,
+    cert_vk: &[u8],
+) -> Result<(CommitmentTree, Vec<FieldElement>), Error> {
+    let mut cmt = CommitmentTree::create();
+    let mut sc_ids = Vec::new();
+
+    for sc in get_array(input, "sidechains")? {
+        let tx_hash = from_hex::<32>(sc, "tx_hash")?;
+        let out_idx = get_u32(sc, "out_idx")?;
+        let sc_id = compute_sc_id(&tx_hash, out_idx)?;
+
+        if !cmt.add_scc(
+            &sc_id,
+            get_u64(sc, "amount")?,
+            &from_hex::<32>(sc, "pub_key")?,
+            &tx_hash,
+            out_idx,
+            get_u32(sc, "withdrawal_epoch_length")?,
+            0,
+            None,
+            None,
+            0,
+            0,
+            None,
+            None,
+            cert_vk,
+            None,
+        ) {
+            Err(format!("Unable to add sidechain {}", fe_to_hex(&sc_id)?))?
+        }
+
+        for fwt in get_array(sc, "fwts")? {
+            if !cmt.add_fwt(
+                &sc_id,
+                get_u64(fwt, "amount")?,
+                &from_hex::<32>(fwt, "pub_key")?,
+                &from_hex::<MC_PK_SIZE>(fwt, "mc_return_address")?,
+                &from_hex::<32>(fwt, "tx_hash")?,
+                get_u32(fwt, "out_idx")?,
+            ) {
+                Err(format!(
+                    "Unable to add fwt to sidechain {}",
+                    fe_to_hex(&sc_id)?
+                ))?
+            }
+        }
+
+        for bwtr in get_array(sc, "bwtrs")? {
+            if !cmt.add_bwtr(
+                &sc_id,
+                get_u64(bwtr, "sc_fee")?,
+                vec![],
+                &from_hex::<MC_PK_SIZE>(bwtr, "mc_destination_address")?,
+                &from_hex::<32>(bwtr, "tx_hash")?,
+                get_u32(bwtr, "out_idx")?,
+            ) {
+                Err(format!(
+                    "Unable to add bwtr to sidechain {}",
+                    fe_to_hex(&sc_id)?
+                ))?
+            }
+        }
+
+        sc_ids.push(sc_id);
+    }
+
+    Ok((cmt, sc_ids))
+}
+
+fn main() -> Result<(), Error> {
+    let input = match std::env::args().nth(1) {
+        Some(path) => fs::read_to_string(path)?,
+        None => DEFAULT_INPUT.to_owned(),
+    };
+    let input: Value = serde_json::from_str(&input)?;
+    let rng = &mut rand::thread_rng();
+
+    // Init keys
+    init_dlog_keys(ProvingSystem::CoboundaryMarlin, SEGMENT_SIZE)?;
+    assert_eq!(get_supported_max_segment_size(), Some(SEGMENT_SIZE));
+    println!("g1_key_hash: {}", to_hex(&get_g1_key_hash()?));
+
+    // Generate test proofs with the built-in test circuit
+    let (pcds, vks) = {
+        let params_g1 = G1_UNIVERSAL_PARAMS.read().unwrap();
+        generate_test_data(
+            SEGMENT_SIZE - 1,
+            SEGMENT_SIZE,
+            params_g1.as_ref().unwrap(),
+            NUM_PROOFS,
+            rng,
+        )
+    };
+    let vk = ZendooVerifierKey::CoboundaryMarlin(vks[0].clone());
+    let vk_bytes = serialize_to_buffer(&vk, Some(true))?;
+    println!("vk: {}", to_hex(&vk_bytes));
+
+    // Build the CommitmentTree
+    let (mut cmt, sc_ids) = build_commitment_tree(&input, &vk_bytes)?;
+    let commitment = cmt
+        .get_commitment()
+        .ok_or("Unable to compute the commitment")?;
+    println!("sc_tx_commitment: {}", fe_to_hex(&commitment)?);
+
+    // Certificate proof inputs, as if the certificates were created at the end of
+    // the epoch the CommitmentTree belongs to
+    let bt_list = vec![BackwardTransfer::default()];
+    for sc_id in sc_ids.iter() {
+        let cert_usr_ins = CertificateProofUserInputs {
+            constant: None,
+            sc_id,
+            epoch_number: 0,
+            quality: 1,
+            bt_list: Some(&bt_list),
+            custom_fields: None,
+            end_cumulative_sc_tx_commitment_tree_root: &commitment,
+            btr_fee: 0,
+            ft_min_amount: 0,
+            sc_prev_wcert_hash: None,
+        };
+        let cert_inputs = cert_usr_ins.get_circuit_inputs()?;
+        println!(
+            "sc_id: {}, cert_data_hash: {}",
+            fe_to_hex(sc_id)?,
+            fe_to_hex(&cert_inputs[0])?
+        );
+    }
+
+    // Verify the proofs singly, preparing the vk only once, and in batch
+    let prepared_vk = PreparedZendooVk::prepare(vk)?;
+    let mut batch_verifier = ZendooBatchVerifier::create();
+    for (id, pcd) in pcds.iter().enumerate() {
+        let proof = ZendooProof::CoboundaryMarlin(pcd.proof.clone());
+        println!(
+            "proof {}: {}, inputs: [{}]",
+            id,
+            to_hex(&serialize_to_buffer(&proof, Some(true))?),
+            pcd.usr_ins
+                .iter()
+                .map(fe_to_hex)
+                .collect::<Result<Vec<_>, _>>()?
+                .join(", ")
+        );
+
+        let verified =
+            verify_zendoo_proof_prepared(pcd.usr_ins.as_slice(), &proof, &prepared_vk, Some(rng))?;
+        println!("proof {} verified: {}", id, verified);
+
+        batch_verifier.add_zendoo_proof_verifier_data_prepared(
+            id as u32,
+            pcd.usr_ins.clone(),
+            proof,
+            &prepared_vk,
+        )?;
+    }
+    let verified = batch_verifier.batch_verify_all(rng)?;
+    println!("batch verified: {}", verified);
+
+    Ok(())
+}
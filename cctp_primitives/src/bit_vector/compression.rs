@@ -2,12 +2,16 @@
 //!
 //! `compression` exposes functions to compress and decompress bit vectors.
 //! Currently supported compression algorithms are Bzip2 and Gzip.
+//! Compressed bit vectors may optionally be wrapped in a framed container,
+//! declaring their uncompressed length and a checksum of their payload.
 
 use std::convert::{TryFrom, TryInto};
+use std::fmt::{Display, Formatter};
 use std::io::{Read, Write};
+use std::str::FromStr;
 
 use bzip2::read::{BzDecoder, BzEncoder};
-use flate2::{read::GzDecoder, write::GzEncoder, Compression as GzipCompression};
+use flate2::{read::GzDecoder, write::GzEncoder, Compression as GzipCompression, Crc};
 
 use crate::type_mapping::Error;
 
@@ -18,16 +22,51 @@ const DECOMPRESSION_CHUNK_SIZE: usize = 1024;
 /// When decompressing, if the size exceeds this threshold, an error is returned.
 /// This value is set around to the double of the size limit for uncompressed
 /// bit vectors on mainchain side.
-const MAX_DECOMPRESSION_SIZE: usize = 1024 * 260; // 260 KB
+pub(crate) const MAX_DECOMPRESSION_SIZE: usize = 1024 * 260; // 260 KB
+
+/// Limits to the resources spent decompressing a bit vector, e.g. one received from an untrusted peer.
+/// Besides the size of the output, the number of chunks read from the decompressor is capped: inputs
+/// crafted to maximize the CPU spent per output byte yield short chunks, so they hit this limit even
+/// if their output is below the size cap.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct DecompressionBudget {
+    /// Maximum size [bytes] of the decompressed bit vector, further capped by `MAX_DECOMPRESSION_SIZE`
+    pub max_output: usize,
+    /// Maximum number of chunks (of `DECOMPRESSION_CHUNK_SIZE` bytes at most) read from the decompressor
+    pub max_cpu_chunks: usize,
+}
+
+impl Default for DecompressionBudget {
+    /// The maximum allowed size, read in (twice) as many chunks as needed when each one is full.
+    fn default() -> Self {
+        Self {
+            max_output: MAX_DECOMPRESSION_SIZE,
+            max_cpu_chunks: 2 * (MAX_DECOMPRESSION_SIZE / DECOMPRESSION_CHUNK_SIZE + 1),
+        }
+    }
+}
+
+/// Magic bytes identifying a framed compressed bit vector.
+pub const FRAMED_BIT_VECTOR_MAGIC: [u8; 4] = *b"BVFR";
+
+/// Current version of the framed compressed bit vector container.
+pub const FRAMED_BIT_VECTOR_VERSION: u8 = 1;
+
+/// Size [bytes] of the header of a framed compressed bit vector, excluding the algorithm byte:
+/// magic (4), version (1), payload algorithm (1), uncompressed length (4), payload CRC32 (4).
+pub const FRAMED_BIT_VECTOR_HEADER_SIZE: usize = 14;
 
 /// Available compression algorithms.
 /// The ffi repr(C) tag has been added here because this enum must be exported from mc-cryptolib.
-#[derive(Copy, Clone)]
+/// `Framed` identifies a bit vector wrapped in the container produced by
+/// `compress_bit_vector_framed()`, and can't be passed to `compress_bit_vector()`.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
 #[repr(C)]
 pub enum CompressionAlgorithm {
     Uncompressed,
     Bzip2,
     Gzip,
+    Framed,
 }
 
 impl TryFrom<u8> for CompressionAlgorithm {
@@ -40,11 +79,59 @@ impl TryFrom<u8> for CompressionAlgorithm {
             }
             x if x == CompressionAlgorithm::Bzip2 as u8 => Ok(CompressionAlgorithm::Bzip2),
             x if x == CompressionAlgorithm::Gzip as u8 => Ok(CompressionAlgorithm::Gzip),
+            x if x == CompressionAlgorithm::Framed as u8 => Ok(CompressionAlgorithm::Framed),
             _ => Err(()),
         }
     }
 }
 
+impl CompressionAlgorithm {
+    /// Return all the supported compression algorithms, ordered by their byte.
+    pub fn all() -> &'static [CompressionAlgorithm] {
+        &[
+            CompressionAlgorithm::Uncompressed,
+            CompressionAlgorithm::Bzip2,
+            CompressionAlgorithm::Gzip,
+            CompressionAlgorithm::Framed,
+        ]
+    }
+
+    /// Return true if `byte` identifies a supported compression algorithm, i.e. if a bit vector
+    /// starting with `byte` may be passed to the decompression functions (framed ones only to
+    /// `decompress_bit_vector_framed()` and `decompress_bit_vector_with_budget()`).
+    pub fn is_supported(byte: u8) -> bool {
+        Self::try_from(byte).is_ok()
+    }
+
+    /// Return the symbolic name of `self`, as accepted by `from_str()`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            CompressionAlgorithm::Uncompressed => "uncompressed",
+            CompressionAlgorithm::Bzip2 => "bzip2",
+            CompressionAlgorithm::Gzip => "gzip",
+            CompressionAlgorithm::Framed => "framed",
+        }
+    }
+}
+
+impl Display for CompressionAlgorithm {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for CompressionAlgorithm {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::all()
+            .iter()
+            .find(|algorithm| algorithm.name() == s)
+            .copied()
+            .ok_or_else(|| format!("Invalid compression algorithm: {}", s).into())
+    }
+}
+
 /// Compresses `raw_bit vector` (represented as a byte vector)
 /// by using the specified compression `algorithm`.
 /// The resulting compressed bit vector has an additional first byte
@@ -77,6 +164,11 @@ pub fn compress_bit_vector(
     raw_bit_vector: &[u8],
     algorithm: CompressionAlgorithm,
 ) -> Result<Vec<u8>, Error> {
+    let _span = trace_span!(
+        "cctp_compress_bit_vector",
+        algorithm = algorithm as u8,
+        size = raw_bit_vector.len()
+    );
     let compressed_bit_vector_result;
 
     log::debug!("Compressing bit vector...");
@@ -95,6 +187,9 @@ pub fn compress_bit_vector(
             compressed_bit_vector_result = bzip2_compress(raw_bit_vector)
         }
         CompressionAlgorithm::Gzip => compressed_bit_vector_result = gzip_compress(raw_bit_vector),
+        CompressionAlgorithm::Framed => {
+            Err("Framed bit vectors must be created with compress_bit_vector_framed")?
+        }
     }
 
     if let Ok(compressed_bit_vector_result) = compressed_bit_vector_result {
@@ -107,12 +202,81 @@ pub fn compress_bit_vector(
     }
 }
 
+/// Compresses `raw_bit_vector` by using the specified compression `algorithm`, like
+/// `compress_bit_vector()`, and wraps the result in a framed container:
+/// [Framed algorithm byte | magic | version | algorithm | uncompressed length (LE u32) | CRC32 of payload (LE u32) | payload].
+/// Framed bit vectors are decompressed by `decompress_bit_vector_framed()` only (and by
+/// `decompress_bit_vector_with_budget()`), and truncated or corrupted ones are rejected
+/// before decompressing them.
+///
+/// # Examples
+///
+/// ```
+/// use cctp_primitives::bit_vector::compression::*;
+///
+/// let bit_vector: Vec<u8> = (0..100).collect();
+///
+/// let framed_bit_vector = compress_bit_vector_framed(&bit_vector, CompressionAlgorithm::Gzip).unwrap();
+/// assert_eq!(framed_bit_vector[0], CompressionAlgorithm::Framed as u8);
+/// let decompressed_bit_vector = decompress_bit_vector_framed(&framed_bit_vector, bit_vector.len()).unwrap();
+/// assert_eq!(bit_vector, decompressed_bit_vector);
+/// ```
+pub fn compress_bit_vector_framed(
+    raw_bit_vector: &[u8],
+    algorithm: CompressionAlgorithm,
+) -> Result<Vec<u8>, Error> {
+    if raw_bit_vector.len() > u32::MAX as usize {
+        Err("Bit vector too big to be framed")?
+    }
+
+    // Includes the algorithm byte
+    let payload = compress_bit_vector(raw_bit_vector, algorithm)?;
+
+    let mut crc = Crc::new();
+    crc.update(&payload[1..]);
+
+    let mut framed_bit_vector = Vec::with_capacity(FRAMED_BIT_VECTOR_HEADER_SIZE + payload.len());
+    framed_bit_vector.push(CompressionAlgorithm::Framed as u8);
+    framed_bit_vector.extend_from_slice(&FRAMED_BIT_VECTOR_MAGIC);
+    framed_bit_vector.push(FRAMED_BIT_VECTOR_VERSION);
+    framed_bit_vector.push(payload[0]);
+    framed_bit_vector.extend_from_slice(&(raw_bit_vector.len() as u32).to_le_bytes());
+    framed_bit_vector.extend_from_slice(&crc.sum().to_le_bytes());
+    framed_bit_vector.extend_from_slice(&payload[1..]);
+
+    Ok(framed_bit_vector)
+}
+
+/// Decompresses the framed `compressed_bit_vector` created by `compress_bit_vector_framed()`,
+/// requiring the resulting vector to have `expected_size` bytes.
+///
+/// # Errors
+/// Returns an error if `compressed_bit_vector` is not framed, if its header is malformed,
+/// if its declared uncompressed length is different than `expected_size`, or if the checksum
+/// of its payload doesn't match; all these checks are performed before decompressing it.
+pub fn decompress_bit_vector_framed(
+    compressed_bit_vector: &[u8],
+    expected_size: usize,
+) -> Result<Vec<u8>, Error> {
+    match compressed_bit_vector.first().map(|&b| b.try_into()) {
+        Some(Ok(CompressionAlgorithm::Framed)) => decompress_bit_vector_with_limits(
+            compressed_bit_vector,
+            Some(expected_size),
+            MAX_DECOMPRESSION_SIZE,
+            usize::MAX,
+            true,
+        ),
+        _ => Err("Bit vector is not framed")?,
+    }
+}
+
 /// Decompresses `compressed_bit vector` (represented as a byte vector slice)
 /// by using the compression `algorithm` specified as the first byte of the vector.
 /// The function requires the resulting vector to have `expected_size` bytes.
 ///
 /// # Errors
-/// Returns an error if the decompressed size is different than `expected size` (bytes).
+/// Returns an error if the decompressed size is different than `expected size` (bytes),
+/// or if `compressed_bit_vector` is framed: use `decompress_bit_vector_framed()` for them.
 ///
 /// # Examples
 ///
@@ -150,10 +314,91 @@ pub fn decompress_bit_vector_without_checks(
     decompress_bit_vector_with_opt_checks(compressed_bit_vector, None)
 }
 
+/// Same as `decompress_bit_vector()`, but the resources spent decompressing `compressed_bit_vector`
+/// are limited by `budget`, as befits bit vectors received from untrusted peers.
+/// Framed bit vectors are accepted, too.
+///
+/// # Errors
+/// Returns an error if `expected_size` exceeds `budget.max_output`, or if the decompression reads
+/// more than `budget.max_cpu_chunks` chunks, besides the cases `decompress_bit_vector()` fails in.
+///
+/// # Examples
+///
+/// ```
+/// use cctp_primitives::bit_vector::compression::*;
+///
+/// let bit_vector: Vec<u8> = (0..100).collect();
+///
+/// let compressed_bit_vector = compress_bit_vector(&bit_vector, CompressionAlgorithm::Bzip2).unwrap();
+/// let budget = DecompressionBudget { max_output: 100, max_cpu_chunks: 2 };
+/// let decompressed_bit_vector = decompress_bit_vector_with_budget(&compressed_bit_vector, bit_vector.len(), &budget).unwrap();
+/// assert_eq!(bit_vector, decompressed_bit_vector);
+///
+/// let budget = DecompressionBudget { max_output: 100, max_cpu_chunks: 1 };
+/// assert!(decompress_bit_vector_with_budget(&compressed_bit_vector, bit_vector.len(), &budget).is_err());
+/// ```
+pub fn decompress_bit_vector_with_budget(
+    compressed_bit_vector: &[u8],
+    expected_size: usize,
+    budget: &DecompressionBudget,
+) -> Result<Vec<u8>, Error> {
+    decompress_bit_vector_with_limits(
+        compressed_bit_vector,
+        Some(expected_size),
+        budget.max_output.min(MAX_DECOMPRESSION_SIZE),
+        budget.max_cpu_chunks,
+        true,
+    )
+}
+
+/// Decompresses `compressed_bit_vector` as `decompress_bit_vector_without_checks()` does, but the
+/// decompression fails as soon as the decompressed size exceeds `max_size` bytes.
+/// A bit vector shorter than `max_size` is returned as it is, so that the caller can tell its size.
+pub(crate) fn decompress_bit_vector_up_to(
+    compressed_bit_vector: &[u8],
+    max_size: usize,
+) -> Result<Vec<u8>, Error> {
+    decompress_bit_vector_with_limits(
+        compressed_bit_vector,
+        None,
+        max_size.min(MAX_DECOMPRESSION_SIZE),
+        usize::MAX,
+        false,
+    )
+}
+
 fn decompress_bit_vector_with_opt_checks(
     compressed_bit_vector: &[u8],
     expected_size_opt: Option<usize>,
 ) -> Result<Vec<u8>, Error> {
+    decompress_bit_vector_with_limits(
+        compressed_bit_vector,
+        expected_size_opt,
+        MAX_DECOMPRESSION_SIZE,
+        usize::MAX,
+        false,
+    )
+}
+
+// Framed bit vectors are decompressed only if `allow_framed` is set: the legacy (consensus)
+// decompression functions must keep rejecting them, as they did before framing was introduced.
+fn decompress_bit_vector_with_limits(
+    compressed_bit_vector: &[u8],
+    expected_size_opt: Option<usize>,
+    max_output: usize,
+    max_chunks: usize,
+    allow_framed: bool,
+) -> Result<Vec<u8>, Error> {
+    if compressed_bit_vector.is_empty() {
+        Err("Empty compressed bit vector")?
+    }
+
+    let _span = trace_span!(
+        "cctp_decompress_bit_vector",
+        algorithm = compressed_bit_vector[0],
+        compressed_size = compressed_bit_vector.len(),
+        expected_size = ?expected_size_opt
+    );
     log::debug!("Decompressing bit vector...");
     log::debug!(
         "Algorithm: {}, size: {}, expected decompressed size: {:?} (check: {}), address: {:p}",
@@ -164,33 +409,44 @@ fn decompress_bit_vector_with_opt_checks(
         compressed_bit_vector
     );
 
-    let mut max_decompressed_size: usize = MAX_DECOMPRESSION_SIZE;
+    let mut max_decompressed_size: usize = max_output;
     log::debug!(
-        "MAX_DECOMPRESSION_SIZE: {}, expected: {:?}",
-        MAX_DECOMPRESSION_SIZE,
-        expected_size_opt
+        "Max decompression size: {}, expected: {:?}, max chunks: {}",
+        max_output,
+        expected_size_opt,
+        max_chunks
     );
 
     if let Some(expected_size_opt) = expected_size_opt {
         max_decompressed_size = expected_size_opt;
 
-        if max_decompressed_size > MAX_DECOMPRESSION_SIZE {
+        if max_decompressed_size > max_output {
             Err(format!(
                 "The expected uncompressed size {} exceeds the maximum allowed size {}",
-                max_decompressed_size, MAX_DECOMPRESSION_SIZE
+                max_decompressed_size, max_output
             ))?
         }
     }
 
     let mut raw_bit_vector_result = match compressed_bit_vector[0].try_into() {
         Ok(CompressionAlgorithm::Uncompressed) => Ok(compressed_bit_vector[1..].to_vec()),
-        Ok(CompressionAlgorithm::Bzip2) => {
-            bzip2_decompress(&compressed_bit_vector[1..], max_decompressed_size)
-        }
-        Ok(CompressionAlgorithm::Gzip) => {
-            gzip_decompress(&compressed_bit_vector[1..], max_decompressed_size)
-        }
-        Err(_) => Err("Compression algorithm not supported")?,
+        Ok(CompressionAlgorithm::Bzip2) => bzip2_decompress(
+            &compressed_bit_vector[1..],
+            max_decompressed_size,
+            max_chunks,
+        ),
+        Ok(CompressionAlgorithm::Gzip) => gzip_decompress(
+            &compressed_bit_vector[1..],
+            max_decompressed_size,
+            max_chunks,
+        ),
+        Ok(CompressionAlgorithm::Framed) if allow_framed => framed_decompress(
+            &compressed_bit_vector[1..],
+            max_decompressed_size,
+            expected_size_opt,
+            max_chunks,
+        ),
+        Ok(CompressionAlgorithm::Framed) | Err(_) => Err("Compression algorithm not supported")?,
     }?;
 
     log::debug!("Decompressed size: {}", raw_bit_vector_result.len());
@@ -210,6 +466,69 @@ fn decompress_bit_vector_with_opt_checks(
     Ok(raw_bit_vector_result)
 }
 
+fn framed_decompress(
+    framed_bit_vector: &[u8],
+    max_decompressed_size: usize,
+    expected_size_opt: Option<usize>,
+    max_chunks: usize,
+) -> Result<Vec<u8>, Error> {
+    if framed_bit_vector.len() < FRAMED_BIT_VECTOR_HEADER_SIZE {
+        Err("Truncated framed bit vector header")?
+    }
+    let (header, payload) = framed_bit_vector.split_at(FRAMED_BIT_VECTOR_HEADER_SIZE);
+
+    if header[..4] != FRAMED_BIT_VECTOR_MAGIC {
+        Err("Invalid framed bit vector magic")?
+    }
+    if header[4] != FRAMED_BIT_VECTOR_VERSION {
+        Err(format!(
+            "Unsupported framed bit vector version {}",
+            header[4]
+        ))?
+    }
+
+    let declared_size = u32::from_le_bytes(header[6..10].try_into()?) as usize;
+    if declared_size > max_decompressed_size {
+        Err(format!(
+            "Declared uncompressed size {} exceeds the maximum allowed size {}",
+            declared_size, max_decompressed_size
+        ))?
+    }
+    if let Some(expected_size) = expected_size_opt {
+        if declared_size != expected_size {
+            Err(format!(
+                "Wrong declared bit vector size. Expected {} bytes, found {} bytes",
+                expected_size, declared_size
+            ))?
+        }
+    }
+
+    let mut crc = Crc::new();
+    crc.update(payload);
+    if crc.sum() != u32::from_le_bytes(header[10..14].try_into()?) {
+        Err("Framed bit vector checksum mismatch")?
+    }
+
+    let raw_bit_vector = match header[5].try_into() {
+        Ok(CompressionAlgorithm::Uncompressed) => Ok(payload.to_vec()),
+        Ok(CompressionAlgorithm::Bzip2) => bzip2_decompress(payload, declared_size, max_chunks),
+        Ok(CompressionAlgorithm::Gzip) => gzip_decompress(payload, declared_size, max_chunks),
+        Ok(CompressionAlgorithm::Framed) | Err(_) => {
+            Err("Framed bit vector compression algorithm not supported")?
+        }
+    }?;
+
+    if raw_bit_vector.len() != declared_size {
+        Err(format!(
+            "Wrong framed bit vector size. Declared {} bytes, found {} bytes",
+            declared_size,
+            raw_bit_vector.len()
+        ))?
+    }
+
+    Ok(raw_bit_vector)
+}
+
 fn bzip2_compress(bit_vector: &[u8]) -> Result<Vec<u8>, Error> {
     let mut compressor = BzEncoder::new(bit_vector, bzip2::Compression::best());
     let mut bzip_compressed = Vec::new();
@@ -221,30 +540,14 @@ fn bzip2_compress(bit_vector: &[u8]) -> Result<Vec<u8>, Error> {
 fn bzip2_decompress(
     compressed_bit_vector: &[u8],
     max_decompressed_size: usize,
+    max_chunks: usize,
 ) -> Result<Vec<u8>, Error> {
-    let mut uncompressed_bitvector = Vec::with_capacity(max_decompressed_size);
-    let mut decompressor = BzDecoder::new(compressed_bit_vector);
-    let mut fixed_array = [0; DECOMPRESSION_CHUNK_SIZE];
-
-    // Uncompress data in chunks of "DECOMPRESSION_CHUNK_SIZE" bytes, so that the processing can be stopped
-    // as soon as the uncompressed size exceeds the "max_decompressed_size" threshold.
-    loop {
-        let read_size = decompressor.read(&mut fixed_array)?;
-        uncompressed_bitvector.extend_from_slice(&fixed_array[..read_size]);
-
-        if uncompressed_bitvector.len() > max_decompressed_size {
-            Err(format!(
-                "Max decompressed size {} exceeded {} while processing [Bzip2]",
-                max_decompressed_size,
-                uncompressed_bitvector.len()
-            ))?
-        }
-        if read_size == 0 {
-            break;
-        }
-    }
-
-    Ok(uncompressed_bitvector)
+    read_decompressed(
+        BzDecoder::new(compressed_bit_vector),
+        max_decompressed_size,
+        max_chunks,
+        "Bzip2",
+    )
 }
 
 fn gzip_compress(bit_vector: &[u8]) -> Result<Vec<u8>, Error> {
@@ -258,22 +561,48 @@ fn gzip_compress(bit_vector: &[u8]) -> Result<Vec<u8>, Error> {
 fn gzip_decompress(
     compressed_bit_vector: &[u8],
     max_decompressed_size: usize,
+    max_chunks: usize,
+) -> Result<Vec<u8>, Error> {
+    read_decompressed(
+        GzDecoder::new(compressed_bit_vector),
+        max_decompressed_size,
+        max_chunks,
+        "Gzip",
+    )
+}
+
+/// Read the output of `decompressor` in chunks of `DECOMPRESSION_CHUNK_SIZE` bytes at most.
+fn read_decompressed<R: Read>(
+    mut decompressor: R,
+    max_decompressed_size: usize,
+    max_chunks: usize,
+    algorithm_name: &str,
 ) -> Result<Vec<u8>, Error> {
     let mut uncompressed_bitvector = Vec::with_capacity(max_decompressed_size);
-    let mut decompressor = GzDecoder::new(compressed_bit_vector);
     let mut fixed_array = [0; DECOMPRESSION_CHUNK_SIZE];
+    let mut chunks = 0;
 
     // Uncompress data in chunks of "DECOMPRESSION_CHUNK_SIZE" bytes, so that the processing can be stopped
-    // as soon as the uncompressed size exceeds the "max_decompressed_size" threshold.
+    // as soon as the uncompressed size exceeds the "max_decompressed_size" threshold,
+    // or the number of chunks read exceeds the "max_chunks" one.
     loop {
+        if chunks == max_chunks {
+            Err(format!(
+                "Max decompression chunks {} exceeded while processing [{}]",
+                max_chunks, algorithm_name
+            ))?
+        }
+        chunks += 1;
+
         let read_size = decompressor.read(&mut fixed_array)?;
         uncompressed_bitvector.extend_from_slice(&fixed_array[..read_size]);
 
         if uncompressed_bitvector.len() > max_decompressed_size {
             Err(format!(
-                "Max decompressed size {} exceeded {} while processing [Gzip]",
+                "Max decompressed size {} exceeded {} while processing [{}]",
                 max_decompressed_size,
-                uncompressed_bitvector.len()
+                uncompressed_bitvector.len(),
+                algorithm_name
             ))?
         }
         if read_size == 0 {
@@ -427,6 +756,174 @@ mod test {
         );
     }
 
+    #[test]
+    fn framed_bit_vector_compression() {
+        let seed: u64 = rand::thread_rng().gen();
+        let original_bit_vector: Vec<u8> = generate_random_bit_vector(seed);
+
+        for &algorithm in [
+            CompressionAlgorithm::Uncompressed,
+            CompressionAlgorithm::Bzip2,
+            CompressionAlgorithm::Gzip,
+        ]
+        .iter()
+        {
+            let framed_bit_vector =
+                compress_bit_vector_framed(&original_bit_vector, algorithm).unwrap();
+            assert_eq!(framed_bit_vector[0], CompressionAlgorithm::Framed as u8);
+            assert_eq!(framed_bit_vector[1..5], FRAMED_BIT_VECTOR_MAGIC);
+            assert_eq!(framed_bit_vector[6], algorithm as u8);
+
+            assert_eq!(
+                decompress_bit_vector_framed(&framed_bit_vector, original_bit_vector.len())
+                    .unwrap(),
+                original_bit_vector
+            );
+
+            // Framed bit vectors are not understood by the legacy decompression functions
+            for result in [
+                decompress_bit_vector(&framed_bit_vector, original_bit_vector.len()),
+                decompress_bit_vector_without_checks(&framed_bit_vector),
+            ]
+            .iter()
+            {
+                assert_eq!(
+                    result.as_ref().unwrap_err().to_string(),
+                    "Compression algorithm not supported"
+                );
+            }
+
+            // Wrong expected size
+            assert!(decompress_bit_vector_framed(
+                &framed_bit_vector,
+                original_bit_vector.len() + 1
+            )
+            .is_err());
+
+            // Truncated payload and header
+            assert!(decompress_bit_vector_framed(
+                &framed_bit_vector[..framed_bit_vector.len() - 1],
+                original_bit_vector.len()
+            )
+            .is_err());
+            assert!(decompress_bit_vector_framed(
+                &framed_bit_vector[..FRAMED_BIT_VECTOR_HEADER_SIZE],
+                original_bit_vector.len()
+            )
+            .is_err());
+
+            // Corrupted magic, version, declared length and payload
+            for &i in [1, 5, 7, framed_bit_vector.len() - 1].iter() {
+                let mut corrupted = framed_bit_vector.clone();
+                corrupted[i] ^= 0x01;
+                assert!(
+                    decompress_bit_vector_framed(&corrupted, original_bit_vector.len()).is_err()
+                );
+            }
+        }
+
+        // Nested frames and non framed bit vectors are not accepted
+        assert!(
+            compress_bit_vector_framed(&original_bit_vector, CompressionAlgorithm::Framed).is_err()
+        );
+        let compressed_bit_vector =
+            compress_bit_vector(&original_bit_vector, CompressionAlgorithm::Gzip).unwrap();
+        assert!(
+            decompress_bit_vector_framed(&compressed_bit_vector, original_bit_vector.len())
+                .is_err()
+        );
+        assert!(decompress_bit_vector_framed(&[], 0).is_err());
+    }
+
+    #[test]
+    fn budget_bit_vector_decompression() {
+        let bit_vector = vec![0u8; 10 * DECOMPRESSION_CHUNK_SIZE];
+        let default_budget = DecompressionBudget::default();
+
+        for &algorithm in [CompressionAlgorithm::Bzip2, CompressionAlgorithm::Gzip].iter() {
+            for compressed_bit_vector in [
+                compress_bit_vector(&bit_vector, algorithm).unwrap(),
+                compress_bit_vector_framed(&bit_vector, algorithm).unwrap(),
+            ]
+            .iter()
+            {
+                // Enough budget
+                assert_eq!(
+                    decompress_bit_vector_with_budget(
+                        compressed_bit_vector,
+                        bit_vector.len(),
+                        &default_budget
+                    )
+                    .unwrap(),
+                    bit_vector,
+                    "{} error",
+                    algorithm
+                );
+
+                // Too few chunks, even if the size is below the cap
+                let budget = DecompressionBudget {
+                    max_cpu_chunks: 5,
+                    ..default_budget
+                };
+                assert!(
+                    decompress_bit_vector_with_budget(
+                        compressed_bit_vector,
+                        bit_vector.len(),
+                        &budget
+                    )
+                    .is_err(),
+                    "{} error",
+                    algorithm
+                );
+
+                // Expected size above the cap
+                let budget = DecompressionBudget {
+                    max_output: bit_vector.len() - 1,
+                    ..default_budget
+                };
+                assert!(
+                    decompress_bit_vector_with_budget(
+                        compressed_bit_vector,
+                        bit_vector.len(),
+                        &budget
+                    )
+                    .is_err(),
+                    "{} error",
+                    algorithm
+                );
+            }
+        }
+
+        // The cap can't be raised above MAX_DECOMPRESSION_SIZE
+        let budget = DecompressionBudget {
+            max_output: usize::MAX,
+            ..default_budget
+        };
+        let compressed_bit_vector = compress_bit_vector(
+            &vec![0u8; MAX_DECOMPRESSION_SIZE + 1],
+            CompressionAlgorithm::Gzip,
+        )
+        .unwrap();
+        assert!(decompress_bit_vector_with_budget(
+            &compressed_bit_vector,
+            MAX_DECOMPRESSION_SIZE + 1,
+            &budget
+        )
+        .is_err());
+
+        // The input file contains a compressed bit vector of around 10 KB whose decompressed size is around 16 GB.
+        let compressed_bit_vector =
+            std::fs::read("./test/compression/16gb_bitvector_bzip2.dat").unwrap();
+        assert!(decompress_bit_vector_with_budget(
+            &compressed_bit_vector,
+            MAX_DECOMPRESSION_SIZE,
+            &default_budget
+        )
+        .is_err());
+
+        assert!(decompress_bit_vector_with_budget(&[], 0, &default_budget).is_err());
+    }
+
     /// Checks that the decompression function doesn't crash when provided with a small compressed bit vector
     /// that once decompressed "expands" to a huge size.
     #[test]
@@ -543,4 +1040,23 @@ mod test {
             "Gzip error"
         );
     }
+
+    #[test]
+    fn compression_algorithm_names() {
+        for (i, &algorithm) in CompressionAlgorithm::all().iter().enumerate() {
+            assert_eq!(algorithm as usize, i);
+            assert!(CompressionAlgorithm::is_supported(algorithm as u8));
+            assert_eq!(
+                algorithm,
+                CompressionAlgorithm::from_str(&algorithm.to_string()).unwrap()
+            );
+        }
+        assert!(!CompressionAlgorithm::is_supported(
+            CompressionAlgorithm::all().len() as u8
+        ));
+        assert!(!CompressionAlgorithm::is_supported(u8::MAX));
+        assert_eq!(CompressionAlgorithm::Gzip.to_string(), "gzip");
+        assert!(CompressionAlgorithm::from_str("Gzip").is_err());
+        assert!(CompressionAlgorithm::from_str("").is_err());
+    }
 }
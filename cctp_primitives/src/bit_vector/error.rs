@@ -0,0 +1,78 @@
+use std::{
+    error::Error,
+    fmt::{Debug, Display},
+};
+
+/// Reasons for which the commitment of a bit vector can't be computed.
+/// Allows to distinguish a bit vector violating its `BitVectorElementsConfig`
+/// from a bit vector whose data is corrupted.
+#[derive(Debug, PartialEq)]
+pub enum BitVectorCommitmentError {
+    /// The `BitVectorElementsConfig` itself can't describe a valid bit vector
+    InvalidConfig(String),
+    /// The compressed bit vector is bigger than the `max_compressed_byte_size` of the config
+    CompressedSizeExceeded { size: usize, max: usize },
+    /// The decompressed bit vector doesn't match the `bit_vector_size_bits` of the config
+    UncompressedSizeMismatch { size: usize, expected: usize },
+    /// The bit vector can't be decompressed or its Merkle root can't be computed
+    CorruptData(String),
+}
+
+/// Stable, C-ABI friendly codes of the `BitVectorCommitmentError` variants.
+/// Codes must never be changed or reused: new variants get new codes.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[repr(C)]
+pub enum BitVectorCommitmentErrorCode {
+    InvalidConfig = 300,
+    CompressedSizeExceeded = 301,
+    UncompressedSizeMismatch = 302,
+    CorruptData = 303,
+}
+
+impl BitVectorCommitmentError {
+    /// Return the stable code of this error.
+    pub fn code(&self) -> BitVectorCommitmentErrorCode {
+        match self {
+            BitVectorCommitmentError::InvalidConfig(_) => {
+                BitVectorCommitmentErrorCode::InvalidConfig
+            }
+            BitVectorCommitmentError::CompressedSizeExceeded { .. } => {
+                BitVectorCommitmentErrorCode::CompressedSizeExceeded
+            }
+            BitVectorCommitmentError::UncompressedSizeMismatch { .. } => {
+                BitVectorCommitmentErrorCode::UncompressedSizeMismatch
+            }
+            BitVectorCommitmentError::CorruptData(_) => BitVectorCommitmentErrorCode::CorruptData,
+        }
+    }
+
+    /// Returns true if the error is due to the bit vector not respecting its config.
+    pub fn is_config_violation(&self) -> bool {
+        !matches!(self, BitVectorCommitmentError::CorruptData(_))
+    }
+}
+
+impl Display for BitVectorCommitmentError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            BitVectorCommitmentError::InvalidConfig(err) => {
+                write!(f, "Invalid bit vector config: {}", err)
+            }
+            BitVectorCommitmentError::CompressedSizeExceeded { size, max } => write!(
+                f,
+                "Compressed bit vector size {} exceeds the maximum allowed size {}",
+                size, max
+            ),
+            BitVectorCommitmentError::UncompressedSizeMismatch { size, expected } => write!(
+                f,
+                "Uncompressed bit vector size {} differs from the expected size {}",
+                size, expected
+            ),
+            BitVectorCommitmentError::CorruptData(err) => {
+                write!(f, "Corrupted bit vector: {}", err)
+            }
+        }
+    }
+}
+
+impl Error for BitVectorCommitmentError {}
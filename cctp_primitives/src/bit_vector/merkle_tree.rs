@@ -2,13 +2,20 @@
 //!
 //! `merkle_tree` exposes functions to compute a bit vector Merkle tree.
 
-use super::compression;
-use crate::type_mapping::*;
+use super::{compression, error::BitVectorCommitmentError};
+use crate::{
+    type_mapping::*,
+    utils::{commitment_tree::hash_vec, data_structures::BitVectorElementsConfig},
+};
 
 use algebra::{log2, ToConstraintField};
 use primitives::merkle_tree::field_based_mht::FieldBasedMerkleTree;
 
 use bit_vec::BitVec;
+use std::collections::BTreeSet;
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
 
 /// Computes the root hash of the Merkle tree created as a representation
 /// of `uncompressed_bit_vector`.
@@ -28,6 +35,27 @@ use bit_vec::BitVec;
 pub fn merkle_root_from_bytes(
     uncompressed_bit_vector: &[u8],
 ) -> Result<algebra::Fp256<algebra::fields::tweedle::FrParameters>, Error> {
+    let _span = trace_span!(
+        "cctp_bit_vector_merkle_root",
+        size = uncompressed_bit_vector.len()
+    );
+    let leaves = leaves_from_bytes(uncompressed_bit_vector)?;
+    let merkle_tree_height = log2(leaves.len()) as usize;
+    let mut mt = GingerMHT::init(merkle_tree_height, leaves.len())?;
+
+    for leaf in leaves.into_iter() {
+        mt.append(leaf)?;
+    }
+
+    match mt.finalize_in_place()?.root() {
+        Some(x) => Ok(x),
+        None => Err("Unable to compute the merkle tree root hash")?,
+    }
+}
+
+/// Splits `uncompressed_bit_vector` in the leaves of its Merkle tree,
+/// checking that their number is a power of two.
+fn leaves_from_bytes(uncompressed_bit_vector: &[u8]) -> Result<Vec<FieldElement>, Error> {
     let bv = BitVec::from_bytes(&uncompressed_bit_vector);
     let bool_vector: Vec<bool> = bv.into_iter().collect();
 
@@ -36,7 +64,6 @@ pub fn merkle_root_from_bytes(
 
     let merkle_tree_height = log2(real_bit_vector_size / FIELD_CAPACITY) as usize;
     let num_leaves = 1 << merkle_tree_height;
-    let mut mt = GingerMHT::init(merkle_tree_height, num_leaves)?;
 
     let leaves = bool_vector[..real_bit_vector_size].to_field_elements()?;
 
@@ -44,14 +71,7 @@ pub fn merkle_root_from_bytes(
         Err("Merkle tree leaves count check failed")?
     }
 
-    for leaf in leaves.into_iter() {
-        mt.append(leaf)?;
-    }
-
-    match mt.finalize_in_place()?.root() {
-        Some(x) => Ok(x),
-        None => Err("Unable to compute the merkle tree root hash")?,
-    }
+    Ok(leaves)
 }
 
 /// Computes the root hash of the Merkle tree created as a representation
@@ -91,11 +111,295 @@ pub fn merkle_root_from_compressed_bytes_without_checks(
     merkle_root_from_bytes(&uncompressed_bit_vector)
 }
 
+/// Computes the commitment (i.e. the Merkle root) of the `compressed_bit_vector` of a
+/// certificate custom field, checking that it respects its `config`: this is the operation
+/// performed by the mainchain on each custom bit vector field of a certificate.
+///
+/// `compressed_bit_vector` must not be bigger than `config.max_compressed_byte_size` bytes,
+/// and it must decompress to exactly `config.bit_vector_size_bits` bits.
+/// The returned error allows to distinguish a bit vector violating the config
+/// from a corrupted one.
+///
+/// # Examples
+///
+/// ```
+/// use cctp_primitives::bit_vector::{compression::*, merkle_tree::*};
+/// use cctp_primitives::utils::data_structures::BitVectorElementsConfig;
+///
+/// let bit_vector: Vec<u8> = vec![0; 127];
+/// let compressed_bit_vector = compress_bit_vector(&bit_vector, CompressionAlgorithm::Gzip).unwrap();
+/// let config = BitVectorElementsConfig {
+///     bit_vector_size_bits: 1016,
+///     max_compressed_byte_size: 1000,
+/// };
+/// let commitment = compute_bit_vector_commitment(&compressed_bit_vector, &config).unwrap();
+/// assert_eq!(commitment, merkle_root_from_bytes(&bit_vector).unwrap());
+/// ```
+pub fn compute_bit_vector_commitment(
+    compressed_bit_vector: &[u8],
+    config: &BitVectorElementsConfig,
+) -> Result<FieldElement, BitVectorCommitmentError> {
+    let _span = trace_span!(
+        "cctp_compute_bit_vector_commitment",
+        compressed_size = compressed_bit_vector.len(),
+        bit_vector_size_bits = config.bit_vector_size_bits
+    );
+    let bit_vector_size_bits = config.bit_vector_size_bits as usize;
+    let max_compressed_byte_size = config.max_compressed_byte_size as usize;
+
+    // The bit vector must be made of a power of two number of full leaves, and be byte aligned
+    if bit_vector_size_bits == 0
+        || bit_vector_size_bits % 8 != 0
+        || bit_vector_size_bits % FIELD_CAPACITY != 0
+        || !(bit_vector_size_bits / FIELD_CAPACITY).is_power_of_two()
+    {
+        return Err(BitVectorCommitmentError::InvalidConfig(format!(
+            "bit_vector_size_bits {} must be a multiple of 8 and a power of two multiple of {}",
+            bit_vector_size_bits, FIELD_CAPACITY
+        )));
+    }
+
+    let expected_uncompressed_size = bit_vector_size_bits / 8;
+    if expected_uncompressed_size > compression::MAX_DECOMPRESSION_SIZE {
+        return Err(BitVectorCommitmentError::InvalidConfig(format!(
+            "bit_vector_size_bits {} exceeds the maximum allowed size of {} bytes",
+            bit_vector_size_bits,
+            compression::MAX_DECOMPRESSION_SIZE
+        )));
+    }
+
+    if compressed_bit_vector.len() > max_compressed_byte_size {
+        return Err(BitVectorCommitmentError::CompressedSizeExceeded {
+            size: compressed_bit_vector.len(),
+            max: max_compressed_byte_size,
+        });
+    }
+
+    if compressed_bit_vector.is_empty() {
+        return Err(BitVectorCommitmentError::CorruptData(
+            "Missing compression algorithm byte".to_owned(),
+        ));
+    }
+
+    // Decompression stops as soon as the expected size is exceeded, so a bit vector longer than
+    // expected is reported as corrupted, while a shorter one as not matching the config
+    let uncompressed_bit_vector =
+        compression::decompress_bit_vector_up_to(compressed_bit_vector, expected_uncompressed_size)
+            .map_err(|e| BitVectorCommitmentError::CorruptData(e.to_string()))?;
+
+    if uncompressed_bit_vector.len() != expected_uncompressed_size {
+        return Err(BitVectorCommitmentError::UncompressedSizeMismatch {
+            size: uncompressed_bit_vector.len(),
+            expected: expected_uncompressed_size,
+        });
+    }
+
+    merkle_root_from_bytes(&uncompressed_bit_vector)
+        .map_err(|e| BitVectorCommitmentError::CorruptData(e.to_string()))
+}
+
+/// Computes the commitments of the bit vectors of a certificate, each one paired with its config,
+/// as done by `compute_bit_vector_commitment()`. If the `parallel` feature is enabled, the bit vectors
+/// are processed concurrently, on the global rayon pool (sized according to the thread budget, see `cctp_init()`).
+///
+/// Decompressed bit vectors share a memory budget of `memory_budget` bytes: bit vectors are processed
+/// in groups of consecutive ones, such that the sum of their expected uncompressed sizes doesn't exceed
+/// the budget. A bit vector bigger than the whole budget is processed alone.
+///
+/// Returns the result of each bit vector, in the same order of `bit_vectors`.
+pub fn compute_bit_vector_commitments_batch(
+    bit_vectors: Vec<(Vec<u8>, BitVectorElementsConfig)>,
+    memory_budget: usize,
+) -> Vec<Result<FieldElement, BitVectorCommitmentError>> {
+    let mut results = Vec::with_capacity(bit_vectors.len());
+    let mut remaining = bit_vectors.as_slice();
+
+    while !remaining.is_empty() {
+        // Take as many bit vectors as the memory budget allows, and at least one
+        let mut used_memory = 0usize;
+        let group_len = remaining
+            .iter()
+            .take_while(|(_, config)| {
+                let required_memory = ((config.bit_vector_size_bits / 8) as usize)
+                    .min(compression::MAX_DECOMPRESSION_SIZE);
+                let fits = used_memory == 0 || used_memory + required_memory <= memory_budget;
+                if fits {
+                    used_memory += required_memory;
+                }
+                fits
+            })
+            .count();
+        let (group, rest) = remaining.split_at(group_len);
+
+        #[cfg(feature = "parallel")]
+        let group = group.par_iter();
+        #[cfg(not(feature = "parallel"))]
+        let group = group.iter();
+
+        results.extend(
+            group
+                .map(|(compressed_bit_vector, config)| {
+                    compute_bit_vector_commitment(compressed_bit_vector, config)
+                })
+                .collect::<Vec<_>>(),
+        );
+        remaining = rest;
+    }
+
+    results
+}
+
+/// Keeps all the nodes of the Merkle tree of a bit vector, so that, when only some of its
+/// leaves change (e.g. a contiguous range of the bit vector between two epochs), the new root
+/// can be obtained by re-hashing only the paths from the changed leaves to the root,
+/// instead of recomputing the whole tree.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BitVectorDeltaUpdater {
+    // nodes[0] contains the leaves, nodes[height] contains the root only
+    nodes: Vec<Vec<FieldElement>>,
+}
+
+impl BitVectorDeltaUpdater {
+    /// Builds the Merkle tree of `uncompressed_bit_vector`, whose root is the same
+    /// computed by `merkle_root_from_bytes()`.
+    pub fn from_bytes(uncompressed_bit_vector: &[u8]) -> Result<Self, Error> {
+        let leaves = leaves_from_bytes(uncompressed_bit_vector)?;
+
+        let mut nodes = vec![leaves];
+        while nodes[nodes.len() - 1].len() > 1 {
+            let level = nodes[nodes.len() - 1]
+                .chunks(2)
+                .map(|children| hash_vec(children.to_vec()))
+                .collect::<Result<Vec<_>, _>>()?;
+            nodes.push(level);
+        }
+
+        Ok(Self { nodes })
+    }
+
+    /// Returns the current root of the Merkle tree.
+    pub fn root(&self) -> FieldElement {
+        self.nodes[self.nodes.len() - 1][0]
+    }
+
+    /// Returns the number of leaves of the Merkle tree.
+    pub fn num_leaves(&self) -> usize {
+        self.nodes[0].len()
+    }
+
+    /// Returns the leaf at `index`, if any.
+    pub fn get_leaf(&self, index: usize) -> Option<FieldElement> {
+        self.nodes[0].get(index).copied()
+    }
+
+    /// Replaces the leaves of the tree whose root is `old_root`, according to `changes`,
+    /// a list of (leaf index, old leaf value, new leaf value), and returns the new root.
+    ///
+    /// # Errors
+    /// Returns an error, leaving the tree untouched, if `old_root` is not the current root,
+    /// or if some index is out of range or its old value doesn't match the current leaf.
+    pub fn update_leaves(
+        &mut self,
+        old_root: &FieldElement,
+        changes: &[(usize, FieldElement, FieldElement)],
+    ) -> Result<FieldElement, Error> {
+        if self.root() != *old_root {
+            Err("Old root doesn't match the current root of the bit vector tree")?
+        }
+
+        for (index, old_leaf, _) in changes.iter() {
+            match self.get_leaf(*index) {
+                Some(leaf) if leaf == *old_leaf => {}
+                Some(_) => Err(format!("Old value of leaf {} doesn't match", index))?,
+                None => Err(format!(
+                    "Leaf index {} out of range, the tree has {} leaves",
+                    index,
+                    self.num_leaves()
+                ))?,
+            }
+        }
+
+        // Compute all the updated nodes before modifying the tree
+        let mut updates = vec![changes
+            .iter()
+            .map(|(index, _, new_leaf)| (*index, *new_leaf))
+            .collect::<Vec<_>>()];
+        let mut dirty_indices = changes
+            .iter()
+            .map(|(index, _, _)| *index)
+            .collect::<BTreeSet<_>>();
+
+        for height in 1..self.nodes.len() {
+            let level_updates = &updates[height - 1];
+            let get_node = |index: usize| {
+                level_updates
+                    .iter()
+                    .rev()
+                    .find(|(i, _)| *i == index)
+                    .map(|(_, node)| *node)
+                    .unwrap_or(self.nodes[height - 1][index])
+            };
+
+            dirty_indices = dirty_indices.into_iter().map(|index| index / 2).collect();
+            let new_nodes = dirty_indices
+                .iter()
+                .map(|&index| {
+                    hash_vec(vec![get_node(2 * index), get_node(2 * index + 1)])
+                        .map(|node| (index, node))
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            updates.push(new_nodes);
+        }
+
+        for (height, level_updates) in updates.into_iter().enumerate() {
+            for (index, node) in level_updates.into_iter() {
+                self.nodes[height][index] = node;
+            }
+        }
+
+        Ok(self.root())
+    }
+
+    /// Replaces the bytes of the bit vector starting at `byte_offset` with `new_bytes` and
+    /// returns the new root, re-hashing only the leaves covering the replaced range.
+    /// `new_bit_vector` is the whole updated bit vector, as the leaves don't start at byte
+    /// boundaries.
+    pub fn update_range(
+        &mut self,
+        old_root: &FieldElement,
+        new_bit_vector: &[u8],
+        byte_offset: usize,
+        num_bytes: usize,
+    ) -> Result<FieldElement, Error> {
+        if num_bytes == 0 {
+            return Ok(self.root());
+        }
+        if byte_offset + num_bytes > new_bit_vector.len() {
+            Err("Updated range exceeds the bit vector size")?
+        }
+
+        let first_leaf = (byte_offset * 8) / FIELD_CAPACITY;
+        let last_leaf = ((byte_offset + num_bytes) * 8 - 1) / FIELD_CAPACITY;
+
+        let new_leaves = leaves_from_bytes(new_bit_vector)?;
+        if new_leaves.len() != self.num_leaves() {
+            Err("The updated bit vector has a different number of leaves")?
+        }
+
+        let changes = (first_leaf..=last_leaf.min(new_leaves.len() - 1))
+            .map(|index| (index, self.nodes[0][index], new_leaves[index]))
+            .collect::<Vec<_>>();
+
+        self.update_leaves(old_root, &changes)
+    }
+}
+
 #[cfg(test)]
 mod test {
 
     use super::*;
     use compression::{compress_bit_vector, CompressionAlgorithm};
+    use rand::Rng;
 
     use std::fmt::Write;
 
@@ -131,6 +435,17 @@ mod test {
         }
 
         assert!(merkle_root_from_compressed_bytes_without_checks(&bit_vector).is_ok());
+
+        // Framed bit vectors are rejected on the legacy path
+        let framed_bit_vector = compression::compress_bit_vector_framed(
+            &bit_vector[1..],
+            CompressionAlgorithm::Uncompressed,
+        )
+        .unwrap();
+        assert!(merkle_root_from_compressed_bytes_without_checks(&framed_bit_vector).is_err());
+        assert!(
+            merkle_root_from_compressed_bytes(&framed_bit_vector, bit_vector.len() - 1).is_err()
+        );
     }
 
     #[test]
@@ -192,6 +507,222 @@ mod test {
         assert!(root_hash != updated_root_hash);
     }
 
+    #[test]
+    fn check_compute_bit_vector_commitment() {
+        let bit_vector: Vec<u8> = (0..254).map(|i| i as u8).collect(); // 8 leaves
+        let config = BitVectorElementsConfig {
+            bit_vector_size_bits: (bit_vector.len() * 8) as u32,
+            max_compressed_byte_size: 1000,
+        };
+        let expected_root = merkle_root_from_bytes(&bit_vector).unwrap();
+
+        for &algorithm in [
+            CompressionAlgorithm::Uncompressed,
+            CompressionAlgorithm::Bzip2,
+            CompressionAlgorithm::Gzip,
+        ]
+        .iter()
+        {
+            let compressed_bit_vector = compress_bit_vector(&bit_vector, algorithm).unwrap();
+            assert_eq!(
+                compute_bit_vector_commitment(&compressed_bit_vector, &config).unwrap(),
+                expected_root
+            );
+        }
+
+        let compressed_bit_vector =
+            compress_bit_vector(&bit_vector, CompressionAlgorithm::Gzip).unwrap();
+
+        // Invalid configs
+        for &bit_vector_size_bits in [0u32, 254, 1016 * 3, 1016 * 4096].iter() {
+            let invalid_config = BitVectorElementsConfig {
+                bit_vector_size_bits,
+                ..config.clone()
+            };
+            let err =
+                compute_bit_vector_commitment(&compressed_bit_vector, &invalid_config).unwrap_err();
+            assert!(matches!(err, BitVectorCommitmentError::InvalidConfig(_)));
+            assert!(err.is_config_violation());
+        }
+
+        // Compressed bit vector too big
+        let small_config = BitVectorElementsConfig {
+            max_compressed_byte_size: (compressed_bit_vector.len() - 1) as u32,
+            ..config.clone()
+        };
+        assert_eq!(
+            compute_bit_vector_commitment(&compressed_bit_vector, &small_config).unwrap_err(),
+            BitVectorCommitmentError::CompressedSizeExceeded {
+                size: compressed_bit_vector.len(),
+                max: compressed_bit_vector.len() - 1
+            }
+        );
+
+        // Wrong uncompressed size
+        let other_config = BitVectorElementsConfig {
+            bit_vector_size_bits: config.bit_vector_size_bits * 2,
+            ..config.clone()
+        };
+        assert_eq!(
+            compute_bit_vector_commitment(&compressed_bit_vector, &other_config).unwrap_err(),
+            BitVectorCommitmentError::UncompressedSizeMismatch {
+                size: bit_vector.len(),
+                expected: bit_vector.len() * 2
+            }
+        );
+
+        // Longer bit vectors are not decompressed past the expected size
+        let half_config = BitVectorElementsConfig {
+            bit_vector_size_bits: config.bit_vector_size_bits / 2,
+            ..config.clone()
+        };
+        assert!(matches!(
+            compute_bit_vector_commitment(&compressed_bit_vector, &half_config).unwrap_err(),
+            BitVectorCommitmentError::CorruptData(_)
+        ));
+        let uncompressed_bit_vector =
+            compress_bit_vector(&bit_vector, CompressionAlgorithm::Uncompressed).unwrap();
+        assert_eq!(
+            compute_bit_vector_commitment(&uncompressed_bit_vector, &half_config).unwrap_err(),
+            BitVectorCommitmentError::UncompressedSizeMismatch {
+                size: bit_vector.len(),
+                expected: bit_vector.len() / 2
+            }
+        );
+
+        // Corrupted data
+        let mut unknown_algorithm = compressed_bit_vector.clone();
+        unknown_algorithm[0] = 0xff;
+        let mut truncated = compressed_bit_vector.clone();
+        truncated.truncate(compressed_bit_vector.len() / 2);
+        for corrupted in [vec![], unknown_algorithm, truncated].iter() {
+            let err = compute_bit_vector_commitment(corrupted, &config).unwrap_err();
+            assert!(matches!(err, BitVectorCommitmentError::CorruptData(_)));
+            assert!(!err.is_config_violation());
+        }
+    }
+
+    #[test]
+    fn check_compute_bit_vector_commitments_batch() {
+        let configs = [1usize, 4, 8, 2]
+            .iter()
+            .map(|&num_chunks| BitVectorElementsConfig {
+                bit_vector_size_bits: (num_chunks * 1016) as u32,
+                max_compressed_byte_size: 10000,
+            })
+            .collect::<Vec<_>>();
+        let bit_vectors = configs
+            .iter()
+            .enumerate()
+            .map(|(i, config)| {
+                let bit_vector = (0..config.bit_vector_size_bits / 8)
+                    .map(|j| (i as u32 + j) as u8)
+                    .collect::<Vec<_>>();
+                (
+                    compress_bit_vector(&bit_vector, CompressionAlgorithm::Bzip2).unwrap(),
+                    config.clone(),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        let expected = bit_vectors
+            .iter()
+            .map(|(bytes, config)| compute_bit_vector_commitment(bytes, config))
+            .collect::<Vec<_>>();
+
+        // The smallest budgets force the bit vectors to be processed one at a time
+        for &memory_budget in [0, 127, 1016, 1524, usize::MAX].iter() {
+            assert_eq!(
+                compute_bit_vector_commitments_batch(bit_vectors.clone(), memory_budget),
+                expected
+            );
+        }
+
+        // Results are reported per bit vector
+        let mut invalid_bit_vectors = bit_vectors;
+        invalid_bit_vectors[1].0[0] = 0xff;
+        invalid_bit_vectors[2].1.max_compressed_byte_size = 1;
+        let results = compute_bit_vector_commitments_batch(invalid_bit_vectors, usize::MAX);
+        assert_eq!(results[0], expected[0]);
+        assert!(matches!(
+            results[1],
+            Err(BitVectorCommitmentError::CorruptData(_))
+        ));
+        assert!(matches!(
+            results[2],
+            Err(BitVectorCommitmentError::CompressedSizeExceeded { .. })
+        ));
+        assert_eq!(results[3], expected[3]);
+
+        // No bit vectors
+        assert!(compute_bit_vector_commitments_batch(vec![], usize::MAX).is_empty());
+    }
+
+    #[test]
+    fn check_bit_vector_delta_updater() {
+        let mut rng = rand::thread_rng();
+
+        for &num_leaves in [4usize, 8, 64].iter() {
+            let mut bit_vector: Vec<u8> = (0..num_leaves * 254 / 8).map(|_| rng.gen()).collect();
+            let mut updater = BitVectorDeltaUpdater::from_bytes(&bit_vector).unwrap();
+            let mut root = updater.root();
+            assert_eq!(root, merkle_root_from_bytes(&bit_vector).unwrap());
+            assert_eq!(updater.num_leaves(), num_leaves);
+
+            // Replace a contiguous range of bytes
+            for _ in 0..5 {
+                let byte_offset = rng.gen_range(0..bit_vector.len());
+                let num_bytes = rng.gen_range(1..=(bit_vector.len() - byte_offset).min(40));
+                bit_vector[byte_offset..byte_offset + num_bytes]
+                    .iter_mut()
+                    .for_each(|b| *b = rng.gen());
+
+                root = updater
+                    .update_range(&root, &bit_vector, byte_offset, num_bytes)
+                    .unwrap();
+                assert_eq!(root, merkle_root_from_bytes(&bit_vector).unwrap());
+                assert_eq!(
+                    updater,
+                    BitVectorDeltaUpdater::from_bytes(&bit_vector).unwrap()
+                );
+            }
+        }
+
+        let bit_vector: Vec<u8> = (0..254).map(|i| i as u8).collect();
+        let mut updater = BitVectorDeltaUpdater::from_bytes(&bit_vector).unwrap();
+        let root = updater.root();
+        let old_leaf = updater.get_leaf(3).unwrap();
+        let new_leaf = FieldElement::from(5u64);
+
+        // Wrong old root, old leaf value and index: the tree is left untouched
+        let wrong_root = FieldElement::from(1u64);
+        assert!(updater
+            .update_leaves(&wrong_root, &[(3, old_leaf, new_leaf)])
+            .is_err());
+        assert!(updater
+            .update_leaves(&root, &[(3, new_leaf, new_leaf)])
+            .is_err());
+        assert!(updater
+            .update_leaves(&root, &[(3, old_leaf, new_leaf), (8, old_leaf, new_leaf)])
+            .is_err());
+        assert_eq!(updater.root(), root);
+
+        // Updating a leaf back to its old value restores the old root
+        let new_root = updater
+            .update_leaves(&root, &[(3, old_leaf, new_leaf)])
+            .unwrap();
+        assert_ne!(new_root, root);
+        assert_eq!(
+            updater
+                .update_leaves(&new_root, &[(3, new_leaf, old_leaf)])
+                .unwrap(),
+            root
+        );
+
+        // Range outside the bit vector
+        assert!(updater.update_range(&root, &bit_vector, 250, 10).is_err());
+    }
+
     fn field_element_to_hex_string(field_element: FieldElement) -> String {
         use algebra::{to_bytes, ToBytes};
 
@@ -4,4 +4,5 @@
 //! The exposed functions are useful both for Mainchain and Sidechain.
 
 pub mod compression;
+pub mod error;
 pub mod merkle_tree;
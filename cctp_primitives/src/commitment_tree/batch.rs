@@ -0,0 +1,480 @@
+use crate::{
+    commitment_tree::{
+        epoch_cert_builder::EpochCert,
+        error::CommitmentTreeError,
+        hash_operations_for_subtree_height,
+        hashers::{hash_bwtr, hash_csw, hash_fwt},
+        proofs::ScSubtreeType,
+        sidechain_tree_alive::SidechainAliveSubtreeType,
+        AddedSummary, CommitmentTree, ScTxEvent,
+    },
+    type_mapping::{Error, FieldElement, MC_PK_SIZE},
+};
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+use std::collections::BTreeMap;
+
+// Forward Transfer Transaction of a sidechain, i.e. the values hash_fwt takes along with the sidechain ID
+#[derive(Clone, Debug, PartialEq)]
+pub struct FwtData {
+    pub sc_id: FieldElement,
+    pub amount: u64,
+    pub pub_key: [u8; 32],
+    pub mc_return_address: [u8; 20],
+    pub tx_hash: [u8; 32],
+    pub out_idx: u32,
+}
+
+impl FwtData {
+    // Computes the leaf of the Forward Transfer Transaction in the FWT subtree
+    pub fn leaf(&self) -> Result<FieldElement, Error> {
+        hash_fwt(
+            self.amount,
+            &self.pub_key,
+            &self.mc_return_address,
+            &self.tx_hash,
+            self.out_idx,
+        )
+    }
+}
+
+// Backward Transfer Request Transaction of a sidechain, i.e. the values hash_bwtr takes along with the sidechain ID
+#[derive(Clone, Debug, PartialEq)]
+pub struct BwtrData {
+    pub sc_id: FieldElement,
+    pub sc_fee: u64,
+    pub sc_request_data: Vec<FieldElement>,
+    pub mc_destination_address: [u8; MC_PK_SIZE],
+    pub tx_hash: [u8; 32],
+    pub out_idx: u32,
+}
+
+impl BwtrData {
+    // Computes the leaf of the Backward Transfer Request Transaction in the BWTR subtree
+    pub fn leaf(&self) -> Result<FieldElement, Error> {
+        hash_bwtr(
+            self.sc_fee,
+            self.sc_request_data.iter().collect(),
+            &self.mc_destination_address,
+            &self.tx_hash,
+            self.out_idx,
+        )
+    }
+}
+
+// Certificate of a sidechain
+#[derive(Clone, Debug, PartialEq)]
+pub struct CertData {
+    pub sc_id: FieldElement,
+    pub cert: EpochCert,
+}
+
+impl CertData {
+    // Computes the leaf of the Certificate in the CERT subtree
+    pub fn leaf(&self) -> Result<FieldElement, Error> {
+        self.cert.data_hash(&self.sc_id)
+    }
+}
+
+// Ceased Sidechain Withdrawal of a sidechain, i.e. the values hash_csw takes along with the sidechain ID
+#[derive(Clone, Debug, PartialEq)]
+pub struct CswData {
+    pub sc_id: FieldElement,
+    pub amount: u64,
+    pub nullifier: FieldElement,
+    pub mc_pk_hash: [u8; MC_PK_SIZE],
+}
+
+impl CswData {
+    // Computes the leaf of the Ceased Sidechain Withdrawal in the CSW subtree
+    pub fn leaf(&self) -> Result<FieldElement, Error> {
+        hash_csw(self.amount, &self.nullifier, &self.mc_pk_hash)
+    }
+}
+
+// Hashes the items of a batch, in parallel if the `parallel` feature is enabled
+// Returns the leaves of the items, in order, or
+//         the index of the first item which can't be hashed, together with the reason why
+fn hash_batch<T, H>(items: &[T], hash: H) -> Result<Vec<FieldElement>, (usize, CommitmentTreeError)>
+where
+    T: Sync,
+    H: Fn(&T) -> Result<FieldElement, Error> + Sync,
+{
+    #[cfg(feature = "parallel")]
+    let items = items.par_iter();
+    #[cfg(not(feature = "parallel"))]
+    let items = items.iter();
+
+    // Error isn't Send, so it's converted before leaving the worker thread
+    let results = items
+        .map(|item| hash(item).map_err(|e| CommitmentTreeError::HashFailed(e.to_string())))
+        .collect::<Vec<_>>();
+    results
+        .into_iter()
+        .enumerate()
+        .map(|(idx, result)| result.map_err(|e| (idx, e)))
+        .collect()
+}
+
+// Groups the leaves of a batch by sidechain, in order of first appearance of the sidechains,
+// keeping the relative order of the leaves of each sidechain
+// Returns, for each sidechain, the index of its first leaf in the batch, its ID and its leaves
+fn group_by_sidechain(
+    leaves: Vec<(FieldElement, FieldElement)>,
+) -> Vec<(usize, FieldElement, Vec<FieldElement>)> {
+    let mut positions = BTreeMap::new();
+    let mut groups: Vec<(usize, FieldElement, Vec<FieldElement>)> = Vec::new();
+    for (idx, (sc_id, leaf)) in leaves.into_iter().enumerate() {
+        let pos = *positions.entry(sc_id).or_insert_with(|| {
+            groups.push((idx, sc_id, Vec::new()));
+            groups.len() - 1
+        });
+        groups[pos].2.push(leaf);
+    }
+    groups
+}
+
+impl CommitmentTree {
+    // Batch counterparts of the add_* methods, for callers adding many leaves at once, e.g. all the ones of a block
+    // The items are hashed in parallel, then their leaves are appended looking up each sidechain and invalidating
+    // its commitment once, instead of once per leaf
+    // As extend method does, all the items of a batch are added, in order, or none of them
+    // Note: the observer, if any, is notified of the added leaves grouped by sidechain
+
+    // Adds a batch of Forward Transfer Transactions to the Commitment Tree
+    // Returns the number of added leaves or
+    //         the index of the first item which can't be hashed or added, together with the reason why
+    pub fn add_fwt_batch(
+        &mut self,
+        fwts: &[FwtData],
+    ) -> Result<AddedSummary, (usize, CommitmentTreeError)> {
+        let leaves = hash_batch(fwts, FwtData::leaf)?;
+        self.add_leaf_batch(
+            fwts.iter().map(|fwt| fwt.sc_id).zip(leaves).collect(),
+            Some(SidechainAliveSubtreeType::FWT),
+        )
+    }
+
+    // Adds a batch of Backward Transfer Request Transactions to the Commitment Tree
+    // Returns the number of added leaves or
+    //         the index of the first item which can't be hashed or added, together with the reason why
+    pub fn add_bwtr_batch(
+        &mut self,
+        bwtrs: &[BwtrData],
+    ) -> Result<AddedSummary, (usize, CommitmentTreeError)> {
+        let leaves = hash_batch(bwtrs, BwtrData::leaf)?;
+        self.add_leaf_batch(
+            bwtrs.iter().map(|bwtr| bwtr.sc_id).zip(leaves).collect(),
+            Some(SidechainAliveSubtreeType::BWTR),
+        )
+    }
+
+    // Adds a batch of Certificates to the Commitment Tree
+    // Returns the number of added leaves or
+    //         the index of the first item which can't be hashed or added, together with the reason why
+    pub fn add_cert_batch(
+        &mut self,
+        certs: &[CertData],
+    ) -> Result<AddedSummary, (usize, CommitmentTreeError)> {
+        let leaves = hash_batch(certs, CertData::leaf)?;
+        self.add_leaf_batch(
+            certs.iter().map(|cert| cert.sc_id).zip(leaves).collect(),
+            Some(SidechainAliveSubtreeType::CERT),
+        )
+    }
+
+    // Adds a batch of Ceased Sidechain Withdrawals to the Commitment Tree
+    // Returns the number of added leaves or
+    //         the index of the first item which can't be hashed or added, together with the reason why
+    pub fn add_csw_batch(
+        &mut self,
+        csws: &[CswData],
+    ) -> Result<AddedSummary, (usize, CommitmentTreeError)> {
+        let leaves = hash_batch(csws, CswData::leaf)?;
+        self.add_leaf_batch(csws.iter().map(|csw| csw.sc_id).zip(leaves).collect(), None)
+    }
+
+    // Validates and appends a batch of (sidechain ID, leaf) pairs to the subtrees of a specified type,
+    // the CSW subtrees if subtree_type is None
+    fn add_leaf_batch(
+        &mut self,
+        leaves: Vec<(FieldElement, FieldElement)>,
+        subtree_type: Option<SidechainAliveSubtreeType>,
+    ) -> Result<AddedSummary, (usize, CommitmentTreeError)> {
+        let events = leaves
+            .iter()
+            .map(|&(sc_id, leaf)| match subtree_type {
+                Some(SidechainAliveSubtreeType::FWT) => ScTxEvent::Fwt { sc_id, leaf },
+                Some(SidechainAliveSubtreeType::BWTR) => ScTxEvent::Bwtr { sc_id, leaf },
+                Some(SidechainAliveSubtreeType::CERT) => ScTxEvent::Cert { sc_id, leaf },
+                Some(SidechainAliveSubtreeType::SCC) => ScTxEvent::Scc { sc_id, leaf },
+                None => ScTxEvent::Csw { sc_id, leaf },
+            })
+            .collect::<Vec<_>>();
+        let summary = self.validate_events(&events)?;
+
+        for (idx, sc_id, sc_leaves) in group_by_sidechain(leaves) {
+            let result = match subtree_type {
+                Some(subtree_type) => self.append_alive_leaves(&sc_id, &sc_leaves, subtree_type),
+                None => self.append_csw_leaves(&sc_id, &sc_leaves),
+            };
+            // Not expected to fail, as all the leaves have already been validated
+            result.map_err(|e| (idx, e))?;
+        }
+
+        Ok(summary)
+    }
+
+    // Appends leaves to a subtree of a specified type in a specified SidechainTreeAlive, adding it if needed
+    // Returns a dedicated error if the sidechain is ceased, if CommitmentTree is full or if the subtree is full
+    fn append_alive_leaves(
+        &mut self,
+        sc_id: &FieldElement,
+        leaves: &[FieldElement],
+        subtree_type: SidechainAliveSubtreeType,
+    ) -> Result<(), CommitmentTreeError> {
+        if self.is_present_sctc(sc_id) {
+            return Err(CommitmentTreeError::SidechainCeased(*sc_id));
+        }
+        let new_sidechain = !self.is_present_scta(sc_id);
+        let sct = if new_sidechain {
+            self.add_scta(sc_id)
+        } else {
+            self.get_scta_mut(sc_id)
+        };
+        let sct = match sct {
+            Some(sct) => sct,
+            None => return Err(self.full_error()),
+        };
+
+        let mut added = 0;
+        for leaf in leaves.iter() {
+            let result = match subtree_type {
+                SidechainAliveSubtreeType::FWT => sct.add_fwt(leaf),
+                SidechainAliveSubtreeType::BWTR => sct.add_bwtr(leaf),
+                SidechainAliveSubtreeType::CERT => sct.add_cert(leaf),
+                SidechainAliveSubtreeType::SCC => {
+                    sct.set_scc(leaf);
+                    true
+                }
+            };
+            if !result {
+                break;
+            }
+            added += 1;
+        }
+        let subtree_height = match subtree_type {
+            SidechainAliveSubtreeType::FWT => self.config.subtree_height(ScSubtreeType::FWT),
+            SidechainAliveSubtreeType::BWTR => self.config.subtree_height(ScSubtreeType::BWTR),
+            SidechainAliveSubtreeType::CERT => self.config.subtree_height(ScSubtreeType::CERT),
+            SidechainAliveSubtreeType::SCC => 0,
+        };
+
+        if new_sidechain || added > 0 {
+            self.invalidate_commitments_tree(sc_id, new_sidechain);
+        }
+        self.hash_operations +=
+            added * hash_operations_for_subtree_height(subtree_height, self.config.cmt_height);
+        for leaf in leaves[..added].iter() {
+            self.notify(|observer| observer.on_alive_leaf_added(sc_id, &subtree_type, leaf));
+        }
+
+        if added < leaves.len() {
+            return Err(CommitmentTreeError::SubtreeFull(*sc_id));
+        }
+        Ok(())
+    }
+
+    // Appends leaves to the CSW subtree of a specified SidechainTreeCeased, adding it if needed
+    // Returns a dedicated error if the sidechain is alive, if CommitmentTree is full or if the subtree is full
+    fn append_csw_leaves(
+        &mut self,
+        sc_id: &FieldElement,
+        leaves: &[FieldElement],
+    ) -> Result<(), CommitmentTreeError> {
+        if self.is_present_scta(sc_id) {
+            return Err(CommitmentTreeError::SidechainAlive(*sc_id));
+        }
+        let new_sidechain = !self.is_present_sctc(sc_id);
+        let sctc = if new_sidechain {
+            self.add_sctc(sc_id)
+        } else {
+            self.get_sctc_mut(sc_id)
+        };
+        let sctc = match sctc {
+            Some(sctc) => sctc,
+            None => return Err(self.full_error()),
+        };
+
+        let added = leaves.iter().take_while(|leaf| sctc.add_csw(leaf)).count();
+
+        if new_sidechain || added > 0 {
+            self.invalidate_commitments_tree(sc_id, new_sidechain);
+        }
+        self.hash_operations += added
+            * hash_operations_for_subtree_height(
+                self.config.subtree_height(ScSubtreeType::CSW),
+                self.config.cmt_height,
+            );
+        for leaf in leaves[..added].iter() {
+            self.notify(|observer| observer.on_csw_leaf_added(sc_id, leaf));
+        }
+
+        if added < leaves.len() {
+            return Err(CommitmentTreeError::SubtreeFull(*sc_id));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::commitment_tree::sidechain_tree_ceased::CSW_MT_HEIGHT;
+    use crate::utils::{
+        commitment_tree::{pow2, rand_fe_vec_with_rng, rand_fe_with_rng},
+        data_structures::BackwardTransfer,
+    };
+    use rand::Rng;
+
+    #[test]
+    fn batch_tests() {
+        let mut rng = rand::thread_rng();
+        let alive_ids = rand_fe_vec_with_rng(5, &mut rng);
+        let ceased_ids = rand_fe_vec_with_rng(3, &mut rng);
+
+        // Items of different sidechains interleaved
+        let fwts = (0..50)
+            .map(|i| FwtData {
+                sc_id: alive_ids[i % alive_ids.len()],
+                amount: rng.gen(),
+                pub_key: rng.gen(),
+                mc_return_address: rng.gen(),
+                tx_hash: rng.gen(),
+                out_idx: rng.gen(),
+            })
+            .collect::<Vec<_>>();
+        let bwtrs = (0..20)
+            .map(|i| BwtrData {
+                sc_id: alive_ids[i % 2],
+                sc_fee: rng.gen(),
+                sc_request_data: rand_fe_vec_with_rng(2, &mut rng),
+                mc_destination_address: rng.gen(),
+                tx_hash: rng.gen(),
+                out_idx: rng.gen(),
+            })
+            .collect::<Vec<_>>();
+        let certs = (0..10)
+            .map(|i| CertData {
+                sc_id: alive_ids[i % 3],
+                cert: EpochCert {
+                    epoch_number: rng.gen(),
+                    quality: rng.gen(),
+                    bt_list: vec![BackwardTransfer::default(); i],
+                    custom_fields: Some(rand_fe_vec_with_rng(2, &mut rng)),
+                    end_cumulative_sc_tx_commitment_tree_root: rand_fe_with_rng(&mut rng),
+                    btr_fee: rng.gen(),
+                    ft_min_amount: rng.gen(),
+                },
+            })
+            .collect::<Vec<_>>();
+        let csws = (0..30)
+            .map(|i| CswData {
+                sc_id: ceased_ids[i % ceased_ids.len()],
+                amount: rng.gen(),
+                nullifier: rand_fe_with_rng(&mut rng),
+                mc_pk_hash: rng.gen(),
+            })
+            .collect::<Vec<_>>();
+
+        // Same state as the one of a CommitmentTree built item by item
+        let mut expected_cmt = CommitmentTree::create();
+        for fwt in fwts.iter() {
+            assert!(expected_cmt.add_fwt_leaf(&fwt.sc_id, &fwt.leaf().unwrap()));
+        }
+        for bwtr in bwtrs.iter() {
+            assert!(expected_cmt.add_bwtr_leaf(&bwtr.sc_id, &bwtr.leaf().unwrap()));
+        }
+        for cert in certs.iter() {
+            assert!(expected_cmt.add_cert_leaf(&cert.sc_id, &cert.leaf().unwrap()));
+        }
+        for csw in csws.iter() {
+            assert!(expected_cmt.add_csw_leaf(&csw.sc_id, &csw.leaf().unwrap()));
+        }
+
+        let mut cmt = CommitmentTree::create();
+        let summary = cmt.add_fwt_batch(&fwts).unwrap();
+        assert_eq!(summary.fwt, fwts.len());
+        assert_eq!(summary.new_sidechains, alive_ids.len());
+        assert_eq!(cmt.add_bwtr_batch(&bwtrs).unwrap().bwtr, bwtrs.len());
+        assert_eq!(cmt.add_cert_batch(&certs).unwrap().cert, certs.len());
+        let summary = cmt.add_csw_batch(&csws).unwrap();
+        assert_eq!(summary.csw, csws.len());
+        assert_eq!(summary.new_sidechains, ceased_ids.len());
+        assert_eq!(cmt.add_fwt_batch(&[]).unwrap(), AddedSummary::default());
+
+        for sc_id in alive_ids.iter() {
+            assert_eq!(
+                cmt.get_fwt_leaves(sc_id),
+                expected_cmt.get_fwt_leaves(sc_id)
+            );
+            assert_eq!(
+                cmt.get_cert_leaves(sc_id),
+                expected_cmt.get_cert_leaves(sc_id)
+            );
+        }
+        assert_eq!(cmt.hash_operations(), expected_cmt.hash_operations());
+        assert_eq!(cmt.get_commitment(), expected_cmt.get_commitment());
+
+        // A batch is added entirely or not at all
+        let commitment = cmt.get_commitment();
+        let mut wrong_fwts = fwts[..3].to_vec();
+        wrong_fwts[2].sc_id = ceased_ids[0];
+        assert_eq!(
+            cmt.add_fwt_batch(&wrong_fwts),
+            Err((2, CommitmentTreeError::SidechainCeased(ceased_ids[0])))
+        );
+        let mut wrong_csws = csws[..3].to_vec();
+        wrong_csws[1].sc_id = alive_ids[0];
+        assert_eq!(
+            cmt.add_csw_batch(&wrong_csws),
+            Err((1, CommitmentTreeError::SidechainAlive(alive_ids[0])))
+        );
+        assert_eq!(cmt.get_commitment(), commitment);
+
+        // A batch of leaves not fitting into a subtree is rejected
+        let sc_id = rand_fe_with_rng(&mut rng);
+        let too_many_csws = (0..pow2(CSW_MT_HEIGHT) + 1)
+            .map(|_| CswData {
+                sc_id,
+                amount: 0,
+                nullifier: rand_fe_with_rng(&mut rng),
+                mc_pk_hash: [0u8; MC_PK_SIZE],
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            cmt.add_csw_batch(&too_many_csws),
+            Err((pow2(CSW_MT_HEIGHT), CommitmentTreeError::SubtreeFull(sc_id)))
+        );
+        assert!(!cmt.is_ceased(&sc_id));
+        assert_eq!(cmt.get_commitment(), commitment);
+
+        // Sidechains not fitting into the CommitmentTree are rejected
+        let num_sidechains = alive_ids.len() + ceased_ids.len();
+        let free_slots = cmt.max_sidechains() - num_sidechains;
+        let new_fwts = (0..free_slots + 1)
+            .map(|i| FwtData {
+                sc_id: rand_fe_with_rng(&mut rng),
+                ..fwts[i % fwts.len()].clone()
+            })
+            .collect::<Vec<_>>();
+        assert!(cmt.add_fwt_batch(&new_fwts).is_err());
+        assert_eq!(cmt.get_commitment(), commitment);
+        assert_eq!(
+            cmt.add_fwt_batch(&new_fwts[..free_slots])
+                .unwrap()
+                .new_sidechains,
+            free_slots
+        );
+    }
+}
@@ -0,0 +1,316 @@
+use crate::{
+    commitment_tree::{hashers::hash_cert, CommitmentTree},
+    type_mapping::FieldElement,
+    utils::{
+        compute_sc_id_with_network_tag,
+        data_structures::{BackwardTransfer, BitVectorElementsConfig},
+    },
+};
+use std::collections::BTreeMap;
+
+// Commitments needed to validate a block, computed in a single pass over its certificates
+#[derive(Clone, Debug, PartialEq)]
+pub struct BlockCommitments {
+    pub sc_tx_commitment: FieldElement, // commitment of the whole CommitmentTree
+    pub cert_roots: BTreeMap<FieldElement, FieldElement>, // root of the CERT subtree of each sidechain with certificates
+    pub cert_leaves: BTreeMap<FieldElement, Vec<FieldElement>>, // hashes of the certificates of each sidechain, in insertion order
+}
+
+// Builder of the CommitmentTree of a block, additionally keeping track of the certificates of each sidechain,
+// so that both the sc_tx_commitment and the per-sidechain cert trees are obtained by hashing every certificate once
+pub struct BlockCommitmentBuilder {
+    cmt: CommitmentTree,
+    cert_leaves: BTreeMap<FieldElement, Vec<FieldElement>>,
+    network_tag: Option<FieldElement>, // personalization of the IDs of the sidechains created in the block, if any
+}
+
+impl BlockCommitmentBuilder {
+    // Creates a new instance of BlockCommitmentBuilder with an empty CommitmentTree
+    pub fn create() -> Self {
+        Self::from_tree(CommitmentTree::create())
+    }
+
+    // Creates a new instance of BlockCommitmentBuilder from a CommitmentTree, possibly already
+    // containing the other transactions of the block
+    // Note: certificates already contained in `cmt` are not tracked
+    pub fn from_tree(cmt: CommitmentTree) -> Self {
+        Self {
+            cmt,
+            cert_leaves: BTreeMap::new(),
+            network_tag: None,
+        }
+    }
+
+    // Sets the network tag personalizing the IDs of the sidechains created in the block,
+    // so that identical transactions on different networks yield different IDs
+    // Without a network tag (default), IDs are the ones compute_sc_id returns
+    pub fn set_network_tag(&mut self, network_tag: Option<FieldElement>) {
+        self.network_tag = network_tag;
+    }
+
+    // Gets the network tag personalizing the IDs of the sidechains created in the block, if any
+    pub fn network_tag(&self) -> Option<&FieldElement> {
+        self.network_tag.as_ref()
+    }
+
+    // Computes the ID of the sidechain created by the output out_idx of the transaction tx_hash,
+    // personalized with the network tag, if any
+    // Returns None if the ID can't be computed
+    pub fn compute_sc_id(&self, tx_hash: &[u8; 32], out_idx: u32) -> Option<FieldElement> {
+        compute_sc_id_with_network_tag(tx_hash, out_idx, self.network_tag.as_ref()).ok()
+    }
+
+    // Adds Sidechain Creation Transaction to the CommitmentTree, with the ID of the sidechain computed
+    // from tx_hash and out_idx by the compute_sc_id method
+    // Returns the ID of the sidechain or
+    //         None if the ID can't be computed or the CommitmentTree add_scc method fails
+    pub fn add_scc(
+        &mut self,
+        amount: u64,
+        pub_key: &[u8; 32],
+        tx_hash: &[u8; 32],
+        out_idx: u32,
+        withdrawal_epoch_length: u32,
+        mc_btr_request_data_length: u8,
+        custom_field_elements_configs: Option<&[u8]>,
+        custom_bitvector_elements_configs: Option<&[BitVectorElementsConfig]>,
+        btr_fee: u64,
+        ft_min_amount: u64,
+        custom_creation_data: Option<&[u8]>,
+        constant: Option<&FieldElement>,
+        cert_verification_key: &[u8],
+        csw_verification_key: Option<&[u8]>,
+    ) -> Option<FieldElement> {
+        let sc_id = self.compute_sc_id(tx_hash, out_idx)?;
+        if !self.cmt.add_scc(
+            &sc_id,
+            amount,
+            pub_key,
+            tx_hash,
+            out_idx,
+            withdrawal_epoch_length,
+            mc_btr_request_data_length,
+            custom_field_elements_configs,
+            custom_bitvector_elements_configs,
+            btr_fee,
+            ft_min_amount,
+            custom_creation_data,
+            constant,
+            cert_verification_key,
+            csw_verification_key,
+        ) {
+            return None;
+        }
+        Some(sc_id)
+    }
+
+    // Gets the underlying CommitmentTree, e.g. to add the other transactions of the block
+    pub fn tree_mut(&mut self) -> &mut CommitmentTree {
+        &mut self.cmt
+    }
+
+    // Adds Certificate to the CommitmentTree, keeping track of its hash
+    // Returns the hash of the Certificate or
+    //         None if hash_cert can't get hash for data given in parameters or the leaf can't be added to the CommitmentTree
+    pub fn add_cert(
+        &mut self,
+        sc_id: &FieldElement,
+        epoch_number: u32,
+        quality: u64,
+        bt_list: Option<&[BackwardTransfer]>,
+        custom_fields: Option<Vec<&FieldElement>>, //aka proof_data - includes custom_field_elements and bit_vectors merkle roots
+        end_cumulative_sc_tx_commitment_tree_root: &FieldElement,
+        btr_fee: u64,
+        ft_min_amount: u64,
+    ) -> Option<FieldElement> {
+        let cert_leaf = hash_cert(
+            sc_id,
+            epoch_number,
+            quality,
+            bt_list,
+            custom_fields,
+            end_cumulative_sc_tx_commitment_tree_root,
+            btr_fee,
+            ft_min_amount,
+        )
+        .ok()?;
+
+        if !self.cmt.add_cert_leaf(sc_id, &cert_leaf) {
+            return None;
+        }
+        self.cert_leaves
+            .entry(*sc_id)
+            .or_insert_with(Vec::new)
+            .push(cert_leaf);
+
+        Some(cert_leaf)
+    }
+
+    // Computes both the commitment of the CommitmentTree and the roots of the CERT subtrees
+    // of the sidechains for which certificates have been added
+    // Returns None if some of the commitments can't be computed
+    pub fn build(mut self) -> Option<BlockCommitments> {
+        let sc_tx_commitment = self.cmt.get_commitment()?;
+
+        let mut cert_roots = BTreeMap::new();
+        for sc_id in self.cert_leaves.keys() {
+            cert_roots.insert(*sc_id, self.cmt.get_cert_commitment(sc_id)?);
+        }
+
+        Some(BlockCommitments {
+            sc_tx_commitment,
+            cert_roots,
+            cert_leaves: self.cert_leaves,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::commitment_tree::{
+        block_builder::BlockCommitmentBuilder, hashers::hash_cert, CommitmentTree,
+    };
+    use crate::utils::{
+        commitment_tree::{rand_fe_vec_with_rng, rand_fe_with_rng},
+        compute_sc_id,
+        data_structures::BackwardTransfer,
+    };
+    use rand::Rng;
+
+    #[test]
+    fn block_commitment_builder_tests() {
+        let mut rng = rand::thread_rng();
+        let sc_ids = rand_fe_vec_with_rng(3, &mut rng);
+        let fwt = rand_fe_with_rng(&mut rng);
+        let bt_list = vec![BackwardTransfer::default(); 2];
+        let end_cum_comm_tree_root = rand_fe_with_rng(&mut rng);
+
+        let mut expected_cmt = CommitmentTree::create();
+        let mut builder = BlockCommitmentBuilder::create();
+
+        // Other transactions of the block
+        assert!(expected_cmt.add_fwt_leaf(&sc_ids[2], &fwt));
+        assert!(builder.tree_mut().add_fwt_leaf(&sc_ids[2], &fwt));
+
+        // Two certificates for the first sidechain, one for the second
+        let mut cert_leaves = Vec::new();
+        for &sc_idx in [0usize, 1, 0].iter() {
+            let (epoch_number, quality, btr_fee, ft_min_amount) =
+                (rng.gen(), rng.gen(), rng.gen(), rng.gen());
+            assert!(expected_cmt.add_cert(
+                &sc_ids[sc_idx],
+                epoch_number,
+                quality,
+                Some(&bt_list),
+                None,
+                &end_cum_comm_tree_root,
+                btr_fee,
+                ft_min_amount,
+            ));
+            let cert_leaf = builder
+                .add_cert(
+                    &sc_ids[sc_idx],
+                    epoch_number,
+                    quality,
+                    Some(&bt_list),
+                    None,
+                    &end_cum_comm_tree_root,
+                    btr_fee,
+                    ft_min_amount,
+                )
+                .unwrap();
+            assert_eq!(
+                cert_leaf,
+                hash_cert(
+                    &sc_ids[sc_idx],
+                    epoch_number,
+                    quality,
+                    Some(&bt_list),
+                    None,
+                    &end_cum_comm_tree_root,
+                    btr_fee,
+                    ft_min_amount,
+                )
+                .unwrap()
+            );
+            cert_leaves.push(cert_leaf);
+        }
+
+        let block_commitments = builder.build().unwrap();
+        assert_eq!(
+            block_commitments.sc_tx_commitment,
+            expected_cmt.get_commitment().unwrap()
+        );
+
+        // Only sidechains with certificates have a cert root
+        assert_eq!(block_commitments.cert_roots.len(), 2);
+        for sc_id in sc_ids[..2].iter() {
+            assert_eq!(
+                block_commitments.cert_roots[sc_id],
+                expected_cmt.get_cert_commitment(sc_id).unwrap()
+            );
+        }
+        assert_eq!(
+            block_commitments.cert_leaves[&sc_ids[0]],
+            vec![cert_leaves[0], cert_leaves[2]]
+        );
+        assert_eq!(
+            block_commitments.cert_leaves[&sc_ids[1]],
+            vec![cert_leaves[1]]
+        );
+
+        // Certificates can't be added to a ceased sidechain
+        let mut builder = BlockCommitmentBuilder::create();
+        assert!(builder
+            .tree_mut()
+            .add_csw_leaf(&sc_ids[0], &rand_fe_with_rng(&mut rng)));
+        assert!(builder
+            .add_cert(&sc_ids[0], 0, 0, None, None, &end_cum_comm_tree_root, 0, 0)
+            .is_none());
+    }
+
+    #[test]
+    fn block_commitment_builder_network_tag_tests() {
+        let mut rng = rand::thread_rng();
+        let network_tag = rand_fe_with_rng(&mut rng);
+        let tx_hash = [3u8; 32];
+        let add_scc = |builder: &mut BlockCommitmentBuilder| {
+            builder.add_scc(
+                10,
+                &[1u8; 32],
+                &tx_hash,
+                0,
+                100,
+                0,
+                None,
+                None,
+                0,
+                0,
+                None,
+                None,
+                &[2u8; 100],
+                None,
+            )
+        };
+
+        // No network tag, default sidechain id
+        let mut builder = BlockCommitmentBuilder::create();
+        assert!(builder.network_tag().is_none());
+        let sc_id = add_scc(&mut builder).unwrap();
+        assert_eq!(sc_id, compute_sc_id(&tx_hash, 0).unwrap());
+        assert!(builder.tree_mut().get_scc(&sc_id).is_some());
+
+        // Network tag, personalized sidechain id
+        let mut tagged_builder = BlockCommitmentBuilder::create();
+        tagged_builder.set_network_tag(Some(network_tag));
+        assert_eq!(tagged_builder.network_tag(), Some(&network_tag));
+        let tagged_sc_id = add_scc(&mut tagged_builder).unwrap();
+        assert_ne!(tagged_sc_id, sc_id);
+        assert_eq!(
+            tagged_builder.compute_sc_id(&tx_hash, 0).unwrap(),
+            tagged_sc_id
+        );
+        assert!(tagged_builder.tree_mut().get_scc(&tagged_sc_id).is_some());
+        assert!(tagged_builder.tree_mut().get_scc(&sc_id).is_none());
+    }
+}
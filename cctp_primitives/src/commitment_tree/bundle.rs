@@ -0,0 +1,232 @@
+use crate::{
+    commitment_tree::{
+        error::CommitmentTreeError,
+        proofs::{ScAbsenceProof, ScExistenceProof},
+        CommitmentTree,
+    },
+    type_mapping::{FieldElement, FIELD_SIZE},
+    utils::serialization::{
+        deserialize_canonical_bounded, serialize_to_buffer, MAX_COMMITMENT_PROOF_SIZE,
+    },
+};
+use algebra::serialize::*;
+use std::convert::TryInto;
+
+// Compact interchange format of an InclusionBundle, as exchanged by sidechain nodes:
+//     version (1 byte) | total length of the blob, header included (4 bytes, LE) | kind (1 byte) | payload
+// where the payload is the compressed canonical serialization of an ScExistenceBundle or an ScAbsenceBundle
+// The version must be bumped whenever the layout of the blob or of its payload changes
+pub const INCLUSION_BUNDLE_VERSION: u8 = 1;
+pub const INCLUSION_BUNDLE_HEADER_SIZE: usize = 1 + 4 + 1;
+
+// Maximum length of a blob: payloads are made of at most 3 field elements and a commitment tree proof
+pub const MAX_INCLUSION_BUNDLE_SIZE: usize =
+    INCLUSION_BUNDLE_HEADER_SIZE + 3 * FIELD_SIZE + MAX_COMMITMENT_PROOF_SIZE;
+
+const EXISTENCE_BUNDLE_KIND: u8 = 0;
+const ABSENCE_BUNDLE_KIND: u8 = 1;
+
+// Everything needed to check the inclusion of a sidechain into the CommitmentTree of a mainchain block
+#[derive(PartialEq, Debug, CanonicalSerialize, CanonicalDeserialize)]
+pub struct ScExistenceBundle {
+    pub sc_id: FieldElement,         // ID of the sidechain
+    pub sc_commitment: FieldElement, // commitment of the sidechain
+    pub proof: ScExistenceProof,     // proof of existence of the sidechain commitment
+    pub commitment: FieldElement,    // root of the CommitmentTree, as stored in the mainchain block
+}
+
+// Everything needed to check the non-inclusion of a sidechain into the CommitmentTree of a mainchain block
+#[derive(PartialEq, Debug, CanonicalSerialize, CanonicalDeserialize)]
+pub struct ScAbsenceBundle {
+    pub sc_id: FieldElement,      // ID of the absent sidechain
+    pub proof: ScAbsenceProof,    // proof of absence of the sidechain
+    pub commitment: FieldElement, // root of the CommitmentTree, as stored in the mainchain block
+}
+
+#[derive(PartialEq, Debug)]
+pub enum InclusionBundle {
+    Existence(ScExistenceBundle),
+    Absence(ScAbsenceBundle),
+}
+
+fn invalid_bundle(reason: String) -> CommitmentTreeError {
+    CommitmentTreeError::InvalidInclusionBundle(reason)
+}
+
+impl InclusionBundle {
+    // Gets the ID of the sidechain the bundle is about
+    pub fn sc_id(&self) -> &FieldElement {
+        match self {
+            InclusionBundle::Existence(bundle) => &bundle.sc_id,
+            InclusionBundle::Absence(bundle) => &bundle.sc_id,
+        }
+    }
+
+    // Gets the root of the CommitmentTree the bundle refers to
+    pub fn commitment(&self) -> &FieldElement {
+        match self {
+            InclusionBundle::Existence(bundle) => &bundle.commitment,
+            InclusionBundle::Absence(bundle) => &bundle.commitment,
+        }
+    }
+
+    // Checks the proof of the bundle against the CommitmentTree root it carries
+    // NOTE: the caller is still in charge of checking that such root is the one of the expected mainchain block
+    pub fn verify(&self) -> bool {
+        match self {
+            InclusionBundle::Existence(bundle) => CommitmentTree::verify_sc_commitment(
+                &bundle.sc_commitment,
+                &bundle.proof,
+                &bundle.commitment,
+            ),
+            InclusionBundle::Absence(bundle) => {
+                CommitmentTree::verify_sc_absence(&bundle.sc_id, &bundle.proof, &bundle.commitment)
+            }
+        }
+    }
+
+    // Serializes the bundle into the interchange format
+    pub fn to_bytes(&self) -> Result<Vec<u8>, CommitmentTreeError> {
+        let (kind, payload) = match self {
+            InclusionBundle::Existence(bundle) => (
+                EXISTENCE_BUNDLE_KIND,
+                serialize_to_buffer(bundle, Some(true)),
+            ),
+            InclusionBundle::Absence(bundle) => {
+                (ABSENCE_BUNDLE_KIND, serialize_to_buffer(bundle, Some(true)))
+            }
+        };
+        let payload = payload.map_err(|e| invalid_bundle(e.to_string()))?;
+
+        let total_len = INCLUSION_BUNDLE_HEADER_SIZE + payload.len();
+        if total_len > MAX_INCLUSION_BUNDLE_SIZE {
+            return Err(invalid_bundle(format!(
+                "bundle is {} bytes long, more than the maximum of {}",
+                total_len, MAX_INCLUSION_BUNDLE_SIZE
+            )));
+        }
+
+        let mut bytes = Vec::with_capacity(total_len);
+        bytes.push(INCLUSION_BUNDLE_VERSION);
+        bytes.extend_from_slice(&(total_len as u32).to_le_bytes());
+        bytes.push(kind);
+        bytes.extend_from_slice(&payload);
+        Ok(bytes)
+    }
+
+    // Deserializes a bundle from the interchange format, without verifying it
+    // Returns InvalidInclusionBundle error if the version is unknown, if the length prefix doesn't match the length
+    // of the blob, or if the payload is malformed (trailing bytes included)
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, CommitmentTreeError> {
+        if bytes.len() < INCLUSION_BUNDLE_HEADER_SIZE {
+            return Err(invalid_bundle(format!(
+                "bundle is {} bytes long, shorter than its header",
+                bytes.len()
+            )));
+        }
+        if bytes.len() > MAX_INCLUSION_BUNDLE_SIZE {
+            return Err(invalid_bundle(format!(
+                "bundle is {} bytes long, more than the maximum of {}",
+                bytes.len(),
+                MAX_INCLUSION_BUNDLE_SIZE
+            )));
+        }
+        if bytes[0] != INCLUSION_BUNDLE_VERSION {
+            return Err(invalid_bundle(format!("unknown version {}", bytes[0])));
+        }
+
+        let total_len = u32::from_le_bytes(bytes[1..5].try_into().unwrap()) as usize;
+        if total_len != bytes.len() {
+            return Err(invalid_bundle(format!(
+                "length prefix is {} but bundle is {} bytes long",
+                total_len,
+                bytes.len()
+            )));
+        }
+
+        let payload = &bytes[INCLUSION_BUNDLE_HEADER_SIZE..];
+        let max_len = MAX_INCLUSION_BUNDLE_SIZE - INCLUSION_BUNDLE_HEADER_SIZE;
+        match bytes[5] {
+            EXISTENCE_BUNDLE_KIND => {
+                deserialize_canonical_bounded(payload, max_len).map(InclusionBundle::Existence)
+            }
+            ABSENCE_BUNDLE_KIND => {
+                deserialize_canonical_bounded(payload, max_len).map(InclusionBundle::Absence)
+            }
+            kind => return Err(invalid_bundle(format!("unknown kind {}", kind))),
+        }
+        .map_err(|e| invalid_bundle(e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::utils::commitment_tree::rand_fe_vec_with_rng;
+
+    #[test]
+    fn inclusion_bundle_tests() {
+        let mut rng = rand::thread_rng();
+        let fe = rand_fe_vec_with_rng(3, &mut rng);
+        let (sc_id, absent_id) = if fe[0] < fe[1] {
+            (fe[0], fe[1])
+        } else {
+            (fe[1], fe[0])
+        };
+
+        let mut cmt = CommitmentTree::create();
+        assert!(cmt.add_fwt_leaf(&sc_id, &fe[2]));
+
+        // Existence bundle
+        let bytes = cmt.export_inclusion_bundle(&sc_id).unwrap();
+        assert_eq!(bytes[0], INCLUSION_BUNDLE_VERSION);
+        assert_eq!(
+            u32::from_le_bytes(bytes[1..5].try_into().unwrap()) as usize,
+            bytes.len()
+        );
+        let bundle = CommitmentTree::verify_inclusion_bundle(&bytes).unwrap();
+        assert!(matches!(bundle, InclusionBundle::Existence(_)));
+        assert_eq!(bundle.sc_id(), &sc_id);
+        assert_eq!(bundle.commitment(), &cmt.get_commitment().unwrap());
+        assert_eq!(bundle.to_bytes().unwrap(), bytes);
+
+        // Absence bundle
+        let bytes = cmt.export_inclusion_bundle(&absent_id).unwrap();
+        let bundle = CommitmentTree::verify_inclusion_bundle(&bytes).unwrap();
+        assert!(matches!(bundle, InclusionBundle::Absence(_)));
+        assert_eq!(bundle.sc_id(), &absent_id);
+
+        // Malformed blobs are rejected
+        let mut wrong_version = bytes.clone();
+        wrong_version[0] = INCLUSION_BUNDLE_VERSION + 1;
+        let mut wrong_kind = bytes.clone();
+        wrong_kind[5] = 2;
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        for wrong_bytes in [
+            &wrong_version[..],
+            &wrong_kind[..],
+            &trailing[..],
+            &bytes[..bytes.len() - 1],
+            &bytes[..INCLUSION_BUNDLE_HEADER_SIZE - 1],
+        ]
+        .iter()
+        {
+            assert!(matches!(
+                InclusionBundle::from_bytes(wrong_bytes),
+                Err(CommitmentTreeError::InvalidInclusionBundle(_))
+            ));
+        }
+
+        // Well-formed bundles whose proof doesn't verify are rejected too
+        let mut wrong_bundle = InclusionBundle::from_bytes(&bytes).unwrap();
+        if let InclusionBundle::Absence(bundle) = &mut wrong_bundle {
+            bundle.sc_id = sc_id;
+        }
+        assert!(!wrong_bundle.verify());
+        assert!(matches!(
+            CommitmentTree::verify_inclusion_bundle(&wrong_bundle.to_bytes().unwrap()),
+            Err(CommitmentTreeError::InvalidInclusionBundle(_))
+        ));
+    }
+}
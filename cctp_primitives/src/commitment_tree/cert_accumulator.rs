@@ -0,0 +1,101 @@
+use crate::{
+    type_mapping::{Error, FieldElement},
+    utils::commitment_tree::hash_vec,
+};
+use algebra::Field;
+
+// Running hash (hash-chain) of the Certificates of a sidechain, as an alternative layout to the CERT subtree
+// which is cheaper to verify in-circuit: starting from zero, each Certificate's hash is absorbed as
+//     value = hash( value | cert )
+// so that the value commits to the whole, ordered, sequence of Certificates
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CertLinearAccumulator {
+    value: FieldElement, // current value of the hash-chain
+    num_leaves: usize,   // number of Certificates absorbed so far
+}
+
+impl Default for CertLinearAccumulator {
+    fn default() -> Self {
+        Self::create()
+    }
+}
+
+impl CertLinearAccumulator {
+    // Creates an empty accumulator
+    pub fn create() -> Self {
+        Self {
+            value: FieldElement::zero(),
+            num_leaves: 0,
+        }
+    }
+
+    // Creates an accumulator absorbing the specified Certificates' hashes, in order
+    pub fn from_leaves<'a>(
+        leaves: impl IntoIterator<Item = &'a FieldElement>,
+    ) -> Result<Self, Error> {
+        let mut acc = Self::create();
+        for leaf in leaves {
+            acc.add(leaf)?;
+        }
+        Ok(acc)
+    }
+
+    // Gets the accumulator resulting from absorbing a specified Certificate's hash, without modifying the current one
+    pub fn next(&self, cert: &FieldElement) -> Result<Self, Error> {
+        Ok(Self {
+            value: hash_vec(vec![self.value, *cert])?,
+            num_leaves: self.num_leaves + 1,
+        })
+    }
+
+    // Absorbs a Certificate's hash into the accumulator
+    pub fn add(&mut self, cert: &FieldElement) -> Result<(), Error> {
+        *self = self.next(cert)?;
+        Ok(())
+    }
+
+    // Gets the current value of the accumulator
+    pub fn value(&self) -> FieldElement {
+        self.value
+    }
+
+    // Gets the number of Certificates absorbed so far
+    pub fn num_leaves(&self) -> usize {
+        self.num_leaves
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::utils::commitment_tree::rand_fe_vec_with_rng;
+
+    #[test]
+    fn cert_linear_accumulator_tests() {
+        let mut rng = rand::thread_rng();
+        let certs = rand_fe_vec_with_rng(3, &mut rng);
+
+        let mut acc = CertLinearAccumulator::create();
+        assert_eq!(acc.value(), FieldElement::zero());
+        assert_eq!(acc.num_leaves(), 0);
+
+        let mut expected = FieldElement::zero();
+        for (i, cert) in certs.iter().enumerate() {
+            let next_acc = acc.next(cert).unwrap();
+            assert_eq!(acc.num_leaves(), i);
+            acc.add(cert).unwrap();
+            expected = hash_vec(vec![expected, *cert]).unwrap();
+            assert_eq!(acc.value(), expected);
+            assert_eq!(acc, next_acc);
+            assert_eq!(acc.num_leaves(), i + 1);
+        }
+        assert_eq!(
+            CertLinearAccumulator::from_leaves(certs.iter()).unwrap(),
+            acc
+        );
+
+        // The value depends on the order of the Certificates
+        let reversed = CertLinearAccumulator::from_leaves(certs.iter().rev()).unwrap();
+        assert_ne!(reversed.value(), acc.value());
+    }
+}
@@ -0,0 +1,195 @@
+use crate::{
+    commitment_tree::{
+        error::CommitmentTreeError,
+        proofs::ScSubtreeType,
+        sidechain_tree_alive::{BWTR_MT_HEIGHT, CERT_MT_HEIGHT, FWT_MT_HEIGHT},
+        sidechain_tree_ceased::CSW_MT_HEIGHT,
+        CMT_MT_HEIGHT,
+    },
+    utils::commitment_tree::pow2,
+};
+use algebra::serialize::{
+    CanonicalDeserialize, CanonicalSerialize, Read, SerializationError, Write,
+};
+
+// Heights of the Commitment-MT and of the subtrees of the sidechains of a CommitmentTree, chosen at its creation
+// The default ones, i.e. the tunable parameters CMT_MT_HEIGHT, FWT_MT_HEIGHT, etc., are the ones of the consensus rules;
+// smaller heights allow e.g. test environments to use smaller trees
+// Note: the commitment of a CommitmentTree, and the proofs built from it, depend on the heights, so proofs must be verified
+//       with the same config of the CommitmentTree they are built from
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct CommitmentTreeConfig {
+    pub cmt_height: usize, // height of the Commitment-MT, whose leaves are the SC-commitments
+    pub fwt_height: usize, // height of the FWT subtree of a SidechainTreeAlive
+    pub bwtr_height: usize, // height of the BWTR subtree of a SidechainTreeAlive
+    pub cert_height: usize, // height of the CERT subtree of a SidechainTreeAlive
+    pub csw_height: usize, // height of the CSW subtree of a SidechainTreeCeased
+}
+
+impl Default for CommitmentTreeConfig {
+    fn default() -> Self {
+        Self {
+            cmt_height: CMT_MT_HEIGHT,
+            fwt_height: FWT_MT_HEIGHT,
+            bwtr_height: BWTR_MT_HEIGHT,
+            cert_height: CERT_MT_HEIGHT,
+            csw_height: CSW_MT_HEIGHT,
+        }
+    }
+}
+
+impl CommitmentTreeConfig {
+    // Checks that each of the heights is at least 1 and at most the one of the consensus rules, i.e. the default one
+    // Note: the heights may come from untrusted bytes, e.g. a serialized CommitmentTree, while the time and memory
+    //       needed to build a MT grow exponentially with its height, so heights bigger than the consensus ones, even if
+    //       supported by the MT parameters, are rejected
+    // Returns InvalidConfig error reporting the first unsupported height otherwise
+    pub fn validate(&self) -> Result<(), CommitmentTreeError> {
+        let max_config = Self::default();
+        for (name, height, max_height) in [
+            ("cmt_height", self.cmt_height, max_config.cmt_height),
+            ("fwt_height", self.fwt_height, max_config.fwt_height),
+            ("bwtr_height", self.bwtr_height, max_config.bwtr_height),
+            ("cert_height", self.cert_height, max_config.cert_height),
+            ("csw_height", self.csw_height, max_config.csw_height),
+        ]
+        .iter()
+        {
+            if *height == 0 || *height > *max_height {
+                return Err(CommitmentTreeError::InvalidConfig(format!(
+                    "{} {} is out of the supported range [1, {}]",
+                    name, height, max_height
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    // Gets the maximum number of sidechains, i.e. of SC-commitments, the Commitment-MT can contain
+    pub fn cmt_capacity(&self) -> usize {
+        pow2(self.cmt_height)
+    }
+
+    // Gets the height of the subtree of a specified type of a sidechain
+    pub fn subtree_height(&self, subtree_type: ScSubtreeType) -> usize {
+        match subtree_type {
+            ScSubtreeType::FWT => self.fwt_height,
+            ScSubtreeType::BWTR => self.bwtr_height,
+            ScSubtreeType::CERT => self.cert_height,
+            ScSubtreeType::CSW => self.csw_height,
+        }
+    }
+
+    // Gets the maximum number of leaves the subtree of a specified type of a sidechain can contain
+    pub fn subtree_capacity(&self, subtree_type: ScSubtreeType) -> usize {
+        pow2(self.subtree_height(subtree_type))
+    }
+
+    // Gets the heights in the order of the fields
+    fn heights(&self) -> [usize; 5] {
+        [
+            self.cmt_height,
+            self.fwt_height,
+            self.bwtr_height,
+            self.cert_height,
+            self.csw_height,
+        ]
+    }
+}
+
+// A CommitmentTreeConfig is serialized as its heights, in the order of its fields, one byte each
+impl CanonicalSerialize for CommitmentTreeConfig {
+    fn serialize<W: Write>(&self, mut writer: W) -> Result<(), SerializationError> {
+        for height in self.heights().iter() {
+            CanonicalSerialize::serialize(&(*height as u8), &mut writer)?;
+        }
+        Ok(())
+    }
+
+    fn serialized_size(&self) -> usize {
+        self.heights().len()
+    }
+}
+
+impl CanonicalDeserialize for CommitmentTreeConfig {
+    fn deserialize<R: Read>(mut reader: R) -> Result<Self, SerializationError> {
+        let mut heights = [0usize; 5];
+        for height in heights.iter_mut() {
+            let byte: u8 = CanonicalDeserialize::deserialize(&mut reader)?;
+            *height = byte as usize;
+        }
+        let config = Self {
+            cmt_height: heights[0],
+            fwt_height: heights[1],
+            bwtr_height: heights[2],
+            cert_height: heights[3],
+            csw_height: heights[4],
+        };
+        config
+            .validate()
+            .map_err(|_| SerializationError::InvalidData)?;
+        Ok(config)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use algebra::test_canonical_serialize_deserialize;
+
+    #[test]
+    fn commitment_tree_config_tests() {
+        let config = CommitmentTreeConfig::default();
+        assert!(config.validate().is_ok());
+        assert_eq!(config.cmt_capacity(), pow2(CMT_MT_HEIGHT));
+        assert_eq!(config.subtree_height(ScSubtreeType::FWT), FWT_MT_HEIGHT);
+        assert_eq!(config.subtree_height(ScSubtreeType::BWTR), BWTR_MT_HEIGHT);
+        assert_eq!(config.subtree_height(ScSubtreeType::CERT), CERT_MT_HEIGHT);
+        assert_eq!(config.subtree_height(ScSubtreeType::CSW), CSW_MT_HEIGHT);
+        assert_eq!(
+            config.subtree_capacity(ScSubtreeType::CSW),
+            pow2(CSW_MT_HEIGHT)
+        );
+        test_canonical_serialize_deserialize(true, &config);
+
+        let config = CommitmentTreeConfig {
+            cmt_height: 3,
+            fwt_height: 1,
+            bwtr_height: 2,
+            cert_height: CERT_MT_HEIGHT,
+            csw_height: 5,
+        };
+        assert!(config.validate().is_ok());
+        assert_eq!(config.cmt_capacity(), 8);
+        assert_eq!(config.subtree_capacity(ScSubtreeType::BWTR), 4);
+        test_canonical_serialize_deserialize(true, &config);
+
+        // Heights out of the supported range are rejected, also on deserialization
+        for invalid_config in [
+            CommitmentTreeConfig {
+                cmt_height: 0,
+                ..config
+            },
+            CommitmentTreeConfig {
+                csw_height: CSW_MT_HEIGHT + 1,
+                ..config
+            },
+            CommitmentTreeConfig {
+                fwt_height: u8::MAX as usize,
+                ..config
+            },
+        ]
+        .iter()
+        {
+            assert!(matches!(
+                invalid_config.validate(),
+                Err(CommitmentTreeError::InvalidConfig(_))
+            ));
+            let mut bytes = Vec::new();
+            CanonicalSerialize::serialize(invalid_config, &mut bytes).unwrap();
+            assert!(
+                <CommitmentTreeConfig as CanonicalDeserialize>::deserialize(&bytes[..]).is_err()
+            );
+        }
+    }
+}
@@ -0,0 +1,67 @@
+use crate::type_mapping::FieldElement;
+use std::cmp::Ordering;
+
+// Summary of a Certificate of a sidechain for a withdrawal epoch, i.e. the data the consensus rules consider
+// when picking the top-quality Certificate of the epoch
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct CertSummary {
+    pub quality: u64,            // quality of the Certificate
+    pub data_hash: FieldElement, // data hash of the Certificate, as computed by hash_cert
+}
+
+impl CertSummary {
+    // Compares two Certificates according to the consensus rules: the one with the higher quality comes first;
+    // ties in quality are broken in favour of the Certificate with the smaller data hash (in FieldElement order)
+    pub fn consensus_cmp(&self, other: &Self) -> Ordering {
+        other
+            .quality
+            .cmp(&self.quality)
+            .then_with(|| self.data_hash.cmp(&other.data_hash))
+    }
+}
+
+// Selects the top-quality Certificate among the ones of a sidechain for a withdrawal epoch, as mainchain does,
+// i.e. the first one according to CertSummary::consensus_cmp
+// Returns None if there are no Certificates; if some Certificates are identical, the first of them is returned
+pub fn select_top_quality_cert(certs: &[CertSummary]) -> Option<&CertSummary> {
+    certs.iter().min_by(|a, b| a.consensus_cmp(b))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::testing::{max_fe, min_fe};
+    use crate::utils::commitment_tree::rand_fe_with_rng;
+
+    #[test]
+    fn select_top_quality_cert_tests() {
+        let mut rng = rand::thread_rng();
+        assert!(select_top_quality_cert(&[]).is_none());
+
+        let cert = |quality: u64, data_hash: FieldElement| CertSummary { quality, data_hash };
+        let certs = vec![
+            cert(10, rand_fe_with_rng(&mut rng)),
+            cert(20, max_fe()),
+            cert(15, rand_fe_with_rng(&mut rng)),
+            cert(20, min_fe()),
+            cert(5, min_fe()),
+        ];
+
+        // The highest quality wins, ties are broken by the smallest data hash
+        assert_eq!(select_top_quality_cert(&certs), Some(&certs[3]));
+        assert_eq!(select_top_quality_cert(&certs[..3]), Some(&certs[1]));
+        assert_eq!(select_top_quality_cert(&certs[4..]), Some(&certs[4]));
+
+        // The result doesn't depend on the order of the Certificates
+        let mut reversed = certs.clone();
+        reversed.reverse();
+        assert_eq!(select_top_quality_cert(&reversed), Some(&certs[3]));
+
+        // The first of identical Certificates is returned
+        let identical = vec![certs[3], certs[3]];
+        assert!(std::ptr::eq(
+            select_top_quality_cert(&identical).unwrap(),
+            &identical[0]
+        ));
+    }
+}
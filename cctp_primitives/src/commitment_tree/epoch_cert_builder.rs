@@ -0,0 +1,248 @@
+use crate::{
+    commitment_tree::{hashers::hash_cert, CommitmentTree},
+    proving_system::verifier::ceased_sidechain_withdrawal::CSWProofUserInputs,
+    type_mapping::{Error, FieldElement, MC_PK_SIZE},
+    utils::data_structures::BackwardTransfer,
+};
+
+// Certificate of a sidechain for a withdrawal epoch, i.e. the values hash_cert takes (but the sidechain ID)
+#[derive(Clone, Debug, PartialEq)]
+pub struct EpochCert {
+    pub epoch_number: u32,
+    pub quality: u64,
+    pub bt_list: Vec<BackwardTransfer>,
+    pub custom_fields: Option<Vec<FieldElement>>, // aka proof_data - includes custom_field_elements and bit_vectors merkle roots
+    pub end_cumulative_sc_tx_commitment_tree_root: FieldElement,
+    pub btr_fee: u64,
+    pub ft_min_amount: u64,
+}
+
+impl EpochCert {
+    // Computes the data hash of the Certificate of a specified sidechain, i.e. its leaf in the CERT subtree
+    pub fn data_hash(&self, sc_id: &FieldElement) -> Result<FieldElement, Error> {
+        hash_cert(
+            sc_id,
+            self.epoch_number,
+            self.quality,
+            Some(self.bt_list.as_slice()),
+            self.custom_fields
+                .as_ref()
+                .map(|custom_fields| custom_fields.iter().collect()),
+            &self.end_cumulative_sc_tx_commitment_tree_root,
+            self.btr_fee,
+            self.ft_min_amount,
+        )
+    }
+}
+
+// Reference to a Certificate accumulated by an EpochCertBuilder
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct EpochCertRef {
+    pub index: usize, // position of the Certificate among the accumulated ones
+    pub quality: u64, // quality of the Certificate
+    pub data_hash: FieldElement, // data hash of the Certificate, as computed by hash_cert
+}
+
+// Accumulates the Certificates of a sidechain for a withdrawal epoch, in the order they are included in mainchain,
+// hashing each of them once and keeping track of the top-quality one, i.e. the one which is finally considered
+// for the epoch, from which the CSW circuit takes the cert data hash and the end cumulative SC tx commitment root
+// Ties in quality are won by the Certificate accumulated first
+pub struct EpochCertBuilder {
+    sc_id: FieldElement,
+    epoch_number: u32,
+    certs: Vec<(EpochCert, FieldElement)>, // accumulated Certificates, with their data hashes
+    top_quality_index: Option<usize>,      // position of the top-quality Certificate, if any
+}
+
+impl EpochCertBuilder {
+    // Creates a new instance of EpochCertBuilder accumulating the Certificates of a sidechain for a withdrawal epoch
+    pub fn create(sc_id: &FieldElement, epoch_number: u32) -> Self {
+        Self {
+            sc_id: *sc_id,
+            epoch_number,
+            certs: Vec::new(),
+            top_quality_index: None,
+        }
+    }
+
+    // Gets the ID of the sidechain
+    pub fn sc_id(&self) -> &FieldElement {
+        &self.sc_id
+    }
+
+    // Gets the withdrawal epoch
+    pub fn epoch_number(&self) -> u32 {
+        self.epoch_number
+    }
+
+    // Gets the number of accumulated Certificates
+    pub fn num_certs(&self) -> usize {
+        self.certs.len()
+    }
+
+    // Accumulates a Certificate, returning a reference to it
+    // Returns an error if the Certificate belongs to another withdrawal epoch or if it can't be hashed
+    pub fn add_cert(&mut self, cert: EpochCert) -> Result<EpochCertRef, Error> {
+        if cert.epoch_number != self.epoch_number {
+            Err(format!(
+                "Certificate of epoch {} can't be added to the certificates of epoch {}",
+                cert.epoch_number, self.epoch_number
+            ))?
+        }
+
+        let data_hash = cert.data_hash(&self.sc_id)?;
+        let index = self.certs.len();
+        if self.best_quality().map_or(true, |best| cert.quality > best) {
+            self.top_quality_index = Some(index);
+        }
+        let quality = cert.quality;
+        self.certs.push((cert, data_hash));
+
+        Ok(EpochCertRef {
+            index,
+            quality,
+            data_hash,
+        })
+    }
+
+    // Gets the quality of the top-quality Certificate, if any
+    pub fn best_quality(&self) -> Option<u64> {
+        self.top_quality_cert().map(|cert_ref| cert_ref.quality)
+    }
+
+    // Gets a reference to the top-quality Certificate, if any
+    pub fn top_quality_cert(&self) -> Option<EpochCertRef> {
+        self.top_quality_index.map(|index| EpochCertRef {
+            index,
+            quality: self.certs[index].0.quality,
+            data_hash: self.certs[index].1,
+        })
+    }
+
+    // Gets an accumulated Certificate
+    pub fn get_cert(&self, index: usize) -> Option<&EpochCert> {
+        self.certs.get(index).map(|(cert, _)| cert)
+    }
+
+    // Gets the data hashes of the accumulated Certificates, in order, i.e. their leaves in the CERT subtree
+    pub fn cert_leaves(&self) -> Vec<FieldElement> {
+        self.certs.iter().map(|(_, data_hash)| *data_hash).collect()
+    }
+
+    // Adds the accumulated Certificates to a CommitmentTree, without hashing them again
+    // Returns false if some of them can't be added, e.g. because the sidechain is ceased or its CERT subtree is full
+    pub fn add_to_commitment_tree(&self, cmt: &mut CommitmentTree) -> bool {
+        self.certs
+            .iter()
+            .all(|(_, data_hash)| cmt.add_cert_leaf(&self.sc_id, data_hash))
+    }
+
+    // Gets the inputs of the CSW circuit for a withdrawal from the sidechain, bound to the top-quality Certificate
+    // Returns None if no Certificate has been accumulated
+    pub fn csw_user_inputs<'a>(
+        &'a self,
+        amount: u64,
+        constant: Option<&'a FieldElement>,
+        nullifier: &'a FieldElement,
+        pub_key_hash: &'a [u8; MC_PK_SIZE],
+    ) -> Option<CSWProofUserInputs<'a>> {
+        let (cert, cert_data_hash) = &self.certs[self.top_quality_index?];
+        Some(CSWProofUserInputs {
+            amount,
+            constant,
+            sc_id: &self.sc_id,
+            nullifier,
+            pub_key_hash,
+            cert_data_hash,
+            end_cumulative_sc_tx_commitment_tree_root: &cert
+                .end_cumulative_sc_tx_commitment_tree_root,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::utils::commitment_tree::{rand_fe_vec_with_rng, rand_fe_with_rng};
+    use rand::Rng;
+
+    #[test]
+    fn epoch_cert_builder_tests() {
+        let mut rng = rand::thread_rng();
+        let sc_id = rand_fe_with_rng(&mut rng);
+        let epoch_number = 5;
+
+        let mut builder = EpochCertBuilder::create(&sc_id, epoch_number);
+        assert!(builder.top_quality_cert().is_none());
+        assert!(builder
+            .csw_user_inputs(10, None, &sc_id, &[0u8; MC_PK_SIZE])
+            .is_none());
+
+        // Certificates of increasing, equal and decreasing quality
+        let mut expected_cmt = CommitmentTree::create();
+        let certs = [10u64, 20, 20, 15]
+            .iter()
+            .map(|&quality| EpochCert {
+                epoch_number,
+                quality,
+                bt_list: vec![BackwardTransfer::default(); 2],
+                custom_fields: Some(rand_fe_vec_with_rng(2, &mut rng)),
+                end_cumulative_sc_tx_commitment_tree_root: rand_fe_with_rng(&mut rng),
+                btr_fee: rng.gen(),
+                ft_min_amount: rng.gen(),
+            })
+            .collect::<Vec<_>>();
+        for (i, cert) in certs.iter().enumerate() {
+            assert!(expected_cmt.add_cert(
+                &sc_id,
+                cert.epoch_number,
+                cert.quality,
+                Some(cert.bt_list.as_slice()),
+                cert.custom_fields
+                    .as_ref()
+                    .map(|custom_fields| custom_fields.iter().collect()),
+                &cert.end_cumulative_sc_tx_commitment_tree_root,
+                cert.btr_fee,
+                cert.ft_min_amount,
+            ));
+            let cert_ref = builder.add_cert(cert.clone()).unwrap();
+            assert_eq!(cert_ref.index, i);
+            assert_eq!(cert_ref.data_hash, cert.data_hash(&sc_id).unwrap());
+        }
+        assert_eq!(builder.num_certs(), certs.len());
+        assert_eq!(builder.get_cert(3), Some(&certs[3]));
+
+        // The first Certificate with the highest quality is the top-quality one
+        let top = builder.top_quality_cert().unwrap();
+        assert_eq!(top.index, 1);
+        assert_eq!(builder.best_quality(), Some(20));
+
+        // Same CERT subtree as the one of the CommitmentTree
+        assert_eq!(
+            builder.cert_leaves(),
+            expected_cmt.get_cert_leaves(&sc_id).unwrap()
+        );
+        let mut cmt = CommitmentTree::create();
+        assert!(builder.add_to_commitment_tree(&mut cmt));
+        assert_eq!(cmt.get_commitment(), expected_cmt.get_commitment());
+
+        // CSW inputs are bound to the top-quality Certificate
+        let nullifier = rand_fe_with_rng(&mut rng);
+        let pub_key_hash = [1u8; MC_PK_SIZE];
+        let csw_inputs = builder
+            .csw_user_inputs(10, None, &nullifier, &pub_key_hash)
+            .unwrap();
+        assert_eq!(csw_inputs.sc_id, &sc_id);
+        assert_eq!(csw_inputs.cert_data_hash, &top.data_hash);
+        assert_eq!(
+            csw_inputs.end_cumulative_sc_tx_commitment_tree_root,
+            &certs[1].end_cumulative_sc_tx_commitment_tree_root
+        );
+
+        // Certificates of other epochs are rejected
+        let mut other_epoch_cert = certs[0].clone();
+        other_epoch_cert.epoch_number += 1;
+        assert!(builder.add_cert(other_epoch_cert).is_err());
+        assert_eq!(builder.num_certs(), certs.len());
+    }
+}
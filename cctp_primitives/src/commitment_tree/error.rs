@@ -0,0 +1,175 @@
+use crate::{
+    commitment_tree::sidechain_tree_alive::SidechainAliveSubtreeType, type_mapping::FieldElement,
+};
+use std::{
+    error::Error,
+    fmt::{Debug, Display},
+};
+
+#[derive(Debug, PartialEq)]
+pub enum CommitmentTreeError {
+    SidechainAlive(FieldElement),
+    SidechainCeased(FieldElement),
+    CommitmentTreeFull,
+    SubtreeFull(FieldElement),
+    DuplicateSidechain(FieldElement),
+    SidechainAliveAndCeased(FieldElement),
+    TooManySidechains(usize),
+    InvalidSidechainCommitment(FieldElement),
+    InvalidCommitment,
+    InvalidFieldElementBytes(String),
+    HashFailed(String),
+    MaxSidechainsReached(usize),
+    NoSubtreeLeaves(SidechainAliveSubtreeType),
+    InvalidCertSubtreeSnapshot(FieldElement),
+    InvalidInclusionBundle(String),
+    InvalidScCreationParams(String),
+    UnsupportedProofFormatVersion(u8, u8), // requested version, supported version
+    InvalidConfig(String),
+}
+
+// Stable, C-ABI friendly codes of the CommitmentTreeError variants, allowing FFI wrappers to map errors
+// without maintaining their own tables
+// Codes must never be changed or reused: new variants get new codes
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[repr(C)]
+pub enum CommitmentTreeErrorCode {
+    SidechainAlive = 200,
+    SidechainCeased = 201,
+    CommitmentTreeFull = 202,
+    SubtreeFull = 203,
+    DuplicateSidechain = 204,
+    SidechainAliveAndCeased = 205,
+    TooManySidechains = 206,
+    InvalidSidechainCommitment = 207,
+    InvalidCommitment = 208,
+    InvalidFieldElementBytes = 209,
+    HashFailed = 210,
+    MaxSidechainsReached = 211,
+    NoSubtreeLeaves = 212,
+    InvalidCertSubtreeSnapshot = 213,
+    InvalidInclusionBundle = 214,
+    InvalidScCreationParams = 215,
+    UnsupportedProofFormatVersion = 216,
+    InvalidConfig = 217,
+}
+
+impl CommitmentTreeError {
+    // Returns the stable code of the error
+    pub fn code(&self) -> CommitmentTreeErrorCode {
+        // No wildcard: adding a variant without a code doesn't compile
+        match self {
+            CommitmentTreeError::SidechainAlive(_) => CommitmentTreeErrorCode::SidechainAlive,
+            CommitmentTreeError::SidechainCeased(_) => CommitmentTreeErrorCode::SidechainCeased,
+            CommitmentTreeError::CommitmentTreeFull => CommitmentTreeErrorCode::CommitmentTreeFull,
+            CommitmentTreeError::SubtreeFull(_) => CommitmentTreeErrorCode::SubtreeFull,
+            CommitmentTreeError::DuplicateSidechain(_) => {
+                CommitmentTreeErrorCode::DuplicateSidechain
+            }
+            CommitmentTreeError::SidechainAliveAndCeased(_) => {
+                CommitmentTreeErrorCode::SidechainAliveAndCeased
+            }
+            CommitmentTreeError::TooManySidechains(_) => CommitmentTreeErrorCode::TooManySidechains,
+            CommitmentTreeError::InvalidSidechainCommitment(_) => {
+                CommitmentTreeErrorCode::InvalidSidechainCommitment
+            }
+            CommitmentTreeError::InvalidCommitment => CommitmentTreeErrorCode::InvalidCommitment,
+            CommitmentTreeError::InvalidFieldElementBytes(_) => {
+                CommitmentTreeErrorCode::InvalidFieldElementBytes
+            }
+            CommitmentTreeError::HashFailed(_) => CommitmentTreeErrorCode::HashFailed,
+            CommitmentTreeError::MaxSidechainsReached(_) => {
+                CommitmentTreeErrorCode::MaxSidechainsReached
+            }
+            CommitmentTreeError::NoSubtreeLeaves(_) => CommitmentTreeErrorCode::NoSubtreeLeaves,
+            CommitmentTreeError::InvalidCertSubtreeSnapshot(_) => {
+                CommitmentTreeErrorCode::InvalidCertSubtreeSnapshot
+            }
+            CommitmentTreeError::InvalidInclusionBundle(_) => {
+                CommitmentTreeErrorCode::InvalidInclusionBundle
+            }
+            CommitmentTreeError::InvalidScCreationParams(_) => {
+                CommitmentTreeErrorCode::InvalidScCreationParams
+            }
+            CommitmentTreeError::UnsupportedProofFormatVersion(_, _) => {
+                CommitmentTreeErrorCode::UnsupportedProofFormatVersion
+            }
+            CommitmentTreeError::InvalidConfig(_) => CommitmentTreeErrorCode::InvalidConfig,
+        }
+    }
+}
+
+impl Display for CommitmentTreeError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            CommitmentTreeError::SidechainAlive(sc_id) => {
+                write!(f, "Sidechain with id: {:?} is alive", sc_id)
+            }
+            CommitmentTreeError::SidechainCeased(sc_id) => {
+                write!(f, "Sidechain with id: {:?} is ceased", sc_id)
+            }
+            CommitmentTreeError::CommitmentTreeFull => {
+                write!(f, "No more sidechains can be added to the Commitment Tree")
+            }
+            CommitmentTreeError::SubtreeFull(sc_id) => write!(
+                f,
+                "No more leaves can be added to the subtree of sidechain with id: {:?}",
+                sc_id
+            ),
+            CommitmentTreeError::DuplicateSidechain(sc_id) => write!(
+                f,
+                "Sidechain with id: {:?} is contained more than once",
+                sc_id
+            ),
+            CommitmentTreeError::SidechainAliveAndCeased(sc_id) => {
+                write!(f, "Sidechain with id: {:?} is both alive and ceased", sc_id)
+            }
+            CommitmentTreeError::TooManySidechains(num_sidechains) => write!(
+                f,
+                "Commitment Tree contains {} sidechains, more than its capacity",
+                num_sidechains
+            ),
+            CommitmentTreeError::InvalidSidechainCommitment(sc_id) => write!(
+                f,
+                "Unable to compute the commitment of sidechain with id: {:?}",
+                sc_id
+            ),
+            CommitmentTreeError::InvalidCommitment => {
+                write!(f, "Unable to compute the commitment of the Commitment Tree")
+            }
+            CommitmentTreeError::InvalidFieldElementBytes(field) => {
+                write!(f, "Invalid field element bytes for: {}", field)
+            }
+            CommitmentTreeError::HashFailed(err) => write!(f, "Unable to compute hash: {}", err),
+            CommitmentTreeError::MaxSidechainsReached(max_sidechains) => write!(
+                f,
+                "Commitment Tree already contains the maximum number of sidechains: {}",
+                max_sidechains
+            ),
+            CommitmentTreeError::NoSubtreeLeaves(subtree_type) => {
+                write!(f, "There are no leaves for {:?} subtree", subtree_type)
+            }
+            CommitmentTreeError::InvalidCertSubtreeSnapshot(sc_id) => write!(
+                f,
+                "Invalid snapshot of the CERT subtree of sidechain with id: {:?}",
+                sc_id
+            ),
+            CommitmentTreeError::InvalidInclusionBundle(reason) => {
+                write!(f, "Invalid inclusion bundle: {}", reason)
+            }
+            CommitmentTreeError::InvalidScCreationParams(reason) => {
+                write!(f, "Invalid sidechain creation parameters: {}", reason)
+            }
+            CommitmentTreeError::UnsupportedProofFormatVersion(requested, supported) => write!(
+                f,
+                "Unsupported proof format version: {}, the supported one is: {}",
+                requested, supported
+            ),
+            CommitmentTreeError::InvalidConfig(reason) => {
+                write!(f, "Invalid Commitment Tree config: {}", reason)
+            }
+        }
+    }
+}
+
+impl Error for CommitmentTreeError {}
@@ -2,7 +2,8 @@ use crate::type_mapping::*;
 use crate::utils::{
     commitment_tree::*,
     data_structures::{BackwardTransfer, BitVectorElementsConfig},
-    get_cert_data_hash,
+    get_cert_data_hash, get_cert_data_hash_from_bt_root_and_custom_fields_hash, join_results,
+    packing::SingleFieldElementPacker,
 };
 
 // Computes FieldElement-based hash on the given Forward Transfer Transaction data
@@ -75,6 +76,30 @@ pub fn hash_cert(
     )
 }
 
+// Computes FieldElement-based hash on the given Certificate data, starting from the already computed
+// Merkle root of the Backward Transfers list and hash of the custom fields (if any)
+pub fn hash_cert_from_parts(
+    sc_id: &FieldElement,
+    epoch_number: u32,
+    quality: u64,
+    bt_root: FieldElement,
+    custom_fields_hash: Option<FieldElement>,
+    end_cumulative_sc_tx_commitment_tree_root: &FieldElement,
+    btr_fee: u64,
+    ft_min_amount: u64,
+) -> Result<FieldElement, Error> {
+    get_cert_data_hash_from_bt_root_and_custom_fields_hash(
+        sc_id,
+        epoch_number,
+        quality,
+        bt_root,
+        custom_fields_hash,
+        end_cumulative_sc_tx_commitment_tree_root,
+        btr_fee,
+        ft_min_amount,
+    )
+}
+
 // Computes FieldElement-based hash on the given Sidechain Creation Transaction data
 pub fn hash_scc(
     amount: u64,
@@ -141,66 +166,145 @@ pub fn hash_scc(
     debug_assert!(fees_field_elements.len() == 1);
     fes.append(&mut fees_field_elements);
 
-    // Compute custom_creation_data hash and add it to fes
-    if custom_creation_data.is_some() {
-        fes.push(
-            DataAccumulator::init()
-                .update(custom_creation_data.unwrap())?
-                .compute_field_hash_constant_length()?,
-        );
+    // Compute custom_creation_data, cert_verification_key and csw_verification_key (if present) hashes,
+    // which are independent of each other (concurrently, if the parallel feature is enabled)
+    let (custom_creation_data_hash, vks_hashes) = join_results(
+        || custom_creation_data.map(hash_bytes_streaming).transpose(),
+        || {
+            let (cert_vk_hash, csw_vk_hash) = join_results(
+                || hash_bytes_streaming(cert_verification_key),
+                || csw_verification_key.map(hash_bytes_streaming).transpose(),
+            );
+            Ok((cert_vk_hash?, csw_vk_hash?))
+        },
+    );
+    let (cert_vk_hash, csw_vk_hash) = vks_hashes?;
+
+    // Add custom_creation_data hash (if present) to fes
+    if let Some(custom_creation_data_hash) = custom_creation_data_hash? {
+        fes.push(custom_creation_data_hash);
     }
 
     if let Some(constant) = constant {
         fes.push(*constant);
     }
 
-    // Compute cert_verification_key hash and add it to fes
-    fes.push(
-        DataAccumulator::init()
-            .update(cert_verification_key)?
-            .compute_field_hash_constant_length()?,
-    );
+    // Add cert_verification_key hash to fes
+    fes.push(cert_vk_hash);
 
-    // Compute csw_verification_key hash (if present) and add it to fes
-    if csw_verification_key.is_some() {
-        fes.push(
-            DataAccumulator::init()
-                .update(csw_verification_key.unwrap())?
-                .compute_field_hash_constant_length()?,
-        );
+    // Add csw_verification_key hash (if present) to fes
+    if let Some(csw_vk_hash) = csw_vk_hash {
+        fes.push(csw_vk_hash);
     }
 
     // Compute final hash
     hash_vec(fes)
 }
 
+// Computes the same hash of the given (possibly huge, e.g. a verification key) data as
+// DataAccumulator::compute_field_hash_constant_length, without holding all of its bits at once
+fn hash_bytes_streaming(data: &[u8]) -> Result<FieldElement, Error> {
+    let mut accumulator = StreamingDataAccumulator::init(8 * data.len());
+    accumulator.update_streaming(data)?;
+    accumulator.finalize()
+}
+
 // Computes FieldElement-based hash on the given Ceased Sidechain Withdrawal data
 pub fn hash_csw(
     amount: u64,
     nullifier: &FieldElement,
     mc_pk_hash: &[u8; MC_PK_SIZE],
 ) -> Result<FieldElement, Error> {
-    // Pack amount and pk_hash into a single field element
-    let mut fes = DataAccumulator::init()
-        .update(amount)?
-        .update(&mc_pk_hash[..])?
-        .get_field_elements()?;
-    debug_assert!(fes.len() == 1);
+    // Pack amount and pk_hash into a single field element (width checked at compile time through CSW_AMOUNT_PK_HASH_BITS)
+    let amount_pk_hash = SingleFieldElementPacker::init()
+        .pack(amount)?
+        .pack_array(mc_pk_hash)?
+        .get_field_element()?;
+
+    // Return final hash, with the nullifier
+    hash_vec(vec![amount_pk_hash, *nullifier])
+}
 
-    // Push the nullifier to fes
-    fes.push(*nullifier);
+// Self-audit helpers: recompute the hash of the given transaction/certificate data and check it
+// against the leaf actually inserted in the CommitmentTree.
+// Return Ok(false) on mismatch, or an error if the hash can't be computed at all.
 
-    // Return final hash
-    hash_vec(fes)
+// Checks that the given Forward Transfer Transaction data hashes to `leaf_hash`
+pub fn check_fwt_consistency(
+    amount: u64,
+    pub_key: &[u8; 32],
+    mc_return_address: &[u8; 20],
+    tx_hash: &[u8; 32],
+    out_idx: u32,
+    leaf_hash: &FieldElement,
+) -> Result<bool, Error> {
+    Ok(hash_fwt(amount, pub_key, mc_return_address, tx_hash, out_idx)? == *leaf_hash)
+}
+
+// Checks that the given Backward Transfer Request Transaction data hashes to `leaf_hash`
+pub fn check_bwtr_consistency(
+    sc_fee: u64,
+    sc_request_data: Vec<&FieldElement>,
+    mc_destination_address: &[u8; MC_PK_SIZE],
+    tx_hash: &[u8; 32],
+    out_idx: u32,
+    leaf_hash: &FieldElement,
+) -> Result<bool, Error> {
+    Ok(hash_bwtr(
+        sc_fee,
+        sc_request_data,
+        mc_destination_address,
+        tx_hash,
+        out_idx,
+    )? == *leaf_hash)
+}
+
+// Checks that the given Certificate data hashes to `leaf_hash`
+pub fn check_cert_consistency(
+    sc_id: &FieldElement,
+    epoch_number: u32,
+    quality: u64,
+    bt_list: Option<&[BackwardTransfer]>,
+    custom_fields: Option<Vec<&FieldElement>>, //aka proof_data - includes custom_field_elements and bit_vectors merkle roots
+    end_cumulative_sc_tx_commitment_tree_root: &FieldElement,
+    btr_fee: u64,
+    ft_min_amount: u64,
+    leaf_hash: &FieldElement,
+) -> Result<bool, Error> {
+    Ok(hash_cert(
+        sc_id,
+        epoch_number,
+        quality,
+        bt_list,
+        custom_fields,
+        end_cumulative_sc_tx_commitment_tree_root,
+        btr_fee,
+        ft_min_amount,
+    )? == *leaf_hash)
+}
+
+// Checks that the given Ceased Sidechain Withdrawal data hashes to `leaf_hash`
+pub fn check_csw_consistency(
+    amount: u64,
+    nullifier: &FieldElement,
+    mc_pk_hash: &[u8; MC_PK_SIZE],
+    leaf_hash: &FieldElement,
+) -> Result<bool, Error> {
+    Ok(hash_csw(amount, nullifier, mc_pk_hash)? == *leaf_hash)
 }
 
 #[cfg(test)]
 mod test {
-    use crate::commitment_tree::hashers::{hash_bwtr, hash_cert, hash_csw, hash_fwt, hash_scc};
+    use crate::commitment_tree::hashers::{
+        check_bwtr_consistency, check_cert_consistency, check_csw_consistency,
+        check_fwt_consistency, hash_bwtr, hash_cert, hash_cert_from_parts, hash_csw, hash_fwt,
+        hash_scc,
+    };
     use crate::type_mapping::MC_PK_SIZE;
     use crate::utils::{
-        commitment_tree::{rand_fe, rand_fe_vec, rand_vec},
+        commitment_tree::{hash_vec, rand_fe_vec_with_rng, rand_fe_with_rng, rand_vec_with_rng},
         data_structures::{BackwardTransfer, BitVectorElementsConfig},
+        get_bt_merkle_root,
     };
     use rand::Rng;
     use std::convert::TryInto;
@@ -211,42 +315,42 @@ mod test {
 
         assert!(hash_fwt(
             rng.gen(),
-            &rand_vec(32).try_into().unwrap(),
-            &rand_vec(20).try_into().unwrap(),
-            &rand_vec(32).try_into().unwrap(),
+            &rand_vec_with_rng(32, &mut rng).try_into().unwrap(),
+            &rand_vec_with_rng(20, &mut rng).try_into().unwrap(),
+            &rand_vec_with_rng(32, &mut rng).try_into().unwrap(),
             rng.gen()
         )
         .is_ok());
 
         assert!(hash_bwtr(
             rng.gen(),
-            rand_fe_vec(5).iter().collect(),
-            &rand_vec(MC_PK_SIZE).try_into().unwrap(),
-            &rand_vec(32).try_into().unwrap(),
+            rand_fe_vec_with_rng(5, &mut rng).iter().collect(),
+            &rand_vec_with_rng(MC_PK_SIZE, &mut rng).try_into().unwrap(),
+            &rand_vec_with_rng(32, &mut rng).try_into().unwrap(),
             rng.gen()
         )
         .is_ok());
 
         let default_bt_vec = vec![BackwardTransfer::default(); 10];
         assert!(hash_cert(
-            &rand_fe(),
+            &rand_fe_with_rng(&mut rng),
             rng.gen(),
             rng.gen(),
             Some(default_bt_vec.as_slice()),
-            Some(rand_fe_vec(2).iter().collect()),
-            &rand_fe(),
+            Some(rand_fe_vec_with_rng(2, &mut rng).iter().collect()),
+            &rand_fe_with_rng(&mut rng),
             rng.gen(),
             rng.gen(),
         )
         .is_ok());
 
         assert!(hash_cert(
-            &rand_fe(),
+            &rand_fe_with_rng(&mut rng),
             rng.gen(),
             rng.gen(),
             None,
             None,
-            &rand_fe(),
+            &rand_fe_with_rng(&mut rng),
             rng.gen(),
             rng.gen(),
         )
@@ -255,26 +359,26 @@ mod test {
         let default_bv_config = vec![BitVectorElementsConfig::default(); 10];
         assert!(hash_scc(
             rng.gen(),
-            &rand_vec(32).try_into().unwrap(),
-            &rand_vec(32).try_into().unwrap(),
+            &rand_vec_with_rng(32, &mut rng).try_into().unwrap(),
+            &rand_vec_with_rng(32, &mut rng).try_into().unwrap(),
             rng.gen(),
             rng.gen(),
             rng.gen(),
-            Some(&rand_vec(10)),
+            Some(&rand_vec_with_rng(10, &mut rng)),
             Some(default_bv_config.as_slice()),
             rng.gen(),
             rng.gen(),
-            Some(&rand_vec(100)),
-            Some(&rand_fe()),
-            &rand_vec(100),
-            Some(&rand_vec(100))
+            Some(&rand_vec_with_rng(100, &mut rng)),
+            Some(&rand_fe_with_rng(&mut rng)),
+            &rand_vec_with_rng(100, &mut rng),
+            Some(&rand_vec_with_rng(100, &mut rng))
         )
         .is_ok());
 
         assert!(hash_scc(
             rng.gen(),
-            &rand_vec(32).try_into().unwrap(),
-            &rand_vec(32).try_into().unwrap(),
+            &rand_vec_with_rng(32, &mut rng).try_into().unwrap(),
+            &rand_vec_with_rng(32, &mut rng).try_into().unwrap(),
             rng.gen(),
             rng.gen(),
             rng.gen(),
@@ -284,16 +388,181 @@ mod test {
             rng.gen(),
             None,
             None,
-            &rand_vec(100),
+            &rand_vec_with_rng(100, &mut rng),
             None
         )
         .is_ok());
 
         assert!(hash_csw(
             rng.gen(),
-            &rand_fe(),
-            &rand_vec(MC_PK_SIZE).try_into().unwrap()
+            &rand_fe_with_rng(&mut rng),
+            &rand_vec_with_rng(MC_PK_SIZE, &mut rng).try_into().unwrap()
         )
         .is_ok());
     }
+
+    #[test]
+    fn test_hash_cert_from_parts() {
+        let mut rng = rand::thread_rng();
+
+        let sc_id = rand_fe_with_rng(&mut rng);
+        let epoch_number: u32 = rng.gen();
+        let quality: u64 = rng.gen();
+        let bt_list = vec![BackwardTransfer::default(); 10];
+        let custom_fields = rand_fe_vec_with_rng(2, &mut rng);
+        let end_cumulative_sc_tx_commitment_tree_root = rand_fe_with_rng(&mut rng);
+        let btr_fee: u64 = rng.gen();
+        let ft_min_amount: u64 = rng.gen();
+
+        for (bt_list, custom_fields) in vec![
+            (Some(bt_list.as_slice()), Some(custom_fields.clone())),
+            (None, None),
+        ] {
+            let expected = hash_cert(
+                &sc_id,
+                epoch_number,
+                quality,
+                bt_list,
+                custom_fields.as_ref().map(|fes| fes.iter().collect()),
+                &end_cumulative_sc_tx_commitment_tree_root,
+                btr_fee,
+                ft_min_amount,
+            )
+            .unwrap();
+
+            let actual = hash_cert_from_parts(
+                &sc_id,
+                epoch_number,
+                quality,
+                get_bt_merkle_root(bt_list).unwrap(),
+                custom_fields.map(|fes| hash_vec(fes).unwrap()),
+                &end_cumulative_sc_tx_commitment_tree_root,
+                btr_fee,
+                ft_min_amount,
+            )
+            .unwrap();
+
+            assert_eq!(expected, actual);
+        }
+    }
+
+    #[test]
+    fn test_consistency_checks() {
+        let mut rng = rand::thread_rng();
+        let wrong_leaf = rand_fe_with_rng(&mut rng);
+
+        // FWT
+        let (amount, pub_key, mc_return_address, tx_hash, out_idx) =
+            (rng.gen(), rng.gen(), rng.gen(), rng.gen(), rng.gen());
+        let leaf = hash_fwt(amount, &pub_key, &mc_return_address, &tx_hash, out_idx).unwrap();
+        assert!(check_fwt_consistency(
+            amount,
+            &pub_key,
+            &mc_return_address,
+            &tx_hash,
+            out_idx,
+            &leaf
+        )
+        .unwrap());
+        assert!(!check_fwt_consistency(
+            amount,
+            &pub_key,
+            &mc_return_address,
+            &tx_hash,
+            out_idx,
+            &wrong_leaf
+        )
+        .unwrap());
+
+        // BWTR
+        let sc_request_data = rand_fe_vec_with_rng(2, &mut rng);
+        let mc_destination_address = rng.gen();
+        let leaf = hash_bwtr(
+            amount,
+            sc_request_data.iter().collect(),
+            &mc_destination_address,
+            &tx_hash,
+            out_idx,
+        )
+        .unwrap();
+        assert!(check_bwtr_consistency(
+            amount,
+            sc_request_data.iter().collect(),
+            &mc_destination_address,
+            &tx_hash,
+            out_idx,
+            &leaf
+        )
+        .unwrap());
+        assert!(!check_bwtr_consistency(
+            amount,
+            sc_request_data.iter().collect(),
+            &mc_destination_address,
+            &tx_hash,
+            out_idx,
+            &wrong_leaf
+        )
+        .unwrap());
+
+        // CERT
+        let sc_id = rand_fe_with_rng(&mut rng);
+        let bt_list = vec![BackwardTransfer::default(); 3];
+        let custom_fields = rand_fe_vec_with_rng(2, &mut rng);
+        let end_cum_comm_tree_root = rand_fe_with_rng(&mut rng);
+        let leaf = hash_cert(
+            &sc_id,
+            rng.gen(),
+            rng.gen(),
+            Some(&bt_list),
+            Some(custom_fields.iter().collect()),
+            &end_cum_comm_tree_root,
+            rng.gen(),
+            rng.gen(),
+        )
+        .unwrap();
+        assert!(!check_cert_consistency(
+            &sc_id,
+            rng.gen(),
+            rng.gen(),
+            Some(&bt_list),
+            Some(custom_fields.iter().collect()),
+            &end_cum_comm_tree_root,
+            rng.gen(),
+            rng.gen(),
+            &leaf
+        )
+        .unwrap());
+        let (epoch_number, quality, btr_fee, ft_min_amount) =
+            (rng.gen(), rng.gen(), rng.gen(), rng.gen());
+        let leaf = hash_cert(
+            &sc_id,
+            epoch_number,
+            quality,
+            Some(&bt_list),
+            Some(custom_fields.iter().collect()),
+            &end_cum_comm_tree_root,
+            btr_fee,
+            ft_min_amount,
+        )
+        .unwrap();
+        assert!(check_cert_consistency(
+            &sc_id,
+            epoch_number,
+            quality,
+            Some(&bt_list),
+            Some(custom_fields.iter().collect()),
+            &end_cum_comm_tree_root,
+            btr_fee,
+            ft_min_amount,
+            &leaf
+        )
+        .unwrap());
+
+        // CSW
+        let nullifier = rand_fe_with_rng(&mut rng);
+        let mc_pk_hash = rng.gen();
+        let leaf = hash_csw(amount, &nullifier, &mc_pk_hash).unwrap();
+        assert!(check_csw_consistency(amount, &nullifier, &mc_pk_hash, &leaf).unwrap());
+        assert!(!check_csw_consistency(amount, &nullifier, &mc_pk_hash, &wrong_leaf).unwrap());
+    }
 }
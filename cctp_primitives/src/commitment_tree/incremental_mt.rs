@@ -0,0 +1,136 @@
+use crate::{
+    type_mapping::{Error, FieldElement},
+    utils::commitment_tree::{empty_root_at_height, hash_vec, pow2},
+};
+
+// FieldElement-based MT whose leaves can be updated in place, recomputing only the path from the updated leaf to the root
+// Leaves are appended contiguously from the leftmost position, as for a GingerMHT of the same height, which has the same root;
+// only the non-empty nodes are stored, the empty ones being taken from the precomputed empty roots
+pub(crate) struct IncrementalMT {
+    height: usize,
+    levels: Vec<Vec<FieldElement>>, // non-empty nodes of each level, from the leaves (level 0) up to the root (level height)
+}
+
+impl IncrementalMT {
+    // Builds an IncrementalMT of a specified height out of its leaves
+    // Returns an error if the leaves don't fit into the MT or if some node can't be computed
+    pub(crate) fn build(height: usize, leaves: Vec<FieldElement>) -> Result<Self, Error> {
+        if leaves.len() > pow2(height) {
+            Err(format!(
+                "{} leaves don't fit into a MT of height {}",
+                leaves.len(),
+                height
+            ))?
+        }
+
+        let mut levels = Vec::with_capacity(height + 1);
+        levels.push(leaves);
+        for level in 0..height {
+            let children = &levels[level];
+            let mut parents = Vec::with_capacity((children.len() + 1) / 2);
+            for index in (0..children.len()).step_by(2) {
+                parents.push(Self::hash_children(children, level, index)?);
+            }
+            levels.push(parents);
+        }
+        Ok(Self { height, levels })
+    }
+
+    // Gets the leaves of the MT
+    pub(crate) fn leaves(&self) -> &[FieldElement] {
+        &self.levels[0]
+    }
+
+    // Gets the root of the MT
+    pub(crate) fn root(&self) -> Result<FieldElement, Error> {
+        match self.levels[self.height].first() {
+            Some(root) => Ok(*root),
+            None => empty_root_at_height(self.height),
+        }
+    }
+
+    // Replaces the leaf at a specified position, recomputing the nodes on its path to the root
+    // Returns an error if there is no leaf at such position or if some node can't be computed
+    pub(crate) fn update_leaf(&mut self, index: usize, leaf: FieldElement) -> Result<(), Error> {
+        if index >= self.levels[0].len() {
+            Err(format!(
+                "No leaf at position {}: the MT has {} leaves",
+                index,
+                self.levels[0].len()
+            ))?
+        }
+
+        self.levels[0][index] = leaf;
+        let mut index = index;
+        for level in 0..self.height {
+            let parent = Self::hash_children(&self.levels[level], level, index & !1)?;
+            index /= 2;
+            self.levels[level + 1][index] = parent;
+        }
+        Ok(())
+    }
+
+    // Hashes the node at a specified (even) position of a level with its right sibling, which is empty if missing
+    fn hash_children(
+        nodes: &[FieldElement],
+        level: usize,
+        index: usize,
+    ) -> Result<FieldElement, Error> {
+        let right = match nodes.get(index + 1) {
+            Some(right) => *right,
+            None => empty_root_at_height(level)?,
+        };
+        hash_vec(vec![nodes[index], right])
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        type_mapping::GingerMHT,
+        utils::commitment_tree::{rand_fe_vec_with_rng, rand_fe_with_rng},
+    };
+    use primitives::FieldBasedMerkleTree;
+
+    fn ginger_mht_root(height: usize, leaves: &[FieldElement]) -> FieldElement {
+        if leaves.is_empty() {
+            return empty_root_at_height(height).unwrap();
+        }
+        let mut mt = GingerMHT::init(height, pow2(height)).unwrap();
+        for leaf in leaves.iter() {
+            mt.append(*leaf).unwrap();
+        }
+        mt.finalize().unwrap().root().unwrap()
+    }
+
+    #[test]
+    fn incremental_mt_tests() {
+        let mut rng = rand::thread_rng();
+        let height = 4;
+
+        for num_leaves in [0, 1, 2, 5, pow2(height)].iter() {
+            let mut leaves = rand_fe_vec_with_rng(*num_leaves, &mut rng);
+            let mut mt = IncrementalMT::build(height, leaves.clone()).unwrap();
+            assert_eq!(mt.leaves(), leaves.as_slice());
+            assert_eq!(mt.root().unwrap(), ginger_mht_root(height, &leaves));
+
+            // Same root as the one of a MT built from scratch with the updated leaves
+            for (index, leaf) in rand_fe_vec_with_rng(*num_leaves, &mut rng)
+                .into_iter()
+                .enumerate()
+            {
+                mt.update_leaf(index, leaf).unwrap();
+                leaves[index] = leaf;
+                assert_eq!(mt.root().unwrap(), ginger_mht_root(height, &leaves));
+            }
+            assert!(mt
+                .update_leaf(*num_leaves, rand_fe_with_rng(&mut rng))
+                .is_err());
+        }
+
+        assert!(
+            IncrementalMT::build(height, rand_fe_vec_with_rng(pow2(height) + 1, &mut rng)).is_err()
+        );
+    }
+}
@@ -1,22 +1,58 @@
 use crate::{
     commitment_tree::{
-        hashers::{hash_bwtr, hash_cert, hash_csw, hash_fwt, hash_scc},
-        proofs::{ScAbsenceProof, ScCommitmentData, ScExistenceProof, ScNeighbour},
+        bundle::{InclusionBundle, ScAbsenceBundle, ScExistenceBundle},
+        cert_accumulator::CertLinearAccumulator,
+        config::CommitmentTreeConfig,
+        error::CommitmentTreeError,
+        hashers::{hash_bwtr, hash_cert, hash_cert_from_parts, hash_csw, hash_fwt, hash_scc},
+        incremental_mt::IncrementalMT,
+        proofs::{
+            CswLeafProof, LeafExistenceProof, ScAbsenceProof, ScCommitmentData, ScExistenceProof,
+            ScNeighbour, ScSubtreeType,
+        },
+        scc_params::{ScCreationParams, ScCreationParamsBuilder},
         sidechain_tree_alive::{SidechainAliveSubtreeType, SidechainTreeAlive},
         sidechain_tree_ceased::SidechainTreeCeased,
+        snapshot::CertSubtreeSnapshot,
     },
+    proving_system::ProvingSystem,
     type_mapping::*,
     utils::{
-        commitment_tree::{new_mt, pow2},
+        commitment_tree::{
+            add_leaf, empty_root_at_height, hash_vec, new_mt, new_sc_subtree_mt, pow2,
+        },
         data_structures::{BackwardTransfer, BitVectorElementsConfig},
+        serialization::deserialize_from_buffer_strict,
     },
 };
+use algebra::{
+    serialize::{CanonicalDeserialize, CanonicalSerialize, Read, SerializationError, Write},
+    Field,
+};
 use primitives::{FieldBasedMerkleTree, FieldBasedMerkleTreePath};
+use std::{
+    cmp::Ordering,
+    collections::{BTreeMap, BTreeSet},
+    sync::Mutex,
+};
 
+pub mod batch;
+pub mod block_builder;
+pub mod bundle;
+pub mod cert_accumulator;
+pub mod config;
+pub mod consensus_helpers;
+pub mod epoch_cert_builder;
+pub mod error;
 pub mod hashers;
+mod incremental_mt;
 pub mod proofs;
+pub mod reindex;
+pub mod scc_params;
+pub mod shared;
 pub mod sidechain_tree_alive;
 pub mod sidechain_tree_ceased;
+pub mod snapshot;
 
 //--------------------------------------------------------------------------------------------------
 // Commitment Tree
@@ -24,12 +60,305 @@ pub mod sidechain_tree_ceased;
 // Tunable parameters
 pub const CMT_MT_HEIGHT: usize = 12;
 const CMT_MT_CAPACITY: usize = pow2(CMT_MT_HEIGHT);
-const CMT_EMPTY_COMMITMENT: &FieldElement = &GINGER_MHT_POSEIDON_PARAMETERS.nodes[CMT_MT_HEIGHT];
+// Number of subtrees of a SidechainTreeAlive which are finalized to get its commitment, i.e. FWT, BWTR and CERT ones
+const ALIVE_SUBTREES: usize = 3;
+
+// Version of the serialized state of a CommitmentTree, to be bumped whenever its layout changes
+pub const CMT_STATE_VERSION: u8 = 2;
+
+// Already hashed leaves of a sidechain, e.g. as persisted by a node,
+// from which the corresponding SidechainTreeAlive/SidechainTreeCeased can be rebuilt without re-hashing the underlying data
+#[derive(Clone, Debug, PartialEq)]
+#[allow(variant_size_differences)]
+pub enum ScLeafSets {
+    Alive {
+        fwt: Vec<FieldElement>,    // hashes of Forward Transfer Transactions
+        bwtr: Vec<FieldElement>,   // hashes of Backward Transfer Request Transactions
+        cert: Vec<FieldElement>,   // hashes of Certificates
+        scc: Option<FieldElement>, // hash of Sidechain Creation Transaction, if any
+    },
+    Ceased {
+        csw: Vec<FieldElement>, // hashes of Ceased Sidechain Withdrawals
+    },
+}
+
+// Order of the SC-commitments, i.e. of the sidechains, inside of the Commitment-MT, chosen at CommitmentTree creation
+// FieldElementOrder (default) orders SC-IDs as field elements;
+// ByteLexOrder orders SC-IDs lexicographically by their canonical serialization, as mainchain orders them by their bytes
+// Absence proofs depend on the ordering, so they encode the one of the CommitmentTree they are built from,
+// which must be the one expected by the verifier
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum CommitmentTreeOrdering {
+    FieldElementOrder,
+    ByteLexOrder,
+}
+
+impl Default for CommitmentTreeOrdering {
+    fn default() -> Self {
+        CommitmentTreeOrdering::FieldElementOrder
+    }
+}
+
+impl CommitmentTreeOrdering {
+    pub fn to_byte(&self) -> u8 {
+        match self {
+            CommitmentTreeOrdering::FieldElementOrder => 0,
+            CommitmentTreeOrdering::ByteLexOrder => 1,
+        }
+    }
+
+    pub fn from_byte(byte: u8) -> Option<Self> {
+        match byte {
+            0 => Some(CommitmentTreeOrdering::FieldElementOrder),
+            1 => Some(CommitmentTreeOrdering::ByteLexOrder),
+            _ => None,
+        }
+    }
+
+    // Compares two SC-IDs according to the ordering
+    pub fn compare(&self, a: &FieldElement, b: &FieldElement) -> Ordering {
+        match self {
+            CommitmentTreeOrdering::FieldElementOrder => a.cmp(b),
+            CommitmentTreeOrdering::ByteLexOrder => sc_id_bytes(a).cmp(&sc_id_bytes(b)),
+        }
+    }
+
+    // Sorts SC-IDs according to the ordering
+    fn sort(&self, ids: &mut Vec<&FieldElement>) {
+        match self {
+            CommitmentTreeOrdering::FieldElementOrder => ids.sort(),
+            CommitmentTreeOrdering::ByteLexOrder => ids.sort_by_cached_key(|id| sc_id_bytes(id)),
+        }
+    }
+}
+
+// Gets the canonical serialization of an SC-ID
+fn sc_id_bytes(id: &FieldElement) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(FIELD_SIZE);
+    // Serialization of a FieldElement into a Vec can't fail
+    id.serialize(&mut bytes).unwrap();
+    bytes
+}
+
+impl CanonicalSerialize for CommitmentTreeOrdering {
+    fn serialize<W: Write>(&self, writer: W) -> Result<(), SerializationError> {
+        CanonicalSerialize::serialize(&self.to_byte(), writer)
+    }
+
+    fn serialized_size(&self) -> usize {
+        1
+    }
+}
+
+impl CanonicalDeserialize for CommitmentTreeOrdering {
+    fn deserialize<R: Read>(reader: R) -> Result<Self, SerializationError> {
+        let ordering_byte: u8 = CanonicalDeserialize::deserialize(reader)?;
+        Self::from_byte(ordering_byte).ok_or(SerializationError::InvalidData)
+    }
+}
+
+// Sidechain-related event, in terms of already hashed leaves, to be applied to a CommitmentTree by the extend method
+#[derive(Clone, Debug, PartialEq)]
+pub enum ScTxEvent {
+    Fwt {
+        sc_id: FieldElement,
+        leaf: FieldElement,
+    }, // Forward Transfer Transaction
+    Bwtr {
+        sc_id: FieldElement,
+        leaf: FieldElement,
+    }, // Backward Transfer Request Transaction
+    Cert {
+        sc_id: FieldElement,
+        leaf: FieldElement,
+    }, // Certificate
+    Scc {
+        sc_id: FieldElement,
+        leaf: FieldElement,
+    }, // Sidechain Creation Transaction
+    Csw {
+        sc_id: FieldElement,
+        leaf: FieldElement,
+    }, // Ceased Sidechain Withdrawal
+    Ceased {
+        sc_id: FieldElement,
+    }, // Sidechain ceasing, as done by mark_ceased
+}
+
+// Estimates the number of Poseidon hash operations an event causes when the commitment of a CommitmentTree is computed:
+// the update of the path from the leaf to the root of its subtree, the computation of the commitment of its sidechain
+// and the update of the path from the latter to the root of the Commitment-MT
+// Hashing the raw data into the leaf is not included, as it's already done when the event is built
+// The estimate depends only on the type of the event, so that all nodes agree on it, e.g. to charge fees for it:
+// it's based on the default heights, i.e. the ones of the consensus rules
+pub fn hash_operations_estimate(event: &ScTxEvent) -> usize {
+    hash_operations_estimate_with_config(event, &CommitmentTreeConfig::default())
+}
+
+// Same as hash_operations_estimate, for a CommitmentTree with a specified config
+pub fn hash_operations_estimate_with_config(
+    event: &ScTxEvent,
+    config: &CommitmentTreeConfig,
+) -> usize {
+    let subtree_height = match event {
+        ScTxEvent::Fwt { .. } => config.subtree_height(ScSubtreeType::FWT),
+        ScTxEvent::Bwtr { .. } => config.subtree_height(ScSubtreeType::BWTR),
+        ScTxEvent::Cert { .. } => config.subtree_height(ScSubtreeType::CERT),
+        ScTxEvent::Csw { .. } => config.subtree_height(ScSubtreeType::CSW),
+        // SCC is hashed directly into the commitment of its sidechain, which has no CSWs when just ceased
+        ScTxEvent::Scc { .. } | ScTxEvent::Ceased { .. } => 0,
+    };
+    hash_operations_for_subtree_height(subtree_height, config.cmt_height)
+}
+
+// Estimated number of Poseidon hash operations caused by a leaf added to a subtree of a specified height, inside of
+// a Commitment-MT of a specified height
+const fn hash_operations_for_subtree_height(subtree_height: usize, cmt_height: usize) -> usize {
+    subtree_height + 1 + cmt_height
+}
+
+// Computes the commitment of an alive sidechain out of the leaves of its FWT, BWTR and CERT subtrees and its SCC
+// (zero if not set), i.e. the same value SidechainTreeAlive::get_commitment returns, without building a SidechainTreeAlive
+// Returns an error if a subtree can't contain its leaves or if hashing fails
+pub fn compute_sc_commitment_alive(
+    sc_id: &FieldElement,
+    fwt_leaves: &[FieldElement],
+    bwtr_leaves: &[FieldElement],
+    cert_leaves: &[FieldElement],
+    scc: Option<&FieldElement>,
+) -> Result<FieldElement, Error> {
+    compute_sc_commitment_alive_with_config(
+        sc_id,
+        fwt_leaves,
+        bwtr_leaves,
+        cert_leaves,
+        scc,
+        &CommitmentTreeConfig::default(),
+    )
+}
+
+// Same as compute_sc_commitment_alive, with the heights of the subtrees of a specified config
+pub fn compute_sc_commitment_alive_with_config(
+    sc_id: &FieldElement,
+    fwt_leaves: &[FieldElement],
+    bwtr_leaves: &[FieldElement],
+    cert_leaves: &[FieldElement],
+    scc: Option<&FieldElement>,
+    config: &CommitmentTreeConfig,
+) -> Result<FieldElement, Error> {
+    let fwt_root = subtree_root(fwt_leaves, config.fwt_height, "FWT")?;
+    let bwtr_root = subtree_root(bwtr_leaves, config.bwtr_height, "BWTR")?;
+    let cert_root = subtree_root(cert_leaves, config.cert_height, "CERT")?;
+    let scc = scc.copied().unwrap_or_else(FieldElement::zero);
+    hash_vec(vec![fwt_root, bwtr_root, cert_root, scc, *sc_id])
+}
+
+// Computes the commitment of a ceased sidechain out of the leaves of its CSW subtree,
+// i.e. the same value SidechainTreeCeased::get_commitment returns, without building a SidechainTreeCeased
+// Returns an error if the subtree can't contain the leaves or if hashing fails
+pub fn compute_sc_commitment_ceased(
+    sc_id: &FieldElement,
+    csw_leaves: &[FieldElement],
+) -> Result<FieldElement, Error> {
+    compute_sc_commitment_ceased_with_config(sc_id, csw_leaves, &CommitmentTreeConfig::default())
+}
+
+// Same as compute_sc_commitment_ceased, with the height of the CSW subtree of a specified config
+pub fn compute_sc_commitment_ceased_with_config(
+    sc_id: &FieldElement,
+    csw_leaves: &[FieldElement],
+    config: &CommitmentTreeConfig,
+) -> Result<FieldElement, Error> {
+    let csw_root = subtree_root(csw_leaves, config.csw_height, "CSW")?;
+    hash_vec(vec![csw_root, *sc_id])
+}
+
+// Computes the root of a subtree of a specified height containing specified leaves
+fn subtree_root(
+    leaves: &[FieldElement],
+    height: usize,
+    subtree_name: &str,
+) -> Result<FieldElement, Error> {
+    if leaves.len() > pow2(height) {
+        Err(format!(
+            "{} leaves exceed the capacity {} of the {} subtree",
+            leaves.len(),
+            pow2(height),
+            subtree_name
+        ))?
+    }
+    let mut mt = new_sc_subtree_mt(height)?;
+    for leaf in leaves.iter() {
+        if !add_leaf(&mut mt, leaf) {
+            Err(format!(
+                "Unable to add a leaf to the {} subtree",
+                subtree_name
+            ))?
+        }
+    }
+    mt.finalize_in_place()?;
+    Ok(mt
+        .root()
+        .ok_or_else(|| format!("Unable to compute the {} tree root", subtree_name))?)
+}
+
+// Number of events of each type applied by the extend method
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct AddedSummary {
+    pub fwt: usize,
+    pub bwtr: usize,
+    pub cert: usize,
+    pub scc: usize,
+    pub csw: usize,
+    pub ceased: usize,
+    pub new_sidechains: usize, // sidechains which were not contained in the CommitmentTree before
+}
+
+// Counters of the expensive operations performed by a CommitmentTree since its creation, allowing to check from the outside
+// whether its caches are effective, e.g. that they aren't invalidated on every query
+// Note: counters aren't part of the serialized state of a CommitmentTree
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct CommitmentTreeStats {
+    pub commitments_tree_builds: usize, // number of times the finalized Commitment-MT, used for Merkle Paths and proofs, has been built
+    pub subtree_finalizations: usize, // number of times a subtree of a sidechain has been finalized to get its root or a Merkle Path
+}
+
+// Observer of the changes of a CommitmentTree, allowing to mirror its state without re-processing the same raw data
+// All methods have an empty default implementation, so that only the events of interest need to be handled
+pub trait CommitmentTreeObserver: Send {
+    // Invoked when a SidechainTreeAlive (if ceased is false) or a SidechainTreeCeased (otherwise) with a specified ID is added
+    fn on_sidechain_added(&mut self, _sc_id: &FieldElement, _ceased: bool) {}
+
+    // Invoked when a leaf (or the SCC) is added to a subtree of a specified type of a SidechainTreeAlive
+    fn on_alive_leaf_added(
+        &mut self,
+        _sc_id: &FieldElement,
+        _subtree_type: &SidechainAliveSubtreeType,
+        _leaf: &FieldElement,
+    ) {
+    }
+
+    // Invoked when a leaf is added to the CSW subtree of a SidechainTreeCeased
+    fn on_csw_leaf_added(&mut self, _sc_id: &FieldElement, _leaf: &FieldElement) {}
+
+    // Invoked whenever the commitment of the whole CommitmentTree is computed
+    fn on_commitment_computed(&mut self, _commitment: &FieldElement) {}
+}
 
 pub struct CommitmentTree {
     alive_sc_trees: Vec<SidechainTreeAlive>, // list of Alive Sidechain Trees
     ceased_sc_trees: Vec<SidechainTreeCeased>, // list of Ceased Sidechain Trees
     commitments_tree: Option<GingerMHT>, // cached Commitment-MT, which is recomputed in case of some changes in underlying Alive/Ceased Sidechain Trees
+    incremental_commitments_tree: Option<IncrementalMT>, // cached Commitment-MT which is updated in place, recomputing only the SC-commitments of the changed sidechains
+    changed_sc_ids: BTreeSet<FieldElement>, // sidechains changed since the last update of incremental_commitments_tree
+    sc_data_cache: BTreeMap<FieldElement, ScCommitmentData>, // cached roots of the subtrees of the sidechains, discarded when they change
+    observer: Option<Mutex<Box<dyn CommitmentTreeObserver>>>, // optional observer notified about the changes of the CommitmentTree; the Mutex, never contended, makes the CommitmentTree Sync
+    max_sidechains: usize, // maximum number of sidechains (alive and ceased) which can be added, up to the capacity of the Commitment-MT
+    hash_operations: usize, // running estimate of the Poseidon hash operations caused by the changes applied so far, as per hash_operations_estimate
+    cert_linear_accumulator: bool, // consensus flag: if set, a CertLinearAccumulator is maintained alongside the CERT subtree of each SidechainTreeAlive
+    ordering: CommitmentTreeOrdering, // order of the SC-commitments inside of the Commitment-MT
+    stats: CommitmentTreeStats,    // counters of the expensive operations performed since creation
+    config: CommitmentTreeConfig, // heights of the Commitment-MT and of the subtrees of the sidechains
 }
 
 impl CommitmentTree {
@@ -39,7 +368,177 @@ impl CommitmentTree {
             alive_sc_trees: Vec::new(),
             ceased_sc_trees: Vec::new(),
             commitments_tree: None,
+            incremental_commitments_tree: None,
+            changed_sc_ids: BTreeSet::new(),
+            sc_data_cache: BTreeMap::new(),
+            observer: None,
+            max_sidechains: CMT_MT_CAPACITY,
+            hash_operations: 0,
+            cert_linear_accumulator: false,
+            ordering: CommitmentTreeOrdering::default(),
+            stats: CommitmentTreeStats::default(),
+            config: CommitmentTreeConfig::default(),
+        }
+    }
+
+    // Creates a new instance of CommitmentTree whose SC-commitments are ordered according to a specified ordering
+    pub fn create_with_ordering(ordering: CommitmentTreeOrdering) -> Self {
+        Self {
+            ordering,
+            ..Self::create()
+        }
+    }
+
+    // Creates a new instance of CommitmentTree accepting at most max_sidechains sidechains, e.g. to enforce a per-block cap
+    // lower than the capacity of the Commitment-MT
+    // Adding a sidechain beyond the cap fails as it does when the CommitmentTree is full,
+    // with the methods returning a typed error reporting MaxSidechainsReached
+    // Returns TooManySidechains error if max_sidechains exceeds the capacity of the Commitment-MT
+    pub fn create_with_params(max_sidechains: usize) -> Result<Self, CommitmentTreeError> {
+        if max_sidechains > CMT_MT_CAPACITY {
+            return Err(CommitmentTreeError::TooManySidechains(max_sidechains));
+        }
+        Ok(Self {
+            max_sidechains,
+            ..Self::create()
+        })
+    }
+
+    // Creates a new instance of CommitmentTree whose Commitment-MT and subtrees have the heights of a specified config,
+    // instead of the default ones; the maximum number of sidechains is the capacity of its Commitment-MT
+    // Note: its proofs must be verified by the *_with_config static methods, with the same config
+    // Returns InvalidConfig error if some height is not supported
+    pub fn create_with_config(config: CommitmentTreeConfig) -> Result<Self, CommitmentTreeError> {
+        config.validate()?;
+        Ok(Self {
+            max_sidechains: config.cmt_capacity(),
+            config,
+            ..Self::create()
+        })
+    }
+
+    // Gets the order of the SC-commitments inside of the Commitment-MT
+    pub fn ordering(&self) -> CommitmentTreeOrdering {
+        self.ordering
+    }
+
+    // Gets the heights of the Commitment-MT and of the subtrees of the sidechains
+    pub fn config(&self) -> CommitmentTreeConfig {
+        self.config
+    }
+
+    // Gets the maximum number of sidechains which can be added to the CommitmentTree
+    pub fn max_sidechains(&self) -> usize {
+        self.max_sidechains
+    }
+
+    // Gets the estimated number of Poseidon hash operations caused by the leaves added, and the sidechains marked as ceased,
+    // since the creation of the CommitmentTree or the last reset_hash_operations call, i.e. the sum of
+    // hash_operations_estimate_with_config, with the config of the CommitmentTree, over the corresponding events
+    // Leaves which are not added (e.g. because their subtree is full) and CommitmentTrees built with from_leaf_hashes are not counted
+    pub fn hash_operations(&self) -> usize {
+        self.hash_operations
+    }
+
+    // Resets the estimated number of Poseidon hash operations, e.g. before applying the transactions of a new block
+    pub fn reset_hash_operations(&mut self) {
+        self.hash_operations = 0;
+    }
+
+    // Gets the counters of the expensive operations performed since the creation of the CommitmentTree
+    pub fn stats(&self) -> CommitmentTreeStats {
+        self.stats
+    }
+
+    // Enables or disables, according to the consensus rules in force, the Certificates hash-chain (CertLinearAccumulator)
+    // of all the SidechainTreeAlives, maintained alongside their CERT subtrees from the same leaves
+    // The hash-chain doesn't contribute to the commitment of the sidechains; when enabled on a non-empty CommitmentTree
+    // it's built out of the Certificates already added
+    // Returns HashFailed error if the hash-chain can't be built
+    pub fn set_cert_linear_accumulator(
+        &mut self,
+        enabled: bool,
+    ) -> Result<(), CommitmentTreeError> {
+        for scta in self.alive_sc_trees.iter_mut() {
+            scta.set_cert_linear_accumulator(enabled)
+                .map_err(|e| CommitmentTreeError::HashFailed(e.to_string()))?;
+        }
+        self.cert_linear_accumulator = enabled;
+        Ok(())
+    }
+
+    // Returns true if the Certificates hash-chain is maintained
+    pub fn is_cert_linear_accumulator_enabled(&self) -> bool {
+        self.cert_linear_accumulator
+    }
+
+    // Gets the Certificates hash-chain of a specified SidechainTreeAlive
+    // Returns None if it's not enabled or if SidechainTreeAlive with a specified ID doesn't exist in a current CommitmentTree
+    pub fn get_cert_linear_accumulator(
+        &self,
+        sc_id: &FieldElement,
+    ) -> Option<&CertLinearAccumulator> {
+        self.get_scta(sc_id)?.get_cert_linear_accumulator()
+    }
+
+    // Sets an observer to be notified about the changes of the CommitmentTree, replacing the current one (if any)
+    pub fn set_observer(&mut self, observer: Box<dyn CommitmentTreeObserver>) {
+        self.observer = Some(Mutex::new(observer));
+    }
+
+    // Removes the current observer (if any) and returns it
+    pub fn remove_observer(&mut self) -> Option<Box<dyn CommitmentTreeObserver>> {
+        self.observer
+            .take()
+            .map(|observer| observer.into_inner().unwrap_or_else(|e| e.into_inner()))
+    }
+
+    // Creates a new instance of CommitmentTree directly from the already hashed leaves of each sidechain
+    // Leaves are trusted, i.e. no per-type hashing is performed
+    // Returns None if there are more sidechains than CommitmentTree can contain or
+    //         if some subtree has no place for all of its leaves
+    pub fn from_leaf_hashes(leaf_sets: BTreeMap<FieldElement, ScLeafSets>) -> Option<Self> {
+        if leaf_sets.len() > CMT_MT_CAPACITY {
+            return None;
+        }
+
+        let mut cmt = Self::create();
+        for (sc_id, sc_leaf_sets) in leaf_sets.into_iter() {
+            match sc_leaf_sets {
+                ScLeafSets::Alive {
+                    fwt,
+                    bwtr,
+                    cert,
+                    scc,
+                } => {
+                    let mut scta = match SidechainTreeAlive::create(&sc_id) {
+                        Ok(scta) => scta,
+                        Err(_) => return None,
+                    };
+                    if !(fwt.iter().all(|leaf| scta.add_fwt(leaf))
+                        && bwtr.iter().all(|leaf| scta.add_bwtr(leaf))
+                        && cert.iter().all(|leaf| scta.add_cert(leaf)))
+                    {
+                        return None;
+                    }
+                    if let Some(scc) = scc {
+                        scta.set_scc(&scc);
+                    }
+                    cmt.alive_sc_trees.push(scta);
+                }
+                ScLeafSets::Ceased { csw } => {
+                    let mut sctc = match SidechainTreeCeased::create(&sc_id) {
+                        Ok(sctc) => sctc,
+                        Err(_) => return None,
+                    };
+                    if !csw.iter().all(|leaf| sctc.add_csw(leaf)) {
+                        return None;
+                    }
+                    cmt.ceased_sc_trees.push(sctc);
+                }
+            }
         }
+        Some(cmt)
     }
 
     // Adds Forward Transfer Transaction to the Commitment Tree
@@ -54,11 +553,8 @@ impl CommitmentTree {
         tx_hash: &[u8; 32],
         out_idx: u32,
     ) -> bool {
-        if let Ok(fwt_leaf) = hash_fwt(amount, pub_key, mc_return_address, tx_hash, out_idx) {
-            self.add_fwt_leaf(sc_id, &fwt_leaf)
-        } else {
-            false
-        }
+        self.try_add_fwt(sc_id, amount, pub_key, mc_return_address, tx_hash, out_idx)
+            .is_ok()
     }
 
     // Adds Backward Transfer Request Transaction to the Commitment Tree
@@ -73,17 +569,15 @@ impl CommitmentTree {
         tx_hash: &[u8; 32],
         out_idx: u32,
     ) -> bool {
-        if let Ok(bwtr_leaf) = hash_bwtr(
+        self.try_add_bwtr(
+            sc_id,
             sc_fee,
             sc_request_data,
             mc_destination_address,
             tx_hash,
             out_idx,
-        ) {
-            self.add_bwtr_leaf(sc_id, &bwtr_leaf)
-        } else {
-            false
-        }
+        )
+        .is_ok()
     }
 
     // Adds Certificate to the Commitment Tree
@@ -100,7 +594,7 @@ impl CommitmentTree {
         btr_fee: u64,
         ft_min_amount: u64,
     ) -> bool {
-        if let Ok(cert_leaf) = hash_cert(
+        self.try_add_cert(
             sc_id,
             epoch_number,
             quality,
@@ -109,16 +603,42 @@ impl CommitmentTree {
             end_cumulative_sc_tx_commitment_tree_root,
             btr_fee,
             ft_min_amount,
-        ) {
-            self.add_cert_leaf(sc_id, &cert_leaf)
-        } else {
-            false
-        }
+        )
+        .is_ok()
+    }
+
+    // Adds Certificate to the Commitment Tree, starting from the already computed Merkle root of
+    // its Backward Transfers list and hash of its custom fields (if any)
+    // Returns false if hash_cert_from_parts can't get hash for data given in parameters;
+    //         otherwise returns the same as add_cert_leaf method
+    pub fn add_cert_from_parts(
+        &mut self,
+        sc_id: &FieldElement,
+        epoch_number: u32,
+        quality: u64,
+        bt_root: FieldElement,
+        custom_fields_hash: Option<FieldElement>,
+        end_cumulative_sc_tx_commitment_tree_root: &FieldElement,
+        btr_fee: u64,
+        ft_min_amount: u64,
+    ) -> bool {
+        self.try_add_cert_from_parts(
+            sc_id,
+            epoch_number,
+            quality,
+            bt_root,
+            custom_fields_hash,
+            end_cumulative_sc_tx_commitment_tree_root,
+            btr_fee,
+            ft_min_amount,
+        )
+        .is_ok()
     }
 
     // Adds Sidechain Creation Transaction to the Commitment Tree
     // Returns false if hash_scc can't get hash for data given in parameters;
     //         otherwise returns the same as set_scc_leaf method
+    // Note: kept for backward compatibility, add_scc_params should be preferred
     pub fn add_scc(
         &mut self,
         sc_id: &FieldElement,
@@ -137,28 +657,49 @@ impl CommitmentTree {
         cert_verification_key: &[u8],
         csw_verification_key: Option<&[u8]>,
     ) -> bool {
-        if let Ok(scc_leaf) = hash_scc(
+        let mut builder = ScCreationParamsBuilder::create(
             amount,
             pub_key,
             tx_hash,
             out_idx,
             withdrawal_epoch_length,
-            mc_btr_request_data_length,
-            custom_field_elements_configs,
-            custom_bitvector_elements_configs,
-            btr_fee,
-            ft_min_amount,
-            custom_creation_data,
-            constant,
             cert_verification_key,
-            csw_verification_key,
-        ) {
-            self.set_scc(sc_id, &scc_leaf)
-        } else {
-            false
+        )
+        .mc_btr_request_data_length(mc_btr_request_data_length)
+        .btr_fee(btr_fee)
+        .ft_min_amount(ft_min_amount);
+        if let Some(configs) = custom_field_elements_configs {
+            builder = builder.custom_field_elements_configs(configs);
+        }
+        if let Some(configs) = custom_bitvector_elements_configs {
+            builder = builder.custom_bitvector_elements_configs(configs);
+        }
+        if let Some(custom_creation_data) = custom_creation_data {
+            builder = builder.custom_creation_data(custom_creation_data);
+        }
+        if let Some(constant) = constant {
+            builder = builder.constant(constant);
+        }
+        if let Some(csw_verification_key) = csw_verification_key {
+            // The proving system of the CSW circuit isn't committed, and it's unknown here
+            builder = builder
+                .csw_proving_system(ProvingSystem::Undefined)
+                .csw_verification_key(csw_verification_key);
+        }
+
+        match builder.build() {
+            Ok(params) => self.add_scc_params(sc_id, &params),
+            Err(_) => false,
         }
     }
 
+    // Adds Sidechain Creation Transaction, described by its parameters, to the Commitment Tree
+    // Returns false if hash_scc can't get hash for the parameters;
+    //         otherwise returns the same as set_scc_leaf method
+    pub fn add_scc_params(&mut self, sc_id: &FieldElement, params: &ScCreationParams) -> bool {
+        self.try_add_scc_params(sc_id, params).is_ok()
+    }
+
     // Adds Ceased Sidechain Withdrawal to the Commitment Tree
     // Returns false if hash_csw can't get hash for data given in parameters;
     //         otherwise returns the same as add_csw_leaf method
@@ -169,53 +710,599 @@ impl CommitmentTree {
         nullifier: &FieldElement,
         mc_pk_hash: &[u8; MC_PK_SIZE],
     ) -> bool {
-        if let Ok(csw_leaf) = hash_csw(amount, nullifier, mc_pk_hash) {
-            self.add_csw_leaf(sc_id, &csw_leaf)
-        } else {
-            false
-        }
+        self.try_add_csw(sc_id, amount, nullifier, mc_pk_hash)
+            .is_ok()
     }
 
-    // Adds Forward Transfer Transaction's hash to the FWT subtree of the corresponding SidechainTreeAlive
-    // Returns false if maximum number of FWTs has been inserted or if there is a SidechainTreeCeased with the specified ID
-    pub fn add_fwt_leaf(&mut self, sc_id: &FieldElement, fwt: &FieldElement) -> bool {
-        self.scta_add_subtree_leaf(sc_id, fwt, SidechainAliveSubtreeType::FWT)
-    }
+    // Result-returning counterparts of the add_* methods, for callers (e.g. FFI wrappers) propagating
+    // the reason why data can't be added to the Commitment Tree
 
-    // Adds Backward Transfer Request Transaction's hash to the BWTR subtree of the corresponding SidechainTreeAlive
-    // Returns false if maximum number of BWTRs has been inserted or if there is a SidechainTreeCeased with the specified ID
-    pub fn add_bwtr_leaf(&mut self, sc_id: &FieldElement, bwtr: &FieldElement) -> bool {
-        self.scta_add_subtree_leaf(sc_id, bwtr, SidechainAliveSubtreeType::BWTR)
+    // Adds Forward Transfer Transaction to the Commitment Tree
+    // Returns HashFailed error if hash_fwt can't get hash for data given in parameters;
+    //         otherwise returns the same as try_add_fwt_leaf method
+    pub fn try_add_fwt(
+        &mut self,
+        sc_id: &FieldElement,
+        amount: u64,
+        pub_key: &[u8; 32],
+        mc_return_address: &[u8; 20],
+        tx_hash: &[u8; 32],
+        out_idx: u32,
+    ) -> Result<(), CommitmentTreeError> {
+        let fwt_leaf = hash_fwt(amount, pub_key, mc_return_address, tx_hash, out_idx)
+            .map_err(|e| CommitmentTreeError::HashFailed(e.to_string()))?;
+        self.try_add_fwt_leaf(sc_id, &fwt_leaf)
     }
 
-    // Adds Certificate's hash to the CERT subtree of the corresponding SidechainTreeAlive
-    // Returns false if maximum number of CERTs has been inserted or if there is a SidechainTreeCeased with the specified ID
-    pub fn add_cert_leaf(&mut self, sc_id: &FieldElement, cert: &FieldElement) -> bool {
-        self.scta_add_subtree_leaf(sc_id, cert, SidechainAliveSubtreeType::CERT)
+    // Adds Backward Transfer Request Transaction to the Commitment Tree
+    // Returns HashFailed error if hash_bwtr can't get hash for data given in parameters;
+    //         otherwise returns the same as try_add_bwtr_leaf method
+    pub fn try_add_bwtr(
+        &mut self,
+        sc_id: &FieldElement,
+        sc_fee: u64,
+        sc_request_data: Vec<&FieldElement>,
+        mc_destination_address: &[u8; MC_PK_SIZE],
+        tx_hash: &[u8; 32],
+        out_idx: u32,
+    ) -> Result<(), CommitmentTreeError> {
+        let bwtr_leaf = hash_bwtr(
+            sc_fee,
+            sc_request_data,
+            mc_destination_address,
+            tx_hash,
+            out_idx,
+        )
+        .map_err(|e| CommitmentTreeError::HashFailed(e.to_string()))?;
+        self.try_add_bwtr_leaf(sc_id, &bwtr_leaf)
     }
 
-    // Sets Sidechain Creation Transaction's hash for the corresponding SidechainTreeAlive
-    // Returns false if there is a SidechainTreeCeased with the specified ID
-    pub fn set_scc(&mut self, sc_id: &FieldElement, scc: &FieldElement) -> bool {
-        self.scta_add_subtree_leaf(sc_id, scc, SidechainAliveSubtreeType::SCC)
+    // Adds Certificate to the Commitment Tree
+    // Returns HashFailed error if hash_cert can't get hash for data given in parameters;
+    //         otherwise returns the same as try_add_cert_leaf method
+    pub fn try_add_cert(
+        &mut self,
+        sc_id: &FieldElement,
+        epoch_number: u32,
+        quality: u64,
+        bt_list: Option<&[BackwardTransfer]>,
+        custom_fields: Option<Vec<&FieldElement>>, //aka proof_data - includes custom_field_elements and bit_vectors merkle roots
+        end_cumulative_sc_tx_commitment_tree_root: &FieldElement,
+        btr_fee: u64,
+        ft_min_amount: u64,
+    ) -> Result<(), CommitmentTreeError> {
+        let cert_leaf = hash_cert(
+            sc_id,
+            epoch_number,
+            quality,
+            bt_list,
+            custom_fields,
+            end_cumulative_sc_tx_commitment_tree_root,
+            btr_fee,
+            ft_min_amount,
+        )
+        .map_err(|e| CommitmentTreeError::HashFailed(e.to_string()))?;
+        self.try_add_cert_leaf(sc_id, &cert_leaf)
     }
 
-    // Adds Ceased Sidechain Withdrawal's hash to the CSW subtree of the corresponding SidechainTreeCeased
-    // Returns false if CSW subtree has no place to add new element or if there is a SidechainTreeAlive with the specified ID
-    pub fn add_csw_leaf(&mut self, sc_id: &FieldElement, csw: &FieldElement) -> bool {
-        self.sctc_add_subtree_leaf(sc_id, csw)
+    // Adds Certificate to the Commitment Tree, starting from the already computed Merkle root of
+    // its Backward Transfers list and hash of its custom fields (if any)
+    // Returns HashFailed error if hash_cert_from_parts can't get hash for data given in parameters;
+    //         otherwise returns the same as try_add_cert_leaf method
+    pub fn try_add_cert_from_parts(
+        &mut self,
+        sc_id: &FieldElement,
+        epoch_number: u32,
+        quality: u64,
+        bt_root: FieldElement,
+        custom_fields_hash: Option<FieldElement>,
+        end_cumulative_sc_tx_commitment_tree_root: &FieldElement,
+        btr_fee: u64,
+        ft_min_amount: u64,
+    ) -> Result<(), CommitmentTreeError> {
+        let cert_leaf = hash_cert_from_parts(
+            sc_id,
+            epoch_number,
+            quality,
+            bt_root,
+            custom_fields_hash,
+            end_cumulative_sc_tx_commitment_tree_root,
+            btr_fee,
+            ft_min_amount,
+        )
+        .map_err(|e| CommitmentTreeError::HashFailed(e.to_string()))?;
+        self.try_add_cert_leaf(sc_id, &cert_leaf)
     }
 
-    // Gets commitment, i.e. root of the Forward Transfer Transactions subtree of a specified SidechainTreeAlive
-    // Returns None if SidechainTreeAlive with a specified ID doesn't exist in a current CommitmentTree
-    pub fn get_fwt_commitment(&mut self, sc_id: &FieldElement) -> Option<FieldElement> {
-        self.scta_get_subtree_commitment(sc_id, SidechainAliveSubtreeType::FWT)
+    // Adds Sidechain Creation Transaction, described by its parameters, to the Commitment Tree
+    // Returns HashFailed error if hash_scc can't get hash for the parameters;
+    //         otherwise returns the same as try_set_scc method
+    pub fn try_add_scc_params(
+        &mut self,
+        sc_id: &FieldElement,
+        params: &ScCreationParams,
+    ) -> Result<(), CommitmentTreeError> {
+        let scc_leaf = params
+            .hash()
+            .map_err(|e| CommitmentTreeError::HashFailed(e.to_string()))?;
+        self.try_set_scc(sc_id, &scc_leaf)
     }
 
-    // Gets commitment, i.e. root of the Backward Transfer Requests Transactions subtree of a specified SidechainTreeAlive
-    // Returns None if SidechainTreeAlive with a specified ID doesn't exist in a current CommitmentTree
-    pub fn get_bwtr_commitment(&mut self, sc_id: &FieldElement) -> Option<FieldElement> {
-        self.scta_get_subtree_commitment(sc_id, SidechainAliveSubtreeType::BWTR)
+    // Adds Ceased Sidechain Withdrawal to the Commitment Tree
+    // Returns HashFailed error if hash_csw can't get hash for data given in parameters;
+    //         otherwise returns the same as try_add_csw_leaf method
+    pub fn try_add_csw(
+        &mut self,
+        sc_id: &FieldElement,
+        amount: u64,
+        nullifier: &FieldElement,
+        mc_pk_hash: &[u8; MC_PK_SIZE],
+    ) -> Result<(), CommitmentTreeError> {
+        let csw_leaf = hash_csw(amount, nullifier, mc_pk_hash)
+            .map_err(|e| CommitmentTreeError::HashFailed(e.to_string()))?;
+        self.try_add_csw_leaf(sc_id, &csw_leaf)
+    }
+
+    // Byte-oriented counterparts of the add_* methods, for callers holding field elements as raw bytes
+    // Each field element is decoded with strict canonical checks, and the errors identify the offending field
+
+    // Adds Forward Transfer Transaction to the Commitment Tree
+    pub fn add_fwt_bytes(
+        &mut self,
+        sc_id: &[u8; FIELD_SIZE],
+        amount: u64,
+        pub_key: &[u8; 32],
+        mc_return_address: &[u8; 20],
+        tx_hash: &[u8; 32],
+        out_idx: u32,
+    ) -> Result<(), CommitmentTreeError> {
+        let sc_id = decode_field_element(sc_id, "sc_id")?;
+        self.try_add_fwt(&sc_id, amount, pub_key, mc_return_address, tx_hash, out_idx)
+    }
+
+    // Adds Backward Transfer Request Transaction to the Commitment Tree
+    pub fn add_bwtr_bytes(
+        &mut self,
+        sc_id: &[u8; FIELD_SIZE],
+        sc_fee: u64,
+        sc_request_data: &[[u8; FIELD_SIZE]],
+        mc_destination_address: &[u8; MC_PK_SIZE],
+        tx_hash: &[u8; 32],
+        out_idx: u32,
+    ) -> Result<(), CommitmentTreeError> {
+        let sc_id = decode_field_element(sc_id, "sc_id")?;
+        let sc_request_data = decode_field_elements(sc_request_data, "sc_request_data")?;
+        self.try_add_bwtr(
+            &sc_id,
+            sc_fee,
+            sc_request_data.iter().collect(),
+            mc_destination_address,
+            tx_hash,
+            out_idx,
+        )
+    }
+
+    // Adds Certificate to the Commitment Tree
+    pub fn add_cert_bytes(
+        &mut self,
+        sc_id: &[u8; FIELD_SIZE],
+        epoch_number: u32,
+        quality: u64,
+        bt_list: Option<&[BackwardTransfer]>,
+        custom_fields: Option<&[[u8; FIELD_SIZE]]>, //aka proof_data - includes custom_field_elements and bit_vectors merkle roots
+        end_cumulative_sc_tx_commitment_tree_root: &[u8; FIELD_SIZE],
+        btr_fee: u64,
+        ft_min_amount: u64,
+    ) -> Result<(), CommitmentTreeError> {
+        let sc_id = decode_field_element(sc_id, "sc_id")?;
+        let custom_fields = custom_fields
+            .map(|custom_fields| decode_field_elements(custom_fields, "custom_fields"))
+            .transpose()?;
+        let end_cumulative_sc_tx_commitment_tree_root = decode_field_element(
+            end_cumulative_sc_tx_commitment_tree_root,
+            "end_cumulative_sc_tx_commitment_tree_root",
+        )?;
+        self.try_add_cert(
+            &sc_id,
+            epoch_number,
+            quality,
+            bt_list,
+            custom_fields
+                .as_ref()
+                .map(|custom_fields| custom_fields.iter().collect()),
+            &end_cumulative_sc_tx_commitment_tree_root,
+            btr_fee,
+            ft_min_amount,
+        )
+    }
+
+    // Adds Sidechain Creation Transaction to the Commitment Tree
+    pub fn add_scc_bytes(
+        &mut self,
+        sc_id: &[u8; FIELD_SIZE],
+        amount: u64,
+        pub_key: &[u8; 32],
+        tx_hash: &[u8; 32],
+        out_idx: u32,
+        withdrawal_epoch_length: u32,
+        mc_btr_request_data_length: u8,
+        custom_field_elements_configs: Option<&[u8]>,
+        custom_bitvector_elements_configs: Option<&[BitVectorElementsConfig]>,
+        btr_fee: u64,
+        ft_min_amount: u64,
+        custom_creation_data: Option<&[u8]>,
+        constant: Option<&[u8; FIELD_SIZE]>,
+        cert_verification_key: &[u8],
+        csw_verification_key: Option<&[u8]>,
+    ) -> Result<(), CommitmentTreeError> {
+        let sc_id = decode_field_element(sc_id, "sc_id")?;
+        let constant = constant
+            .map(|constant| decode_field_element(constant, "constant"))
+            .transpose()?;
+        let scc_leaf = hash_scc(
+            amount,
+            pub_key,
+            tx_hash,
+            out_idx,
+            withdrawal_epoch_length,
+            mc_btr_request_data_length,
+            custom_field_elements_configs,
+            custom_bitvector_elements_configs,
+            btr_fee,
+            ft_min_amount,
+            custom_creation_data,
+            constant.as_ref(),
+            cert_verification_key,
+            csw_verification_key,
+        )
+        .map_err(|e| CommitmentTreeError::HashFailed(e.to_string()))?;
+        self.try_set_scc(&sc_id, &scc_leaf)
+    }
+
+    // Adds Ceased Sidechain Withdrawal to the Commitment Tree
+    pub fn add_csw_bytes(
+        &mut self,
+        sc_id: &[u8; FIELD_SIZE],
+        amount: u64,
+        nullifier: &[u8; FIELD_SIZE],
+        mc_pk_hash: &[u8; MC_PK_SIZE],
+    ) -> Result<(), CommitmentTreeError> {
+        let sc_id = decode_field_element(sc_id, "sc_id")?;
+        let nullifier = decode_field_element(nullifier, "nullifier")?;
+        self.try_add_csw(&sc_id, amount, &nullifier, mc_pk_hash)
+    }
+
+    // Adds Forward Transfer Transaction's hash to the FWT subtree of the corresponding SidechainTreeAlive
+    // Returns false if maximum number of FWTs has been inserted or if there is a SidechainTreeCeased with the specified ID
+    pub fn add_fwt_leaf(&mut self, sc_id: &FieldElement, fwt: &FieldElement) -> bool {
+        self.scta_add_subtree_leaf(sc_id, fwt, SidechainAliveSubtreeType::FWT)
+    }
+
+    // Adds Backward Transfer Request Transaction's hash to the BWTR subtree of the corresponding SidechainTreeAlive
+    // Returns false if maximum number of BWTRs has been inserted or if there is a SidechainTreeCeased with the specified ID
+    pub fn add_bwtr_leaf(&mut self, sc_id: &FieldElement, bwtr: &FieldElement) -> bool {
+        self.scta_add_subtree_leaf(sc_id, bwtr, SidechainAliveSubtreeType::BWTR)
+    }
+
+    // Adds Certificate's hash to the CERT subtree of the corresponding SidechainTreeAlive
+    // Returns false if maximum number of CERTs has been inserted or if there is a SidechainTreeCeased with the specified ID
+    pub fn add_cert_leaf(&mut self, sc_id: &FieldElement, cert: &FieldElement) -> bool {
+        self.scta_add_subtree_leaf(sc_id, cert, SidechainAliveSubtreeType::CERT)
+    }
+
+    // Sets Sidechain Creation Transaction's hash for the corresponding SidechainTreeAlive
+    // Returns false if there is a SidechainTreeCeased with the specified ID
+    pub fn set_scc(&mut self, sc_id: &FieldElement, scc: &FieldElement) -> bool {
+        self.scta_add_subtree_leaf(sc_id, scc, SidechainAliveSubtreeType::SCC)
+    }
+
+    // Adds Ceased Sidechain Withdrawal's hash to the CSW subtree of the corresponding SidechainTreeCeased
+    // Returns false if CSW subtree has no place to add new element or if there is a SidechainTreeAlive with the specified ID
+    pub fn add_csw_leaf(&mut self, sc_id: &FieldElement, csw: &FieldElement) -> bool {
+        self.sctc_add_subtree_leaf(sc_id, csw)
+    }
+
+    // Result-returning counterparts of the add_*_leaf and set_scc methods
+    // Each of them returns SidechainCeased (or SidechainAlive) error if the sidechain has the wrong type,
+    // CommitmentTreeFull (or MaxSidechainsReached) error if a new sidechain can't be added and
+    // SubtreeFull error if the subtree has no place to add a new leaf
+
+    // Adds Forward Transfer Transaction's hash to the FWT subtree of the corresponding SidechainTreeAlive
+    pub fn try_add_fwt_leaf(
+        &mut self,
+        sc_id: &FieldElement,
+        fwt: &FieldElement,
+    ) -> Result<(), CommitmentTreeError> {
+        self.try_add_alive_leaf(sc_id, fwt, SidechainAliveSubtreeType::FWT)
+    }
+
+    // Adds Backward Transfer Request Transaction's hash to the BWTR subtree of the corresponding SidechainTreeAlive
+    pub fn try_add_bwtr_leaf(
+        &mut self,
+        sc_id: &FieldElement,
+        bwtr: &FieldElement,
+    ) -> Result<(), CommitmentTreeError> {
+        self.try_add_alive_leaf(sc_id, bwtr, SidechainAliveSubtreeType::BWTR)
+    }
+
+    // Adds Certificate's hash to the CERT subtree of the corresponding SidechainTreeAlive
+    pub fn try_add_cert_leaf(
+        &mut self,
+        sc_id: &FieldElement,
+        cert: &FieldElement,
+    ) -> Result<(), CommitmentTreeError> {
+        self.try_add_alive_leaf(sc_id, cert, SidechainAliveSubtreeType::CERT)
+    }
+
+    // Sets Sidechain Creation Transaction's hash for the corresponding SidechainTreeAlive
+    pub fn try_set_scc(
+        &mut self,
+        sc_id: &FieldElement,
+        scc: &FieldElement,
+    ) -> Result<(), CommitmentTreeError> {
+        self.try_add_alive_leaf(sc_id, scc, SidechainAliveSubtreeType::SCC)
+    }
+
+    // Adds Ceased Sidechain Withdrawal's hash to the CSW subtree of the corresponding SidechainTreeCeased
+    pub fn try_add_csw_leaf(
+        &mut self,
+        sc_id: &FieldElement,
+        csw: &FieldElement,
+    ) -> Result<(), CommitmentTreeError> {
+        if self.is_present_scta(sc_id) {
+            return Err(CommitmentTreeError::SidechainAlive(*sc_id));
+        }
+        if !self.is_present_sctc(sc_id) && self.is_full() {
+            return Err(self.full_error());
+        }
+        if !self.sctc_add_subtree_leaf(sc_id, csw) {
+            return Err(CommitmentTreeError::SubtreeFull(*sc_id));
+        }
+        Ok(())
+    }
+
+    // Marks a sidechain as ceased, so that no more FWT/BWTR/CERT/SCC leaves can be added for it
+    // The sidechain is included in the CommitmentTree as a SidechainTreeCeased even if no CSW is added for it,
+    // so that existence and absence proofs are consistent with its state
+    // Returns an error if there is a SidechainTreeAlive with the specified ID or if CommitmentTree is full
+    // Note: marking an already ceased sidechain has no effect
+    pub fn mark_ceased(&mut self, sc_id: &FieldElement) -> Result<(), CommitmentTreeError> {
+        if self.is_present_sctc(sc_id) {
+            return Ok(());
+        }
+        if self.is_present_scta(sc_id) {
+            return Err(CommitmentTreeError::SidechainAlive(*sc_id));
+        }
+        if self.is_full() {
+            return Err(self.full_error());
+        }
+        if self.add_sctc(sc_id).is_none() {
+            return Err(CommitmentTreeError::CommitmentTreeFull);
+        }
+
+        // A new sidechain has been added, so the commitment tree should be rebuilt
+        self.invalidate_commitments_tree(sc_id, true);
+        self.hash_operations += hash_operations_for_subtree_height(0, self.config.cmt_height);
+        Ok(())
+    }
+
+    // Returns true if there is a SidechainTreeCeased with the specified ID
+    pub fn is_ceased(&self, sc_id: &FieldElement) -> bool {
+        self.is_present_sctc(sc_id)
+    }
+
+    // Adds leaf to a subtree of a specified type in a specified SidechainTreeAlive, as add_*_leaf methods do
+    // Returns a dedicated error if the sidechain is ceased, if CommitmentTree is full or if the subtree is full
+    pub fn try_add_alive_leaf(
+        &mut self,
+        sc_id: &FieldElement,
+        leaf: &FieldElement,
+        subtree_type: SidechainAliveSubtreeType,
+    ) -> Result<(), CommitmentTreeError> {
+        if self.is_present_sctc(sc_id) {
+            return Err(CommitmentTreeError::SidechainCeased(*sc_id));
+        }
+        if !self.is_present_scta(sc_id) && self.is_full() {
+            return Err(self.full_error());
+        }
+        if !self.scta_add_subtree_leaf(sc_id, leaf, subtree_type) {
+            return Err(CommitmentTreeError::SubtreeFull(*sc_id));
+        }
+        Ok(())
+    }
+
+    // Applies all the events, in order, or none of them: events are first validated against the current state of
+    // the CommitmentTree, and applied only if all of them are valid
+    // Returns the number of applied events of each type or
+    //         the index of the first invalid event, together with the reason why it can't be applied
+    pub fn extend(
+        &mut self,
+        events: impl IntoIterator<Item = ScTxEvent>,
+    ) -> Result<AddedSummary, (usize, CommitmentTreeError)> {
+        let events = events.into_iter().collect::<Vec<_>>();
+        let summary = self.validate_events(&events)?;
+
+        for (idx, event) in events.into_iter().enumerate() {
+            let result = match event {
+                ScTxEvent::Fwt { sc_id, leaf } => {
+                    self.try_add_alive_leaf(&sc_id, &leaf, SidechainAliveSubtreeType::FWT)
+                }
+                ScTxEvent::Bwtr { sc_id, leaf } => {
+                    self.try_add_alive_leaf(&sc_id, &leaf, SidechainAliveSubtreeType::BWTR)
+                }
+                ScTxEvent::Cert { sc_id, leaf } => {
+                    self.try_add_alive_leaf(&sc_id, &leaf, SidechainAliveSubtreeType::CERT)
+                }
+                ScTxEvent::Scc { sc_id, leaf } => {
+                    self.try_add_alive_leaf(&sc_id, &leaf, SidechainAliveSubtreeType::SCC)
+                }
+                ScTxEvent::Csw { sc_id, leaf } => self.try_add_csw_leaf(&sc_id, &leaf),
+                ScTxEvent::Ceased { sc_id } => self.mark_ceased(&sc_id),
+            };
+            // Not expected to fail, as all the events have already been validated
+            result.map_err(|e| (idx, e))?;
+        }
+
+        Ok(summary)
+    }
+
+    // Checks that all the events can be applied, in order, to the CommitmentTree, without modifying it
+    // Returns the number of events of each type or
+    //         the index of the first invalid event, together with the reason why it can't be applied
+    fn validate_events(
+        &self,
+        events: &[ScTxEvent],
+    ) -> Result<AddedSummary, (usize, CommitmentTreeError)> {
+        // Number of leaves in the FWT/BWTR/CERT subtrees of an alive sidechain, or in the CSW subtree of a ceased one,
+        // as they would be after applying the events validated so far
+        enum ScState {
+            Alive([usize; 3]),
+            Ceased(usize),
+        }
+
+        let mut states = BTreeMap::<FieldElement, ScState>::new();
+        let mut num_sidechains = self.alive_sc_trees.len() + self.ceased_sc_trees.len();
+        let mut summary = AddedSummary::default();
+
+        for (idx, event) in events.iter().enumerate() {
+            let sc_id = match event {
+                ScTxEvent::Fwt { sc_id, .. }
+                | ScTxEvent::Bwtr { sc_id, .. }
+                | ScTxEvent::Cert { sc_id, .. }
+                | ScTxEvent::Scc { sc_id, .. }
+                | ScTxEvent::Csw { sc_id, .. }
+                | ScTxEvent::Ceased { sc_id } => *sc_id,
+            };
+
+            // Take the current state of the sidechain, if it's contained in the CommitmentTree
+            if !states.contains_key(&sc_id) {
+                if let Some(scta) = self.get_scta(&sc_id) {
+                    let num_leaves = [
+                        scta.num_leaves(&SidechainAliveSubtreeType::FWT),
+                        scta.num_leaves(&SidechainAliveSubtreeType::BWTR),
+                        scta.num_leaves(&SidechainAliveSubtreeType::CERT),
+                    ];
+                    states.insert(sc_id, ScState::Alive(num_leaves));
+                } else if let Some(sctc) = self.get_sctc(&sc_id) {
+                    states.insert(sc_id, ScState::Ceased(sctc.num_csw_leaves()));
+                }
+            }
+
+            let is_new = !states.contains_key(&sc_id);
+            if is_new && num_sidechains == self.max_sidechains {
+                return Err((idx, self.full_error()));
+            }
+
+            let state = states.entry(sc_id).or_insert_with(|| match event {
+                ScTxEvent::Csw { .. } | ScTxEvent::Ceased { .. } => ScState::Ceased(0),
+                _ => ScState::Alive([0; 3]),
+            });
+
+            match (event, state) {
+                (ScTxEvent::Csw { .. }, ScState::Alive(_))
+                | (ScTxEvent::Ceased { .. }, ScState::Alive(_)) => {
+                    return Err((idx, CommitmentTreeError::SidechainAlive(sc_id)));
+                }
+                (ScTxEvent::Csw { .. }, ScState::Ceased(num_csw)) => {
+                    if *num_csw == self.config.subtree_capacity(ScSubtreeType::CSW) {
+                        return Err((idx, CommitmentTreeError::SubtreeFull(sc_id)));
+                    }
+                    *num_csw += 1;
+                    summary.csw += 1;
+                }
+                (ScTxEvent::Ceased { .. }, ScState::Ceased(_)) => summary.ceased += 1,
+                (_, ScState::Ceased(_)) => {
+                    return Err((idx, CommitmentTreeError::SidechainCeased(sc_id)));
+                }
+                (ScTxEvent::Scc { .. }, ScState::Alive(_)) => summary.scc += 1,
+                (_, ScState::Alive(num_leaves)) => {
+                    // Only FWT, BWTR and CERT events are left
+                    let (subtree_idx, subtree_type, counter) = match event {
+                        ScTxEvent::Fwt { .. } => (0, ScSubtreeType::FWT, &mut summary.fwt),
+                        ScTxEvent::Bwtr { .. } => (1, ScSubtreeType::BWTR, &mut summary.bwtr),
+                        _ => (2, ScSubtreeType::CERT, &mut summary.cert),
+                    };
+                    let capacity = self.config.subtree_capacity(subtree_type);
+                    if num_leaves[subtree_idx] == capacity {
+                        return Err((idx, CommitmentTreeError::SubtreeFull(sc_id)));
+                    }
+                    num_leaves[subtree_idx] += 1;
+                    *counter += 1;
+                }
+            }
+
+            if is_new {
+                num_sidechains += 1;
+                summary.new_sidechains += 1;
+            }
+        }
+
+        Ok(summary)
+    }
+
+    // Checks the invariants of a CommitmentTree, which must be verified whenever it is reconstructed from untrusted data:
+    // - each sidechain is contained at most once, i.e. it is either alive or ceased
+    // - the number of sidechains doesn't exceed the capacity (or the maximum number of sidechains) of the CommitmentTree
+    // - the commitments of all the sidechains, and of the whole CommitmentTree, can be computed
+    // The cached SC-commitments, e.g. the ones restored on deserialization, are checked as well: if any of them doesn't match
+    // the one recomputed out of its sidechain, the cache is dropped and the Commitment-MT is rebuilt out of the sidechains
+    // Returns the first violated invariant as an error
+    pub fn validate_invariants(&mut self) -> Result<(), CommitmentTreeError> {
+        self.validate_sidechain_ids()?;
+
+        for scta in self.alive_sc_trees.iter_mut() {
+            self.stats.subtree_finalizations += ALIVE_SUBTREES;
+            if scta.get_commitment().is_none() {
+                return Err(CommitmentTreeError::InvalidSidechainCommitment(*scta.id()));
+            }
+        }
+        for sctc in self.ceased_sc_trees.iter_mut() {
+            self.stats.subtree_finalizations += 1;
+            if sctc.get_commitment().is_none() {
+                return Err(CommitmentTreeError::InvalidSidechainCommitment(*sctc.id()));
+            }
+        }
+
+        if self.incremental_commitments_tree.is_some() {
+            let ids = self
+                .get_indexed_sc_ids()
+                .into_iter()
+                .map(|s| *s.1)
+                .collect::<Vec<FieldElement>>();
+            let mut sc_commitments = Vec::with_capacity(ids.len());
+            for id in ids {
+                sc_commitments.push(
+                    self.get_sc_data(&id)
+                        .and_then(|sc_data| sc_data.get_sc_commitment(&id))
+                        .ok_or(CommitmentTreeError::InvalidSidechainCommitment(id))?,
+                );
+            }
+            let is_cache_valid = self
+                .get_incremental_commitments_tree()
+                .map_or(false, |cmt| cmt.leaves() == sc_commitments.as_slice());
+            if !is_cache_valid {
+                self.commitments_tree = None;
+                self.incremental_commitments_tree = None;
+                self.changed_sc_ids.clear();
+            }
+        }
+
+        // Also caches the Commitment-MT
+        self.get_commitments_tree()
+            .ok_or(CommitmentTreeError::InvalidCommitment)?;
+        Ok(())
+    }
+
+    // Gets commitment, i.e. root of the Forward Transfer Transactions subtree of a specified SidechainTreeAlive
+    // Returns None if SidechainTreeAlive with a specified ID doesn't exist in a current CommitmentTree
+    pub fn get_fwt_commitment(&mut self, sc_id: &FieldElement) -> Option<FieldElement> {
+        self.scta_get_subtree_commitment(sc_id, SidechainAliveSubtreeType::FWT)
+    }
+
+    // Gets commitment, i.e. root of the Backward Transfer Requests Transactions subtree of a specified SidechainTreeAlive
+    // Returns None if SidechainTreeAlive with a specified ID doesn't exist in a current CommitmentTree
+    pub fn get_bwtr_commitment(&mut self, sc_id: &FieldElement) -> Option<FieldElement> {
+        self.scta_get_subtree_commitment(sc_id, SidechainAliveSubtreeType::BWTR)
     }
 
     // Gets commitment, i.e. root of the Certificates subtree of a specified SidechainTreeAlive
@@ -239,90 +1326,338 @@ impl CommitmentTree {
     // Gets all leaves, of a Forward Transfer Transactions subtree of a specified SidechainTreeAlive
     // Returns None if SidechainTreeCeased with a specified ID doesn't exist in a current CommitmentTree
     pub fn get_fwt_leaves(&mut self, sc_id: &FieldElement) -> Option<Vec<FieldElement>> {
-        self.scta_get_subtree_leaves(sc_id, SidechainAliveSubtreeType::FWT)
+        self.get_subtree_leaves(sc_id, SidechainAliveSubtreeType::FWT)
+            .unwrap_or(None)
     }
 
     // Gets all leaves, of a Backward Transfer Requests Transactions subtree of a specified SidechainTreeAlive
     // Returns None if SidechainTreeCeased with a specified ID doesn't exist in a current CommitmentTree
     pub fn get_bwtr_leaves(&mut self, sc_id: &FieldElement) -> Option<Vec<FieldElement>> {
-        self.scta_get_subtree_leaves(sc_id, SidechainAliveSubtreeType::BWTR)
+        self.get_subtree_leaves(sc_id, SidechainAliveSubtreeType::BWTR)
+            .unwrap_or(None)
     }
 
     // Gets all leaves, of a Certificates subtree of a specified SidechainTreeAlive
     // Returns None if SidechainTreeCeased with a specified ID doesn't exist in a current CommitmentTree
     pub fn get_cert_leaves(&mut self, sc_id: &FieldElement) -> Option<Vec<FieldElement>> {
-        self.scta_get_subtree_leaves(sc_id, SidechainAliveSubtreeType::CERT)
+        self.get_subtree_leaves(sc_id, SidechainAliveSubtreeType::CERT)
+            .unwrap_or(None)
     }
 
-    // Gets commitment of a specified SidechainTreeAlive/SidechainTreeCeased
-    // Returns None if SidechainTreeAlive/SidechainTreeCeased with a specified ID doesn't exist in a current CommitmentTree
-    pub fn get_sc_commitment(&mut self, sc_id: &FieldElement) -> Option<FieldElement> {
-        self.get_sc_commitment_internal(sc_id)
+    // Gets all leaves, of a Ceased Sidechain Withdrawals subtree of a specified SidechainTreeCeased
+    // Returns None if SidechainTreeCeased with a specified ID doesn't exist in a current CommitmentTree
+    pub fn get_csw_leaves(&self, sc_id: &FieldElement) -> Option<Vec<FieldElement>> {
+        self.get_sctc(sc_id).map(|sctc| sctc.get_csw_leaves())
     }
 
-    // Gets commitment for a CommitmentTree
-    // Returns None in case if some error occurred during `new_smt` creation
-    // Note: The commitment value is computed as a root of MT with SCT-commitments leafs ordered by corresponding SCT-IDs
-    pub fn get_commitment(&mut self) -> Option<FieldElement> {
-        if let Some(cmt) = self.get_commitments_tree() {
-            match cmt.finalize() {
-                Ok(tree) => tree.root(),
-                Err(_) => None,
+    // Gets all leaves of a subtree of a specified type in a specified SidechainTreeAlive
+    // Returns Ok(None) if SidechainTreeAlive with a specified ID doesn't exist in a current CommitmentTree
+    // Returns NoSubtreeLeaves error for the SCC subtree, which is a single value and has no leaves
+    pub fn get_subtree_leaves(
+        &mut self,
+        sc_id: &FieldElement,
+        subtree_type: SidechainAliveSubtreeType,
+    ) -> Result<Option<Vec<FieldElement>>, CommitmentTreeError> {
+        let sc_tree = match self.get_scta_mut(sc_id) {
+            Some(sc_tree) => sc_tree,
+            None => return Ok(None),
+        };
+        Ok(Some(match subtree_type {
+            SidechainAliveSubtreeType::FWT => sc_tree.get_fwt_leaves(),
+            SidechainAliveSubtreeType::BWTR => sc_tree.get_bwtr_leaves(),
+            SidechainAliveSubtreeType::CERT => sc_tree.get_cert_leaves(),
+            SidechainAliveSubtreeType::SCC => {
+                return Err(CommitmentTreeError::NoSubtreeLeaves(subtree_type))
             }
-        } else {
-            None
-        }
+        }))
     }
 
-    // Gets merkle path to the leaf of the Forward Transfers subtree of a specified SidechainTreeAlive
+    // Takes a snapshot of the current state of the Certificates subtree of a specified SidechainTreeAlive
     // Returns None if SidechainTreeAlive with a specified ID doesn't exist in a current CommitmentTree
-    //              if leaf_index is out of range
-    pub fn get_fwt_merkle_path(
+    //              if the root of the subtree can't be computed
+    pub fn snapshot_cert_subtree(&mut self, sc_id: &FieldElement) -> Option<CertSubtreeSnapshot> {
+        let sc_tree = self.get_scta_mut(sc_id)?;
+        let root = sc_tree.get_cert_commitment();
+        let leaves = sc_tree.get_cert_leaves();
+        self.stats.subtree_finalizations += 1;
+        let root = root?;
+        Some(CertSubtreeSnapshot {
+            sc_id: *sc_id,
+            root,
+            num_leaves: leaves.len() as u32,
+            leaves,
+        })
+    }
+
+    // Restores the Certificates subtree of a sidechain from a snapshot, adding the corresponding SidechainTreeAlive if needed,
+    // so that more Certificates can be appended to it by add_cert* methods
+    // The current Certificates of the sidechain, if any, are replaced; as for from_leaf_hashes, the restored leaves are trusted,
+    // i.e. they are neither notified to the observer nor counted in hash_operations
+    // The snapshot is validated before the sidechain is touched (or added), so that a rejected snapshot leaves CommitmentTree unchanged
+    // Returns InvalidCertSubtreeSnapshot error if the leaves of the snapshot don't match its root and leaf count,
+    //         a dedicated error if the sidechain is ceased or if CommitmentTree is full
+    pub fn restore_cert_subtree(
         &mut self,
-        sc_id: &FieldElement,
-        leaf_index: usize,
-    ) -> Option<GingerMHTPath> {
-        self.scta_get_subtree_leaf_merkle_path(sc_id, SidechainAliveSubtreeType::FWT, leaf_index)
+        snapshot: &CertSubtreeSnapshot,
+    ) -> Result<(), CommitmentTreeError> {
+        let sc_id = &snapshot.sc_id;
+        if self.is_present_sctc(sc_id) {
+            return Err(CommitmentTreeError::SidechainCeased(*sc_id));
+        }
+        if snapshot.leaves.len() != snapshot.num_leaves as usize
+            || snapshot.leaves.len() > self.config.subtree_capacity(ScSubtreeType::CERT)
+        {
+            return Err(CommitmentTreeError::InvalidCertSubtreeSnapshot(*sc_id));
+        }
+        let new_sidechain = !self.is_present_scta(sc_id);
+        if new_sidechain && self.is_full() {
+            return Err(self.full_error());
+        }
+        let cert_mt = SidechainTreeAlive::build_cert_mt(
+            self.config.subtree_height(ScSubtreeType::CERT),
+            &snapshot.leaves,
+            &snapshot.root,
+        )
+        .ok_or(CommitmentTreeError::InvalidCertSubtreeSnapshot(*sc_id))?;
+
+        let restored = self
+            .get_add_scta_mut(sc_id)
+            .ok_or(CommitmentTreeError::CommitmentTreeFull)?
+            .restore_cert_mt(cert_mt);
+
+        // The CERT subtree (or the sidechain itself) may have been replaced, so the commitment tree should be updated
+        self.invalidate_commitments_tree(sc_id, new_sidechain);
+        if !restored {
+            return Err(CommitmentTreeError::InvalidCertSubtreeSnapshot(*sc_id));
+        }
+        Ok(())
     }
 
-    // Gets merkle path to the leaf of the Backward Transfer Requests subtree of a specified SidechainTreeAlive
+    // Iterates over the leaves of a Forward Transfer Transactions subtree of a specified SidechainTreeAlive,
+    // without cloning them as get_fwt_leaves does
     // Returns None if SidechainTreeAlive with a specified ID doesn't exist in a current CommitmentTree
-    //              if leaf_index is out of range
-    pub fn get_bwtr_merkle_path(
-        &mut self,
+    pub fn iter_fwt_leaves(
+        &self,
         sc_id: &FieldElement,
-        leaf_index: usize,
-    ) -> Option<GingerMHTPath> {
-        self.scta_get_subtree_leaf_merkle_path(sc_id, SidechainAliveSubtreeType::BWTR, leaf_index)
+    ) -> Option<impl Iterator<Item = &FieldElement>> {
+        self.get_scta(sc_id).map(|sct| sct.iter_fwt_leaves())
     }
 
-    // Gets merkle path to the leaf of the Certificates subtree of a specified SidechainTreeAlive
+    // Iterates over the leaves of a Backward Transfer Requests Transactions subtree of a specified SidechainTreeAlive,
+    // without cloning them as get_bwtr_leaves does
     // Returns None if SidechainTreeAlive with a specified ID doesn't exist in a current CommitmentTree
-    //              if leaf_index is out of range
-    pub fn get_cert_merkle_path(
-        &mut self,
+    pub fn iter_bwtr_leaves(
+        &self,
+        sc_id: &FieldElement,
+    ) -> Option<impl Iterator<Item = &FieldElement>> {
+        self.get_scta(sc_id).map(|sct| sct.iter_bwtr_leaves())
+    }
+
+    // Iterates over the leaves of a Certificates subtree of a specified SidechainTreeAlive,
+    // without cloning them as get_cert_leaves does
+    // Returns None if SidechainTreeAlive with a specified ID doesn't exist in a current CommitmentTree
+    pub fn iter_cert_leaves(
+        &self,
+        sc_id: &FieldElement,
+    ) -> Option<impl Iterator<Item = &FieldElement>> {
+        self.get_scta(sc_id).map(|sct| sct.iter_cert_leaves())
+    }
+
+    // Iterates over the leaves of a Ceased Sidechain Withdrawals subtree of a specified SidechainTreeCeased,
+    // without cloning them as get_csw_leaves does
+    // Returns None if SidechainTreeCeased with a specified ID doesn't exist in a current CommitmentTree
+    pub fn iter_csw_leaves(
+        &self,
+        sc_id: &FieldElement,
+    ) -> Option<impl Iterator<Item = &FieldElement>> {
+        self.get_sctc(sc_id).map(|sctc| sctc.iter_csw_leaves())
+    }
+
+    // Gets the number of leaves of a Forward Transfer Transactions subtree of a specified SidechainTreeAlive
+    // Returns None if SidechainTreeAlive with a specified ID doesn't exist in a current CommitmentTree
+    pub fn get_fwt_count(&self, sc_id: &FieldElement) -> Option<usize> {
+        self.get_scta(sc_id)
+            .map(|sct| sct.num_leaves(&SidechainAliveSubtreeType::FWT))
+    }
+
+    // Gets the number of leaves of a Backward Transfer Requests Transactions subtree of a specified SidechainTreeAlive
+    // Returns None if SidechainTreeAlive with a specified ID doesn't exist in a current CommitmentTree
+    pub fn get_bwtr_count(&self, sc_id: &FieldElement) -> Option<usize> {
+        self.get_scta(sc_id)
+            .map(|sct| sct.num_leaves(&SidechainAliveSubtreeType::BWTR))
+    }
+
+    // Gets the number of leaves of a Certificates subtree of a specified SidechainTreeAlive
+    // Returns None if SidechainTreeAlive with a specified ID doesn't exist in a current CommitmentTree
+    pub fn get_cert_count(&self, sc_id: &FieldElement) -> Option<usize> {
+        self.get_scta(sc_id)
+            .map(|sct| sct.num_leaves(&SidechainAliveSubtreeType::CERT))
+    }
+
+    // Gets the number of leaves of a Ceased Sidechain Withdrawals subtree of a specified SidechainTreeCeased
+    // Returns None if SidechainTreeCeased with a specified ID doesn't exist in a current CommitmentTree
+    pub fn get_csw_count(&self, sc_id: &FieldElement) -> Option<usize> {
+        self.get_sctc(sc_id).map(|sctc| sctc.num_csw_leaves())
+    }
+
+    // Gets the number of leaves which can still be added to the subtree of a specified type of a specified sidechain,
+    // allowing to reject a transaction before trying to add it
+    // If the sidechain doesn't exist, that's the capacity of the subtree of a new sidechain, unless none can be added
+    // Returns 0 if the sidechain has no subtree of such type, i.e. a CSW subtree for a SidechainTreeAlive or vice versa,
+    //           if the sidechain doesn't exist and the CommitmentTree is full
+    pub fn remaining_capacity(&self, sc_id: &FieldElement, subtree_type: ScSubtreeType) -> usize {
+        let count = match subtree_type {
+            ScSubtreeType::FWT => self.get_fwt_count(sc_id),
+            ScSubtreeType::BWTR => self.get_bwtr_count(sc_id),
+            ScSubtreeType::CERT => self.get_cert_count(sc_id),
+            ScSubtreeType::CSW => self.get_csw_count(sc_id),
+        };
+        let capacity = self.config.subtree_capacity(subtree_type);
+        match count {
+            Some(count) => capacity.saturating_sub(count),
+            None if self.is_present_scta(sc_id)
+                || self.is_present_sctc(sc_id)
+                || self.is_full() =>
+            {
+                0
+            }
+            None => capacity,
+        }
+    }
+
+    // Gets commitment of a specified SidechainTreeAlive/SidechainTreeCeased
+    // Returns None if SidechainTreeAlive/SidechainTreeCeased with a specified ID doesn't exist in a current CommitmentTree
+    pub fn get_sc_commitment(&mut self, sc_id: &FieldElement) -> Option<FieldElement> {
+        self.get_sc_commitment_internal(sc_id)
+    }
+
+    // Gets commitment for a CommitmentTree
+    // Returns None in case if some error occurred during `new_smt` creation
+    // Note: The commitment value is computed as a root of MT with SCT-commitments leafs ordered by corresponding SCT-IDs
+    pub fn get_commitment(&mut self) -> Option<FieldElement> {
+        let commitment = self
+            .get_incremental_commitments_tree()
+            .and_then(|cmt| cmt.root().ok());
+        if let Some(commitment) = commitment.as_ref() {
+            self.notify(|observer| observer.on_commitment_computed(commitment));
+        }
+        commitment
+    }
+
+    // Gets merkle path to the leaf of the Forward Transfers subtree of a specified SidechainTreeAlive
+    // Returns None if SidechainTreeAlive with a specified ID doesn't exist in a current CommitmentTree
+    //              if leaf_index is out of range
+    pub fn get_fwt_merkle_path(
+        &mut self,
+        sc_id: &FieldElement,
+        leaf_index: usize,
+    ) -> Option<GingerMHTPath> {
+        self.scta_get_subtree_leaf_merkle_path(sc_id, SidechainAliveSubtreeType::FWT, leaf_index)
+    }
+
+    // Gets merkle path to the leaf of the Backward Transfer Requests subtree of a specified SidechainTreeAlive
+    // Returns None if SidechainTreeAlive with a specified ID doesn't exist in a current CommitmentTree
+    //              if leaf_index is out of range
+    pub fn get_bwtr_merkle_path(
+        &mut self,
+        sc_id: &FieldElement,
+        leaf_index: usize,
+    ) -> Option<GingerMHTPath> {
+        self.scta_get_subtree_leaf_merkle_path(sc_id, SidechainAliveSubtreeType::BWTR, leaf_index)
+    }
+
+    // Gets merkle path to the leaf of the Certificates subtree of a specified SidechainTreeAlive
+    // Returns None if SidechainTreeAlive with a specified ID doesn't exist in a current CommitmentTree
+    //              if leaf_index is out of range
+    pub fn get_cert_merkle_path(
+        &mut self,
         sc_id: &FieldElement,
         leaf_index: usize,
     ) -> Option<GingerMHTPath> {
         self.scta_get_subtree_leaf_merkle_path(sc_id, SidechainAliveSubtreeType::CERT, leaf_index)
     }
 
+    // Gets merkle path to the leaf of the Ceased Sidechain Withdrawals subtree of a specified SidechainTreeCeased
+    // Returns None if SidechainTreeCeased with a specified ID doesn't exist in a current CommitmentTree
+    //              if leaf_index is out of range
+    pub fn get_csw_merkle_path(
+        &mut self,
+        sc_id: &FieldElement,
+        leaf_index: usize,
+    ) -> Option<GingerMHTPath> {
+        let path = self.get_sctc_mut(sc_id)?.get_csw_merkle_path(leaf_index);
+        self.stats.subtree_finalizations += 1;
+        path
+    }
+
+    // Gets a proof of inclusion of the CSW leaf at position leaf_index of a specified SidechainTreeCeased
+    // into a current CommitmentTree
+    // Returns None if SidechainTreeCeased with a specified ID doesn't exist in a current CommitmentTree,
+    //              if leaf_index is out of range
+    pub fn get_csw_leaf_proof(
+        &mut self,
+        sc_id: &FieldElement,
+        leaf_index: usize,
+    ) -> Option<CswLeafProof> {
+        let csw_mpath = self.get_csw_merkle_path(sc_id, leaf_index)?;
+        let sc_proof = self.get_sc_existence_proof(sc_id)?;
+        Some(CswLeafProof::create(csw_mpath, sc_proof))
+    }
+
+    // Gets a proof of inclusion of the FWT leaf at position leaf_index of a specified SidechainTreeAlive
+    // into a current CommitmentTree
+    // Returns None if SidechainTreeAlive with a specified ID doesn't exist in a current CommitmentTree,
+    //              if leaf_index is out of range
+    pub fn get_fwt_leaf_existence_proof(
+        &mut self,
+        sc_id: &FieldElement,
+        leaf_index: usize,
+    ) -> Option<LeafExistenceProof> {
+        self.get_leaf_existence_proof(sc_id, ScSubtreeType::FWT, leaf_index)
+    }
+
+    // Gets a proof of inclusion of the BWTR leaf at position leaf_index of a specified SidechainTreeAlive
+    // into a current CommitmentTree
+    // Returns None if SidechainTreeAlive with a specified ID doesn't exist in a current CommitmentTree,
+    //              if leaf_index is out of range
+    pub fn get_bwtr_leaf_existence_proof(
+        &mut self,
+        sc_id: &FieldElement,
+        leaf_index: usize,
+    ) -> Option<LeafExistenceProof> {
+        self.get_leaf_existence_proof(sc_id, ScSubtreeType::BWTR, leaf_index)
+    }
+
+    // Gets a proof of inclusion of the CERT leaf at position leaf_index of a specified SidechainTreeAlive
+    // into a current CommitmentTree
+    // Returns None if SidechainTreeAlive with a specified ID doesn't exist in a current CommitmentTree,
+    //              if leaf_index is out of range
+    pub fn get_cert_leaf_existence_proof(
+        &mut self,
+        sc_id: &FieldElement,
+        leaf_index: usize,
+    ) -> Option<LeafExistenceProof> {
+        self.get_leaf_existence_proof(sc_id, ScSubtreeType::CERT, leaf_index)
+    }
+
+    // Gets a proof of inclusion of the CSW leaf at position leaf_index of a specified SidechainTreeCeased
+    // into a current CommitmentTree
+    // Returns None if SidechainTreeCeased with a specified ID doesn't exist in a current CommitmentTree,
+    //              if leaf_index is out of range
+    pub fn get_csw_leaf_existence_proof(
+        &mut self,
+        sc_id: &FieldElement,
+        leaf_index: usize,
+    ) -> Option<LeafExistenceProof> {
+        self.get_leaf_existence_proof(sc_id, ScSubtreeType::CSW, leaf_index)
+    }
+
     // Gets merkle path to the sc_commitment with specified ID into a current CommitmentTree
     // Returns None if sidechain with a specified ID is absent in a current CommitmentTree,
     //              if get_commitments_tree or get_merkle_path returned None
     pub fn get_sc_commitment_merkle_path(&mut self, sc_id: &FieldElement) -> Option<GingerMHTPath> {
-        if let Some(index) = self.sc_id_to_index(sc_id) {
-            if let Some(tree) = self.get_commitments_tree() {
-                match tree.finalize() {
-                    Ok(finalized_tree) => finalized_tree.get_merkle_path(index),
-                    Err(_) => None,
-                }
-            } else {
-                None
-            }
-        } else {
-            None
-        }
+        let index = self.sc_id_to_index(sc_id)?;
+        self.get_commitments_tree()?.get_merkle_path(index)
     }
 
     // Gets a proof of inclusion of a sidechain with specified ID into a current CommitmentTree
@@ -339,32 +1674,66 @@ impl CommitmentTree {
     //              if some internal error occurred
     pub fn get_sc_absence_proof(&mut self, absent_id: &FieldElement) -> Option<ScAbsenceProof> {
         let (left, right) = self.get_neighbours_for_absent(absent_id)?;
-        let tree = match self.get_commitments_tree()?.finalize() {
-            Ok(finalized_tree) => finalized_tree,
-            Err(_) => return None,
+        let left = match left {
+            Some((index, id)) => Some(self.get_neighbour(index, id)?),
+            None => None,
         };
+        let right = match right {
+            Some((index, id)) => Some(self.get_neighbour(index, id)?),
+            None => None,
+        };
+        Some(ScAbsenceProof::create(left, right, self.ordering))
+    }
 
-        let mut get_neighbour = |index_id: Option<(usize, FieldElement)>| {
-            if let Some((index, id)) = index_id {
-                Some(ScNeighbour::create(
-                    id,
-                    tree.get_merkle_path(index)?,
-                    self.get_sc_data(&id)?,
-                ))
-            } else {
-                None
-            }
+    // Gets the proof of inclusion of a sidechain with specified ID into a current CommitmentTree, or of its non-inclusion
+    // if the sidechain is absent, along with the data needed to verify it, serialized as an InclusionBundle
+    // Returns InvalidCommitment error if the commitment or the proofs can't be computed
+    pub fn export_inclusion_bundle(
+        &mut self,
+        sc_id: &FieldElement,
+    ) -> Result<Vec<u8>, CommitmentTreeError> {
+        let commitment = self
+            .get_commitment()
+            .ok_or(CommitmentTreeError::InvalidCommitment)?;
+        let bundle = if let Some(sc_commitment) = self.get_sc_commitment(sc_id) {
+            InclusionBundle::Existence(ScExistenceBundle {
+                sc_id: *sc_id,
+                sc_commitment,
+                proof: self
+                    .get_sc_existence_proof(sc_id)
+                    .ok_or(CommitmentTreeError::InvalidCommitment)?,
+                commitment,
+            })
+        } else {
+            InclusionBundle::Absence(ScAbsenceBundle {
+                sc_id: *sc_id,
+                proof: self
+                    .get_sc_absence_proof(sc_id)
+                    .ok_or(CommitmentTreeError::InvalidCommitment)?,
+                commitment,
+            })
         };
-        Some(ScAbsenceProof::create(
-            get_neighbour(left),
-            get_neighbour(right),
-        ))
+        bundle.to_bytes()
     }
 
     //----------------------------------------------------------------------------------------------
     // Static methods
     //----------------------------------------------------------------------------------------------
 
+    // Deserializes and verifies an InclusionBundle, as built by export_inclusion_bundle
+    // Returns the bundle, whose CommitmentTree root must still be checked by the caller against the one of the expected
+    // mainchain block, or InvalidInclusionBundle error if the bundle is malformed or its proof is not correct
+    pub fn verify_inclusion_bundle(bundle: &[u8]) -> Result<InclusionBundle, CommitmentTreeError> {
+        let bundle = InclusionBundle::from_bytes(bundle)?;
+        if bundle.verify() {
+            Ok(bundle)
+        } else {
+            Err(CommitmentTreeError::InvalidInclusionBundle(
+                "proof is not correct".to_owned(),
+            ))
+        }
+    }
+
     // Verifies proof of sidechain inclusion into a specified CommitmentTree
     // Takes sidechain commitment, sidechain existence proof and a root of CommitmentTree - CMT-commitment
     // Returns true if proof is correct, false otherwise
@@ -373,77 +1742,285 @@ impl CommitmentTree {
         proof: &ScExistenceProof,
         commitment: &FieldElement,
     ) -> bool {
-        if let Ok(res) = proof.mpath.verify(CMT_MT_HEIGHT, sc_commitment, commitment) {
+        Self::verify_sc_commitment_with_config(
+            sc_commitment,
+            proof,
+            commitment,
+            &CommitmentTreeConfig::default(),
+        )
+    }
+
+    // Same as verify_sc_commitment, for a CommitmentTree created with a specified config
+    pub fn verify_sc_commitment_with_config(
+        sc_commitment: &FieldElement,
+        proof: &ScExistenceProof,
+        commitment: &FieldElement,
+        config: &CommitmentTreeConfig,
+    ) -> bool {
+        if let Ok(res) = proof
+            .mpath
+            .verify(config.cmt_height, sc_commitment, commitment)
+        {
             res
         } else {
             false
         }
     }
 
+    // Verifies proof of inclusion of a CSW leaf into the SidechainTreeCeased with a specified ID of a specified CommitmentTree
+    // Takes sidechain ID, CSW leaf, CSW leaf proof and a root of CommitmentTree - CMT-commitment
+    // Returns true if proof is correct, false otherwise
+    pub fn verify_csw_leaf(
+        sc_id: &FieldElement,
+        csw_leaf: &FieldElement,
+        proof: &CswLeafProof,
+        commitment: &FieldElement,
+    ) -> bool {
+        Self::verify_csw_leaf_with_config(
+            sc_id,
+            csw_leaf,
+            proof,
+            commitment,
+            &CommitmentTreeConfig::default(),
+        )
+    }
+
+    // Same as verify_csw_leaf, for a CommitmentTree created with a specified config
+    pub fn verify_csw_leaf_with_config(
+        sc_id: &FieldElement,
+        csw_leaf: &FieldElement,
+        proof: &CswLeafProof,
+        commitment: &FieldElement,
+        config: &CommitmentTreeConfig,
+    ) -> bool {
+        // Rebuild the CSW subtree root and check that the path has the expected length
+        let csw_mr = proof.csw_mpath.compute_root(csw_leaf);
+        if !matches!(
+            proof.csw_mpath.verify(config.csw_height, csw_leaf, &csw_mr),
+            Ok(true)
+        ) {
+            return false;
+        }
+
+        // Rebuild the SC-commitment and verify its inclusion into the CommitmentTree
+        if let Some(sc_commitment) = SidechainTreeCeased::build_commitment(*sc_id, csw_mr) {
+            Self::verify_sc_commitment_with_config(
+                &sc_commitment,
+                &proof.sc_proof,
+                commitment,
+                config,
+            )
+        } else {
+            false
+        }
+    }
+
+    // Verifies that a leaf is included into the subtree of a specified type of the sidechain with a specified ID
+    // of a specified CommitmentTree
+    // Takes sidechain ID, subtree type, leaf, leaf existence proof and a root of CommitmentTree - CMT-commitment
+    // Returns true if proof is correct, false otherwise
+    pub fn verify_leaf_inclusion(
+        sc_id: &FieldElement,
+        subtree_type: ScSubtreeType,
+        leaf: &FieldElement,
+        proof: &LeafExistenceProof,
+        commitment: &FieldElement,
+    ) -> bool {
+        Self::verify_leaf_inclusion_with_config(
+            sc_id,
+            subtree_type,
+            leaf,
+            proof,
+            commitment,
+            &CommitmentTreeConfig::default(),
+        )
+    }
+
+    // Same as verify_leaf_inclusion, for a CommitmentTree created with a specified config
+    pub fn verify_leaf_inclusion_with_config(
+        sc_id: &FieldElement,
+        subtree_type: ScSubtreeType,
+        leaf: &FieldElement,
+        proof: &LeafExistenceProof,
+        commitment: &FieldElement,
+        config: &CommitmentTreeConfig,
+    ) -> bool {
+        // Rebuild the subtree root, check that the path has the expected length
+        // and that the root is the one the SC-commitment is built from
+        let subtree_root = proof.leaf_mpath.compute_root(leaf);
+        if !matches!(
+            proof
+                .leaf_mpath
+                .verify(config.subtree_height(subtree_type), leaf, &subtree_root),
+            Ok(true)
+        ) || proof.sc_data.get_subtree_root(subtree_type) != Some(&subtree_root)
+        {
+            return false;
+        }
+
+        // Rebuild the SC-commitment and verify its inclusion into the CommitmentTree
+        if let Some(sc_commitment) = proof.sc_data.get_sc_commitment(sc_id) {
+            Self::verify_sc_commitment_with_config(
+                &sc_commitment,
+                &proof.sc_proof,
+                commitment,
+                config,
+            )
+        } else {
+            false
+        }
+    }
+
+    // Verifies that a CSW with the given data is included into the SidechainTreeCeased with a specified ID
+    // of a specified CommitmentTree. The CSW leaf is recomputed from amount, nullifier and mc_pk_hash.
+    // Returns true if proof is correct, false otherwise
+    pub fn verify_csw(
+        sc_id: &FieldElement,
+        amount: u64,
+        nullifier: &FieldElement,
+        mc_pk_hash: &[u8; MC_PK_SIZE],
+        proof: &CswLeafProof,
+        commitment: &FieldElement,
+    ) -> bool {
+        if let Ok(csw_leaf) = hash_csw(amount, nullifier, mc_pk_hash) {
+            Self::verify_csw_leaf(sc_id, &csw_leaf, proof, commitment)
+        } else {
+            false
+        }
+    }
+
     // Verifies proof of sidechain non-inclusion into a specified CommitmentTree
     // Takes sidechain ID, sidechain absence proof and a root of CommitmentTree - CMT-commitment
+    // The CommitmentTree is expected to have the default ordering: proofs built according to another one are rejected
     // Returns true if proof is correct, false otherwise
     pub fn verify_sc_absence(
         absent_id: &FieldElement,
         proof: &ScAbsenceProof,
         commitment: &FieldElement,
     ) -> bool {
+        Self::verify_sc_absence_with_config(
+            absent_id,
+            proof,
+            commitment,
+            &CommitmentTreeConfig::default(),
+        )
+    }
+
+    // Same as verify_sc_absence, for a CommitmentTree created with a specified config
+    pub fn verify_sc_absence_with_config(
+        absent_id: &FieldElement,
+        proof: &ScAbsenceProof,
+        commitment: &FieldElement,
+        config: &CommitmentTreeConfig,
+    ) -> bool {
+        Self::verify_sc_absence_with_ordering(
+            absent_id,
+            proof,
+            commitment,
+            CommitmentTreeOrdering::default(),
+            config,
+        )
+    }
+
+    // Same as verify_sc_absence_with_config, for a CommitmentTree created with a specified ordering
+    // The ordering is supplied by the verifier: proofs built according to a different one are rejected,
+    // otherwise a prover could choose the ordering under which an existing SC-ID looks absent
+    pub fn verify_sc_absence_with_ordering(
+        absent_id: &FieldElement,
+        proof: &ScAbsenceProof,
+        commitment: &FieldElement,
+        ordering: CommitmentTreeOrdering,
+        config: &CommitmentTreeConfig,
+    ) -> bool {
+        if proof.ordering != ordering {
+            return false;
+        }
+
+        // Getting SC-commitments for the SC-IDs of the neighbours
+        let neighbour = |n: &ScNeighbour| (n.id, n.sc_data.get_sc_commitment(&n.id));
+        Self::verify_sc_absence_neighbours(
+            absent_id,
+            proof
+                .left
+                .as_ref()
+                .map(|left| (neighbour(left), &left.mpath)),
+            proof
+                .right
+                .as_ref()
+                .map(|right| (neighbour(right), &right.mpath)),
+            ordering,
+            commitment,
+            config,
+        )
+    }
+
+    // Verifies that the neighbours of an absent SC-ID, each one given as (SC-ID, SC-commitment) and its Merkle Path,
+    // prove the absence of such SC-ID from a CommitmentTree ordered according to a specified ordering
+    // A None SC-commitment means that it couldn't be built
+    fn verify_sc_absence_neighbours(
+        absent_id: &FieldElement,
+        left: Option<((FieldElement, Option<FieldElement>), &GingerMHTPath)>,
+        right: Option<((FieldElement, Option<FieldElement>), &GingerMHTPath)>,
+        ordering: CommitmentTreeOrdering,
+        commitment: &FieldElement,
+        config: &CommitmentTreeConfig,
+    ) -> bool {
+        let cmt_height = config.cmt_height;
+        // SC-IDs are compared according to the ordering of the CommitmentTree, as expected by the verifier
+        let less = |a: &FieldElement, b: &FieldElement| ordering.compare(a, b) == Ordering::Less;
+
         // Checking if left and right neighbours are present
-        if let (Some(left), Some(right)) = (proof.left.as_ref(), proof.right.as_ref()) {
-            // Getting SC-commitments for the given SC-IDs
-            if let (Some(left_sc_commitment), Some(right_sc_commitment)) = (
-                left.sc_data.get_sc_commitment(&left.id),
-                right.sc_data.get_sc_commitment(&right.id),
-            ) {
+        if let (
+            Some(((left_id, left_sc_commitment), left_mpath)),
+            Some(((right_id, right_sc_commitment), right_mpath)),
+        ) = (left, right)
+        {
+            if let (Some(left_sc_commitment), Some(right_sc_commitment)) =
+                (left_sc_commitment, right_sc_commitment)
+            {
                 // Validating Merkle Paths of SC-commitments
                 let left_path_status =
-                    left.mpath
-                        .verify(CMT_MT_HEIGHT, &left_sc_commitment, commitment);
+                    left_mpath.verify(cmt_height, &left_sc_commitment, commitment);
                 let right_path_status =
-                    right
-                        .mpath
-                        .verify(CMT_MT_HEIGHT, &right_sc_commitment, commitment);
+                    right_mpath.verify(cmt_height, &right_sc_commitment, commitment);
 
                 // `left.id < right.id` is verified transitively with `left.id < absent_id && absent_id < right.id`
-                &left.id < absent_id
-                    && absent_id < &right.id
+                less(&left_id, absent_id)
+                    && less(absent_id, &right_id)
                     && left_path_status.is_ok()
                     && left_path_status.unwrap()
                     && right_path_status.is_ok()
                     && right_path_status.unwrap()
-                    && left.mpath.leaf_index() + 1 == right.mpath.leaf_index() // the smaller and bigger IDs have adjacent positions in MT
+                    && left_mpath.leaf_index() + 1 == right_mpath.leaf_index() // the smaller and bigger IDs have adjacent positions in MT
             } else {
                 false // couldn't build sc_commitment
             }
         }
         // Checking if only left neighbour is present
-        else if let Some(left) = proof.left.as_ref() {
-            if let Some(left_sc_commitment) = left.sc_data.get_sc_commitment(&left.id) {
+        else if let Some(((left_id, left_sc_commitment), left_mpath)) = left {
+            if let Some(left_sc_commitment) = left_sc_commitment {
                 let left_path_status =
-                    left.mpath
-                        .verify(CMT_MT_HEIGHT, &left_sc_commitment, commitment);
+                    left_mpath.verify(cmt_height, &left_sc_commitment, commitment);
 
-                &left.id < absent_id
+                less(&left_id, absent_id)
                     && left_path_status.is_ok()
                     && left_path_status.unwrap()
-                    && (left.mpath.is_rightmost() || left.mpath.are_right_leaves_empty())
+                    && (left_mpath.is_rightmost() || left_mpath.are_right_leaves_empty())
             // is a last leaf in MT or a last non-empty leaf in MT
             } else {
                 false // couldn't build sc_commitment
             }
         }
         // Checking if only right neighbour is present
-        else if let Some(right) = proof.right.as_ref() {
-            if let Some(right_sc_commitment) = right.sc_data.get_sc_commitment(&right.id) {
+        else if let Some(((right_id, right_sc_commitment), right_mpath)) = right {
+            if let Some(right_sc_commitment) = right_sc_commitment {
                 let right_path_status =
-                    right
-                        .mpath
-                        .verify(CMT_MT_HEIGHT, &right_sc_commitment, commitment);
+                    right_mpath.verify(cmt_height, &right_sc_commitment, commitment);
 
-                absent_id < &right.id
+                less(absent_id, &right_id)
                     && right_path_status.is_ok()
                     && right_path_status.unwrap()
-                    && right.mpath.is_leftmost() // the bigger ID is the smallest one in MT
+                    && right_mpath.is_leftmost() // the bigger ID is the smallest one in MT
             } else {
                 false // couldn't build sc_commitment
             }
@@ -451,7 +2028,7 @@ impl CommitmentTree {
         // Neither of neighbours is present
         else {
             // Empty proof is valid only for an empty CMT
-            commitment == CMT_EMPTY_COMMITMENT
+            empty_root_at_height(cmt_height).ok().as_ref() == Some(commitment)
         }
     }
 
@@ -459,9 +2036,69 @@ impl CommitmentTree {
     // Private auxiliary methods
     //----------------------------------------------------------------------------------------------
 
+    // Checks the invariants of a CommitmentTree about the IDs of its sidechains, i.e. the ones checked by validate_invariants
+    // but the computability of the commitments
+    fn validate_sidechain_ids(&self) -> Result<(), CommitmentTreeError> {
+        let num_sidechains = self.alive_sc_trees.len() + self.ceased_sc_trees.len();
+        if num_sidechains > self.max_sidechains {
+            return Err(CommitmentTreeError::TooManySidechains(num_sidechains));
+        }
+
+        let mut alive_ids = BTreeSet::new();
+        for scta in self.alive_sc_trees.iter() {
+            if !alive_ids.insert(scta.id()) {
+                return Err(CommitmentTreeError::DuplicateSidechain(*scta.id()));
+            }
+        }
+
+        let mut ceased_ids = BTreeSet::new();
+        for sctc in self.ceased_sc_trees.iter() {
+            if alive_ids.contains(sctc.id()) {
+                return Err(CommitmentTreeError::SidechainAliveAndCeased(*sctc.id()));
+            }
+            if !ceased_ids.insert(sctc.id()) {
+                return Err(CommitmentTreeError::DuplicateSidechain(*sctc.id()));
+            }
+        }
+        Ok(())
+    }
+
+    // Gets the SC-commitments of the incrementally updated Commitment-MT, if it's up to date
+    fn cached_sc_commitments(&self) -> Option<Vec<FieldElement>> {
+        if self.changed_sc_ids.is_empty() {
+            self.incremental_commitments_tree
+                .as_ref()
+                .map(|cmt| cmt.leaves().to_vec())
+        } else {
+            None
+        }
+    }
+
+    // Invokes a specified notification on the current observer, if any
+    fn notify<F: FnOnce(&mut dyn CommitmentTreeObserver)>(&mut self, notification: F) {
+        if let Some(observer) = self.observer.as_mut() {
+            notification(
+                observer
+                    .get_mut()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .as_mut(),
+            )
+        }
+    }
+
     // Returns true if no more sidechain-trees can be added to a CommitmentTree
     fn is_full(&self) -> bool {
-        (self.alive_sc_trees.len() + self.ceased_sc_trees.len()) == CMT_MT_CAPACITY
+        (self.alive_sc_trees.len() + self.ceased_sc_trees.len()) >= self.max_sidechains
+    }
+
+    // Gets the error reporting that no more sidechain-trees can be added to a CommitmentTree:
+    // CommitmentTreeFull if the Commitment-MT capacity is reached, MaxSidechainsReached if a lower cap is
+    fn full_error(&self) -> CommitmentTreeError {
+        if self.max_sidechains == self.config.cmt_capacity() {
+            CommitmentTreeError::CommitmentTreeFull
+        } else {
+            CommitmentTreeError::MaxSidechainsReached(self.max_sidechains)
+        }
     }
 
     // Returns true if CommitmentTree contains SidechainTreeAlive with a specified ID
@@ -502,8 +2139,13 @@ impl CommitmentTree {
     //         None if CommitmentTree is full or an error occurred during creation of a new SidechainTreeAlive
     fn add_scta(&mut self, sc_id: &FieldElement) -> Option<&mut SidechainTreeAlive> {
         if !self.is_full() {
-            if let Ok(new_sct) = SidechainTreeAlive::create(&sc_id) {
+            if let Ok(mut new_sct) = SidechainTreeAlive::create_with_config(&sc_id, &self.config) {
+                if self.cert_linear_accumulator {
+                    // The CERT subtree is empty, so no hashing is needed
+                    new_sct.set_cert_linear_accumulator(true).ok()?;
+                }
                 self.alive_sc_trees.push(new_sct);
+                self.notify(|observer| observer.on_sidechain_added(sc_id, false));
                 self.alive_sc_trees.last_mut()
             } else {
                 None
@@ -519,8 +2161,9 @@ impl CommitmentTree {
     fn add_sctc(&mut self, sc_id: &FieldElement) -> Option<&mut SidechainTreeCeased> {
         if !self.is_full() {
             // Add new SidechainTreeCeased if there is free space in CommitmentTree
-            if let Ok(new_sctc) = SidechainTreeCeased::create(&sc_id) {
+            if let Ok(new_sctc) = SidechainTreeCeased::create_with_config(&sc_id, &self.config) {
                 self.ceased_sc_trees.push(new_sctc);
+                self.notify(|observer| observer.on_sidechain_added(sc_id, true));
                 self.ceased_sc_trees.last_mut()
             } else {
                 None
@@ -546,7 +2189,7 @@ impl CommitmentTree {
     // If such a SidechainTreeCeased doesn't exist adds new tree with a specified ID and returns mutable reference to it
     // Returns None if SidechainTreeCeased with a specified ID doesn't exist and can't be added
     fn get_add_sctc_mut(&mut self, sc_id: &FieldElement) -> Option<&mut SidechainTreeCeased> {
-        if self.is_present_sctc(sc_id) && !self.is_full() {
+        if self.is_present_sctc(sc_id) {
             self.get_sctc_mut(sc_id)
         } else {
             self.add_sctc(sc_id)
@@ -563,19 +2206,34 @@ impl CommitmentTree {
     ) -> bool {
         if !self.is_present_sctc(&sc_id) {
             // there shouldn't be SCTC with the same ID
+            let new_sidechain = !self.is_present_scta(sc_id);
+            let config = self.config;
             if let Some(sct) = self.get_add_scta_mut(sc_id) {
-                let result = match subtree_type {
-                    SidechainAliveSubtreeType::FWT => sct.add_fwt(leaf),
-                    SidechainAliveSubtreeType::BWTR => sct.add_bwtr(leaf),
-                    SidechainAliveSubtreeType::CERT => sct.add_cert(leaf),
+                let (result, subtree_height) = match subtree_type {
+                    SidechainAliveSubtreeType::FWT => {
+                        (sct.add_fwt(leaf), config.subtree_height(ScSubtreeType::FWT))
+                    }
+                    SidechainAliveSubtreeType::BWTR => (
+                        sct.add_bwtr(leaf),
+                        config.subtree_height(ScSubtreeType::BWTR),
+                    ),
+                    SidechainAliveSubtreeType::CERT => (
+                        sct.add_cert(leaf),
+                        config.subtree_height(ScSubtreeType::CERT),
+                    ),
                     SidechainAliveSubtreeType::SCC => {
                         sct.set_scc(leaf);
-                        true
+                        (true, 0)
                     }
                 };
-                // If contents of the commitment tree has been updated then it should be rebuilt, so discard its current version
-                if self.commitments_tree.is_some() && result {
-                    self.commitments_tree = None
+                // If contents of the commitment tree has been updated then it should be updated too
+                if result {
+                    self.invalidate_commitments_tree(sc_id, new_sidechain);
+                    self.hash_operations +=
+                        hash_operations_for_subtree_height(subtree_height, config.cmt_height);
+                    self.notify(|observer| {
+                        observer.on_alive_leaf_added(sc_id, &subtree_type, leaf)
+                    });
                 }
                 result
             } else {
@@ -591,11 +2249,17 @@ impl CommitmentTree {
     fn sctc_add_subtree_leaf(&mut self, sc_id: &FieldElement, leaf: &FieldElement) -> bool {
         if !self.is_present_scta(sc_id) {
             // there shouldn't be SCTA with the same ID
+            let new_sidechain = !self.is_present_sctc(sc_id);
             if let Some(sctc) = self.get_add_sctc_mut(&sc_id) {
                 let result = sctc.add_csw(leaf);
-                // If contents of the commitment tree has been updated then it should be rebuilt, so discard its current version
-                if self.commitments_tree.is_some() && result {
-                    self.commitments_tree = None
+                // If contents of the commitment tree has been updated then it should be updated too
+                if result {
+                    self.invalidate_commitments_tree(sc_id, new_sidechain);
+                    self.hash_operations += hash_operations_for_subtree_height(
+                        self.config.subtree_height(ScSubtreeType::CSW),
+                        self.config.cmt_height,
+                    );
+                    self.notify(|observer| observer.on_csw_leaf_added(sc_id, leaf));
                 }
                 result
             } else {
@@ -614,25 +2278,15 @@ impl CommitmentTree {
         subtree_type: SidechainAliveSubtreeType,
         leaf_index: usize,
     ) -> Option<GingerMHTPath> {
-        if let Some(sc_tree) = self.get_scta_mut(sc_id) {
-            Some(match subtree_type {
-                SidechainAliveSubtreeType::FWT => match sc_tree.get_fwt_merkle_path(leaf_index) {
-                    Some(v) => v,
-                    None => return None,
-                },
-                SidechainAliveSubtreeType::BWTR => match sc_tree.get_bwtr_merkle_path(leaf_index) {
-                    Some(v) => v,
-                    None => return None,
-                },
-                SidechainAliveSubtreeType::CERT => match sc_tree.get_cert_merkle_path(leaf_index) {
-                    Some(v) => v,
-                    None => return None,
-                },
-                SidechainAliveSubtreeType::SCC => return None, // no merkle path for SCC exists
-            })
-        } else {
-            None
-        }
+        let sc_tree = self.get_scta_mut(sc_id)?;
+        let path = match subtree_type {
+            SidechainAliveSubtreeType::FWT => sc_tree.get_fwt_merkle_path(leaf_index),
+            SidechainAliveSubtreeType::BWTR => sc_tree.get_bwtr_merkle_path(leaf_index),
+            SidechainAliveSubtreeType::CERT => sc_tree.get_cert_merkle_path(leaf_index),
+            SidechainAliveSubtreeType::SCC => return None, // no merkle path for SCC exists
+        };
+        self.stats.subtree_finalizations += 1;
+        path
     }
 
     // Gets commitment i.e. root of a subtree of a specified type in a specified SidechainTreeAlive
@@ -642,100 +2296,115 @@ impl CommitmentTree {
         sc_id: &FieldElement,
         subtree_type: SidechainAliveSubtreeType,
     ) -> Option<FieldElement> {
-        if let Some(sc_tree) = self.get_scta_mut(sc_id) {
-            Some(match subtree_type {
-                SidechainAliveSubtreeType::FWT => match sc_tree.get_fwt_commitment() {
-                    Some(v) => v,
-                    None => return None,
-                },
-                SidechainAliveSubtreeType::BWTR => match sc_tree.get_bwtr_commitment() {
-                    Some(v) => v,
-                    None => return None,
-                },
-                SidechainAliveSubtreeType::CERT => match sc_tree.get_cert_commitment() {
-                    Some(v) => v,
-                    None => return None,
-                },
-                SidechainAliveSubtreeType::SCC => sc_tree.get_scc(), // just SCC value instead of commitment
-            })
-        } else {
-            None
-        }
+        let sc_tree = self.get_scta_mut(sc_id)?;
+        let commitment = match subtree_type {
+            SidechainAliveSubtreeType::FWT => sc_tree.get_fwt_commitment(),
+            SidechainAliveSubtreeType::BWTR => sc_tree.get_bwtr_commitment(),
+            SidechainAliveSubtreeType::CERT => sc_tree.get_cert_commitment(),
+            SidechainAliveSubtreeType::SCC => return Some(sc_tree.get_scc()), // just SCC value instead of commitment
+        };
+        self.stats.subtree_finalizations += 1;
+        commitment
     }
 
     // Gets commitment i.e. root of a subtree of a specified type in a specified SidechainTreeCeased
     // Returns None if get_sctc couldn't get SidechainTreeCeased with a specified ID
     fn sctc_get_subtree_commitment(&mut self, sc_id: &FieldElement) -> Option<FieldElement> {
-        if let Some(sctc) = self.get_sctc_mut(sc_id) {
-            sctc.get_csw_commitment()
-        } else {
-            None
-        }
-    }
-
-    // Gets all leaves of a subtree of a specified type in a specified SidechainTreeAlive
-    // Returns None if there is no SidechainTreeAlive with a specified ID
-    fn scta_get_subtree_leaves(
-        &mut self,
-        sc_id: &FieldElement,
-        subtree_type: SidechainAliveSubtreeType,
-    ) -> Option<Vec<FieldElement>> {
-        if let Some(sc_tree) = self.get_scta_mut(sc_id) {
-            Some(match subtree_type {
-                SidechainAliveSubtreeType::FWT => sc_tree.get_fwt_leaves(),
-                SidechainAliveSubtreeType::BWTR => sc_tree.get_bwtr_leaves(),
-                SidechainAliveSubtreeType::CERT => sc_tree.get_cert_leaves(),
-                SidechainAliveSubtreeType::SCC => panic!("There are no leaves for SCC"),
-            })
-        } else {
-            None
-        }
+        let commitment = self.get_sctc_mut(sc_id)?.get_csw_commitment();
+        self.stats.subtree_finalizations += 1;
+        commitment
     }
 
     // Gets internal commitment-related data needed for building SC-Commitment for a specified by ID sidechain
+    // The data is cached until the sidechain changes, so that the subtrees of the sidechain are finalized only once
     // Returns None if specified sidechain is not present in CommitmentTree
     fn get_sc_data(&mut self, sc_id: &FieldElement) -> Option<ScCommitmentData> {
-        if let Some(sct) = self.get_scta_mut(sc_id) {
-            Some(ScCommitmentData::create_alive(
-                match sct.get_fwt_commitment() {
-                    Some(v) => v,
-                    None => return None,
-                },
-                match sct.get_bwtr_commitment() {
-                    Some(v) => v,
-                    None => return None,
-                },
-                match sct.get_cert_commitment() {
-                    Some(v) => v,
-                    None => return None,
-                },
-                sct.get_scc(),
-            ))
+        if let Some(sc_data) = self.sc_data_cache.get(sc_id) {
+            return Some(sc_data.clone());
+        }
+
+        let sc_data = if let Some(sct) = self.get_scta_mut(sc_id) {
+            let finalized = sct.finalized();
+            self.stats.subtree_finalizations += ALIVE_SUBTREES;
+            let finalized = finalized.ok()?;
+            ScCommitmentData::create_alive(
+                finalized.get_fwt_commitment(),
+                finalized.get_bwtr_commitment(),
+                finalized.get_cert_commitment(),
+                finalized.get_scc(),
+            )
         } else if let Some(sctc) = self.get_sctc_mut(sc_id) {
-            Some(ScCommitmentData::create_ceased(
-                match sctc.get_csw_commitment() {
-                    Some(v) => v,
-                    None => return None,
-                },
-            ))
+            let csw_commitment = sctc.get_csw_commitment();
+            self.stats.subtree_finalizations += 1;
+            ScCommitmentData::create_ceased(csw_commitment?)
         } else {
-            None
-        }
+            return None;
+        };
+        self.sc_data_cache.insert(*sc_id, sc_data.clone());
+        Some(sc_data)
+    }
+
+    // Gets a proof of inclusion of the leaf at position leaf_index of the subtree of a specified type
+    // of a specified sidechain into a current CommitmentTree
+    // Returns None if the sidechain doesn't exist or has no subtree of such type, if leaf_index is out of range
+    //              or if some Merkle Path can't be computed
+    fn get_leaf_existence_proof(
+        &mut self,
+        sc_id: &FieldElement,
+        subtree_type: ScSubtreeType,
+        leaf_index: usize,
+    ) -> Option<LeafExistenceProof> {
+        let alive_subtree_type = match subtree_type {
+            ScSubtreeType::FWT => Some(SidechainAliveSubtreeType::FWT),
+            ScSubtreeType::BWTR => Some(SidechainAliveSubtreeType::BWTR),
+            ScSubtreeType::CERT => Some(SidechainAliveSubtreeType::CERT),
+            ScSubtreeType::CSW => None,
+        };
+        let leaf_mpath = match alive_subtree_type {
+            Some(alive_subtree_type) => {
+                // Paths to the empty leaves of a subtree are available too, but they don't prove anything
+                if leaf_index >= self.get_scta(sc_id)?.num_leaves(&alive_subtree_type) {
+                    return None;
+                }
+                self.scta_get_subtree_leaf_merkle_path(sc_id, alive_subtree_type, leaf_index)?
+            }
+            None => {
+                if leaf_index >= self.get_sctc(sc_id)?.num_csw_leaves() {
+                    return None;
+                }
+                self.get_csw_merkle_path(sc_id, leaf_index)?
+            }
+        };
+        let sc_data = self.get_sc_data(sc_id)?;
+        let sc_proof = self.get_sc_existence_proof(sc_id)?;
+        Some(LeafExistenceProof::create(leaf_mpath, sc_data, sc_proof))
+    }
+
+    // Builds the neighbour, at a specified position of the Commitment-MT, of an absent sidechain
+    // out of the cached Commitment-MT and SC-data, without finalizing the trees again
+    // Returns None if the Merkle Path or the SC-data of the neighbour can't be computed
+    fn get_neighbour(&mut self, index: usize, id: FieldElement) -> Option<ScNeighbour> {
+        let mpath = self.get_commitments_tree()?.get_merkle_path(index)?;
+        Some(ScNeighbour::create(id, mpath, self.get_sc_data(&id)?))
     }
 
     // Gets commitment of a specified SidechainTreeAlive/SidechainTreeCeased
     // Returns None if SidechainTreeAlive/SidechainTreeCeased with a specified ID doesn't exist in a current CommitmentTree
     fn get_sc_commitment_internal(&mut self, sc_id: &FieldElement) -> Option<FieldElement> {
         if let Some(sct) = self.get_scta_mut(sc_id) {
-            sct.get_commitment()
+            let commitment = sct.get_commitment();
+            self.stats.subtree_finalizations += ALIVE_SUBTREES;
+            commitment
         } else if let Some(sctc) = self.get_sctc_mut(sc_id) {
-            sctc.get_commitment()
+            let commitment = sctc.get_commitment();
+            self.stats.subtree_finalizations += 1;
+            commitment
         } else {
             None
         }
     }
 
-    // Returns an indexed list of lexicographically ordered SC-IDs for all contained SCTAs and SCTCs
+    // Returns an indexed list of SC-IDs for all contained SCTAs and SCTCs, ordered according to the CommitmentTreeOrdering
     fn get_indexed_sc_ids(&self) -> Vec<(usize, &FieldElement)> {
         // List of all SCTAs and SCTCs IDs merged together
         let mut ids: Vec<&FieldElement> = self
@@ -745,37 +2414,85 @@ impl CommitmentTree {
             .chain(self.ceased_sc_trees.iter().map(|sc| sc.id()))
             .collect();
         // Ordering IDs
-        ids.sort();
+        self.ordering.sort(&mut ids);
         // Zip sorted IDs with indexes
         ids.into_iter().enumerate().collect()
     }
 
-    // Build MT with ID-ordered SC-commitments as its leafs
+    // Build finalized MT with ID-ordered SC-commitments as its leafs, taking them from the incrementally updated Commitment-MT
     fn build_commitments_tree(&mut self) -> Option<GingerMHT> {
-        let mut cmt = match new_mt(CMT_MT_HEIGHT) {
-            Ok(v) => v,
-            Err(_) => {
-                return None;
+        let _span = trace_span!(
+            "cctp_build_commitments_tree",
+            alive_sidechains = self.alive_sc_trees.len(),
+            ceased_sidechains = self.ceased_sc_trees.len()
+        );
+        self.stats.commitments_tree_builds += 1;
+        let mut cmt = new_mt(self.config.cmt_height).ok()?;
+        for sc_commitment in self.get_incremental_commitments_tree()?.leaves() {
+            cmt.append(*sc_commitment).ok()?;
+        }
+        cmt.finalize_in_place().ok()?;
+        Some(cmt)
+    }
+
+    // Gets the incrementally updated Commitment-MT, building it in case of its absence,
+    // after recomputing the SC-commitments of the sidechains changed since its last update
+    fn get_incremental_commitments_tree(&mut self) -> Option<&IncrementalMT> {
+        if self.incremental_commitments_tree.is_none() {
+            let ids = self
+                .get_indexed_sc_ids()
+                .into_iter()
+                .map(|s| *s.1)
+                .collect::<Vec<FieldElement>>();
+            let mut sc_commitments = Vec::with_capacity(ids.len());
+            for id in ids {
+                sc_commitments.push(self.get_sc_data(&id)?.get_sc_commitment(&id)?);
             }
-        };
-        let ids = self
-            .get_indexed_sc_ids()
-            .into_iter()
-            .map(|s| *s.1)
-            .collect::<Vec<FieldElement>>();
-        for id in ids {
-            // SCTAs/SCTCs with such IDs exist, so unwrap() is safe here
-            if cmt
-                .append(match self.get_sc_commitment_internal(&id) {
-                    Some(v) => v,
-                    None => return None,
-                })
-                .is_err()
-            {
-                return None;
+            self.incremental_commitments_tree =
+                Some(IncrementalMT::build(self.config.cmt_height, sc_commitments).ok()?);
+            self.changed_sc_ids.clear();
+        } else if !self.changed_sc_ids.is_empty() {
+            // Only the changed SC-commitments, and their paths, are recomputed
+            let indices = self
+                .get_indexed_sc_ids()
+                .into_iter()
+                .map(|(index, id)| (*id, index))
+                .collect::<BTreeMap<FieldElement, usize>>();
+            for id in std::mem::take(&mut self.changed_sc_ids) {
+                let sc_commitment = self
+                    .get_sc_data(&id)
+                    .and_then(|sc_data| sc_data.get_sc_commitment(&id));
+                let updated = match (sc_commitment, indices.get(&id)) {
+                    (Some(sc_commitment), Some(index)) => self
+                        .incremental_commitments_tree
+                        .as_mut()?
+                        .update_leaf(*index, sc_commitment)
+                        .is_ok(),
+                    _ => false,
+                };
+                // The Commitment-MT is rebuilt from scratch the next time it's needed
+                if !updated {
+                    self.incremental_commitments_tree = None;
+                    return None;
+                }
             }
         }
-        Some(cmt)
+        self.incremental_commitments_tree.as_ref()
+    }
+
+    // Discards the cached Commitment-MTs after a change of the sidechain with a specified ID
+    // If the sidechain was already contained, the position of its SC-commitment doesn't change, so only such SC-commitment
+    // (and its path) is recomputed on the next update of the incremental Commitment-MT; otherwise, the positions of
+    // the SC-commitments may have changed, so the whole incremental Commitment-MT is rebuilt
+    fn invalidate_commitments_tree(&mut self, sc_id: &FieldElement, new_sidechain: bool) {
+        self.commitments_tree = None;
+        self.sc_data_cache.remove(sc_id);
+        if new_sidechain {
+            self.incremental_commitments_tree = None;
+            self.changed_sc_ids.clear();
+        } else if self.incremental_commitments_tree.is_some() {
+            self.changed_sc_ids.insert(*sc_id);
+        }
     }
 
     // Gets index of an MT leaf for a specified SC-ID
@@ -793,14 +2510,14 @@ impl CommitmentTree {
         }
     }
 
-    // Gets a mutable reference to a current sc-commitments tree
+    // Gets a reference to a current (finalized) sc-commitments tree
     // Builds sc-commitments tree in case of its absence
-    fn get_commitments_tree(&mut self) -> Option<&mut GingerMHT> {
+    fn get_commitments_tree(&mut self) -> Option<&GingerMHT> {
         // build or rebuild a sidechain-commitments tree if there were updates of sc-subtrees
         if self.commitments_tree.is_none() {
             self.commitments_tree = self.build_commitments_tree()
         }
-        self.commitments_tree.as_mut()
+        self.commitments_tree.as_ref()
     }
 
     // For a given absent ID gets smaller and bigger neighbours in pair with their positions in a sorted list of existing SC-IDs
@@ -821,7 +2538,9 @@ impl CommitmentTree {
                     (index_idref.0, *index_idref.1)
                 }
                 // Find a bigger neighbour of the absent_id
-                let bigger_id = sc_ids.iter().find(|(_, id)| *id > absent_id);
+                let bigger_id = sc_ids
+                    .iter()
+                    .find(|(_, id)| self.ordering.compare(id, absent_id) == Ordering::Greater);
                 Some(
                     // Return a pair of neighbours according to a relative position of absent_id in sorted SC-IDs list
                     if let Some(bigger_id) = bigger_id {
@@ -848,245 +2567,2401 @@ impl CommitmentTree {
     }
 }
 
+// The state of a CommitmentTree, e.g. as persisted by a node to be restored after a restart without re-applying all the
+// transactions, is serialized as:
+//     version | ordering | config | max_sidechains | cert_linear_accumulator | hash_operations | alive_sc_trees |
+//     ceased_sc_trees | cached SC-commitments, if the Commitment-MT is up to date
+// The observer is not part of the state. The cached SC-commitments are restored as they are, so that the Commitment-MT
+// doesn't need to be rebuilt out of the sidechains; as for from_leaf_hashes, they are trusted: the IDs of the sidechains
+// are checked on deserialization, while validate_invariants, which also checks the cached SC-commitments against the
+// sidechains, should be called on a CommitmentTree from an untrusted source
+impl CanonicalSerialize for CommitmentTree {
+    fn serialize<W: Write>(&self, mut writer: W) -> Result<(), SerializationError> {
+        CanonicalSerialize::serialize(&CMT_STATE_VERSION, &mut writer)?;
+        CanonicalSerialize::serialize(&self.ordering, &mut writer)?;
+        CanonicalSerialize::serialize(&self.config, &mut writer)?;
+        CanonicalSerialize::serialize(&(self.max_sidechains as u32), &mut writer)?;
+        CanonicalSerialize::serialize(&self.cert_linear_accumulator, &mut writer)?;
+        CanonicalSerialize::serialize(&(self.hash_operations as u64), &mut writer)?;
+        CanonicalSerialize::serialize(&self.alive_sc_trees, &mut writer)?;
+        CanonicalSerialize::serialize(&self.ceased_sc_trees, &mut writer)?;
+        CanonicalSerialize::serialize(&self.cached_sc_commitments(), &mut writer)
+    }
+
+    fn serialized_size(&self) -> usize {
+        CMT_STATE_VERSION.serialized_size()
+            + self.ordering.serialized_size()
+            + self.config.serialized_size()
+            + (self.max_sidechains as u32).serialized_size()
+            + self.cert_linear_accumulator.serialized_size()
+            + (self.hash_operations as u64).serialized_size()
+            + self.alive_sc_trees.serialized_size()
+            + self.ceased_sc_trees.serialized_size()
+            + self.cached_sc_commitments().serialized_size()
+    }
+}
+
+impl CanonicalDeserialize for CommitmentTree {
+    fn deserialize<R: Read>(mut reader: R) -> Result<Self, SerializationError> {
+        let version: u8 = CanonicalDeserialize::deserialize(&mut reader)?;
+        if version != CMT_STATE_VERSION {
+            return Err(SerializationError::InvalidData);
+        }
+        let ordering: CommitmentTreeOrdering = CanonicalDeserialize::deserialize(&mut reader)?;
+        let config: CommitmentTreeConfig = CanonicalDeserialize::deserialize(&mut reader)?;
+        let max_sidechains: u32 = CanonicalDeserialize::deserialize(&mut reader)?;
+        let cert_linear_accumulator: bool = CanonicalDeserialize::deserialize(&mut reader)?;
+        let hash_operations: u64 = CanonicalDeserialize::deserialize(&mut reader)?;
+        // The subtrees of the sidechains are rebuilt with the heights of the config
+        let alive_sc_trees = deserialize_sc_trees(&mut reader, |reader| {
+            SidechainTreeAlive::deserialize_with_config(reader, &config)
+        })?;
+        let ceased_sc_trees = deserialize_sc_trees(&mut reader, |reader| {
+            SidechainTreeCeased::deserialize_with_config(reader, &config)
+        })?;
+        let sc_commitments: Option<Vec<FieldElement>> =
+            CanonicalDeserialize::deserialize(&mut reader)?;
+
+        if max_sidechains as usize > config.cmt_capacity()
+            || alive_sc_trees
+                .iter()
+                .any(|scta| scta.get_cert_linear_accumulator().is_some() != cert_linear_accumulator)
+        {
+            return Err(SerializationError::InvalidData);
+        }
+        let mut cmt = Self {
+            alive_sc_trees,
+            ceased_sc_trees,
+            max_sidechains: max_sidechains as usize,
+            hash_operations: hash_operations as usize,
+            cert_linear_accumulator,
+            ordering,
+            config,
+            ..Self::create()
+        };
+        cmt.validate_sidechain_ids()
+            .map_err(|_| SerializationError::InvalidData)?;
+
+        if let Some(sc_commitments) = sc_commitments {
+            if sc_commitments.len() != cmt.alive_sc_trees.len() + cmt.ceased_sc_trees.len() {
+                return Err(SerializationError::InvalidData);
+            }
+            cmt.incremental_commitments_tree = Some(
+                IncrementalMT::build(config.cmt_height, sc_commitments)
+                    .map_err(|_| SerializationError::InvalidData)?,
+            );
+        }
+        Ok(cmt)
+    }
+}
+
+// Deserializes a list of sidechain trees, serialized as a Vec, each one by a specified function
+fn deserialize_sc_trees<R: Read, T>(
+    mut reader: R,
+    deserialize_sc_tree: impl Fn(&mut R) -> Result<T, SerializationError>,
+) -> Result<Vec<T>, SerializationError> {
+    let len: u64 = CanonicalDeserialize::deserialize(&mut reader)?;
+    let mut sc_trees = Vec::new();
+    for _ in 0..len {
+        sc_trees.push(deserialize_sc_tree(&mut reader)?);
+    }
+    Ok(sc_trees)
+}
+
+// Decodes a FieldElement from its canonical serialization, naming the field in the error
+fn decode_field_element(
+    bytes: &[u8; FIELD_SIZE],
+    field_name: &str,
+) -> Result<FieldElement, CommitmentTreeError> {
+    deserialize_from_buffer_strict(&bytes[..], Some(true), None)
+        .map_err(|_| CommitmentTreeError::InvalidFieldElementBytes(field_name.to_owned()))
+}
+
+// Decodes a list of FieldElements, naming the field and the index of the offending element in the error
+fn decode_field_elements(
+    bytes: &[[u8; FIELD_SIZE]],
+    field_name: &str,
+) -> Result<Vec<FieldElement>, CommitmentTreeError> {
+    bytes
+        .iter()
+        .enumerate()
+        .map(|(i, fe_bytes)| decode_field_element(fe_bytes, &format!("{}[{}]", field_name, i)))
+        .collect()
+}
+
 #[cfg(test)]
 mod test {
-    use crate::commitment_tree::CommitmentTree;
+    use crate::commitment_tree::proofs::{
+        ScAbsenceProof, ScExistenceProof, SC_ABSENCE_PROOF_FORMAT_VERSION,
+        SC_EXISTENCE_PROOF_FORMAT_VERSION,
+    };
+    use crate::commitment_tree::{
+        cert_accumulator::CertLinearAccumulator,
+        compute_sc_commitment_alive, compute_sc_commitment_alive_with_config,
+        compute_sc_commitment_ceased, compute_sc_commitment_ceased_with_config,
+        config::CommitmentTreeConfig,
+        error::CommitmentTreeError,
+        hash_operations_estimate, hash_operations_estimate_with_config,
+        proofs::ScSubtreeType,
+        sc_id_bytes,
+        sidechain_tree_alive::{SidechainAliveSubtreeType, SidechainTreeAlive, FWT_MT_HEIGHT},
+        sidechain_tree_ceased::{SidechainTreeCeased, CSW_MT_HEIGHT},
+        AddedSummary, CommitmentTree, CommitmentTreeObserver, CommitmentTreeOrdering,
+        CommitmentTreeStats, ScLeafSets, ScTxEvent, CMT_MT_CAPACITY, CMT_MT_HEIGHT,
+        CMT_STATE_VERSION,
+    };
+    use crate::testing::{
+        adjacent_fes, cmt_with_sidechains_with_rng, corner_case_absent_ids, full_cmt_with_rng,
+        max_fe, min_fe, sorted_sc_ids_with_rng,
+    };
     use crate::type_mapping::*;
     use crate::utils::{
-        commitment_tree::{rand_fe, rand_fe_vec, rand_vec},
+        commitment_tree::{
+            hash_vec, pow2, rand_fe_vec_with_rng, rand_fe_with_rng, rand_vec_with_rng,
+        },
         data_structures::{BackwardTransfer, BitVectorElementsConfig},
-        mht,
+        get_bt_merkle_root, mht,
+        serialization::serialize_to_buffer,
+    };
+    use algebra::{
+        serialize::{CanonicalDeserialize, CanonicalSerialize},
+        test_canonical_serialize_deserialize, Field,
     };
-    use algebra::{test_canonical_serialize_deserialize, Field};
-    use rand::Rng;
-    use std::convert::TryInto;
+    use primitives::FieldBasedMerkleTreePath;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+    use std::{
+        cmp::Ordering,
+        collections::BTreeMap,
+        convert::TryInto,
+        sync::{Arc, Mutex},
+    };
+
+    // Creates a sequence of FieldElements with values [0, 1, 2, 3, 4]
+    fn get_fe_0_4() -> Vec<FieldElement> {
+        let fe0 = FieldElement::zero();
+        let fe1 = FieldElement::one();
+        let fe2 = FieldElement::one() + fe1;
+        let fe3 = FieldElement::one() + fe2;
+        let fe4 = FieldElement::one() + fe3;
+        vec![fe0, fe1, fe2, fe3, fe4]
+    }
+
+    #[test]
+    fn commitment_tree_tests() {
+        let mut cmt = CommitmentTree::create();
+        let fe = get_fe_0_4();
+        // Initial order of IDs is reversed, i.e. vec![3, 2, 1, 0] to test SCIDs-ordering functionality
+        let sc_ids = fe.iter().take(4).rev().collect::<Vec<_>>();
+        let non_existing_sc_id = &fe[4];
+
+        // Initial commitment_tree value of an empty CMT
+        let empty_comm = cmt.get_commitment().unwrap();
+
+        // Initial SCT commitments are empty due to absence of such SCTs
+        assert_eq!(cmt.get_fwt_commitment(sc_ids[0]), None);
+        assert_eq!(cmt.get_bwtr_commitment(sc_ids[1]), None);
+        assert_eq!(cmt.get_cert_commitment(sc_ids[2]), None);
+        assert_eq!(cmt.get_csw_commitment(sc_ids[3]), None);
+
+        // Merkle path to unknown SC Commitment must be absent
+        assert_eq!(cmt.get_sc_commitment_merkle_path(sc_ids[0]), None);
+
+        // Set values in corresponding subtrees with transparent creation of the SCTs with specified IDs
+        assert!(cmt.add_fwt_leaf(sc_ids[0], &fe[1]));
+        assert!(cmt.add_bwtr_leaf(sc_ids[1], &fe[2]));
+        assert!(cmt.add_cert_leaf(sc_ids[2], &fe[3]));
+        assert!(cmt.add_csw_leaf(sc_ids[3], &fe[4]));
+
+        // All updated subtrees should have the same leaves as what have been added
+        assert_eq!(cmt.get_fwt_leaves(sc_ids[0]).unwrap(), vec![fe[1]]);
+        assert_eq!(cmt.get_bwtr_leaves(sc_ids[1]).unwrap(), vec![fe[2]]);
+        assert_eq!(cmt.get_cert_leaves(sc_ids[2]).unwrap(), vec![fe[3]]);
+        assert_eq!(cmt.get_csw_leaves(sc_ids[3]).unwrap(), vec![fe[4]]);
+
+        // All non-updated subtrees should have empty list of leaves
+        assert!(cmt.get_fwt_leaves(sc_ids[1]).unwrap().is_empty());
+        assert!(cmt.get_bwtr_leaves(sc_ids[2]).unwrap().is_empty());
+        assert!(cmt.get_cert_leaves(sc_ids[0]).unwrap().is_empty());
+
+        // There should not be any leaves for nonexisting subtrees
+        assert!(cmt.get_fwt_leaves(non_existing_sc_id).is_none());
+        assert!(cmt.get_bwtr_leaves(non_existing_sc_id).is_none());
+        assert!(cmt.get_cert_leaves(non_existing_sc_id).is_none());
+        assert!(cmt.get_csw_leaves(non_existing_sc_id).is_none());
+
+        // Alive sidechains have no CSW leaves, ceased ones have no alive subtrees leaves
+        assert!(cmt.get_csw_leaves(sc_ids[0]).is_none());
+        assert!(cmt.get_fwt_leaves(sc_ids[3]).is_none());
+
+        // All updated subtrees should have non-empty subtrees roots
+        assert!(cmt.get_fwt_commitment(sc_ids[0]).is_some());
+        assert!(cmt.get_bwtr_commitment(sc_ids[1]).is_some());
+        assert!(cmt.get_cert_commitment(sc_ids[2]).is_some());
+        assert!(cmt.get_csw_commitment(sc_ids[3]).is_some());
+
+        // There should not be any roots for nonexisting subtrees
+        assert!(cmt.get_fwt_commitment(non_existing_sc_id).is_none());
+        assert!(cmt.get_bwtr_commitment(non_existing_sc_id).is_none());
+        assert!(cmt.get_cert_commitment(non_existing_sc_id).is_none());
+        assert!(cmt.get_csw_commitment(non_existing_sc_id).is_none());
+
+        // All updated SCTs should have non-empty commitments
+        sc_ids
+            .clone()
+            .into_iter()
+            .for_each(|sc_id| assert!(cmt.get_sc_commitment(sc_id).is_some()));
+
+        // There is no SCT for ID which wasn't added during previous calls
+        assert!(cmt.get_sc_commitment(non_existing_sc_id).is_none());
+
+        // No CSW data can be added to any SCT
+        assert!(!cmt.add_csw_leaf(sc_ids[0], &fe[1]));
+        assert!(!cmt.add_csw_leaf(sc_ids[1], &fe[1]));
+        assert!(!cmt.add_csw_leaf(sc_ids[2], &fe[1]));
+
+        // No SCT-related data can be added to SCTC
+        assert!(!cmt.add_fwt_leaf(sc_ids[3], &fe[1]));
+        assert!(!cmt.add_bwtr_leaf(sc_ids[3], &fe[1]));
+        assert!(!cmt.add_cert_leaf(sc_ids[3], &fe[1]));
+
+        // Updating SCC in the first SCT and checking that commitment of this tree also has been updated
+        let comm_without_scc = cmt.get_sc_commitment(sc_ids[0]);
+        cmt.set_scc(sc_ids[0], &fe[1]);
+        assert_eq!(cmt.get_scc(sc_ids[0]).unwrap(), fe[1]);
+        assert_ne!(comm_without_scc, cmt.get_sc_commitment(sc_ids[0]));
+
+        // Commitment of the updated CMT has non-empty value
+        assert_ne!(empty_comm, cmt.get_commitment().unwrap());
+
+        // There is no existence-proof for a non-existing SC-ID
+        assert!(cmt.get_sc_existence_proof(non_existing_sc_id).is_none());
+
+        // Creating a valid existence proof
+        let existence_proof = cmt.get_sc_existence_proof(sc_ids[0]);
+        assert!(existence_proof.is_some());
+
+        test_canonical_serialize_deserialize(true, &existence_proof);
+
+        // Verification of a valid deserialized existence-proof
+        assert!(CommitmentTree::verify_sc_commitment(
+            cmt.get_sc_commitment(sc_ids[0]).as_ref().unwrap(),
+            &existence_proof.unwrap(),
+            cmt.get_commitment().as_ref().unwrap()
+        ));
+
+        // Verify merkle path to sc commitment
+        let commitment = cmt.get_commitment().unwrap();
+        sc_ids.clone().into_iter().for_each(|sc_id| {
+            let sc_commitment = cmt.get_sc_commitment(sc_id).unwrap();
+            let sc_commitment_merkle_path = cmt.get_sc_commitment_merkle_path(sc_id).unwrap();
+            assert!(mht::verify_ginger_merkle_path_without_length_check(
+                &sc_commitment_merkle_path,
+                &sc_commitment,
+                &commitment
+            ));
+        });
+
+        // Verify merkle path to FT leaf
+        assert!(mht::verify_ginger_merkle_path_without_length_check(
+            &cmt.get_fwt_merkle_path(sc_ids[0], 0).unwrap(),
+            &fe[1],
+            &cmt.get_fwt_commitment(sc_ids[0]).unwrap()
+        ));
+
+        // Verify merkle path to BWTR leaf
+        assert!(mht::verify_ginger_merkle_path_without_length_check(
+            &cmt.get_bwtr_merkle_path(sc_ids[1], 0).unwrap(),
+            &fe[2],
+            &cmt.get_bwtr_commitment(sc_ids[1]).unwrap()
+        ));
+
+        // Verify merkle path to Cert leaf
+        assert!(mht::verify_ginger_merkle_path_without_length_check(
+            &cmt.get_cert_merkle_path(sc_ids[2], 0).unwrap(),
+            &fe[3],
+            &cmt.get_cert_commitment(sc_ids[2]).unwrap()
+        ));
+    }
+
+    #[test]
+    fn sc_absence_proofs_tests() {
+        let sc_id = get_fe_0_4().into_iter().collect::<Vec<_>>();
+
+        let leaf = FieldElement::one();
+
+        let mut cmt = CommitmentTree::create();
+
+        // Getting commitment for empty CMT
+        let commitment_empty = cmt.get_commitment();
+        //------------------------------------------------------------------------------------------
+        // Creating and validating absence proof in case of an empty CMT; Any SC-ID is absent in such a CMT
+        let proof_empty = cmt.get_sc_absence_proof(&sc_id[0]);
+        assert!(proof_empty.is_some());
+
+        test_canonical_serialize_deserialize(true, &proof_empty);
+
+        // Verification of a valid deserialized absence-proof
+        assert!(CommitmentTree::verify_sc_absence(
+            &sc_id[0],
+            proof_empty.as_ref().unwrap(),
+            commitment_empty.as_ref().unwrap()
+        ));
+
+        //------------------------------------------------------------------------------------------
+        // Initializing Commitment Tree
+        // NOTE: Here index is the same as FieldElement-value of corresponding scId
+
+        // Creating two SC-Trees with IDs: 1 and 3
+        assert!(cmt.add_fwt_leaf(&sc_id[1], &leaf));
+        assert!(cmt.add_csw_leaf(&sc_id[3], &leaf));
+
+        // Getting commitment for all SC-trees
+        let commitment = cmt.get_commitment();
+
+        // There is no absence-proof for an existing SC-ID
+        assert!(cmt.get_sc_absence_proof(&sc_id[1]).is_none());
+
+        // Empty proof is not valid for a non-empty Commitment Tree
+        assert!(!CommitmentTree::verify_sc_absence(
+            &sc_id[0],
+            proof_empty.as_ref().unwrap(),
+            commitment.as_ref().unwrap()
+        ));
+        //------------------------------------------------------------------------------------------
+        // Creating and validating absence proof for non-existing ID which value is smaller than any existing ID
+        let proof_leftmost = cmt.get_sc_absence_proof(&sc_id[0]);
+        assert!(proof_leftmost.is_some());
+
+        test_canonical_serialize_deserialize(true, &proof_leftmost);
+
+        // Verification of a valid deserialized absence-proof
+        assert!(CommitmentTree::verify_sc_absence(
+            &sc_id[0],
+            proof_leftmost.as_ref().unwrap(),
+            commitment.as_ref().unwrap()
+        ));
+
+        //------------------------------------------------------------------------------------------
+        // Creating and validating absence proof for non-existing ID which value is between existing IDs
+        let proof_midst = cmt.get_sc_absence_proof(&sc_id[2]);
+        assert!(proof_midst.is_some());
+
+        test_canonical_serialize_deserialize(true, &proof_midst);
+
+        // Verification of a valid deserialized absence-proof
+        assert!(CommitmentTree::verify_sc_absence(
+            &sc_id[2],
+            proof_midst.as_ref().unwrap(),
+            commitment.as_ref().unwrap()
+        ));
+
+        //------------------------------------------------------------------------------------------
+        // Creating and validating absence proof for non-existing ID which value is bigger than any existing ID
+        let proof_rightmost = cmt.get_sc_absence_proof(&sc_id[4]);
+        assert!(proof_rightmost.is_some());
+
+        test_canonical_serialize_deserialize(true, &proof_rightmost);
+
+        // Verification of a valid deserialized absence-proof
+        assert!(CommitmentTree::verify_sc_absence(
+            &sc_id[4],
+            proof_rightmost.as_ref().unwrap(),
+            commitment.as_ref().unwrap()
+        ));
+    }
+
+    // Builds and verifies the absence proofs of the given absent IDs, checking that their neighbours
+    // are at the boundaries of the Commitment-MT when the absent ID is out of the range of the existing ones
+    fn check_absence_proofs(
+        cmt: &mut CommitmentTree,
+        sorted_ids: &[FieldElement],
+        absent_ids: &[FieldElement],
+    ) {
+        let ordering = cmt.ordering();
+        let commitment = cmt.get_commitment().unwrap();
+        let config = cmt.config;
+        let verify = |absent_id: &FieldElement, proof: &ScAbsenceProof| {
+            CommitmentTree::verify_sc_absence_with_ordering(
+                absent_id,
+                proof,
+                &commitment,
+                ordering,
+                &config,
+            )
+        };
+        for absent_id in absent_ids.iter() {
+            let proof = cmt.get_sc_absence_proof(absent_id).unwrap();
+            assert!(verify(absent_id, &proof));
+
+            let first = &sorted_ids[0];
+            let last = &sorted_ids[sorted_ids.len() - 1];
+            if ordering.compare(absent_id, first) == Ordering::Less {
+                assert!(proof.left.is_none());
+                assert_eq!(proof.right.as_ref().unwrap().mpath.leaf_index(), 0);
+            } else if ordering.compare(absent_id, last) == Ordering::Greater {
+                assert!(proof.right.is_none());
+                assert_eq!(
+                    proof.left.as_ref().unwrap().mpath.leaf_index(),
+                    sorted_ids.len() - 1
+                );
+            } else {
+                let (left, right) = (proof.left.as_ref().unwrap(), proof.right.as_ref().unwrap());
+                assert_eq!(left.mpath.leaf_index() + 1, right.mpath.leaf_index());
+
+                // Neither of the neighbours alone proves the absence
+                for forged in [
+                    ScAbsenceProof::create(proof.left.clone(), None, ordering),
+                    ScAbsenceProof::create(None, proof.right.clone(), ordering),
+                ]
+                .iter()
+                {
+                    assert!(!verify(absent_id, forged));
+                }
+            }
+
+            // The proof doesn't prove the absence of the neighbours
+            for neighbour in proof.left.iter().chain(proof.right.iter()) {
+                assert!(!verify(&neighbour.id, &proof));
+            }
+        }
+    }
+
+    #[test]
+    fn proof_format_version_tests() {
+        assert_eq!(
+            ScExistenceProof::proof_format_version(),
+            SC_EXISTENCE_PROOF_FORMAT_VERSION
+        );
+        assert_eq!(
+            ScAbsenceProof::proof_format_version(),
+            SC_ABSENCE_PROOF_FORMAT_VERSION
+        );
+        assert!(ScExistenceProof::can_verify(SC_EXISTENCE_PROOF_FORMAT_VERSION).is_ok());
+        assert!(ScAbsenceProof::can_verify(SC_ABSENCE_PROOF_FORMAT_VERSION).is_ok());
+
+        // Unknown versions are rejected
+        assert_eq!(
+            ScExistenceProof::can_verify(SC_EXISTENCE_PROOF_FORMAT_VERSION + 1),
+            Err(CommitmentTreeError::UnsupportedProofFormatVersion(
+                SC_EXISTENCE_PROOF_FORMAT_VERSION + 1,
+                SC_EXISTENCE_PROOF_FORMAT_VERSION
+            ))
+        );
+        assert_eq!(
+            ScAbsenceProof::can_verify(0),
+            Err(CommitmentTreeError::UnsupportedProofFormatVersion(
+                0,
+                SC_ABSENCE_PROOF_FORMAT_VERSION
+            ))
+        );
+    }
+
+    #[test]
+    fn sc_absence_proofs_random_tests() {
+        // Random trees, with random orderings and sizes, and random absent IDs besides the corner-case ones
+        // Each trial is reproducible from its seed, printed in case of failure
+        let seeds = rand::thread_rng()
+            .sample_iter(rand::distributions::Standard)
+            .take(32)
+            .collect::<Vec<u64>>();
+        for seed in seeds {
+            println!("Absence proofs trial seed: {}", seed);
+            let mut rng = StdRng::seed_from_u64(seed);
+            let ordering = if rng.gen() {
+                CommitmentTreeOrdering::FieldElementOrder
+            } else {
+                CommitmentTreeOrdering::ByteLexOrder
+            };
+
+            let sc_ids = sorted_sc_ids_with_rng(rng.gen_range(1..=64), ordering, &mut rng);
+            let mut cmt = cmt_with_sidechains_with_rng(&sc_ids, ordering, &mut rng);
+
+            let mut absent_ids = corner_case_absent_ids(&sc_ids);
+            absent_ids.extend(
+                (0..8)
+                    .map(|_| rand_fe_with_rng(&mut rng))
+                    .filter(|id| !sc_ids.contains(id)),
+            );
+            check_absence_proofs(&mut cmt, &sc_ids, &absent_ids);
+
+            // Existing IDs have no absence proof
+            for sc_id in sc_ids.iter() {
+                assert!(cmt.get_sc_absence_proof(sc_id).is_none());
+            }
+        }
+    }
+
+    #[test]
+    fn sc_absence_proofs_corner_cases_tests() {
+        let mut rng = rand::thread_rng();
+
+        for ordering in [
+            CommitmentTreeOrdering::FieldElementOrder,
+            CommitmentTreeOrdering::ByteLexOrder,
+        ]
+        .iter()
+        {
+            // Random sidechains, with absent IDs adjacent to them and at the extremes of the field
+            let sc_ids = sorted_sc_ids_with_rng(8, *ordering, &mut rng);
+            let mut cmt = cmt_with_sidechains_with_rng(&sc_ids, *ordering, &mut rng);
+            check_absence_proofs(&mut cmt, &sc_ids, &corner_case_absent_ids(&sc_ids));
+
+            // Sidechains at the extremes of the field
+            let mut sc_ids = vec![min_fe(), max_fe(), rand_fe_with_rng(&mut rng)];
+            sc_ids.sort_by(|a, b| ordering.compare(a, b));
+            let mut cmt = cmt_with_sidechains_with_rng(&sc_ids, *ordering, &mut rng);
+            check_absence_proofs(&mut cmt, &sc_ids, &corner_case_absent_ids(&sc_ids));
+            assert!(cmt.get_sc_absence_proof(&min_fe()).is_none());
+            assert!(cmt.get_sc_absence_proof(&max_fe()).is_none());
+
+            // A single sidechain
+            let sc_ids = vec![rand_fe_with_rng(&mut rng)];
+            let mut cmt = cmt_with_sidechains_with_rng(&sc_ids, *ordering, &mut rng);
+            check_absence_proofs(&mut cmt, &sc_ids, &corner_case_absent_ids(&sc_ids));
+        }
+
+        // Full Commitment-MT: the neighbours of the IDs out of range are the first and the last leaves
+        let ordering = CommitmentTreeOrdering::FieldElementOrder;
+        let (mut cmt, sc_ids) = full_cmt_with_rng(ordering, &mut rng);
+        assert!(!cmt.add_fwt_leaf(&min_fe(), &rand_fe_with_rng(&mut rng)));
+        let mut absent_ids = vec![min_fe(), max_fe()];
+        for sc_id in [
+            sc_ids[0],
+            sc_ids[CMT_MT_CAPACITY / 2],
+            sc_ids[CMT_MT_CAPACITY - 1],
+        ]
+        .iter()
+        {
+            let (prev, next) = adjacent_fes(sc_id);
+            absent_ids.extend(
+                [prev, next]
+                    .iter()
+                    .filter(|id| !sc_ids.contains(id))
+                    .copied(),
+            );
+        }
+        check_absence_proofs(&mut cmt, &sc_ids, &absent_ids);
+    }
+
+    #[test]
+    fn incremental_commitment_tests() {
+        let mut rng = rand::thread_rng();
+        let sc_ids = rand_fe_vec_with_rng(4, &mut rng);
+        let mut cmt = CommitmentTree::create();
+        let mut events = Vec::new();
+
+        // Alternating adds and commitment queries give the same commitments of a CommitmentTree built from scratch
+        for i in 0..24 {
+            let (sc_id, leaf) = (sc_ids[i % sc_ids.len()], rand_fe_with_rng(&mut rng));
+            let event = match i % 3 {
+                _ if sc_id == sc_ids[3] => ScTxEvent::Csw { sc_id, leaf },
+                0 => ScTxEvent::Fwt { sc_id, leaf },
+                1 => ScTxEvent::Cert { sc_id, leaf },
+                _ => ScTxEvent::Scc { sc_id, leaf },
+            };
+            cmt.extend(vec![event.clone()]).unwrap();
+            events.push(event);
+
+            let mut expected_cmt = CommitmentTree::create();
+            expected_cmt.extend(events.clone()).unwrap();
+            let commitment = cmt.get_commitment().unwrap();
+            assert_eq!(commitment, expected_cmt.get_commitment().unwrap());
+            assert!(cmt.changed_sc_ids.is_empty());
+
+            // Merkle paths are consistent with the incrementally updated commitment
+            let sc_commitment = cmt.get_sc_commitment(&sc_id).unwrap();
+            let proof = cmt.get_sc_existence_proof(&sc_id).unwrap();
+            assert!(CommitmentTree::verify_sc_commitment(
+                &sc_commitment,
+                &proof,
+                &commitment
+            ));
+        }
+
+        // Leaves added to already contained sidechains only mark them as changed
+        assert!(cmt.add_fwt_leaf(&sc_ids[0], &rand_fe_with_rng(&mut rng)));
+        assert!(cmt.add_csw_leaf(&sc_ids[3], &rand_fe_with_rng(&mut rng)));
+        assert!(cmt.incremental_commitments_tree.is_some());
+        assert_eq!(cmt.changed_sc_ids.len(), 2);
+
+        // While new sidechains make the Commitment-MT be rebuilt
+        assert!(cmt.mark_ceased(&rand_fe_with_rng(&mut rng)).is_ok());
+        assert!(cmt.incremental_commitments_tree.is_none());
+        assert!(cmt.changed_sc_ids.is_empty());
+        assert!(cmt.get_commitment().is_some());
+    }
+
+    #[test]
+    fn sc_absence_proofs_caching_tests() {
+        let mut rng = rand::thread_rng();
+        let sc_ids = sorted_sc_ids_with_rng(4, CommitmentTreeOrdering::FieldElementOrder, &mut rng);
+        let (existing_ids, absent_ids) = ([sc_ids[0], sc_ids[2]], [sc_ids[1], sc_ids[3]]);
+        let mut cmt = cmt_with_sidechains_with_rng(
+            &existing_ids,
+            CommitmentTreeOrdering::FieldElementOrder,
+            &mut rng,
+        );
+
+        // Computing the commitment caches the data of all the sidechains, which is reused by the absence proofs
+        let commitment = cmt.get_commitment().unwrap();
+        assert_eq!(cmt.sc_data_cache.len(), existing_ids.len());
+        for absent_id in absent_ids.iter() {
+            let proof = cmt.get_sc_absence_proof(absent_id).unwrap();
+            assert!(CommitmentTree::verify_sc_absence(
+                absent_id,
+                &proof,
+                &commitment
+            ));
+        }
+
+        // The data of a changed sidechain is discarded, and the proofs are built out of the new one
+        assert!(cmt.add_fwt_leaf(&existing_ids[0], &rand_fe_with_rng(&mut rng)));
+        assert!(!cmt.sc_data_cache.contains_key(&existing_ids[0]));
+        let commitment = cmt.get_commitment().unwrap();
+        let proof = cmt.get_sc_absence_proof(&absent_ids[0]).unwrap();
+        assert!(CommitmentTree::verify_sc_absence(
+            &absent_ids[0],
+            &proof,
+            &commitment
+        ));
+        assert_eq!(
+            proof.left.unwrap().sc_data,
+            cmt.get_sc_data(&existing_ids[0]).unwrap()
+        );
+    }
+
+    #[test]
+    fn commitment_tree_serialization_tests() {
+        let mut rng = rand::thread_rng();
+        let sc_ids = rand_fe_vec_with_rng(5, &mut rng);
+        let mut cmt =
+            cmt_with_sidechains_with_rng(&sc_ids, CommitmentTreeOrdering::ByteLexOrder, &mut rng);
+        cmt.set_cert_linear_accumulator(true).unwrap();
+        assert!(cmt.add_cert_leaf(&sc_ids[0], &rand_fe_with_rng(&mut rng)));
+        assert!(cmt.set_scc(&sc_ids[2], &rand_fe_with_rng(&mut rng)));
+
+        let serialize = |cmt: &CommitmentTree| {
+            let mut bytes = Vec::new();
+            CanonicalSerialize::serialize(cmt, &mut bytes).unwrap();
+            assert_eq!(bytes.len(), cmt.serialized_size());
+            bytes
+        };
+
+        // Both with and without the cached SC-commitments
+        for with_cache in [false, true].iter() {
+            let expected_commitment = if *with_cache {
+                cmt.get_commitment()
+            } else {
+                None
+            };
+            let bytes = serialize(&cmt);
+            let mut restored_cmt = CommitmentTree::deserialize(bytes.as_slice()).unwrap();
+            assert_eq!(
+                restored_cmt.incremental_commitments_tree.is_some(),
+                *with_cache
+            );
+            assert_eq!(restored_cmt.ordering(), cmt.ordering());
+            assert_eq!(restored_cmt.max_sidechains(), cmt.max_sidechains());
+            assert_eq!(restored_cmt.hash_operations(), cmt.hash_operations());
+            assert_eq!(
+                restored_cmt.get_cert_linear_accumulator(&sc_ids[0]),
+                cmt.get_cert_linear_accumulator(&sc_ids[0])
+            );
+            for sc_id in sc_ids.iter() {
+                assert_eq!(restored_cmt.is_ceased(sc_id), cmt.is_ceased(sc_id));
+                assert_eq!(
+                    restored_cmt.get_sc_commitment(sc_id),
+                    cmt.get_sc_commitment(sc_id)
+                );
+            }
+            assert!(restored_cmt.validate_invariants().is_ok());
+            assert_eq!(restored_cmt.get_commitment(), cmt.get_commitment());
+            if *with_cache {
+                assert_eq!(restored_cmt.get_commitment(), expected_commitment);
+            }
+
+            // The restored CommitmentTree can be updated as the original one
+            let leaf = rand_fe_with_rng(&mut rng);
+            for cmt in [&mut cmt, &mut restored_cmt].iter_mut() {
+                assert!(cmt.add_fwt_leaf(&sc_ids[4], &leaf));
+                assert!(cmt.add_csw_leaf(&sc_ids[1], &leaf));
+            }
+            assert_eq!(restored_cmt.get_commitment(), cmt.get_commitment());
+            assert_eq!(serialize(&restored_cmt), serialize(&cmt));
+        }
+
+        // Malformed states are rejected
+        let bytes = serialize(&cmt);
+        let mut wrong_version = bytes.clone();
+        wrong_version[0] = CMT_STATE_VERSION + 1;
+        assert!(CommitmentTree::deserialize(wrong_version.as_slice()).is_err());
+        assert!(CommitmentTree::deserialize(&bytes[..bytes.len() - 1]).is_err());
+
+        // Heights bigger than the consensus ones are rejected before building any MT out of them
+        let config_offset = CMT_STATE_VERSION.serialized_size() + cmt.ordering().serialized_size();
+        for i in 0..cmt.config().serialized_size() {
+            for oversized_height in [CMT_MT_HEIGHT as u8 + 1, u8::MAX].iter() {
+                let mut oversized_config = bytes.clone();
+                oversized_config[config_offset + i] = *oversized_height;
+                assert!(CommitmentTree::deserialize(oversized_config.as_slice()).is_err());
+            }
+        }
+
+        let mut invalid_cmt = CommitmentTree::create();
+        for _ in 0..2 {
+            invalid_cmt
+                .ceased_sc_trees
+                .push(SidechainTreeCeased::create(&sc_ids[0]).unwrap());
+        }
+        assert!(CommitmentTree::deserialize(serialize(&invalid_cmt).as_slice()).is_err());
+    }
+
+    #[test]
+    fn csw_leaf_proof_tests() {
+        let mut rng = rand::thread_rng();
+        let mut cmt = CommitmentTree::create();
+
+        let sc_id = rand_fe_with_rng(&mut rng);
+        let other_sc_id = rand_fe_with_rng(&mut rng);
+        let csw_data = (0..5)
+            .map(|_| {
+                (
+                    rng.gen::<u64>(),
+                    rand_fe_with_rng(&mut rng),
+                    rand_vec_with_rng(MC_PK_SIZE, &mut rng).try_into().unwrap(),
+                )
+            })
+            .collect::<Vec<(u64, FieldElement, [u8; MC_PK_SIZE])>>();
+
+        for (amount, nullifier, mc_pk_hash) in csw_data.iter() {
+            assert!(cmt.add_csw(&sc_id, *amount, nullifier, mc_pk_hash));
+        }
+        assert!(cmt.add_fwt_leaf(&other_sc_id, &rand_fe_with_rng(&mut rng)));
+
+        // There is no CSW leaf proof for an alive or non-existing sidechain
+        assert!(cmt.get_csw_leaf_proof(&other_sc_id, 0).is_none());
+        assert!(cmt
+            .get_csw_leaf_proof(&rand_fe_with_rng(&mut rng), 0)
+            .is_none());
+
+        let commitment = cmt.get_commitment().unwrap();
+
+        for (i, (amount, nullifier, mc_pk_hash)) in csw_data.iter().enumerate() {
+            let proof = cmt.get_csw_leaf_proof(&sc_id, i).unwrap();
+            test_canonical_serialize_deserialize(true, &proof);
+
+            // Valid CSW data is verified against the commitment
+            assert!(CommitmentTree::verify_csw(
+                &sc_id,
+                *amount,
+                nullifier,
+                mc_pk_hash,
+                &proof,
+                &commitment
+            ));
+
+            // Wrong CSW data, sidechain or commitment must be rejected
+            assert!(!CommitmentTree::verify_csw(
+                &sc_id,
+                amount.wrapping_add(1),
+                nullifier,
+                mc_pk_hash,
+                &proof,
+                &commitment
+            ));
+            assert!(!CommitmentTree::verify_csw(
+                &other_sc_id,
+                *amount,
+                nullifier,
+                mc_pk_hash,
+                &proof,
+                &commitment
+            ));
+            assert!(!CommitmentTree::verify_csw(
+                &sc_id,
+                *amount,
+                nullifier,
+                mc_pk_hash,
+                &proof,
+                &rand_fe_with_rng(&mut rng)
+            ));
+        }
+    }
+
+    #[test]
+    fn leaf_existence_proofs_tests() {
+        let mut rng = rand::thread_rng();
+        let mut cmt = CommitmentTree::create();
+
+        let alive_id = rand_fe_with_rng(&mut rng);
+        let ceased_id = rand_fe_with_rng(&mut rng);
+        let fwt_leaves = rand_fe_vec_with_rng(3, &mut rng);
+        let bwtr_leaves = rand_fe_vec_with_rng(2, &mut rng);
+        let cert_leaves = rand_fe_vec_with_rng(4, &mut rng);
+        let csw_leaves = rand_fe_vec_with_rng(5, &mut rng);
+        for leaf in fwt_leaves.iter() {
+            assert!(cmt.add_fwt_leaf(&alive_id, leaf));
+        }
+        for leaf in bwtr_leaves.iter() {
+            assert!(cmt.add_bwtr_leaf(&alive_id, leaf));
+        }
+        for leaf in cert_leaves.iter() {
+            assert!(cmt.add_cert_leaf(&alive_id, leaf));
+        }
+        for leaf in csw_leaves.iter() {
+            assert!(cmt.add_csw_leaf(&ceased_id, leaf));
+        }
+        assert!(cmt.set_scc(&alive_id, &rand_fe_with_rng(&mut rng)));
+
+        let commitment = cmt.get_commitment().unwrap();
+        let cases = vec![
+            (alive_id, ScSubtreeType::FWT, &fwt_leaves),
+            (alive_id, ScSubtreeType::BWTR, &bwtr_leaves),
+            (alive_id, ScSubtreeType::CERT, &cert_leaves),
+            (ceased_id, ScSubtreeType::CSW, &csw_leaves),
+        ];
+        for (sc_id, subtree_type, leaves) in cases {
+            let get_proof = |cmt: &mut CommitmentTree, leaf_index: usize| match subtree_type {
+                ScSubtreeType::FWT => cmt.get_fwt_leaf_existence_proof(&sc_id, leaf_index),
+                ScSubtreeType::BWTR => cmt.get_bwtr_leaf_existence_proof(&sc_id, leaf_index),
+                ScSubtreeType::CERT => cmt.get_cert_leaf_existence_proof(&sc_id, leaf_index),
+                ScSubtreeType::CSW => cmt.get_csw_leaf_existence_proof(&sc_id, leaf_index),
+            };
+
+            // There is no proof for the empty leaves of the subtree
+            assert!(get_proof(&mut cmt, leaves.len()).is_none());
+
+            for (i, leaf) in leaves.iter().enumerate() {
+                let proof = get_proof(&mut cmt, i).unwrap();
+                test_canonical_serialize_deserialize(true, &proof);
+                assert!(CommitmentTree::verify_leaf_inclusion(
+                    &sc_id,
+                    subtree_type,
+                    leaf,
+                    &proof,
+                    &commitment
+                ));
+
+                // Wrong leaf, subtree type, sidechain or commitment must be rejected
+                let other_leaf = leaves[(i + 1) % leaves.len()];
+                assert!(!CommitmentTree::verify_leaf_inclusion(
+                    &sc_id,
+                    subtree_type,
+                    &other_leaf,
+                    &proof,
+                    &commitment
+                ));
+                for other_type in [
+                    ScSubtreeType::FWT,
+                    ScSubtreeType::BWTR,
+                    ScSubtreeType::CERT,
+                    ScSubtreeType::CSW,
+                ]
+                .iter()
+                .filter(|other_type| **other_type != subtree_type)
+                {
+                    assert!(!CommitmentTree::verify_leaf_inclusion(
+                        &sc_id,
+                        *other_type,
+                        leaf,
+                        &proof,
+                        &commitment
+                    ));
+                }
+                let other_sc_id = if sc_id == alive_id {
+                    ceased_id
+                } else {
+                    alive_id
+                };
+                assert!(!CommitmentTree::verify_leaf_inclusion(
+                    &other_sc_id,
+                    subtree_type,
+                    leaf,
+                    &proof,
+                    &commitment
+                ));
+                assert!(!CommitmentTree::verify_leaf_inclusion(
+                    &sc_id,
+                    subtree_type,
+                    leaf,
+                    &proof,
+                    &rand_fe_with_rng(&mut rng)
+                ));
+            }
+        }
+
+        // There are no proofs for the subtrees a sidechain doesn't have, nor for a non-existing sidechain
+        assert!(cmt.get_csw_leaf_existence_proof(&alive_id, 0).is_none());
+        assert!(cmt.get_fwt_leaf_existence_proof(&ceased_id, 0).is_none());
+        assert!(cmt
+            .get_cert_leaf_existence_proof(&rand_fe_with_rng(&mut rng), 0)
+            .is_none());
+    }
+
+    #[test]
+    fn add_cert_from_parts_tests() {
+        let mut rng = rand::thread_rng();
+        let mut cmt = CommitmentTree::create();
+        let mut cmt_from_parts = CommitmentTree::create();
+
+        let sc_id = rand_fe_with_rng(&mut rng);
+        let epoch_number: u32 = rng.gen();
+        let quality: u64 = rng.gen();
+        let bt_list = vec![BackwardTransfer::default(); 10];
+        let custom_fields = rand_fe_vec_with_rng(2, &mut rng);
+        let end_cumulative_sc_tx_commitment_tree_root = rand_fe_with_rng(&mut rng);
+        let btr_fee: u64 = rng.gen();
+        let ft_min_amount: u64 = rng.gen();
+
+        assert!(cmt.add_cert(
+            &sc_id,
+            epoch_number,
+            quality,
+            Some(bt_list.as_slice()),
+            Some(custom_fields.iter().collect()),
+            &end_cumulative_sc_tx_commitment_tree_root,
+            btr_fee,
+            ft_min_amount,
+        ));
+
+        assert!(cmt_from_parts.add_cert_from_parts(
+            &sc_id,
+            epoch_number,
+            quality,
+            get_bt_merkle_root(Some(bt_list.as_slice())).unwrap(),
+            Some(hash_vec(custom_fields).unwrap()),
+            &end_cumulative_sc_tx_commitment_tree_root,
+            btr_fee,
+            ft_min_amount,
+        ));
+
+        // Both ways of adding the certificate must lead to the same leaf and commitment
+        assert_eq!(
+            cmt.get_cert_leaves(&sc_id),
+            cmt_from_parts.get_cert_leaves(&sc_id)
+        );
+        assert_eq!(cmt.get_commitment(), cmt_from_parts.get_commitment());
+    }
+
+    #[test]
+    fn from_leaf_hashes_tests() {
+        let mut rng = rand::thread_rng();
+        let mut cmt = CommitmentTree::create();
+        let mut leaf_sets = BTreeMap::new();
+
+        // Alive sidechains, with and without SCC
+        for i in 0..5 {
+            let sc_id = rand_fe_with_rng(&mut rng);
+            let fwt = rand_fe_vec_with_rng(rng.gen_range(0..10), &mut rng);
+            let bwtr = rand_fe_vec_with_rng(rng.gen_range(0..10), &mut rng);
+            let cert = rand_fe_vec_with_rng(rng.gen_range(1..10), &mut rng);
+            let scc = if i % 2 == 0 {
+                Some(rand_fe_with_rng(&mut rng))
+            } else {
+                None
+            };
+
+            fwt.iter()
+                .for_each(|leaf| assert!(cmt.add_fwt_leaf(&sc_id, leaf)));
+            bwtr.iter()
+                .for_each(|leaf| assert!(cmt.add_bwtr_leaf(&sc_id, leaf)));
+            cert.iter()
+                .for_each(|leaf| assert!(cmt.add_cert_leaf(&sc_id, leaf)));
+            if let Some(scc) = scc.as_ref() {
+                assert!(cmt.set_scc(&sc_id, scc));
+            }
+
+            leaf_sets.insert(
+                sc_id,
+                ScLeafSets::Alive {
+                    fwt,
+                    bwtr,
+                    cert,
+                    scc,
+                },
+            );
+        }
+
+        // Ceased sidechains
+        for _ in 0..3 {
+            let sc_id = rand_fe_with_rng(&mut rng);
+            let csw = rand_fe_vec_with_rng(rng.gen_range(1..10), &mut rng);
+            csw.iter()
+                .for_each(|leaf| assert!(cmt.add_csw_leaf(&sc_id, leaf)));
+            leaf_sets.insert(sc_id, ScLeafSets::Ceased { csw });
+        }
+
+        let mut cmt_from_leaves = CommitmentTree::from_leaf_hashes(leaf_sets.clone()).unwrap();
+        assert_eq!(cmt.get_commitment(), cmt_from_leaves.get_commitment());
+        for sc_id in leaf_sets.keys() {
+            assert_eq!(
+                cmt.get_sc_commitment(sc_id),
+                cmt_from_leaves.get_sc_commitment(sc_id)
+            );
+        }
+
+        // Rebuilt tree can be updated as usual
+        let sc_id = leaf_sets.keys().next().unwrap();
+        let leaf = rand_fe_with_rng(&mut rng);
+        assert_eq!(
+            cmt.add_cert_leaf(sc_id, &leaf),
+            cmt_from_leaves.add_cert_leaf(sc_id, &leaf)
+        );
+        assert_eq!(cmt.get_commitment(), cmt_from_leaves.get_commitment());
+
+        // Empty map leads to an empty CommitmentTree
+        assert_eq!(
+            CommitmentTree::from_leaf_hashes(BTreeMap::new())
+                .unwrap()
+                .get_commitment(),
+            CommitmentTree::create().get_commitment()
+        );
+
+        // Too many leaves in a subtree
+        let mut too_many_leaves = BTreeMap::new();
+        too_many_leaves.insert(
+            rand_fe_with_rng(&mut rng),
+            ScLeafSets::Ceased {
+                csw: vec![FieldElement::one(); pow2(CSW_MT_HEIGHT) + 1],
+            },
+        );
+        assert!(CommitmentTree::from_leaf_hashes(too_many_leaves).is_none());
+
+        // Too many sidechains
+        let too_many_scs = (0..=CMT_MT_CAPACITY)
+            .map(|_| {
+                (
+                    rand_fe_with_rng(&mut rng),
+                    ScLeafSets::Ceased { csw: vec![] },
+                )
+            })
+            .collect::<BTreeMap<_, _>>();
+        assert!(CommitmentTree::from_leaf_hashes(too_many_scs).is_none());
+    }
+
+    #[derive(Debug, PartialEq)]
+    enum ObservedEvent {
+        ScAdded(FieldElement, bool),
+        AliveLeafAdded(FieldElement, &'static str, FieldElement),
+        CswLeafAdded(FieldElement, FieldElement),
+        CommitmentComputed(FieldElement),
+    }
+
+    struct TestObserver {
+        events: Arc<Mutex<Vec<ObservedEvent>>>,
+    }
+
+    impl CommitmentTreeObserver for TestObserver {
+        fn on_sidechain_added(&mut self, sc_id: &FieldElement, ceased: bool) {
+            self.events
+                .lock()
+                .unwrap()
+                .push(ObservedEvent::ScAdded(*sc_id, ceased));
+        }
+
+        fn on_alive_leaf_added(
+            &mut self,
+            sc_id: &FieldElement,
+            subtree_type: &SidechainAliveSubtreeType,
+            leaf: &FieldElement,
+        ) {
+            let subtree_type = match subtree_type {
+                SidechainAliveSubtreeType::FWT => "FWT",
+                SidechainAliveSubtreeType::BWTR => "BWTR",
+                SidechainAliveSubtreeType::CERT => "CERT",
+                SidechainAliveSubtreeType::SCC => "SCC",
+            };
+            self.events
+                .lock()
+                .unwrap()
+                .push(ObservedEvent::AliveLeafAdded(*sc_id, subtree_type, *leaf));
+        }
+
+        fn on_csw_leaf_added(&mut self, sc_id: &FieldElement, leaf: &FieldElement) {
+            self.events
+                .lock()
+                .unwrap()
+                .push(ObservedEvent::CswLeafAdded(*sc_id, *leaf));
+        }
+
+        fn on_commitment_computed(&mut self, commitment: &FieldElement) {
+            self.events
+                .lock()
+                .unwrap()
+                .push(ObservedEvent::CommitmentComputed(*commitment));
+        }
+    }
+
+    #[test]
+    fn observer_tests() {
+        let mut rng = rand::thread_rng();
+        let mut cmt = CommitmentTree::create();
+        let events = Arc::new(Mutex::new(Vec::new()));
+        cmt.set_observer(Box::new(TestObserver {
+            events: events.clone(),
+        }));
+
+        let alive_id = rand_fe_with_rng(&mut rng);
+        let ceased_id = rand_fe_with_rng(&mut rng);
+        let leaves = rand_fe_vec_with_rng(5, &mut rng);
+
+        assert!(cmt.add_fwt_leaf(&alive_id, &leaves[0]));
+        assert!(cmt.set_scc(&alive_id, &leaves[1]));
+        assert!(cmt.add_cert_leaf(&alive_id, &leaves[2]));
+        assert!(cmt.add_csw_leaf(&ceased_id, &leaves[3]));
+        // Failed insertions are not notified
+        assert!(!cmt.add_csw_leaf(&alive_id, &leaves[4]));
+        assert!(!cmt.add_bwtr_leaf(&ceased_id, &leaves[4]));
+        let commitment = cmt.get_commitment().unwrap();
+
+        assert_eq!(
+            *events.lock().unwrap(),
+            vec![
+                ObservedEvent::ScAdded(alive_id, false),
+                ObservedEvent::AliveLeafAdded(alive_id, "FWT", leaves[0]),
+                ObservedEvent::AliveLeafAdded(alive_id, "SCC", leaves[1]),
+                ObservedEvent::AliveLeafAdded(alive_id, "CERT", leaves[2]),
+                ObservedEvent::ScAdded(ceased_id, true),
+                ObservedEvent::CswLeafAdded(ceased_id, leaves[3]),
+                ObservedEvent::CommitmentComputed(commitment),
+            ]
+        );
+
+        // No more notifications after the observer has been removed
+        assert!(cmt.remove_observer().is_some());
+        assert!(cmt.add_bwtr_leaf(&alive_id, &leaves[4]));
+        cmt.get_commitment().unwrap();
+        assert_eq!(events.lock().unwrap().len(), 7);
+    }
+
+    #[test]
+    fn mark_ceased_tests() {
+        let mut rng = rand::thread_rng();
+        let mut cmt = CommitmentTree::create();
+        let alive_id = rand_fe_with_rng(&mut rng);
+        let ceased_id = rand_fe_with_rng(&mut rng);
+        let leaf = rand_fe_with_rng(&mut rng);
+
+        let empty_commitment = cmt.get_commitment().unwrap();
+
+        // Marking a sidechain as ceased adds it to the CommitmentTree
+        assert!(!cmt.is_ceased(&ceased_id));
+        assert!(cmt.mark_ceased(&ceased_id).is_ok());
+        assert!(cmt.is_ceased(&ceased_id));
+        let commitment = cmt.get_commitment().unwrap();
+        assert_ne!(commitment, empty_commitment);
+        assert!(cmt.get_csw_commitment(&ceased_id).is_some());
+
+        // Marking it again has no effect
+        assert!(cmt.mark_ceased(&ceased_id).is_ok());
+        assert_eq!(cmt.get_commitment().unwrap(), commitment);
+
+        // Alive leaves are rejected with a dedicated error
+        for subtree_type in vec![
+            SidechainAliveSubtreeType::FWT,
+            SidechainAliveSubtreeType::BWTR,
+            SidechainAliveSubtreeType::CERT,
+            SidechainAliveSubtreeType::SCC,
+        ] {
+            assert_eq!(
+                cmt.try_add_alive_leaf(&ceased_id, &leaf, subtree_type),
+                Err(CommitmentTreeError::SidechainCeased(ceased_id))
+            );
+        }
+        assert!(!cmt.add_fwt_leaf(&ceased_id, &leaf));
+        assert_eq!(cmt.get_commitment().unwrap(), commitment);
+
+        // CSW can still be added
+        assert!(cmt.add_csw_leaf(&ceased_id, &leaf));
+
+        // Existence and absence proofs are consistent with the ceased state
+        let commitment = cmt.get_commitment().unwrap();
+        let existence_proof = cmt.get_sc_existence_proof(&ceased_id).unwrap();
+        assert!(CommitmentTree::verify_sc_commitment(
+            &cmt.get_sc_commitment(&ceased_id).unwrap(),
+            &existence_proof,
+            &commitment
+        ));
+        assert!(cmt.get_sc_absence_proof(&ceased_id).is_none());
+
+        // An alive sidechain can't be marked as ceased
+        assert!(cmt
+            .try_add_alive_leaf(&alive_id, &leaf, SidechainAliveSubtreeType::FWT)
+            .is_ok());
+        assert_eq!(
+            cmt.mark_ceased(&alive_id),
+            Err(CommitmentTreeError::SidechainAlive(alive_id))
+        );
+        assert!(!cmt.is_ceased(&alive_id));
+    }
+
+    #[test]
+    fn extend_tests() {
+        let mut rng = rand::thread_rng();
+        let sc_ids = rand_fe_vec_with_rng(6, &mut rng);
+        let leaves = rand_fe_vec_with_rng(6, &mut rng);
+
+        let mut cmt = CommitmentTree::create();
+        assert!(cmt.add_fwt_leaf(&sc_ids[0], &leaves[0]));
+        assert!(cmt.add_csw_leaf(&sc_ids[1], &leaves[0]));
+        let mut expected_cmt = CommitmentTree::create();
+        assert!(expected_cmt.add_fwt_leaf(&sc_ids[0], &leaves[0]));
+        assert!(expected_cmt.add_csw_leaf(&sc_ids[1], &leaves[0]));
+
+        // Events for existing and new sidechains are applied in order
+        let events = vec![
+            ScTxEvent::Bwtr {
+                sc_id: sc_ids[0],
+                leaf: leaves[1],
+            },
+            ScTxEvent::Scc {
+                sc_id: sc_ids[2],
+                leaf: leaves[2],
+            },
+            ScTxEvent::Cert {
+                sc_id: sc_ids[2],
+                leaf: leaves[3],
+            },
+            ScTxEvent::Csw {
+                sc_id: sc_ids[1],
+                leaf: leaves[4],
+            },
+            ScTxEvent::Ceased { sc_id: sc_ids[3] },
+            ScTxEvent::Csw {
+                sc_id: sc_ids[3],
+                leaf: leaves[5],
+            },
+            ScTxEvent::Fwt {
+                sc_id: sc_ids[0],
+                leaf: leaves[5],
+            },
+        ];
+        assert_eq!(
+            cmt.extend(events).unwrap(),
+            AddedSummary {
+                fwt: 1,
+                bwtr: 1,
+                cert: 1,
+                scc: 1,
+                csw: 2,
+                ceased: 1,
+                new_sidechains: 2,
+            }
+        );
+
+        assert!(expected_cmt.add_bwtr_leaf(&sc_ids[0], &leaves[1]));
+        assert!(expected_cmt.set_scc(&sc_ids[2], &leaves[2]));
+        assert!(expected_cmt.add_cert_leaf(&sc_ids[2], &leaves[3]));
+        assert!(expected_cmt.add_csw_leaf(&sc_ids[1], &leaves[4]));
+        assert!(expected_cmt.mark_ceased(&sc_ids[3]).is_ok());
+        assert!(expected_cmt.add_csw_leaf(&sc_ids[3], &leaves[5]));
+        assert!(expected_cmt.add_fwt_leaf(&sc_ids[0], &leaves[5]));
+        let commitment = cmt.get_commitment().unwrap();
+        assert_eq!(commitment, expected_cmt.get_commitment().unwrap());
+
+        // Invalid events are reported by index, and nothing is applied
+        let events = vec![
+            ScTxEvent::Fwt {
+                sc_id: sc_ids[4],
+                leaf: leaves[0],
+            },
+            ScTxEvent::Csw {
+                sc_id: sc_ids[0],
+                leaf: leaves[0],
+            },
+        ];
+        assert_eq!(
+            cmt.extend(events),
+            Err((1, CommitmentTreeError::SidechainAlive(sc_ids[0])))
+        );
+
+        let events = vec![
+            ScTxEvent::Ceased { sc_id: sc_ids[4] },
+            ScTxEvent::Scc {
+                sc_id: sc_ids[4],
+                leaf: leaves[0],
+            },
+        ];
+        assert_eq!(
+            cmt.extend(events),
+            Err((1, CommitmentTreeError::SidechainCeased(sc_ids[4])))
+        );
+
+        let events = (0..=pow2(FWT_MT_HEIGHT)).map(|_| ScTxEvent::Fwt {
+            sc_id: sc_ids[5],
+            leaf: leaves[0],
+        });
+        assert_eq!(
+            cmt.extend(events),
+            Err((
+                pow2(FWT_MT_HEIGHT),
+                CommitmentTreeError::SubtreeFull(sc_ids[5])
+            ))
+        );
+
+        assert!(!cmt.is_ceased(&sc_ids[4]));
+        assert!(cmt.get_fwt_leaves(&sc_ids[4]).is_none());
+        assert!(cmt.get_fwt_leaves(&sc_ids[5]).is_none());
+        assert_eq!(cmt.get_commitment().unwrap(), commitment);
+
+        // No events
+        assert_eq!(cmt.extend(vec![]).unwrap(), AddedSummary::default());
+    }
+
+    #[test]
+    fn hash_operations_tests() {
+        let mut rng = rand::thread_rng();
+        let sc_ids = rand_fe_vec_with_rng(3, &mut rng);
+        let leaves = rand_fe_vec_with_rng(5, &mut rng);
+
+        let events = vec![
+            ScTxEvent::Scc {
+                sc_id: sc_ids[0],
+                leaf: leaves[0],
+            },
+            ScTxEvent::Fwt {
+                sc_id: sc_ids[0],
+                leaf: leaves[1],
+            },
+            ScTxEvent::Bwtr {
+                sc_id: sc_ids[0],
+                leaf: leaves[2],
+            },
+            ScTxEvent::Cert {
+                sc_id: sc_ids[0],
+                leaf: leaves[3],
+            },
+            ScTxEvent::Ceased { sc_id: sc_ids[1] },
+            ScTxEvent::Csw {
+                sc_id: sc_ids[1],
+                leaf: leaves[4],
+            },
+            ScTxEvent::Csw {
+                sc_id: sc_ids[2],
+                leaf: leaves[4],
+            },
+        ];
+
+        // The estimate depends only on the type of the event
+        assert_eq!(
+            hash_operations_estimate(&events[0]),
+            hash_operations_estimate(&events[4])
+        );
+        assert_eq!(hash_operations_estimate(&events[4]), CMT_MT_HEIGHT + 1);
+        assert_eq!(
+            hash_operations_estimate(&events[1]),
+            FWT_MT_HEIGHT + CMT_MT_HEIGHT + 1
+        );
+        assert_eq!(
+            hash_operations_estimate(&events[5]),
+            CSW_MT_HEIGHT + CMT_MT_HEIGHT + 1
+        );
+        assert_eq!(
+            hash_operations_estimate(&events[5]),
+            hash_operations_estimate(&events[6])
+        );
+        let expected_hash_operations = events.iter().map(hash_operations_estimate).sum::<usize>();
+
+        // Events applied through extend and one by one are counted the same way
+        let mut cmt = CommitmentTree::create();
+        assert_eq!(cmt.hash_operations(), 0);
+        cmt.extend(events.clone()).unwrap();
+        assert_eq!(cmt.hash_operations(), expected_hash_operations);
+
+        let mut cmt_by_leaf = CommitmentTree::create();
+        assert!(cmt_by_leaf.set_scc(&sc_ids[0], &leaves[0]));
+        assert!(cmt_by_leaf.add_fwt_leaf(&sc_ids[0], &leaves[1]));
+        assert!(cmt_by_leaf.add_bwtr_leaf(&sc_ids[0], &leaves[2]));
+        assert!(cmt_by_leaf.add_cert_leaf(&sc_ids[0], &leaves[3]));
+        assert!(cmt_by_leaf.mark_ceased(&sc_ids[1]).is_ok());
+        assert!(cmt_by_leaf.add_csw_leaf(&sc_ids[1], &leaves[4]));
+        assert!(cmt_by_leaf.add_csw_leaf(&sc_ids[2], &leaves[4]));
+        assert_eq!(cmt_by_leaf.hash_operations(), expected_hash_operations);
+
+        // Rejected changes are not counted
+        assert!(!cmt_by_leaf.add_fwt_leaf(&sc_ids[1], &leaves[0]));
+        assert!(cmt_by_leaf.mark_ceased(&sc_ids[0]).is_err());
+        assert!(cmt_by_leaf.mark_ceased(&sc_ids[1]).is_ok());
+        assert_eq!(cmt_by_leaf.hash_operations(), expected_hash_operations);
+
+        // Counter is reset, e.g. for a new block
+        cmt_by_leaf.reset_hash_operations();
+        assert_eq!(cmt_by_leaf.hash_operations(), 0);
+        assert!(cmt_by_leaf.add_fwt_leaf(&sc_ids[0], &leaves[4]));
+        assert_eq!(
+            cmt_by_leaf.hash_operations(),
+            FWT_MT_HEIGHT + CMT_MT_HEIGHT + 1
+        );
+
+        // Under a non-default config, events are counted according to its heights
+        let config = CommitmentTreeConfig {
+            cmt_height: 3,
+            fwt_height: 2,
+            bwtr_height: 1,
+            cert_height: 4,
+            csw_height: 5,
+        };
+        assert_eq!(
+            hash_operations_estimate_with_config(&events[0], &config),
+            3 + 1
+        );
+        assert_eq!(
+            hash_operations_estimate_with_config(&events[1], &config),
+            2 + 3 + 1
+        );
+        assert_eq!(
+            hash_operations_estimate_with_config(&events[5], &config),
+            5 + 3 + 1
+        );
+        let expected_hash_operations = events
+            .iter()
+            .map(|event| hash_operations_estimate_with_config(event, &config))
+            .sum::<usize>();
+
+        let mut cmt = CommitmentTree::create_with_config(config).unwrap();
+        cmt.extend(events).unwrap();
+        assert_eq!(cmt.hash_operations(), expected_hash_operations);
+
+        let mut cmt_by_leaf = CommitmentTree::create_with_config(config).unwrap();
+        assert!(cmt_by_leaf.set_scc(&sc_ids[0], &leaves[0]));
+        assert!(cmt_by_leaf.add_fwt_leaf(&sc_ids[0], &leaves[1]));
+        assert!(cmt_by_leaf.add_bwtr_leaf(&sc_ids[0], &leaves[2]));
+        assert!(cmt_by_leaf.add_cert_leaf(&sc_ids[0], &leaves[3]));
+        assert!(cmt_by_leaf.mark_ceased(&sc_ids[1]).is_ok());
+        assert!(cmt_by_leaf.add_csw_leaf(&sc_ids[1], &leaves[4]));
+        assert!(cmt_by_leaf.add_csw_leaf(&sc_ids[2], &leaves[4]));
+        assert_eq!(cmt_by_leaf.hash_operations(), expected_hash_operations);
+    }
+
+    #[test]
+    fn validate_invariants_tests() {
+        let mut rng = rand::thread_rng();
+        let alive_id = rand_fe_with_rng(&mut rng);
+        let ceased_id = rand_fe_with_rng(&mut rng);
+
+        let mut cmt = CommitmentTree::create();
+        assert!(cmt.validate_invariants().is_ok());
+
+        assert!(cmt.add_fwt_leaf(&alive_id, &rand_fe_with_rng(&mut rng)));
+        assert!(cmt.add_csw_leaf(&ceased_id, &rand_fe_with_rng(&mut rng)));
+        assert!(cmt.validate_invariants().is_ok());
+
+        // Sidechain both alive and ceased
+        let mut invalid_cmt = CommitmentTree::create();
+        invalid_cmt
+            .alive_sc_trees
+            .push(SidechainTreeAlive::create(&alive_id).unwrap());
+        invalid_cmt
+            .ceased_sc_trees
+            .push(SidechainTreeCeased::create(&alive_id).unwrap());
+        assert_eq!(
+            invalid_cmt.validate_invariants(),
+            Err(CommitmentTreeError::SidechainAliveAndCeased(alive_id))
+        );
+
+        // Duplicated sidechains
+        let mut invalid_cmt = CommitmentTree::create();
+        for _ in 0..2 {
+            invalid_cmt
+                .ceased_sc_trees
+                .push(SidechainTreeCeased::create(&ceased_id).unwrap());
+        }
+        assert_eq!(
+            invalid_cmt.validate_invariants(),
+            Err(CommitmentTreeError::DuplicateSidechain(ceased_id))
+        );
+
+        // Too many sidechains
+        let mut invalid_cmt = CommitmentTree::create();
+        for _ in 0..=CMT_MT_CAPACITY {
+            invalid_cmt
+                .ceased_sc_trees
+                .push(SidechainTreeCeased::create(&rand_fe_with_rng(&mut rng)).unwrap());
+        }
+        assert_eq!(
+            invalid_cmt.validate_invariants(),
+            Err(CommitmentTreeError::TooManySidechains(CMT_MT_CAPACITY + 1))
+        );
+
+        // Forged cached SC-commitment: the serialized cache ends with the SC-commitment of the last sidechain
+        let commitment = cmt.get_commitment().unwrap();
+        let mut bytes = serialize_to_buffer(&cmt, None).unwrap();
+        let forged_sc_commitment = serialize_to_buffer(&rand_fe_with_rng(&mut rng), None).unwrap();
+        let cache_end = bytes.len() - forged_sc_commitment.len();
+        bytes[cache_end..].copy_from_slice(&forged_sc_commitment);
+        let mut invalid_cmt = CommitmentTree::deserialize(bytes.as_slice()).unwrap();
+        assert!(invalid_cmt.incremental_commitments_tree.is_some());
+        assert_ne!(invalid_cmt.get_commitment(), Some(commitment));
+        assert!(invalid_cmt.validate_invariants().is_ok());
+        assert_eq!(invalid_cmt.get_commitment(), Some(commitment));
+    }
+
+    #[test]
+    fn max_sidechains_tests() {
+        let mut rng = rand::thread_rng();
+        let max_sidechains = 3;
+
+        assert!(matches!(
+            CommitmentTree::create_with_params(CMT_MT_CAPACITY + 1),
+            Err(CommitmentTreeError::TooManySidechains(n)) if n == CMT_MT_CAPACITY + 1
+        ));
+        assert_eq!(
+            CommitmentTree::create_with_params(CMT_MT_CAPACITY)
+                .unwrap()
+                .max_sidechains(),
+            CommitmentTree::create().max_sidechains()
+        );
+
+        let mut cmt = CommitmentTree::create_with_params(max_sidechains).unwrap();
+        assert_eq!(cmt.max_sidechains(), max_sidechains);
+        let sc_ids = rand_fe_vec_with_rng(max_sidechains, &mut rng);
+        assert!(cmt.add_fwt_leaf(&sc_ids[0], &rand_fe_with_rng(&mut rng)));
+        assert!(cmt.add_csw_leaf(&sc_ids[1], &rand_fe_with_rng(&mut rng)));
+        cmt.mark_ceased(&sc_ids[2]).unwrap();
+
+        // No more sidechains can be added
+        let new_id = rand_fe_with_rng(&mut rng);
+        let leaf = rand_fe_with_rng(&mut rng);
+        assert!(!cmt.add_fwt_leaf(&new_id, &leaf));
+        assert!(!cmt.add_csw_leaf(&new_id, &leaf));
+        assert_eq!(
+            cmt.try_add_alive_leaf(&new_id, &leaf, SidechainAliveSubtreeType::CERT),
+            Err(CommitmentTreeError::MaxSidechainsReached(max_sidechains))
+        );
+        assert_eq!(
+            cmt.mark_ceased(&new_id),
+            Err(CommitmentTreeError::MaxSidechainsReached(max_sidechains))
+        );
+        assert_eq!(
+            cmt.extend(vec![ScTxEvent::Csw {
+                sc_id: new_id,
+                leaf
+            }]),
+            Err((0, CommitmentTreeError::MaxSidechainsReached(max_sidechains)))
+        );
+
+        // Sidechains already contained are still updatable
+        assert!(cmt.add_bwtr_leaf(&sc_ids[0], &leaf));
+        assert!(cmt.add_csw_leaf(&sc_ids[2], &leaf));
+        assert!(cmt.validate_invariants().is_ok());
+
+        // A full CommitmentTree without a lower cap reports the usual error
+        let mut full_cmt = CommitmentTree::create();
+        for _ in 0..CMT_MT_CAPACITY {
+            full_cmt.mark_ceased(&rand_fe_with_rng(&mut rng)).unwrap();
+        }
+        assert_eq!(
+            full_cmt.mark_ceased(&new_id),
+            Err(CommitmentTreeError::CommitmentTreeFull)
+        );
+    }
+
+    #[test]
+    fn try_add_tests() {
+        let mut rng = rand::thread_rng();
+        let max_sidechains = 2;
+        let mut cmt = CommitmentTree::create_with_params(max_sidechains).unwrap();
+        let alive_id = rand_fe_with_rng(&mut rng);
+        let ceased_id = rand_fe_with_rng(&mut rng);
+        let leaf = rand_fe_with_rng(&mut rng);
+
+        // Same leaves as the ones added by the bool-returning methods
+        let mut expected_cmt = CommitmentTree::create();
+        let pub_key: [u8; 32] = rng.gen();
+        let mc_return_address: [u8; 20] = rng.gen();
+        let tx_hash: [u8; 32] = rng.gen();
+        let out_idx: u32 = rng.gen();
+        assert!(cmt
+            .try_add_fwt(
+                &alive_id,
+                10,
+                &pub_key,
+                &mc_return_address,
+                &tx_hash,
+                out_idx
+            )
+            .is_ok());
+        assert!(expected_cmt.add_fwt(
+            &alive_id,
+            10,
+            &pub_key,
+            &mc_return_address,
+            &tx_hash,
+            out_idx
+        ));
+        let nullifier = rand_fe_with_rng(&mut rng);
+        let mc_pk_hash = [1u8; MC_PK_SIZE];
+        assert!(cmt
+            .try_add_csw(&ceased_id, 10, &nullifier, &mc_pk_hash)
+            .is_ok());
+        assert!(expected_cmt.add_csw(&ceased_id, 10, &nullifier, &mc_pk_hash));
+        assert_eq!(cmt.get_commitment(), expected_cmt.get_commitment());
+
+        // The reason of the failure is reported
+        assert_eq!(
+            cmt.try_add_csw_leaf(&alive_id, &leaf),
+            Err(CommitmentTreeError::SidechainAlive(alive_id))
+        );
+        assert_eq!(
+            cmt.try_add_cert_leaf(&ceased_id, &leaf),
+            Err(CommitmentTreeError::SidechainCeased(ceased_id))
+        );
+        assert_eq!(
+            cmt.try_set_scc(&ceased_id, &leaf),
+            Err(CommitmentTreeError::SidechainCeased(ceased_id))
+        );
+        let new_id = rand_fe_with_rng(&mut rng);
+        assert_eq!(
+            cmt.try_add_bwtr_leaf(&new_id, &leaf),
+            Err(CommitmentTreeError::MaxSidechainsReached(max_sidechains))
+        );
+        assert_eq!(
+            cmt.try_add_csw_leaf(&new_id, &leaf),
+            Err(CommitmentTreeError::MaxSidechainsReached(max_sidechains))
+        );
+
+        // Sidechains already contained in a full CommitmentTree are still updatable, until their subtrees are full
+        for _ in 1..pow2(CSW_MT_HEIGHT) {
+            assert!(cmt.try_add_csw_leaf(&ceased_id, &leaf).is_ok());
+        }
+        assert_eq!(
+            cmt.try_add_csw_leaf(&ceased_id, &leaf),
+            Err(CommitmentTreeError::SubtreeFull(ceased_id))
+        );
+        assert!(!cmt.add_csw_leaf(&ceased_id, &leaf));
+        assert!(cmt.try_add_fwt_leaf(&alive_id, &leaf).is_ok());
+        assert!(cmt.validate_invariants().is_ok());
+    }
+
+    #[test]
+    fn stats_tests() {
+        let mut rng = rand::thread_rng();
+        let alive_id = rand_fe_with_rng(&mut rng);
+        let ceased_id = rand_fe_with_rng(&mut rng);
+
+        let mut cmt = CommitmentTree::create();
+        assert_eq!(cmt.stats(), CommitmentTreeStats::default());
+        assert!(cmt.add_fwt_leaf(&alive_id, &rand_fe_with_rng(&mut rng)));
+        assert!(cmt.add_csw_leaf(&ceased_id, &rand_fe_with_rng(&mut rng)));
+        assert_eq!(cmt.stats(), CommitmentTreeStats::default());
+
+        // The subtrees are finalized once, then the cached SC-commitments are reused
+        let commitment = cmt.get_commitment();
+        assert!(commitment.is_some());
+        let stats = cmt.stats();
+        assert_eq!(stats.commitments_tree_builds, 0);
+        assert_eq!(stats.subtree_finalizations, 3 + 1);
+        assert_eq!(cmt.get_commitment(), commitment);
+        assert_eq!(cmt.stats(), stats);
+
+        // The Commitment-MT is built once, until a change
+        assert!(cmt.get_sc_existence_proof(&alive_id).is_some());
+        assert!(cmt.get_sc_existence_proof(&ceased_id).is_some());
+        assert_eq!(cmt.stats().commitments_tree_builds, 1);
+        assert_eq!(
+            cmt.stats().subtree_finalizations,
+            stats.subtree_finalizations
+        );
+
+        // Only the subtrees of the changed sidechain are finalized again
+        assert!(cmt.add_csw_leaf(&ceased_id, &rand_fe_with_rng(&mut rng)));
+        assert!(cmt.get_sc_existence_proof(&alive_id).is_some());
+        assert_eq!(cmt.stats().commitments_tree_builds, 2);
+        assert_eq!(
+            cmt.stats().subtree_finalizations,
+            stats.subtree_finalizations + 1
+        );
+
+        // Merkle Paths and subtree roots are computed on request
+        assert!(cmt.get_fwt_merkle_path(&alive_id, 0).is_some());
+        assert!(cmt.get_csw_commitment(&ceased_id).is_some());
+        assert_eq!(
+            cmt.stats().subtree_finalizations,
+            stats.subtree_finalizations + 3
+        );
+    }
+
+    #[test]
+    fn remaining_capacity_tests() {
+        let mut rng = rand::thread_rng();
+        let alive_id = rand_fe_with_rng(&mut rng);
+        let ceased_id = rand_fe_with_rng(&mut rng);
+        let new_id = rand_fe_with_rng(&mut rng);
+        let leaf = rand_fe_with_rng(&mut rng);
+        let mut cmt = CommitmentTree::create_with_params(2).unwrap();
+
+        // Any sidechain can still be added
+        assert!(cmt.get_csw_count(&ceased_id).is_none());
+        assert_eq!(
+            cmt.remaining_capacity(&new_id, ScSubtreeType::FWT),
+            pow2(FWT_MT_HEIGHT)
+        );
+        assert_eq!(
+            cmt.remaining_capacity(&new_id, ScSubtreeType::CSW),
+            pow2(CSW_MT_HEIGHT)
+        );
+
+        assert!(cmt.add_fwt_leaf(&alive_id, &leaf));
+        assert!(cmt.add_csw_leaf(&ceased_id, &leaf));
+        assert!(cmt.add_csw_leaf(&ceased_id, &leaf));
+        assert_eq!(cmt.get_csw_count(&ceased_id), Some(2));
+        assert!(cmt.get_csw_count(&alive_id).is_none());
+        assert_eq!(
+            cmt.remaining_capacity(&alive_id, ScSubtreeType::FWT),
+            pow2(FWT_MT_HEIGHT) - 1
+        );
+        assert_eq!(
+            cmt.remaining_capacity(&ceased_id, ScSubtreeType::CSW),
+            pow2(CSW_MT_HEIGHT) - 2
+        );
+
+        // Subtrees the sidechains can't have
+        assert_eq!(cmt.remaining_capacity(&alive_id, ScSubtreeType::CSW), 0);
+        assert_eq!(cmt.remaining_capacity(&ceased_id, ScSubtreeType::CERT), 0);
+
+        // No more sidechains can be added
+        assert_eq!(cmt.remaining_capacity(&new_id, ScSubtreeType::BWTR), 0);
+        assert!(!cmt.add_bwtr_leaf(&new_id, &leaf));
+
+        // Adding as many leaves as the remaining capacity fills the subtree
+        for _ in 0..cmt.remaining_capacity(&ceased_id, ScSubtreeType::CSW) {
+            assert!(cmt.add_csw_leaf(&ceased_id, &leaf));
+        }
+        assert_eq!(cmt.remaining_capacity(&ceased_id, ScSubtreeType::CSW), 0);
+        assert!(!cmt.add_csw_leaf(&ceased_id, &leaf));
+    }
+
+    #[test]
+    fn config_tests() {
+        let mut rng = rand::thread_rng();
+        let config = CommitmentTreeConfig {
+            cmt_height: 2,
+            fwt_height: 1,
+            bwtr_height: 2,
+            cert_height: 3,
+            csw_height: 2,
+        };
+        assert!(matches!(
+            CommitmentTree::create_with_config(CommitmentTreeConfig {
+                cmt_height: 0,
+                ..config
+            }),
+            Err(CommitmentTreeError::InvalidConfig(_))
+        ));
+
+        // Proofs of an empty CommitmentTree
+        let absent_id = min_fe();
+        let mut cmt = CommitmentTree::create_with_config(config).unwrap();
+        assert_eq!(cmt.config(), config);
+        assert_eq!(cmt.max_sidechains(), pow2(config.cmt_height));
+        let commitment = cmt.get_commitment().unwrap();
+        assert_ne!(Some(commitment), CommitmentTree::create().get_commitment());
+        let proof = cmt.get_sc_absence_proof(&absent_id).unwrap();
+        assert!(CommitmentTree::verify_sc_absence_with_config(
+            &absent_id,
+            &proof,
+            &commitment,
+            &config
+        ));
+        assert!(!CommitmentTree::verify_sc_absence(
+            &absent_id,
+            &proof,
+            &commitment
+        ));
+
+        // Subtrees have the capacity of the config
+        let sc_ids = sorted_sc_ids_with_rng(4, cmt.ordering(), &mut rng);
+        let leaf = rand_fe_with_rng(&mut rng);
+        for _ in 0..pow2(config.fwt_height) {
+            assert!(cmt.add_fwt_leaf(&sc_ids[0], &leaf));
+        }
+        assert_eq!(
+            cmt.try_add_fwt_leaf(&sc_ids[0], &leaf),
+            Err(CommitmentTreeError::SubtreeFull(sc_ids[0]))
+        );
+        for _ in 0..pow2(config.csw_height) {
+            assert!(cmt.add_csw_leaf(&sc_ids[1], &leaf));
+        }
+        assert!(!cmt.add_csw_leaf(&sc_ids[1], &leaf));
+        assert!(cmt.add_cert_leaf(&sc_ids[2], &leaf));
+        assert_eq!(
+            cmt.remaining_capacity(&sc_ids[2], ScSubtreeType::CERT),
+            pow2(config.cert_height) - 1
+        );
+        assert_eq!(
+            cmt.remaining_capacity(&sc_ids[3], ScSubtreeType::BWTR),
+            pow2(config.bwtr_height)
+        );
+
+        // Proofs must be verified with the same config
+        let commitment = cmt.get_commitment().unwrap();
+        let sc_commitment = cmt.get_sc_commitment(&sc_ids[0]).unwrap();
+        let proof = cmt.get_sc_existence_proof(&sc_ids[0]).unwrap();
+        assert!(CommitmentTree::verify_sc_commitment_with_config(
+            &sc_commitment,
+            &proof,
+            &commitment,
+            &config
+        ));
+        assert!(!CommitmentTree::verify_sc_commitment(
+            &sc_commitment,
+            &proof,
+            &commitment
+        ));
+
+        let proof = cmt.get_fwt_leaf_existence_proof(&sc_ids[0], 1).unwrap();
+        assert!(CommitmentTree::verify_leaf_inclusion_with_config(
+            &sc_ids[0],
+            ScSubtreeType::FWT,
+            &leaf,
+            &proof,
+            &commitment,
+            &config
+        ));
+        assert!(!CommitmentTree::verify_leaf_inclusion(
+            &sc_ids[0],
+            ScSubtreeType::FWT,
+            &leaf,
+            &proof,
+            &commitment
+        ));
+
+        let proof = cmt.get_csw_leaf_proof(&sc_ids[1], 0).unwrap();
+        assert!(CommitmentTree::verify_csw_leaf_with_config(
+            &sc_ids[1],
+            &leaf,
+            &proof,
+            &commitment,
+            &config
+        ));
+        assert!(!CommitmentTree::verify_csw_leaf(
+            &sc_ids[1],
+            &leaf,
+            &proof,
+            &commitment
+        ));
+
+        let proof = cmt.get_sc_absence_proof(&absent_id).unwrap();
+        assert!(CommitmentTree::verify_sc_absence_with_config(
+            &absent_id,
+            &proof,
+            &commitment,
+            &config
+        ));
+        assert!(!CommitmentTree::verify_sc_absence(
+            &absent_id,
+            &proof,
+            &commitment
+        ));
+
+        // The config is part of the serialized state
+        let mut bytes = Vec::new();
+        CanonicalSerialize::serialize(&cmt, &mut bytes).unwrap();
+        let mut restored_cmt = CommitmentTree::deserialize(bytes.as_slice()).unwrap();
+        assert_eq!(restored_cmt.config(), config);
+        assert!(restored_cmt.validate_invariants().is_ok());
+        assert_eq!(restored_cmt.get_commitment(), Some(commitment));
+        assert!(!restored_cmt.add_fwt_leaf(&sc_ids[0], &leaf));
 
-    // Creates a sequence of FieldElements with values [0, 1, 2, 3, 4]
-    fn get_fe_0_4() -> Vec<FieldElement> {
-        let fe0 = FieldElement::zero();
-        let fe1 = FieldElement::one();
-        let fe2 = FieldElement::one() + fe1;
-        let fe3 = FieldElement::one() + fe2;
-        let fe4 = FieldElement::one() + fe3;
-        vec![fe0, fe1, fe2, fe3, fe4]
+        // The Commitment-MT has the capacity of the config
+        assert!(cmt.add_bwtr_leaf(&sc_ids[3], &leaf));
+        assert_eq!(
+            cmt.try_add_bwtr_leaf(&absent_id, &leaf),
+            Err(CommitmentTreeError::CommitmentTreeFull)
+        );
     }
 
     #[test]
-    fn commitment_tree_tests() {
-        let mut cmt = CommitmentTree::create();
-        let fe = get_fe_0_4();
-        // Initial order of IDs is reversed, i.e. vec![3, 2, 1, 0] to test SCIDs-ordering functionality
-        let sc_ids = fe.iter().take(4).rev().collect::<Vec<_>>();
-        let non_existing_sc_id = &fe[4];
-
-        // Initial commitment_tree value of an empty CMT
-        let empty_comm = cmt.get_commitment().unwrap();
+    fn leaves_iterators_tests() {
+        let mut rng = rand::thread_rng();
+        let alive_id = rand_fe_with_rng(&mut rng);
+        let ceased_id = rand_fe_with_rng(&mut rng);
+        let fwts = rand_fe_vec_with_rng(5, &mut rng);
+        let certs = rand_fe_vec_with_rng(2, &mut rng);
 
-        // Initial SCT commitments are empty due to absence of such SCTs
-        assert_eq!(cmt.get_fwt_commitment(sc_ids[0]), None);
-        assert_eq!(cmt.get_bwtr_commitment(sc_ids[1]), None);
-        assert_eq!(cmt.get_cert_commitment(sc_ids[2]), None);
-        assert_eq!(cmt.get_csw_commitment(sc_ids[3]), None);
+        let mut cmt = CommitmentTree::create();
+        assert!(cmt.iter_fwt_leaves(&alive_id).is_none());
+        assert!(cmt.get_fwt_count(&alive_id).is_none());
 
-        // Merkle path to unknown SC Commitment must be absent
-        assert_eq!(cmt.get_sc_commitment_merkle_path(sc_ids[0]), None);
+        assert!(fwts.iter().all(|fwt| cmt.add_fwt_leaf(&alive_id, fwt)));
+        assert!(certs.iter().all(|cert| cmt.add_cert_leaf(&alive_id, cert)));
+        assert!(cmt.add_csw_leaf(&ceased_id, &rand_fe_with_rng(&mut rng)));
 
-        // Set values in corresponding subtrees with transparent creation of the SCTs with specified IDs
-        assert!(cmt.add_fwt_leaf(sc_ids[0], &fe[1]));
-        assert!(cmt.add_bwtr_leaf(sc_ids[1], &fe[2]));
-        assert!(cmt.add_cert_leaf(sc_ids[2], &fe[3]));
-        assert!(cmt.add_csw_leaf(sc_ids[3], &fe[4]));
+        assert!(cmt.iter_fwt_leaves(&alive_id).unwrap().eq(fwts.iter()));
+        assert_eq!(cmt.iter_bwtr_leaves(&alive_id).unwrap().count(), 0);
+        assert_eq!(
+            cmt.iter_cert_leaves(&alive_id)
+                .unwrap()
+                .take(1)
+                .collect::<Vec<_>>(),
+            vec![&certs[0]]
+        );
+        assert_eq!(cmt.get_fwt_count(&alive_id), Some(fwts.len()));
+        assert_eq!(cmt.get_bwtr_count(&alive_id), Some(0));
+        assert_eq!(cmt.get_cert_count(&alive_id), Some(certs.len()));
 
-        // All updated subtrees should have the same leaves as what have been added
-        assert_eq!(cmt.get_fwt_leaves(sc_ids[0]).unwrap(), vec![fe[1]]);
-        assert_eq!(cmt.get_bwtr_leaves(sc_ids[1]).unwrap(), vec![fe[2]]);
-        assert_eq!(cmt.get_cert_leaves(sc_ids[2]).unwrap(), vec![fe[3]]);
+        // Same leaves as the cloning getters
+        let fwt_leaves = cmt.get_fwt_leaves(&alive_id).unwrap();
+        assert!(cmt
+            .iter_fwt_leaves(&alive_id)
+            .unwrap()
+            .eq(fwt_leaves.iter()));
 
-        // All non-updated subtrees should have empty list of leaves
-        assert!(cmt.get_fwt_leaves(sc_ids[1]).unwrap().is_empty());
-        assert!(cmt.get_bwtr_leaves(sc_ids[2]).unwrap().is_empty());
-        assert!(cmt.get_cert_leaves(sc_ids[0]).unwrap().is_empty());
+        // Same CSW leaves as the cloning getter
+        let csw_leaves = cmt.get_csw_leaves(&ceased_id).unwrap();
+        assert_eq!(csw_leaves.len(), 1);
+        assert!(cmt
+            .iter_csw_leaves(&ceased_id)
+            .unwrap()
+            .eq(csw_leaves.iter()));
 
-        // There should not be any leaves for nonexisting subtrees
-        assert!(cmt.get_fwt_leaves(non_existing_sc_id).is_none());
-        assert!(cmt.get_bwtr_leaves(non_existing_sc_id).is_none());
-        assert!(cmt.get_cert_leaves(non_existing_sc_id).is_none());
+        // Ceased sidechains have no alive subtrees, and vice versa
+        assert!(cmt.iter_cert_leaves(&ceased_id).is_none());
+        assert!(cmt.get_cert_count(&ceased_id).is_none());
+        assert!(cmt.iter_csw_leaves(&alive_id).is_none());
+        assert!(cmt.get_csw_leaves(&alive_id).is_none());
+    }
 
-        // All updated subtrees should have non-empty subtrees roots
-        assert!(cmt.get_fwt_commitment(sc_ids[0]).is_some());
-        assert!(cmt.get_bwtr_commitment(sc_ids[1]).is_some());
-        assert!(cmt.get_cert_commitment(sc_ids[2]).is_some());
-        assert!(cmt.get_csw_commitment(sc_ids[3]).is_some());
+    #[test]
+    fn cert_linear_accumulator_tests() {
+        let mut rng = rand::thread_rng();
+        let sc_ids = rand_fe_vec_with_rng(2, &mut rng);
+        let certs = rand_fe_vec_with_rng(4, &mut rng);
 
-        // There should not be any roots for nonexisting subtrees
-        assert!(cmt.get_fwt_commitment(non_existing_sc_id).is_none());
-        assert!(cmt.get_bwtr_commitment(non_existing_sc_id).is_none());
-        assert!(cmt.get_cert_commitment(non_existing_sc_id).is_none());
-        assert!(cmt.get_csw_commitment(non_existing_sc_id).is_none());
+        let mut cmt = CommitmentTree::create();
+        let mut cmt_without_acc = CommitmentTree::create();
+        assert!(!cmt.is_cert_linear_accumulator_enabled());
 
-        // All updated SCTs should have non-empty commitments
-        sc_ids
-            .clone()
-            .into_iter()
-            .for_each(|sc_id| assert!(cmt.get_sc_commitment(sc_id).is_some()));
+        // Certificates added before enabling the hash-chain are absorbed when it's enabled
+        for cmt in [&mut cmt, &mut cmt_without_acc].iter_mut() {
+            assert!(cmt.add_cert_leaf(&sc_ids[0], &certs[0]));
+        }
+        assert!(cmt.get_cert_linear_accumulator(&sc_ids[0]).is_none());
+        cmt.set_cert_linear_accumulator(true).unwrap();
+        assert!(cmt.is_cert_linear_accumulator_enabled());
+        assert_eq!(
+            cmt.get_cert_linear_accumulator(&sc_ids[0]),
+            Some(&CertLinearAccumulator::from_leaves(certs[..1].iter()).unwrap())
+        );
 
-        // There is no SCT for ID which wasn't added during previous calls
-        assert!(cmt.get_sc_commitment(non_existing_sc_id).is_none());
+        // Both layouts are produced from the same leaves, for existing and new sidechains
+        for cmt in [&mut cmt, &mut cmt_without_acc].iter_mut() {
+            assert!(cmt.add_cert_leaf(&sc_ids[0], &certs[1]));
+            assert!(cmt.add_cert_leaf(&sc_ids[1], &certs[2]));
+            assert!(cmt.add_cert_leaf(&sc_ids[1], &certs[3]));
+        }
+        for sc_id in sc_ids.iter() {
+            let cert_leaves = cmt.get_cert_leaves(sc_id).unwrap();
+            let acc = cmt.get_cert_linear_accumulator(sc_id).unwrap();
+            assert_eq!(acc.num_leaves(), 2);
+            assert_eq!(
+                acc,
+                &CertLinearAccumulator::from_leaves(cert_leaves.iter()).unwrap()
+            );
+            assert_eq!(cert_leaves, cmt_without_acc.get_cert_leaves(sc_id).unwrap());
+        }
 
-        // No CSW data can be added to any SCT
-        assert!(!cmt.add_csw_leaf(sc_ids[0], &fe[1]));
-        assert!(!cmt.add_csw_leaf(sc_ids[1], &fe[1]));
-        assert!(!cmt.add_csw_leaf(sc_ids[2], &fe[1]));
+        // The hash-chain doesn't affect the commitment
+        assert_eq!(cmt.get_commitment(), cmt_without_acc.get_commitment());
 
-        // No SCT-related data can be added to SCTC
-        assert!(!cmt.add_fwt_leaf(sc_ids[3], &fe[1]));
-        assert!(!cmt.add_bwtr_leaf(sc_ids[3], &fe[1]));
-        assert!(!cmt.add_cert_leaf(sc_ids[3], &fe[1]));
+        // No hash-chain for ceased or not existing sidechains
+        let ceased_id = rand_fe_with_rng(&mut rng);
+        assert!(cmt.mark_ceased(&ceased_id).is_ok());
+        assert!(cmt.get_cert_linear_accumulator(&ceased_id).is_none());
+        assert!(cmt
+            .get_cert_linear_accumulator(&rand_fe_with_rng(&mut rng))
+            .is_none());
 
-        // Updating SCC in the first SCT and checking that commitment of this tree also has been updated
-        let comm_without_scc = cmt.get_sc_commitment(sc_ids[0]);
-        cmt.set_scc(sc_ids[0], &fe[1]);
-        assert_eq!(cmt.get_scc(sc_ids[0]).unwrap(), fe[1]);
-        assert_ne!(comm_without_scc, cmt.get_sc_commitment(sc_ids[0]));
+        // Disabling drops the hash-chains
+        cmt.set_cert_linear_accumulator(false).unwrap();
+        assert!(sc_ids
+            .iter()
+            .all(|sc_id| cmt.get_cert_linear_accumulator(sc_id).is_none()));
+    }
 
-        // Commitment of the updated CMT has non-empty value
-        assert_ne!(empty_comm, cmt.get_commitment().unwrap());
+    #[test]
+    fn cert_subtree_snapshot_tests() {
+        let mut rng = rand::thread_rng();
+        let sc_id = rand_fe_with_rng(&mut rng);
+        let certs = rand_fe_vec_with_rng(5, &mut rng);
 
-        // There is no existence-proof for a non-existing SC-ID
-        assert!(cmt.get_sc_existence_proof(non_existing_sc_id).is_none());
+        let mut cmt = CommitmentTree::create();
+        assert!(cmt.snapshot_cert_subtree(&sc_id).is_none());
+        assert!(cmt.add_fwt_leaf(&sc_id, &rand_fe_with_rng(&mut rng)));
 
-        // Creating a valid existence proof
-        let existence_proof = cmt.get_sc_existence_proof(sc_ids[0]);
-        assert!(existence_proof.is_some());
+        // Snapshot of the empty subtree
+        let empty_snapshot = cmt.snapshot_cert_subtree(&sc_id).unwrap();
+        assert_eq!(empty_snapshot.num_leaves, 0);
+        assert_eq!(Some(empty_snapshot.root), cmt.get_cert_commitment(&sc_id));
 
-        test_canonical_serialize_deserialize(true, &existence_proof);
+        assert!(certs[..3]
+            .iter()
+            .all(|cert| cmt.add_cert_leaf(&sc_id, cert)));
+        let snapshot = cmt.snapshot_cert_subtree(&sc_id).unwrap();
+        assert_eq!(snapshot.sc_id, sc_id);
+        assert_eq!(snapshot.num_leaves, 3);
+        assert_eq!(snapshot.leaves, certs[..3].to_vec());
+        assert_eq!(Some(snapshot.root), cmt.get_cert_commitment(&sc_id));
+        test_canonical_serialize_deserialize(true, &snapshot);
 
-        // Verification of a valid deserialized existence-proof
-        assert!(CommitmentTree::verify_sc_commitment(
-            cmt.get_sc_commitment(sc_ids[0]).as_ref().unwrap(),
-            &existence_proof.unwrap(),
-            cmt.get_commitment().as_ref().unwrap()
-        ));
+        // Continue appending into a tree restored from the snapshot
+        let mut restored_cmt = CommitmentTree::create();
+        restored_cmt.restore_cert_subtree(&snapshot).unwrap();
+        assert_eq!(
+            restored_cmt.get_cert_commitment(&sc_id),
+            Some(snapshot.root)
+        );
+        for cmt in [&mut cmt, &mut restored_cmt].iter_mut() {
+            assert!(certs[3..]
+                .iter()
+                .all(|cert| cmt.add_cert_leaf(&sc_id, cert)));
+        }
+        assert_eq!(
+            restored_cmt.get_cert_commitment(&sc_id),
+            cmt.get_cert_commitment(&sc_id)
+        );
+        assert_eq!(restored_cmt.get_cert_leaves(&sc_id), Some(certs.clone()));
 
-        // Verify merkle path to sc commitment
-        let commitment = cmt.get_commitment().unwrap();
-        sc_ids.clone().into_iter().for_each(|sc_id| {
-            let sc_commitment = cmt.get_sc_commitment(sc_id).unwrap();
-            let sc_commitment_merkle_path = cmt.get_sc_commitment_merkle_path(sc_id).unwrap();
-            assert!(mht::verify_ginger_merkle_path_without_length_check(
-                &sc_commitment_merkle_path,
-                &sc_commitment,
-                &commitment
-            ));
-        });
+        // Restoring replaces the current Certificates
+        restored_cmt.restore_cert_subtree(&empty_snapshot).unwrap();
+        assert_eq!(restored_cmt.get_cert_count(&sc_id), Some(0));
 
-        // Verify merkle path to FT leaf
-        assert!(mht::verify_ginger_merkle_path_without_length_check(
-            &cmt.get_fwt_merkle_path(sc_ids[0], 0).unwrap(),
-            &fe[1],
-            &cmt.get_fwt_commitment(sc_ids[0]).unwrap()
-        ));
+        // Inconsistent snapshots are rejected, leaving the subtree untouched
+        let mut wrong_root = snapshot.clone();
+        wrong_root.root = rand_fe_with_rng(&mut rng);
+        let mut wrong_num_leaves = snapshot.clone();
+        wrong_num_leaves.num_leaves += 1;
+        let mut wrong_leaves = snapshot.clone();
+        wrong_leaves.leaves.swap(0, 1);
+        for wrong_snapshot in [wrong_root, wrong_num_leaves, wrong_leaves].iter() {
+            assert_eq!(
+                restored_cmt.restore_cert_subtree(wrong_snapshot),
+                Err(CommitmentTreeError::InvalidCertSubtreeSnapshot(sc_id))
+            );
+            assert_eq!(restored_cmt.get_cert_count(&sc_id), Some(0));
+        }
 
-        // Verify merkle path to BWTR leaf
-        assert!(mht::verify_ginger_merkle_path_without_length_check(
-            &cmt.get_bwtr_merkle_path(sc_ids[1], 0).unwrap(),
-            &fe[2],
-            &cmt.get_bwtr_commitment(sc_ids[1]).unwrap()
-        ));
+        // No sidechain is added for a rejected snapshot either, leaving the commitment unchanged
+        let commitment = restored_cmt.get_commitment();
+        let mut wrong_new_sc = snapshot.clone();
+        wrong_new_sc.sc_id = rand_fe_with_rng(&mut rng);
+        wrong_new_sc.root = rand_fe_with_rng(&mut rng);
+        assert_eq!(
+            restored_cmt.restore_cert_subtree(&wrong_new_sc),
+            Err(CommitmentTreeError::InvalidCertSubtreeSnapshot(
+                wrong_new_sc.sc_id
+            ))
+        );
+        assert!(!restored_cmt.is_present_scta(&wrong_new_sc.sc_id));
+        assert_eq!(restored_cmt.get_commitment(), commitment);
 
-        // Verify merkle path to Cert leaf
-        assert!(mht::verify_ginger_merkle_path_without_length_check(
-            &cmt.get_cert_merkle_path(sc_ids[2], 0).unwrap(),
-            &fe[3],
-            &cmt.get_cert_commitment(sc_ids[2]).unwrap()
-        ));
+        // Ceased sidechains can't be restored
+        let mut ceased_snapshot = snapshot;
+        ceased_snapshot.sc_id = rand_fe_with_rng(&mut rng);
+        assert!(cmt.mark_ceased(&ceased_snapshot.sc_id).is_ok());
+        assert_eq!(
+            cmt.restore_cert_subtree(&ceased_snapshot),
+            Err(CommitmentTreeError::SidechainCeased(ceased_snapshot.sc_id))
+        );
     }
 
     #[test]
-    fn sc_absence_proofs_tests() {
-        let sc_id = get_fe_0_4().into_iter().collect::<Vec<_>>();
-
-        let leaf = FieldElement::one();
+    fn subtree_leaves_tests() {
+        let mut rng = rand::thread_rng();
+        let alive_id = rand_fe_with_rng(&mut rng);
+        let ceased_id = rand_fe_with_rng(&mut rng);
+        let fwts = rand_fe_vec_with_rng(3, &mut rng);
 
         let mut cmt = CommitmentTree::create();
+        assert!(fwts.iter().all(|fwt| cmt.add_fwt_leaf(&alive_id, fwt)));
+        assert!(cmt.set_scc(&alive_id, &rand_fe_with_rng(&mut rng)));
+        assert!(cmt.add_csw_leaf(&ceased_id, &rand_fe_with_rng(&mut rng)));
 
-        // Getting commitment for empty CMT
-        let commitment_empty = cmt.get_commitment();
-        //------------------------------------------------------------------------------------------
-        // Creating and validating absence proof in case of an empty CMT; Any SC-ID is absent in such a CMT
-        let proof_empty = cmt.get_sc_absence_proof(&sc_id[0]);
-        assert!(proof_empty.is_some());
+        assert_eq!(
+            cmt.get_subtree_leaves(&alive_id, SidechainAliveSubtreeType::FWT),
+            Ok(Some(fwts.clone()))
+        );
+        assert_eq!(
+            cmt.get_subtree_leaves(&alive_id, SidechainAliveSubtreeType::BWTR),
+            Ok(Some(vec![]))
+        );
+        assert_eq!(cmt.get_fwt_leaves(&alive_id), Some(fwts));
 
-        test_canonical_serialize_deserialize(true, &proof_empty);
+        // SCC is a single value, so it has no leaves
+        let err = cmt
+            .get_subtree_leaves(&alive_id, SidechainAliveSubtreeType::SCC)
+            .unwrap_err();
+        assert_eq!(
+            err,
+            CommitmentTreeError::NoSubtreeLeaves(SidechainAliveSubtreeType::SCC)
+        );
+        assert_eq!(err.code() as u32, 212);
 
-        // Verification of a valid deserialized absence-proof
-        assert!(CommitmentTree::verify_sc_absence(
-            &sc_id[0],
-            proof_empty.as_ref().unwrap(),
-            commitment_empty.as_ref().unwrap()
-        ));
+        // Not alive or not existing sidechains have no subtrees, whatever their type
+        for sc_id in [ceased_id, rand_fe_with_rng(&mut rng)].iter() {
+            assert_eq!(
+                cmt.get_subtree_leaves(sc_id, SidechainAliveSubtreeType::CERT),
+                Ok(None)
+            );
+            assert_eq!(
+                cmt.get_subtree_leaves(sc_id, SidechainAliveSubtreeType::SCC),
+                Ok(None)
+            );
+            assert!(cmt.get_cert_leaves(sc_id).is_none());
+        }
+    }
 
-        //------------------------------------------------------------------------------------------
-        // Initializing Commitment Tree
-        // NOTE: Here index is the same as FieldElement-value of corresponding scId
+    #[test]
+    fn ordering_tests() {
+        let mut rng = rand::thread_rng();
+        let ids = rand_fe_vec_with_rng(8, &mut rng);
+        let (existing_ids, absent_ids) = ids.split_at(4);
 
-        // Creating two SC-Trees with IDs: 1 and 3
-        assert!(cmt.add_fwt_leaf(&sc_id[1], &leaf));
-        assert!(cmt.add_csw_leaf(&sc_id[3], &leaf));
+        assert_eq!(
+            CommitmentTree::create().ordering(),
+            CommitmentTreeOrdering::FieldElementOrder
+        );
+        assert_eq!(
+            CommitmentTreeOrdering::ByteLexOrder.compare(&ids[0], &ids[1]),
+            sc_id_bytes(&ids[0]).cmp(&sc_id_bytes(&ids[1]))
+        );
 
-        // Getting commitment for all SC-trees
-        let commitment = cmt.get_commitment();
+        for ordering in [
+            CommitmentTreeOrdering::FieldElementOrder,
+            CommitmentTreeOrdering::ByteLexOrder,
+        ]
+        .iter()
+        {
+            assert_eq!(
+                CommitmentTreeOrdering::from_byte(ordering.to_byte()),
+                Some(*ordering)
+            );
 
-        // There is no absence-proof for an existing SC-ID
-        assert!(cmt.get_sc_absence_proof(&sc_id[1]).is_none());
+            let mut cmt = CommitmentTree::create_with_ordering(*ordering);
+            assert_eq!(cmt.ordering(), *ordering);
+            for id in existing_ids.iter() {
+                assert!(cmt.add_fwt_leaf(id, &rand_fe_with_rng(&mut rng)));
+            }
 
-        // Empty proof is not valid for a non-empty Commitment Tree
-        assert!(!CommitmentTree::verify_sc_absence(
-            &sc_id[0],
-            proof_empty.as_ref().unwrap(),
-            commitment.as_ref().unwrap()
-        ));
-        //------------------------------------------------------------------------------------------
-        // Creating and validating absence proof for non-existing ID which value is smaller than any existing ID
-        let proof_leftmost = cmt.get_sc_absence_proof(&sc_id[0]);
-        assert!(proof_leftmost.is_some());
+            // SC-commitments are placed according to the ordering
+            let mut sorted_ids = existing_ids.to_vec();
+            sorted_ids.sort_by(|a, b| ordering.compare(a, b));
+            for (i, id) in sorted_ids.iter().enumerate() {
+                assert_eq!(
+                    cmt.get_sc_commitment_merkle_path(id).unwrap().leaf_index(),
+                    i
+                );
+            }
 
-        test_canonical_serialize_deserialize(true, &proof_leftmost);
+            // Absence proofs encode the ordering and are verified according to the one expected by the verifier
+            let commitment = cmt.get_commitment().unwrap();
+            for absent_id in absent_ids.iter() {
+                let proof = cmt.get_sc_absence_proof(absent_id).unwrap();
+                assert_eq!(proof.ordering(), *ordering);
+                test_canonical_serialize_deserialize(true, &proof);
+                assert!(CommitmentTree::verify_sc_absence_with_ordering(
+                    absent_id,
+                    &proof,
+                    &commitment,
+                    *ordering,
+                    &CommitmentTreeConfig::default()
+                ));
+                assert_eq!(
+                    CommitmentTree::verify_sc_absence(absent_id, &proof, &commitment),
+                    *ordering == CommitmentTreeOrdering::default()
+                );
+            }
+        }
+        assert!(CommitmentTreeOrdering::from_byte(2).is_none());
 
-        // Verification of a valid deserialized absence-proof
-        assert!(CommitmentTree::verify_sc_absence(
-            &sc_id[0],
-            proof_leftmost.as_ref().unwrap(),
-            commitment.as_ref().unwrap()
+        // A ByteLexOrder proof can't be used against a FieldElementOrder CommitmentTree: 2 < 256 as field elements,
+        // while 256 < 2 according to their little-endian bytes, so that the leftmost neighbour 2 would prove 256 absent
+        let (small_id, big_id) = (FieldElement::from(2u64), FieldElement::from(256u64));
+        let mut cmt = CommitmentTree::create();
+        for id in [small_id, big_id].iter() {
+            assert!(cmt.add_fwt_leaf(id, &rand_fe_with_rng(&mut rng)));
+        }
+        let commitment = cmt.get_commitment().unwrap();
+        let proof = cmt.get_sc_absence_proof(&FieldElement::from(1u64)).unwrap();
+        assert_eq!(proof.right.as_ref().unwrap().id, small_id);
+        let forged_proof =
+            ScAbsenceProof::create(None, proof.right, CommitmentTreeOrdering::ByteLexOrder);
+        assert!(!CommitmentTree::verify_sc_absence(
+            &big_id,
+            &forged_proof,
+            &commitment
         ));
+        assert!(!CommitmentTree::verify_sc_absence_with_ordering(
+            &big_id,
+            &forged_proof,
+            &commitment,
+            CommitmentTreeOrdering::FieldElementOrder,
+            &CommitmentTreeConfig::default()
+        ));
+        // The same neighbour is accepted only by a verifier expecting ByteLexOrder
+        assert!(CommitmentTree::verify_sc_absence_with_ordering(
+            &big_id,
+            &forged_proof,
+            &commitment,
+            CommitmentTreeOrdering::ByteLexOrder,
+            &CommitmentTreeConfig::default()
+        ));
+    }
 
-        //------------------------------------------------------------------------------------------
-        // Creating and validating absence proof for non-existing ID which value is between existing IDs
-        let proof_midst = cmt.get_sc_absence_proof(&sc_id[2]);
-        assert!(proof_midst.is_some());
-
-        test_canonical_serialize_deserialize(true, &proof_midst);
+    #[test]
+    fn bytes_adding_tests() {
+        let mut rng = rand::thread_rng();
+        let to_bytes = |fe: &FieldElement| -> [u8; FIELD_SIZE] {
+            serialize_to_buffer(fe, None).unwrap().try_into().unwrap()
+        };
+        let alive_id = rand_fe_with_rng(&mut rng);
+        let ceased_id = rand_fe_with_rng(&mut rng);
+        let custom_fields = rand_fe_vec_with_rng(3, &mut rng);
+        let end_cum_comm_tree_root = rand_fe_with_rng(&mut rng);
+        let nullifier = rand_fe_with_rng(&mut rng);
+        let (amount, pub_key, mc_return_address, tx_hash, out_idx) =
+            (rng.gen(), rng.gen(), rng.gen(), rng.gen(), rng.gen());
+        let mc_pk_hash = rng.gen();
 
-        // Verification of a valid deserialized absence-proof
-        assert!(CommitmentTree::verify_sc_absence(
-            &sc_id[2],
-            proof_midst.as_ref().unwrap(),
-            commitment.as_ref().unwrap()
+        let mut expected_cmt = CommitmentTree::create();
+        assert!(expected_cmt.add_fwt(
+            &alive_id,
+            amount,
+            &pub_key,
+            &mc_return_address,
+            &tx_hash,
+            out_idx
+        ));
+        assert!(expected_cmt.add_bwtr(
+            &alive_id,
+            amount,
+            custom_fields.iter().collect(),
+            &mc_pk_hash,
+            &tx_hash,
+            out_idx
+        ));
+        assert!(expected_cmt.add_cert(
+            &alive_id,
+            out_idx,
+            amount,
+            None,
+            Some(custom_fields.iter().collect()),
+            &end_cum_comm_tree_root,
+            amount,
+            amount
         ));
+        assert!(expected_cmt.add_csw(&ceased_id, amount, &nullifier, &mc_pk_hash));
 
-        //------------------------------------------------------------------------------------------
-        // Creating and validating absence proof for non-existing ID which value is bigger than any existing ID
-        let proof_rightmost = cmt.get_sc_absence_proof(&sc_id[4]);
-        assert!(proof_rightmost.is_some());
+        let custom_fields_bytes = custom_fields.iter().map(to_bytes).collect::<Vec<_>>();
+        let mut cmt = CommitmentTree::create();
+        cmt.add_fwt_bytes(
+            &to_bytes(&alive_id),
+            amount,
+            &pub_key,
+            &mc_return_address,
+            &tx_hash,
+            out_idx,
+        )
+        .unwrap();
+        cmt.add_bwtr_bytes(
+            &to_bytes(&alive_id),
+            amount,
+            &custom_fields_bytes,
+            &mc_pk_hash,
+            &tx_hash,
+            out_idx,
+        )
+        .unwrap();
+        cmt.add_cert_bytes(
+            &to_bytes(&alive_id),
+            out_idx,
+            amount,
+            None,
+            Some(&custom_fields_bytes),
+            &to_bytes(&end_cum_comm_tree_root),
+            amount,
+            amount,
+        )
+        .unwrap();
+        cmt.add_csw_bytes(
+            &to_bytes(&ceased_id),
+            amount,
+            &to_bytes(&nullifier),
+            &mc_pk_hash,
+        )
+        .unwrap();
+        assert_eq!(
+            cmt.get_commitment().unwrap(),
+            expected_cmt.get_commitment().unwrap()
+        );
 
-        test_canonical_serialize_deserialize(true, &proof_rightmost);
+        // Non canonical field elements are rejected, identifying the offending field
+        let invalid_fe_bytes = [0xFFu8; FIELD_SIZE];
+        assert_eq!(
+            cmt.add_csw_bytes(
+                &invalid_fe_bytes,
+                amount,
+                &to_bytes(&nullifier),
+                &mc_pk_hash
+            ),
+            Err(CommitmentTreeError::InvalidFieldElementBytes(
+                "sc_id".to_owned()
+            ))
+        );
+        assert_eq!(
+            cmt.add_csw_bytes(
+                &to_bytes(&ceased_id),
+                amount,
+                &invalid_fe_bytes,
+                &mc_pk_hash
+            ),
+            Err(CommitmentTreeError::InvalidFieldElementBytes(
+                "nullifier".to_owned()
+            ))
+        );
+        let mut invalid_custom_fields_bytes = custom_fields_bytes;
+        invalid_custom_fields_bytes[1] = invalid_fe_bytes;
+        assert_eq!(
+            cmt.add_cert_bytes(
+                &to_bytes(&alive_id),
+                out_idx,
+                amount,
+                None,
+                Some(&invalid_custom_fields_bytes),
+                &to_bytes(&end_cum_comm_tree_root),
+                amount,
+                amount,
+            ),
+            Err(CommitmentTreeError::InvalidFieldElementBytes(
+                "custom_fields[1]".to_owned()
+            ))
+        );
 
-        // Verification of a valid deserialized absence-proof
-        assert!(CommitmentTree::verify_sc_absence(
-            &sc_id[4],
-            proof_rightmost.as_ref().unwrap(),
-            commitment.as_ref().unwrap()
-        ));
+        // Leaves of the wrong type are rejected as well
+        assert_eq!(
+            cmt.add_csw_bytes(
+                &to_bytes(&alive_id),
+                amount,
+                &to_bytes(&nullifier),
+                &mc_pk_hash
+            ),
+            Err(CommitmentTreeError::SidechainAlive(alive_id))
+        );
+        assert_eq!(
+            cmt.add_fwt_bytes(
+                &to_bytes(&ceased_id),
+                amount,
+                &pub_key,
+                &mc_return_address,
+                &tx_hash,
+                out_idx,
+            ),
+            Err(CommitmentTreeError::SidechainCeased(ceased_id))
+        );
+        assert_eq!(
+            cmt.get_commitment().unwrap(),
+            expected_cmt.get_commitment().unwrap()
+        );
     }
 
     #[test]
@@ -1097,11 +4972,11 @@ mod test {
         let comm0 = cmt.get_commitment();
 
         assert!(cmt.add_fwt(
-            &rand_fe(),
+            &rand_fe_with_rng(&mut rng),
             rng.gen(),
-            &rand_vec(32).try_into().unwrap(),
-            &rand_vec(20).try_into().unwrap(),
-            &rand_vec(32).try_into().unwrap(),
+            &rand_vec_with_rng(32, &mut rng).try_into().unwrap(),
+            &rand_vec_with_rng(20, &mut rng).try_into().unwrap(),
+            &rand_vec_with_rng(32, &mut rng).try_into().unwrap(),
             rng.gen()
         ));
 
@@ -1110,11 +4985,11 @@ mod test {
         assert_ne!(comm0, comm1);
 
         assert!(cmt.add_bwtr(
-            &rand_fe(),
+            &rand_fe_with_rng(&mut rng),
             rng.gen(),
-            rand_fe_vec(10).iter().collect(),
-            &rand_vec(MC_PK_SIZE).try_into().unwrap(),
-            &rand_vec(32).try_into().unwrap(),
+            rand_fe_vec_with_rng(10, &mut rng).iter().collect(),
+            &rand_vec_with_rng(MC_PK_SIZE, &mut rng).try_into().unwrap(),
+            &rand_vec_with_rng(32, &mut rng).try_into().unwrap(),
             rng.gen()
         ));
 
@@ -1123,12 +4998,12 @@ mod test {
 
         let default_bt_vec = vec![BackwardTransfer::default(); 10];
         assert!(cmt.add_cert(
-            &rand_fe(),
+            &rand_fe_with_rng(&mut rng),
             rng.gen(),
             rng.gen(),
             Some(default_bt_vec.as_slice()),
-            Some(rand_fe_vec(2).iter().collect()),
-            &rand_fe(),
+            Some(rand_fe_vec_with_rng(2, &mut rng).iter().collect()),
+            &rand_fe_with_rng(&mut rng),
             rng.gen(),
             rng.gen(),
         ));
@@ -1137,12 +5012,12 @@ mod test {
         assert_ne!(comm2, comm3);
 
         assert!(cmt.add_cert(
-            &rand_fe(),
+            &rand_fe_with_rng(&mut rng),
             rng.gen(),
             rng.gen(),
             None,
-            Some(rand_fe_vec(2).iter().collect()),
-            &rand_fe(),
+            Some(rand_fe_vec_with_rng(2, &mut rng).iter().collect()),
+            &rand_fe_with_rng(&mut rng),
             rng.gen(),
             rng.gen(),
         ));
@@ -1152,31 +5027,31 @@ mod test {
 
         let default_bv_config = vec![BitVectorElementsConfig::default(); 10];
         assert!(cmt.add_scc(
-            &rand_fe(),
+            &rand_fe_with_rng(&mut rng),
             rng.gen(),
-            &rand_vec(32).try_into().unwrap(),
-            &rand_vec(32).try_into().unwrap(),
+            &rand_vec_with_rng(32, &mut rng).try_into().unwrap(),
+            &rand_vec_with_rng(32, &mut rng).try_into().unwrap(),
             rng.gen(),
             rng.gen(),
             rng.gen(),
-            Some(&rand_vec(10)),
+            Some(&rand_vec_with_rng(10, &mut rng)),
             Some(default_bv_config.as_slice()),
             rng.gen(),
             rng.gen(),
-            Some(&rand_vec(100)),
-            Some(&rand_fe()),
-            &rand_vec(100),
-            Some(&rand_vec(100))
+            Some(&rand_vec_with_rng(100, &mut rng)),
+            Some(&rand_fe_with_rng(&mut rng)),
+            &rand_vec_with_rng(100, &mut rng),
+            Some(&rand_vec_with_rng(100, &mut rng))
         ));
 
         let comm5 = cmt.get_commitment();
         assert_ne!(comm4, comm5);
 
         assert!(cmt.add_scc(
-            &rand_fe(),
+            &rand_fe_with_rng(&mut rng),
             rng.gen(),
-            &rand_vec(32).try_into().unwrap(),
-            &rand_vec(32).try_into().unwrap(),
+            &rand_vec_with_rng(32, &mut rng).try_into().unwrap(),
+            &rand_vec_with_rng(32, &mut rng).try_into().unwrap(),
             rng.gen(),
             rng.gen(),
             rng.gen(),
@@ -1186,7 +5061,7 @@ mod test {
             rng.gen(),
             None,
             None,
-            &rand_vec(100),
+            &rand_vec_with_rng(100, &mut rng),
             None
         ));
 
@@ -1194,12 +5069,132 @@ mod test {
         assert_ne!(comm5, comm6);
 
         assert!(cmt.add_csw(
-            &rand_fe(),
+            &rand_fe_with_rng(&mut rng),
             rng.gen(),
-            &rand_fe(),
-            &rand_vec(MC_PK_SIZE).try_into().unwrap()
+            &rand_fe_with_rng(&mut rng),
+            &rand_vec_with_rng(MC_PK_SIZE, &mut rng).try_into().unwrap()
         ));
 
         assert_ne!(comm6, cmt.get_commitment());
     }
+
+    #[test]
+    fn compute_sc_commitment_tests() {
+        let mut rng = rand::thread_rng();
+        let alive_id = rand_fe_with_rng(&mut rng);
+        let ceased_id = rand_fe_with_rng(&mut rng);
+        let fwt_leaves = rand_fe_vec_with_rng(3, &mut rng);
+        let bwtr_leaves = rand_fe_vec_with_rng(2, &mut rng);
+        let cert_leaves = rand_fe_vec_with_rng(1, &mut rng);
+        let csw_leaves = rand_fe_vec_with_rng(4, &mut rng);
+        let scc = rand_fe_with_rng(&mut rng);
+
+        // Empty sidechains
+        assert_eq!(
+            compute_sc_commitment_alive(&alive_id, &[], &[], &[], None).unwrap(),
+            SidechainTreeAlive::create(&alive_id)
+                .unwrap()
+                .get_commitment()
+                .unwrap()
+        );
+        assert_eq!(
+            compute_sc_commitment_ceased(&ceased_id, &[]).unwrap(),
+            SidechainTreeCeased::create(&ceased_id)
+                .unwrap()
+                .get_commitment()
+                .unwrap()
+        );
+
+        // Same commitments as the ones of the sidechains of a CommitmentTree
+        let mut cmt = CommitmentTree::create();
+        fwt_leaves
+            .iter()
+            .for_each(|leaf| assert!(cmt.add_fwt_leaf(&alive_id, leaf)));
+        bwtr_leaves
+            .iter()
+            .for_each(|leaf| assert!(cmt.add_bwtr_leaf(&alive_id, leaf)));
+        cert_leaves
+            .iter()
+            .for_each(|leaf| assert!(cmt.add_cert_leaf(&alive_id, leaf)));
+        csw_leaves
+            .iter()
+            .for_each(|leaf| assert!(cmt.add_csw_leaf(&ceased_id, leaf)));
+        assert_eq!(
+            compute_sc_commitment_alive(&alive_id, &fwt_leaves, &bwtr_leaves, &cert_leaves, None)
+                .unwrap(),
+            cmt.get_sc_commitment(&alive_id).unwrap()
+        );
+        assert!(cmt.set_scc(&alive_id, &scc));
+        assert_eq!(
+            compute_sc_commitment_alive(
+                &alive_id,
+                &fwt_leaves,
+                &bwtr_leaves,
+                &cert_leaves,
+                Some(&scc)
+            )
+            .unwrap(),
+            cmt.get_sc_commitment(&alive_id).unwrap()
+        );
+        assert_eq!(
+            compute_sc_commitment_ceased(&ceased_id, &csw_leaves).unwrap(),
+            cmt.get_sc_commitment(&ceased_id).unwrap()
+        );
+
+        // Same with a custom config, whose subtrees can't contain more leaves than their capacity
+        let config = CommitmentTreeConfig {
+            cmt_height: 2,
+            fwt_height: 2,
+            bwtr_height: 1,
+            cert_height: 1,
+            csw_height: 2,
+        };
+        let mut cmt = CommitmentTree::create_with_config(config).unwrap();
+        fwt_leaves
+            .iter()
+            .for_each(|leaf| assert!(cmt.add_fwt_leaf(&alive_id, leaf)));
+        csw_leaves
+            .iter()
+            .for_each(|leaf| assert!(cmt.add_csw_leaf(&ceased_id, leaf)));
+        assert_eq!(
+            compute_sc_commitment_alive_with_config(
+                &alive_id,
+                &fwt_leaves,
+                &[],
+                &[],
+                None,
+                &config
+            )
+            .unwrap(),
+            cmt.get_sc_commitment(&alive_id).unwrap()
+        );
+        assert_eq!(
+            compute_sc_commitment_ceased_with_config(&ceased_id, &csw_leaves, &config).unwrap(),
+            cmt.get_sc_commitment(&ceased_id).unwrap()
+        );
+        assert!(compute_sc_commitment_alive_with_config(
+            &alive_id,
+            &[],
+            &bwtr_leaves,
+            &cert_leaves,
+            None,
+            &config
+        )
+        .is_ok());
+        assert!(compute_sc_commitment_alive_with_config(
+            &alive_id,
+            &fwt_leaves,
+            &[],
+            &csw_leaves,
+            None,
+            &config
+        )
+        .is_err());
+        assert!(compute_sc_commitment_ceased_with_config(
+            &ceased_id,
+            &rand_fe_vec_with_rng(5, &mut rng),
+            &config
+        )
+        .is_err());
+    }
 }
@@ -1,10 +1,12 @@
+use crate::commitment_tree::error::CommitmentTreeError;
 use crate::commitment_tree::sidechain_tree_alive::SidechainTreeAlive;
 use crate::commitment_tree::sidechain_tree_ceased::SidechainTreeCeased;
+use crate::commitment_tree::CommitmentTreeOrdering;
 use crate::type_mapping::{FieldElement, GingerMHTPath};
 use algebra::serialize::*;
 
 //--------------------------------------------------------------------------------------------------
-#[derive(PartialEq, Debug, CanonicalSerialize, CanonicalDeserialize)]
+#[derive(Clone, PartialEq, Debug, CanonicalSerialize, CanonicalDeserialize)]
 struct ScAliveCommitmentData {
     fwt_mr: FieldElement,
     bwtr_mr: FieldElement,
@@ -13,13 +15,39 @@ struct ScAliveCommitmentData {
 }
 
 //--------------------------------------------------------------------------------------------------
-#[derive(PartialEq, Debug, CanonicalSerialize, CanonicalDeserialize)]
+#[derive(Clone, PartialEq, Debug, CanonicalSerialize, CanonicalDeserialize)]
 struct ScCeasedCommitmentData {
     csw_mr: FieldElement,
 }
 
+// Versions of the serialization formats of the proofs, exchanged by nodes (e.g. during handshake) to tell
+// which proofs they are able to verify
+// A version must be bumped whenever the serialization format of the corresponding proof changes
+pub const SC_EXISTENCE_PROOF_FORMAT_VERSION: u8 = 1;
+pub const SC_ABSENCE_PROOF_FORMAT_VERSION: u8 = 1;
+
+// Checks that proofs serialized according to a format version can be verified, given the supported version
+fn check_proof_format_version(version: u8, supported: u8) -> Result<(), CommitmentTreeError> {
+    if version != supported {
+        return Err(CommitmentTreeError::UnsupportedProofFormatVersion(
+            version, supported,
+        ));
+    }
+    Ok(())
+}
+
 //--------------------------------------------------------------------------------------------------
-#[derive(PartialEq, Debug, CanonicalSerialize, CanonicalDeserialize)]
+// Types of the subtrees of a sidechain whose leaves can be proven to exist inside of a CommitmentTree
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ScSubtreeType {
+    FWT,
+    BWTR,
+    CERT,
+    CSW,
+}
+
+//--------------------------------------------------------------------------------------------------
+#[derive(Clone, PartialEq, Debug, CanonicalSerialize, CanonicalDeserialize)]
 pub struct ScCommitmentData {
     sc_alive: Option<ScAliveCommitmentData>,
     sc_ceased: Option<ScCeasedCommitmentData>,
@@ -50,6 +78,22 @@ impl ScCommitmentData {
         }
     }
 
+    // Gets the root of a subtree of a specified type
+    // Returns None if the sidechain has no subtree of such type, e.g. a CSW subtree for an alive sidechain
+    pub(crate) fn get_subtree_root(&self, subtree_type: ScSubtreeType) -> Option<&FieldElement> {
+        match (
+            subtree_type,
+            self.sc_alive.as_ref(),
+            self.sc_ceased.as_ref(),
+        ) {
+            (ScSubtreeType::FWT, Some(data), None) => Some(&data.fwt_mr),
+            (ScSubtreeType::BWTR, Some(data), None) => Some(&data.bwtr_mr),
+            (ScSubtreeType::CERT, Some(data), None) => Some(&data.cert_mr),
+            (ScSubtreeType::CSW, None, Some(data)) => Some(&data.csw_mr),
+            _ => None,
+        }
+    }
+
     // Builds Commitment of SidechainTreeAlive or SidechainTreeCeased for a specified SC-ID
     pub(crate) fn get_sc_commitment(&self, sc_id: &FieldElement) -> Option<FieldElement> {
         if self.sc_alive.is_some() && self.sc_ceased.is_some() {
@@ -71,7 +115,7 @@ impl ScCommitmentData {
 }
 
 //--------------------------------------------------------------------------------------------------
-#[derive(PartialEq, Debug, CanonicalSerialize, CanonicalDeserialize)]
+#[derive(Clone, PartialEq, Debug, CanonicalSerialize, CanonicalDeserialize)]
 pub struct ScNeighbour {
     pub(crate) id: FieldElement,          // ID of SC
     pub(crate) mpath: GingerMHTPath, // Merkle Path for SC-commitment of an SC with the given ID
@@ -90,16 +134,41 @@ impl ScNeighbour {
 
 //--------------------------------------------------------------------------------------------------
 // Proof of absence of some Sidechain-ID inside of a CommitmentTree
-// Contains 0 or 1 or 2 neighbours of an absent ID
+// Contains 0 or 1 or 2 neighbours of an absent ID, and the ordering of the SC-IDs they are neighbours according to
 #[derive(PartialEq, Debug, CanonicalSerialize, CanonicalDeserialize)]
 pub struct ScAbsenceProof {
     pub(crate) left: Option<ScNeighbour>, // neighbour with a smaller ID
     pub(crate) right: Option<ScNeighbour>, // neighbour with a bigger ID
+    pub(crate) ordering: CommitmentTreeOrdering, // ordering of the CommitmentTree the proof has been built from
 }
 
 impl ScAbsenceProof {
-    pub(crate) fn create(left: Option<ScNeighbour>, right: Option<ScNeighbour>) -> Self {
-        Self { left, right }
+    pub(crate) fn create(
+        left: Option<ScNeighbour>,
+        right: Option<ScNeighbour>,
+        ordering: CommitmentTreeOrdering,
+    ) -> Self {
+        Self {
+            left,
+            right,
+            ordering,
+        }
+    }
+
+    // Gets the ordering of the CommitmentTree the proof has been built from
+    pub fn ordering(&self) -> CommitmentTreeOrdering {
+        self.ordering
+    }
+
+    // Gets the version of the serialization format of ScAbsenceProof supported by this library
+    pub fn proof_format_version() -> u8 {
+        SC_ABSENCE_PROOF_FORMAT_VERSION
+    }
+
+    // Checks whether proofs serialized according to a specified format version can be deserialized and verified
+    // Returns UnsupportedProofFormatVersion error otherwise
+    pub fn can_verify(version: u8) -> Result<(), CommitmentTreeError> {
+        check_proof_format_version(version, SC_ABSENCE_PROOF_FORMAT_VERSION)
     }
 }
 
@@ -115,6 +184,61 @@ impl ScExistenceProof {
     pub(crate) fn create(mpath: GingerMHTPath) -> Self {
         Self { mpath }
     }
+
+    // Gets the version of the serialization format of ScExistenceProof supported by this library
+    pub fn proof_format_version() -> u8 {
+        SC_EXISTENCE_PROOF_FORMAT_VERSION
+    }
+
+    // Checks whether proofs serialized according to a specified format version can be deserialized and verified
+    // Returns UnsupportedProofFormatVersion error otherwise
+    pub fn can_verify(version: u8) -> Result<(), CommitmentTreeError> {
+        check_proof_format_version(version, SC_EXISTENCE_PROOF_FORMAT_VERSION)
+    }
+}
+
+//--------------------------------------------------------------------------------------------------
+// Proof of existence of some CSW leaf inside of a CommitmentTree;
+// Contains a Merkle Path of the leaf inside of the CSW subtree of a SidechainTreeCeased
+// and the existence proof of that SidechainTreeCeased inside of a CommitmentTree
+#[derive(PartialEq, Debug, CanonicalSerialize, CanonicalDeserialize)]
+pub struct CswLeafProof {
+    pub(crate) csw_mpath: GingerMHTPath, // Merkle Path of a CSW leaf inside of the CSW subtree
+    pub(crate) sc_proof: ScExistenceProof, // Existence proof of the SidechainTreeCeased
+}
+
+impl CswLeafProof {
+    pub(crate) fn create(csw_mpath: GingerMHTPath, sc_proof: ScExistenceProof) -> Self {
+        Self {
+            csw_mpath,
+            sc_proof,
+        }
+    }
+}
+
+//--------------------------------------------------------------------------------------------------
+// Proof of existence of some FWT/BWTR/CERT/CSW leaf inside of a CommitmentTree;
+// Contains a Merkle Path of the leaf inside of its subtree, the data needed to build the SC-commitment
+// out of the root of such subtree and the existence proof of the sidechain inside of a CommitmentTree
+#[derive(PartialEq, Debug, CanonicalSerialize, CanonicalDeserialize)]
+pub struct LeafExistenceProof {
+    pub(crate) leaf_mpath: GingerMHTPath, // Merkle Path of a leaf inside of its subtree
+    pub(crate) sc_data: ScCommitmentData, // data needed to build the SC-commitment, including the subtree root
+    pub(crate) sc_proof: ScExistenceProof, // Existence proof of the sidechain
+}
+
+impl LeafExistenceProof {
+    pub(crate) fn create(
+        leaf_mpath: GingerMHTPath,
+        sc_data: ScCommitmentData,
+        sc_proof: ScExistenceProof,
+    ) -> Self {
+        Self {
+            leaf_mpath,
+            sc_data,
+            sc_proof,
+        }
+    }
 }
 
 //--------------------------------------------------------------------------------------------------
@@ -130,7 +254,7 @@ mod test {
     use algebra::{test_canonical_serialize_deserialize, UniformRand};
     use primitives::FieldBasedMerkleTree;
 
-    // NOTE: Tests for ScExistenceProof and ScAbsenceProof are inside of the CommitmentTree module
+    // NOTE: Tests for ScExistenceProof, ScAbsenceProof, CswLeafProof and LeafExistenceProof are inside of the CommitmentTree module
 
     #[test]
     fn test_sc_alive() {
@@ -0,0 +1,137 @@
+use crate::commitment_tree::{CommitmentTree, ScLeafSets};
+use crate::config::budgeted_num_threads;
+use crate::type_mapping::FieldElement;
+use std::{
+    collections::BTreeMap,
+    sync::{mpsc, Arc, Mutex},
+    thread,
+};
+
+// Sidechain-related leaf hashes of a block, from which its CommitmentTree can be rebuilt
+pub type BlockScData = BTreeMap<FieldElement, ScLeafSets>;
+
+// Computes the commitments of many independent blocks in parallel on `num_threads` threads (at least 1,
+// and at most the thread budget)
+// Memory is bounded: at most `num_threads` CommitmentTrees are alive at the same time
+// Returns commitments in the same order of `blocks` or
+//         None if the CommitmentTree of some block can't be built or its commitment can't be computed
+pub fn compute_block_commitments_parallel(
+    blocks: Vec<BlockScData>,
+    num_threads: usize,
+) -> Option<Vec<FieldElement>> {
+    let num_blocks = blocks.len();
+    let jobs = Arc::new(Mutex::new(blocks.into_iter().enumerate()));
+    let (result_sender, result_receiver) = mpsc::channel();
+
+    let workers = (0..budgeted_num_threads(num_threads).min(num_blocks))
+        .map(|_| {
+            let jobs = jobs.clone();
+            let result_sender = result_sender.clone();
+            thread::spawn(move || loop {
+                // Lock is released as soon as a block has been fetched
+                let job = match jobs.lock() {
+                    Ok(mut jobs) => jobs.next(),
+                    Err(_) => break,
+                };
+                let (idx, block) = match job {
+                    Some(job) => job,
+                    None => break, // no more blocks
+                };
+
+                let commitment = CommitmentTree::from_leaf_hashes(block)
+                    .and_then(|mut cmt| cmt.get_commitment());
+
+                // Stop this worker as soon as a failure has been detected
+                if commitment.is_none() || result_sender.send((idx, commitment)).is_err() {
+                    break;
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+    drop(result_sender);
+
+    let mut commitments = vec![None; num_blocks];
+    for (idx, commitment) in result_receiver.iter() {
+        commitments[idx] = commitment;
+    }
+
+    for worker in workers.into_iter() {
+        if worker.join().is_err() {
+            return None;
+        }
+    }
+
+    // If some block failed, its commitment (and possibly the ones of the following blocks) is missing
+    commitments.into_iter().collect()
+}
+
+#[cfg(test)]
+mod test {
+    use crate::commitment_tree::{
+        reindex::{compute_block_commitments_parallel, BlockScData},
+        CommitmentTree, ScLeafSets,
+    };
+    use crate::utils::commitment_tree::{rand_fe_vec_with_rng, rand_fe_with_rng};
+    use rand::Rng;
+
+    fn rand_block_sc_data<R: Rng>(rng: &mut R) -> BlockScData {
+        (0..rng.gen_range(0..4))
+            .map(|_| {
+                let sc_leaf_sets = if rng.gen() {
+                    ScLeafSets::Alive {
+                        fwt: rand_fe_vec_with_rng(rng.gen_range(0..5), rng),
+                        bwtr: rand_fe_vec_with_rng(rng.gen_range(0..5), rng),
+                        cert: rand_fe_vec_with_rng(rng.gen_range(0..5), rng),
+                        scc: Some(rand_fe_with_rng(rng)),
+                    }
+                } else {
+                    ScLeafSets::Ceased {
+                        csw: rand_fe_vec_with_rng(rng.gen_range(0..5), rng),
+                    }
+                };
+                (rand_fe_with_rng(rng), sc_leaf_sets)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn compute_block_commitments_parallel_tests() {
+        let mut rng = rand::thread_rng();
+        let blocks = (0..20)
+            .map(|_| rand_block_sc_data(&mut rng))
+            .collect::<Vec<_>>();
+
+        let expected = blocks
+            .iter()
+            .map(|block| {
+                CommitmentTree::from_leaf_hashes(block.clone())
+                    .unwrap()
+                    .get_commitment()
+                    .unwrap()
+            })
+            .collect::<Vec<_>>();
+
+        for &num_threads in [0, 1, 4, 32].iter() {
+            assert_eq!(
+                compute_block_commitments_parallel(blocks.clone(), num_threads).unwrap(),
+                expected
+            );
+        }
+
+        // No blocks
+        assert_eq!(
+            compute_block_commitments_parallel(vec![], 4).unwrap(),
+            vec![]
+        );
+
+        // A block whose CommitmentTree can't be built
+        let mut invalid_blocks = blocks;
+        invalid_blocks[10].insert(
+            rand_fe_with_rng(&mut rng),
+            ScLeafSets::Ceased {
+                csw: rand_fe_vec_with_rng(5000, &mut rng),
+            },
+        );
+        assert!(compute_block_commitments_parallel(invalid_blocks, 4).is_none());
+    }
+}
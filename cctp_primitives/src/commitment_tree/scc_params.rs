@@ -0,0 +1,390 @@
+use crate::{
+    commitment_tree::{error::CommitmentTreeError, hashers::hash_scc},
+    proving_system::ProvingSystem,
+    type_mapping::{Error, FieldElement},
+    utils::data_structures::BitVectorElementsConfig,
+};
+
+// Parameters of a Sidechain Creation Transaction, i.e. the values hash_scc takes, along with the proving system
+// of the CSW circuit, which isn't committed but must be declared together with its verification key
+// Instances can only be obtained through ScCreationParamsBuilder, which checks the consistency of the optional
+// components, so that callers (e.g. FFI wrappers) can't pass them in the wrong combination or position
+#[derive(Clone, Debug, PartialEq)]
+pub struct ScCreationParams {
+    amount: u64,
+    pub_key: [u8; 32],
+    tx_hash: [u8; 32],
+    out_idx: u32,
+    withdrawal_epoch_length: u32,
+    mc_btr_request_data_length: u8,
+    custom_field_elements_configs: Option<Vec<u8>>,
+    custom_bitvector_elements_configs: Option<Vec<BitVectorElementsConfig>>,
+    btr_fee: u64,
+    ft_min_amount: u64,
+    custom_creation_data: Option<Vec<u8>>,
+    constant: Option<FieldElement>,
+    cert_verification_key: Vec<u8>,
+    csw: Option<(ProvingSystem, Vec<u8>)>, // proving system and verification key of the CSW circuit, if any
+}
+
+impl ScCreationParams {
+    // Gets the hash of the transaction creating the sidechain
+    pub fn tx_hash(&self) -> &[u8; 32] {
+        &self.tx_hash
+    }
+
+    // Gets the index of the output creating the sidechain
+    pub fn out_idx(&self) -> u32 {
+        self.out_idx
+    }
+
+    // Gets the proving system of the CSW circuit, if CSWs are supported by the sidechain
+    pub fn csw_proving_system(&self) -> Option<ProvingSystem> {
+        self.csw.as_ref().map(|(proving_system, _)| *proving_system)
+    }
+
+    // Gets the verification key of the CSW circuit, if CSWs are supported by the sidechain
+    pub fn csw_verification_key(&self) -> Option<&[u8]> {
+        self.csw.as_ref().map(|(_, vk)| vk.as_slice())
+    }
+
+    // Computes the hash of the Sidechain Creation Transaction, i.e. its SCC leaf
+    pub fn hash(&self) -> Result<FieldElement, Error> {
+        hash_scc(
+            self.amount,
+            &self.pub_key,
+            &self.tx_hash,
+            self.out_idx,
+            self.withdrawal_epoch_length,
+            self.mc_btr_request_data_length,
+            self.custom_field_elements_configs.as_deref(),
+            self.custom_bitvector_elements_configs.as_deref(),
+            self.btr_fee,
+            self.ft_min_amount,
+            self.custom_creation_data.as_deref(),
+            self.constant.as_ref(),
+            &self.cert_verification_key,
+            self.csw_verification_key(),
+        )
+    }
+}
+
+// Builds ScCreationParams out of the mandatory values, passed on creation, and of the optional ones, set one by one
+// The optional values not set take their default, i.e. 0 for numbers and absent for the optional components
+pub struct ScCreationParamsBuilder {
+    params: ScCreationParams,
+    csw_proving_system: Option<ProvingSystem>,
+    csw_verification_key: Option<Vec<u8>>,
+}
+
+impl ScCreationParamsBuilder {
+    // Creates a new instance of ScCreationParamsBuilder for the sidechain created by the output
+    // of a specified transaction
+    pub fn create(
+        amount: u64,
+        pub_key: &[u8; 32],
+        tx_hash: &[u8; 32],
+        out_idx: u32,
+        withdrawal_epoch_length: u32,
+        cert_verification_key: &[u8],
+    ) -> Self {
+        Self {
+            params: ScCreationParams {
+                amount,
+                pub_key: *pub_key,
+                tx_hash: *tx_hash,
+                out_idx,
+                withdrawal_epoch_length,
+                mc_btr_request_data_length: 0,
+                custom_field_elements_configs: None,
+                custom_bitvector_elements_configs: None,
+                btr_fee: 0,
+                ft_min_amount: 0,
+                custom_creation_data: None,
+                constant: None,
+                cert_verification_key: cert_verification_key.to_vec(),
+                csw: None,
+            },
+            csw_proving_system: None,
+            csw_verification_key: None,
+        }
+    }
+
+    // Setters of the optional values, each one overriding the value previously set, if any
+    pub fn mc_btr_request_data_length(mut self, mc_btr_request_data_length: u8) -> Self {
+        self.params.mc_btr_request_data_length = mc_btr_request_data_length;
+        self
+    }
+
+    pub fn custom_field_elements_configs(mut self, configs: &[u8]) -> Self {
+        self.params.custom_field_elements_configs = Some(configs.to_vec());
+        self
+    }
+
+    pub fn custom_bitvector_elements_configs(
+        mut self,
+        configs: &[BitVectorElementsConfig],
+    ) -> Self {
+        self.params.custom_bitvector_elements_configs = Some(configs.to_vec());
+        self
+    }
+
+    pub fn btr_fee(mut self, btr_fee: u64) -> Self {
+        self.params.btr_fee = btr_fee;
+        self
+    }
+
+    pub fn ft_min_amount(mut self, ft_min_amount: u64) -> Self {
+        self.params.ft_min_amount = ft_min_amount;
+        self
+    }
+
+    pub fn custom_creation_data(mut self, custom_creation_data: &[u8]) -> Self {
+        self.params.custom_creation_data = Some(custom_creation_data.to_vec());
+        self
+    }
+
+    pub fn constant(mut self, constant: &FieldElement) -> Self {
+        self.params.constant = Some(*constant);
+        self
+    }
+
+    // The CSW proving system and verification key must be set together, or not at all
+    pub fn csw_proving_system(mut self, csw_proving_system: ProvingSystem) -> Self {
+        self.csw_proving_system = Some(csw_proving_system);
+        self
+    }
+
+    pub fn csw_verification_key(mut self, csw_verification_key: &[u8]) -> Self {
+        self.csw_verification_key = Some(csw_verification_key.to_vec());
+        self
+    }
+
+    // Builds the ScCreationParams
+    // Returns InvalidScCreationParams error if only one of the CSW proving system and verification key is set
+    pub fn build(self) -> Result<ScCreationParams, CommitmentTreeError> {
+        let mut params = self.params;
+        params.csw = match (self.csw_proving_system, self.csw_verification_key) {
+            (None, None) => None,
+            (Some(_), None) => {
+                return Err(CommitmentTreeError::InvalidScCreationParams(
+                    "csw_proving_system set without csw_verification_key".to_owned(),
+                ))
+            }
+            (None, Some(_)) => {
+                return Err(CommitmentTreeError::InvalidScCreationParams(
+                    "csw_verification_key set without csw_proving_system".to_owned(),
+                ))
+            }
+            (Some(proving_system), Some(vk)) => Some((proving_system, vk)),
+        };
+        Ok(params)
+    }
+}
+
+// JSON fixture of the ScCreationParams, built back through ScCreationParamsBuilder so that the fixtures are
+// subject to its same consistency checks
+#[cfg(feature = "fixtures")]
+impl crate::fixtures::HashFixture for ScCreationParams {
+    const NAME: &'static str = "scc";
+
+    fn to_json(&self) -> Result<serde_json::Value, Error> {
+        use crate::fixtures::{opt_fe_to_json, to_hex};
+        use serde_json::{json, Value};
+
+        let opt_bytes =
+            |bytes: Option<&[u8]>| bytes.map_or(Value::Null, |bytes| json!(to_hex(bytes)));
+        let custom_bitvector_elements_configs = self
+            .custom_bitvector_elements_configs
+            .as_ref()
+            .map_or(Value::Null, |configs| {
+                configs
+                    .iter()
+                    .map(|config| {
+                        json!({
+                            "bit_vector_size_bits": config.bit_vector_size_bits,
+                            "max_compressed_byte_size": config.max_compressed_byte_size,
+                        })
+                    })
+                    .collect()
+            });
+        let csw_proving_system = self
+            .csw_proving_system()
+            .map_or(Value::Null, |proving_system| {
+                json!(proving_system.to_string())
+            });
+        Ok(json!({
+            "amount": self.amount,
+            "pub_key": to_hex(&self.pub_key),
+            "tx_hash": to_hex(&self.tx_hash),
+            "out_idx": self.out_idx,
+            "withdrawal_epoch_length": self.withdrawal_epoch_length,
+            "mc_btr_request_data_length": self.mc_btr_request_data_length,
+            "custom_field_elements_configs":
+                opt_bytes(self.custom_field_elements_configs.as_deref()),
+            "custom_bitvector_elements_configs": custom_bitvector_elements_configs,
+            "btr_fee": self.btr_fee,
+            "ft_min_amount": self.ft_min_amount,
+            "custom_creation_data": opt_bytes(self.custom_creation_data.as_deref()),
+            "constant": opt_fe_to_json(self.constant.as_ref())?,
+            "cert_verification_key": to_hex(&self.cert_verification_key),
+            "csw_proving_system": csw_proving_system,
+            "csw_verification_key": opt_bytes(self.csw_verification_key()),
+        }))
+    }
+
+    fn from_json(value: &serde_json::Value) -> Result<Self, Error> {
+        use crate::fixtures::{
+            bytes_from_json, get_array, get_byte_array, get_bytes, get_opt_fe, get_opt_field,
+            get_u32, get_u64, get_u8,
+        };
+
+        let mut builder = ScCreationParamsBuilder::create(
+            get_u64(value, "amount")?,
+            &get_byte_array(value, "pub_key")?,
+            &get_byte_array(value, "tx_hash")?,
+            get_u32(value, "out_idx")?,
+            get_u32(value, "withdrawal_epoch_length")?,
+            &get_bytes(value, "cert_verification_key")?,
+        )
+        .mc_btr_request_data_length(get_u8(value, "mc_btr_request_data_length")?)
+        .btr_fee(get_u64(value, "btr_fee")?)
+        .ft_min_amount(get_u64(value, "ft_min_amount")?);
+
+        if let Some(configs) = get_opt_field(value, "custom_field_elements_configs") {
+            builder = builder.custom_field_elements_configs(&bytes_from_json(
+                configs,
+                "custom_field_elements_configs",
+            )?);
+        }
+        if get_opt_field(value, "custom_bitvector_elements_configs").is_some() {
+            let configs = get_array(value, "custom_bitvector_elements_configs")?
+                .iter()
+                .map(|config| {
+                    Ok(BitVectorElementsConfig {
+                        bit_vector_size_bits: get_u32(config, "bit_vector_size_bits")?,
+                        max_compressed_byte_size: get_u32(config, "max_compressed_byte_size")?,
+                    })
+                })
+                .collect::<Result<Vec<_>, Error>>()?;
+            builder = builder.custom_bitvector_elements_configs(&configs);
+        }
+        if let Some(data) = get_opt_field(value, "custom_creation_data") {
+            builder = builder.custom_creation_data(&bytes_from_json(data, "custom_creation_data")?);
+        }
+        if let Some(constant) = get_opt_fe(value, "constant")? {
+            builder = builder.constant(&constant);
+        }
+        if let Some(proving_system) = get_opt_field(value, "csw_proving_system") {
+            builder = builder.csw_proving_system(
+                proving_system
+                    .as_str()
+                    .ok_or("\"csw_proving_system\" must be a string")?
+                    .parse()?,
+            );
+        }
+        if let Some(vk) = get_opt_field(value, "csw_verification_key") {
+            builder = builder.csw_verification_key(&bytes_from_json(vk, "csw_verification_key")?);
+        }
+        Ok(builder.build()?)
+    }
+
+    fn fixture_hash(&self) -> Result<FieldElement, Error> {
+        self.hash()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        commitment_tree::CommitmentTree,
+        utils::commitment_tree::{rand_fe_with_rng, rand_vec_with_rng},
+    };
+    use rand::Rng;
+
+    #[test]
+    fn sc_creation_params_tests() {
+        let mut rng = rand::thread_rng();
+        let sc_id = rand_fe_with_rng(&mut rng);
+        let amount: u64 = rng.gen();
+        let pub_key: [u8; 32] = rng.gen();
+        let tx_hash: [u8; 32] = rng.gen();
+        let out_idx: u32 = rng.gen();
+        let withdrawal_epoch_length: u32 = rng.gen();
+        let mc_btr_request_data_length: u8 = rng.gen();
+        let custom_field_elements_configs = rand_vec_with_rng(3, &mut rng);
+        let custom_bitvector_elements_configs = vec![BitVectorElementsConfig::default(); 2];
+        let btr_fee: u64 = rng.gen();
+        let ft_min_amount: u64 = rng.gen();
+        let custom_creation_data = rand_vec_with_rng(100, &mut rng);
+        let constant = rand_fe_with_rng(&mut rng);
+        let cert_vk = rand_vec_with_rng(100, &mut rng);
+        let csw_vk = rand_vec_with_rng(100, &mut rng);
+
+        let builder = || {
+            ScCreationParamsBuilder::create(
+                amount,
+                &pub_key,
+                &tx_hash,
+                out_idx,
+                withdrawal_epoch_length,
+                &cert_vk,
+            )
+            .mc_btr_request_data_length(mc_btr_request_data_length)
+            .custom_field_elements_configs(&custom_field_elements_configs)
+            .custom_bitvector_elements_configs(&custom_bitvector_elements_configs)
+            .btr_fee(btr_fee)
+            .ft_min_amount(ft_min_amount)
+            .custom_creation_data(&custom_creation_data)
+            .constant(&constant)
+        };
+
+        // Same SCC leaf as the one added by add_scc
+        let params = builder()
+            .csw_proving_system(ProvingSystem::Darlin)
+            .csw_verification_key(&csw_vk)
+            .build()
+            .unwrap();
+        assert_eq!(params.tx_hash(), &tx_hash);
+        assert_eq!(params.out_idx(), out_idx);
+        assert_eq!(params.csw_proving_system(), Some(ProvingSystem::Darlin));
+        assert_eq!(params.csw_verification_key(), Some(csw_vk.as_slice()));
+
+        let mut cmt = CommitmentTree::create();
+        assert!(cmt.add_scc_params(&sc_id, &params));
+        let mut expected_cmt = CommitmentTree::create();
+        assert!(expected_cmt.add_scc(
+            &sc_id,
+            amount,
+            &pub_key,
+            &tx_hash,
+            out_idx,
+            withdrawal_epoch_length,
+            mc_btr_request_data_length,
+            Some(&custom_field_elements_configs),
+            Some(&custom_bitvector_elements_configs),
+            btr_fee,
+            ft_min_amount,
+            Some(&custom_creation_data),
+            Some(&constant),
+            &cert_vk,
+            Some(&csw_vk),
+        ));
+        assert_eq!(cmt.get_scc(&sc_id), Some(params.hash().unwrap()));
+        assert_eq!(cmt.get_scc(&sc_id), expected_cmt.get_scc(&sc_id));
+
+        // Without CSW support
+        let params = builder().build().unwrap();
+        assert!(params.csw_proving_system().is_none());
+        assert!(params.csw_verification_key().is_none());
+        assert_ne!(cmt.get_scc(&sc_id), Some(params.hash().unwrap()));
+
+        // Inconsistent optional components are rejected
+        assert!(builder()
+            .csw_proving_system(ProvingSystem::CoboundaryMarlin)
+            .build()
+            .is_err());
+        assert!(builder().csw_verification_key(&csw_vk).build().is_err());
+    }
+}
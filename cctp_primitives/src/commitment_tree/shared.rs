@@ -0,0 +1,458 @@
+use crate::{
+    commitment_tree::{
+        incremental_mt::IncrementalMT,
+        proofs::{
+            LeafExistenceProof, ScAbsenceProof, ScExistenceProof, ScNeighbour, ScSubtreeType,
+        },
+        sidechain_tree_alive::SidechainAliveSubtreeType,
+        CommitmentTree,
+    },
+    type_mapping::{FieldElement, GingerMHT},
+};
+use primitives::FieldBasedMerkleTree;
+use std::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+// CommitmentTree shared among threads: multiple readers can query the commitments and the proofs concurrently
+// while a single writer applies changes
+// After each change the incremental Commitment-MT and the data of the changed sidechains are brought up to date by
+// the writer, so that the commitments are answered out of them without mutable access; the finalized Commitment-MT,
+// needed by the proofs, is only built by the first proof query after a change, with exclusive access, and then shared
+// by the following ones. A query falls back to exclusive access whenever the caches aren't up to date
+// Note: queries answered out of the caches don't notify the observer of the CommitmentTree, if any, nor update its stats
+pub struct SharedCommitmentTree {
+    cmt: RwLock<CommitmentTree>,
+}
+
+impl SharedCommitmentTree {
+    // Creates a new instance of SharedCommitmentTree out of a specified CommitmentTree
+    pub fn new(mut cmt: CommitmentTree) -> Self {
+        cmt.refresh_caches();
+        Self {
+            cmt: RwLock::new(cmt),
+        }
+    }
+
+    // Applies changes to the CommitmentTree through a specified function, returning its result
+    // Readers are blocked until the changes have been applied and the caches have been brought up to date
+    pub fn update<R, F: FnOnce(&mut CommitmentTree) -> R>(&self, f: F) -> R {
+        let mut cmt = self.write();
+        let result = f(&mut cmt);
+        cmt.refresh_caches();
+        result
+    }
+
+    // Queries the CommitmentTree through a specified function, concurrently with the other readers
+    // Note: only the getters of CommitmentTree not requiring mutable access are available; the queries of
+    //       SharedCommitmentTree should be used for the commitments and the proofs
+    pub fn read<R, F: FnOnce(&CommitmentTree) -> R>(&self, f: F) -> R {
+        f(&self.read_lock())
+    }
+
+    // Gets commitment for a CommitmentTree
+    // Returns None in the same cases as CommitmentTree::get_commitment
+    pub fn get_commitment(&self) -> Option<FieldElement> {
+        self.query(
+            |cmt| cmt.cached_commitment().map(Some),
+            |cmt| cmt.get_commitment(),
+        )
+    }
+
+    // Gets commitment of a specified SidechainTreeAlive/SidechainTreeCeased
+    // Returns None if SidechainTreeAlive/SidechainTreeCeased with a specified ID doesn't exist in a current CommitmentTree
+    pub fn get_sc_commitment(&self, sc_id: &FieldElement) -> Option<FieldElement> {
+        self.query(
+            |cmt| cmt.cached_sc_commitment(sc_id),
+            |cmt| cmt.get_sc_commitment(sc_id),
+        )
+    }
+
+    // Gets a proof of inclusion of a sidechain with specified ID into a current CommitmentTree
+    // Returns None in the same cases as CommitmentTree::get_sc_existence_proof
+    pub fn get_sc_existence_proof(&self, sc_id: &FieldElement) -> Option<ScExistenceProof> {
+        self.query(
+            |cmt| cmt.cached_sc_existence_proof(sc_id),
+            |cmt| cmt.get_sc_existence_proof(sc_id),
+        )
+    }
+
+    // Gets a proof of non-inclusion of a sidechain with specified ID into a current CommitmentTree
+    // Returns None in the same cases as CommitmentTree::get_sc_absence_proof
+    pub fn get_sc_absence_proof(&self, absent_id: &FieldElement) -> Option<ScAbsenceProof> {
+        self.query(
+            |cmt| cmt.cached_sc_absence_proof(absent_id),
+            |cmt| cmt.get_sc_absence_proof(absent_id),
+        )
+    }
+
+    // Gets a proof of inclusion of the FWT leaf at position leaf_index of a specified SidechainTreeAlive
+    // Returns None in the same cases as CommitmentTree::get_fwt_leaf_existence_proof
+    pub fn get_fwt_leaf_existence_proof(
+        &self,
+        sc_id: &FieldElement,
+        leaf_index: usize,
+    ) -> Option<LeafExistenceProof> {
+        self.get_leaf_existence_proof(sc_id, ScSubtreeType::FWT, leaf_index)
+    }
+
+    // Gets a proof of inclusion of the BWTR leaf at position leaf_index of a specified SidechainTreeAlive
+    // Returns None in the same cases as CommitmentTree::get_bwtr_leaf_existence_proof
+    pub fn get_bwtr_leaf_existence_proof(
+        &self,
+        sc_id: &FieldElement,
+        leaf_index: usize,
+    ) -> Option<LeafExistenceProof> {
+        self.get_leaf_existence_proof(sc_id, ScSubtreeType::BWTR, leaf_index)
+    }
+
+    // Gets a proof of inclusion of the CERT leaf at position leaf_index of a specified SidechainTreeAlive
+    // Returns None in the same cases as CommitmentTree::get_cert_leaf_existence_proof
+    pub fn get_cert_leaf_existence_proof(
+        &self,
+        sc_id: &FieldElement,
+        leaf_index: usize,
+    ) -> Option<LeafExistenceProof> {
+        self.get_leaf_existence_proof(sc_id, ScSubtreeType::CERT, leaf_index)
+    }
+
+    // Gets a proof of inclusion of the CSW leaf at position leaf_index of a specified SidechainTreeCeased
+    // Returns None in the same cases as CommitmentTree::get_csw_leaf_existence_proof
+    pub fn get_csw_leaf_existence_proof(
+        &self,
+        sc_id: &FieldElement,
+        leaf_index: usize,
+    ) -> Option<LeafExistenceProof> {
+        self.get_leaf_existence_proof(sc_id, ScSubtreeType::CSW, leaf_index)
+    }
+
+    // Gets back the CommitmentTree
+    pub fn into_inner(self) -> CommitmentTree {
+        self.cmt.into_inner().unwrap_or_else(|e| e.into_inner())
+    }
+
+    // Gets a proof of inclusion of the leaf at position leaf_index of a subtree of a specified type of a specified sidechain
+    fn get_leaf_existence_proof(
+        &self,
+        sc_id: &FieldElement,
+        subtree_type: ScSubtreeType,
+        leaf_index: usize,
+    ) -> Option<LeafExistenceProof> {
+        self.query(
+            |cmt| cmt.cached_leaf_existence_proof(sc_id, subtree_type, leaf_index),
+            |cmt| cmt.get_leaf_existence_proof(sc_id, subtree_type, leaf_index),
+        )
+    }
+
+    // Answers a query out of the caches, if they are up to date, or by computing it with exclusive access otherwise
+    fn query<R>(
+        &self,
+        cached: impl FnOnce(&CommitmentTree) -> Option<Option<R>>,
+        compute: impl FnOnce(&mut CommitmentTree) -> Option<R>,
+    ) -> Option<R> {
+        if let Some(result) = cached(&self.read_lock()) {
+            return result;
+        }
+        compute(&mut self.write())
+    }
+
+    // Locks the CommitmentTree for reading
+    // A poisoned lock is still usable: each change of the CommitmentTree keeps it consistent
+    fn read_lock(&self) -> RwLockReadGuard<'_, CommitmentTree> {
+        self.cmt.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    // Locks the CommitmentTree for writing
+    fn write(&self) -> RwLockWriteGuard<'_, CommitmentTree> {
+        self.cmt.write().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+// Queries answered out of the caches of a CommitmentTree, without mutable access to it
+// Each one returns None if the caches aren't up to date, otherwise the result of the corresponding query
+impl CommitmentTree {
+    // Brings the caches up to date: updating the incremental Commitment-MT caches the data of the changed sidechains
+    // The finalized Commitment-MT is left to the first proof query, so that a change doesn't rebuild it as a whole
+    fn refresh_caches(&mut self) {
+        self.get_incremental_commitments_tree();
+    }
+
+    // Gets the incremental Commitment-MT, if the SC-commitments of all the changed sidechains have been updated
+    fn cached_incremental_commitments_tree(&self) -> Option<&IncrementalMT> {
+        if self.changed_sc_ids.is_empty() {
+            self.incremental_commitments_tree.as_ref()
+        } else {
+            None
+        }
+    }
+
+    // Gets the finalized Commitment-MT, which is discarded on any change, so it's up to date if present
+    fn cached_commitments_tree(&self) -> Option<&GingerMHT> {
+        self.commitments_tree.as_ref()
+    }
+
+    fn cached_commitment(&self) -> Option<FieldElement> {
+        self.cached_incremental_commitments_tree()?.root().ok()
+    }
+
+    fn cached_sc_commitment(&self, sc_id: &FieldElement) -> Option<Option<FieldElement>> {
+        let sc_commitments = self.cached_incremental_commitments_tree()?.leaves();
+        Some(
+            self.cached_sc_index(sc_id)
+                .and_then(|index| sc_commitments.get(index).copied()),
+        )
+    }
+
+    fn cached_sc_existence_proof(&self, sc_id: &FieldElement) -> Option<Option<ScExistenceProof>> {
+        let cmt = self.cached_commitments_tree()?;
+        Some(
+            self.cached_sc_index(sc_id)
+                .and_then(|index| cmt.get_merkle_path(index))
+                .map(ScExistenceProof::create),
+        )
+    }
+
+    fn cached_sc_absence_proof(&self, absent_id: &FieldElement) -> Option<Option<ScAbsenceProof>> {
+        let cmt = self.cached_commitments_tree()?;
+        let (left, right) = match self.get_neighbours_for_absent(absent_id) {
+            Some(neighbours) => neighbours,
+            None => return Some(None),
+        };
+        let neighbour = |(index, id): (usize, FieldElement)| -> Option<Option<ScNeighbour>> {
+            let sc_data = self.sc_data_cache.get(&id)?.clone();
+            Some(
+                cmt.get_merkle_path(index)
+                    .map(|mpath| ScNeighbour::create(id, mpath, sc_data)),
+            )
+        };
+        let left = match left {
+            Some(left) => match neighbour(left)? {
+                Some(left) => Some(left),
+                None => return Some(None),
+            },
+            None => None,
+        };
+        let right = match right {
+            Some(right) => match neighbour(right)? {
+                Some(right) => Some(right),
+                None => return Some(None),
+            },
+            None => None,
+        };
+        Some(Some(ScAbsenceProof::create(left, right, self.ordering)))
+    }
+
+    fn cached_leaf_existence_proof(
+        &self,
+        sc_id: &FieldElement,
+        subtree_type: ScSubtreeType,
+        leaf_index: usize,
+    ) -> Option<Option<LeafExistenceProof>> {
+        let cmt = self.cached_commitments_tree()?;
+        let index = match self.cached_sc_index(sc_id) {
+            Some(index) => index,
+            None => return Some(None),
+        };
+        let sc_data = self.sc_data_cache.get(sc_id)?.clone();
+        // Paths to the empty leaves of a subtree are available too, but they don't prove anything
+        let leaf_mpath = match (subtree_type, self.get_scta(sc_id), self.get_sctc(sc_id)) {
+            (ScSubtreeType::FWT, Some(scta), _)
+                if leaf_index < scta.num_leaves(&SidechainAliveSubtreeType::FWT) =>
+            {
+                scta.get_fwt_merkle_path(leaf_index)
+            }
+            (ScSubtreeType::BWTR, Some(scta), _)
+                if leaf_index < scta.num_leaves(&SidechainAliveSubtreeType::BWTR) =>
+            {
+                scta.get_bwtr_merkle_path(leaf_index)
+            }
+            (ScSubtreeType::CERT, Some(scta), _)
+                if leaf_index < scta.num_leaves(&SidechainAliveSubtreeType::CERT) =>
+            {
+                scta.get_cert_merkle_path(leaf_index)
+            }
+            (ScSubtreeType::CSW, _, Some(sctc)) if leaf_index < sctc.num_csw_leaves() => {
+                sctc.get_csw_merkle_path(leaf_index)
+            }
+            _ => None,
+        };
+        Some(leaf_mpath.and_then(|leaf_mpath| {
+            cmt.get_merkle_path(index).map(|mpath| {
+                LeafExistenceProof::create(leaf_mpath, sc_data, ScExistenceProof::create(mpath))
+            })
+        }))
+    }
+
+    // Gets the position of the SC-commitment of a specified sidechain inside of the Commitment-MT
+    fn cached_sc_index(&self, sc_id: &FieldElement) -> Option<usize> {
+        self.get_indexed_sc_ids()
+            .into_iter()
+            .find(|(_, id)| *id == sc_id)
+            .map(|(index, _)| index)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        commitment_tree::{CommitmentTreeOrdering, CommitmentTreeStats},
+        testing::{corner_case_absent_ids, sorted_sc_ids_with_rng},
+        utils::commitment_tree::rand_fe_with_rng,
+    };
+    use std::{sync::Arc, thread};
+
+    fn assert_send_sync<T: Send + Sync>() {}
+
+    #[test]
+    fn shared_commitment_tree_tests() {
+        assert_send_sync::<SharedCommitmentTree>();
+
+        let mut rng = rand::thread_rng();
+        let ordering = CommitmentTreeOrdering::default();
+        let sc_ids = sorted_sc_ids_with_rng(6, ordering, &mut rng);
+        let absent_ids = corner_case_absent_ids(&sc_ids);
+        let leaves = (0..sc_ids.len())
+            .map(|_| rand_fe_with_rng(&mut rng))
+            .collect::<Vec<_>>();
+
+        // Same answers as the ones of the CommitmentTree
+        let mut expected_cmt = CommitmentTree::create_with_ordering(ordering);
+        let shared = Arc::new(SharedCommitmentTree::new(
+            CommitmentTree::create_with_ordering(ordering),
+        ));
+        for (i, sc_id) in sc_ids.iter().enumerate() {
+            let leaf = leaves[i];
+            if i % 2 == 0 {
+                assert!(expected_cmt.add_fwt_leaf(sc_id, &leaf));
+                assert!(shared.update(|cmt| cmt.add_fwt_leaf(sc_id, &leaf)));
+            } else {
+                assert!(expected_cmt.add_csw_leaf(sc_id, &leaf));
+                assert!(shared.update(|cmt| cmt.add_csw_leaf(sc_id, &leaf)));
+            }
+        }
+        let commitment = expected_cmt.get_commitment();
+        assert!(commitment.is_some());
+
+        // Commitments are answered out of the incremental Commitment-MT, without building the finalized one
+        let stats = shared.read(|cmt| cmt.stats());
+        assert_eq!(shared.get_commitment(), commitment);
+        for sc_id in sc_ids.iter() {
+            assert_eq!(
+                shared.get_sc_commitment(sc_id),
+                expected_cmt.get_sc_commitment(sc_id)
+            );
+        }
+        assert!(shared.read(|cmt| cmt.cached_commitments_tree().is_none()));
+        assert_eq!(shared.read(|cmt| cmt.stats()), stats);
+
+        // The finalized Commitment-MT is built by the first proof query only
+        assert!(shared.get_sc_existence_proof(&sc_ids[0]).is_some());
+        let stats = shared.read(|cmt| cmt.stats());
+        assert!(shared.read(|cmt| cmt.cached_commitments_tree().is_some()));
+
+        // Readers query the CommitmentTree concurrently
+        let readers = (0..4)
+            .map(|_| {
+                let shared = shared.clone();
+                let sc_ids = sc_ids.clone();
+                let absent_ids = absent_ids.clone();
+                let leaves = leaves.clone();
+                thread::spawn(move || {
+                    for (i, sc_id) in sc_ids.iter().enumerate() {
+                        let (subtree_type, proof) = if i % 2 == 0 {
+                            (
+                                ScSubtreeType::FWT,
+                                shared.get_fwt_leaf_existence_proof(sc_id, 0),
+                            )
+                        } else {
+                            (
+                                ScSubtreeType::CSW,
+                                shared.get_csw_leaf_existence_proof(sc_id, 0),
+                            )
+                        };
+                        assert!(CommitmentTree::verify_leaf_inclusion(
+                            sc_id,
+                            subtree_type,
+                            &leaves[i],
+                            &proof.unwrap(),
+                            &commitment.unwrap()
+                        ));
+                        assert!(shared.get_fwt_leaf_existence_proof(sc_id, 1).is_none());
+                        assert!(shared.get_csw_leaf_existence_proof(sc_id, 1).is_none());
+
+                        assert!(shared.get_sc_commitment(sc_id).is_some());
+                        let proof = shared.get_sc_existence_proof(sc_id).unwrap();
+                        assert!(CommitmentTree::verify_sc_commitment(
+                            &shared.get_sc_commitment(sc_id).unwrap(),
+                            &proof,
+                            &commitment.unwrap()
+                        ));
+                        assert!(shared.get_sc_absence_proof(sc_id).is_none());
+                    }
+                    for absent_id in absent_ids.iter() {
+                        assert!(shared.get_sc_commitment(absent_id).is_none());
+                        assert!(shared.get_sc_existence_proof(absent_id).is_none());
+                        let proof = shared.get_sc_absence_proof(absent_id).unwrap();
+                        assert!(CommitmentTree::verify_sc_absence(
+                            absent_id,
+                            &proof,
+                            &commitment.unwrap()
+                        ));
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+        for reader in readers {
+            reader.join().unwrap();
+        }
+
+        // Queries are answered out of the caches
+        assert_eq!(shared.read(|cmt| cmt.stats()), stats);
+        for sc_id in sc_ids.iter() {
+            assert_eq!(
+                shared.get_sc_commitment(sc_id),
+                expected_cmt.get_sc_commitment(sc_id)
+            );
+            assert_eq!(
+                shared.get_sc_existence_proof(sc_id),
+                expected_cmt.get_sc_existence_proof(sc_id)
+            );
+            assert_eq!(
+                shared.get_fwt_leaf_existence_proof(sc_id, 0),
+                expected_cmt.get_fwt_leaf_existence_proof(sc_id, 0)
+            );
+            assert_eq!(
+                shared.get_csw_leaf_existence_proof(sc_id, 0),
+                expected_cmt.get_csw_leaf_existence_proof(sc_id, 0)
+            );
+        }
+        for absent_id in absent_ids.iter() {
+            assert_eq!(
+                shared.get_sc_absence_proof(absent_id),
+                expected_cmt.get_sc_absence_proof(absent_id)
+            );
+            assert!(shared.get_cert_leaf_existence_proof(absent_id, 0).is_none());
+        }
+        assert_eq!(shared.read(|cmt| cmt.stats()), stats);
+
+        // A writer changes the CommitmentTree while readers query it
+        let writer = {
+            let shared = shared.clone();
+            let sc_id = sc_ids[0];
+            thread::spawn(move || {
+                for _ in 0..10 {
+                    let leaf = rand_fe_with_rng(&mut rand::thread_rng());
+                    assert!(shared.update(|cmt| cmt.add_fwt_leaf(&sc_id, &leaf)));
+                }
+            })
+        };
+        for _ in 0..10 {
+            assert!(shared.get_commitment().is_some());
+            assert!(shared.get_sc_existence_proof(&sc_ids[1]).is_some());
+        }
+        writer.join().unwrap();
+        let mut cmt = Arc::try_unwrap(shared).ok().unwrap().into_inner();
+        assert_eq!(cmt.get_fwt_count(&sc_ids[0]), Some(11));
+        assert_ne!(cmt.get_commitment(), commitment);
+        assert_ne!(cmt.stats(), CommitmentTreeStats::default());
+    }
+}
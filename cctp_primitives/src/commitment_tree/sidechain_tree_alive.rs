@@ -1,6 +1,8 @@
+use crate::commitment_tree::cert_accumulator::CertLinearAccumulator;
+use crate::commitment_tree::config::CommitmentTreeConfig;
 use crate::type_mapping::{Error, FieldElement, GingerMHT, GingerMHTPath};
-use crate::utils::commitment_tree::{add_leaf, hash_vec, new_mt};
-use algebra::Field;
+use crate::utils::commitment_tree::{add_leaf, hash_vec, new_sc_subtree_mt};
+use algebra::{serialize::*, Field};
 use primitives::FieldBasedMerkleTree;
 
 // Tunable parameters
@@ -9,6 +11,7 @@ pub const BWTR_MT_HEIGHT: usize = 12;
 pub const CERT_MT_HEIGHT: usize = 12;
 
 // Types of contained subtrees
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum SidechainAliveSubtreeType {
     FWT,
     BWTR,
@@ -23,11 +26,22 @@ pub struct SidechainTreeAlive {
     fwt_mt: GingerMHT,  // MT for Forward Transfer Transactions
     bwtr_mt: GingerMHT, // MT for Backward Transfers Requests Transactions
     cert_mt: GingerMHT, // MT for Certificates
+    cert_height: usize, // height of the MT for Certificates, which is rebuilt when its leaves are restored
+
+    cert_acc: Option<CertLinearAccumulator>, // optional hash-chain of Certificates, maintained alongside the CERT MT
 }
 
 impl SidechainTreeAlive {
     // Creates a new instance of SidechainTreeAlive with a specified ID
     pub fn create(sc_id: &FieldElement) -> Result<Self, Error> {
+        Self::create_with_config(sc_id, &CommitmentTreeConfig::default())
+    }
+
+    // Creates a new instance of SidechainTreeAlive with a specified ID, whose subtrees have the heights of a specified config
+    pub fn create_with_config(
+        sc_id: &FieldElement,
+        config: &CommitmentTreeConfig,
+    ) -> Result<Self, Error> {
         Ok(Self {
             sc_id: *sc_id,
 
@@ -35,9 +49,12 @@ impl SidechainTreeAlive {
             scc: FieldElement::zero(),
 
             // Default leaves values of an empty GingerMHT are also FieldElement::zero(); They are specified in MHT_PARAMETERS as 0-level nodes
-            fwt_mt: new_mt(FWT_MT_HEIGHT)?,
-            bwtr_mt: new_mt(BWTR_MT_HEIGHT)?,
-            cert_mt: new_mt(CERT_MT_HEIGHT)?,
+            fwt_mt: new_sc_subtree_mt(config.fwt_height)?,
+            bwtr_mt: new_sc_subtree_mt(config.bwtr_height)?,
+            cert_mt: new_sc_subtree_mt(config.cert_height)?,
+            cert_height: config.cert_height,
+
+            cert_acc: None,
         })
     }
 
@@ -56,9 +73,72 @@ impl SidechainTreeAlive {
         add_leaf(&mut self.bwtr_mt, bwtr)
     }
 
-    // Sequentially adds leafs to the CERT MT
+    // Sequentially adds leafs to the CERT MT, and to the Certificates hash-chain if it's enabled
+    // Returns false, leaving both untouched, if the leaf can't be added to any of them
     pub fn add_cert(&mut self, cert: &FieldElement) -> bool {
-        add_leaf(&mut self.cert_mt, cert)
+        let next_acc = match self.cert_acc.as_ref().map(|acc| acc.next(cert)) {
+            Some(Ok(next_acc)) => Some(next_acc),
+            Some(Err(_)) => return false,
+            None => None,
+        };
+        if !add_leaf(&mut self.cert_mt, cert) {
+            return false;
+        }
+        if next_acc.is_some() {
+            self.cert_acc = next_acc;
+        }
+        true
+    }
+
+    // Builds a CERT MT of the specified height made of the specified leaves, provided that its root is the expected one
+    // Returns None if the leaves don't fit the CERT MT or their root is a different one
+    pub fn build_cert_mt(
+        height: usize,
+        leaves: &[FieldElement],
+        root: &FieldElement,
+    ) -> Option<GingerMHT> {
+        let mut cert_mt = new_sc_subtree_mt(height).ok()?;
+        if !leaves.iter().all(|leaf| add_leaf(&mut cert_mt, leaf)) {
+            return None;
+        }
+        match cert_mt.finalize() {
+            Ok(finalized) if finalized.root().as_ref() == Some(root) => Some(cert_mt),
+            _ => None,
+        }
+    }
+
+    // Replaces the CERT MT with the specified one, e.g. as built by build_cert_mt
+    // The Certificates hash-chain, if enabled, is rebuilt accordingly
+    // Returns false, leaving the SidechainTreeAlive untouched, if the hash-chain can't be rebuilt
+    pub fn restore_cert_mt(&mut self, cert_mt: GingerMHT) -> bool {
+        let cert_acc = match self.cert_acc {
+            Some(_) => match CertLinearAccumulator::from_leaves(cert_mt.get_leaves().iter()) {
+                Ok(cert_acc) => Some(cert_acc),
+                Err(_) => return false,
+            },
+            None => None,
+        };
+
+        self.cert_mt = cert_mt;
+        self.cert_acc = cert_acc;
+        true
+    }
+
+    // Enables (building it out of the leaves of the CERT MT) or disables the Certificates hash-chain
+    pub fn set_cert_linear_accumulator(&mut self, enabled: bool) -> Result<(), Error> {
+        self.cert_acc = if enabled {
+            Some(CertLinearAccumulator::from_leaves(
+                self.cert_mt.get_leaves().iter(),
+            )?)
+        } else {
+            None
+        };
+        Ok(())
+    }
+
+    // Gets the Certificates hash-chain, if it's enabled
+    pub fn get_cert_linear_accumulator(&self) -> Option<&CertLinearAccumulator> {
+        self.cert_acc.as_ref()
     }
 
     // Sets SCC value
@@ -71,6 +151,17 @@ impl SidechainTreeAlive {
         self.scc
     }
 
+    // Gets the number of leaves added to a subtree of a specified type
+    // Note: SCC is a single value, so it's considered as a subtree with one leaf
+    pub(crate) fn num_leaves(&self, subtree_type: &SidechainAliveSubtreeType) -> usize {
+        match subtree_type {
+            SidechainAliveSubtreeType::FWT => self.fwt_mt.get_leaves().len(),
+            SidechainAliveSubtreeType::BWTR => self.bwtr_mt.get_leaves().len(),
+            SidechainAliveSubtreeType::CERT => self.cert_mt.get_leaves().len(),
+            SidechainAliveSubtreeType::SCC => 1,
+        }
+    }
+
     // Gets all leaves of the FWT MT
     pub fn get_fwt_leaves(&self) -> Vec<FieldElement> {
         self.fwt_mt.get_leaves().to_vec()
@@ -84,72 +175,85 @@ impl SidechainTreeAlive {
         self.cert_mt.get_leaves().to_vec()
     }
 
-    // Gets merkle path to the Forward Transfer in the tree
-    pub fn get_fwt_merkle_path(&mut self, leaf_index: usize) -> Option<GingerMHTPath> {
-        match self.fwt_mt.finalize() {
-            Ok(finalized_tree) => finalized_tree.get_merkle_path(leaf_index),
-            Err(_) => None,
-        }
+    // Iterates over the leaves of the FWT MT, without cloning them
+    pub fn iter_fwt_leaves(&self) -> impl Iterator<Item = &FieldElement> {
+        self.fwt_mt.get_leaves().iter()
+    }
+    // Iterates over the leaves of the BWTR MT, without cloning them
+    pub fn iter_bwtr_leaves(&self) -> impl Iterator<Item = &FieldElement> {
+        self.bwtr_mt.get_leaves().iter()
+    }
+    // Iterates over the leaves of the CERT MT, without cloning them
+    pub fn iter_cert_leaves(&self) -> impl Iterator<Item = &FieldElement> {
+        self.cert_mt.get_leaves().iter()
     }
 
-    // Gets merkle path to the Forward Transfer in the tree
-    pub fn get_bwtr_merkle_path(&mut self, leaf_index: usize) -> Option<GingerMHTPath> {
-        match self.bwtr_mt.finalize() {
-            Ok(finalized_tree) => finalized_tree.get_merkle_path(leaf_index),
-            Err(_) => None,
-        }
+    // Finalizes the SidechainTreeAlive, computing the roots of its subtrees and its commitment
+    // Returns an error if some of them can't be computed
+    pub fn finalize(mut self) -> Result<FinalizedSidechainTreeAlive, Error> {
+        self.fwt_mt.finalize_in_place()?;
+        self.bwtr_mt.finalize_in_place()?;
+        self.cert_mt.finalize_in_place()?;
+        FinalizedSidechainTreeAlive::from_finalized_subtrees(
+            self.sc_id,
+            self.scc,
+            self.fwt_mt,
+            self.bwtr_mt,
+            self.cert_mt,
+        )
+    }
+
+    // Same as finalize, but leaves the current SidechainTreeAlive untouched, so that more leaves can be added to it
+    pub fn finalized(&self) -> Result<FinalizedSidechainTreeAlive, Error> {
+        FinalizedSidechainTreeAlive::from_finalized_subtrees(
+            self.sc_id,
+            self.scc,
+            self.fwt_mt.finalize()?,
+            self.bwtr_mt.finalize()?,
+            self.cert_mt.finalize()?,
+        )
     }
 
     // Gets merkle path to the Forward Transfer in the tree
-    pub fn get_cert_merkle_path(&mut self, leaf_index: usize) -> Option<GingerMHTPath> {
-        match self.cert_mt.finalize() {
-            Ok(finalized_tree) => finalized_tree.get_merkle_path(leaf_index),
-            Err(_) => None,
-        }
+    pub fn get_fwt_merkle_path(&self, leaf_index: usize) -> Option<GingerMHTPath> {
+        self.fwt_mt.finalize().ok()?.get_merkle_path(leaf_index)
+    }
+
+    // Gets merkle path to the Backward Transfer Request in the tree
+    pub fn get_bwtr_merkle_path(&self, leaf_index: usize) -> Option<GingerMHTPath> {
+        self.bwtr_mt.finalize().ok()?.get_merkle_path(leaf_index)
+    }
+
+    // Gets merkle path to the Certificate in the tree
+    pub fn get_cert_merkle_path(&self, leaf_index: usize) -> Option<GingerMHTPath> {
+        self.cert_mt.finalize().ok()?.get_merkle_path(leaf_index)
     }
 
     // Gets commitment (root) of the Forward Transfer Transactions tree
     pub fn get_fwt_commitment(&mut self) -> Option<FieldElement> {
-        match self.fwt_mt.finalize() {
-            Ok(finalized_tree) => finalized_tree.root(),
-            Err(_) => None,
-        }
+        self.fwt_mt.finalize().ok()?.root()
     }
 
     // Gets commitment (root) of the Backward Transfer Requests Transactions tree
     pub fn get_bwtr_commitment(&mut self) -> Option<FieldElement> {
-        match self.bwtr_mt.finalize() {
-            Ok(finalized_tree) => finalized_tree.root(),
-            Err(_) => None,
-        }
+        self.bwtr_mt.finalize().ok()?.root()
     }
 
     // Gets commitment (root) of the Certificates tree
     pub fn get_cert_commitment(&mut self) -> Option<FieldElement> {
-        match self.cert_mt.finalize() {
-            Ok(finalized_tree) => finalized_tree.root(),
-            Err(_) => None,
-        }
+        self.cert_mt.finalize().ok()?.root()
     }
 
     // Gets commitment of a SidechainTreeAlive
+    // Returns None, logging the reason, if it can't be computed
     pub fn get_commitment(&mut self) -> Option<FieldElement> {
-        SidechainTreeAlive::build_commitment(
-            self.sc_id,
-            match self.get_fwt_commitment() {
-                Some(v) => v,
-                None => return None,
-            },
-            match self.get_bwtr_commitment() {
-                Some(v) => v,
-                None => return None,
-            },
-            match self.get_cert_commitment() {
-                Some(v) => v,
-                None => return None,
-            },
-            self.scc,
-        )
+        match self.finalized() {
+            Ok(finalized) => Some(finalized.get_commitment()),
+            Err(e) => {
+                log::error!("{}", e);
+                None
+            }
+        }
     }
 
     // Builds Commitment for SidechainTreeAlive as: hash( fwt_root | bwtr_root | cert_root | SCC | SC_ID )
@@ -170,6 +274,156 @@ impl SidechainTreeAlive {
     }
 }
 
+// A SidechainTreeAlive is serialized as its ID, its SCC, the leaves of its subtrees and whether the Certificates
+// hash-chain is enabled; on deserialization the subtrees (and the hash-chain) are rebuilt out of the leaves
+impl CanonicalSerialize for SidechainTreeAlive {
+    fn serialize<W: Write>(&self, mut writer: W) -> Result<(), SerializationError> {
+        CanonicalSerialize::serialize(&self.sc_id, &mut writer)?;
+        CanonicalSerialize::serialize(&self.scc, &mut writer)?;
+        CanonicalSerialize::serialize(&self.get_fwt_leaves(), &mut writer)?;
+        CanonicalSerialize::serialize(&self.get_bwtr_leaves(), &mut writer)?;
+        CanonicalSerialize::serialize(&self.get_cert_leaves(), &mut writer)?;
+        CanonicalSerialize::serialize(&self.cert_acc.is_some(), &mut writer)
+    }
+
+    fn serialized_size(&self) -> usize {
+        self.sc_id.serialized_size()
+            + self.scc.serialized_size()
+            + self.get_fwt_leaves().serialized_size()
+            + self.get_bwtr_leaves().serialized_size()
+            + self.get_cert_leaves().serialized_size()
+            + self.cert_acc.is_some().serialized_size()
+    }
+}
+
+impl CanonicalDeserialize for SidechainTreeAlive {
+    fn deserialize<R: Read>(reader: R) -> Result<Self, SerializationError> {
+        Self::deserialize_with_config(reader, &CommitmentTreeConfig::default())
+    }
+}
+
+impl SidechainTreeAlive {
+    // Deserializes a SidechainTreeAlive, rebuilding its subtrees with the heights of a specified config
+    pub(crate) fn deserialize_with_config<R: Read>(
+        mut reader: R,
+        config: &CommitmentTreeConfig,
+    ) -> Result<Self, SerializationError> {
+        let sc_id: FieldElement = CanonicalDeserialize::deserialize(&mut reader)?;
+        let scc: FieldElement = CanonicalDeserialize::deserialize(&mut reader)?;
+        let fwt: Vec<FieldElement> = CanonicalDeserialize::deserialize(&mut reader)?;
+        let bwtr: Vec<FieldElement> = CanonicalDeserialize::deserialize(&mut reader)?;
+        let cert: Vec<FieldElement> = CanonicalDeserialize::deserialize(&mut reader)?;
+        let cert_acc_enabled: bool = CanonicalDeserialize::deserialize(&mut reader)?;
+
+        let mut sct = Self::create_with_config(&sc_id, config)
+            .map_err(|_| SerializationError::InvalidData)?;
+        if !(fwt.iter().all(|leaf| sct.add_fwt(leaf))
+            && bwtr.iter().all(|leaf| sct.add_bwtr(leaf))
+            && cert.iter().all(|leaf| sct.add_cert(leaf)))
+        {
+            return Err(SerializationError::InvalidData);
+        }
+        sct.set_scc(&scc);
+        sct.set_cert_linear_accumulator(cert_acc_enabled)
+            .map_err(|_| SerializationError::InvalidData)?;
+        Ok(sct)
+    }
+}
+
+// SidechainTreeAlive whose subtrees have been finalized: all its commitments are available
+pub struct FinalizedSidechainTreeAlive {
+    sc_id: FieldElement,
+    scc: FieldElement,
+
+    fwt_mt: GingerMHT,
+    bwtr_mt: GingerMHT,
+    cert_mt: GingerMHT,
+
+    fwt_root: FieldElement,
+    bwtr_root: FieldElement,
+    cert_root: FieldElement,
+    commitment: FieldElement,
+}
+
+impl FinalizedSidechainTreeAlive {
+    fn from_finalized_subtrees(
+        sc_id: FieldElement,
+        scc: FieldElement,
+        fwt_mt: GingerMHT,
+        bwtr_mt: GingerMHT,
+        cert_mt: GingerMHT,
+    ) -> Result<Self, Error> {
+        let fwt_root = fwt_mt.root().ok_or("Unable to compute the FWT tree root")?;
+        let bwtr_root = bwtr_mt
+            .root()
+            .ok_or("Unable to compute the BWTR tree root")?;
+        let cert_root = cert_mt
+            .root()
+            .ok_or("Unable to compute the CERT tree root")?;
+        let commitment = hash_vec(vec![fwt_root, bwtr_root, cert_root, scc, sc_id])?;
+
+        Ok(Self {
+            sc_id,
+            scc,
+            fwt_mt,
+            bwtr_mt,
+            cert_mt,
+            fwt_root,
+            bwtr_root,
+            cert_root,
+            commitment,
+        })
+    }
+
+    // Gets ID of the sidechain
+    pub fn id(&self) -> &FieldElement {
+        &self.sc_id
+    }
+
+    // Gets SCC value
+    pub fn get_scc(&self) -> FieldElement {
+        self.scc
+    }
+
+    // Gets commitment (root) of the Forward Transfer Transactions tree
+    pub fn get_fwt_commitment(&self) -> FieldElement {
+        self.fwt_root
+    }
+
+    // Gets commitment (root) of the Backward Transfer Requests Transactions tree
+    pub fn get_bwtr_commitment(&self) -> FieldElement {
+        self.bwtr_root
+    }
+
+    // Gets commitment (root) of the Certificates tree
+    pub fn get_cert_commitment(&self) -> FieldElement {
+        self.cert_root
+    }
+
+    // Gets commitment of the SidechainTreeAlive
+    pub fn get_commitment(&self) -> FieldElement {
+        self.commitment
+    }
+
+    // Gets merkle path to the Forward Transfer in the tree
+    // Returns None if leaf_index is out of range
+    pub fn get_fwt_merkle_path(&self, leaf_index: usize) -> Option<GingerMHTPath> {
+        self.fwt_mt.get_merkle_path(leaf_index)
+    }
+
+    // Gets merkle path to the Backward Transfer Request in the tree
+    // Returns None if leaf_index is out of range
+    pub fn get_bwtr_merkle_path(&self, leaf_index: usize) -> Option<GingerMHTPath> {
+        self.bwtr_mt.get_merkle_path(leaf_index)
+    }
+
+    // Gets merkle path to the Certificate in the tree
+    // Returns None if leaf_index is out of range
+    pub fn get_cert_merkle_path(&self, leaf_index: usize) -> Option<GingerMHTPath> {
+        self.cert_mt.get_merkle_path(leaf_index)
+    }
+}
+
 #[cfg(test)]
 mod test {
     use crate::commitment_tree::sidechain_tree_alive::SidechainTreeAlive;
@@ -263,4 +517,54 @@ mod test {
         let cert_num_leaves = 1 << sct.cert_mt.height();
         assert!(sct.get_cert_merkle_path(cert_num_leaves).is_none());
     }
+
+    #[test]
+    fn finalized_sidechain_tree_tests() {
+        let sc_id = FieldElement::one();
+        let fe = FieldElement::one() + FieldElement::one();
+
+        let mut sct = SidechainTreeAlive::create(&sc_id).unwrap();
+        assert!(sct.add_fwt(&fe));
+        assert!(sct.add_cert(&fe));
+        sct.set_scc(&fe);
+
+        // Finalizing a copy and consuming the tree give the same results as the fallible getters
+        let finalized_copy = sct.finalized().unwrap();
+        assert_eq!(finalized_copy.id(), &sc_id);
+        assert_eq!(finalized_copy.get_scc(), fe);
+        assert_eq!(
+            Some(finalized_copy.get_fwt_commitment()),
+            sct.get_fwt_commitment()
+        );
+        assert_eq!(
+            Some(finalized_copy.get_bwtr_commitment()),
+            sct.get_bwtr_commitment()
+        );
+        assert_eq!(
+            Some(finalized_copy.get_cert_commitment()),
+            sct.get_cert_commitment()
+        );
+        assert_eq!(Some(finalized_copy.get_commitment()), sct.get_commitment());
+        assert_eq!(
+            finalized_copy.get_fwt_merkle_path(0),
+            sct.get_fwt_merkle_path(0)
+        );
+
+        // More leaves can still be added after finalized()
+        assert!(sct.add_bwtr(&fe));
+        let bwtr_commitment = sct.get_bwtr_commitment().unwrap();
+        assert_ne!(bwtr_commitment, finalized_copy.get_bwtr_commitment());
+
+        let finalized = sct.finalize().unwrap();
+        assert_eq!(finalized.get_bwtr_commitment(), bwtr_commitment);
+        assert!(mht::verify_ginger_merkle_path_without_length_check(
+            &finalized.get_bwtr_merkle_path(0).unwrap(),
+            &fe,
+            &bwtr_commitment
+        ));
+        assert_ne!(finalized.get_commitment(), finalized_copy.get_commitment());
+        assert!(finalized
+            .get_cert_merkle_path(1 << finalized.cert_mt.height())
+            .is_none());
+    }
 }
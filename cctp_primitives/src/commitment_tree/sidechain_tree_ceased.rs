@@ -1,5 +1,7 @@
-use crate::type_mapping::{Error, FieldElement, GingerMHT};
-use crate::utils::commitment_tree::{add_leaf, hash_vec, new_mt};
+use crate::commitment_tree::config::CommitmentTreeConfig;
+use crate::type_mapping::{Error, FieldElement, GingerMHT, GingerMHTPath};
+use crate::utils::commitment_tree::{add_leaf, hash_vec, new_sc_subtree_mt};
+use algebra::serialize::*;
 use primitives::FieldBasedMerkleTree;
 
 // Tunable parameters
@@ -13,9 +15,17 @@ pub struct SidechainTreeCeased {
 impl SidechainTreeCeased {
     // Creates a new instance of SidechainTree with a specified ID
     pub fn create(sc_id: &FieldElement) -> Result<Self, Error> {
+        Self::create_with_config(sc_id, &CommitmentTreeConfig::default())
+    }
+
+    // Creates a new instance of SidechainTreeCeased with a specified ID, whose CSW subtree has the height of a specified config
+    pub fn create_with_config(
+        sc_id: &FieldElement,
+        config: &CommitmentTreeConfig,
+    ) -> Result<Self, Error> {
         Ok(Self {
             sc_id: *sc_id,
-            csw_mt: new_mt(CSW_MT_HEIGHT)?,
+            csw_mt: new_sc_subtree_mt(config.csw_height)?,
         })
     }
 
@@ -29,6 +39,29 @@ impl SidechainTreeCeased {
         add_leaf(&mut self.csw_mt, csw)
     }
 
+    // Gets the number of leaves added to the CSW MT
+    pub(crate) fn num_csw_leaves(&self) -> usize {
+        self.csw_mt.get_leaves().len()
+    }
+
+    // Gets all leaves of the CSW MT
+    pub fn get_csw_leaves(&self) -> Vec<FieldElement> {
+        self.csw_mt.get_leaves().to_vec()
+    }
+
+    // Iterates over the leaves of the CSW MT, without cloning them
+    pub fn iter_csw_leaves(&self) -> impl Iterator<Item = &FieldElement> {
+        self.csw_mt.get_leaves().iter()
+    }
+
+    // Gets merkle path to the Ceased Sidechain Withdrawal in the tree
+    pub fn get_csw_merkle_path(&self, leaf_index: usize) -> Option<GingerMHTPath> {
+        match self.csw_mt.finalize() {
+            Ok(finalized_tree) => finalized_tree.get_merkle_path(leaf_index),
+            Err(_) => None,
+        }
+    }
+
     // Gets commitment of the Ceased Sidechain Withdrawals tree
     pub fn get_csw_commitment(&mut self) -> Option<FieldElement> {
         match self.csw_mt.finalize() {
@@ -60,11 +93,49 @@ impl SidechainTreeCeased {
     }
 }
 
+// A SidechainTreeCeased is serialized as its ID and the leaves of its CSW subtree, which is rebuilt on deserialization
+impl CanonicalSerialize for SidechainTreeCeased {
+    fn serialize<W: Write>(&self, mut writer: W) -> Result<(), SerializationError> {
+        CanonicalSerialize::serialize(&self.sc_id, &mut writer)?;
+        CanonicalSerialize::serialize(&self.csw_mt.get_leaves().to_vec(), &mut writer)
+    }
+
+    fn serialized_size(&self) -> usize {
+        self.sc_id.serialized_size() + self.csw_mt.get_leaves().to_vec().serialized_size()
+    }
+}
+
+impl CanonicalDeserialize for SidechainTreeCeased {
+    fn deserialize<R: Read>(reader: R) -> Result<Self, SerializationError> {
+        Self::deserialize_with_config(reader, &CommitmentTreeConfig::default())
+    }
+}
+
+impl SidechainTreeCeased {
+    // Deserializes a SidechainTreeCeased, rebuilding its CSW subtree with the height of a specified config
+    pub(crate) fn deserialize_with_config<R: Read>(
+        mut reader: R,
+        config: &CommitmentTreeConfig,
+    ) -> Result<Self, SerializationError> {
+        let sc_id: FieldElement = CanonicalDeserialize::deserialize(&mut reader)?;
+        let csw: Vec<FieldElement> = CanonicalDeserialize::deserialize(&mut reader)?;
+
+        let mut sctc = Self::create_with_config(&sc_id, config)
+            .map_err(|_| SerializationError::InvalidData)?;
+        if !csw.iter().all(|leaf| sctc.add_csw(leaf)) {
+            return Err(SerializationError::InvalidData);
+        }
+        Ok(sctc)
+    }
+}
+
 #[cfg(test)]
 mod test {
     use crate::commitment_tree::sidechain_tree_ceased::SidechainTreeCeased;
     use crate::type_mapping::FieldElement;
+    use crate::utils::mht;
     use algebra::Field;
+    use primitives::FieldBasedMerkleTree;
 
     #[test]
     fn sidechain_tree_ceased_tests() {
@@ -84,5 +155,18 @@ mod test {
         assert_ne!(empty_csw, sctc.get_csw_commitment());
         // SCTC commitment has non-empty value
         assert_ne!(empty_comm, sctc.get_commitment());
+
+        // Merkle path to existing leaf should be present and valid
+        let csw_merkle_path_opt = sctc.get_csw_merkle_path(0);
+        assert!(csw_merkle_path_opt.is_some());
+        assert!(mht::verify_ginger_merkle_path_without_length_check(
+            &csw_merkle_path_opt.unwrap(),
+            &fe,
+            &sctc.get_csw_commitment().unwrap()
+        ));
+
+        // No merkle path for out of range leaf
+        let csw_num_leaves = 1 << sctc.csw_mt.height();
+        assert!(sctc.get_csw_merkle_path(csw_num_leaves).is_none());
     }
 }
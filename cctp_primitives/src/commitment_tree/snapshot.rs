@@ -0,0 +1,14 @@
+use crate::type_mapping::FieldElement;
+use algebra::serialize::*;
+
+// State of the CERT subtree of a sidechain at a given point, e.g. at each mainchain block of an epoch,
+// as needed by the certifier for its circuit witnesses
+// The leaves are included, so that more Certificates can be appended to a CommitmentTree restored from the snapshot
+// through CommitmentTree::restore_cert_subtree, which also checks root and leaf count against them
+#[derive(Clone, Debug, PartialEq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct CertSubtreeSnapshot {
+    pub sc_id: FieldElement, // ID of the sidechain the CERT subtree belongs to
+    pub root: FieldElement,  // root of the CERT subtree
+    pub num_leaves: u32,     // number of Certificates in the CERT subtree
+    pub leaves: Vec<FieldElement>, // hashes of the Certificates in the CERT subtree, in order
+}
@@ -0,0 +1,307 @@
+//! Single entry point for the initialization of the library.
+//! `cctp_init()` loads the committer keys required by the configured proving systems and
+//! stores the configuration, so that it can be queried by the components of the library
+//! (and by the embedding application) through `get_cctp_config()`.
+//! `cctp_shutdown()` frees all the resources, allowing the library to be initialized again.
+//! The serialization policy of the configuration becomes the process-wide `SerializationPolicy`.
+//! The thread budget of the configuration bounds the threads of the library: the ones of the shared
+//! deserialization pool and of the global rayon pool the proofs are verified on, if the `parallel`
+//! feature is enabled, and the workers spawned by the functions taking a number of threads
+//! (e.g. `BlockProofVerifier::verify()`).
+use crate::proving_system::{
+    error::ProvingSystemError,
+    init::{
+        load_g1_committer_key, load_g1_committer_key_from_file, load_g2_committer_key,
+        load_g2_committer_key_from_file, unload_committer_keys,
+    },
+    ProvingSystem,
+};
+use crate::type_mapping::Error;
+#[cfg(feature = "parallel")]
+use crate::utils::serialization::{build_deserialization_pool, set_deserialization_pool};
+use crate::utils::serialization::{
+    set_serialization_policy, unset_serialization_policy, SerializationPolicy,
+};
+use lazy_static::lazy_static;
+use std::{path::PathBuf, sync::RwLock};
+
+lazy_static! {
+    static ref CCTP_CONFIG: RwLock<Option<CctpConfig>> = RwLock::new(None);
+}
+
+/// Paths of the files from which the committer keys are read, if they exist,
+/// or to which they are saved after being generated otherwise.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CommitterKeyPaths {
+    pub g1_key_path: PathBuf,
+    pub g2_key_path: PathBuf,
+}
+
+/// Configuration of the library.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CctpConfig {
+    /// Proving systems to be supported. G2 committer key is loaded only if
+    /// `ProvingSystem::Darlin` is among them.
+    pub proving_systems: Vec<ProvingSystem>,
+    /// Maximum segment size supported by the committer keys
+    pub max_segment_size: usize,
+    /// If None, committer keys are generated in memory at each initialization
+    pub key_paths: Option<CommitterKeyPaths>,
+    /// Maximum number of threads the library components are allowed to spawn
+    pub thread_budget: usize,
+    /// Default (de)serialization flags, and whether data coming from outside should be
+    /// deserialized with strict checks
+    pub serialization_policy: SerializationPolicy,
+}
+
+impl CctpConfig {
+    /// Create a configuration for `proving_systems` and `max_segment_size`, with committer keys
+    /// generated in memory, as many threads as the available CPUs, and the default serialization policy.
+    pub fn new(proving_systems: Vec<ProvingSystem>, max_segment_size: usize) -> Self {
+        Self {
+            proving_systems,
+            max_segment_size,
+            key_paths: None,
+            thread_budget: std::thread::available_parallelism().map_or(1, |n| n.get()),
+            serialization_policy: SerializationPolicy::default(),
+        }
+    }
+
+    fn check(&self) -> Result<(), Error> {
+        if self.proving_systems.is_empty()
+            || self
+                .proving_systems
+                .iter()
+                .any(|ps| matches!(ps, ProvingSystem::Undefined))
+        {
+            return Err(ProvingSystemError::UndefinedProvingSystem)?;
+        }
+
+        if self.max_segment_size < 2 {
+            return Err(format!(
+                "Invalid max segment size: {}",
+                self.max_segment_size
+            ))?;
+        }
+
+        if self.thread_budget == 0 {
+            return Err("Thread budget must be at least 1")?;
+        }
+
+        Ok(())
+    }
+
+    fn requires_g2_key(&self) -> bool {
+        self.proving_systems
+            .iter()
+            .any(|ps| matches!(ps, ProvingSystem::Darlin))
+    }
+}
+
+/// Initialize the library according to `config`: committer keys are loaded (or generated)
+/// coherently with the configured proving systems and max segment size, and `config` is
+/// stored for later retrieval.
+/// Returns an error if the library has already been initialized: `cctp_shutdown()`
+/// must be called before initializing it again.
+pub fn cctp_init(config: &CctpConfig) -> Result<(), Error> {
+    config.check()?;
+
+    let mut config_guard = CCTP_CONFIG
+        .write()
+        .map_err(|_| "Failed to acquire lock for CCTP_CONFIG")?;
+    if config_guard.is_some() {
+        return Err("CCTP library has already been initialized")?;
+    }
+
+    let max_degree = config.max_segment_size - 1;
+    let load_keys = || -> Result<(), Error> {
+        match config.key_paths.as_ref() {
+            Some(paths) => load_g1_committer_key_from_file(max_degree, &paths.g1_key_path)?,
+            None => load_g1_committer_key(max_degree)?,
+        }
+
+        if config.requires_g2_key() {
+            match config.key_paths.as_ref() {
+                Some(paths) => load_g2_committer_key_from_file(max_degree, &paths.g2_key_path)?,
+                None => load_g2_committer_key(max_degree)?,
+            }
+        }
+
+        Ok(())
+    };
+
+    // Build the pool before installing anything, so that a failure here leaves nothing to undo
+    #[cfg(feature = "parallel")]
+    let deserialization_pool = build_deserialization_pool(config.thread_budget)?;
+
+    set_serialization_policy(config.serialization_policy)?;
+
+    // Don't leave partially initialized keys around
+    if let Err(e) = load_keys() {
+        unload_committer_keys()?;
+        unset_serialization_policy()?;
+        return Err(e);
+    }
+
+    #[cfg(feature = "parallel")]
+    {
+        if let Err(e) = set_deserialization_pool(Some(deserialization_pool)) {
+            unload_committer_keys()?;
+            unset_serialization_policy()?;
+            return Err(e);
+        }
+
+        // The global rayon pool can be built only once per process: if it has already been built
+        // (e.g. by a previous initialization, or by the embedding application), it's left as it is
+        if let Err(e) = rayon::ThreadPoolBuilder::new()
+            .num_threads(config.thread_budget)
+            .build_global()
+        {
+            log::warn!("Global thread pool not resized to the thread budget: {}", e);
+        }
+    }
+
+    config_guard.replace(config.clone());
+    Ok(())
+}
+
+/// Free all the resources allocated by `cctp_init()` (or by direct calls to the
+/// committer keys loading functions). Calling it on a not initialized library is a no-op.
+pub fn cctp_shutdown() -> Result<(), Error> {
+    let mut config_guard = CCTP_CONFIG
+        .write()
+        .map_err(|_| "Failed to acquire lock for CCTP_CONFIG")?;
+    unload_committer_keys()?;
+    unset_serialization_policy()?;
+    #[cfg(feature = "parallel")]
+    set_deserialization_pool(None)?;
+    config_guard.take();
+    Ok(())
+}
+
+/// Return the configuration with which the library has been initialized, if any.
+pub fn get_cctp_config() -> Option<CctpConfig> {
+    CCTP_CONFIG
+        .read()
+        .ok()
+        .and_then(|config_guard| config_guard.clone())
+}
+
+/// Return the thread budget with which the library has been initialized, if any.
+pub fn get_thread_budget() -> Option<usize> {
+    CCTP_CONFIG
+        .read()
+        .ok()
+        .and_then(|config_guard| config_guard.as_ref().map(|config| config.thread_budget))
+}
+
+/// Return the number of threads to be spawned by a component asking for `num_threads` of them:
+/// at least 1 and at most the thread budget, if the library has been initialized.
+pub(crate) fn budgeted_num_threads(num_threads: usize) -> usize {
+    let num_threads = num_threads.max(1);
+    get_thread_budget().map_or(num_threads, |budget| num_threads.min(budget))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::proving_system::init::{
+        get_g1_committer_key, get_g2_committer_key, COMMITTER_KEY_MAX_DEGREE_FOR_TESTING,
+    };
+    use crate::utils::serialization::get_serialization_policy;
+    use serial_test::serial;
+
+    #[serial]
+    #[test]
+    fn cctp_init_shutdown_test() {
+        cctp_shutdown().unwrap();
+
+        let mut config = CctpConfig::new(
+            vec![ProvingSystem::CoboundaryMarlin],
+            COMMITTER_KEY_MAX_DEGREE_FOR_TESTING + 1,
+        );
+
+        // Invalid configurations
+        config.proving_systems = vec![];
+        assert!(cctp_init(&config).is_err());
+        config.proving_systems = vec![ProvingSystem::Undefined];
+        assert!(cctp_init(&config).is_err());
+        config.proving_systems = vec![ProvingSystem::CoboundaryMarlin];
+        config.thread_budget = 0;
+        assert!(cctp_init(&config).is_err());
+        config.thread_budget = 4;
+        assert!(get_cctp_config().is_none());
+        assert_eq!(budgeted_num_threads(8), 8);
+        assert_eq!(budgeted_num_threads(0), 1);
+
+        // Only G1 key is required by CoboundaryMarlin
+        cctp_init(&config).unwrap();
+        assert_eq!(get_cctp_config().unwrap(), config);
+        assert_eq!(get_thread_budget(), Some(4));
+        assert_eq!(budgeted_num_threads(8), 4);
+        assert_eq!(budgeted_num_threads(2), 2);
+        assert_eq!(get_serialization_policy(), config.serialization_policy);
+        assert!(get_g1_committer_key(None).is_ok());
+        assert!(get_g2_committer_key(None).is_err());
+
+        // Double initialization is not allowed
+        assert!(cctp_init(&config).is_err());
+
+        cctp_shutdown().unwrap();
+        assert!(get_cctp_config().is_none());
+        assert!(get_thread_budget().is_none());
+        assert_eq!(get_serialization_policy(), SerializationPolicy::default());
+        assert!(get_g1_committer_key(None).is_err());
+
+        // Both keys are required by Darlin
+        config.proving_systems = vec![ProvingSystem::CoboundaryMarlin, ProvingSystem::Darlin];
+        cctp_init(&config).unwrap();
+        assert!(get_g1_committer_key(None).is_ok());
+        assert!(get_g2_committer_key(None).is_ok());
+
+        cctp_shutdown().unwrap();
+    }
+
+    #[serial]
+    #[test]
+    fn cctp_init_with_key_paths_test() {
+        cctp_shutdown().unwrap();
+
+        let tmp_dir = std::env::temp_dir();
+        let key_paths = CommitterKeyPaths {
+            g1_key_path: tmp_dir.join("cctp_init_test_g1_ck"),
+            g2_key_path: tmp_dir.join("cctp_init_test_g2_ck"),
+        };
+        let _ = std::fs::remove_file(&key_paths.g1_key_path);
+        let _ = std::fs::remove_file(&key_paths.g2_key_path);
+
+        let mut config = CctpConfig::new(
+            vec![ProvingSystem::Darlin],
+            COMMITTER_KEY_MAX_DEGREE_FOR_TESTING + 1,
+        );
+        config.key_paths = Some(key_paths.clone());
+
+        // Keys are generated and saved
+        cctp_init(&config).unwrap();
+        assert!(key_paths.g1_key_path.exists());
+        assert!(key_paths.g2_key_path.exists());
+        let g1_ck = get_g1_committer_key(None).unwrap();
+        let g2_ck = get_g2_committer_key(None).unwrap();
+        cctp_shutdown().unwrap();
+
+        // Keys are read from file
+        cctp_init(&config).unwrap();
+        assert_eq!(g1_ck.hash, get_g1_committer_key(None).unwrap().hash);
+        assert_eq!(g2_ck.hash, get_g2_committer_key(None).unwrap().hash);
+        cctp_shutdown().unwrap();
+
+        // Keys saved for a different degree are rejected, and nothing is left initialized
+        config.max_segment_size = COMMITTER_KEY_MAX_DEGREE_FOR_TESTING / 2 + 1;
+        assert!(cctp_init(&config).is_err());
+        assert!(get_cctp_config().is_none());
+        assert!(get_g1_committer_key(None).is_err());
+
+        std::fs::remove_file(&key_paths.g1_key_path).unwrap();
+        std::fs::remove_file(&key_paths.g2_key_path).unwrap();
+    }
+}
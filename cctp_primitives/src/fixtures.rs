@@ -0,0 +1,547 @@
+//! JSON fixtures of the data hashed into the leaves of the CommitmentTree, shared by the tests of this
+//! crate and by the cross-language tests of the SDK, so that any change to a hasher is validated against
+//! the same vectors.
+//! Byte fields, FieldElements included (as their canonical serialization), are hex encoded, numbers are
+//! JSON integers and absent optional fields are null.
+use crate::{
+    commitment_tree::{
+        hashers::{hash_bwtr, hash_csw, hash_fwt},
+        scc_params::{ScCreationParams, ScCreationParamsBuilder},
+    },
+    proving_system::{verifier::certificate::Certificate, ProvingSystem},
+    type_mapping::{Error, FieldElement, MC_PK_SIZE},
+    utils::{
+        commitment_tree::{rand_fe_vec_with_rng, rand_fe_with_rng, rand_vec_with_rng},
+        data_structures::{BackwardTransfer, BitVectorElementsConfig},
+        serialization::{deserialize_from_buffer_strict, serialize_to_buffer},
+    },
+};
+use rand::Rng;
+use serde_json::{json, Map, Value};
+use std::{convert::TryInto, fs, path::Path};
+
+/// Path, relative to the root of this crate, of the checked-in file holding the fixtures.
+pub const FIXTURES_PATH: &str = "test/fixtures/hashers.json";
+
+/// Data hashed into a leaf of the CommitmentTree, which can be stored as a JSON fixture.
+pub trait HashFixture: Sized {
+    /// Name of the list of fixtures of this type in the fixtures file.
+    const NAME: &'static str;
+
+    /// Convert `self` into its JSON representation.
+    fn to_json(&self) -> Result<Value, Error>;
+
+    /// Build an instance out of its JSON representation.
+    fn from_json(value: &Value) -> Result<Self, Error>;
+
+    /// Compute the hash of `self`, i.e. its leaf in the CommitmentTree.
+    fn fixture_hash(&self) -> Result<FieldElement, Error>;
+}
+
+/// Forward Transfer output, as hashed by `hash_fwt`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FwtOutput {
+    pub amount: u64,
+    pub pub_key: [u8; 32],
+    pub mc_return_address: [u8; MC_PK_SIZE],
+    pub tx_hash: [u8; 32],
+    pub out_idx: u32,
+}
+
+/// Backward Transfer Request output, as hashed by `hash_bwtr`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BwtrOutput {
+    pub sc_fee: u64,
+    pub sc_request_data: Vec<FieldElement>,
+    pub mc_destination_address: [u8; MC_PK_SIZE],
+    pub tx_hash: [u8; 32],
+    pub out_idx: u32,
+}
+
+/// Certificate data, as hashed by `hash_cert` (the circuit-only fields, i.e. `constant` and
+/// `sc_prev_wcert_hash`, are stored as well, but don't affect the hash).
+pub type CertificateData = Certificate;
+
+/// Ceased Sidechain Withdrawal input, as hashed by `hash_csw`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CswInput {
+    pub amount: u64,
+    pub nullifier: FieldElement,
+    pub mc_pk_hash: [u8; MC_PK_SIZE],
+}
+
+/// Encode `bytes` as a lowercase hex string.
+pub(crate) fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Decode the hex string `hex`.
+pub(crate) fn from_hex(hex: &str) -> Result<Vec<u8>, Error> {
+    if hex.len() % 2 != 0 || !hex.is_ascii() {
+        Err(format!("Invalid hex string \"{}\"", hex))?
+    }
+    Ok((0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16))
+        .collect::<Result<Vec<_>, _>>()?)
+}
+
+/// Encode `fe` as the hex string of its canonical serialization.
+pub(crate) fn fe_to_hex(fe: &FieldElement) -> Result<String, Error> {
+    Ok(to_hex(&serialize_to_buffer(fe, None)?))
+}
+
+/// Get the field `field` of the JSON object `value`, returning an error if it's missing.
+pub(crate) fn get_field<'a>(value: &'a Value, field: &str) -> Result<&'a Value, Error> {
+    Ok(value
+        .get(field)
+        .ok_or(format!("Missing field \"{}\"", field))?)
+}
+
+/// Get the field `field` of `value`, or None if it's null or missing.
+pub(crate) fn get_opt_field<'a>(value: &'a Value, field: &str) -> Option<&'a Value> {
+    value.get(field).filter(|value| !value.is_null())
+}
+
+pub(crate) fn get_u64(value: &Value, field: &str) -> Result<u64, Error> {
+    Ok(get_field(value, field)?
+        .as_u64()
+        .ok_or(format!("\"{}\" must be an integer", field))?)
+}
+
+pub(crate) fn get_u32(value: &Value, field: &str) -> Result<u32, Error> {
+    Ok(get_u64(value, field)?.try_into()?)
+}
+
+pub(crate) fn get_u8(value: &Value, field: &str) -> Result<u8, Error> {
+    Ok(get_u64(value, field)?.try_into()?)
+}
+
+pub(crate) fn get_array<'a>(value: &'a Value, field: &str) -> Result<&'a Vec<Value>, Error> {
+    Ok(get_field(value, field)?
+        .as_array()
+        .ok_or(format!("\"{}\" must be an array", field))?)
+}
+
+/// Decode a hex string holding bytes.
+pub(crate) fn bytes_from_json(value: &Value, field: &str) -> Result<Vec<u8>, Error> {
+    from_hex(
+        value
+            .as_str()
+            .ok_or(format!("\"{}\" must be a hex string", field))?,
+    )
+}
+
+pub(crate) fn get_bytes(value: &Value, field: &str) -> Result<Vec<u8>, Error> {
+    bytes_from_json(get_field(value, field)?, field)
+}
+
+pub(crate) fn get_byte_array<const N: usize>(value: &Value, field: &str) -> Result<[u8; N], Error> {
+    get_bytes(value, field)?
+        .try_into()
+        .map_err(|_| format!("\"{}\" must be {} hex encoded bytes", field, N).into())
+}
+
+/// Decode a hex string holding the canonical serialization of a FieldElement.
+pub(crate) fn fe_from_json(value: &Value, field: &str) -> Result<FieldElement, Error> {
+    Ok(deserialize_from_buffer_strict(
+        &bytes_from_json(value, field)?,
+        Some(true),
+        None,
+    )?)
+}
+
+pub(crate) fn get_fe(value: &Value, field: &str) -> Result<FieldElement, Error> {
+    fe_from_json(get_field(value, field)?, field)
+}
+
+pub(crate) fn get_opt_fe(value: &Value, field: &str) -> Result<Option<FieldElement>, Error> {
+    get_opt_field(value, field)
+        .map(|fe| fe_from_json(fe, field))
+        .transpose()
+}
+
+pub(crate) fn fes_to_json(fes: &[FieldElement]) -> Result<Value, Error> {
+    Ok(Value::Array(
+        fes.iter()
+            .map(|fe| fe_to_hex(fe).map(Value::String))
+            .collect::<Result<Vec<_>, _>>()?,
+    ))
+}
+
+pub(crate) fn fes_from_json(value: &Value, field: &str) -> Result<Vec<FieldElement>, Error> {
+    value
+        .as_array()
+        .ok_or(format!("\"{}\" must be an array", field))?
+        .iter()
+        .map(|fe| fe_from_json(fe, field))
+        .collect()
+}
+
+pub(crate) fn opt_fe_to_json(fe: Option<&FieldElement>) -> Result<Value, Error> {
+    Ok(match fe {
+        Some(fe) => Value::String(fe_to_hex(fe)?),
+        None => Value::Null,
+    })
+}
+
+impl HashFixture for FwtOutput {
+    const NAME: &'static str = "fwt";
+
+    fn to_json(&self) -> Result<Value, Error> {
+        Ok(json!({
+            "amount": self.amount,
+            "pub_key": to_hex(&self.pub_key),
+            "mc_return_address": to_hex(&self.mc_return_address),
+            "tx_hash": to_hex(&self.tx_hash),
+            "out_idx": self.out_idx,
+        }))
+    }
+
+    fn from_json(value: &Value) -> Result<Self, Error> {
+        Ok(Self {
+            amount: get_u64(value, "amount")?,
+            pub_key: get_byte_array(value, "pub_key")?,
+            mc_return_address: get_byte_array(value, "mc_return_address")?,
+            tx_hash: get_byte_array(value, "tx_hash")?,
+            out_idx: get_u32(value, "out_idx")?,
+        })
+    }
+
+    fn fixture_hash(&self) -> Result<FieldElement, Error> {
+        hash_fwt(
+            self.amount,
+            &self.pub_key,
+            &self.mc_return_address,
+            &self.tx_hash,
+            self.out_idx,
+        )
+    }
+}
+
+impl HashFixture for BwtrOutput {
+    const NAME: &'static str = "bwtr";
+
+    fn to_json(&self) -> Result<Value, Error> {
+        Ok(json!({
+            "sc_fee": self.sc_fee,
+            "sc_request_data": fes_to_json(&self.sc_request_data)?,
+            "mc_destination_address": to_hex(&self.mc_destination_address),
+            "tx_hash": to_hex(&self.tx_hash),
+            "out_idx": self.out_idx,
+        }))
+    }
+
+    fn from_json(value: &Value) -> Result<Self, Error> {
+        Ok(Self {
+            sc_fee: get_u64(value, "sc_fee")?,
+            sc_request_data: fes_from_json(
+                get_field(value, "sc_request_data")?,
+                "sc_request_data",
+            )?,
+            mc_destination_address: get_byte_array(value, "mc_destination_address")?,
+            tx_hash: get_byte_array(value, "tx_hash")?,
+            out_idx: get_u32(value, "out_idx")?,
+        })
+    }
+
+    fn fixture_hash(&self) -> Result<FieldElement, Error> {
+        hash_bwtr(
+            self.sc_fee,
+            self.sc_request_data.iter().collect(),
+            &self.mc_destination_address,
+            &self.tx_hash,
+            self.out_idx,
+        )
+    }
+}
+
+impl HashFixture for CertificateData {
+    const NAME: &'static str = "cert";
+
+    fn to_json(&self) -> Result<Value, Error> {
+        let bt_list = self
+            .bt_list
+            .iter()
+            .map(|bt| json!({ "pk_dest": to_hex(&bt.pk_dest), "amount": bt.amount }))
+            .collect::<Vec<_>>();
+        Ok(json!({
+            "constant": opt_fe_to_json(self.constant.as_ref())?,
+            "sc_id": fe_to_hex(&self.sc_id)?,
+            "epoch_number": self.epoch_number,
+            "quality": self.quality,
+            "bt_list": bt_list,
+            "custom_fields": match &self.custom_fields {
+                Some(custom_fields) => fes_to_json(custom_fields)?,
+                None => Value::Null,
+            },
+            "end_cumulative_sc_tx_commitment_tree_root":
+                fe_to_hex(&self.end_cumulative_sc_tx_commitment_tree_root)?,
+            "btr_fee": self.btr_fee,
+            "ft_min_amount": self.ft_min_amount,
+            "sc_prev_wcert_hash": opt_fe_to_json(self.sc_prev_wcert_hash.as_ref())?,
+        }))
+    }
+
+    fn from_json(value: &Value) -> Result<Self, Error> {
+        let bt_list = get_array(value, "bt_list")?
+            .iter()
+            .map(|bt| {
+                Ok(BackwardTransfer {
+                    pk_dest: get_byte_array(bt, "pk_dest")?,
+                    amount: get_u64(bt, "amount")?,
+                })
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+        Ok(Self {
+            constant: get_opt_fe(value, "constant")?,
+            sc_id: get_fe(value, "sc_id")?,
+            epoch_number: get_u32(value, "epoch_number")?,
+            quality: get_u64(value, "quality")?,
+            bt_list,
+            custom_fields: get_opt_field(value, "custom_fields")
+                .map(|custom_fields| fes_from_json(custom_fields, "custom_fields"))
+                .transpose()?,
+            end_cumulative_sc_tx_commitment_tree_root: get_fe(
+                value,
+                "end_cumulative_sc_tx_commitment_tree_root",
+            )?,
+            btr_fee: get_u64(value, "btr_fee")?,
+            ft_min_amount: get_u64(value, "ft_min_amount")?,
+            sc_prev_wcert_hash: get_opt_fe(value, "sc_prev_wcert_hash")?,
+        })
+    }
+
+    fn fixture_hash(&self) -> Result<FieldElement, Error> {
+        self.leaf_hash()
+    }
+}
+
+impl HashFixture for CswInput {
+    const NAME: &'static str = "csw";
+
+    fn to_json(&self) -> Result<Value, Error> {
+        Ok(json!({
+            "amount": self.amount,
+            "nullifier": fe_to_hex(&self.nullifier)?,
+            "mc_pk_hash": to_hex(&self.mc_pk_hash),
+        }))
+    }
+
+    fn from_json(value: &Value) -> Result<Self, Error> {
+        Ok(Self {
+            amount: get_u64(value, "amount")?,
+            nullifier: get_fe(value, "nullifier")?,
+            mc_pk_hash: get_byte_array(value, "mc_pk_hash")?,
+        })
+    }
+
+    fn fixture_hash(&self) -> Result<FieldElement, Error> {
+        hash_csw(self.amount, &self.nullifier, &self.mc_pk_hash)
+    }
+}
+
+/// Return the fixtures of type `T` in `fixtures`, each one along with its expected hash.
+pub fn load_fixtures<T: HashFixture>(fixtures: &Value) -> Result<Vec<(T, FieldElement)>, Error> {
+    get_array(fixtures, T::NAME)?
+        .iter()
+        .map(|entry| {
+            Ok((
+                T::from_json(get_field(entry, "data")?)?,
+                get_fe(entry, "hash")?,
+            ))
+        })
+        .collect()
+}
+
+/// Hash each of the fixtures of type `T` in `fixtures`, checking that the result is the expected hash.
+/// Return the number of checked fixtures, or an error naming the first mismatching one.
+pub fn check_fixtures_of<T: HashFixture>(fixtures: &Value) -> Result<usize, Error> {
+    let loaded = load_fixtures::<T>(fixtures)?;
+    for (i, (data, expected_hash)) in loaded.iter().enumerate() {
+        if data.fixture_hash()? != *expected_hash {
+            Err(format!("Hash mismatch for {}[{}]", T::NAME, i))?
+        }
+    }
+    Ok(loaded.len())
+}
+
+/// Same as `check_fixtures_of`, for the fixtures of all the types.
+pub fn check_fixtures(fixtures: &Value) -> Result<usize, Error> {
+    Ok(check_fixtures_of::<FwtOutput>(fixtures)?
+        + check_fixtures_of::<BwtrOutput>(fixtures)?
+        + check_fixtures_of::<CertificateData>(fixtures)?
+        + check_fixtures_of::<ScCreationParams>(fixtures)?
+        + check_fixtures_of::<CswInput>(fixtures)?)
+}
+
+/// Read the fixtures file at `path` and check all of its fixtures, as `check_fixtures` does.
+pub fn load_and_check_fixtures(path: &Path) -> Result<usize, Error> {
+    let fixtures: Value = serde_json::from_str(&fs::read_to_string(path)?)?;
+    check_fixtures(&fixtures)
+}
+
+fn fixtures_to_json<T: HashFixture>(fixtures: &[T]) -> Result<Value, Error> {
+    Ok(Value::Array(
+        fixtures
+            .iter()
+            .map(|data| {
+                Ok(json!({
+                    "data": data.to_json()?,
+                    "hash": fe_to_hex(&data.fixture_hash()?)?,
+                }))
+            })
+            .collect::<Result<Vec<_>, Error>>()?,
+    ))
+}
+
+/// Return `num` random fixtures of each type, as a JSON object mapping the name of each type to
+/// the list of its fixtures, each one as a `{"data": <data>, "hash": <hex of the hash>}` object.
+/// Optional fields are alternately set and unset.
+pub fn generate_fixtures<R: Rng + ?Sized>(num: usize, rng: &mut R) -> Result<Value, Error> {
+    let fwts = (0..num)
+        .map(|_| FwtOutput {
+            amount: rng.gen(),
+            pub_key: rng.gen(),
+            mc_return_address: rng.gen(),
+            tx_hash: rng.gen(),
+            out_idx: rng.gen(),
+        })
+        .collect::<Vec<_>>();
+
+    let bwtrs = (0..num)
+        .map(|i| BwtrOutput {
+            sc_fee: rng.gen(),
+            sc_request_data: rand_fe_vec_with_rng(i % 3, rng),
+            mc_destination_address: rng.gen(),
+            tx_hash: rng.gen(),
+            out_idx: rng.gen(),
+        })
+        .collect::<Vec<_>>();
+
+    let certs = (0..num)
+        .map(|i| CertificateData {
+            constant: if i % 2 == 0 {
+                Some(rand_fe_with_rng(rng))
+            } else {
+                None
+            },
+            sc_id: rand_fe_with_rng(rng),
+            epoch_number: rng.gen(),
+            quality: rng.gen(),
+            bt_list: (0..i % 3)
+                .map(|_| BackwardTransfer {
+                    pk_dest: rng.gen(),
+                    amount: rng.gen(),
+                })
+                .collect(),
+            custom_fields: if i % 2 == 0 {
+                None
+            } else {
+                Some(rand_fe_vec_with_rng(i % 4, rng))
+            },
+            end_cumulative_sc_tx_commitment_tree_root: rand_fe_with_rng(rng),
+            btr_fee: rng.gen(),
+            ft_min_amount: rng.gen(),
+            sc_prev_wcert_hash: if i % 2 == 0 {
+                None
+            } else {
+                Some(rand_fe_with_rng(rng))
+            },
+        })
+        .collect::<Vec<_>>();
+
+    let sccs = (0..num)
+        .map(|i| {
+            let builder = ScCreationParamsBuilder::create(
+                rng.gen(),
+                &rng.gen(),
+                &rng.gen(),
+                rng.gen(),
+                rng.gen(),
+                &rand_vec_with_rng(100, rng),
+            );
+            if i % 2 == 0 {
+                return builder.build();
+            }
+            builder
+                .mc_btr_request_data_length(rng.gen())
+                .custom_field_elements_configs(&rand_vec_with_rng(i % 4, rng))
+                .custom_bitvector_elements_configs(&[BitVectorElementsConfig {
+                    bit_vector_size_bits: 254 * 8,
+                    max_compressed_byte_size: rng.gen(),
+                }])
+                .btr_fee(rng.gen())
+                .ft_min_amount(rng.gen())
+                .custom_creation_data(&rand_vec_with_rng(50, rng))
+                .constant(&rand_fe_with_rng(rng))
+                .csw_proving_system(ProvingSystem::CoboundaryMarlin)
+                .csw_verification_key(&rand_vec_with_rng(100, rng))
+                .build()
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let csws = (0..num)
+        .map(|_| CswInput {
+            amount: rng.gen(),
+            nullifier: rand_fe_with_rng(rng),
+            mc_pk_hash: rng.gen(),
+        })
+        .collect::<Vec<_>>();
+
+    let mut fixtures = Map::new();
+    fixtures.insert(FwtOutput::NAME.to_owned(), fixtures_to_json(&fwts)?);
+    fixtures.insert(BwtrOutput::NAME.to_owned(), fixtures_to_json(&bwtrs)?);
+    fixtures.insert(CertificateData::NAME.to_owned(), fixtures_to_json(&certs)?);
+    fixtures.insert(ScCreationParams::NAME.to_owned(), fixtures_to_json(&sccs)?);
+    fixtures.insert(CswInput::NAME.to_owned(), fixtures_to_json(&csws)?);
+    Ok(Value::Object(fixtures))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::testing::check_golden_file;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    fn assert_round_trip<T: HashFixture + PartialEq + std::fmt::Debug>(fixtures: &Value) {
+        for (data, hash) in load_fixtures::<T>(fixtures).unwrap() {
+            let json = data.to_json().unwrap();
+            assert_eq!(T::from_json(&json).unwrap(), data);
+            assert_eq!(data.fixture_hash().unwrap(), hash);
+        }
+    }
+
+    #[test]
+    fn fixtures_round_trip_test() {
+        let num = 4;
+        let fixtures = generate_fixtures(num, &mut StdRng::seed_from_u64(7)).unwrap();
+        assert_eq!(check_fixtures(&fixtures).unwrap(), 5 * num);
+
+        assert_round_trip::<FwtOutput>(&fixtures);
+        assert_round_trip::<BwtrOutput>(&fixtures);
+        assert_round_trip::<CertificateData>(&fixtures);
+        assert_round_trip::<ScCreationParams>(&fixtures);
+        assert_round_trip::<CswInput>(&fixtures);
+
+        // A changed hash is detected
+        let mut tampered = fixtures.clone();
+        tampered[CswInput::NAME][1]["hash"] = tampered[CswInput::NAME][0]["hash"].clone();
+        assert!(check_fixtures(&tampered).is_err());
+
+        // Malformed data is rejected
+        let mut malformed = fixtures;
+        malformed[FwtOutput::NAME][0]["data"]["pub_key"] = json!("0011");
+        assert!(check_fixtures(&malformed).is_err());
+    }
+
+    // Compares the fixtures with the checked-in ones, so that any change to a hasher is noticed.
+    // Set UPDATE_FIXTURES to rewrite the file instead, whenever a change is intended.
+    #[test]
+    fn fixtures_file_test() {
+        let fixtures = generate_fixtures(4, &mut StdRng::seed_from_u64(42)).unwrap();
+        let contents = serde_json::to_string_pretty(&fixtures).unwrap() + "\n";
+        let path = check_golden_file(FIXTURES_PATH, &contents, "UPDATE_FIXTURES");
+        assert_eq!(load_and_check_fixtures(&path).unwrap(), 5 * 4);
+    }
+}
@@ -43,8 +43,16 @@
     clippy::large_enum_variant
 )]
 
+#[macro_use]
+mod trace;
+
 pub mod bit_vector;
 pub mod commitment_tree;
+pub mod config;
+#[cfg(feature = "fixtures")]
+pub mod fixtures;
 pub mod proving_system;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
 pub mod type_mapping;
-pub mod utils;
\ No newline at end of file
+pub mod utils;
@@ -12,12 +12,105 @@ pub enum ProvingSystemError {
     ProofCreationFailed(String),
     ProofVerificationFailed(String),
     FailedBatchVerification(Option<Vec<u32>>),
+    FailedBatchVerificationWithDetails(Vec<(u32, String)>),
     NoProofsToVerify,
     ProofAlreadyExists(u32),
     ProofNotPresent(u32),
+    DeserializationFailed(u32, String),
+    InvalidCircuitParameters(String),
+    UnsupportedDegree { requested: usize, available: usize },
+    Timeout,
+    KeyHashMismatch { expected: Vec<u8>, found: Vec<u8> },
+    QueueFull(usize),
+    VkResolutionFailed(String),
+    CommitterKeyInitFailed(String),
+    UnsupportedProofFormatVersion { requested: u8, supported: u8 },
     Other(String),
 }
 
+/// Stable, C-ABI friendly codes of the `ProvingSystemError` variants, allowing FFI wrappers
+/// to map errors without maintaining their own tables.
+/// Codes must never be changed or reused: new variants get new codes.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[repr(C)]
+pub enum ProvingSystemErrorCode {
+    UndefinedProvingSystem = 100,
+    ProvingSystemMismatch = 101,
+    CommitterKeyNotInitialized = 102,
+    SetupFailed = 103,
+    ProofCreationFailed = 104,
+    ProofVerificationFailed = 105,
+    FailedBatchVerification = 106,
+    FailedBatchVerificationWithDetails = 107,
+    NoProofsToVerify = 108,
+    ProofAlreadyExists = 109,
+    ProofNotPresent = 110,
+    DeserializationFailed = 111,
+    InvalidCircuitParameters = 112,
+    UnsupportedDegree = 113,
+    Timeout = 114,
+    KeyHashMismatch = 115,
+    QueueFull = 116,
+    VkResolutionFailed = 117,
+    CommitterKeyInitFailed = 118,
+    UnsupportedProofFormatVersion = 119,
+    Other = 199,
+}
+
+impl ProvingSystemError {
+    /// Return the stable code of this error.
+    pub fn code(&self) -> ProvingSystemErrorCode {
+        // No wildcard: adding a variant without a code doesn't compile
+        match self {
+            ProvingSystemError::UndefinedProvingSystem => {
+                ProvingSystemErrorCode::UndefinedProvingSystem
+            }
+            ProvingSystemError::ProvingSystemMismatch => {
+                ProvingSystemErrorCode::ProvingSystemMismatch
+            }
+            ProvingSystemError::CommitterKeyNotInitialized => {
+                ProvingSystemErrorCode::CommitterKeyNotInitialized
+            }
+            ProvingSystemError::SetupFailed(_) => ProvingSystemErrorCode::SetupFailed,
+            ProvingSystemError::ProofCreationFailed(_) => {
+                ProvingSystemErrorCode::ProofCreationFailed
+            }
+            ProvingSystemError::ProofVerificationFailed(_) => {
+                ProvingSystemErrorCode::ProofVerificationFailed
+            }
+            ProvingSystemError::FailedBatchVerification(_) => {
+                ProvingSystemErrorCode::FailedBatchVerification
+            }
+            ProvingSystemError::FailedBatchVerificationWithDetails(_) => {
+                ProvingSystemErrorCode::FailedBatchVerificationWithDetails
+            }
+            ProvingSystemError::NoProofsToVerify => ProvingSystemErrorCode::NoProofsToVerify,
+            ProvingSystemError::ProofAlreadyExists(_) => ProvingSystemErrorCode::ProofAlreadyExists,
+            ProvingSystemError::ProofNotPresent(_) => ProvingSystemErrorCode::ProofNotPresent,
+            ProvingSystemError::DeserializationFailed(_, _) => {
+                ProvingSystemErrorCode::DeserializationFailed
+            }
+            ProvingSystemError::InvalidCircuitParameters(_) => {
+                ProvingSystemErrorCode::InvalidCircuitParameters
+            }
+            ProvingSystemError::UnsupportedDegree { .. } => {
+                ProvingSystemErrorCode::UnsupportedDegree
+            }
+            ProvingSystemError::Timeout => ProvingSystemErrorCode::Timeout,
+            ProvingSystemError::KeyHashMismatch { .. } => ProvingSystemErrorCode::KeyHashMismatch,
+            ProvingSystemError::QueueFull(_) => ProvingSystemErrorCode::QueueFull,
+            ProvingSystemError::VkResolutionFailed(_) => ProvingSystemErrorCode::VkResolutionFailed,
+            ProvingSystemError::CommitterKeyInitFailed(_) => {
+                ProvingSystemErrorCode::CommitterKeyInitFailed
+            }
+            ProvingSystemError::UnsupportedProofFormatVersion { .. } => {
+                ProvingSystemErrorCode::UnsupportedProofFormatVersion
+            }
+            ProvingSystemError::Other(_) => ProvingSystemErrorCode::Other,
+        }
+    }
+}
+
 impl Display for ProvingSystemError {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         match self {
@@ -51,6 +144,13 @@ impl Display for ProvingSystemError {
                     "Batch verification failed. Unable to determine the offending proofs"
                 ),
             },
+            ProvingSystemError::FailedBatchVerificationWithDetails(details) => {
+                write!(f, "Batch verification failed due to proofs:")?;
+                for (id, err) in details.iter() {
+                    write!(f, " [id: {}, reason: {}]", id, err)?;
+                }
+                Ok(())
+            }
             ProvingSystemError::NoProofsToVerify => write!(f, "There is no proof to verify"),
             ProvingSystemError::ProofAlreadyExists(id) => write!(
                 f,
@@ -60,9 +160,98 @@ impl Display for ProvingSystemError {
             ProvingSystemError::ProofNotPresent(id) => {
                 write!(f, "Proof with id: {} is not present in the batch", id)
             }
+            ProvingSystemError::DeserializationFailed(id, err) => {
+                write!(
+                    f,
+                    "Failed to deserialize data of proof with id: {}. {}",
+                    id, err
+                )
+            }
+            ProvingSystemError::InvalidCircuitParameters(err) => {
+                write!(f, "Invalid circuit parameters: {}", err)
+            }
+            ProvingSystemError::UnsupportedDegree {
+                requested,
+                available,
+            } => write!(
+                f,
+                "Requested degree {} is bigger than the max supported one {}",
+                requested, available
+            ),
+            ProvingSystemError::Timeout => {
+                write!(f, "Verification couldn't be completed before the deadline")
+            }
+            ProvingSystemError::KeyHashMismatch { expected, found } => write!(
+                f,
+                "Committer key hash mismatch: expected {}, found {}",
+                to_hex(expected),
+                to_hex(found)
+            ),
+            ProvingSystemError::QueueFull(capacity) => write!(
+                f,
+                "Verification queue is full, with {} proofs waiting to be verified",
+                capacity
+            ),
+            ProvingSystemError::VkResolutionFailed(err) => {
+                write!(f, "Unable to resolve verification key: {}", err)
+            }
+            ProvingSystemError::CommitterKeyInitFailed(err) => {
+                write!(f, "Committer Key loading failed: {}", err)
+            }
+            ProvingSystemError::UnsupportedProofFormatVersion {
+                requested,
+                supported,
+            } => write!(
+                f,
+                "Unsupported proof format version {}, the supported one is {}",
+                requested, supported
+            ),
             ProvingSystemError::Other(err) => write!(f, "{}", err),
         }
     }
 }
 
 impl Error for ProvingSystemError {}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        bit_vector::error::{BitVectorCommitmentError, BitVectorCommitmentErrorCode},
+        commitment_tree::error::{CommitmentTreeError, CommitmentTreeErrorCode},
+    };
+
+    #[test]
+    fn error_codes_are_stable() {
+        // Codes are part of the FFI interface: these values must never change
+        assert_eq!(
+            ProvingSystemError::UndefinedProvingSystem.code() as u32,
+            100
+        );
+        assert_eq!(
+            ProvingSystemError::UnsupportedDegree {
+                requested: 2,
+                available: 1
+            }
+            .code(),
+            ProvingSystemErrorCode::UnsupportedDegree
+        );
+        assert_eq!(ProvingSystemErrorCode::UnsupportedDegree as u32, 113);
+        assert_eq!(ProvingSystemError::Other("".to_owned()).code() as u32, 199);
+        assert_eq!(
+            CommitmentTreeError::CommitmentTreeFull.code(),
+            CommitmentTreeErrorCode::CommitmentTreeFull
+        );
+        assert_eq!(CommitmentTreeErrorCode::SidechainAlive as u32, 200);
+        assert_eq!(CommitmentTreeErrorCode::HashFailed as u32, 210);
+        assert_eq!(
+            BitVectorCommitmentError::CorruptData("".to_owned()).code() as u32,
+            303
+        );
+        assert_eq!(BitVectorCommitmentErrorCode::InvalidConfig as u32, 300);
+    }
+}
@@ -1,10 +1,17 @@
-use crate::proving_system::error::ProvingSystemError;
+use crate::proving_system::{error::ProvingSystemError, ZendooVerifierKey};
 use crate::type_mapping::*;
 use algebra::{serialize::*, AffineCurve};
 use lazy_static::lazy_static;
-use poly_commit::ipa_pc::{InnerProductArgPC, UniversalParams};
+use poly_commit::ipa_pc::{CommitterKey, InnerProductArgPC, UniversalParams};
 use poly_commit::{PCUniversalParams, PolynomialCommitment};
-use std::sync::RwLock;
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{BufReader, BufWriter, Write},
+    path::Path,
+    sync::{Arc, Condvar, Mutex, MutexGuard, RwLock},
+    time::{Duration, Instant},
+};
 
 // We need a mutable static variable to store the committer key.
 // To avoid the usage of unsafe code blocks (required when mutating a static variable)
@@ -20,44 +27,127 @@ lazy_static! {
     pub static ref G2_UNIVERSAL_PARAMS: RwLock<Option<UniversalParams<G2>>> = RwLock::new(None);
 }
 
+// Committer keys already trimmed from the universal params, indexed by their supported degree.
+// They are cleared whenever the universal params are (un)loaded.
+lazy_static! {
+    static ref G1_COMMITTER_KEYS_CACHE: RwLock<HashMap<usize, Arc<CommitterKeyG1>>> =
+        RwLock::new(HashMap::new());
+}
+
+lazy_static! {
+    static ref G2_COMMITTER_KEYS_CACHE: RwLock<HashMap<usize, Arc<CommitterKeyG2>>> =
+        RwLock::new(HashMap::new());
+}
+
+/// Initialization state of `G1_UNIVERSAL_PARAMETERS` or `G2_UNIVERSAL_PARAMETERS`, e.g. for
+/// health checks to report whether the (possibly long) generation of the parameters is over.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum InitState {
+    /// The parameters haven't been loaded yet, or have been unloaded.
+    NotStarted,
+    /// The parameters are being generated or read from file.
+    InProgress,
+    /// The parameters are loaded.
+    Ready,
+    /// The last attempt to load the parameters failed, for the reason reported.
+    Failed(String),
+}
+
+/// `InitState` of some universal parameters, along with the condition variable notified on
+/// each change of it, allowing to wait for the parameters to be loaded.
+struct InitStatus {
+    state: Mutex<InitState>,
+    changed: Condvar,
+}
+
+impl InitStatus {
+    fn new() -> Self {
+        Self {
+            state: Mutex::new(InitState::NotStarted),
+            changed: Condvar::new(),
+        }
+    }
+
+    /// Lock the state. A poisoned state is still usable: it's only ever replaced as a whole.
+    fn lock(&self) -> MutexGuard<'_, InitState> {
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn get(&self) -> InitState {
+        self.lock().clone()
+    }
+
+    fn set(&self, state: InitState) {
+        *self.lock() = state;
+        self.changed.notify_all();
+    }
+
+    /// Wait, up to `timeout`, for a load of the parameters to be completed (successfully or not),
+    /// returning the state found at the end of the wait.
+    fn wait(&self, timeout: Duration) -> InitState {
+        let (state, _) = self
+            .changed
+            .wait_timeout_while(self.lock(), timeout, |state| {
+                *state == InitState::NotStarted || *state == InitState::InProgress
+            })
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        state.clone()
+    }
+}
+
+lazy_static! {
+    static ref G1_INIT_STATUS: InitStatus = InitStatus::new();
+}
+
+lazy_static! {
+    static ref G2_INIT_STATUS: InitStatus = InitStatus::new();
+}
+
+/// Return the initialization state of `G1_UNIVERSAL_PARAMETERS`.
+pub fn get_g1_init_state() -> InitState {
+    G1_INIT_STATUS.get()
+}
+
+/// Return the initialization state of `G2_UNIVERSAL_PARAMETERS`.
+pub fn get_g2_init_state() -> InitState {
+    G2_INIT_STATUS.get()
+}
+
 /// Generate `G1_UNIVERSAL_PARAMETERS` and store it in memory.
 /// This function should be called exactly once during program execution and before any call to
 /// `get_g1_committer_key()`. Further calls leave `G1_UNIVERSAL_PARAMETERS` unchanged and return an
 /// error instead.
 /// The parameter `max_degree` is required in order to derive a unique hash for the key itself.
 pub fn load_g1_committer_key(max_degree: usize) -> Result<(), SerializationError> {
-    {
-        let pp_g1_guard = G1_UNIVERSAL_PARAMS.read().map_err(|_| {
-            SerializationError::IoError(std::io::Error::new(
-                std::io::ErrorKind::Other,
-                "Failed to acquire lock for G1_UNIVERSAL_PARAMS".to_owned(),
-            ))
-        })?;
-        if pp_g1_guard.is_some() {
-            return Err(SerializationError::IoError(std::io::Error::new(
-                std::io::ErrorKind::AlreadyExists,
-                "G1_UNIVERSAL_PARAMS has already been generated",
-            )));
-        }
-    }
-    match load_universal_params::<G1>(max_degree) {
-        // Generation/Loading successfull, assign the key to the lazy_static
-        Ok(loaded_params) => {
-            G1_UNIVERSAL_PARAMS
-                .write()
-                .as_mut()
-                .map_err(|_| {
-                    SerializationError::IoError(std::io::Error::new(
-                        std::io::ErrorKind::Other,
-                        "G1_UNIVERSAL_PARAMS write failed",
-                    ))
-                })?
-                .replace(loaded_params);
-            Ok(())
-        }
-        // Error while generating/reading file/writing file
-        Err(e) => Err(e),
-    }
+    load_committer_key_inner(
+        &G1_UNIVERSAL_PARAMS,
+        &G1_COMMITTER_KEYS_CACHE,
+        &G1_INIT_STATUS,
+        "G1_UNIVERSAL_PARAMS",
+        max_degree,
+        None,
+        None,
+    )
+}
+
+/// Same as `load_g1_committer_key()`, but `G1_UNIVERSAL_PARAMETERS` are read from `file_path`,
+/// if it exists; otherwise, they are generated and saved to `file_path`.
+/// Returns an error if the parameters read from file don't match `max_degree`.
+pub fn load_g1_committer_key_from_file(
+    max_degree: usize,
+    file_path: &Path,
+) -> Result<(), SerializationError> {
+    load_committer_key_inner(
+        &G1_UNIVERSAL_PARAMS,
+        &G1_COMMITTER_KEYS_CACHE,
+        &G1_INIT_STATUS,
+        "G1_UNIVERSAL_PARAMS",
+        max_degree,
+        Some(file_path),
+        None,
+    )
 }
 
 /// Generate `G2_UNIVERSAL_PARAMETERS` and store it in memory.
@@ -66,38 +156,261 @@ pub fn load_g1_committer_key(max_degree: usize) -> Result<(), SerializationError
 /// error instead.
 /// The parameter `max_degree` is required in order to derive a unique hash for the key itself.
 pub fn load_g2_committer_key(max_degree: usize) -> Result<(), SerializationError> {
+    load_committer_key_inner(
+        &G2_UNIVERSAL_PARAMS,
+        &G2_COMMITTER_KEYS_CACHE,
+        &G2_INIT_STATUS,
+        "G2_UNIVERSAL_PARAMS",
+        max_degree,
+        None,
+        None,
+    )
+}
+
+/// Same as `load_g2_committer_key()`, but `G2_UNIVERSAL_PARAMETERS` are read from `file_path`,
+/// if it exists; otherwise, they are generated and saved to `file_path`.
+/// Returns an error if the parameters read from file don't match `max_degree`.
+pub fn load_g2_committer_key_from_file(
+    max_degree: usize,
+    file_path: &Path,
+) -> Result<(), SerializationError> {
+    load_committer_key_inner(
+        &G2_UNIVERSAL_PARAMS,
+        &G2_COMMITTER_KEYS_CACHE,
+        &G2_INIT_STATUS,
+        "G2_UNIVERSAL_PARAMS",
+        max_degree,
+        Some(file_path),
+        None,
+    )
+}
+
+/// Same as `load_g1_committer_key()`, but the generators of `G1_UNIVERSAL_PARAMETERS` are
+/// derived deterministically from `seed` (e.g. a published transcript string), so that all the
+/// nodes using the same seed get the same parameters. Their hash, recorded in the parameters,
+/// can be checked against the expected one through `verify_g1_committer_key_hash()`.
+pub fn load_g1_committer_key_from_seed(
+    max_degree: usize,
+    seed: &[u8],
+) -> Result<(), SerializationError> {
+    load_committer_key_inner(
+        &G1_UNIVERSAL_PARAMS,
+        &G1_COMMITTER_KEYS_CACHE,
+        &G1_INIT_STATUS,
+        "G1_UNIVERSAL_PARAMS",
+        max_degree,
+        None,
+        Some(seed),
+    )
+}
+
+/// Same as `load_g2_committer_key()`, but the generators of `G2_UNIVERSAL_PARAMETERS` are
+/// derived deterministically from `seed`. See `load_g1_committer_key_from_seed()`.
+pub fn load_g2_committer_key_from_seed(
+    max_degree: usize,
+    seed: &[u8],
+) -> Result<(), SerializationError> {
+    load_committer_key_inner(
+        &G2_UNIVERSAL_PARAMS,
+        &G2_COMMITTER_KEYS_CACHE,
+        &G2_INIT_STATUS,
+        "G2_UNIVERSAL_PARAMS",
+        max_degree,
+        None,
+        Some(seed),
+    )
+}
+
+/// Check that the hash of the loaded `G1_UNIVERSAL_PARAMETERS` is `expected_hash`.
+/// Returns an error if `G1_UNIVERSAL_PARAMETERS` have not been loaded.
+pub fn verify_g1_committer_key_hash(expected_hash: &[u8]) -> Result<bool, ProvingSystemError> {
+    verify_universal_params_hash(&G1_UNIVERSAL_PARAMS, "G1_UNIVERSAL_PARAMS", expected_hash)
+}
+
+/// Check that the hash of the loaded `G2_UNIVERSAL_PARAMETERS` is `expected_hash`.
+/// Returns an error if `G2_UNIVERSAL_PARAMETERS` have not been loaded.
+pub fn verify_g2_committer_key_hash(expected_hash: &[u8]) -> Result<bool, ProvingSystemError> {
+    verify_universal_params_hash(&G2_UNIVERSAL_PARAMS, "G2_UNIVERSAL_PARAMS", expected_hash)
+}
+
+fn verify_universal_params_hash<G: AffineCurve>(
+    params: &RwLock<Option<UniversalParams<G>>>,
+    params_name: &str,
+    expected_hash: &[u8],
+) -> Result<bool, ProvingSystemError> {
+    Ok(get_universal_params_hash(params, params_name)? == expected_hash)
+}
+
+/// Return the hash of the loaded `G1_UNIVERSAL_PARAMETERS`, shared by all the committer keys
+/// trimmed from them.
+pub fn get_g1_key_hash() -> Result<Vec<u8>, ProvingSystemError> {
+    get_universal_params_hash(&G1_UNIVERSAL_PARAMS, "G1_UNIVERSAL_PARAMS")
+}
+
+/// Return the hash of the loaded `G2_UNIVERSAL_PARAMETERS`, shared by all the committer keys
+/// trimmed from them.
+pub fn get_g2_key_hash() -> Result<Vec<u8>, ProvingSystemError> {
+    get_universal_params_hash(&G2_UNIVERSAL_PARAMS, "G2_UNIVERSAL_PARAMS")
+}
+
+fn get_universal_params_hash<G: AffineCurve>(
+    params: &RwLock<Option<UniversalParams<G>>>,
+    params_name: &str,
+) -> Result<Vec<u8>, ProvingSystemError> {
+    params
+        .read()
+        .map_err(|_| {
+            ProvingSystemError::Other(format!("Failed to acquire lock for {}", params_name))
+        })?
+        .as_ref()
+        .map(|pp| pp.hash.clone())
+        .ok_or(ProvingSystemError::CommitterKeyNotInitialized)
+}
+
+/// Check, before attempting any verification with `vk`, that the loaded committer keys are the
+/// ones `vk` has been generated with, whose hashes are `g1_key_hash` and `g2_key_hash`.
+/// Verifier keys don't carry these hashes, so they must be recorded together with the
+/// verifier key itself (e.g. at sidechain creation).
+/// The G2 key is used only by Darlin, so `g2_key_hash` is checked only for Darlin verifier keys,
+/// and only if provided.
+/// Return a `ProvingSystemError::KeyHashMismatch` error, reporting both the hashes, if the keys
+/// are not compatible.
+pub fn check_vk_key_compatibility(
+    vk: &ZendooVerifierKey,
+    g1_key_hash: &[u8],
+    g2_key_hash: Option<&[u8]>,
+) -> Result<(), ProvingSystemError> {
+    check_key_hash(g1_key_hash, get_g1_key_hash()?)?;
+    if let (ZendooVerifierKey::Darlin(_), Some(g2_key_hash)) = (vk, g2_key_hash) {
+        check_key_hash(g2_key_hash, get_g2_key_hash()?)?;
+    }
+    Ok(())
+}
+
+fn check_key_hash(expected: &[u8], found: Vec<u8>) -> Result<(), ProvingSystemError> {
+    if expected == found.as_slice() {
+        Ok(())
+    } else {
+        Err(ProvingSystemError::KeyHashMismatch {
+            expected: expected.to_vec(),
+            found,
+        })
+    }
+}
+
+/// Free the memory of `G1_UNIVERSAL_PARAMETERS` and `G2_UNIVERSAL_PARAMETERS`, allowing them
+/// to be loaded again.
+pub(crate) fn unload_committer_keys() -> Result<(), ProvingSystemError> {
+    G1_UNIVERSAL_PARAMS
+        .write()
+        .map_err(|_| {
+            ProvingSystemError::Other("Failed to acquire lock for G1_UNIVERSAL_PARAMS".to_owned())
+        })?
+        .take();
+    G2_UNIVERSAL_PARAMS
+        .write()
+        .map_err(|_| {
+            ProvingSystemError::Other("Failed to acquire lock for G2_UNIVERSAL_PARAMS".to_owned())
+        })?
+        .take();
+    clear_committer_keys_cache(&G1_COMMITTER_KEYS_CACHE);
+    clear_committer_keys_cache(&G2_COMMITTER_KEYS_CACHE);
+    G1_INIT_STATUS.set(InitState::NotStarted);
+    G2_INIT_STATUS.set(InitState::NotStarted);
+    Ok(())
+}
+
+fn clear_committer_keys_cache<G: AffineCurve>(
+    cache: &RwLock<HashMap<usize, Arc<CommitterKey<G>>>>,
+) {
+    // A poisoned cache is cleared too: its content is never read without the params lock
+    match cache.write() {
+        Ok(mut cache) => cache.clear(),
+        Err(poisoned) => poisoned.into_inner().clear(),
+    }
+}
+
+fn load_committer_key_inner<G: AffineCurve>(
+    params: &RwLock<Option<UniversalParams<G>>>,
+    cache: &RwLock<HashMap<usize, Arc<CommitterKey<G>>>>,
+    status: &InitStatus,
+    params_name: &str,
+    max_degree: usize,
+    file_path: Option<&Path>,
+    seed: Option<&[u8]>,
+) -> Result<(), SerializationError> {
     {
-        let pp_g2_guard = G2_UNIVERSAL_PARAMS.read().map_err(|_| {
+        // Holding the state lock while checking the params guarantees that a single load is in progress
+        let mut state = status.lock();
+        if *state == InitState::InProgress {
+            return Err(SerializationError::IoError(std::io::Error::new(
+                std::io::ErrorKind::AlreadyExists,
+                format!("{} is already being generated", params_name),
+            )));
+        }
+        let pp_guard = params.read().map_err(|_| {
             SerializationError::IoError(std::io::Error::new(
                 std::io::ErrorKind::Other,
-                "Failed to acquire lock for G2_UNIVERSAL_PARAMS".to_owned(),
+                format!("Failed to acquire lock for {}", params_name),
             ))
         })?;
-        if pp_g2_guard.is_some() {
+        if pp_guard.is_some() {
             return Err(SerializationError::IoError(std::io::Error::new(
                 std::io::ErrorKind::AlreadyExists,
-                "G2_UNIVERSAL_PARAMS has already been generated",
+                format!("{} has already been generated", params_name),
             )));
         }
+        *state = InitState::InProgress;
     }
-    match load_universal_params::<G2>(max_degree) {
-        // Generation/Loading successful, assign the key to the lazy_static
-        Ok(loaded_params) => {
-            G2_UNIVERSAL_PARAMS
-                .write()
-                .as_mut()
-                .map_err(|_| {
-                    SerializationError::IoError(std::io::Error::new(
-                        std::io::ErrorKind::Other,
-                        "G2_UNIVERSAL_PARAMS write failed",
-                    ))
-                })?
-                .replace(loaded_params);
-            Ok(())
+
+    let result = store_universal_params(params, cache, params_name, max_degree, file_path, seed);
+    status.set(match &result {
+        Ok(()) => InitState::Ready,
+        Err(e) => InitState::Failed(e.to_string()),
+    });
+    result
+}
+
+fn store_universal_params<G: AffineCurve>(
+    params: &RwLock<Option<UniversalParams<G>>>,
+    cache: &RwLock<HashMap<usize, Arc<CommitterKey<G>>>>,
+    params_name: &str,
+    max_degree: usize,
+    file_path: Option<&Path>,
+    seed: Option<&[u8]>,
+) -> Result<(), SerializationError> {
+    let _span = trace_span!(
+        "cctp_universal_params",
+        params = params_name,
+        max_degree,
+        from_file = file_path.is_some(),
+        from_seed = seed.is_some()
+    );
+    let loaded_params = match file_path {
+        Some(file_path) if file_path.exists() => read_universal_params::<G>(max_degree, file_path)?,
+        Some(file_path) => {
+            let pp = load_universal_params::<G>(max_degree, seed)?;
+            let mut fs = BufWriter::new(File::create(file_path)?);
+            pp.serialize(&mut fs)?;
+            fs.flush()?;
+            pp
         }
-        // Error while generating/reading file/writing file
-        Err(e) => Err(e),
-    }
+        None => load_universal_params::<G>(max_degree, seed)?,
+    };
+
+    // Generation/Loading successful, assign the key to the lazy_static
+    params
+        .write()
+        .as_mut()
+        .map_err(|_| {
+            SerializationError::IoError(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                format!("{} write failed", params_name),
+            ))
+        })?
+        .replace(loaded_params);
+    clear_committer_keys_cache(cache);
+    Ok(())
 }
 
 /// If `G1_UNIVERSAL_PARAMETERS` has been initialized, return `CommitterKeyG1`, otherwise return
@@ -116,10 +429,11 @@ pub fn get_g1_committer_key(
         // TODO: Everytime the committer key is trimmed, a copy of the generators is performed.
         //   Currently the generators in the CommitterKey struct are stored as a Vec. Maybe we can
         //   do better by defining them as a slice with some lifetime?
-        let (ck, _) =
-            InnerProductArgPC::<_, Digest>::trim(&pp_g1_guard.as_ref().unwrap(), supported_degree)
-                .map_err(|err| ProvingSystemError::Other(err.to_string()))?;
-        Ok(ck)
+        trim_committer_key(
+            pp_g1_guard.as_ref().unwrap(),
+            supported_degree,
+            "G1_UNIVERSAL_PARAMS",
+        )
     } else {
         Err(ProvingSystemError::CommitterKeyNotInitialized)
     }
@@ -141,28 +455,223 @@ pub fn get_g2_committer_key(
         // TODO: Everytime the committer key is trimmed, a copy of the generators is performed.
         //   Currently the generators in the CommitterKey struct are stored as a Vec. Maybe we can
         //   do better by defining them as a slice with some lifetime?
-        let (ck, _) =
-            InnerProductArgPC::<_, Digest>::trim(&pp_g2_guard.as_ref().unwrap(), supported_degree)
-                .map_err(|err| ProvingSystemError::Other(err.to_string()))?;
-        Ok(ck)
+        trim_committer_key(
+            pp_g2_guard.as_ref().unwrap(),
+            supported_degree,
+            "G2_UNIVERSAL_PARAMS",
+        )
     } else {
         Err(ProvingSystemError::CommitterKeyNotInitialized)
     }
 }
 
+/// Same as `get_g1_committer_key()`, but if `G1_UNIVERSAL_PARAMETERS` are being loaded, or their
+/// loading hasn't started yet, wait up to `timeout` for them to be ready, instead of failing
+/// straight away.
+/// Return `ProvingSystemError::CommitterKeyInitFailed` if the loading failed, or
+/// `ProvingSystemError::CommitterKeyNotInitialized` if it isn't over by `timeout`.
+pub fn get_g1_committer_key_wait(
+    supported_degree: Option<usize>,
+    timeout: Duration,
+) -> Result<CommitterKeyG1, ProvingSystemError> {
+    wait_for_init(&G1_INIT_STATUS, timeout)?;
+    get_g1_committer_key(supported_degree)
+}
+
+/// Same as `get_g2_committer_key()`, but waiting up to `timeout` for `G2_UNIVERSAL_PARAMETERS`
+/// to be ready. See `get_g1_committer_key_wait()`.
+pub fn get_g2_committer_key_wait(
+    supported_degree: Option<usize>,
+    timeout: Duration,
+) -> Result<CommitterKeyG2, ProvingSystemError> {
+    wait_for_init(&G2_INIT_STATUS, timeout)?;
+    get_g2_committer_key(supported_degree)
+}
+
+fn wait_for_init(status: &InitStatus, timeout: Duration) -> Result<(), ProvingSystemError> {
+    match status.wait(timeout) {
+        InitState::Ready => Ok(()),
+        InitState::Failed(reason) => Err(ProvingSystemError::CommitterKeyInitFailed(reason)),
+        InitState::NotStarted | InitState::InProgress => {
+            Err(ProvingSystemError::CommitterKeyNotInitialized)
+        }
+    }
+}
+
+/// If `G1_UNIVERSAL_PARAMETERS` has been initialized, return a `CommitterKeyG1` for each one of
+/// the requested `degrees`, otherwise return Error.
+/// All the keys are trimmed while holding the universal parameters once, and are cached,
+/// so that further requests for the same degrees don't need to trim (and copy) them again.
+pub fn get_g1_committer_keys(
+    degrees: &[usize],
+) -> Result<Vec<Arc<CommitterKeyG1>>, ProvingSystemError> {
+    get_committer_keys_inner(
+        &G1_UNIVERSAL_PARAMS,
+        &G1_COMMITTER_KEYS_CACHE,
+        "G1_UNIVERSAL_PARAMS",
+        degrees,
+    )
+}
+
+/// If `G2_UNIVERSAL_PARAMETERS` has been initialized, return a `CommitterKeyG2` for each one of
+/// the requested `degrees`, otherwise return Error.
+/// All the keys are trimmed while holding the universal parameters once, and are cached,
+/// so that further requests for the same degrees don't need to trim (and copy) them again.
+pub fn get_g2_committer_keys(
+    degrees: &[usize],
+) -> Result<Vec<Arc<CommitterKeyG2>>, ProvingSystemError> {
+    get_committer_keys_inner(
+        &G2_UNIVERSAL_PARAMS,
+        &G2_COMMITTER_KEYS_CACHE,
+        "G2_UNIVERSAL_PARAMS",
+        degrees,
+    )
+}
+
+/// Return the (cached) `CommitterKeyG1` supporting the max degree of `G1_UNIVERSAL_PARAMETERS`.
+pub(crate) fn get_full_g1_committer_key() -> Result<Arc<CommitterKeyG1>, ProvingSystemError> {
+    let max_degree = get_g1_max_degree()?;
+    Ok(get_g1_committer_keys(&[max_degree])?.remove(0))
+}
+
+/// Return the (cached) `CommitterKeyG2` supporting the max degree of `G2_UNIVERSAL_PARAMETERS`.
+pub(crate) fn get_full_g2_committer_key() -> Result<Arc<CommitterKeyG2>, ProvingSystemError> {
+    let max_degree = get_g2_max_degree()?;
+    Ok(get_g2_committer_keys(&[max_degree])?.remove(0))
+}
+
+/// Return the max degree supported by `G1_UNIVERSAL_PARAMETERS`, if initialized.
+pub(crate) fn get_g1_max_degree() -> Result<usize, ProvingSystemError> {
+    get_max_degree(&G1_UNIVERSAL_PARAMS, "G1_UNIVERSAL_PARAMS")
+}
+
+/// Return the max degree supported by `G2_UNIVERSAL_PARAMETERS`, if initialized.
+pub(crate) fn get_g2_max_degree() -> Result<usize, ProvingSystemError> {
+    get_max_degree(&G2_UNIVERSAL_PARAMS, "G2_UNIVERSAL_PARAMS")
+}
+
+/// Return the max segment size of the circuits whose proofs can be created and verified with
+/// the loaded universal parameters (i.e. their max degree + 1), or None if they haven't been
+/// loaded. If the G2 parameters are loaded too, the smallest of the two sizes is returned.
+pub fn get_supported_max_segment_size() -> Option<usize> {
+    let g1_max_degree = get_g1_max_degree().ok()?;
+    let max_degree = match get_g2_max_degree() {
+        Ok(g2_max_degree) => std::cmp::min(g1_max_degree, g2_max_degree),
+        Err(_) => g1_max_degree,
+    };
+    Some(max_degree + 1)
+}
+
+fn get_max_degree<G: AffineCurve>(
+    params: &RwLock<Option<UniversalParams<G>>>,
+    params_name: &str,
+) -> Result<usize, ProvingSystemError> {
+    params
+        .read()
+        .map_err(|_| {
+            ProvingSystemError::Other(format!("Failed to acquire lock for {}", params_name))
+        })?
+        .as_ref()
+        .map(|pp| pp.max_degree())
+        .ok_or(ProvingSystemError::CommitterKeyNotInitialized)
+}
+
+fn get_committer_keys_inner<G: AffineCurve>(
+    params: &RwLock<Option<UniversalParams<G>>>,
+    cache: &RwLock<HashMap<usize, Arc<CommitterKey<G>>>>,
+    params_name: &str,
+    degrees: &[usize],
+) -> Result<Vec<Arc<CommitterKey<G>>>, ProvingSystemError> {
+    // Holding the params lock for the whole function guarantees that the cache is not
+    // cleared, nor filled with keys trimmed from different params, in the meantime
+    let pp_guard = params.read().map_err(|_| {
+        ProvingSystemError::Other(format!("Failed to acquire lock for {}", params_name))
+    })?;
+    let pp = pp_guard
+        .as_ref()
+        .ok_or(ProvingSystemError::CommitterKeyNotInitialized)?;
+
+    let mut cache = cache.write().map_err(|_| {
+        ProvingSystemError::Other(format!(
+            "Failed to acquire lock for {} committer keys cache",
+            params_name
+        ))
+    })?;
+
+    degrees
+        .iter()
+        .map(|&degree| {
+            if let Some(ck) = cache.get(&degree) {
+                return Ok(ck.clone());
+            }
+            let ck = Arc::new(trim_committer_key(pp, degree, params_name)?);
+            cache.insert(degree, ck.clone());
+            Ok(ck)
+        })
+        .collect()
+}
+
+/// Trim `pp` to `degree`, checking that it is supported.
+/// Every trim is logged, along with its duration, under the `cctp::committer_key` target.
+fn trim_committer_key<G: AffineCurve>(
+    pp: &UniversalParams<G>,
+    degree: usize,
+    params_name: &str,
+) -> Result<CommitterKey<G>, ProvingSystemError> {
+    let available = pp.max_degree();
+    if degree > available {
+        return Err(ProvingSystemError::UnsupportedDegree {
+            requested: degree,
+            available,
+        });
+    }
+
+    let start = Instant::now();
+    let (ck, _) = InnerProductArgPC::<_, Digest>::trim(pp, degree)
+        .map_err(|err| ProvingSystemError::Other(err.to_string()))?;
+    log::debug!(
+        target: "cctp::committer_key",
+        "Trimmed {} to degree {} in {:?}",
+        params_name,
+        degree,
+        start.elapsed()
+    );
+
+    Ok(ck)
+}
+
 fn load_universal_params<G: AffineCurve>(
     max_degree: usize,
+    seed: Option<&[u8]>,
 ) -> Result<UniversalParams<G>, SerializationError> {
-    let pp = InnerProductArgPC::<G, Digest>::setup(max_degree)
-        .map_err(|_| SerializationError::InvalidData)?;
+    let pp = match seed {
+        Some(seed) => InnerProductArgPC::<G, Digest>::setup_from_seed(max_degree, seed),
+        None => InnerProductArgPC::<G, Digest>::setup(max_degree),
+    }
+    .map_err(|_| SerializationError::InvalidData)?;
 
     // Return the read/generated universal parameters
     Ok(pp)
 }
 
-#[cfg(test)]
+fn read_universal_params<G: AffineCurve>(
+    max_degree: usize,
+    file_path: &Path,
+) -> Result<UniversalParams<G>, SerializationError> {
+    let fs = BufReader::new(File::open(file_path)?);
+    let pp = UniversalParams::<G>::deserialize(fs)?;
+
+    // Parameters must have been generated for the requested degree
+    if pp.max_degree() != max_degree {
+        return Err(SerializationError::InvalidData);
+    }
+
+    Ok(pp)
+}
+
 /// To ensure consistency across tests, each test should initialize the committer keys to the same
 /// value of `max_degree`. For this reason the following constant is defined.
+#[cfg(any(test, feature = "testing"))]
 pub(crate) const COMMITTER_KEY_MAX_DEGREE_FOR_TESTING: usize = 1 << 10;
 
 #[cfg(test)]
@@ -171,6 +680,11 @@ mod test {
 
     use poly_commit::ipa_pc::InnerProductArgPC;
     use poly_commit::PolynomialCommitment;
+    use proof_systems::darlin::tests::{
+        final_darlin::generate_test_data as generate_final_darlin_test_data,
+        simple_marlin::generate_test_data as generate_simple_marlin_test_data,
+    };
+    use rand::thread_rng;
     use serial_test::serial;
 
     #[test]
@@ -222,4 +736,237 @@ mod test {
         assert_eq!(pk.hash, ck.hash);
         assert_eq!(pp.hash, ck.hash);
     }
+
+    #[test]
+    #[serial]
+    fn check_load_committer_keys_from_seed() {
+        let max_degree = COMMITTER_KEY_MAX_DEGREE_FOR_TESTING;
+        let seed = b"CCTP_TEST_SRS_TRANSCRIPT";
+        unload_committer_keys().unwrap();
+
+        // Nothing to verify if the keys are not loaded
+        assert!(matches!(
+            verify_g1_committer_key_hash(&[]),
+            Err(ProvingSystemError::CommitterKeyNotInitialized)
+        ));
+
+        let expected_g1_pp =
+            InnerProductArgPC::<G1, Digest>::setup_from_seed(max_degree, seed).unwrap();
+        let expected_g2_pp =
+            InnerProductArgPC::<G2, Digest>::setup_from_seed(max_degree, seed).unwrap();
+        let default_g1_pp = InnerProductArgPC::<G1, Digest>::setup(max_degree).unwrap();
+
+        // Same seed, same keys
+        load_g1_committer_key_from_seed(max_degree, seed).unwrap();
+        load_g2_committer_key_from_seed(max_degree, seed).unwrap();
+        assert!(verify_g1_committer_key_hash(&expected_g1_pp.hash).unwrap());
+        assert!(verify_g2_committer_key_hash(&expected_g2_pp.hash).unwrap());
+        assert_eq!(
+            get_g1_committer_key(None).unwrap().comm_key,
+            expected_g1_pp.comm_key
+        );
+
+        // Keys derived from a different seed are detected
+        assert!(!verify_g1_committer_key_hash(&default_g1_pp.hash).unwrap());
+
+        // Restore the default keys for the other tests
+        unload_committer_keys().unwrap();
+        load_g1_committer_key(max_degree).unwrap();
+        assert!(verify_g1_committer_key_hash(&default_g1_pp.hash).unwrap());
+        unload_committer_keys().unwrap();
+    }
+
+    #[test]
+    #[serial]
+    fn check_vk_key_compatibility_test() {
+        let max_degree = COMMITTER_KEY_MAX_DEGREE_FOR_TESTING;
+        let segment_size = 1 << 7;
+        let rng = &mut thread_rng();
+        unload_committer_keys().unwrap();
+
+        let g1_pp = InnerProductArgPC::<G1, Digest>::setup(max_degree).unwrap();
+        let g2_pp = InnerProductArgPC::<G2, Digest>::setup(max_degree).unwrap();
+        let (_, cob_marlin_vks) =
+            generate_simple_marlin_test_data(segment_size - 1, segment_size, &g1_pp, 1, rng);
+        let (_, darlin_vks) =
+            generate_final_darlin_test_data(segment_size - 1, segment_size, &g1_pp, &g2_pp, 1, rng);
+        let cob_marlin_vk = ZendooVerifierKey::CoboundaryMarlin(cob_marlin_vks[0].clone());
+        let darlin_vk = ZendooVerifierKey::Darlin(darlin_vks[0].clone());
+
+        // Keys not loaded
+        assert!(matches!(
+            get_g1_key_hash(),
+            Err(ProvingSystemError::CommitterKeyNotInitialized)
+        ));
+        assert!(matches!(
+            check_vk_key_compatibility(&cob_marlin_vk, &g1_pp.hash, None),
+            Err(ProvingSystemError::CommitterKeyNotInitialized)
+        ));
+
+        load_g1_committer_key(max_degree).unwrap();
+        load_g2_committer_key(max_degree).unwrap();
+        assert_eq!(get_g1_key_hash().unwrap(), g1_pp.hash);
+        assert_eq!(get_g2_key_hash().unwrap(), g2_pp.hash);
+
+        // Compatible keys
+        check_vk_key_compatibility(&cob_marlin_vk, &g1_pp.hash, None).unwrap();
+        check_vk_key_compatibility(&darlin_vk, &g1_pp.hash, Some(&g2_pp.hash)).unwrap();
+
+        // The G2 key hash is ignored for CoboundaryMarlin vks
+        check_vk_key_compatibility(&cob_marlin_vk, &g1_pp.hash, Some(&[0u8; 32])).unwrap();
+
+        // Mismatching keys are reported with both the hashes
+        match check_vk_key_compatibility(&darlin_vk, &g1_pp.hash, Some(&g1_pp.hash)) {
+            Err(ProvingSystemError::KeyHashMismatch { expected, found }) => {
+                assert_eq!(expected, g1_pp.hash);
+                assert_eq!(found, g2_pp.hash);
+            }
+            res => panic!("Unexpected result: {:?}", res),
+        }
+        assert!(matches!(
+            check_vk_key_compatibility(&cob_marlin_vk, &[0u8; 32], None),
+            Err(ProvingSystemError::KeyHashMismatch { .. })
+        ));
+
+        unload_committer_keys().unwrap();
+    }
+
+    #[test]
+    #[serial]
+    fn check_get_committer_keys() {
+        let max_degree = COMMITTER_KEY_MAX_DEGREE_FOR_TESTING;
+        let _result_g1 = load_g1_committer_key(max_degree);
+        let _result_g2 = load_g2_committer_key(max_degree);
+
+        let degrees = vec![max_degree / 4, max_degree, max_degree / 2, max_degree / 4];
+        let g1_cks = get_g1_committer_keys(&degrees).unwrap();
+        let g2_cks = get_g2_committer_keys(&degrees).unwrap();
+        assert_eq!(g1_cks.len(), degrees.len());
+        assert_eq!(g2_cks.len(), degrees.len());
+
+        for (i, &degree) in degrees.iter().enumerate() {
+            let g1_ck = get_g1_committer_key(Some(degree)).unwrap();
+            assert_eq!(g1_cks[i].comm_key, g1_ck.comm_key);
+            assert_eq!(g1_cks[i].hash, g1_ck.hash);
+
+            let g2_ck = get_g2_committer_key(Some(degree)).unwrap();
+            assert_eq!(g2_cks[i].comm_key, g2_ck.comm_key);
+            assert_eq!(g2_cks[i].hash, g2_ck.hash);
+        }
+
+        // Same degree, same cached key
+        assert!(Arc::ptr_eq(&g1_cks[0], &g1_cks[3]));
+        assert!(Arc::ptr_eq(
+            &g1_cks[1],
+            &get_g1_committer_keys(&[max_degree]).unwrap()[0]
+        ));
+        assert!(Arc::ptr_eq(
+            &g1_cks[1],
+            &get_full_g1_committer_key().unwrap()
+        ));
+        assert!(Arc::ptr_eq(
+            &g2_cks[1],
+            &get_full_g2_committer_key().unwrap()
+        ));
+
+        // Unsupported degree
+        for res in [
+            get_g1_committer_keys(&[max_degree + 1]).map(|_| ()),
+            get_g2_committer_keys(&[1, max_degree + 1]).map(|_| ()),
+            get_g1_committer_key(Some(max_degree + 1)).map(|_| ()),
+            get_g2_committer_key(Some(max_degree + 1)).map(|_| ()),
+        ]
+        .iter()
+        {
+            match res {
+                Err(ProvingSystemError::UnsupportedDegree {
+                    requested,
+                    available,
+                }) => {
+                    assert_eq!(*requested, max_degree + 1);
+                    assert_eq!(*available, max_degree);
+                }
+                _ => panic!("Trimming to an unsupported degree should have failed"),
+            }
+        }
+
+        // Cache is cleared when params are unloaded
+        unload_committer_keys().unwrap();
+        assert!(matches!(
+            get_g1_committer_keys(&[max_degree]),
+            Err(ProvingSystemError::CommitterKeyNotInitialized)
+        ));
+        load_g1_committer_key(max_degree).unwrap();
+        load_g2_committer_key(max_degree).unwrap();
+        assert!(!Arc::ptr_eq(
+            &g1_cks[1],
+            &get_g1_committer_keys(&[max_degree]).unwrap()[0]
+        ));
+    }
+
+    #[test]
+    #[serial]
+    fn check_get_committer_key_wait() {
+        let max_degree = COMMITTER_KEY_MAX_DEGREE_FOR_TESTING;
+        unload_committer_keys().unwrap();
+        assert_eq!(get_g1_init_state(), InitState::NotStarted);
+        assert!(matches!(
+            get_g1_committer_key_wait(None, Duration::from_millis(10)),
+            Err(ProvingSystemError::CommitterKeyNotInitialized)
+        ));
+
+        // The getter waits for the params being loaded by another thread
+        let getter = std::thread::spawn(|| {
+            get_g1_committer_key_wait(None, Duration::from_secs(3600)).map(|ck| ck.max_degree)
+        });
+        load_g1_committer_key(max_degree).unwrap();
+        assert_eq!(get_g1_init_state(), InitState::Ready);
+        assert_eq!(getter.join().unwrap().unwrap(), max_degree);
+        assert!(get_g1_committer_key_wait(None, Duration::from_secs(0)).is_ok());
+
+        // A failed loading is reported, along with its reason
+        let file_path = std::env::temp_dir().join("cctp_init_state_test_g2_ck");
+        let pp = InnerProductArgPC::<G2, Digest>::setup(max_degree / 2).unwrap();
+        let mut fs = BufWriter::new(File::create(&file_path).unwrap());
+        pp.serialize(&mut fs).unwrap();
+        fs.flush().unwrap();
+        drop(fs);
+        assert!(load_g2_committer_key_from_file(max_degree, &file_path).is_err());
+        assert!(matches!(get_g2_init_state(), InitState::Failed(_)));
+        assert!(matches!(
+            get_g2_committer_key_wait(None, Duration::from_secs(3600)),
+            Err(ProvingSystemError::CommitterKeyInitFailed(_))
+        ));
+        let _ = std::fs::remove_file(&file_path);
+
+        // A new attempt can be done after a failure
+        load_g2_committer_key(max_degree).unwrap();
+        assert_eq!(get_g2_init_state(), InitState::Ready);
+
+        unload_committer_keys().unwrap();
+        assert_eq!(get_g1_init_state(), InitState::NotStarted);
+        assert_eq!(get_g2_init_state(), InitState::NotStarted);
+    }
+
+    #[test]
+    #[serial]
+    fn check_get_supported_max_segment_size() {
+        let max_degree = COMMITTER_KEY_MAX_DEGREE_FOR_TESTING;
+        unload_committer_keys().unwrap();
+        assert!(get_supported_max_segment_size().is_none());
+
+        // Only G1 params loaded
+        load_g1_committer_key(max_degree).unwrap();
+        assert_eq!(get_supported_max_segment_size(), Some(max_degree + 1));
+
+        // G2 params supporting a smaller degree
+        load_g2_committer_key(max_degree / 2).unwrap();
+        assert_eq!(get_supported_max_segment_size(), Some(max_degree / 2 + 1));
+
+        unload_committer_keys().unwrap();
+        load_g1_committer_key(max_degree).unwrap();
+        load_g2_committer_key(max_degree).unwrap();
+        assert_eq!(get_supported_max_segment_size(), Some(max_degree + 1));
+        unload_committer_keys().unwrap();
+    }
 }
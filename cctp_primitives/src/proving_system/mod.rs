@@ -1,4 +1,9 @@
-use std::marker::PhantomData;
+use std::{
+    convert::TryFrom,
+    fmt::{Display, Formatter},
+    marker::PhantomData,
+    str::FromStr,
+};
 
 use crate::{
     proving_system::{
@@ -7,10 +12,14 @@ use crate::{
     },
     type_mapping::{
         CoboundaryMarlinProof, CoboundaryMarlinProverKey, CoboundaryMarlinVerifierKey, DarlinProof,
-        DarlinProverKey, DarlinVerifierKey, Error, FieldElement,
+        DarlinProverKey, DarlinVerifierKey, Error, FieldElement, FIELD_SIZE, GROUP_COMPRESSED_SIZE,
+    },
+    utils::{
+        commitment_tree::DataAccumulator,
+        serialization::{deserialize_from_buffer_bounded, serialize_to_buffer, StrictlyValid},
     },
 };
-use algebra::{serialize::*, SemanticallyValid};
+use algebra::{serialize::*, FpParameters, PrimeField, SemanticallyValid};
 
 pub mod error;
 pub mod init;
@@ -20,6 +29,11 @@ pub const UNDEFINED_PS_BYTE: u8 = 0u8;
 pub const DARLIN_PS_BYTE: u8 = 1u8;
 pub const COBOUNDARY_MARLIN_PS_BYTE: u8 = 2u8;
 
+/// Version of the serialization format of `ZendooProof`, exchanged by nodes (e.g. during handshake)
+/// to tell which proofs they are able to verify.
+/// Must be bumped whenever the serialization format of `ZendooProof` changes.
+pub const ZENDOO_PROOF_FORMAT_VERSION: u8 = 1;
+
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
 #[repr(C)]
 pub enum ProvingSystem {
@@ -34,16 +48,86 @@ impl Default for ProvingSystem {
     }
 }
 
-impl CanonicalSerialize for ProvingSystem {
-    fn serialize<W: Write>(&self, writer: W) -> Result<(), SerializationError> {
+impl ProvingSystem {
+    /// Return the ProvingSystem identified by `byte`, if any.
+    pub fn from_byte(byte: u8) -> Option<Self> {
+        match byte {
+            UNDEFINED_PS_BYTE => Some(ProvingSystem::Undefined),
+            DARLIN_PS_BYTE => Some(ProvingSystem::Darlin),
+            COBOUNDARY_MARLIN_PS_BYTE => Some(ProvingSystem::CoboundaryMarlin),
+            _ => None,
+        }
+    }
+
+    /// Same as `from_byte`, but rejecting the byte of `ProvingSystem::Undefined`, which
+    /// no proof or vk can belong to, as required for data coming from outside.
+    pub fn from_byte_strict(byte: u8) -> Option<Self> {
+        Self::from_byte(byte).filter(|ps| !matches!(ps, ProvingSystem::Undefined))
+    }
+
+    /// Deserialize a `ProvingSystem` coming from outside (e.g. from the network) out of `buffer`.
+    /// Unless strict checks for untrusted data have been disabled in the `SerializationPolicy`,
+    /// the byte of `ProvingSystem::Undefined` is rejected as invalid data.
+    pub fn deserialize_untrusted(buffer: &[u8]) -> Result<Self, SerializationError> {
+        deserialize_from_buffer_bounded(buffer, 1, Some(false), None)
+    }
+
+    /// Return the byte identifying `self`.
+    pub fn to_byte(&self) -> u8 {
         match self {
-            ProvingSystem::Undefined => CanonicalSerialize::serialize(&UNDEFINED_PS_BYTE, writer),
-            ProvingSystem::Darlin => CanonicalSerialize::serialize(&DARLIN_PS_BYTE, writer),
-            ProvingSystem::CoboundaryMarlin => {
-                CanonicalSerialize::serialize(&COBOUNDARY_MARLIN_PS_BYTE, writer)
-            }
+            ProvingSystem::Undefined => UNDEFINED_PS_BYTE,
+            ProvingSystem::Darlin => DARLIN_PS_BYTE,
+            ProvingSystem::CoboundaryMarlin => COBOUNDARY_MARLIN_PS_BYTE,
+        }
+    }
+}
+
+impl TryFrom<u8> for ProvingSystem {
+    type Error = ProvingSystemError;
+
+    fn try_from(byte: u8) -> Result<Self, Self::Error> {
+        Self::from_byte(byte).ok_or_else(|| {
+            ProvingSystemError::Other(format!("Invalid proving system byte: {}", byte))
+        })
+    }
+}
+
+impl From<ProvingSystem> for u8 {
+    fn from(ps: ProvingSystem) -> Self {
+        ps.to_byte()
+    }
+}
+
+impl Display for ProvingSystem {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProvingSystem::Undefined => write!(f, "undefined"),
+            ProvingSystem::Darlin => write!(f, "darlin"),
+            ProvingSystem::CoboundaryMarlin => write!(f, "cob_marlin"),
         }
     }
+}
+
+impl FromStr for ProvingSystem {
+    type Err = ProvingSystemError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "undefined" => Ok(ProvingSystem::Undefined),
+            "darlin" => Ok(ProvingSystem::Darlin),
+            "cob_marlin" => Ok(ProvingSystem::CoboundaryMarlin),
+            _ => Err(ProvingSystemError::Other(format!(
+                "Invalid proving system: {}",
+                s
+            ))),
+        }
+    }
+}
+
+impl CanonicalSerialize for ProvingSystem {
+    fn serialize<W: Write>(&self, writer: W) -> Result<(), SerializationError> {
+        CanonicalSerialize::serialize(&self.to_byte(), writer)
+    }
 
     fn serialized_size(&self) -> usize {
         1
@@ -53,22 +137,24 @@ impl CanonicalSerialize for ProvingSystem {
 impl CanonicalDeserialize for ProvingSystem {
     fn deserialize<R: Read>(reader: R) -> Result<Self, SerializationError> {
         let ps_type_byte: u8 = CanonicalDeserialize::deserialize(reader)?;
-        match ps_type_byte {
-            UNDEFINED_PS_BYTE => Ok(ProvingSystem::Undefined),
-            DARLIN_PS_BYTE => Ok(ProvingSystem::Darlin),
-            COBOUNDARY_MARLIN_PS_BYTE => Ok(ProvingSystem::CoboundaryMarlin),
-            _ => Err(SerializationError::InvalidData),
-        }
+        Self::from_byte(ps_type_byte).ok_or(SerializationError::InvalidData)
     }
 }
 
-// Dummy implementation
+// Dummy implementation: ProvingSystem::Undefined is rejected only by the strict checks
+// for data coming from outside (see `StrictlyValid`)
 impl SemanticallyValid for ProvingSystem {
     fn is_valid(&self) -> bool {
         true
     }
 }
 
+impl StrictlyValid for ProvingSystem {
+    fn is_strictly_valid(&self) -> bool {
+        !matches!(self, ProvingSystem::Undefined)
+    }
+}
+
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub enum ZendooProof {
     CoboundaryMarlin(CoboundaryMarlinProof),
@@ -82,6 +168,35 @@ impl ZendooProof {
             ZendooProof::CoboundaryMarlin(_) => ProvingSystem::CoboundaryMarlin,
         }
     }
+
+    /// Return the version of the serialization format of `ZendooProof` supported by this library.
+    pub fn proof_format_version() -> u8 {
+        ZENDOO_PROOF_FORMAT_VERSION
+    }
+
+    /// Check whether proofs serialized according to the format `version` can be deserialized
+    /// and verified, returning an `UnsupportedProofFormatVersion` error otherwise.
+    pub fn can_verify(version: u8) -> Result<(), ProvingSystemError> {
+        if version != ZENDOO_PROOF_FORMAT_VERSION {
+            return Err(ProvingSystemError::UnsupportedProofFormatVersion {
+                requested: version,
+                supported: ZENDOO_PROOF_FORMAT_VERSION,
+            });
+        }
+        Ok(())
+    }
+
+    /// Compute a compact identifier of `self`, as the Poseidon hash of its canonical
+    /// (i.e. compressed) serialization, proving system byte included.
+    /// Suitable to identify a proof, e.g. in a mempool, and to detect duplicated proofs.
+    pub fn hash(&self) -> Result<FieldElement, ProvingSystemError> {
+        let proof_bytes = serialize_to_buffer(self, Some(true))
+            .map_err(|e| ProvingSystemError::Other(format!("{:?}", e)))?;
+        DataAccumulator::init()
+            .update(proof_bytes.as_slice())
+            .and_then(|acc| acc.compute_field_hash_constant_length())
+            .map_err(|e| ProvingSystemError::Other(format!("{:?}", e)))
+    }
 }
 
 impl CanonicalSerialize for ZendooProof {
@@ -197,6 +312,9 @@ impl CanonicalDeserialize for ZendooProof {
     }
 }
 
+// The byte of ProvingSystem::Undefined is already rejected by the deserialization
+impl StrictlyValid for ZendooProof {}
+
 impl SemanticallyValid for ZendooProof {
     fn is_valid(&self) -> bool {
         match self {
@@ -338,6 +456,9 @@ impl CanonicalDeserialize for ZendooVerifierKey {
     }
 }
 
+// The byte of ProvingSystem::Undefined is already rejected by the deserialization
+impl StrictlyValid for ZendooVerifierKey {}
+
 impl SemanticallyValid for ZendooVerifierKey {
     fn is_valid(&self) -> bool {
         match self {
@@ -511,8 +632,34 @@ pub fn check_matching_proving_system_type(proof: &ZendooProof, vk: &ZendooVerifi
 
 use marlin::ahp::indexer::IndexInfo;
 
+/// Dimensions of a circuit, as required to compute the size of its proof and vk.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct CircuitDims {
+    /// Number of constraints
+    pub constraints: usize,
+    /// Number of witness variables
+    pub witnesses: usize,
+    /// Number of public inputs
+    pub inputs: usize,
+    /// Number of non zero entries in the constraint matrices
+    pub non_zero: usize,
+}
+
+impl From<CircuitDims> for IndexInfo<FieldElement> {
+    fn from(dims: CircuitDims) -> Self {
+        IndexInfo::<FieldElement> {
+            num_witness: dims.witnesses,
+            num_inputs: dims.inputs,
+            num_constraints: dims.constraints,
+            num_non_zero: dims.non_zero,
+            f: PhantomData,
+        }
+    }
+}
+
 /// Checks that size of proof and vk for a circuit with given segment_size, indexer_info, proof_type and zk,
 /// are smaller than, respectively, max_proof_size and max_vk_size.
+/// Returns an error if the parameters are not valid (see `compute_proof_vk_size`).
 pub fn check_proof_vk_size(
     segment_size: usize,
     info: IndexInfo<FieldElement>,
@@ -520,27 +667,99 @@ pub fn check_proof_vk_size(
     proof_type: ProvingSystem,
     max_proof_size: usize,
     max_vk_size: usize,
-) -> bool {
-    let (proof_size, vk_size) = compute_proof_vk_size(segment_size, info, zk, proof_type);
-    proof_size <= max_proof_size && vk_size <= max_vk_size
+) -> Result<bool, ProvingSystemError> {
+    let (proof_size, vk_size) = compute_proof_vk_size(segment_size, info, zk, proof_type)?;
+    Ok(proof_size <= max_proof_size && vk_size <= max_vk_size)
 }
 
+/// Same as `check_proof_vk_size`, but taking the dimensions of the circuit
+/// instead of its `IndexInfo`.
+pub fn check_proof_vk_size_from_dims(
+    segment_size: usize,
+    dims: CircuitDims,
+    zk: bool,
+    proof_type: ProvingSystem,
+    max_proof_size: usize,
+    max_vk_size: usize,
+) -> Result<bool, ProvingSystemError> {
+    check_proof_vk_size(
+        segment_size,
+        dims.into(),
+        zk,
+        proof_type,
+        max_proof_size,
+        max_vk_size,
+    )
+}
+
+/// Maximum size of the domains (and of the segments) supported by the size estimation
+/// functions. It matches the 2-adicity of the scalar field of the Tweedle curves,
+/// so no circuit can actually be bigger than this.
+const MAX_SUPPORTED_DOMAIN_SIZE: u64 = 1 << 32;
+
+/// Return the smallest power of two greater or equal to `value`, checking
+/// that `value` is not 0 and that the result doesn't exceed `MAX_SUPPORTED_DOMAIN_SIZE`.
+fn checked_domain_size(value: usize, name: &str) -> Result<usize, ProvingSystemError> {
+    if value == 0 {
+        return Err(ProvingSystemError::InvalidCircuitParameters(format!(
+            "{} must be greater than 0",
+            name
+        )));
+    }
+
+    match value.checked_next_power_of_two() {
+        Some(size) if size as u64 <= MAX_SUPPORTED_DOMAIN_SIZE => Ok(size),
+        _ => Err(ProvingSystemError::InvalidCircuitParameters(format!(
+            "{} ({}) exceeds the maximum supported size {}",
+            name, value, MAX_SUPPORTED_DOMAIN_SIZE
+        ))),
+    }
+}
+
+// Compile-time checks of the sizes [bytes] `compute_proof_vk_size()` relies on: a field element
+// (e.g. an evaluation) is serialized in FIELD_SIZE bytes, enough to hold any of its values, while
+// a compressed point is serialized as one of its coordinates plus one byte of flags.
+const _: [(); 0] =
+    [(); (FIELD_SIZE * 8 < <FieldElement as PrimeField>::Params::MODULUS_BITS as usize) as usize];
+const _: [(); GROUP_COMPRESSED_SIZE] = [(); FIELD_SIZE + 1];
+
 /// Compute size of proof and vk.
+/// Sizes are derived from `GROUP_COMPRESSED_SIZE` and `FIELD_SIZE`, for the serialized
+/// group elements and field elements respectively.
+/// Returns an error if `proof_type` is `ProvingSystem::Undefined`, if `segment_size` or any of
+/// the dimensions in `info` (except `num_witness`) is 0, if `info.num_non_zero` is smaller
+/// than 2, or if any of them exceeds the maximum supported domain size.
 pub fn compute_proof_vk_size(
     segment_size: usize,
     info: IndexInfo<FieldElement>,
     zk: bool,
     proof_type: ProvingSystem,
-) -> (usize, usize) {
+) -> Result<(usize, usize), ProvingSystemError> {
+    if matches!(proof_type, ProvingSystem::Undefined) {
+        return Err(ProvingSystemError::UndefinedProvingSystem);
+    }
+
     // Compute config data
     let zk_bound: usize = if zk { 1 } else { 0 };
-    let segment_size = segment_size.next_power_of_two();
-    let num_inputs = info.num_inputs.next_power_of_two();
+    let segment_size = checked_domain_size(segment_size, "Segment size")?;
+    let num_inputs = checked_domain_size(info.num_inputs, "Number of inputs")?;
+    let num_variables = info
+        .num_witness
+        .checked_add(info.num_inputs)
+        .ok_or_else(|| {
+            ProvingSystemError::InvalidCircuitParameters("Number of variables overflows".to_owned())
+        })?;
     let h = std::cmp::max(
-        info.num_constraints.next_power_of_two(),
-        (info.num_witness + info.num_inputs).next_power_of_two(),
+        checked_domain_size(info.num_constraints, "Number of constraints")?,
+        checked_domain_size(num_variables, "Number of variables")?,
     );
-    let k = info.num_non_zero.next_power_of_two();
+    if info.num_non_zero < 2 {
+        return Err(ProvingSystemError::InvalidCircuitParameters(format!(
+            "Number of non zero entries ({}) must be at least 2",
+            info.num_non_zero
+        )));
+    }
+    let k = checked_domain_size(info.num_non_zero, "Number of non zero entries")?;
 
     // Compute num segments
     let w_segs = ((h + 2 * zk_bound - num_inputs) as f64 / segment_size as f64).ceil() as usize;
@@ -557,45 +776,45 @@ pub fn compute_proof_vk_size(
     let num_evaluations = 22; // indexer polys (12) + prover polys (8) + 2 (z_1 and z_2 are queried at 2 different points)
 
     let pc_proof_size = 1 // l_vec_len
-        + 2 * algebra::log2_floor(segment_size) * 33 // l_vec and r_vec elems
-        + 33 // G_final
-        + 32 // c_final
+        + 2 * algebra::log2_floor(segment_size) * GROUP_COMPRESSED_SIZE // l_vec and r_vec elems
+        + GROUP_COMPRESSED_SIZE // G_final
+        + FIELD_SIZE // c_final
         + 1 // Hiding comm is Some or None
-        + if zk { 33 } else { 0 } // If zk we will have the hiding comm
+        + if zk { GROUP_COMPRESSED_SIZE } else { 0 } // If zk we will have the hiding comm
         + 1 // Rand is Some or None
-        + if zk { 32 } else { 0 }; // If zk we will have the rand
+        + if zk { FIELD_SIZE } else { 0 }; // If zk we will have the rand
 
     let h_poly_segs = ((3 * k - 4) as f64 / segment_size as f64).ceil() as usize;
-    let pc_batch_proof_size = 33 * h_poly_segs // num segs of the highest degree polynomial as the h poly will have this degree too
+    let pc_batch_proof_size = GROUP_COMPRESSED_SIZE * h_poly_segs // num segs of the highest degree polynomial as the h poly will have this degree too
         + 1 // 1 byte to encode length of segments vec
         + pc_proof_size as usize;
 
-    let proof_size = num_segments * 33 // 33 bytes used for point compressed representation
+    let proof_size = num_segments * GROUP_COMPRESSED_SIZE // point compressed representation
         + 8 // 1 byte for each poly to encode shifted comm being Some or None
         + 8 // 1 byte for each poly to encode length of segments vector
-        + num_evaluations * 32
+        + num_evaluations * FIELD_SIZE
         + pc_batch_proof_size
         + match proof_type {
             ProvingSystem::Darlin =>
                 2 * // 2 deferred accumulators
                 (
-                    33 // G_final
+                    GROUP_COMPRESSED_SIZE // G_final
                     + 1 // xi_s len
                     + algebra::log2_floor(segment_size) * 16 // xi_s (only 128 bits long)
                 ),
             ProvingSystem::CoboundaryMarlin => 0,
-            _ => unreachable!()
+            ProvingSystem::Undefined => return Err(ProvingSystemError::UndefinedProvingSystem),
         } as usize;
 
     let indexer_polys_num_segs = (k as f64 / segment_size as f64).ceil() as usize;
     let vk_size = 32 // index_info
         + 1 // indexer comms vec len
-        + indexer_polys_num_segs * 33 * 12 // segment commitments for each indexer poly
+        + indexer_polys_num_segs * GROUP_COMPRESSED_SIZE * 12 // segment commitments for each indexer poly
         + 12 // comms vec len for each indexer poly
         + 12 // shifted comm some or none for each indexer poly
     ;
 
-    (proof_size, vk_size)
+    Ok((proof_size, vk_size))
 }
 
 #[allow(dead_code)]
@@ -603,6 +822,7 @@ pub fn compute_proof_vk_size(
 /// number of constraints and variables a circuit can have s.t.
 /// proof size <= max_proof_size and vk size is <= max_vk_size, and return the
 /// corresponding values of proof size and vk size too.
+/// Returns an error if the parameters are not valid.
 pub(crate) fn compute_max_constraints_and_variables(
     segment_size: usize,
     density: usize,
@@ -611,9 +831,14 @@ pub(crate) fn compute_max_constraints_and_variables(
     max_proof_size: usize,
     max_vk_size: usize,
     proof_type: ProvingSystem,
-) -> (usize, usize, usize, usize) {
-    let segment_size = segment_size.next_power_of_two();
-    let num_inputs = num_inputs.next_power_of_two();
+) -> Result<(usize, usize, usize, usize), ProvingSystemError> {
+    if density == 0 {
+        return Err(ProvingSystemError::InvalidCircuitParameters(
+            "Density must be greater than 0".to_owned(),
+        ));
+    }
+    let segment_size = checked_domain_size(segment_size, "Segment size")?;
+    let num_inputs = checked_domain_size(num_inputs, "Number of inputs")?;
     let mut max_supported_proof_size = 0;
     let mut max_supported_vk_size = 0;
     let mut k_ctr = algebra::log2(num_inputs * density);
@@ -634,7 +859,7 @@ pub(crate) fn compute_max_constraints_and_variables(
         };
 
         // we compute proof_size and vk_size in the most conservative setting for num_variables.
-        let (proof_size, vk_size) = compute_proof_vk_size(segment_size, info, zk, proof_type);
+        let (proof_size, vk_size) = compute_proof_vk_size(segment_size, info, zk, proof_type)?;
 
         // If we exceed one of the two thresholds, we exceeded the domain k size but maybe we can still increase the num_variables
         // without increasing num_constraints and domain_k_size (thus without increasing vk_size).
@@ -657,14 +882,14 @@ pub(crate) fn compute_max_constraints_and_variables(
             let mut h = num_constraints.next_power_of_two();
             loop {
                 info.num_witness = h - num_inputs;
-                let (proof_size, _) = compute_proof_vk_size(segment_size, info, zk, proof_type);
+                let (proof_size, _) = compute_proof_vk_size(segment_size, info, zk, proof_type)?;
                 if proof_size > max_proof_size {
-                    return (
+                    return Ok((
                         info.num_constraints,
                         h / 2,
                         max_supported_proof_size,
                         max_supported_vk_size,
-                    );
+                    ));
                 }
                 max_supported_proof_size = proof_size;
                 h *= 2;
@@ -699,7 +924,8 @@ fn test_check_proof_vk_size() {
                             max_proof_size,
                             max_vk_size,
                             proof_type,
-                        );
+                        )
+                        .unwrap();
                     println!(
                         "For Density: {}, MaxProofSize: {}, MaxVkSize: {}, ProofType: {:?}, Zk: {}, SegmentSize: 1 << {}, Num inputs: {}, Max supported constraints are: {}, Max supported variables are: {}, Proof size: {} bytes, Vk size: {} bytes",
                         density, max_proof_size, max_vk_size, proof_type, zk, size, num_inputs, max_num_constraints, max_num_variables, proof_size, vk_size
@@ -720,7 +946,8 @@ fn test_check_proof_vk_size() {
                         proof_type,
                         max_proof_size,
                         max_vk_size
-                    ));
+                    )
+                    .unwrap());
 
                     info.num_constraints = max_num_constraints + 1;
                     info.num_witness = h - num_inputs;
@@ -733,7 +960,8 @@ fn test_check_proof_vk_size() {
                         proof_type,
                         max_proof_size,
                         max_vk_size
-                    ));
+                    )
+                    .unwrap());
 
                     info.num_constraints = max_num_constraints;
                     info.num_witness = h - num_inputs + 1;
@@ -746,7 +974,8 @@ fn test_check_proof_vk_size() {
                         proof_type,
                         max_proof_size,
                         max_vk_size
-                    ));
+                    )
+                    .unwrap());
 
                     info.num_constraints = max_num_constraints;
                     info.num_witness = h - num_inputs;
@@ -759,9 +988,276 @@ fn test_check_proof_vk_size() {
                         proof_type,
                         max_proof_size,
                         max_vk_size
-                    ));
+                    )
+                    .unwrap());
                 }
             }
         }
     }
 }
+
+#[test]
+fn test_proving_system_conversions() {
+    for &(ps, byte, name) in [
+        (ProvingSystem::Undefined, UNDEFINED_PS_BYTE, "undefined"),
+        (ProvingSystem::Darlin, DARLIN_PS_BYTE, "darlin"),
+        (
+            ProvingSystem::CoboundaryMarlin,
+            COBOUNDARY_MARLIN_PS_BYTE,
+            "cob_marlin",
+        ),
+    ]
+    .iter()
+    {
+        assert_eq!(ps.to_byte(), byte);
+        assert_eq!(u8::from(ps), byte);
+        assert_eq!(ProvingSystem::from_byte(byte), Some(ps));
+        assert_eq!(ProvingSystem::try_from(byte).ok(), Some(ps));
+        assert_eq!(ps.to_string(), name);
+        assert_eq!(name.parse::<ProvingSystem>().ok(), Some(ps));
+    }
+
+    assert!(ProvingSystem::from_byte(COBOUNDARY_MARLIN_PS_BYTE + 1).is_none());
+    assert!(ProvingSystem::try_from(u8::MAX).is_err());
+    assert!("Darlin".parse::<ProvingSystem>().is_err());
+    assert!("coboundary_marlin".parse::<ProvingSystem>().is_err());
+}
+
+#[test]
+fn test_proof_format_version() {
+    assert_eq!(
+        ZendooProof::proof_format_version(),
+        ZENDOO_PROOF_FORMAT_VERSION
+    );
+    assert!(ZendooProof::can_verify(ZENDOO_PROOF_FORMAT_VERSION).is_ok());
+
+    // Unknown versions are rejected
+    for &version in [0, ZENDOO_PROOF_FORMAT_VERSION + 1, u8::MAX].iter() {
+        match ZendooProof::can_verify(version) {
+            Err(ProvingSystemError::UnsupportedProofFormatVersion {
+                requested,
+                supported,
+            }) => {
+                assert_eq!(requested, version);
+                assert_eq!(supported, ZENDOO_PROOF_FORMAT_VERSION);
+            }
+            other => panic!("Unexpected result: {:?}", other),
+        }
+    }
+}
+
+#[test]
+fn test_proving_system_strict_deserialization() {
+    use crate::utils::serialization::{deserialize_from_buffer, get_serialization_policy};
+
+    for &ps in [ProvingSystem::Darlin, ProvingSystem::CoboundaryMarlin].iter() {
+        assert_eq!(ProvingSystem::from_byte_strict(ps.to_byte()), Some(ps));
+        assert_eq!(
+            ProvingSystem::deserialize_untrusted(&[ps.to_byte()]).unwrap(),
+            ps
+        );
+        assert!(ps.is_valid());
+    }
+
+    // Undefined is rejected by strict deserialization (the default for untrusted data) only
+    assert!(get_serialization_policy().strict_untrusted);
+    assert!(ProvingSystem::from_byte_strict(UNDEFINED_PS_BYTE).is_none());
+    assert!(ProvingSystem::from_byte_strict(COBOUNDARY_MARLIN_PS_BYTE + 1).is_none());
+    assert!(ProvingSystem::deserialize_untrusted(&[UNDEFINED_PS_BYTE]).is_err());
+    assert!(!ProvingSystem::Undefined.is_strictly_valid());
+    assert!(ProvingSystem::Undefined.is_valid());
+    for &semantic_checks in [true, false].iter() {
+        assert!(deserialize_from_buffer_bounded::<ProvingSystem>(
+            &[UNDEFINED_PS_BYTE],
+            1,
+            Some(semantic_checks),
+            None
+        )
+        .is_err());
+        assert_eq!(
+            deserialize_from_buffer::<ProvingSystem>(
+                &[UNDEFINED_PS_BYTE],
+                Some(semantic_checks),
+                None
+            )
+            .unwrap(),
+            ProvingSystem::Undefined
+        );
+    }
+
+    // Trailing bytes and unknown bytes are rejected anyway
+    assert!(ProvingSystem::deserialize_untrusted(&[DARLIN_PS_BYTE, 0]).is_err());
+    assert!(ProvingSystem::deserialize_untrusted(&[COBOUNDARY_MARLIN_PS_BYTE + 1]).is_err());
+}
+
+#[test]
+fn test_proof_vk_element_sizes() {
+    use crate::{type_mapping::G1, utils::serialization::serialize_to_buffer};
+    use algebra::{AffineCurve, UniformRand};
+
+    // Tie the sizes used by compute_proof_vk_size() to the actual serializers
+    let rng = &mut rand::thread_rng();
+    let point = G1::prime_subgroup_generator();
+    assert_eq!(
+        serialize_to_buffer(&point, Some(true)).unwrap().len(),
+        GROUP_COMPRESSED_SIZE
+    );
+    let fe = FieldElement::rand(rng);
+    assert_eq!(serialize_to_buffer(&fe, None).unwrap().len(), FIELD_SIZE);
+    assert_eq!(fe.serialized_size(), FIELD_SIZE);
+}
+
+#[test]
+fn test_check_proof_vk_size_from_dims() {
+    let dims = CircuitDims {
+        constraints: 1 << 10,
+        witnesses: (1 << 10) - 32,
+        inputs: 32,
+        non_zero: 1 << 12,
+    };
+
+    let info: IndexInfo<FieldElement> = dims.into();
+    assert_eq!(info.num_constraints, dims.constraints);
+    assert_eq!(info.num_witness, dims.witnesses);
+    assert_eq!(info.num_inputs, dims.inputs);
+    assert_eq!(info.num_non_zero, dims.non_zero);
+
+    for &proof_type in [ProvingSystem::CoboundaryMarlin, ProvingSystem::Darlin].iter() {
+        let (proof_size, vk_size) = compute_proof_vk_size(1 << 15, info, true, proof_type).unwrap();
+        assert!(check_proof_vk_size_from_dims(
+            1 << 15,
+            dims,
+            true,
+            proof_type,
+            proof_size,
+            vk_size
+        )
+        .unwrap());
+        assert!(!check_proof_vk_size_from_dims(
+            1 << 15,
+            dims,
+            true,
+            proof_type,
+            proof_size - 1,
+            vk_size
+        )
+        .unwrap());
+        assert!(!check_proof_vk_size_from_dims(
+            1 << 15,
+            dims,
+            true,
+            proof_type,
+            proof_size,
+            vk_size - 1
+        )
+        .unwrap());
+    }
+}
+
+#[test]
+/// Check that invalid parameters are rejected, and that no combination of parameters
+/// makes the size estimation functions panic.
+fn test_compute_proof_vk_size_invalid_params() {
+    use rand::{thread_rng, Rng};
+
+    let valid_info = IndexInfo::<FieldElement>::from(CircuitDims {
+        constraints: 1 << 10,
+        witnesses: (1 << 10) - 32,
+        inputs: 32,
+        non_zero: 1 << 12,
+    });
+    let ps = ProvingSystem::CoboundaryMarlin;
+    assert!(compute_proof_vk_size(1 << 15, valid_info, false, ps).is_ok());
+
+    let invalid_params = vec![
+        (0, valid_info, ps),
+        (usize::MAX, valid_info, ps),
+        (1 << 15, valid_info, ProvingSystem::Undefined),
+        (
+            1 << 15,
+            IndexInfo {
+                num_constraints: 0,
+                ..valid_info
+            },
+            ps,
+        ),
+        (
+            1 << 15,
+            IndexInfo {
+                num_inputs: 0,
+                ..valid_info
+            },
+            ps,
+        ),
+        (
+            1 << 15,
+            IndexInfo {
+                num_non_zero: 0,
+                ..valid_info
+            },
+            ps,
+        ),
+        (
+            1 << 15,
+            IndexInfo {
+                num_non_zero: 1,
+                ..valid_info
+            },
+            ps,
+        ),
+        (
+            1 << 15,
+            IndexInfo {
+                num_witness: usize::MAX,
+                ..valid_info
+            },
+            ps,
+        ),
+        (
+            1 << 15,
+            IndexInfo {
+                num_constraints: (1 << 32) + 1,
+                ..valid_info
+            },
+            ps,
+        ),
+    ];
+    for (segment_size, info, proof_type) in invalid_params.into_iter() {
+        assert!(compute_proof_vk_size(segment_size, info, true, proof_type).is_err());
+        assert!(check_proof_vk_size(segment_size, info, true, proof_type, 0, 0).is_err());
+    }
+
+    assert!(compute_max_constraints_and_variables(1 << 15, 0, true, 32, 7000, 4000, ps).is_err());
+    assert!(compute_max_constraints_and_variables(1 << 15, 2, true, 0, 7000, 4000, ps).is_err());
+    assert!(compute_max_constraints_and_variables(0, 2, true, 32, 7000, 4000, ps).is_err());
+
+    // Fuzz the parameter space, including edge values: must never panic
+    let rng = &mut thread_rng();
+    let mut random_dim = || match rng.gen_range(0..4) {
+        0 => rng.gen_range(0..4),
+        1 => rng.gen_range(0..1 << 20),
+        2 => rng.gen_range((1 << 31)..(1 << 33)),
+        _ => usize::MAX - rng.gen_range(0..4),
+    };
+    for _ in 0..10000 {
+        let segment_size = random_dim();
+        let info = IndexInfo::<FieldElement> {
+            num_witness: random_dim(),
+            num_inputs: random_dim(),
+            num_constraints: random_dim(),
+            num_non_zero: random_dim(),
+            f: PhantomData,
+        };
+        for &proof_type in [
+            ProvingSystem::Undefined,
+            ProvingSystem::CoboundaryMarlin,
+            ProvingSystem::Darlin,
+        ]
+        .iter()
+        {
+            for &zk in [true, false].iter() {
+                let _ = compute_proof_vk_size(segment_size, info, zk, proof_type);
+            }
+        }
+    }
+}
@@ -1,29 +1,267 @@
-use crate::proving_system::{check_matching_proving_system_type, ZendooProof, ZendooVerifierKey};
+use crate::proving_system::{
+    check_matching_proving_system_type, ProvingSystem, ZendooProof, ZendooVerifierKey,
+};
 use crate::proving_system::{
     error::ProvingSystemError,
-    init::{get_g1_committer_key, get_g2_committer_key},
+    init::{
+        get_full_g1_committer_key, get_full_g2_committer_key, get_g1_max_degree, get_g2_max_degree,
+    },
     verifier::*,
 };
-use proof_systems::darlin::pcd::{
-    final_darlin::FinalDarlinPCD, simple_marlin::SimpleMarlinPCD, GeneralPCD,
+use algebra::CanonicalSerialize;
+use proof_systems::darlin::{
+    accumulators::{
+        dlog::{DLogItemAccumulator, DualDLogItem},
+        ItemAccumulator,
+    },
+    pcd::{
+        final_darlin::FinalDarlinPCD, simple_marlin::SimpleMarlinPCD, DualPCDVerifierKey,
+        GeneralPCD, PCD,
+    },
 };
-use rand::RngCore;
-use std::collections::HashMap;
+use rand::{seq::SliceRandom, RngCore};
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+/// Statistics about the proofs of a given proving system verified in a batch.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ProvingSystemStats {
+    /// Number of verified proofs
+    pub num_proofs: usize,
+    /// Sum of the number of constraints of the circuits the proofs refer to
+    pub total_constraints: usize,
+    /// Time spent verifying the proofs
+    pub verification_time: Duration,
+}
+
+/// Statistics about a batch verification, split per proving system.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct BatchVerificationStats {
+    pub coboundary_marlin: ProvingSystemStats,
+    pub darlin: ProvingSystemStats,
+}
+
+impl BatchVerificationStats {
+    /// Return the total number of verified proofs
+    pub fn num_proofs(&self) -> usize {
+        self.coboundary_marlin.num_proofs + self.darlin.num_proofs
+    }
+
+    /// Return the sum of the number of constraints of all the verified proofs
+    pub fn total_constraints(&self) -> usize {
+        self.coboundary_marlin.total_constraints + self.darlin.total_constraints
+    }
+
+    /// Return the total time spent verifying the proofs
+    pub fn verification_time(&self) -> Duration {
+        self.coboundary_marlin.verification_time + self.darlin.verification_time
+    }
+}
+
+/// Abstract estimation of the cost of verifying a batch of proofs, allowing to decide
+/// whether to verify it now or to defer it, or to enforce verification budgets, before
+/// actually paying the cost.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct VerificationCost {
+    /// Number of proofs in the batch
+    pub num_proofs: usize,
+    /// Total number of public inputs of the proofs
+    pub num_inputs: usize,
+    /// Approximate number of group elements (mostly segment commitments) in the proofs,
+    /// each one requiring a scalar multiplication during succinct verification
+    pub num_proof_group_elements: usize,
+    /// Size of the MSMs checking the aggregated opening proofs: one as big as the G1
+    /// committer key and, if there are Darlin proofs, one as big as the G2 committer key
+    pub msm_size: usize,
+}
+
+impl VerificationCost {
+    /// Return the overall cost, as the number of (scalar multiplication-like) operations
+    pub fn total(&self) -> usize {
+        self.num_inputs + self.num_proof_group_elements + self.msm_size
+    }
+}
+
+/// How a batch verification failure is handled, selectable per call through
+/// `ZendooBatchVerifier::batch_verify_subset_with_mode()`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum BatchVerificationMode {
+    /// As configured through `ZendooBatchVerifier::set_diagnostic_mode()`.
+    Configured,
+    /// Return immediately on failure, without any per-proof fallback (e.g. for block
+    /// validation, where a single invalid proof makes the whole block invalid).
+    FailFast,
+    /// After a failure, verify all the proofs of the batch one by one, so to identify
+    /// all the invalid ones (e.g. for mempool admission, where the valid proofs are kept).
+    CollectAll,
+}
+
+impl Default for BatchVerificationMode {
+    fn default() -> Self {
+        BatchVerificationMode::Configured
+    }
+}
+
+/// Source of the verification keys of the proofs added to a `ZendooBatchVerifier` through
+/// `add_zendoo_proof_verifier_data_with_vk_ref()`, e.g. a storage-backed map from sidechain ids
+/// (or vk hashes) to serialized vks, allowing the proofs of a sidechain to share a single vk.
+pub trait VkResolver: Send + Sync {
+    /// Fetch (and deserialize) the vk referenced by `vk_ref`.
+    fn resolve(&self, vk_ref: &FieldElement) -> Result<Arc<ZendooVerifierKey>, ProvingSystemError>;
+}
+
+/// Verification key of an entry of a `ZendooBatchVerifier`, as passed to the predicate of
+/// `ZendooBatchVerifier::batch_verify_filtered()`.
+pub enum VkSlot {
+    /// Vk supplied when adding the entry (or when rebinding it)
+    Resolved(Arc<ZendooVerifierKey>),
+    /// Reference to a vk to be resolved through the `VkResolver` at verification time
+    Unresolved(FieldElement),
+}
 
 /// Updatable struct storing all the data required to verify a batch of proof.
 /// The struct provides function to add new proofs and to verify a subset of them.
 /// Data is not cleared automatically from the `verifier_data` HashMap after
 /// the corresponding verification procedure has been performed.
+/// Entries whose vk has been replaced through `rebind_vk()` are marked as "dirty"
+/// until they are successfully verified again through `batch_verify_dirty()`.
+/// If diagnostic mode is enabled, the proofs causing a batch verification failure are
+/// verified again one by one, and the reason for which each of them fails is reported
+/// in a `FailedBatchVerificationWithDetails` error.
+/// Unless disabled through `set_shuffle_batches()`, the order of the proofs in a batch is
+/// shuffled, using the RNG supplied for the verification, before verifying them.
+/// Unless allowed through `set_allow_duplicate_proofs()`, the same proof can't be added
+/// under two different ids.
+/// Entries can be added with just a reference to their vk, if a `VkResolver` is set: vks are
+/// then resolved lazily at verification time, and cached, so that entries referencing the same
+/// vk share a single copy of it.
 pub struct ZendooBatchVerifier {
-    pub(crate) verifier_data: HashMap<u32, (ZendooProof, ZendooVerifierKey, Vec<FieldElement>)>,
+    pub(crate) verifier_data: HashMap<u32, (ZendooProof, VkSlot, Vec<FieldElement>)>,
+    dirty_ids: HashSet<u32>,
+    diagnostic_mode: bool,
+    shuffle_batches: bool,
+    allow_duplicate_proofs: bool,
+    proof_ids: BTreeMap<FieldElement, u32>,
+    proof_hashes: HashMap<u32, FieldElement>,
+    vk_resolver: Option<Arc<dyn VkResolver>>,
+    vk_cache: Mutex<BTreeMap<FieldElement, Arc<ZendooVerifierKey>>>,
 }
 
 impl ZendooBatchVerifier {
-    /// Constructor for Self, currently just the constructor for the HashMap.
+    /// Constructor for Self, currently just the constructor for the HashMaps.
     pub fn create() -> Self {
         Self {
             verifier_data: HashMap::new(),
+            dirty_ids: HashSet::new(),
+            diagnostic_mode: false,
+            shuffle_batches: true,
+            allow_duplicate_proofs: false,
+            proof_ids: BTreeMap::new(),
+            proof_hashes: HashMap::new(),
+            vk_resolver: None,
+            vk_cache: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    /// Enable or disable the diagnostic mode (disabled by default).
+    /// Useful for triaging invalid proofs, e.g. sent by peers, at the cost of
+    /// one additional single verification for each offending proof.
+    pub fn set_diagnostic_mode(&mut self, enabled: bool) {
+        self.diagnostic_mode = enabled;
+    }
+
+    /// Enable or disable the shuffling of the proofs before batch verifying them (enabled by default).
+    /// Shuffling prevents the submitter of the proofs from choosing the order in which they are
+    /// aggregated, and thus from crafting orderings that maximize the work needed to identify the
+    /// offending proofs on failure. Disabling it makes the verification reproducible for debugging.
+    pub fn set_shuffle_batches(&mut self, enabled: bool) {
+        self.shuffle_batches = enabled;
+    }
+
+    /// Allow or forbid adding the same proof under different ids (forbidden by default).
+    /// Duplicates are detected through `ZendooProof::hash()`, computed only for the proofs
+    /// added while duplicates are forbidden.
+    pub fn set_allow_duplicate_proofs(&mut self, allowed: bool) {
+        self.allow_duplicate_proofs = allowed;
+    }
+
+    /// Set (or unset) the `VkResolver` of the vks referenced by the entries added through
+    /// `add_zendoo_proof_verifier_data_with_vk_ref()`, clearing the cache of the resolved vks.
+    pub fn set_vk_resolver(&mut self, vk_resolver: Option<Arc<dyn VkResolver>>) {
+        self.vk_resolver = vk_resolver;
+        self.clear_vk_cache();
+    }
+
+    /// Clear the cache of the resolved vks, e.g. after the storage backing the `VkResolver`
+    /// has been updated. Vks are resolved again the next time they are needed.
+    pub fn clear_vk_cache(&self) {
+        self.vk_cache.lock().unwrap().clear();
+    }
+
+    /// Return the vk of an entry, resolving it (or taking it from the cache) if needed.
+    fn resolve_vk(&self, vk: &VkSlot) -> Result<Arc<ZendooVerifierKey>, ProvingSystemError> {
+        let vk_ref = match vk {
+            VkSlot::Resolved(vk) => return Ok(vk.clone()),
+            VkSlot::Unresolved(vk_ref) => vk_ref,
+        };
+
+        if let Some(vk) = self.vk_cache.lock().unwrap().get(vk_ref) {
+            return Ok(vk.clone());
+        }
+
+        let vk = self.fetch_vk(vk_ref)?;
+        self.vk_cache.lock().unwrap().insert(*vk_ref, vk.clone());
+        Ok(vk)
+    }
+
+    /// Fetch the vk referenced by `vk_ref` through the `VkResolver`, bypassing the cache.
+    fn fetch_vk(
+        &self,
+        vk_ref: &FieldElement,
+    ) -> Result<Arc<ZendooVerifierKey>, ProvingSystemError> {
+        self.vk_resolver
+            .as_ref()
+            .ok_or_else(|| ProvingSystemError::VkResolutionFailed("No VkResolver set".to_owned()))?
+            .resolve(vk_ref)
+    }
+
+    /// Move the entries with the given `ids` out of the ZendooBatchVerifier, in the same order,
+    /// together with their (resolved) vks. The cache of the resolved vks is emptied first, so
+    /// that each vk is only referenced by the entries it belongs to (and by the `VkResolver`,
+    /// if it keeps it).
+    fn take_entries(
+        &mut self,
+        ids: &[u32],
+    ) -> Result<Vec<(ZendooProof, Arc<ZendooVerifierKey>, Vec<FieldElement>)>, ProvingSystemError>
+    {
+        let mut vk_cache = std::mem::take(&mut *self.vk_cache.lock().unwrap());
+
+        let mut entries = Vec::with_capacity(ids.len());
+        for id in ids.iter() {
+            let (proof, vk, usr_ins) = self
+                .verifier_data
+                .remove(id)
+                .ok_or(ProvingSystemError::ProofNotPresent(*id))?;
+            let vk = match vk {
+                VkSlot::Resolved(vk) => vk,
+                VkSlot::Unresolved(vk_ref) => match vk_cache.get(&vk_ref) {
+                    Some(vk) => vk.clone(),
+                    None => {
+                        let vk = self.fetch_vk(&vk_ref)?;
+                        vk_cache.insert(vk_ref, vk.clone());
+                        vk
+                    }
+                },
+            };
+            entries.push((proof, vk, usr_ins));
         }
+
+        Ok(entries)
     }
 
     /// Return the number of proofs currently in the ZendooBatchVerifier
@@ -34,6 +272,8 @@ impl ZendooBatchVerifier {
     /// Add a proof, uniquely identified by `id`, to the batch of proof to be verified.
     /// `proof` and `vk` must belong to the same proving system, as enforced by
     /// `check_matching_proving_system_type()` function.
+    /// Unless duplicates are allowed, return a `ProofAlreadyExists` error, reporting the id
+    /// of the existing proof, if the same proof has already been added under another id.
     pub fn add_zendoo_proof_verifier_data<I: UserInputs>(
         &mut self,
         id: u32,
@@ -45,56 +285,237 @@ impl ZendooBatchVerifier {
             return Err(ProvingSystemError::ProvingSystemMismatch);
         }
 
+        self.insert_entry(id, inputs, proof, VkSlot::Resolved(Arc::new(vk)))
+    }
+
+    /// Same as `add_zendoo_proof_verifier_data`, with a reference to the vk (e.g. the sidechain id
+    /// or the vk hash), to be resolved through the `VkResolver` at verification time, instead of the vk.
+    /// As the vk is not available yet, the proving system of `proof` is checked against it only at
+    /// verification time: a mismatch makes the proof fail, as an invalid one would.
+    /// Return a `VkResolutionFailed` error if no `VkResolver` is set.
+    pub fn add_zendoo_proof_verifier_data_with_vk_ref<I: UserInputs>(
+        &mut self,
+        id: u32,
+        inputs: I,
+        proof: ZendooProof,
+        vk_ref: &FieldElement,
+    ) -> Result<(), ProvingSystemError> {
+        if self.vk_resolver.is_none() {
+            return Err(ProvingSystemError::VkResolutionFailed(
+                "No VkResolver set".to_owned(),
+            ));
+        }
+
+        self.insert_entry(id, inputs, proof, VkSlot::Unresolved(*vk_ref))
+    }
+
+    /// Add an entry, uniquely identified by `id`, rejecting duplicate proofs unless they are allowed.
+    fn insert_entry<I: UserInputs>(
+        &mut self,
+        id: u32,
+        inputs: I,
+        proof: ZendooProof,
+        vk: VkSlot,
+    ) -> Result<(), ProvingSystemError> {
         let usr_ins = inputs.get_circuit_inputs()?;
+
+        let proof_hash = if self.allow_duplicate_proofs {
+            None
+        } else {
+            let proof_hash = proof.hash()?;
+            match self.proof_ids.get(&proof_hash) {
+                Some(&existing_id) if existing_id != id => {
+                    return Err(ProvingSystemError::ProofAlreadyExists(existing_id))
+                }
+                _ => Some(proof_hash),
+            }
+        };
+
+        // The proof previously added under the same id, if any, is replaced
+        if let Some(old_proof_hash) = self.proof_hashes.remove(&id) {
+            self.proof_ids.remove(&old_proof_hash);
+        }
+        if let Some(proof_hash) = proof_hash {
+            self.proof_ids.insert(proof_hash, id);
+            self.proof_hashes.insert(id, proof_hash);
+        }
+
         self.verifier_data.insert(id, (proof, vk, usr_ins));
+        self.dirty_ids.remove(&id);
+
+        Ok(())
+    }
+
+    /// Same as `add_zendoo_proof_verifier_data`, with a `PreparedZendooVk` instead of a
+    /// `ZendooVerifierKey`, allowing to share the same prepared vk among the proofs of a
+    /// sidechain submitted for single and batch verification.
+    pub fn add_zendoo_proof_verifier_data_prepared<I: UserInputs>(
+        &mut self,
+        id: u32,
+        inputs: I,
+        proof: ZendooProof,
+        prepared_vk: &PreparedZendooVk,
+    ) -> Result<(), ProvingSystemError> {
+        self.add_zendoo_proof_verifier_data(id, inputs, proof, prepared_vk.vk().clone())
+    }
+
+    /// Replace the vk of the proofs with the given `ids` with `new_vk` (e.g. after the
+    /// sidechain they belong to has rotated its verification key), marking them as dirty.
+    /// Either all the entries are updated or none of them is: an error is returned if any
+    /// of the `ids` is not present, or if `new_vk` doesn't match the proving system of
+    /// any of the corresponding proofs.
+    pub fn rebind_vk(
+        &mut self,
+        ids: &[u32],
+        new_vk: ZendooVerifierKey,
+    ) -> Result<(), ProvingSystemError> {
+        for id in ids.iter() {
+            match self.verifier_data.get(id) {
+                Some((proof, _, _)) => {
+                    if !check_matching_proving_system_type(proof, &new_vk) {
+                        return Err(ProvingSystemError::ProvingSystemMismatch);
+                    }
+                }
+                None => return Err(ProvingSystemError::ProofNotPresent(*id)),
+            }
+        }
+
+        // The entries share the new vk
+        let new_vk = Arc::new(new_vk);
+        for id in ids.iter() {
+            let (_, vk, _) = self.verifier_data.get_mut(id).unwrap();
+            *vk = VkSlot::Resolved(new_vk.clone());
+            self.dirty_ids.insert(*id);
+        }
 
         Ok(())
     }
 
+    /// Return the (sorted) ids of the entries whose vk has been replaced through
+    /// `rebind_vk()` and that haven't been successfully verified since then.
+    pub fn dirty_ids(&self) -> Vec<u32> {
+        let mut dirty_ids = self.dirty_ids.iter().copied().collect::<Vec<_>>();
+        dirty_ids.sort_unstable();
+        dirty_ids
+    }
+
     /// Perform batch verification of `proofs_vks_ins` returning the result of the verification
     /// procedure. If the verification procedure fails, it may be possible to get the index of
     /// the proof that has caused the failure: in that case the Err type Option<usize> will
     /// contain the index in `proofs_vks_ins` of the offending proof; otherwise, it will be set
-    /// to None.
-    fn batch_verify_proofs<R: RngCore>(
-        proofs_vks_ins: Vec<(ZendooProof, ZendooVerifierKey, Vec<FieldElement>)>,
+    /// to None. A proof whose vk belongs to a different proving system is reported as offending.
+    pub(crate) fn batch_verify_proofs<R: RngCore>(
+        proofs_vks_ins: &[(&ZendooProof, &ZendooVerifierKey, &[FieldElement])],
         g1_ck: &CommitterKeyG1,
         g2_ck: &CommitterKeyG2,
         rng: &mut R,
     ) -> Result<bool, Option<Vec<usize>>> {
-        let batch_len = proofs_vks_ins.len();
+        // Without a deadline, the verification is always completed
+        Self::batch_verify_proofs_with_deadline(proofs_vks_ins, None, g1_ck, g2_ck, rng)
+            .unwrap_or(Err(None))
+    }
 
-        // Collect all data in (GeneralPCD, VerificationKey) pairs
-        let mut pcds = Vec::with_capacity(batch_len);
-        let mut vks = Vec::with_capacity(batch_len);
-        proofs_vks_ins
-            .into_iter()
-            .for_each(|(proof, vk, ins)| match (proof, vk) {
-                (ZendooProof::CoboundaryMarlin(proof), ZendooVerifierKey::CoboundaryMarlin(vk)) => {
-                    pcds.push(GeneralPCD::SimpleMarlin(
-                        SimpleMarlinPCD::<G1, Digest>::new(proof, ins),
-                    ));
-                    vks.push(vk);
-                }
-                (ZendooProof::Darlin(proof), ZendooVerifierKey::Darlin(vk)) => {
-                    pcds.push(GeneralPCD::FinalDarlin(
-                        FinalDarlinPCD::<G1, G2, Digest>::new(proof, ins),
-                    ));
-                    vks.push(vk);
+    /// Same as `batch_verify_proofs`, returning None if `deadline` is reached before all the
+    /// proofs have been succinctly verified. The deadline is checked before the succinct
+    /// verification of each proof, while the final check of the accumulators (a single MSM
+    /// for each group, whatever the number of proofs) is always completed once started.
+    fn batch_verify_proofs_with_deadline<R: RngCore>(
+        proofs_vks_ins: &[(&ZendooProof, &ZendooVerifierKey, &[FieldElement])],
+        deadline: Option<Instant>,
+        g1_ck: &CommitterKeyG1,
+        g2_ck: &CommitterKeyG2,
+        rng: &mut R,
+    ) -> Option<Result<bool, Option<Vec<usize>>>> {
+        let _span = trace_span!("cctp_batch_verify_proofs", batch_len = proofs_vks_ins.len());
+
+        #[cfg(feature = "parallel")]
+        let entries = proofs_vks_ins.par_iter();
+        #[cfg(not(feature = "parallel"))]
+        let entries = proofs_vks_ins.iter();
+
+        // The PCDs own their proof and inputs: the ones of each entry are copied right before
+        // its succinct verification, and dropped right after it, while the vk is only borrowed.
+        // The index of the offending proof is reported on failure, None if the deadline is reached
+        let accumulators = entries
+            .enumerate()
+            .map(|(idx, &(proof, vk, ins))| {
+                if deadline.map_or(false, |deadline| Instant::now() >= deadline) {
+                    return Err(None);
                 }
-                _ => unreachable!(),
-            });
-
-        // Perform batch_verification
-        let result = proof_systems::darlin::proof_aggregator::batch_verify_proofs(
-            pcds.as_slice(),
-            vks.as_slice(),
-            g1_ck,
-            g2_ck,
-            rng,
-        )?;
+                Self::succinct_verify(proof.clone(), vk, ins.to_vec(), g1_ck, g2_ck)
+                    .ok_or(Some(idx))
+            })
+            .collect::<Result<Vec<_>, _>>();
+
+        match accumulators {
+            Ok(accumulators) => Some(Self::check_accumulators(accumulators, g1_ck, g2_ck, rng)),
+            Err(Some(idx)) => Some(Err(Some(vec![idx]))),
+            Err(None) => None,
+        }
+    }
+
+    /// Perform the succinct verification of `proof` against `vk`, returning the accumulators
+    /// of its opening proof, to be checked together with the ones of the other proofs of the
+    /// batch through `check_accumulators()`. Unlike the proof aggregator, which takes
+    /// ownership of the vks, `vk` is just borrowed.
+    /// Return None if the verification fails, or if `proof` and `vk` belong to different
+    /// proving systems.
+    fn succinct_verify(
+        proof: ZendooProof,
+        vk: &ZendooVerifierKey,
+        usr_ins: Vec<FieldElement>,
+        g1_ck: &CommitterKeyG1,
+        g2_ck: &CommitterKeyG2,
+    ) -> Option<DualDLogItem<G1, G2>> {
+        let (pcd, vk) = match (proof, vk) {
+            (ZendooProof::CoboundaryMarlin(proof), ZendooVerifierKey::CoboundaryMarlin(vk)) => (
+                GeneralPCD::SimpleMarlin(SimpleMarlinPCD::<G1, Digest>::new(proof, usr_ins)),
+                vk,
+            ),
+            (ZendooProof::Darlin(proof), ZendooVerifierKey::Darlin(vk)) => (
+                GeneralPCD::FinalDarlin(FinalDarlinPCD::<G1, G2, Digest>::new(proof, usr_ins)),
+                vk,
+            ),
+            _ => return None,
+        };
+
+        let vk = DualPCDVerifierKey::<G1, G2, Digest> {
+            final_darlin_vk: vk,
+            dlog_vks: (g1_ck, g2_ck),
+        };
+        pcd.succinct_verify(&vk).ok()
+    }
+
+    /// Check all together the accumulators returned by `succinct_verify()` for the proofs of
+    /// a batch, with a single MSM for each group.
+    fn check_accumulators<R: RngCore>(
+        accumulators: Vec<DualDLogItem<G1, G2>>,
+        g1_ck: &CommitterKeyG1,
+        g2_ck: &CommitterKeyG2,
+        rng: &mut R,
+    ) -> Result<bool, Option<Vec<usize>>> {
+        let (accs_g1, accs_g2): (Vec<_>, Vec<_>) =
+            accumulators.into_iter().map(|acc| (acc.0, acc.1)).unzip();
+        let accs_g1 = accs_g1.into_iter().flatten().collect::<Vec<_>>();
+        let accs_g2 = accs_g2.into_iter().flatten().collect::<Vec<_>>();
+
+        let result_g1 = accs_g1.is_empty()
+            || DLogItemAccumulator::<G1, Digest>::check_items(g1_ck, &accs_g1, rng)
+                .map_err(|_| None)?;
+        let result_g2 = accs_g2.is_empty()
+            || DLogItemAccumulator::<G2, Digest>::check_items(g2_ck, &accs_g2, rng)
+                .map_err(|_| None)?;
+
+        Ok(result_g1 && result_g2)
+    }
 
-        Ok(result)
+    /// Return the order in which the proofs with the given `ids` are batch verified:
+    /// a permutation of `ids` drawn from `rng` if shuffling is enabled, `ids` as it is otherwise.
+    fn batch_order<R: RngCore>(&self, mut ids: Vec<u32>, rng: &mut R) -> Vec<u32> {
+        if self.shuffle_batches {
+            ids.shuffle(rng);
+        }
+        ids
     }
 
     /// Verify only the proofs whose id is contained in `ids`.
@@ -105,49 +526,397 @@ impl ZendooBatchVerifier {
         ids: Vec<u32>,
         rng: &mut R,
     ) -> Result<bool, ProvingSystemError> {
-        // Retrieve committer keys
-        let g1_ck = get_g1_committer_key(None)?;
-        let g2_ck = get_g2_committer_key(None)?;
+        self.batch_verify_subset_with_mode(ids, BatchVerificationMode::Configured, rng)
+    }
+
+    /// Same as `batch_verify_subset`, handling a failure according to `mode`:
+    /// - `Configured`: as `batch_verify_subset` does;
+    /// - `FailFast`: return a `FailedBatchVerification` error as soon as the batch fails,
+    ///   even if the diagnostic mode is enabled;
+    /// - `CollectAll`: verify all the proofs one by one, returning a
+    ///   `FailedBatchVerificationWithDetails` error reporting all the invalid ones.
+    pub fn batch_verify_subset_with_mode<R: RngCore>(
+        &self,
+        ids: Vec<u32>,
+        mode: BatchVerificationMode,
+        rng: &mut R,
+    ) -> Result<bool, ProvingSystemError> {
+        self.verify_subset(ids, mode, None, rng)
+    }
+
+    /// Same as `batch_verify_subset_with_mode`, returning `ProvingSystemError::Timeout` if
+    /// `deadline`, if any, is reached before all the proofs have been succinctly verified.
+    fn verify_subset<R: RngCore>(
+        &self,
+        ids: Vec<u32>,
+        mode: BatchVerificationMode,
+        deadline: Option<Instant>,
+        rng: &mut R,
+    ) -> Result<bool, ProvingSystemError> {
+        let _span = trace_span!(
+            "cctp_batch_verify_subset",
+            num_proofs = ids.len(),
+            first_id = ?ids.first(),
+            mode = ?mode
+        );
+
+        // Retrieve (cached) committer keys
+        let g1_ck = get_full_g1_committer_key()?;
+        let g2_ck = get_full_g2_committer_key()?;
 
         if ids.is_empty() {
             Err(ProvingSystemError::NoProofsToVerify)
         } else {
-            let to_verify = ids
+            let ids = self.batch_order(ids, rng);
+            let entries = ids
                 .iter()
                 .map(|id| match self.verifier_data.get(id) {
-                    Some(data) => Ok(data.clone()),
+                    Some((proof, vk, usr_ins)) => {
+                        Ok((proof, self.resolve_vk(vk)?, usr_ins.as_slice()))
+                    }
                     None => Err(ProvingSystemError::ProofNotPresent(*id)),
                 })
                 .collect::<Result<Vec<_>, ProvingSystemError>>()?;
+            let to_verify = entries
+                .iter()
+                .map(|(proof, vk, usr_ins)| (*proof, vk.as_ref(), *usr_ins))
+                .collect::<Vec<_>>();
 
             // Perform batch verifications of the requested proofs
-            let res = Self::batch_verify_proofs(to_verify, &g1_ck, &g2_ck, rng);
+            let res =
+                Self::batch_verify_proofs_with_deadline(&to_verify, deadline, &g1_ck, &g2_ck, rng)
+                    .ok_or(ProvingSystemError::Timeout)?;
 
             // Return the id of the first failing proof if it's possible to determine it
             if let Err(res) = res {
-                match res {
-                    Some(indices) => {
-                        let mut offending_ids =
-                            indices.into_iter().map(|idx| ids[idx]).collect::<Vec<_>>();
-                        offending_ids.sort_unstable();
-                        return Err(ProvingSystemError::FailedBatchVerification(Some(
-                            offending_ids,
-                        )));
+                let err = Self::batch_failure(&ids, res);
+                return Err(match mode {
+                    BatchVerificationMode::Configured if self.diagnostic_mode => {
+                        self.diagnose_failure(&ids, err, rng)
                     }
-                    None => return Err(ProvingSystemError::FailedBatchVerification(None)),
-                }
+                    BatchVerificationMode::Configured | BatchVerificationMode::FailFast => err,
+                    BatchVerificationMode::CollectAll => self.diagnose_all(&ids, err, rng),
+                });
             }
 
-            Ok(res.unwrap())
+            match res.unwrap() {
+                false if mode == BatchVerificationMode::CollectAll => Err(self.diagnose_all(
+                    &ids,
+                    ProvingSystemError::FailedBatchVerification(None),
+                    rng,
+                )),
+                res => Ok(res),
+            }
+        }
+    }
+
+    /// Map the indices of the offending proofs, if known, reported by `batch_verify_proofs()`
+    /// for the proofs with the given `ids` (in batch order) to a `FailedBatchVerification` error.
+    fn batch_failure(ids: &[u32], offending_indices: Option<Vec<usize>>) -> ProvingSystemError {
+        ProvingSystemError::FailedBatchVerification(offending_indices.map(|indices| {
+            let mut offending_ids = indices.into_iter().map(|idx| ids[idx]).collect::<Vec<_>>();
+            offending_ids.sort_unstable();
+            offending_ids
+        }))
+    }
+
+    /// Verify one by one the offending proofs reported by `err` (or all the proofs
+    /// with the given `ids`, if they are unknown), collecting the reason for which
+    /// each of them fails. If no proof fails on its own, `err` is returned unchanged.
+    fn diagnose_failure<R: RngCore>(
+        &self,
+        ids: &[u32],
+        err: ProvingSystemError,
+        rng: &mut R,
+    ) -> ProvingSystemError {
+        let candidates = match &err {
+            ProvingSystemError::FailedBatchVerification(Some(offending_ids)) => {
+                offending_ids.clone()
+            }
+            _ => {
+                let mut ids = ids.to_vec();
+                ids.sort_unstable();
+                ids
+            }
+        };
+
+        let details = candidates
+            .into_iter()
+            .filter_map(|id| {
+                let (proof, vk, usr_ins) = &self.verifier_data[&id];
+                let vk = match self.resolve_vk(vk) {
+                    Ok(vk) => vk,
+                    Err(e) => return Some((id, e.to_string())),
+                };
+                match verify_zendoo_proof_with_circuit_inputs(usr_ins, proof, &vk, Some(&mut *rng))
+                {
+                    Ok(true) => None,
+                    Ok(false) => Some((id, "Proof is not valid".to_owned())),
+                    Err(e) => Some((id, e.to_string())),
+                }
+            })
+            .collect::<Vec<_>>();
+
+        if details.is_empty() {
+            err
+        } else {
+            ProvingSystemError::FailedBatchVerificationWithDetails(details)
+        }
+    }
+
+    /// Verify one by one all the proofs with the given `ids`, even if some of the offending
+    /// ones are already reported by `err`, collecting the reason for which each of them fails.
+    /// If no proof fails on its own, `err` is returned unchanged.
+    fn diagnose_all<R: RngCore>(
+        &self,
+        ids: &[u32],
+        err: ProvingSystemError,
+        rng: &mut R,
+    ) -> ProvingSystemError {
+        match self.diagnose_failure(ids, ProvingSystemError::FailedBatchVerification(None), rng) {
+            details @ ProvingSystemError::FailedBatchVerificationWithDetails(_) => details,
+            _ => err,
         }
     }
 
+    /// Same as `batch_verify_subset`, but `ProvingSystemError::Timeout` is returned if
+    /// `deadline` is reached before all the proofs have been verified.
+    /// The deadline is checked before the succinct verification of each proof: once all of
+    /// them have been succinctly verified, the final check of their accumulators, costing
+    /// as much as in `batch_verify_subset`, is always completed.
+    pub fn batch_verify_subset_with_deadline<R: RngCore>(
+        &self,
+        ids: Vec<u32>,
+        rng: &mut R,
+        deadline: Instant,
+    ) -> Result<bool, ProvingSystemError> {
+        self.verify_subset(ids, BatchVerificationMode::Configured, Some(deadline), rng)
+    }
+
     /// Verify all the proofs in `verifier_data`.
     /// If the verification procedure fails, it may be possible to get the id of
     /// the proof that has caused the failure.
     pub fn batch_verify_all<R: RngCore>(&self, rng: &mut R) -> Result<bool, ProvingSystemError> {
         self.batch_verify_subset(self.verifier_data.keys().copied().collect::<Vec<_>>(), rng)
     }
+
+    /// Verify-only counterpart of `batch_verify_all`, consuming `self`: proofs and inputs are moved
+    /// into the PCDs instead of being copied out of `verifier_data`, and each entry is dropped as soon
+    /// as it has been succinctly verified.
+    /// As the proofs are consumed, a failure is always reported as in `BatchVerificationMode::FailFast`,
+    /// i.e. without verifying the offending proofs one by one, even if the diagnostic mode is enabled.
+    pub fn into_batch_verify_all<R: RngCore>(
+        mut self,
+        rng: &mut R,
+    ) -> Result<bool, ProvingSystemError> {
+        // Retrieve (cached) committer keys
+        let g1_ck = get_full_g1_committer_key()?;
+        let g2_ck = get_full_g2_committer_key()?;
+
+        if self.verifier_data.is_empty() {
+            return Err(ProvingSystemError::NoProofsToVerify);
+        }
+
+        let ids = self.batch_order(self.verifier_data.keys().copied().collect(), rng);
+        let entries = self.take_entries(&ids)?;
+
+        #[cfg(feature = "parallel")]
+        let entries = entries.into_par_iter();
+        #[cfg(not(feature = "parallel"))]
+        let entries = entries.into_iter();
+
+        entries
+            .enumerate()
+            .map(|(idx, (proof, vk, usr_ins))| {
+                Self::succinct_verify(proof, &vk, usr_ins, &g1_ck, &g2_ck)
+                    .ok_or_else(|| Some(vec![idx]))
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .and_then(|accumulators| Self::check_accumulators(accumulators, &g1_ck, &g2_ck, rng))
+            .map_err(|res| Self::batch_failure(&ids, res))
+    }
+
+    /// Same as `batch_verify_all`, handling a failure according to `mode`, as
+    /// `batch_verify_subset_with_mode` does.
+    pub fn batch_verify_all_with_mode<R: RngCore>(
+        &self,
+        mode: BatchVerificationMode,
+        rng: &mut R,
+    ) -> Result<bool, ProvingSystemError> {
+        self.batch_verify_subset_with_mode(
+            self.verifier_data.keys().copied().collect::<Vec<_>>(),
+            mode,
+            rng,
+        )
+    }
+
+    /// Verify only the dirty proofs, i.e. the ones whose vk has been replaced through
+    /// `rebind_vk()`. If the verification succeeds, the proofs are not dirty anymore;
+    /// otherwise they stay dirty, and it may be possible to get the id of the proof
+    /// that has caused the failure.
+    pub fn batch_verify_dirty<R: RngCore>(
+        &mut self,
+        rng: &mut R,
+    ) -> Result<bool, ProvingSystemError> {
+        let res = self.batch_verify_subset(self.dirty_ids(), rng)?;
+        if res {
+            self.dirty_ids.clear();
+        }
+        Ok(res)
+    }
+
+    /// Estimate the cost of verifying in a batch the proofs with the given `ids`,
+    /// based on their proving system, size and number of inputs, and on the size
+    /// of the loaded committer keys.
+    pub fn estimate_verification_cost(
+        &self,
+        ids: &[u32],
+    ) -> Result<VerificationCost, ProvingSystemError> {
+        if ids.is_empty() {
+            return Err(ProvingSystemError::NoProofsToVerify);
+        }
+
+        let mut cost = VerificationCost::default();
+        let mut has_darlin_proofs = false;
+        for id in ids.iter() {
+            let (proof, _, usr_ins) = self
+                .verifier_data
+                .get(id)
+                .ok_or(ProvingSystemError::ProofNotPresent(*id))?;
+            cost.num_proofs += 1;
+            cost.num_inputs += usr_ins.len();
+            cost.num_proof_group_elements += proof.serialized_size() / GROUP_COMPRESSED_SIZE;
+            has_darlin_proofs |= proof.get_proving_system_type() == ProvingSystem::Darlin;
+        }
+
+        cost.msm_size = get_g1_max_degree()? + 1;
+        if has_darlin_proofs {
+            cost.msm_size += get_g2_max_degree()? + 1;
+        }
+
+        Ok(cost)
+    }
+
+    /// Verify all the proofs in `verifier_data` for which `predicate`, invoked with
+    /// the id, the proof and the vk slot of each entry, returns true.
+    /// The vks referenced by the entries are not resolved by the selection, but only
+    /// for the selected entries, when verifying them.
+    /// This allows to express selections like "all the proofs of a given sidechain"
+    /// (e.g. if the sidechain is encoded in the upper bits of the id) without
+    /// maintaining external maps of ids.
+    /// If the verification procedure fails, it may be possible to get the id of
+    /// the proof that has caused the failure.
+    pub fn batch_verify_filtered<P, R>(
+        &self,
+        mut predicate: P,
+        rng: &mut R,
+    ) -> Result<bool, ProvingSystemError>
+    where
+        P: FnMut(u32, &ZendooProof, &VkSlot) -> bool,
+        R: RngCore,
+    {
+        let ids = self
+            .verifier_data
+            .iter()
+            .filter(|(id, (proof, vk, _))| predicate(**id, proof, vk))
+            .map(|(id, _)| *id)
+            .collect::<Vec<_>>();
+
+        self.batch_verify_subset(ids, rng)
+    }
+
+    /// Same as `batch_verify_subset`, but additionally returns statistics about the verified
+    /// proofs, split per proving system.
+    /// In order to measure the verification time of each proving system, the proofs of
+    /// different proving systems are verified in separate batches: CoboundaryMarlin proofs
+    /// first, then Darlin ones (the latter are not verified if the former fail).
+    pub fn batch_verify_subset_with_stats<R: RngCore>(
+        &self,
+        ids: Vec<u32>,
+        rng: &mut R,
+    ) -> Result<(bool, BatchVerificationStats), ProvingSystemError> {
+        if ids.is_empty() {
+            return Err(ProvingSystemError::NoProofsToVerify);
+        }
+
+        // Split ids according to the proving system of the corresponding proof
+        let mut coboundary_marlin_ids = Vec::new();
+        let mut darlin_ids = Vec::new();
+        for id in ids.into_iter() {
+            match self.verifier_data.get(&id) {
+                Some((proof, _, _)) => match proof.get_proving_system_type() {
+                    ProvingSystem::CoboundaryMarlin => coboundary_marlin_ids.push(id),
+                    ProvingSystem::Darlin => darlin_ids.push(id),
+                    ProvingSystem::Undefined => {
+                        return Err(ProvingSystemError::UndefinedProvingSystem)
+                    }
+                },
+                None => return Err(ProvingSystemError::ProofNotPresent(id)),
+            }
+        }
+
+        let mut stats = BatchVerificationStats::default();
+
+        let (coboundary_marlin_res, coboundary_marlin_stats) =
+            self.batch_verify_group_with_stats(coboundary_marlin_ids, rng)?;
+        stats.coboundary_marlin = coboundary_marlin_stats;
+        if !coboundary_marlin_res {
+            return Ok((false, stats));
+        }
+
+        let (darlin_res, darlin_stats) = self.batch_verify_group_with_stats(darlin_ids, rng)?;
+        stats.darlin = darlin_stats;
+
+        Ok((darlin_res, stats))
+    }
+
+    /// Same as `batch_verify_all`, but additionally returns statistics about the verified
+    /// proofs, split per proving system. See `batch_verify_subset_with_stats` for details.
+    pub fn batch_verify_all_with_stats<R: RngCore>(
+        &self,
+        rng: &mut R,
+    ) -> Result<(bool, BatchVerificationStats), ProvingSystemError> {
+        self.batch_verify_subset_with_stats(
+            self.verifier_data.keys().copied().collect::<Vec<_>>(),
+            rng,
+        )
+    }
+
+    /// Batch verify the proofs with the given `ids`, assumed to be present and to
+    /// belong to the same proving system, collecting statistics about them.
+    /// An empty group is trivially verified.
+    fn batch_verify_group_with_stats<R: RngCore>(
+        &self,
+        ids: Vec<u32>,
+        rng: &mut R,
+    ) -> Result<(bool, ProvingSystemStats), ProvingSystemError> {
+        if ids.is_empty() {
+            return Ok((true, ProvingSystemStats::default()));
+        }
+
+        let mut total_constraints = 0;
+        for id in ids.iter() {
+            let (_, vk, _) = &self.verifier_data[id];
+            total_constraints += match self.resolve_vk(vk)?.as_ref() {
+                ZendooVerifierKey::CoboundaryMarlin(vk) => vk.index_info.num_constraints,
+                ZendooVerifierKey::Darlin(vk) => vk.index_info.num_constraints,
+            };
+        }
+        let num_proofs = ids.len();
+
+        let start = Instant::now();
+        let res = self.batch_verify_subset(ids, rng)?;
+        let verification_time = start.elapsed();
+
+        Ok((
+            res,
+            ProvingSystemStats {
+                num_proofs,
+                total_constraints,
+                verification_time,
+            },
+        ))
+    }
 }
 
 #[cfg(test)]
@@ -168,7 +937,7 @@ mod test {
         },
         type_mapping::{FieldElement, G1, G2},
         utils::{
-            commitment_tree::{rand_fe, rand_vec},
+            commitment_tree::{rand_fe_with_rng, rand_vec_with_rng},
             data_structures::BackwardTransfer,
         },
     };
@@ -178,7 +947,7 @@ mod test {
         final_darlin::generate_test_data as generate_final_darlin_test_data,
         simple_marlin::generate_test_data as generate_simple_marlin_test_data,
     };
-    use rand::{thread_rng, Rng};
+    use rand::{rngs::StdRng, thread_rng, Rng, SeedableRng};
     use serial_test::serial;
 
     // ***********************Tests with real test circuit*************************
@@ -284,18 +1053,166 @@ mod test {
         }
     }
 
-    use std::collections::HashSet;
+    #[test]
+    #[serial]
+    fn prepared_vk_test() {
+        let generation_rng = &mut thread_rng();
+        let (params_g1, params_g2, _, segment_size) = get_params();
 
-    fn randomize_batch_verifier_data<R: RngCore>(
-        batch_verifier: &mut ZendooBatchVerifier,
-        num_proofs: u32,
-        ids_offset: u32,
-        rng: &mut R,
-    ) -> HashSet<u32> {
-        // Select num proofs to randomize
-        let num_proofs_to_randomize = rng.gen_range(1..num_proofs);
+        let (simple_pcds, simple_vks) = generate_simple_marlin_test_data(
+            segment_size - 1,
+            segment_size,
+            &params_g1,
+            3,
+            generation_rng,
+        );
+        let (final_pcds, final_vks) = generate_final_darlin_test_data(
+            segment_size - 1,
+            segment_size,
+            &params_g1,
+            &params_g2,
+            1,
+            generation_rng,
+        );
 
-        // Select ids
+        let simple_vk = ZendooVerifierKey::CoboundaryMarlin(simple_vks[0].clone());
+        let prepared_simple_vk = PreparedZendooVk::prepare(simple_vk.clone()).unwrap();
+        assert_eq!(
+            prepared_simple_vk.get_proving_system_type(),
+            ProvingSystem::CoboundaryMarlin
+        );
+        let prepared_final_vk =
+            PreparedZendooVk::prepare(ZendooVerifierKey::Darlin(final_vks[0].clone())).unwrap();
+        assert_eq!(
+            prepared_final_vk.get_proving_system_type(),
+            ProvingSystem::Darlin
+        );
+
+        // The same prepared vk verifies many proofs, as the unprepared one does
+        let mut batch_verifier = ZendooBatchVerifier::create();
+        for (id, pcd) in simple_pcds.iter().enumerate() {
+            let proof = ZendooProof::CoboundaryMarlin(pcd.proof.clone());
+            assert!(verify_zendoo_proof_prepared(
+                pcd.usr_ins.as_slice(),
+                &proof,
+                &prepared_simple_vk,
+                Some(generation_rng)
+            )
+            .unwrap());
+            assert!(verify_zendoo_proof(
+                pcd.usr_ins.as_slice(),
+                &proof,
+                &simple_vk,
+                Some(generation_rng)
+            )
+            .unwrap());
+            batch_verifier
+                .add_zendoo_proof_verifier_data_prepared(
+                    id as u32,
+                    pcd.usr_ins.clone(),
+                    proof,
+                    &prepared_simple_vk,
+                )
+                .unwrap();
+        }
+
+        let final_proof = ZendooProof::Darlin(final_pcds[0].final_darlin_proof.clone());
+        assert!(verify_zendoo_proof_prepared(
+            final_pcds[0].usr_ins.as_slice(),
+            &final_proof,
+            &prepared_final_vk,
+            Some(generation_rng)
+        )
+        .unwrap());
+        batch_verifier
+            .add_zendoo_proof_verifier_data_prepared(
+                3,
+                final_pcds[0].usr_ins.clone(),
+                final_proof.clone(),
+                &prepared_final_vk,
+            )
+            .unwrap();
+        assert!(batch_verifier.batch_verify_all(generation_rng).unwrap());
+
+        // Wrong inputs
+        let wrong_usr_ins = vec![
+            rand_fe_with_rng(generation_rng),
+            rand_fe_with_rng(generation_rng),
+        ];
+        let res = verify_zendoo_proof_prepared(
+            wrong_usr_ins,
+            &ZendooProof::CoboundaryMarlin(simple_pcds[0].proof.clone()),
+            &prepared_simple_vk,
+            Some(generation_rng),
+        );
+        assert!(res.is_err() || !res.unwrap());
+
+        // Mismatching proving systems
+        assert!(matches!(
+            verify_zendoo_proof_prepared(
+                final_pcds[0].usr_ins.as_slice(),
+                &final_proof,
+                &prepared_simple_vk,
+                Some(generation_rng)
+            ),
+            Err(ProvingSystemError::ProvingSystemMismatch)
+        ));
+        assert!(matches!(
+            ZendooBatchVerifier::create().add_zendoo_proof_verifier_data_prepared(
+                0,
+                final_pcds[0].usr_ins.clone(),
+                final_proof,
+                &prepared_simple_vk,
+            ),
+            Err(ProvingSystemError::ProvingSystemMismatch)
+        ));
+    }
+
+    #[test]
+    #[serial]
+    fn raw_user_inputs_test() {
+        let generation_rng = &mut thread_rng();
+        let (params_g1, _, _, segment_size) = get_params();
+        let (pcds, vks) = generate_simple_marlin_test_data(
+            segment_size - 1,
+            segment_size,
+            &params_g1,
+            1,
+            generation_rng,
+        );
+        let proof = ZendooProof::CoboundaryMarlin(pcds[0].proof.clone());
+        let vk = ZendooVerifierKey::CoboundaryMarlin(vks[0].clone());
+        let usr_ins = pcds[0].usr_ins.clone();
+
+        // Inputs as a slice and as a vector can be used directly
+        assert!(
+            verify_zendoo_proof(usr_ins.as_slice(), &proof, &vk, Some(generation_rng)).unwrap()
+        );
+        assert!(verify_zendoo_proof(usr_ins.clone(), &proof, &vk, Some(generation_rng)).unwrap());
+
+        let mut batch_verifier = ZendooBatchVerifier::create();
+        batch_verifier.set_allow_duplicate_proofs(true);
+        batch_verifier
+            .add_zendoo_proof_verifier_data(0, usr_ins.as_slice(), proof.clone(), vk.clone())
+            .unwrap();
+        batch_verifier
+            .add_zendoo_proof_verifier_data(1, usr_ins, proof, vk)
+            .unwrap();
+        assert!(batch_verifier.batch_verify_all(generation_rng).unwrap());
+    }
+
+    use std::collections::HashSet;
+
+    fn randomize_batch_verifier_data<R: RngCore>(
+        batch_verifier: &mut ZendooBatchVerifier,
+        num_proofs: u32,
+        ids_offset: u32,
+        rng: &mut R,
+    ) -> HashSet<u32> {
+        // Select num proofs to randomize
+        let num_proofs_to_randomize = rng.gen_range(1..num_proofs);
+
+        // Select ids
         let ids = (0..num_proofs_to_randomize)
             .map(|_| rng.gen_range(0..num_proofs) + ids_offset)
             .collect::<HashSet<u32>>();
@@ -417,6 +1334,956 @@ mod test {
         }
     }
 
+    #[test]
+    #[serial]
+    fn batch_verifier_stats_test() {
+        let num_proofs = 20;
+        let generation_rng = &mut thread_rng();
+        let mut batch_verifier = ZendooBatchVerifier::create();
+
+        let (params_g1, params_g2, _, segment_size) = get_params();
+        let num_constraints = segment_size;
+
+        let mut expected_stats = BatchVerificationStats::default();
+        for i in 0..num_proofs {
+            // Alternate SimpleMarlin and FinalDarlin proofs
+            let (proof, vk, usr_ins) = if i % 2 == 0 {
+                let (pcds, vks) = generate_simple_marlin_test_data(
+                    num_constraints - 1,
+                    segment_size,
+                    &params_g1,
+                    1,
+                    generation_rng,
+                );
+                expected_stats.coboundary_marlin.num_proofs += 1;
+                expected_stats.coboundary_marlin.total_constraints +=
+                    vks[0].index_info.num_constraints;
+                (
+                    ZendooProof::CoboundaryMarlin(pcds[0].proof.clone()),
+                    ZendooVerifierKey::CoboundaryMarlin(vks[0].clone()),
+                    TestCircuitInputs {
+                        c: pcds[0].usr_ins[0],
+                        d: pcds[0].usr_ins[1],
+                    },
+                )
+            } else {
+                let (pcds, vks) = generate_final_darlin_test_data(
+                    num_constraints - 1,
+                    segment_size,
+                    &params_g1,
+                    &params_g2,
+                    1,
+                    generation_rng,
+                );
+                expected_stats.darlin.num_proofs += 1;
+                expected_stats.darlin.total_constraints += vks[0].index_info.num_constraints;
+                (
+                    ZendooProof::Darlin(pcds[0].final_darlin_proof.clone()),
+                    ZendooVerifierKey::Darlin(vks[0].clone()),
+                    TestCircuitInputs {
+                        c: pcds[0].usr_ins[0],
+                        d: pcds[0].usr_ins[1],
+                    },
+                )
+            };
+
+            batch_verifier
+                .add_zendoo_proof_verifier_data(i, usr_ins, proof, vk)
+                .unwrap();
+        }
+
+        let (res, stats) = batch_verifier
+            .batch_verify_all_with_stats(generation_rng)
+            .unwrap();
+        assert!(res);
+        assert_eq!(stats.num_proofs(), num_proofs as usize);
+        assert_eq!(
+            stats.coboundary_marlin.num_proofs,
+            expected_stats.coboundary_marlin.num_proofs
+        );
+        assert_eq!(stats.darlin.num_proofs, expected_stats.darlin.num_proofs);
+        assert_eq!(
+            stats.coboundary_marlin.total_constraints,
+            expected_stats.coboundary_marlin.total_constraints
+        );
+        assert_eq!(
+            stats.darlin.total_constraints,
+            expected_stats.darlin.total_constraints
+        );
+
+        // Only Darlin proofs: no time must be spent on CoboundaryMarlin ones
+        let darlin_ids = (0..num_proofs).filter(|i| i % 2 == 1).collect::<Vec<_>>();
+        let (res, stats) = batch_verifier
+            .batch_verify_subset_with_stats(darlin_ids, generation_rng)
+            .unwrap();
+        assert!(res);
+        assert_eq!(stats.coboundary_marlin, ProvingSystemStats::default());
+        assert_eq!(stats.darlin.num_proofs, expected_stats.darlin.num_proofs);
+
+        // Unknown ids must be rejected
+        assert!(matches!(
+            batch_verifier.batch_verify_subset_with_stats(vec![num_proofs], generation_rng),
+            Err(ProvingSystemError::ProofNotPresent(id)) if id == num_proofs
+        ));
+    }
+
+    #[test]
+    #[serial]
+    fn batch_verifier_filtered_test() {
+        let num_proofs_per_sc = 3;
+        let generation_rng = &mut thread_rng();
+        let mut batch_verifier = ZendooBatchVerifier::create();
+        // The same proof is added under different ids
+        batch_verifier.set_allow_duplicate_proofs(true);
+
+        let (params_g1, _, _, segment_size) = get_params();
+        let num_constraints = segment_size;
+
+        // Encode the sidechain index in the upper 16 bits of the id
+        let sc_of = |id: u32| id >> 16;
+        for sc in 0..2u32 {
+            for i in 0..num_proofs_per_sc {
+                let (pcds, vks) = generate_simple_marlin_test_data(
+                    num_constraints - 1,
+                    segment_size,
+                    &params_g1,
+                    1,
+                    generation_rng,
+                );
+
+                // Make the last proof of sidechain 1 fail
+                let d = if sc == 1 && i == num_proofs_per_sc - 1 {
+                    rand_fe_with_rng(generation_rng)
+                } else {
+                    pcds[0].usr_ins[1]
+                };
+
+                batch_verifier
+                    .add_zendoo_proof_verifier_data(
+                        (sc << 16) | i,
+                        TestCircuitInputs {
+                            c: pcds[0].usr_ins[0],
+                            d,
+                        },
+                        ZendooProof::CoboundaryMarlin(pcds[0].proof.clone()),
+                        ZendooVerifierKey::CoboundaryMarlin(vks[0].clone()),
+                    )
+                    .unwrap();
+            }
+        }
+
+        // All the proofs of sidechain 0 are valid
+        assert!(batch_verifier
+            .batch_verify_filtered(|id, _, _| sc_of(id) == 0, generation_rng)
+            .unwrap());
+
+        // One of the proofs of sidechain 1 is not
+        match batch_verifier.batch_verify_filtered(|id, _, _| sc_of(id) == 1, generation_rng) {
+            Err(ProvingSystemError::FailedBatchVerification(Some(ids))) => {
+                assert_eq!(ids, vec![(1 << 16) | (num_proofs_per_sc - 1)])
+            }
+            Ok(false) | Err(ProvingSystemError::FailedBatchVerification(None)) => {}
+            _ => panic!("Batch verification of sidechain 1 proofs should have failed"),
+        }
+
+        // Predicate can inspect proofs too
+        assert!(matches!(
+            batch_verifier.batch_verify_filtered(
+                |_, proof, _| proof.get_proving_system_type() == ProvingSystem::Darlin,
+                generation_rng
+            ),
+            Err(ProvingSystemError::NoProofsToVerify)
+        ));
+    }
+
+    #[test]
+    #[serial]
+    fn batch_verifier_rebind_vk_test() {
+        let generation_rng = &mut thread_rng();
+        let mut batch_verifier = ZendooBatchVerifier::create();
+
+        let (params_g1, params_g2, _, segment_size) = get_params();
+        let num_constraints = segment_size;
+
+        // Proofs created with the new vk, but added with the old one
+        let (_, old_vks) = generate_simple_marlin_test_data(
+            num_constraints - 1,
+            segment_size,
+            &params_g1,
+            1,
+            generation_rng,
+        );
+        let (pcds, new_vks) = generate_simple_marlin_test_data(
+            num_constraints - 1,
+            segment_size,
+            &params_g1,
+            3,
+            generation_rng,
+        );
+        for (id, pcd) in pcds.iter().enumerate() {
+            batch_verifier
+                .add_zendoo_proof_verifier_data(
+                    id as u32,
+                    TestCircuitInputs {
+                        c: pcd.usr_ins[0],
+                        d: pcd.usr_ins[1],
+                    },
+                    ZendooProof::CoboundaryMarlin(pcd.proof.clone()),
+                    ZendooVerifierKey::CoboundaryMarlin(old_vks[0].clone()),
+                )
+                .unwrap();
+        }
+        assert!(batch_verifier.dirty_ids().is_empty());
+        match batch_verifier.batch_verify_all(generation_rng) {
+            Ok(false) | Err(ProvingSystemError::FailedBatchVerification(_)) => {}
+            _ => panic!("Verification with the old vk should have failed"),
+        }
+
+        // Nothing is changed if an id is not present or if the proving system doesn't match
+        let new_vk = ZendooVerifierKey::CoboundaryMarlin(new_vks[0].clone());
+        assert!(matches!(
+            batch_verifier.rebind_vk(&[0, 3], new_vk.clone()),
+            Err(ProvingSystemError::ProofNotPresent(3))
+        ));
+        let (_, darlin_vks) = generate_final_darlin_test_data(
+            num_constraints - 1,
+            segment_size,
+            &params_g1,
+            &params_g2,
+            1,
+            generation_rng,
+        );
+        assert!(matches!(
+            batch_verifier.rebind_vk(&[0], ZendooVerifierKey::Darlin(darlin_vks[0].clone())),
+            Err(ProvingSystemError::ProvingSystemMismatch)
+        ));
+        assert!(batch_verifier.dirty_ids().is_empty());
+
+        // Rebind only some of the proofs: only those are verified
+        batch_verifier.rebind_vk(&[2, 0], new_vk.clone()).unwrap();
+        assert_eq!(batch_verifier.dirty_ids(), vec![0, 2]);
+        assert!(batch_verifier.batch_verify_dirty(generation_rng).unwrap());
+        assert!(batch_verifier.dirty_ids().is_empty());
+        assert!(matches!(
+            batch_verifier.batch_verify_dirty(generation_rng),
+            Err(ProvingSystemError::NoProofsToVerify)
+        ));
+
+        // Rebind the remaining one
+        batch_verifier.rebind_vk(&[1], new_vk).unwrap();
+        assert!(batch_verifier.batch_verify_dirty(generation_rng).unwrap());
+        assert!(batch_verifier.batch_verify_all(generation_rng).unwrap());
+    }
+
+    #[test]
+    #[serial]
+    fn batch_verifier_diagnostic_mode_test() {
+        let num_proofs = 4;
+        let generation_rng = &mut thread_rng();
+        let mut batch_verifier = ZendooBatchVerifier::create();
+
+        let (params_g1, _, _, segment_size) = get_params();
+        let (pcds, vks) = generate_simple_marlin_test_data(
+            segment_size - 1,
+            segment_size,
+            &params_g1,
+            num_proofs,
+            generation_rng,
+        );
+        for (id, pcd) in pcds.iter().enumerate() {
+            // Make the second proof fail
+            let d = if id == 1 {
+                rand_fe_with_rng(generation_rng)
+            } else {
+                pcd.usr_ins[1]
+            };
+            batch_verifier
+                .add_zendoo_proof_verifier_data(
+                    id as u32,
+                    TestCircuitInputs {
+                        c: pcd.usr_ins[0],
+                        d,
+                    },
+                    ZendooProof::CoboundaryMarlin(pcd.proof.clone()),
+                    ZendooVerifierKey::CoboundaryMarlin(vks[0].clone()),
+                )
+                .unwrap();
+        }
+
+        // Without diagnostic mode no details are reported
+        match batch_verifier.batch_verify_all(generation_rng) {
+            Ok(false) | Err(ProvingSystemError::FailedBatchVerification(_)) => {}
+            _ => panic!("Batch verification should have failed"),
+        }
+
+        // With diagnostic mode, only the offending proof is reported
+        batch_verifier.set_diagnostic_mode(true);
+        match batch_verifier.batch_verify_all(generation_rng) {
+            Ok(false) => {}
+            Err(ProvingSystemError::FailedBatchVerificationWithDetails(details)) => {
+                assert_eq!(details.len(), 1);
+                assert_eq!(details[0].0, 1);
+                assert!(!details[0].1.is_empty());
+            }
+            _ => panic!("Batch verification should have failed with details"),
+        }
+
+        // Diagnostic mode doesn't affect successful verifications
+        assert!(batch_verifier
+            .batch_verify_subset(vec![0, 2, 3], generation_rng)
+            .unwrap());
+    }
+
+    #[test]
+    #[serial]
+    fn batch_verifier_mode_test() {
+        let num_proofs = 5;
+        let generation_rng = &mut thread_rng();
+        let mut batch_verifier = ZendooBatchVerifier::create();
+
+        let (params_g1, _, _, segment_size) = get_params();
+        let (pcds, vks) = generate_simple_marlin_test_data(
+            segment_size - 1,
+            segment_size,
+            &params_g1,
+            num_proofs,
+            generation_rng,
+        );
+        for (id, pcd) in pcds.iter().enumerate() {
+            // Make the second and the fourth proofs fail
+            let d = if id == 1 || id == 3 {
+                rand_fe_with_rng(generation_rng)
+            } else {
+                pcd.usr_ins[1]
+            };
+            batch_verifier
+                .add_zendoo_proof_verifier_data(
+                    id as u32,
+                    TestCircuitInputs {
+                        c: pcd.usr_ins[0],
+                        d,
+                    },
+                    ZendooProof::CoboundaryMarlin(pcd.proof.clone()),
+                    ZendooVerifierKey::CoboundaryMarlin(vks[0].clone()),
+                )
+                .unwrap();
+        }
+
+        // Fail fast never reports details, even in diagnostic mode
+        batch_verifier.set_diagnostic_mode(true);
+        match batch_verifier
+            .batch_verify_all_with_mode(BatchVerificationMode::FailFast, generation_rng)
+        {
+            Ok(false) | Err(ProvingSystemError::FailedBatchVerification(_)) => {}
+            res => panic!("Unexpected result: {:?}", res),
+        }
+
+        // Collect all reports all the invalid proofs, even without diagnostic mode
+        batch_verifier.set_diagnostic_mode(false);
+        match batch_verifier
+            .batch_verify_all_with_mode(BatchVerificationMode::CollectAll, generation_rng)
+        {
+            Err(ProvingSystemError::FailedBatchVerificationWithDetails(details)) => {
+                assert_eq!(
+                    details.iter().map(|(id, _)| *id).collect::<Vec<_>>(),
+                    vec![1, 3]
+                );
+                assert!(details.iter().all(|(_, reason)| !reason.is_empty()));
+            }
+            res => panic!("Unexpected result: {:?}", res),
+        }
+
+        // Configured mode behaves as batch_verify_subset
+        match batch_verifier
+            .batch_verify_all_with_mode(BatchVerificationMode::default(), generation_rng)
+        {
+            Ok(false) | Err(ProvingSystemError::FailedBatchVerification(_)) => {}
+            res => panic!("Unexpected result: {:?}", res),
+        }
+
+        // Modes don't affect successful verifications
+        for mode in [
+            BatchVerificationMode::Configured,
+            BatchVerificationMode::FailFast,
+            BatchVerificationMode::CollectAll,
+        ]
+        .iter()
+        {
+            assert!(batch_verifier
+                .batch_verify_subset_with_mode(vec![0, 2, 4], *mode, generation_rng)
+                .unwrap());
+        }
+    }
+
+    #[test]
+    #[serial]
+    fn into_batch_verify_all_test() {
+        let num_proofs = 3;
+        let generation_rng = &mut thread_rng();
+
+        let (params_g1, _, _, segment_size) = get_params();
+        let (pcds, vks) = generate_simple_marlin_test_data(
+            segment_size - 1,
+            segment_size,
+            &params_g1,
+            num_proofs,
+            generation_rng,
+        );
+        let wrong_d = rand_fe_with_rng(generation_rng);
+        let get_batch_verifier = |invalid_id: Option<usize>| {
+            let mut batch_verifier = ZendooBatchVerifier::create();
+            batch_verifier.set_diagnostic_mode(true);
+            for (id, pcd) in pcds.iter().enumerate() {
+                batch_verifier
+                    .add_zendoo_proof_verifier_data(
+                        id as u32,
+                        TestCircuitInputs {
+                            c: pcd.usr_ins[0],
+                            d: if invalid_id == Some(id) {
+                                wrong_d
+                            } else {
+                                pcd.usr_ins[1]
+                            },
+                        },
+                        ZendooProof::CoboundaryMarlin(pcd.proof.clone()),
+                        ZendooVerifierKey::CoboundaryMarlin(vks[0].clone()),
+                    )
+                    .unwrap();
+            }
+            batch_verifier
+        };
+
+        // Same result as batch_verify_all
+        let batch_verifier = get_batch_verifier(None);
+        assert!(batch_verifier.batch_verify_all(generation_rng).unwrap());
+        assert!(batch_verifier
+            .into_batch_verify_all(generation_rng)
+            .unwrap());
+
+        // Failures are never diagnosed
+        match get_batch_verifier(Some(1)).into_batch_verify_all(generation_rng) {
+            Ok(false) | Err(ProvingSystemError::FailedBatchVerification(_)) => {}
+            res => panic!("Unexpected result: {:?}", res),
+        }
+
+        assert!(matches!(
+            ZendooBatchVerifier::create().into_batch_verify_all(generation_rng),
+            Err(ProvingSystemError::NoProofsToVerify)
+        ));
+    }
+
+    #[test]
+    #[serial]
+    fn take_entries_test() {
+        let num_proofs = 3;
+        let generation_rng = &mut thread_rng();
+
+        let (params_g1, _, _, segment_size) = get_params();
+        let (pcds, vks) = generate_simple_marlin_test_data(
+            segment_size - 1,
+            segment_size,
+            &params_g1,
+            num_proofs,
+            generation_rng,
+        );
+        let vk_ref = rand_fe_with_rng(generation_rng);
+        let resolver = Arc::new(TestVkResolver {
+            vk_ref,
+            vk: Arc::new(ZendooVerifierKey::CoboundaryMarlin(vks[0].clone())),
+            num_calls: Mutex::new(0),
+        });
+        let mut batch_verifier = ZendooBatchVerifier::create();
+        batch_verifier.set_vk_resolver(Some(resolver.clone()));
+        for (id, pcd) in pcds.iter().enumerate() {
+            let inputs = TestCircuitInputs {
+                c: pcd.usr_ins[0],
+                d: pcd.usr_ins[1],
+            };
+            let proof = ZendooProof::CoboundaryMarlin(pcd.proof.clone());
+            if id == 0 {
+                batch_verifier
+                    .add_zendoo_proof_verifier_data(
+                        id as u32,
+                        inputs,
+                        proof,
+                        ZendooVerifierKey::CoboundaryMarlin(vks[0].clone()),
+                    )
+                    .unwrap();
+            } else {
+                batch_verifier
+                    .add_zendoo_proof_verifier_data_with_vk_ref(id as u32, inputs, proof, &vk_ref)
+                    .unwrap();
+            }
+        }
+
+        // Resolved vks are cached
+        assert!(batch_verifier.batch_verify_all(generation_rng).unwrap());
+        assert_eq!(Arc::strong_count(&resolver.vk), 2);
+
+        // The entries are the only owners of their vks, besides the resolver
+        let entries = batch_verifier.take_entries(&[1, 2]).unwrap();
+        assert_eq!(Arc::strong_count(&resolver.vk), 3);
+        assert_eq!(*resolver.num_calls.lock().unwrap(), 1);
+        assert_eq!(batch_verifier.num_proofs(), 1);
+        drop(entries);
+        assert_eq!(Arc::strong_count(&resolver.vk), 1);
+
+        // A vk not shared with other entries can be moved
+        let mut batch_verifier = ZendooBatchVerifier::create();
+        batch_verifier
+            .add_zendoo_proof_verifier_data(
+                0,
+                TestCircuitInputs {
+                    c: pcds[0].usr_ins[0],
+                    d: pcds[0].usr_ins[1],
+                },
+                ZendooProof::CoboundaryMarlin(pcds[0].proof.clone()),
+                ZendooVerifierKey::CoboundaryMarlin(vks[0].clone()),
+            )
+            .unwrap();
+        assert!(batch_verifier.batch_verify_all(generation_rng).unwrap());
+        let (_, vk, _) = batch_verifier.take_entries(&[0]).unwrap().pop().unwrap();
+        assert_eq!(Arc::strong_count(&vk), 1);
+        assert!(Arc::try_unwrap(vk).is_ok());
+    }
+
+    struct TestVkResolver {
+        vk_ref: FieldElement,
+        vk: Arc<ZendooVerifierKey>,
+        num_calls: Mutex<usize>,
+    }
+
+    impl VkResolver for TestVkResolver {
+        fn resolve(
+            &self,
+            vk_ref: &FieldElement,
+        ) -> Result<Arc<ZendooVerifierKey>, ProvingSystemError> {
+            *self.num_calls.lock().unwrap() += 1;
+            if vk_ref == &self.vk_ref {
+                Ok(self.vk.clone())
+            } else {
+                Err(ProvingSystemError::VkResolutionFailed(format!(
+                    "Unknown vk: {:?}",
+                    vk_ref
+                )))
+            }
+        }
+    }
+
+    #[test]
+    #[serial]
+    fn vk_resolver_test() {
+        let num_proofs = 3;
+        let generation_rng = &mut thread_rng();
+
+        let (params_g1, _, _, segment_size) = get_params();
+        let (pcds, vks) = generate_simple_marlin_test_data(
+            segment_size - 1,
+            segment_size,
+            &params_g1,
+            num_proofs + 1,
+            generation_rng,
+        );
+        let add_proof =
+            |batch_verifier: &mut ZendooBatchVerifier, id: usize, vk_ref: &FieldElement| {
+                batch_verifier.add_zendoo_proof_verifier_data_with_vk_ref(
+                    id as u32,
+                    TestCircuitInputs {
+                        c: pcds[id].usr_ins[0],
+                        d: pcds[id].usr_ins[1],
+                    },
+                    ZendooProof::CoboundaryMarlin(pcds[id].proof.clone()),
+                    vk_ref,
+                )
+            };
+
+        // No resolver set
+        let vk_ref = rand_fe_with_rng(generation_rng);
+        let mut batch_verifier = ZendooBatchVerifier::create();
+        assert!(matches!(
+            add_proof(&mut batch_verifier, 0, &vk_ref),
+            Err(ProvingSystemError::VkResolutionFailed(_))
+        ));
+
+        // Entries referencing the same vk resolve it once
+        let resolver = Arc::new(TestVkResolver {
+            vk_ref,
+            vk: Arc::new(ZendooVerifierKey::CoboundaryMarlin(vks[0].clone())),
+            num_calls: Mutex::new(0),
+        });
+        batch_verifier.set_vk_resolver(Some(resolver.clone()));
+        for id in 0..num_proofs {
+            add_proof(&mut batch_verifier, id, &vk_ref).unwrap();
+        }
+        assert_eq!(*resolver.num_calls.lock().unwrap(), 0);
+        assert!(batch_verifier.batch_verify_all(generation_rng).unwrap());
+        assert!(batch_verifier.batch_verify_all(generation_rng).unwrap());
+        assert_eq!(*resolver.num_calls.lock().unwrap(), 1);
+
+        // Resolved again once the cache is cleared
+        batch_verifier.clear_vk_cache();
+        assert!(batch_verifier.batch_verify_all(generation_rng).unwrap());
+        assert_eq!(*resolver.num_calls.lock().unwrap(), 2);
+
+        // Entries with and without vk reference can be mixed
+        batch_verifier
+            .add_zendoo_proof_verifier_data(
+                num_proofs as u32,
+                TestCircuitInputs {
+                    c: pcds[num_proofs].usr_ins[0],
+                    d: pcds[num_proofs].usr_ins[1],
+                },
+                ZendooProof::CoboundaryMarlin(pcds[num_proofs].proof.clone()),
+                ZendooVerifierKey::CoboundaryMarlin(vks[0].clone()),
+            )
+            .unwrap();
+        assert!(batch_verifier.batch_verify_all(generation_rng).unwrap());
+
+        // Unresolvable vks make the verification fail
+        let wrong_vk_ref = rand_fe_with_rng(generation_rng);
+        add_proof(&mut batch_verifier, 0, &wrong_vk_ref).unwrap();
+        assert!(matches!(
+            batch_verifier.batch_verify_subset(vec![0, 1], generation_rng),
+            Err(ProvingSystemError::VkResolutionFailed(_))
+        ));
+        assert!(batch_verifier
+            .batch_verify_subset(vec![1, 2], generation_rng)
+            .unwrap());
+
+        // Only the vks of the entries selected by the predicate are resolved
+        assert!(batch_verifier
+            .batch_verify_filtered(|id, _, _| id != 0, generation_rng)
+            .unwrap());
+        assert!(matches!(
+            batch_verifier.batch_verify_filtered(
+                |_, _, vk| matches!(vk, VkSlot::Unresolved(vk_ref) if *vk_ref == wrong_vk_ref),
+                generation_rng
+            ),
+            Err(ProvingSystemError::VkResolutionFailed(_))
+        ));
+        batch_verifier.set_diagnostic_mode(true);
+        match batch_verifier
+            .batch_verify_all_with_mode(BatchVerificationMode::CollectAll, generation_rng)
+        {
+            Err(ProvingSystemError::VkResolutionFailed(_)) => {}
+            res => panic!("Unexpected result: {:?}", res),
+        }
+    }
+
+    #[test]
+    #[serial]
+    fn batch_verifier_deadline_test() {
+        let num_proofs = 4;
+        let generation_rng = &mut thread_rng();
+        let mut batch_verifier = ZendooBatchVerifier::create();
+        // The same proof is added under different ids
+        batch_verifier.set_allow_duplicate_proofs(true);
+
+        let (params_g1, _, _, segment_size) = get_params();
+        let (pcds, vks) = generate_simple_marlin_test_data(
+            segment_size - 1,
+            segment_size,
+            &params_g1,
+            num_proofs,
+            generation_rng,
+        );
+        for (id, pcd) in pcds.iter().enumerate() {
+            batch_verifier
+                .add_zendoo_proof_verifier_data(
+                    id as u32,
+                    pcd.usr_ins.clone(),
+                    ZendooProof::CoboundaryMarlin(pcd.proof.clone()),
+                    ZendooVerifierKey::CoboundaryMarlin(vks[0].clone()),
+                )
+                .unwrap();
+        }
+        let ids = (0..num_proofs as u32).collect::<Vec<_>>();
+        let far_deadline = Instant::now() + Duration::from_secs(3600);
+
+        // Enough time to verify all the proofs
+        assert!(batch_verifier
+            .batch_verify_subset_with_deadline(ids.clone(), generation_rng, far_deadline)
+            .unwrap());
+
+        // Deadline already expired
+        assert!(matches!(
+            batch_verifier.batch_verify_subset_with_deadline(
+                ids.clone(),
+                generation_rng,
+                Instant::now()
+            ),
+            Err(ProvingSystemError::Timeout)
+        ));
+
+        // Invalid requests are rejected regardless of the deadline
+        assert!(matches!(
+            batch_verifier.batch_verify_subset_with_deadline(vec![], generation_rng, far_deadline),
+            Err(ProvingSystemError::NoProofsToVerify)
+        ));
+        assert!(matches!(
+            batch_verifier.batch_verify_subset_with_deadline(
+                vec![0, num_proofs as u32],
+                generation_rng,
+                Instant::now()
+            ),
+            Err(ProvingSystemError::ProofNotPresent(id)) if id == num_proofs as u32
+        ));
+
+        // A failing proof is detected
+        batch_verifier
+            .add_zendoo_proof_verifier_data(
+                num_proofs as u32,
+                vec![rand_fe_with_rng(generation_rng); pcds[0].usr_ins.len()],
+                ZendooProof::CoboundaryMarlin(pcds[0].proof.clone()),
+                ZendooVerifierKey::CoboundaryMarlin(vks[0].clone()),
+            )
+            .unwrap();
+        let mut ids = ids;
+        ids.push(num_proofs as u32);
+        match batch_verifier.batch_verify_subset_with_deadline(ids, generation_rng, far_deadline) {
+            Ok(false) | Err(ProvingSystemError::FailedBatchVerification(_)) => {}
+            _ => panic!("Batch verification should have failed"),
+        }
+    }
+
+    #[test]
+    fn batch_verifier_shuffle_test() {
+        let mut batch_verifier = ZendooBatchVerifier::create();
+        let ids = (0..100u32).collect::<Vec<_>>();
+
+        // Same seed, same order
+        let order = batch_verifier.batch_order(ids.clone(), &mut StdRng::seed_from_u64(42));
+        assert_eq!(
+            order,
+            batch_verifier.batch_order(ids.clone(), &mut StdRng::seed_from_u64(42))
+        );
+        assert_ne!(order, ids);
+
+        // The order is always a permutation of the ids
+        let mut sorted_order = order;
+        sorted_order.sort_unstable();
+        assert_eq!(sorted_order, ids);
+
+        // Shuffling disabled, order preserved
+        batch_verifier.set_shuffle_batches(false);
+        assert_eq!(
+            batch_verifier.batch_order(ids.clone(), &mut StdRng::seed_from_u64(42)),
+            ids
+        );
+    }
+
+    #[test]
+    #[serial]
+    fn batch_verifier_shuffle_disabled_test() {
+        let num_proofs = 5;
+        let generation_rng = &mut thread_rng();
+        let mut batch_verifier = ZendooBatchVerifier::create();
+        // The same proof is added under different ids
+        batch_verifier.set_allow_duplicate_proofs(true);
+        batch_verifier.set_shuffle_batches(false);
+
+        let (params_g1, _, _, segment_size) = get_params();
+        let (pcds, vks) = generate_simple_marlin_test_data(
+            segment_size - 1,
+            segment_size,
+            &params_g1,
+            num_proofs,
+            generation_rng,
+        );
+        for (id, pcd) in pcds.iter().enumerate() {
+            batch_verifier
+                .add_zendoo_proof_verifier_data(
+                    id as u32,
+                    pcd.usr_ins.clone(),
+                    ZendooProof::CoboundaryMarlin(pcd.proof.clone()),
+                    ZendooVerifierKey::CoboundaryMarlin(vks[0].clone()),
+                )
+                .unwrap();
+        }
+        assert!(batch_verifier.batch_verify_all(generation_rng).unwrap());
+
+        // Offending ids are still correctly reported, with and without shuffling
+        batch_verifier
+            .add_zendoo_proof_verifier_data(
+                num_proofs as u32,
+                vec![rand_fe_with_rng(generation_rng); pcds[0].usr_ins.len()],
+                ZendooProof::CoboundaryMarlin(pcds[0].proof.clone()),
+                ZendooVerifierKey::CoboundaryMarlin(vks[0].clone()),
+            )
+            .unwrap();
+        for &shuffle in [false, true].iter() {
+            batch_verifier.set_shuffle_batches(shuffle);
+            match batch_verifier.batch_verify_all(generation_rng) {
+                Err(ProvingSystemError::FailedBatchVerification(Some(ids))) => {
+                    assert_eq!(ids, vec![num_proofs as u32])
+                }
+                Ok(false) | Err(ProvingSystemError::FailedBatchVerification(None)) => {}
+                res => panic!("Unexpected result: {:?}", res),
+            }
+        }
+    }
+
+    #[test]
+    #[serial]
+    fn batch_verifier_duplicate_proofs_test() {
+        let generation_rng = &mut thread_rng();
+        let mut batch_verifier = ZendooBatchVerifier::create();
+
+        let (params_g1, _, _, segment_size) = get_params();
+        let (pcds, vks) = generate_simple_marlin_test_data(
+            segment_size - 1,
+            segment_size,
+            &params_g1,
+            2,
+            generation_rng,
+        );
+        let proofs = pcds
+            .iter()
+            .map(|pcd| ZendooProof::CoboundaryMarlin(pcd.proof.clone()))
+            .collect::<Vec<_>>();
+        let vk = ZendooVerifierKey::CoboundaryMarlin(vks[0].clone());
+
+        // Same proof, same hash
+        assert_eq!(proofs[0].hash().unwrap(), proofs[0].clone().hash().unwrap());
+        assert_ne!(proofs[0].hash().unwrap(), proofs[1].hash().unwrap());
+
+        batch_verifier
+            .add_zendoo_proof_verifier_data(
+                0,
+                pcds[0].usr_ins.clone(),
+                proofs[0].clone(),
+                vk.clone(),
+            )
+            .unwrap();
+
+        // Re-adding the same proof under the same id is fine
+        batch_verifier
+            .add_zendoo_proof_verifier_data(
+                0,
+                pcds[0].usr_ins.clone(),
+                proofs[0].clone(),
+                vk.clone(),
+            )
+            .unwrap();
+
+        // The same proof can't be added under another id
+        assert!(matches!(
+            batch_verifier.add_zendoo_proof_verifier_data(
+                1,
+                pcds[0].usr_ins.clone(),
+                proofs[0].clone(),
+                vk.clone()
+            ),
+            Err(ProvingSystemError::ProofAlreadyExists(0))
+        ));
+        assert_eq!(batch_verifier.num_proofs(), 1);
+
+        // Replacing the proof with id 0 frees its old proof
+        batch_verifier
+            .add_zendoo_proof_verifier_data(
+                0,
+                pcds[1].usr_ins.clone(),
+                proofs[1].clone(),
+                vk.clone(),
+            )
+            .unwrap();
+        batch_verifier
+            .add_zendoo_proof_verifier_data(
+                1,
+                pcds[0].usr_ins.clone(),
+                proofs[0].clone(),
+                vk.clone(),
+            )
+            .unwrap();
+        assert_eq!(batch_verifier.proof_ids.len(), 2);
+        assert_eq!(batch_verifier.proof_hashes.len(), 2);
+
+        // Duplicates explicitly allowed
+        batch_verifier.set_allow_duplicate_proofs(true);
+        batch_verifier
+            .add_zendoo_proof_verifier_data(2, pcds[0].usr_ins.clone(), proofs[0].clone(), vk)
+            .unwrap();
+        assert_eq!(batch_verifier.num_proofs(), 3);
+        assert!(batch_verifier.batch_verify_all(generation_rng).unwrap());
+    }
+
+    #[test]
+    #[serial]
+    fn batch_verifier_cost_estimation_test() {
+        let generation_rng = &mut thread_rng();
+        let mut batch_verifier = ZendooBatchVerifier::create();
+
+        let (params_g1, params_g2, _, segment_size) = get_params();
+        let (simple_pcds, simple_vks) = generate_simple_marlin_test_data(
+            segment_size - 1,
+            segment_size,
+            &params_g1,
+            2,
+            generation_rng,
+        );
+        for (id, pcd) in simple_pcds.iter().enumerate() {
+            batch_verifier
+                .add_zendoo_proof_verifier_data(
+                    id as u32,
+                    pcd.usr_ins.clone(),
+                    ZendooProof::CoboundaryMarlin(pcd.proof.clone()),
+                    ZendooVerifierKey::CoboundaryMarlin(simple_vks[0].clone()),
+                )
+                .unwrap();
+        }
+        let (final_pcds, final_vks) = generate_final_darlin_test_data(
+            segment_size - 1,
+            segment_size,
+            &params_g1,
+            &params_g2,
+            1,
+            generation_rng,
+        );
+        batch_verifier
+            .add_zendoo_proof_verifier_data(
+                2,
+                final_pcds[0].usr_ins.clone(),
+                ZendooProof::Darlin(final_pcds[0].final_darlin_proof.clone()),
+                ZendooVerifierKey::Darlin(final_vks[0].clone()),
+            )
+            .unwrap();
+
+        // Only CoboundaryMarlin proofs: a single MSM with the G1 committer key
+        let cost = batch_verifier.estimate_verification_cost(&[0, 1]).unwrap();
+        assert_eq!(cost.num_proofs, 2);
+        assert_eq!(cost.num_inputs, 4);
+        assert!(cost.num_proof_group_elements > 0);
+        assert_eq!(cost.msm_size, COMMITTER_KEY_MAX_DEGREE_FOR_TESTING + 1);
+
+        // Cost is additive in the proofs and Darlin proofs require an additional MSM
+        assert_eq!(
+            batch_verifier
+                .estimate_verification_cost(&[0])
+                .unwrap()
+                .num_proof_group_elements
+                * 2,
+            cost.num_proof_group_elements
+        );
+        let full_cost = batch_verifier
+            .estimate_verification_cost(&[0, 1, 2])
+            .unwrap();
+        assert_eq!(full_cost.num_proofs, 3);
+        assert_eq!(
+            full_cost.msm_size,
+            2 * (COMMITTER_KEY_MAX_DEGREE_FOR_TESTING + 1)
+        );
+        assert!(full_cost.total() > cost.total());
+
+        assert!(matches!(
+            batch_verifier.estimate_verification_cost(&[]),
+            Err(ProvingSystemError::NoProofsToVerify)
+        ));
+        assert!(matches!(
+            batch_verifier.estimate_verification_cost(&[0, 3]),
+            Err(ProvingSystemError::ProofNotPresent(3))
+        ));
+    }
+
     // ************Tests with mocks for certificate and csw proofs batch verifier***************
 
     struct TestZendooBatchVerifier {
@@ -546,7 +2413,7 @@ mod test {
         dummy_batch_verifier_internal(true)
     }
 
-    fn dummy_batch_verifier_internal(generate_prev_wcert_hash :bool) {
+    fn dummy_batch_verifier_internal(generate_prev_wcert_hash: bool) {
         use std::convert::TryInto;
 
         let num_proofs = 100;
@@ -555,33 +2422,35 @@ mod test {
         let (params_g1, params_g2, _, segment_size) = get_params();
         let num_constraints = segment_size;
 
-        let prev_cert_hash = rand_fe();
+        let prev_cert_hash = rand_fe_with_rng(generation_rng);
         let bt_list = vec![BackwardTransfer::default()];
         let cert_usr_ins = CertificateProofUserInputs {
             constant: None,
-            sc_id: &rand_fe(),
+            sc_id: &rand_fe_with_rng(generation_rng),
             epoch_number: 0,
             quality: 0,
             bt_list: Some(&bt_list),
             custom_fields: None,
-            end_cumulative_sc_tx_commitment_tree_root: &rand_fe(),
+            end_cumulative_sc_tx_commitment_tree_root: &rand_fe_with_rng(generation_rng),
             btr_fee: 0,
             ft_min_amount: 0,
             sc_prev_wcert_hash: if generate_prev_wcert_hash {
                 Some(&prev_cert_hash)
             } else {
                 None
-            }
+            },
         };
 
         let csw_usr_ins = CSWProofUserInputs {
             amount: 0,
             constant: None,
-            sc_id: &rand_fe(),
-            nullifier: &rand_fe(),
-            pub_key_hash: &rand_vec(MC_PK_SIZE).try_into().unwrap(),
-            cert_data_hash: &rand_fe(),
-            end_cumulative_sc_tx_commitment_tree_root: &rand_fe(),
+            sc_id: &rand_fe_with_rng(generation_rng),
+            nullifier: &rand_fe_with_rng(generation_rng),
+            pub_key_hash: &rand_vec_with_rng(MC_PK_SIZE, generation_rng)
+                .try_into()
+                .unwrap(),
+            cert_data_hash: &rand_fe_with_rng(generation_rng),
+            end_cumulative_sc_tx_commitment_tree_root: &rand_fe_with_rng(generation_rng),
         };
 
         // Generate test CoboundaryMarlinProof and CoboundaryMarlinVk
@@ -0,0 +1,296 @@
+//! Pipeline for the verification of all the proofs included in a block.
+//! Deserialization (and semantic checks) of proofs and vks is performed by a pool of
+//! worker threads, while the already deserialized entries are batch verified, in chunks,
+//! by the calling thread, thus overlapping the two CPU-intensive phases.
+use crate::{
+    config::budgeted_num_threads,
+    proving_system::{
+        error::ProvingSystemError, verifier::batch_verifier::ZendooBatchVerifier, ZendooProof,
+        ZendooVerifierKey,
+    },
+    type_mapping::FieldElement,
+    utils::serialization::{
+        deserialize_from_buffer_bounded, MAX_ZENDOO_PROOF_SIZE, MAX_ZENDOO_VK_SIZE,
+    },
+};
+use rand::RngCore;
+use std::{
+    sync::{mpsc, Arc, Mutex},
+    thread,
+};
+
+/// Raw entry to be verified: (id, proof_bytes, vk_bytes, circuit inputs)
+pub type BlockProofEntry = (u32, Vec<u8>, Vec<u8>, Vec<FieldElement>);
+
+type DeserializedEntry = (u32, ZendooProof, ZendooVerifierKey, Vec<FieldElement>);
+
+/// Maximum number of entries per worker buffered by `BlockProofVerifier::verify()`.
+pub const ENTRIES_PER_WORKER: usize = 2;
+
+/// Verifier of the proofs of a block, deserializing them on a pool of worker threads.
+#[derive(Clone, Copy, Debug)]
+pub struct BlockProofVerifier {
+    num_workers: usize,
+    max_batch_size: Option<usize>,
+    semantic_checks: bool,
+    compressed: bool,
+}
+
+impl BlockProofVerifier {
+    /// Create a new BlockProofVerifier, deserializing the entries on `num_workers` threads
+    /// (at least 1, and at most the thread budget at verification time).
+    /// Deserialized entries are batch verified in chunks of at most `max_batch_size` proofs
+    /// while the deserialization of the other ones proceeds; if `max_batch_size` is None,
+    /// all the proofs will be verified in a single batch, once all of them have been deserialized.
+    /// `semantic_checks` and `compressed` are the flags used to deserialize proofs and vks,
+    /// which are rejected if bigger than `MAX_ZENDOO_PROOF_SIZE` and `MAX_ZENDOO_VK_SIZE`.
+    pub fn create(
+        num_workers: usize,
+        max_batch_size: Option<usize>,
+        semantic_checks: bool,
+        compressed: bool,
+    ) -> Self {
+        Self {
+            num_workers: num_workers.max(1),
+            max_batch_size: max_batch_size.map(|size| size.max(1)),
+            semantic_checks,
+            compressed,
+        }
+    }
+
+    fn deserialize_entry(
+        &self,
+        entry: BlockProofEntry,
+    ) -> Result<DeserializedEntry, ProvingSystemError> {
+        let (id, proof_bytes, vk_bytes, inputs) = entry;
+
+        let proof = deserialize_from_buffer_bounded::<ZendooProof>(
+            proof_bytes.as_slice(),
+            MAX_ZENDOO_PROOF_SIZE,
+            Some(self.semantic_checks),
+            Some(self.compressed),
+        )
+        .map_err(|e| {
+            ProvingSystemError::DeserializationFailed(id, format!("Invalid proof: {:?}", e))
+        })?;
+
+        let vk = deserialize_from_buffer_bounded::<ZendooVerifierKey>(
+            vk_bytes.as_slice(),
+            MAX_ZENDOO_VK_SIZE,
+            Some(self.semantic_checks),
+            Some(self.compressed),
+        )
+        .map_err(|e| {
+            ProvingSystemError::DeserializationFailed(id, format!("Invalid vk: {:?}", e))
+        })?;
+
+        Ok((id, proof, vk, inputs))
+    }
+
+    /// Deserialize and verify all the `entries`.
+    /// Returns Ok(true) only if all the proofs are valid. If the deserialization of an entry
+    /// fails, or if the batch verification of a chunk fails, the corresponding error is returned
+    /// immediately, and the remaining entries are discarded.
+    /// Entries are fetched from `entries` only as fast as the workers deserialize them, so that at most
+    /// `ENTRIES_PER_WORKER` of them per worker are buffered, waiting to be deserialized or verified.
+    pub fn verify<I, R>(&self, entries: I, rng: &mut R) -> Result<bool, ProvingSystemError>
+    where
+        I: IntoIterator<Item = BlockProofEntry>,
+        R: RngCore,
+    {
+        let num_workers = budgeted_num_threads(self.num_workers);
+        let capacity = num_workers * ENTRIES_PER_WORKER;
+        let (job_sender, job_receiver) = mpsc::sync_channel::<BlockProofEntry>(capacity);
+        let (result_sender, result_receiver) = mpsc::sync_channel(capacity);
+        let job_receiver = Arc::new(Mutex::new(job_receiver));
+
+        // Spawn deserialization workers
+        let workers = (0..num_workers)
+            .map(|_| {
+                let job_receiver = job_receiver.clone();
+                let result_sender = result_sender.clone();
+                let verifier = *self;
+                thread::spawn(move || loop {
+                    // Lock is released as soon as an entry has been fetched
+                    let job = match job_receiver.lock() {
+                        Ok(receiver) => receiver.recv(),
+                        Err(_) => break,
+                    };
+                    let entry = match job {
+                        Ok(entry) => entry,
+                        Err(_) => break, // No more entries
+                    };
+
+                    // The receiver is dropped if the verification has been interrupted
+                    if result_sender
+                        .send(verifier.deserialize_entry(entry))
+                        .is_err()
+                    {
+                        break;
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+        drop(result_sender);
+
+        let result = self.feed_and_verify(entries, job_sender, result_receiver, rng);
+
+        // Both the channels have been closed, so the workers stop as soon as they are done with
+        // their current entry, also if the verification has been interrupted
+        let mut join_result = Ok(());
+        for worker in workers.into_iter() {
+            if let Err(e) = worker.join() {
+                join_result = Err(ProvingSystemError::Other(format!("{:?}", e)));
+            }
+        }
+        let verified = result?;
+        join_result?;
+        Ok(verified)
+    }
+
+    // Sends the entries to the workers, without ever blocking on a full job channel, and batch verifies
+    // the deserialized ones as soon as they are available
+    fn feed_and_verify<I, R>(
+        &self,
+        entries: I,
+        job_sender: mpsc::SyncSender<BlockProofEntry>,
+        result_receiver: mpsc::Receiver<Result<DeserializedEntry, ProvingSystemError>>,
+        rng: &mut R,
+    ) -> Result<bool, ProvingSystemError>
+    where
+        I: IntoIterator<Item = BlockProofEntry>,
+        R: RngCore,
+    {
+        let mut entries = entries.into_iter();
+        let mut job_sender = Some(job_sender);
+        let mut pending = None;
+        let mut num_sent = 0;
+        let mut num_received = 0;
+        let mut batch_verifier = ZendooBatchVerifier::create();
+
+        loop {
+            // Send as many entries as the job channel can hold
+            if let Some(sender) = job_sender.as_ref() {
+                while let Some(entry) = pending.take().or_else(|| entries.next()) {
+                    match sender.try_send(entry) {
+                        Ok(()) => num_sent += 1,
+                        Err(mpsc::TrySendError::Full(entry)) => {
+                            pending = Some(entry);
+                            break;
+                        }
+                        Err(e) => return Err(ProvingSystemError::Other(format!("{:?}", e))),
+                    }
+                }
+                if pending.is_none() {
+                    // All the entries have been sent: closing the channel lets the workers stop
+                    job_sender = None;
+                }
+            }
+            let all_sent = job_sender.is_none();
+
+            if num_received == num_sent {
+                if !all_sent {
+                    continue;
+                }
+                if num_sent == 0 {
+                    return Err(ProvingSystemError::NoProofsToVerify);
+                }
+                return Ok(true);
+            }
+
+            // At least one entry is being deserialized, so a result is going to be available
+            let (id, proof, vk, inputs) = result_receiver
+                .recv()
+                .map_err(|e| ProvingSystemError::Other(format!("{:?}", e)))??;
+            num_received += 1;
+            batch_verifier.add_zendoo_proof_verifier_data(id, inputs, proof, vk)?;
+
+            let last = all_sent && num_received == num_sent;
+            if Some(batch_verifier.num_proofs()) == self.max_batch_size || last {
+                if !batch_verifier.batch_verify_all(rng)? {
+                    return Ok(false);
+                }
+                batch_verifier = ZendooBatchVerifier::create();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        proving_system::init::{
+            load_g1_committer_key, COMMITTER_KEY_MAX_DEGREE_FOR_TESTING, G1_UNIVERSAL_PARAMS,
+        },
+        utils::{commitment_tree::rand_fe_with_rng, serialization::serialize_to_buffer},
+    };
+    use proof_systems::darlin::tests::simple_marlin::generate_test_data;
+    use rand::thread_rng;
+    use serial_test::serial;
+
+    fn generate_entries(num_proofs: u32) -> Vec<BlockProofEntry> {
+        let rng = &mut thread_rng();
+        let segment_size = 1 << 7;
+
+        let _ = load_g1_committer_key(COMMITTER_KEY_MAX_DEGREE_FOR_TESTING);
+        let params_g1 = G1_UNIVERSAL_PARAMS
+            .read()
+            .unwrap()
+            .as_ref()
+            .unwrap()
+            .clone();
+
+        (0..num_proofs)
+            .map(|id| {
+                let (pcds, vks) =
+                    generate_test_data(segment_size - 1, segment_size, &params_g1, 1, rng);
+                let proof = ZendooProof::CoboundaryMarlin(pcds[0].proof.clone());
+                let vk = ZendooVerifierKey::CoboundaryMarlin(vks[0].clone());
+                (
+                    id,
+                    serialize_to_buffer(&proof, Some(true)).unwrap(),
+                    serialize_to_buffer(&vk, Some(true)).unwrap(),
+                    pcds[0].usr_ins.clone(),
+                )
+            })
+            .collect()
+    }
+
+    #[serial]
+    #[test]
+    fn block_proof_verifier_test() {
+        let rng = &mut thread_rng();
+        let entries = generate_entries(10);
+
+        // Single batch and chunked verification must succeed
+        for max_batch_size in [None, Some(1), Some(3)].iter().copied() {
+            let verifier = BlockProofVerifier::create(4, max_batch_size, true, true);
+            assert!(verifier.verify(entries.clone(), rng).unwrap());
+        }
+
+        let verifier = BlockProofVerifier::create(2, Some(3), true, true);
+
+        // No entries
+        assert!(matches!(
+            verifier.verify(vec![], rng),
+            Err(ProvingSystemError::NoProofsToVerify)
+        ));
+
+        // Corrupted proof bytes
+        let mut corrupted = entries.clone();
+        corrupted[5].1.truncate(10);
+        assert!(matches!(
+            verifier.verify(corrupted, rng),
+            Err(ProvingSystemError::DeserializationFailed(5, _))
+        ));
+
+        // Wrong inputs
+        let mut wrong_inputs = entries;
+        wrong_inputs[7].3[0] = rand_fe_with_rng(rng);
+        match verifier.verify(wrong_inputs, rng) {
+            Ok(false) | Err(ProvingSystemError::FailedBatchVerification(_)) => {}
+            _ => panic!("Verification with wrong inputs should have failed"),
+        }
+    }
+}
@@ -1,6 +1,9 @@
-use crate::proving_system::{error::ProvingSystemError, verifier::UserInputs};
+use crate::proving_system::{
+    error::ProvingSystemError,
+    verifier::{compress_inputs_to_single_fe, UserInputs},
+};
 use crate::type_mapping::{BigInteger256, FieldElement, MC_PK_SIZE};
-use crate::utils::commitment_tree::{hash_vec, DataAccumulator};
+use crate::utils::packing::SingleFieldElementPacker;
 use algebra::field_new;
 
 pub const PHANTOM_CERT_DATA_HASH: FieldElement = field_new!(
@@ -32,22 +35,24 @@ impl<'a> UserInputs for CSWProofUserInputs<'a> {
             inputs.push(*self.constant.unwrap());
         }
 
-        let mut fes = DataAccumulator::init()
-            .update(self.amount)
+        // Width checked at compile time through CSW_AMOUNT_PK_HASH_BITS
+        let amount_pk_hash = SingleFieldElementPacker::init()
+            .pack(self.amount)
             .map_err(|e| ProvingSystemError::Other(format!("{:?}", e)))?
-            .update(&self.pub_key_hash[..])
+            .pack_array(self.pub_key_hash)
             .map_err(|e| ProvingSystemError::Other(format!("{:?}", e)))?
-            .get_field_elements()
+            .get_field_element()
             .map_err(|e| ProvingSystemError::Other(format!("{:?}", e)))?;
 
-        fes.append(&mut vec![
+        let fes = vec![
+            amount_pk_hash,
             *self.sc_id,
             *self.nullifier,
             *self.cert_data_hash,
             *self.end_cumulative_sc_tx_commitment_tree_root,
-        ]);
+        ];
 
-        inputs.push(hash_vec(fes).map_err(|e| ProvingSystemError::Other(format!("{:?}", e)))?);
+        inputs.push(compress_inputs_to_single_fe(fes)?);
 
         Ok(inputs)
     }
@@ -59,7 +64,7 @@ impl<'a> UserInputs for CSWProofUserInputs<'a> {
 fn test_phantom_cert_data_hash() {
     assert_eq!(
         PHANTOM_CERT_DATA_HASH,
-        DataAccumulator::init()
+        crate::utils::commitment_tree::DataAccumulator::init()
             .update(&b"BASOOKA"[..])
             .unwrap()
             .get_field_elements()
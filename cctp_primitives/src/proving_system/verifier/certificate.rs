@@ -1,8 +1,8 @@
-use crate::utils::data_structures::BackwardTransfer;
-use crate::utils::get_cert_data_hash;
+use crate::utils::data_structures::{BackwardTransfer, CertSysData};
+use crate::utils::get_cert_data_hash_from_sys_data;
 use crate::{
     proving_system::{error::ProvingSystemError, verifier::UserInputs},
-    type_mapping::FieldElement,
+    type_mapping::{Error, FieldElement},
 };
 
 /// All the data needed to reconstruct the aggregated input for the NaiveThresholdSignatureCircuit
@@ -21,6 +21,18 @@ pub struct CertificateProofUserInputs<'a> {
     pub sc_prev_wcert_hash: Option<&'a FieldElement>,
 }
 
+impl CertificateProofUserInputs<'_> {
+    /// Epoch number, quality and fees of the certificate, packed as in the circuit.
+    pub fn sys_data(&self) -> CertSysData {
+        CertSysData::new(
+            self.epoch_number,
+            self.quality,
+            self.btr_fee,
+            self.ft_min_amount,
+        )
+    }
+}
+
 impl UserInputs for CertificateProofUserInputs<'_> {
     fn get_circuit_inputs(&self) -> Result<Vec<FieldElement>, ProvingSystemError> {
         let mut inputs = Vec::new();
@@ -29,15 +41,12 @@ impl UserInputs for CertificateProofUserInputs<'_> {
             inputs.push(*self.constant.unwrap());
         }
 
-        let cert_data_hash = get_cert_data_hash(
+        let cert_data_hash = get_cert_data_hash_from_sys_data(
             self.sc_id,
-            self.epoch_number,
-            self.quality,
+            &self.sys_data(),
             self.bt_list,
             self.custom_fields.clone(),
             self.end_cumulative_sc_tx_commitment_tree_root,
-            self.btr_fee,
-            self.ft_min_amount,
         )
         .map_err(|e| ProvingSystemError::Other(format!("{:?}", e)))?;
         inputs.push(cert_data_hash);
@@ -49,3 +58,166 @@ impl UserInputs for CertificateProofUserInputs<'_> {
         Ok(inputs)
     }
 }
+
+/// A certificate as seen by the user, owning all of its data, from which the values derived from it
+/// (its data hash, its leaf in the CERT subtree of the CommitmentTree and the inputs of its circuit)
+/// are computed in one call each, out of the same normalized data.
+/// The backward transfers are normalized into a list, being an absent list equivalent to an empty one;
+/// the custom fields instead are kept optional, since absent custom fields and an empty list of them
+/// yield different hashes.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Certificate {
+    pub constant: Option<FieldElement>,
+    pub sc_id: FieldElement,
+    pub epoch_number: u32,
+    pub quality: u64,
+    pub bt_list: Vec<BackwardTransfer>,
+    pub custom_fields: Option<Vec<FieldElement>>,
+    pub end_cumulative_sc_tx_commitment_tree_root: FieldElement,
+    pub btr_fee: u64,
+    pub ft_min_amount: u64,
+    pub sc_prev_wcert_hash: Option<FieldElement>,
+}
+
+impl Certificate {
+    /// Epoch number, quality and fees of the certificate, packed as in the circuit.
+    pub fn sys_data(&self) -> CertSysData {
+        CertSysData::new(
+            self.epoch_number,
+            self.quality,
+            self.btr_fee,
+            self.ft_min_amount,
+        )
+    }
+
+    /// Compute the data hash of the certificate, i.e. the one `get_cert_data_hash` returns.
+    pub fn data_hash(&self) -> Result<FieldElement, Error> {
+        let user_inputs = self.as_user_inputs();
+        get_cert_data_hash_from_sys_data(
+            user_inputs.sc_id,
+            &user_inputs.sys_data(),
+            user_inputs.bt_list,
+            user_inputs.custom_fields,
+            user_inputs.end_cumulative_sc_tx_commitment_tree_root,
+        )
+    }
+
+    /// Compute the leaf of the certificate in the CERT subtree of the CommitmentTree, i.e. the one
+    /// `hash_cert` returns and `CommitmentTree::add_cert` adds: it's the data hash of the certificate.
+    pub fn leaf_hash(&self) -> Result<FieldElement, Error> {
+        self.data_hash()
+    }
+
+    /// Compute the inputs of the certificate circuit, i.e. the ones of `as_user_inputs()`.
+    pub fn circuit_inputs(&self) -> Result<Vec<FieldElement>, ProvingSystemError> {
+        self.as_user_inputs().get_circuit_inputs()
+    }
+
+    /// Borrow the data of the certificate as the user inputs of its circuit, e.g. to verify its proof.
+    pub fn as_user_inputs(&self) -> CertificateProofUserInputs<'_> {
+        CertificateProofUserInputs {
+            constant: self.constant.as_ref(),
+            sc_id: &self.sc_id,
+            epoch_number: self.epoch_number,
+            quality: self.quality,
+            bt_list: Some(self.bt_list.as_slice()),
+            custom_fields: self
+                .custom_fields
+                .as_ref()
+                .map(|custom_fields| custom_fields.iter().collect()),
+            end_cumulative_sc_tx_commitment_tree_root: &self
+                .end_cumulative_sc_tx_commitment_tree_root,
+            btr_fee: self.btr_fee,
+            ft_min_amount: self.ft_min_amount,
+            sc_prev_wcert_hash: self.sc_prev_wcert_hash.as_ref(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        commitment_tree::{hashers::hash_cert, CommitmentTree},
+        utils::commitment_tree::{rand_fe_vec_with_rng, rand_fe_with_rng},
+    };
+    use rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    fn certificate_test() {
+        let rng = &mut StdRng::seed_from_u64(42);
+        let mut cert = Certificate {
+            constant: Some(rand_fe_with_rng(rng)),
+            sc_id: rand_fe_with_rng(rng),
+            epoch_number: 10,
+            quality: 20,
+            bt_list: vec![BackwardTransfer::default(); 3],
+            custom_fields: Some(rand_fe_vec_with_rng(4, rng)),
+            end_cumulative_sc_tx_commitment_tree_root: rand_fe_with_rng(rng),
+            btr_fee: 30,
+            ft_min_amount: 40,
+            sc_prev_wcert_hash: Some(rand_fe_with_rng(rng)),
+        };
+
+        // Same values as the ones computed out of the separate data
+        let leaf = hash_cert(
+            &cert.sc_id,
+            cert.epoch_number,
+            cert.quality,
+            Some(cert.bt_list.as_slice()),
+            cert.custom_fields.as_ref().map(|cfs| cfs.iter().collect()),
+            &cert.end_cumulative_sc_tx_commitment_tree_root,
+            cert.btr_fee,
+            cert.ft_min_amount,
+        )
+        .unwrap();
+        assert_eq!(cert.leaf_hash().unwrap(), leaf);
+        assert_eq!(cert.data_hash().unwrap(), leaf);
+        assert_eq!(
+            cert.circuit_inputs().unwrap(),
+            vec![
+                cert.constant.unwrap(),
+                leaf,
+                cert.sc_prev_wcert_hash.unwrap()
+            ]
+        );
+
+        let mut cmt = CommitmentTree::create();
+        assert!(cmt.add_cert(
+            &cert.sc_id,
+            cert.epoch_number,
+            cert.quality,
+            Some(cert.bt_list.as_slice()),
+            cert.custom_fields.as_ref().map(|cfs| cfs.iter().collect()),
+            &cert.end_cumulative_sc_tx_commitment_tree_root,
+            cert.btr_fee,
+            cert.ft_min_amount,
+        ));
+        let mut expected_cmt = CommitmentTree::create();
+        assert!(expected_cmt.add_cert_leaf(&cert.sc_id, &cert.leaf_hash().unwrap()));
+        assert_eq!(cmt.get_commitment(), expected_cmt.get_commitment());
+
+        // An empty list of backward transfers is the same as an absent one
+        cert.bt_list.clear();
+        let leaf = hash_cert(
+            &cert.sc_id,
+            cert.epoch_number,
+            cert.quality,
+            None,
+            cert.custom_fields.as_ref().map(|cfs| cfs.iter().collect()),
+            &cert.end_cumulative_sc_tx_commitment_tree_root,
+            cert.btr_fee,
+            cert.ft_min_amount,
+        )
+        .unwrap();
+        assert_eq!(cert.leaf_hash().unwrap(), leaf);
+
+        // Absent custom fields and optional inputs
+        cert.constant = None;
+        cert.custom_fields = None;
+        cert.sc_prev_wcert_hash = None;
+        let data_hash = cert.data_hash().unwrap();
+        assert_ne!(data_hash, leaf);
+        assert_eq!(cert.circuit_inputs().unwrap(), vec![data_hash]);
+    }
+}
@@ -1,21 +1,57 @@
-use crate::proving_system::init::{get_g1_committer_key, get_g2_committer_key};
+use crate::proving_system::init::{
+    get_full_g1_committer_key, get_full_g2_committer_key, get_g1_committer_key,
+    get_g2_committer_key,
+};
 use crate::{
     proving_system::error::ProvingSystemError,
-    proving_system::{check_matching_proving_system_type, ZendooProof, ZendooVerifierKey},
+    proving_system::{
+        check_matching_proving_system_type, ProvingSystem, ZendooProof, ZendooVerifierKey,
+    },
     type_mapping::*,
+    utils::commitment_tree::hash_vec,
 };
 use rand::RngCore;
+use std::sync::Arc;
 
 pub mod batch_verifier;
+pub mod block_proof_verifier;
 pub mod ceased_sidechain_withdrawal;
 pub mod certificate;
+pub mod sc_proof_set;
+pub mod user_inputs;
+pub mod verification_queue;
 
-/// Wrapper for the user inputs of a circuit, assumed to be a vector of Field Elements
+/// Wrapper for the user inputs of a circuit, assumed to be a vector of Field Elements.
+/// Custom circuits should implement it through `impl_user_inputs!` (or `UserInputsBuilder`),
+/// to pack their inputs following the same conventions of the circuits supported by this crate.
 pub trait UserInputs {
     /// Handle all the boiler plate to get the user inputs of a given circuit.
     fn get_circuit_inputs(&self) -> Result<Vec<FieldElement>, ProvingSystemError>;
 }
 
+/// Circuit inputs already available as field elements
+impl UserInputs for Vec<FieldElement> {
+    fn get_circuit_inputs(&self) -> Result<Vec<FieldElement>, ProvingSystemError> {
+        Ok(self.clone())
+    }
+}
+
+/// Circuit inputs already available as field elements
+impl UserInputs for &[FieldElement] {
+    fn get_circuit_inputs(&self) -> Result<Vec<FieldElement>, ProvingSystemError> {
+        Ok(self.to_vec())
+    }
+}
+
+/// Fold `inputs` into a single field element, as done by the circuits (e.g. CSW and
+/// certificate ones) exposing a single hashed public input. Any new circuit adopting the
+/// same design should build its `UserInputs` through this function.
+pub fn compress_inputs_to_single_fe(
+    inputs: Vec<FieldElement>,
+) -> Result<FieldElement, ProvingSystemError> {
+    hash_vec(inputs).map_err(|e| ProvingSystemError::Other(format!("{:?}", e)))
+}
+
 /// Verify the content of `self`
 pub fn verify_zendoo_proof<I: UserInputs, R: RngCore>(
     inputs: I,
@@ -24,29 +60,113 @@ pub fn verify_zendoo_proof<I: UserInputs, R: RngCore>(
     rng: Option<&mut R>,
 ) -> Result<bool, ProvingSystemError> {
     let usr_ins = inputs.get_circuit_inputs()?;
+    verify_zendoo_proof_with_circuit_inputs(usr_ins.as_slice(), proof, vk, rng)
+}
 
+/// Same as `verify_zendoo_proof`, with the circuit inputs already available as field elements
+pub(crate) fn verify_zendoo_proof_with_circuit_inputs<R: RngCore>(
+    usr_ins: &[FieldElement],
+    proof: &ZendooProof,
+    vk: &ZendooVerifierKey,
+    rng: Option<&mut R>,
+) -> Result<bool, ProvingSystemError> {
     if !check_matching_proving_system_type(proof, vk) {
         return Err(ProvingSystemError::ProvingSystemMismatch);
     }
 
     let ck_g1 = get_g1_committer_key(None)?;
+    let ck_g2 = match vk {
+        ZendooVerifierKey::Darlin(_) => Some(get_g2_committer_key(None)?),
+        ZendooVerifierKey::CoboundaryMarlin(_) => None,
+    };
+
+    verify_zendoo_proof_with_committer_keys(usr_ins, proof, vk, &ck_g1, ck_g2.as_ref(), rng)
+}
+
+/// A `ZendooVerifierKey` prepared once for verifying any number of proofs against it:
+/// the committer keys needed to verify its proofs are retrieved once, at preparation time,
+/// instead of being trimmed (and copied) out of the universal parameters at each verification.
+/// Must be prepared again if the committer keys are reloaded.
+#[derive(Clone)]
+pub struct PreparedZendooVk {
+    vk: ZendooVerifierKey,
+    ck_g1: Arc<CommitterKeyG1>,
+    ck_g2: Option<Arc<CommitterKeyG2>>,
+}
+
+impl PreparedZendooVk {
+    /// Prepare `vk` for verification. Return an error if the committer keys required to
+    /// verify its proofs (the G2 one only for Darlin) haven't been initialized.
+    pub fn prepare(vk: ZendooVerifierKey) -> Result<Self, ProvingSystemError> {
+        let ck_g1 = get_full_g1_committer_key()?;
+        let ck_g2 = match vk {
+            ZendooVerifierKey::Darlin(_) => Some(get_full_g2_committer_key()?),
+            ZendooVerifierKey::CoboundaryMarlin(_) => None,
+        };
+
+        Ok(Self { vk, ck_g1, ck_g2 })
+    }
+
+    /// Return the prepared `ZendooVerifierKey`
+    pub fn vk(&self) -> &ZendooVerifierKey {
+        &self.vk
+    }
 
+    /// Return the proving system of the prepared `ZendooVerifierKey`
+    pub fn get_proving_system_type(&self) -> ProvingSystem {
+        self.vk.get_proving_system_type()
+    }
+}
+
+/// Same as `verify_zendoo_proof`, with a `PreparedZendooVk` instead of a `ZendooVerifierKey`,
+/// amortizing the preparation of the vk across all the proofs verified against it.
+pub fn verify_zendoo_proof_prepared<I: UserInputs, R: RngCore>(
+    inputs: I,
+    proof: &ZendooProof,
+    prepared_vk: &PreparedZendooVk,
+    rng: Option<&mut R>,
+) -> Result<bool, ProvingSystemError> {
+    if !check_matching_proving_system_type(proof, &prepared_vk.vk) {
+        return Err(ProvingSystemError::ProvingSystemMismatch);
+    }
+
+    let usr_ins = inputs.get_circuit_inputs()?;
+    verify_zendoo_proof_with_committer_keys(
+        usr_ins.as_slice(),
+        proof,
+        &prepared_vk.vk,
+        &prepared_vk.ck_g1,
+        prepared_vk.ck_g2.as_deref(),
+        rng,
+    )
+}
+
+/// Verify `proof` against `vk` using the given committer keys. `ck_g2` is required only
+/// for Darlin proofs. `proof` and `vk` are assumed to belong to the same proving system.
+pub(crate) fn verify_zendoo_proof_with_committer_keys<R: RngCore>(
+    usr_ins: &[FieldElement],
+    proof: &ZendooProof,
+    vk: &ZendooVerifierKey,
+    ck_g1: &CommitterKeyG1,
+    ck_g2: Option<&CommitterKeyG2>,
+    rng: Option<&mut R>,
+) -> Result<bool, ProvingSystemError> {
     // Verify proof (selecting the proper proving system)
     let res = match (proof, vk) {
         // Verify CoboundaryMarlinProof
         (ZendooProof::CoboundaryMarlin(proof), ZendooVerifierKey::CoboundaryMarlin(vk)) => {
-            CoboundaryMarlin::verify(vk, &ck_g1, usr_ins.as_slice(), &proof.0)
+            CoboundaryMarlin::verify(vk, ck_g1, usr_ins, &proof.0)
                 .map_err(|e| ProvingSystemError::ProofVerificationFailed(format!("{:?}", e)))?
         }
 
         // Verify DarlinProof
         (ZendooProof::Darlin(proof), ZendooVerifierKey::Darlin(vk)) => {
-            let ck_g2 = get_g2_committer_key(None)?;
+            let ck_g2 = ck_g2.ok_or(ProvingSystemError::CommitterKeyNotInitialized)?;
             Darlin::verify(
                 vk,
-                &ck_g1,
-                &ck_g2,
-                usr_ins.as_slice(),
+                ck_g1,
+                ck_g2,
+                usr_ins,
                 proof,
                 match rng {
                     Some(v) => v,
@@ -55,8 +175,31 @@ pub fn verify_zendoo_proof<I: UserInputs, R: RngCore>(
             )
             .map_err(|e| ProvingSystemError::ProofVerificationFailed(format!("{:?}", e)))?
         }
-        _ => unreachable!(),
+        _ => return Err(ProvingSystemError::ProvingSystemMismatch),
     };
 
     Ok(res)
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::utils::commitment_tree::rand_fe_vec_with_rng;
+
+    #[test]
+    fn test_compress_inputs_to_single_fe() {
+        let rng = &mut rand::thread_rng();
+        let inputs = rand_fe_vec_with_rng(5, rng);
+
+        let compressed = compress_inputs_to_single_fe(inputs.clone()).unwrap();
+        assert_eq!(compressed, hash_vec(inputs.clone()).unwrap());
+
+        // Order of the inputs matters
+        let mut reversed_inputs = inputs;
+        reversed_inputs.reverse();
+        assert_ne!(
+            compress_inputs_to_single_fe(reversed_inputs).unwrap(),
+            compressed
+        );
+    }
+}
@@ -0,0 +1,272 @@
+use crate::{
+    proving_system::{
+        check_matching_proving_system_type,
+        error::ProvingSystemError,
+        verifier::{
+            batch_verifier::ZendooBatchVerifier, verify_zendoo_proof_with_committer_keys,
+            UserInputs,
+        },
+        ZendooProof, ZendooVerifierKey,
+    },
+    type_mapping::*,
+};
+use rand::RngCore;
+
+/// A proof to be verified as part of a `ScProofSet`, along with its vk and circuit inputs.
+#[derive(Clone)]
+pub struct ScProofSetItem {
+    pub proof: ZendooProof,
+    pub vk: ZendooVerifierKey,
+    pub inputs: Vec<FieldElement>,
+}
+
+impl ScProofSetItem {
+    /// Build an item out of `proof`, `vk` and `inputs`, checking that `proof` and `vk`
+    /// belong to the same proving system.
+    pub fn new<I: UserInputs>(
+        inputs: I,
+        proof: ZendooProof,
+        vk: ZendooVerifierKey,
+    ) -> Result<Self, ProvingSystemError> {
+        if !check_matching_proving_system_type(&proof, &vk) {
+            return Err(ProvingSystemError::ProvingSystemMismatch);
+        }
+
+        Ok(Self {
+            proof,
+            vk,
+            inputs: inputs.get_circuit_inputs()?,
+        })
+    }
+}
+
+/// All the certificate and CSW proofs submitted by a sidechain (e.g. in an epoch),
+/// verified together by `verify_all()`.
+#[derive(Clone)]
+pub struct ScProofSet {
+    pub sc_id: FieldElement,
+    pub certs: Vec<ScProofSetItem>,
+    pub csws: Vec<ScProofSetItem>,
+}
+
+/// Outcome of the verification of a `ScProofSet`: for each certificate and CSW proof,
+/// in the same order as in the set, either success or the reason for which it fails.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ScProofSetResults {
+    pub sc_id: FieldElement,
+    pub certs: Vec<Result<(), String>>,
+    pub csws: Vec<Result<(), String>>,
+}
+
+impl ScProofSetResults {
+    /// Return true if all the proofs of the set have been successfully verified
+    pub fn all_verified(&self) -> bool {
+        self.certs.iter().chain(self.csws.iter()).all(Result::is_ok)
+    }
+
+    /// Return the number of proofs of the set which failed the verification
+    pub fn num_failed(&self) -> usize {
+        self.certs
+            .iter()
+            .chain(self.csws.iter())
+            .filter(|res| res.is_err())
+            .count()
+    }
+}
+
+impl ScProofSet {
+    /// Create an empty set for the sidechain with id `sc_id`
+    pub fn new(sc_id: FieldElement) -> Self {
+        Self {
+            sc_id,
+            certs: Vec::new(),
+            csws: Vec::new(),
+        }
+    }
+
+    /// Add a certificate proof to the set
+    pub fn add_cert<I: UserInputs>(
+        &mut self,
+        inputs: I,
+        proof: ZendooProof,
+        vk: ZendooVerifierKey,
+    ) -> Result<(), ProvingSystemError> {
+        self.certs.push(ScProofSetItem::new(inputs, proof, vk)?);
+        Ok(())
+    }
+
+    /// Add a CSW proof to the set
+    pub fn add_csw<I: UserInputs>(
+        &mut self,
+        inputs: I,
+        proof: ZendooProof,
+        vk: ZendooVerifierKey,
+    ) -> Result<(), ProvingSystemError> {
+        self.csws.push(ScProofSetItem::new(inputs, proof, vk)?);
+        Ok(())
+    }
+
+    /// Return the number of proofs in the set
+    pub fn num_proofs(&self) -> usize {
+        self.certs.len() + self.csws.len()
+    }
+
+    /// Verify all the proofs of the set, reporting the outcome for each one of them.
+    /// All the proofs are batch verified at first: if the batch verification fails, the
+    /// offending proofs (or all of them, if they can't be determined) are verified one by one,
+    /// collecting the reason for which each of them fails.
+    pub fn verify_all<R: RngCore>(
+        &self,
+        g1_ck: &CommitterKeyG1,
+        g2_ck: &CommitterKeyG2,
+        rng: &mut R,
+    ) -> ScProofSetResults {
+        let items = self
+            .certs
+            .iter()
+            .chain(self.csws.iter())
+            .collect::<Vec<_>>();
+        let mut results = vec![Ok(()); items.len()];
+
+        if !items.is_empty() {
+            let to_verify = items
+                .iter()
+                .map(|item| (&item.proof, &item.vk, item.inputs.as_slice()))
+                .collect::<Vec<_>>();
+
+            let candidates =
+                match ZendooBatchVerifier::batch_verify_proofs(&to_verify, g1_ck, g2_ck, rng) {
+                    Ok(true) => vec![],
+                    Err(Some(indices)) => indices,
+                    Ok(false) | Err(None) => (0..items.len()).collect(),
+                };
+
+            for idx in candidates {
+                let item = items[idx];
+                results[idx] = match verify_zendoo_proof_with_committer_keys(
+                    item.inputs.as_slice(),
+                    &item.proof,
+                    &item.vk,
+                    g1_ck,
+                    Some(g2_ck),
+                    Some(&mut *rng),
+                ) {
+                    Ok(true) => Ok(()),
+                    Ok(false) => Err("Proof is not valid".to_owned()),
+                    Err(e) => Err(e.to_string()),
+                };
+            }
+        }
+
+        let csws = results.split_off(self.certs.len());
+        ScProofSetResults {
+            sc_id: self.sc_id,
+            certs: results,
+            csws,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        proving_system::init::{
+            get_full_g1_committer_key, get_full_g2_committer_key, load_g1_committer_key,
+            load_g2_committer_key, COMMITTER_KEY_MAX_DEGREE_FOR_TESTING, G1_UNIVERSAL_PARAMS,
+            G2_UNIVERSAL_PARAMS,
+        },
+        utils::commitment_tree::{rand_fe_vec_with_rng, rand_fe_with_rng},
+    };
+    use proof_systems::darlin::tests::{
+        final_darlin::generate_test_data as generate_final_darlin_test_data,
+        simple_marlin::generate_test_data as generate_simple_marlin_test_data,
+    };
+    use rand::thread_rng;
+    use serial_test::serial;
+
+    #[test]
+    #[serial]
+    fn sc_proof_set_test() {
+        let segment_size = 1 << 7;
+        let rng = &mut thread_rng();
+
+        let _result_g1 = load_g1_committer_key(COMMITTER_KEY_MAX_DEGREE_FOR_TESTING);
+        let _result_g2 = load_g2_committer_key(COMMITTER_KEY_MAX_DEGREE_FOR_TESTING);
+        let params_g1 = G1_UNIVERSAL_PARAMS.read().unwrap().clone().unwrap();
+        let params_g2 = G2_UNIVERSAL_PARAMS.read().unwrap().clone().unwrap();
+        let g1_ck = get_full_g1_committer_key().unwrap();
+        let g2_ck = get_full_g2_committer_key().unwrap();
+
+        let (cert_pcds, cert_vks) =
+            generate_simple_marlin_test_data(segment_size - 1, segment_size, &params_g1, 2, rng);
+        let (csw_pcds, csw_vks) = generate_final_darlin_test_data(
+            segment_size - 1,
+            segment_size,
+            &params_g1,
+            &params_g2,
+            2,
+            rng,
+        );
+        let cert_vk = ZendooVerifierKey::CoboundaryMarlin(cert_vks[0].clone());
+        let csw_vk = ZendooVerifierKey::Darlin(csw_vks[0].clone());
+
+        let sc_id = rand_fe_with_rng(rng);
+        let mut sc_proof_set = ScProofSet::new(sc_id);
+
+        // Empty set
+        let results = sc_proof_set.verify_all(&g1_ck, &g2_ck, rng);
+        assert!(results.all_verified());
+        assert!(results.certs.is_empty() && results.csws.is_empty());
+
+        for pcd in cert_pcds.iter() {
+            sc_proof_set
+                .add_cert(
+                    pcd.usr_ins.clone(),
+                    ZendooProof::CoboundaryMarlin(pcd.proof.clone()),
+                    cert_vk.clone(),
+                )
+                .unwrap();
+        }
+        for pcd in csw_pcds.iter() {
+            sc_proof_set
+                .add_csw(
+                    pcd.usr_ins.clone(),
+                    ZendooProof::Darlin(pcd.final_darlin_proof.clone()),
+                    csw_vk.clone(),
+                )
+                .unwrap();
+        }
+        assert_eq!(sc_proof_set.num_proofs(), 4);
+
+        // Mismatching proving systems are rejected when adding
+        assert!(matches!(
+            sc_proof_set.add_cert(
+                cert_pcds[0].usr_ins.clone(),
+                ZendooProof::CoboundaryMarlin(cert_pcds[0].proof.clone()),
+                csw_vk.clone(),
+            ),
+            Err(ProvingSystemError::ProvingSystemMismatch)
+        ));
+        assert_eq!(sc_proof_set.num_proofs(), 4);
+
+        // All proofs verified
+        let results = sc_proof_set.verify_all(&g1_ck, &g2_ck, rng);
+        assert_eq!(results.sc_id, sc_id);
+        assert_eq!(results.certs, vec![Ok(()); 2]);
+        assert_eq!(results.csws, vec![Ok(()); 2]);
+        assert!(results.all_verified());
+        assert_eq!(results.num_failed(), 0);
+
+        // Failures are reported per item
+        sc_proof_set.certs[1].inputs = rand_fe_vec_with_rng(2, rng);
+        sc_proof_set.csws[0].inputs = rand_fe_vec_with_rng(2, rng);
+        let results = sc_proof_set.verify_all(&g1_ck, &g2_ck, rng);
+        assert!(!results.all_verified());
+        assert_eq!(results.num_failed(), 2);
+        assert!(results.certs[0].is_ok());
+        assert!(results.certs[1].is_err());
+        assert!(results.csws[0].is_err());
+        assert!(results.csws[1].is_ok());
+    }
+}
@@ -0,0 +1,300 @@
+use crate::{
+    proving_system::{error::ProvingSystemError, verifier::compress_inputs_to_single_fe},
+    type_mapping::{Error, FieldElement},
+    utils::commitment_tree::DataAccumulator,
+};
+
+/// Data which is not a field element (amounts, flags, hashes and addresses as bytes, ...)
+/// and which is packed into field elements by a `DataAccumulator` when used as circuit input.
+pub trait Packable {
+    /// Update `acc` with `self`
+    fn pack_into(&self, acc: &mut DataAccumulator) -> Result<(), Error>;
+}
+
+macro_rules! impl_packable_for_scalar {
+    ($($t:ty),*) => {
+        $(
+            impl Packable for $t {
+                fn pack_into(&self, acc: &mut DataAccumulator) -> Result<(), Error> {
+                    acc.update(*self)?;
+                    Ok(())
+                }
+            }
+        )*
+    };
+}
+
+impl_packable_for_scalar!(bool, u8, u32, u64);
+
+impl Packable for [u8] {
+    fn pack_into(&self, acc: &mut DataAccumulator) -> Result<(), Error> {
+        acc.update(self)?;
+        Ok(())
+    }
+}
+
+impl<const N: usize> Packable for [u8; N] {
+    fn pack_into(&self, acc: &mut DataAccumulator) -> Result<(), Error> {
+        self[..].pack_into(acc)
+    }
+}
+
+impl Packable for Vec<u8> {
+    fn pack_into(&self, acc: &mut DataAccumulator) -> Result<(), Error> {
+        self[..].pack_into(acc)
+    }
+}
+
+impl<T: Packable + ?Sized> Packable for &T {
+    fn pack_into(&self, acc: &mut DataAccumulator) -> Result<(), Error> {
+        (**self).pack_into(acc)
+    }
+}
+
+/// Field element used as circuit input, either owned or borrowed.
+pub trait AsFieldElement {
+    fn as_field_element(&self) -> &FieldElement;
+}
+
+impl AsFieldElement for FieldElement {
+    fn as_field_element(&self) -> &FieldElement {
+        self
+    }
+}
+
+impl AsFieldElement for &FieldElement {
+    fn as_field_element(&self) -> &FieldElement {
+        self
+    }
+}
+
+/// Builder of the inputs of a circuit, following the same conventions of the circuits
+/// supported by this crate (e.g. the CSW one):
+/// - the constant, if any, is the first input;
+/// - the data which is not a field element is packed, in the order it's added, into
+///   field elements by a `DataAccumulator`;
+/// - the field elements follow, in the order they are added.
+/// If `compressed`, all the inputs but the constant are folded into a single field element
+/// through `compress_inputs_to_single_fe()`.
+#[derive(Clone)]
+pub struct UserInputsBuilder {
+    constant: Option<FieldElement>,
+    packed: DataAccumulator,
+    field_elements: Vec<FieldElement>,
+}
+
+impl Default for UserInputsBuilder {
+    fn default() -> Self {
+        Self::init()
+    }
+}
+
+impl UserInputsBuilder {
+    /// Initialize a builder with no inputs
+    pub fn init() -> Self {
+        Self {
+            constant: None,
+            packed: DataAccumulator::init(),
+            field_elements: Vec::new(),
+        }
+    }
+
+    /// Set the constant of the circuit, if any
+    pub fn constant(&mut self, constant: Option<&FieldElement>) -> &mut Self {
+        self.constant = constant.copied();
+        self
+    }
+
+    /// Add `data` to the data to be packed into field elements
+    pub fn pack<T: Packable + ?Sized>(
+        &mut self,
+        data: &T,
+    ) -> Result<&mut Self, ProvingSystemError> {
+        data.pack_into(&mut self.packed)
+            .map_err(|e| ProvingSystemError::Other(format!("{:?}", e)))?;
+        Ok(self)
+    }
+
+    /// Add `fe` to the field element inputs
+    pub fn field_element(&mut self, fe: &FieldElement) -> &mut Self {
+        self.field_elements.push(*fe);
+        self
+    }
+
+    /// Return the inputs of the circuit, folding all of them but the constant into a
+    /// single field element if `compressed`.
+    pub fn build(&self, compressed: bool) -> Result<Vec<FieldElement>, ProvingSystemError> {
+        let mut fes = self
+            .packed
+            .get_field_elements()
+            .map_err(|e| ProvingSystemError::Other(format!("{:?}", e)))?;
+        fes.extend_from_slice(&self.field_elements);
+
+        if compressed {
+            fes = vec![compress_inputs_to_single_fe(fes)?];
+        }
+
+        let mut inputs = Vec::with_capacity(fes.len() + 1);
+        inputs.extend(self.constant);
+        inputs.append(&mut fes);
+        Ok(inputs)
+    }
+}
+
+/// Implement `UserInputs` for a struct, building its inputs through a `UserInputsBuilder` out
+/// of the given fields (in the given order), and optionally compressing them. The constant field
+/// must be an `Option` of a (borrowed) field element, the `packed` fields must be `Packable`,
+/// and the `field_elements` ones must be (borrowed) field elements. E.g.:
+/// ```ignore
+/// struct MyCircuitInputs<'a> {
+///     constant: Option<&'a FieldElement>,
+///     amount: u64,
+///     pub_key_hash: [u8; MC_PK_SIZE],
+///     sc_id: &'a FieldElement,
+/// }
+///
+/// impl_user_inputs!(MyCircuitInputs<'a> {
+///     constant: constant,
+///     packed: [amount, pub_key_hash],
+///     field_elements: [sc_id],
+///     compressed: true,
+/// });
+/// ```
+#[macro_export]
+macro_rules! impl_user_inputs {
+    (
+        $name:ident $(<$lt:lifetime>)? {
+            $(constant: $constant:ident,)?
+            packed: [$($packed:ident),* $(,)?],
+            field_elements: [$($fe:ident),* $(,)?],
+            compressed: $compressed:expr $(,)?
+        }
+    ) => {
+        impl$(<$lt>)? $crate::proving_system::verifier::UserInputs for $name$(<$lt>)? {
+            fn get_circuit_inputs(
+                &self,
+            ) -> Result<
+                Vec<$crate::type_mapping::FieldElement>,
+                $crate::proving_system::error::ProvingSystemError,
+            > {
+                #[allow(unused_imports)]
+                use $crate::proving_system::verifier::user_inputs::AsFieldElement;
+
+                let mut builder =
+                    $crate::proving_system::verifier::user_inputs::UserInputsBuilder::init();
+                $(builder.constant(self.$constant.as_ref().map(|c| c.as_field_element()));)?
+                $(builder.pack(&self.$packed)?;)*
+                $(builder.field_element(self.$fe.as_field_element());)*
+                builder.build($compressed)
+            }
+        }
+    };
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        proving_system::verifier::{ceased_sidechain_withdrawal::CSWProofUserInputs, UserInputs},
+        type_mapping::MC_PK_SIZE,
+        utils::commitment_tree::{rand_fe_vec_with_rng, rand_fe_with_rng},
+    };
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    struct TestCSWInputs<'a> {
+        constant: Option<&'a FieldElement>,
+        amount: u64,
+        pub_key_hash: [u8; MC_PK_SIZE],
+        sc_id: &'a FieldElement,
+        nullifier: FieldElement,
+        cert_data_hash: &'a FieldElement,
+        end_cumulative_sc_tx_commitment_tree_root: &'a FieldElement,
+    }
+
+    impl_user_inputs!(TestCSWInputs<'a> {
+        constant: constant,
+        packed: [amount, pub_key_hash],
+        field_elements: [
+            sc_id,
+            nullifier,
+            cert_data_hash,
+            end_cumulative_sc_tx_commitment_tree_root,
+        ],
+        compressed: true,
+    });
+
+    struct TestUncompressedInputs {
+        flag: bool,
+        data: Vec<u8>,
+        fe: FieldElement,
+    }
+
+    impl_user_inputs!(TestUncompressedInputs {
+        packed: [flag, data],
+        field_elements: [fe],
+        compressed: false,
+    });
+
+    #[test]
+    fn impl_user_inputs_test() {
+        let rng = &mut StdRng::seed_from_u64(42);
+        let fes = rand_fe_vec_with_rng(5, rng);
+        let pub_key_hash = rng.gen::<[u8; MC_PK_SIZE]>();
+
+        // Same inputs as the ones of the CSW circuit, with and without constant
+        for constant in [None, Some(&fes[4])].iter() {
+            let csw_inputs = CSWProofUserInputs {
+                amount: 10,
+                constant: *constant,
+                sc_id: &fes[0],
+                nullifier: &fes[1],
+                pub_key_hash: &pub_key_hash,
+                cert_data_hash: &fes[2],
+                end_cumulative_sc_tx_commitment_tree_root: &fes[3],
+            };
+            let test_csw_inputs = TestCSWInputs {
+                constant: *constant,
+                amount: 10,
+                pub_key_hash,
+                sc_id: &fes[0],
+                nullifier: fes[1],
+                cert_data_hash: &fes[2],
+                end_cumulative_sc_tx_commitment_tree_root: &fes[3],
+            };
+            let inputs = test_csw_inputs.get_circuit_inputs().unwrap();
+            assert_eq!(inputs, csw_inputs.get_circuit_inputs().unwrap());
+            assert_eq!(inputs.len(), if constant.is_some() { 2 } else { 1 });
+        }
+
+        // Uncompressed inputs: packed data first, then field elements
+        let fe = rand_fe_with_rng(rng);
+        let test_inputs = TestUncompressedInputs {
+            flag: true,
+            data: vec![1, 2, 3],
+            fe,
+        };
+        let mut expected_inputs = DataAccumulator::init()
+            .update(true)
+            .unwrap()
+            .update(&[1u8, 2, 3][..])
+            .unwrap()
+            .get_field_elements()
+            .unwrap();
+        expected_inputs.push(fe);
+        assert_eq!(test_inputs.get_circuit_inputs().unwrap(), expected_inputs);
+
+        // Builder used directly
+        assert_eq!(
+            UserInputsBuilder::init()
+                .pack(&true)
+                .unwrap()
+                .pack(&test_inputs.data[..])
+                .unwrap()
+                .field_element(&fe)
+                .build(false)
+                .unwrap(),
+            expected_inputs
+        );
+        assert!(UserInputsBuilder::init().build(false).unwrap().is_empty());
+    }
+}
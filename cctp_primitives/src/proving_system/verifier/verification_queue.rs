@@ -0,0 +1,317 @@
+use crate::proving_system::{
+    error::ProvingSystemError,
+    verifier::{
+        batch_verifier::{BatchVerificationMode, ZendooBatchVerifier},
+        UserInputs,
+    },
+    ZendooProof, ZendooVerifierKey,
+};
+use rand::RngCore;
+use std::{
+    collections::BTreeMap,
+    time::{Duration, Instant},
+};
+
+/// Default maximum number of proofs waiting to be verified in a `VerificationQueue`.
+pub const DEFAULT_QUEUE_CAPACITY: usize = 1 << 10;
+
+/// Default number of waiting proofs triggering the verification of a `VerificationQueue`.
+pub const DEFAULT_QUEUE_FLUSH_SIZE: usize = 1 << 6;
+
+/// Default maximum time a proof waits in a `VerificationQueue` before being verified.
+pub const DEFAULT_QUEUE_FLUSH_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Callback invoked once the proof with the given ticket has been verified, with either
+/// success or the reason for which it fails.
+pub type VerificationCallback = Box<dyn FnOnce(u32, Result<(), String>) + Send>;
+
+/// Thresholds of a `VerificationQueue`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct VerificationQueueConfig {
+    /// Maximum number of proofs waiting to be verified: beyond it, proofs are rejected
+    pub capacity: usize,
+    /// Number of waiting proofs triggering their verification
+    pub flush_size: usize,
+    /// Maximum time the oldest waiting proof waits before all of them are verified
+    pub flush_interval: Duration,
+}
+
+impl Default for VerificationQueueConfig {
+    fn default() -> Self {
+        Self {
+            capacity: DEFAULT_QUEUE_CAPACITY,
+            flush_size: DEFAULT_QUEUE_FLUSH_SIZE,
+            flush_interval: DEFAULT_QUEUE_FLUSH_INTERVAL,
+        }
+    }
+}
+
+/// Bounded front-end to `ZendooBatchVerifier`, collecting incoming proofs and batch verifying
+/// them as soon as `flush_size` of them are waiting, or the oldest of them has waited for
+/// `flush_interval` (as checked by `try_enqueue()` and `poll()`).
+/// Each proof gets a ticket when enqueued, and its outcome is notified through its callback,
+/// if any: all the invalid proofs of a batch are identified, as per `BatchVerificationMode::CollectAll`.
+/// When `capacity` proofs are waiting, new ones are rejected, so that callers can apply backpressure.
+pub struct VerificationQueue {
+    config: VerificationQueueConfig,
+    verifier: ZendooBatchVerifier,
+    callbacks: BTreeMap<u32, Option<VerificationCallback>>,
+    next_ticket: u32,
+    oldest_enqueued: Option<Instant>,
+}
+
+impl VerificationQueue {
+    /// Create an empty queue with the given thresholds.
+    pub fn create(config: VerificationQueueConfig) -> Self {
+        Self {
+            config,
+            verifier: ZendooBatchVerifier::create(),
+            callbacks: BTreeMap::new(),
+            next_ticket: 0,
+            oldest_enqueued: None,
+        }
+    }
+
+    /// Return the thresholds of the queue
+    pub fn config(&self) -> &VerificationQueueConfig {
+        &self.config
+    }
+
+    /// Return the number of proofs waiting to be verified
+    pub fn len(&self) -> usize {
+        self.callbacks.len()
+    }
+
+    /// Return true if no proof is waiting to be verified
+    pub fn is_empty(&self) -> bool {
+        self.callbacks.is_empty()
+    }
+
+    /// Enqueue a proof, returning its ticket, and verify all the waiting proofs if a threshold
+    /// has been hit. Return a `QueueFull` error, leaving the queue untouched, if `capacity` proofs
+    /// are already waiting, or the same errors `ZendooBatchVerifier::add_zendoo_proof_verifier_data()`
+    /// does if the proof can't be added.
+    pub fn try_enqueue<I: UserInputs, R: RngCore>(
+        &mut self,
+        inputs: I,
+        proof: ZendooProof,
+        vk: ZendooVerifierKey,
+        callback: Option<VerificationCallback>,
+        rng: &mut R,
+    ) -> Result<u32, ProvingSystemError> {
+        if self.len() >= self.config.capacity {
+            return Err(ProvingSystemError::QueueFull(self.config.capacity));
+        }
+
+        let ticket = self.next_ticket;
+        self.verifier
+            .add_zendoo_proof_verifier_data(ticket, inputs, proof, vk)?;
+        self.callbacks.insert(ticket, callback);
+        self.next_ticket = self.next_ticket.wrapping_add(1);
+        self.oldest_enqueued.get_or_insert_with(Instant::now);
+
+        self.poll(rng);
+        Ok(ticket)
+    }
+
+    /// Verify all the waiting proofs if a threshold has been hit, returning their number.
+    /// Should be invoked periodically, so that proofs don't wait longer than `flush_interval`
+    /// when no new proof is enqueued.
+    pub fn poll<R: RngCore>(&mut self, rng: &mut R) -> usize {
+        let size_hit = self.len() >= self.config.flush_size;
+        let time_hit = self.oldest_enqueued.map_or(false, |oldest| {
+            oldest.elapsed() >= self.config.flush_interval
+        });
+
+        if size_hit || time_hit {
+            self.flush(rng)
+        } else {
+            0
+        }
+    }
+
+    /// Verify all the waiting proofs, notifying their outcome through their callbacks,
+    /// and return their number.
+    pub fn flush<R: RngCore>(&mut self, rng: &mut R) -> usize {
+        if self.is_empty() {
+            return 0;
+        }
+
+        let verifier = std::mem::replace(&mut self.verifier, ZendooBatchVerifier::create());
+        let callbacks = std::mem::take(&mut self.callbacks);
+        self.oldest_enqueued = None;
+
+        let mut failures = BTreeMap::new();
+        let mut common_failure = None;
+        match verifier.batch_verify_all_with_mode(BatchVerificationMode::CollectAll, rng) {
+            Ok(true) => {}
+            Err(ProvingSystemError::FailedBatchVerificationWithDetails(details)) => {
+                failures = details.into_iter().collect()
+            }
+            // No proof can be told apart from the others
+            Ok(false) => common_failure = Some("Batch verification failed".to_owned()),
+            Err(e) => common_failure = Some(e.to_string()),
+        }
+
+        let num_proofs = callbacks.len();
+        for (ticket, callback) in callbacks.into_iter() {
+            let result = match (failures.remove(&ticket), &common_failure) {
+                (Some(reason), _) => Err(reason),
+                (None, Some(reason)) => Err(reason.clone()),
+                (None, None) => Ok(()),
+            };
+            if let Some(callback) = callback {
+                callback(ticket, result);
+            }
+        }
+        num_proofs
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        proving_system::init::{
+            load_g1_committer_key, COMMITTER_KEY_MAX_DEGREE_FOR_TESTING, G1_UNIVERSAL_PARAMS,
+        },
+        type_mapping::FieldElement,
+        utils::commitment_tree::rand_fe_with_rng,
+    };
+    use proof_systems::darlin::tests::simple_marlin::generate_test_data as generate_simple_marlin_test_data;
+    use rand::thread_rng;
+    use serial_test::serial;
+    use std::sync::{Arc, Mutex};
+
+    struct TestCircuitInputs {
+        c: FieldElement,
+        d: FieldElement,
+    }
+
+    impl UserInputs for TestCircuitInputs {
+        fn get_circuit_inputs(&self) -> Result<Vec<FieldElement>, ProvingSystemError> {
+            Ok(vec![self.c, self.d])
+        }
+    }
+
+    #[test]
+    #[serial]
+    fn verification_queue_test() {
+        let segment_size = 1 << 7;
+        let rng = &mut thread_rng();
+
+        let _result_g1 = load_g1_committer_key(COMMITTER_KEY_MAX_DEGREE_FOR_TESTING);
+        let params_g1 = G1_UNIVERSAL_PARAMS.read().unwrap().clone().unwrap();
+        let (pcds, vks) =
+            generate_simple_marlin_test_data(segment_size - 1, segment_size, &params_g1, 5, rng);
+        let vk = ZendooVerifierKey::CoboundaryMarlin(vks[0].clone());
+
+        let results = Arc::new(Mutex::new(BTreeMap::new()));
+        let callback = || -> Option<VerificationCallback> {
+            let results = results.clone();
+            Some(Box::new(move |ticket, result| {
+                results.lock().unwrap().insert(ticket, result);
+            }))
+        };
+        // Make the second proof fail
+        let wrong_input = rand_fe_with_rng(rng);
+        let inputs = |idx: usize| TestCircuitInputs {
+            c: pcds[idx].usr_ins[0],
+            d: if idx == 1 {
+                wrong_input
+            } else {
+                pcds[idx].usr_ins[1]
+            },
+        };
+
+        let mut queue = VerificationQueue::create(VerificationQueueConfig {
+            capacity: 3,
+            flush_size: 3,
+            flush_interval: Duration::from_secs(3600),
+        });
+
+        // No threshold hit
+        for idx in 0..2 {
+            let ticket = queue
+                .try_enqueue(
+                    inputs(idx),
+                    ZendooProof::CoboundaryMarlin(pcds[idx].proof.clone()),
+                    vk.clone(),
+                    callback(),
+                    rng,
+                )
+                .unwrap();
+            assert_eq!(ticket, idx as u32);
+        }
+        assert_eq!(queue.len(), 2);
+        assert_eq!(queue.poll(rng), 0);
+        assert!(results.lock().unwrap().is_empty());
+
+        // Size threshold hit: all the waiting proofs are verified
+        queue
+            .try_enqueue(
+                inputs(2),
+                ZendooProof::CoboundaryMarlin(pcds[2].proof.clone()),
+                vk.clone(),
+                callback(),
+                rng,
+            )
+            .unwrap();
+        assert!(queue.is_empty());
+        {
+            let results = results.lock().unwrap();
+            assert_eq!(results.len(), 3);
+            assert!(results[&0].is_ok());
+            assert!(results[&1].is_err());
+            assert!(results[&2].is_ok());
+        }
+
+        // Proofs beyond capacity are rejected
+        let mut queue = VerificationQueue::create(VerificationQueueConfig {
+            capacity: 1,
+            flush_size: 2,
+            flush_interval: Duration::from_secs(3600),
+        });
+        queue
+            .try_enqueue(
+                inputs(3),
+                ZendooProof::CoboundaryMarlin(pcds[3].proof.clone()),
+                vk.clone(),
+                callback(),
+                rng,
+            )
+            .unwrap();
+        assert!(matches!(
+            queue.try_enqueue(
+                inputs(4),
+                ZendooProof::CoboundaryMarlin(pcds[4].proof.clone()),
+                vk.clone(),
+                callback(),
+                rng,
+            ),
+            Err(ProvingSystemError::QueueFull(1))
+        ));
+        assert_eq!(queue.len(), 1);
+
+        // Explicit flush
+        assert_eq!(queue.flush(rng), 1);
+        assert_eq!(queue.flush(rng), 0);
+        assert!(results.lock().unwrap()[&0].is_ok());
+
+        // Time threshold hit
+        let mut queue = VerificationQueue::create(VerificationQueueConfig {
+            flush_interval: Duration::from_secs(0),
+            ..VerificationQueueConfig::default()
+        });
+        queue
+            .try_enqueue(
+                inputs(4),
+                ZendooProof::CoboundaryMarlin(pcds[4].proof.clone()),
+                vk,
+                None,
+                rng,
+            )
+            .unwrap();
+        assert!(queue.is_empty());
+    }
+}
@@ -0,0 +1,455 @@
+//! Generators of test data, shared by the tests of this crate and by the ones of the crates
+//! built on top of it (e.g. the FFI bindings), so that the corner cases are covered consistently.
+//! Available to the latter through the `testing` feature.
+use crate::{
+    commitment_tree::{
+        sidechain_tree_alive::{BWTR_MT_HEIGHT, CERT_MT_HEIGHT, FWT_MT_HEIGHT},
+        sidechain_tree_ceased::CSW_MT_HEIGHT,
+        CommitmentTree, CommitmentTreeOrdering, CMT_MT_HEIGHT,
+    },
+    proving_system::{
+        error::ProvingSystemError,
+        init::{
+            get_g1_max_degree, get_g2_max_degree, load_g1_committer_key, load_g2_committer_key,
+            COMMITTER_KEY_MAX_DEGREE_FOR_TESTING, G1_UNIVERSAL_PARAMS, G2_UNIVERSAL_PARAMS,
+        },
+        verifier::ceased_sidechain_withdrawal::PHANTOM_CERT_DATA_HASH,
+        ProvingSystem, ZendooProof, ZendooVerifierKey,
+    },
+    type_mapping::FieldElement,
+    utils::{
+        commitment_tree::{empty_root_at_height, pow2, rand_fe_with_rng, MAX_EMPTY_ROOT_HEIGHT},
+        mc_block_ref::{hash_mc_block_reference, MC_BLOCK_HASH_SIZE},
+        serialization::serialize_to_buffer,
+    },
+};
+use algebra::{AffineCurve, Field};
+use poly_commit::{ipa_pc::UniversalParams, PCUniversalParams};
+use proof_systems::darlin::tests::{
+    final_darlin::generate_test_data as generate_final_darlin_test_data,
+    simple_marlin::generate_test_data as generate_simple_marlin_test_data,
+};
+use rand::{Rng, RngCore};
+use std::{
+    collections::BTreeSet,
+    fmt::Write,
+    fs,
+    path::{Path, PathBuf},
+    sync::RwLock,
+};
+
+/// Return the smallest FieldElement, i.e. 0.
+pub fn min_fe() -> FieldElement {
+    FieldElement::zero()
+}
+
+/// Return the biggest FieldElement, i.e. the modulus minus 1.
+pub fn max_fe() -> FieldElement {
+    -FieldElement::one()
+}
+
+/// Return the FieldElements immediately preceding and following `fe`, wrapping around the modulus.
+pub fn adjacent_fes(fe: &FieldElement) -> (FieldElement, FieldElement) {
+    (*fe - FieldElement::one(), *fe + FieldElement::one())
+}
+
+/// Return `len` distinct random SC-IDs, sorted according to `ordering`.
+pub fn sorted_sc_ids_with_rng<R: Rng + ?Sized>(
+    len: usize,
+    ordering: CommitmentTreeOrdering,
+    rng: &mut R,
+) -> Vec<FieldElement> {
+    let mut ids = BTreeSet::new();
+    while ids.len() < len {
+        ids.insert(rand_fe_with_rng(rng));
+    }
+    let mut ids = ids.into_iter().collect::<Vec<_>>();
+    ids.sort_by(|a, b| ordering.compare(a, b));
+    ids
+}
+
+/// Return a CommitmentTree, with the given `ordering`, containing the sidechains with the given IDs,
+/// each one with a random leaf: sidechains at even positions in `sc_ids` are alive, the others are ceased.
+/// Panics if the sidechains can't be added, e.g. because `sc_ids` contains duplicates.
+pub fn cmt_with_sidechains_with_rng<R: Rng + ?Sized>(
+    sc_ids: &[FieldElement],
+    ordering: CommitmentTreeOrdering,
+    rng: &mut R,
+) -> CommitmentTree {
+    let mut cmt = CommitmentTree::create_with_ordering(ordering);
+    for (i, sc_id) in sc_ids.iter().enumerate() {
+        let leaf = rand_fe_with_rng(rng);
+        let added = if i % 2 == 0 {
+            cmt.add_fwt_leaf(sc_id, &leaf)
+        } else {
+            cmt.add_csw_leaf(sc_id, &leaf)
+        };
+        assert!(added, "Unable to add sidechain {:?}", sc_id);
+    }
+    cmt
+}
+
+/// Return a full CommitmentTree, i.e. containing as many sidechains as the capacity of the Commitment-MT,
+/// with the given `ordering`, along with the IDs of its sidechains sorted according to `ordering`.
+/// The smallest and the biggest FieldElements are absent from the tree, so that absence proofs at
+/// the boundaries of the Commitment-MT can be built for them.
+pub fn full_cmt_with_rng<R: Rng + ?Sized>(
+    ordering: CommitmentTreeOrdering,
+    rng: &mut R,
+) -> (CommitmentTree, Vec<FieldElement>) {
+    let mut sc_ids = sorted_sc_ids_with_rng(pow2(CMT_MT_HEIGHT), ordering, rng);
+    // The chances of drawing them are negligible, but anyway
+    while sc_ids.contains(&min_fe()) || sc_ids.contains(&max_fe()) {
+        sc_ids = sorted_sc_ids_with_rng(pow2(CMT_MT_HEIGHT), ordering, rng);
+    }
+    let cmt = cmt_with_sidechains_with_rng(&sc_ids, ordering, rng);
+    (cmt, sc_ids)
+}
+
+/// Return the IDs absent from a CommitmentTree containing the sidechains with the given IDs
+/// that are most likely to hit corner cases of absence proofs: the smallest and the biggest
+/// FieldElements and the FieldElements adjacent to each of the existing IDs.
+pub fn corner_case_absent_ids(sc_ids: &[FieldElement]) -> Vec<FieldElement> {
+    let existing = sc_ids.iter().collect::<BTreeSet<_>>();
+    let mut absent_ids = vec![min_fe(), max_fe()];
+    for sc_id in sc_ids.iter() {
+        let (prev, next) = adjacent_fes(sc_id);
+        absent_ids.push(prev);
+        absent_ids.push(next);
+    }
+    absent_ids
+        .into_iter()
+        .filter(|id| !existing.contains(id))
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect()
+}
+
+/// Segment size of the default `TestCircuit`.
+pub const TEST_CIRCUIT_SEGMENT_SIZE: usize = 1 << 7;
+
+/// Number of constraints of the default `TestCircuit`.
+pub const TEST_CIRCUIT_NUM_CONSTRAINTS: usize = TEST_CIRCUIT_SEGMENT_SIZE - 1;
+
+/// Load the committer keys the proofs of a `TestCircuit` are created and verified with, i.e. the G1 and
+/// G2 ones supporting the max degree the tests of this crate use, unless they have already been loaded
+/// (in which case the loaded ones are kept, and `TestCircuit::setup_and_prove()` checks they are big enough).
+pub fn load_test_committer_keys() -> Result<(), ProvingSystemError> {
+    // Loading fails if the keys have already been loaded: their presence is checked below anyway
+    let _ = load_g1_committer_key(COMMITTER_KEY_MAX_DEGREE_FOR_TESTING);
+    let _ = load_g2_committer_key(COMMITTER_KEY_MAX_DEGREE_FOR_TESTING);
+    get_g1_max_degree()?;
+    get_g2_max_degree()?;
+    Ok(())
+}
+
+/// A dummy circuit, proving the knowledge of `a` and `b` such that `c = a·b` and `d = c·b`, being `c`
+/// and `d` its public inputs, which allows to run end-to-end verification tests (e.g. of the batch
+/// verifier) without depending on the test utilities of the proof systems.
+/// Proofs are fully determined by the rng they are created with, and all the proofs of a circuit
+/// have the same size, since it only depends on the proving system and on the dimensions of the circuit.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct TestCircuit {
+    pub proving_system: ProvingSystem,
+    pub num_constraints: usize,
+    pub segment_size: usize,
+}
+
+/// A proof of a `TestCircuit`, along with its public inputs, i.e. `c` and `d`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TestCircuitProof {
+    pub proof: ZendooProof,
+    pub inputs: Vec<FieldElement>,
+}
+
+impl TestCircuit {
+    /// Return a `TestCircuit` for `proving_system`, with `TEST_CIRCUIT_NUM_CONSTRAINTS` constraints
+    /// and segment size `TEST_CIRCUIT_SEGMENT_SIZE`.
+    pub fn create(proving_system: ProvingSystem) -> Self {
+        Self {
+            proving_system,
+            num_constraints: TEST_CIRCUIT_NUM_CONSTRAINTS,
+            segment_size: TEST_CIRCUIT_SEGMENT_SIZE,
+        }
+    }
+
+    /// Generate the vk of the circuit and `num_proofs` proofs of it, each one for random `a` and `b`.
+    /// The committer keys must have been loaded (see `load_test_committer_keys()`) with a max degree
+    /// supporting the segment size of the circuit.
+    /// Returns an error if `num_proofs` is 0, if the proving system is undefined, if the segment size
+    /// isn't a power of 2 greater than 1 or if it's not supported by the loaded committer keys.
+    pub fn setup_and_prove<R: RngCore>(
+        &self,
+        num_proofs: usize,
+        rng: &mut R,
+    ) -> Result<(ZendooVerifierKey, Vec<TestCircuitProof>), ProvingSystemError> {
+        if num_proofs == 0 {
+            return Err(ProvingSystemError::ProofCreationFailed(
+                "At least one proof must be created".to_owned(),
+            ));
+        }
+        if self.segment_size < 2 || !self.segment_size.is_power_of_two() {
+            return Err(ProvingSystemError::InvalidCircuitParameters(format!(
+                "Segment size ({}) must be a power of 2 greater than 1",
+                self.segment_size
+            )));
+        }
+        if self.num_constraints == 0 {
+            return Err(ProvingSystemError::InvalidCircuitParameters(
+                "Number of constraints must be greater than 0".to_owned(),
+            ));
+        }
+
+        match self.proving_system {
+            ProvingSystem::CoboundaryMarlin => {
+                let params_g1 = supporting_params(&G1_UNIVERSAL_PARAMS, self.segment_size - 1)?;
+                let (pcds, vks) = generate_simple_marlin_test_data(
+                    self.num_constraints,
+                    self.segment_size,
+                    &params_g1,
+                    num_proofs,
+                    rng,
+                );
+                let proofs = pcds
+                    .into_iter()
+                    .map(|pcd| TestCircuitProof {
+                        proof: ZendooProof::CoboundaryMarlin(pcd.proof),
+                        inputs: pcd.usr_ins,
+                    })
+                    .collect();
+                Ok((ZendooVerifierKey::CoboundaryMarlin(vks[0].clone()), proofs))
+            }
+            ProvingSystem::Darlin => {
+                let params_g1 = supporting_params(&G1_UNIVERSAL_PARAMS, self.segment_size - 1)?;
+                let params_g2 = supporting_params(&G2_UNIVERSAL_PARAMS, self.segment_size - 1)?;
+                let (pcds, vks) = generate_final_darlin_test_data(
+                    self.num_constraints,
+                    self.segment_size,
+                    &params_g1,
+                    &params_g2,
+                    num_proofs,
+                    rng,
+                );
+                let proofs = pcds
+                    .into_iter()
+                    .map(|pcd| TestCircuitProof {
+                        proof: ZendooProof::Darlin(pcd.final_darlin_proof),
+                        inputs: pcd.usr_ins,
+                    })
+                    .collect();
+                Ok((ZendooVerifierKey::Darlin(vks[0].clone()), proofs))
+            }
+            ProvingSystem::Undefined => Err(ProvingSystemError::UndefinedProvingSystem),
+        }
+    }
+}
+
+/// Return a copy of the loaded universal `params`, checking that they support `degree`.
+fn supporting_params<G: AffineCurve>(
+    params: &RwLock<Option<UniversalParams<G>>>,
+    degree: usize,
+) -> Result<UniversalParams<G>, ProvingSystemError> {
+    let params = params
+        .read()
+        .map_err(|_| ProvingSystemError::Other("Failed to acquire lock for the params".to_owned()))?
+        .as_ref()
+        .ok_or(ProvingSystemError::CommitterKeyNotInitialized)?
+        .clone();
+    if params.max_degree() < degree {
+        return Err(ProvingSystemError::UnsupportedDegree {
+            requested: degree,
+            available: params.max_degree(),
+        });
+    }
+    Ok(params)
+}
+
+/// Path, relative to the root of this crate, of the checked-in file holding the golden vectors.
+pub const GOLDEN_VECTORS_PATH: &str = "test/golden/constants.txt";
+
+/// Return the golden vectors, i.e. the constants that implementations of the CCTP in other languages
+/// must agree on, as pairs of name and canonical serialization, in the order they are stored in
+/// `GOLDEN_VECTORS_PATH`: the phantom cert data hash, the empty roots of the MTs of each supported height,
+/// the empty roots of the subtrees of a sidechain, the commitment of an empty CommitmentTree and the
+/// hash of the reference to the MC block with hash `0x000102..1f` at height 1000, with cumulative root 1.
+pub fn golden_vectors() -> Vec<(String, Vec<u8>)> {
+    let mut vectors = vec![("phantom_cert_data_hash".to_owned(), PHANTOM_CERT_DATA_HASH)];
+    for height in 0..=MAX_EMPTY_ROOT_HEIGHT {
+        vectors.push((
+            format!("empty_root_height_{}", height),
+            empty_root_at_height(height).unwrap(),
+        ));
+    }
+    for (subtree, height) in [
+        ("fwt", FWT_MT_HEIGHT),
+        ("bwtr", BWTR_MT_HEIGHT),
+        ("cert", CERT_MT_HEIGHT),
+        ("csw", CSW_MT_HEIGHT),
+    ]
+    .iter()
+    {
+        vectors.push((
+            format!("empty_{}_subtree_root", subtree),
+            empty_root_at_height(*height).unwrap(),
+        ));
+    }
+    vectors.push((
+        "empty_commitment_tree_commitment".to_owned(),
+        CommitmentTree::create().get_commitment().unwrap(),
+    ));
+    let mut block_hash = [0u8; MC_BLOCK_HASH_SIZE];
+    for (i, byte) in block_hash.iter_mut().enumerate() {
+        *byte = i as u8;
+    }
+    vectors.push((
+        "mc_block_reference_hash".to_owned(),
+        hash_mc_block_reference(&block_hash, 1000, &FieldElement::one()).unwrap(),
+    ));
+
+    vectors
+        .into_iter()
+        .map(|(name, fe)| (name, serialize_to_buffer(&fe, None).unwrap()))
+        .collect()
+}
+
+/// Return the contents of the golden vectors file: one `<name> = 0x<hex of the canonical serialization>`
+/// line for each vector.
+pub fn golden_vectors_to_string(vectors: &[(String, Vec<u8>)]) -> String {
+    let mut contents = String::new();
+    for (name, bytes) in vectors.iter() {
+        write!(contents, "{} = 0x", name).unwrap();
+        for byte in bytes.iter() {
+            write!(contents, "{:02x}", byte).unwrap();
+        }
+        contents.push('\n');
+    }
+    contents
+}
+
+/// Compare `contents` with the ones of the checked-in file at `path`, relative to the root of this crate,
+/// so that any change to them is noticed. If the environment variable `update_var` is set, the file is
+/// (re)written with `contents` instead, whenever a change is intended.
+/// Return the full path of the file.
+/// Panics if the file differs from `contents`, or if it's missing and `update_var` is not set.
+pub fn check_golden_file(path: &str, contents: &str, update_var: &str) -> PathBuf {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join(path);
+    if std::env::var_os(update_var).is_some() {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, contents).unwrap();
+        log::info!("{} written", path.display());
+    }
+
+    let checked_in = fs::read_to_string(&path).unwrap_or_else(|e| {
+        panic!(
+            "Unable to read {}: {}. Run the tests with {} set to generate it",
+            path.display(),
+            e,
+            update_var
+        )
+    });
+    assert_eq!(
+        checked_in,
+        contents,
+        "{} is out of date: run the tests with {} set if the change is intended",
+        path.display(),
+        update_var
+    );
+    path
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::proving_system::verifier::{
+        batch_verifier::ZendooBatchVerifier, verify_zendoo_proof,
+    };
+    use algebra::CanonicalSerialize;
+    use rand::{rngs::StdRng, SeedableRng};
+    use serial_test::serial;
+
+    #[test]
+    #[serial]
+    fn test_circuit_test() {
+        load_test_committer_keys().unwrap();
+        let rng = &mut StdRng::seed_from_u64(42);
+        let mut batch_verifier = ZendooBatchVerifier::create();
+        let mut id = 0u32;
+
+        for proving_system in [ProvingSystem::CoboundaryMarlin, ProvingSystem::Darlin].iter() {
+            let circuit = TestCircuit::create(*proving_system);
+            let (vk, proofs) = circuit.setup_and_prove(3, rng).unwrap();
+            assert_eq!(vk.get_proving_system_type(), *proving_system);
+            assert_eq!(proofs.len(), 3);
+
+            // Same rng, same vk and proofs
+            assert_eq!(
+                circuit
+                    .setup_and_prove(2, &mut StdRng::seed_from_u64(7))
+                    .unwrap(),
+                circuit
+                    .setup_and_prove(2, &mut StdRng::seed_from_u64(7))
+                    .unwrap()
+            );
+
+            // All the proofs have the same size
+            let proof_size = proofs[0].proof.serialized_size();
+            for proof in proofs.into_iter() {
+                assert_eq!(proof.proof.get_proving_system_type(), *proving_system);
+                assert_eq!(proof.proof.serialized_size(), proof_size);
+                assert!(
+                    verify_zendoo_proof(proof.inputs.clone(), &proof.proof, &vk, Some(rng))
+                        .unwrap()
+                );
+
+                // Verification failure with wrong inputs
+                let wrong_inputs = vec![proof.inputs[1], proof.inputs[0]];
+                let res = verify_zendoo_proof(wrong_inputs, &proof.proof, &vk, Some(rng));
+                assert!(res.is_err() || !res.unwrap());
+
+                batch_verifier
+                    .add_zendoo_proof_verifier_data(id, proof.inputs, proof.proof, vk.clone())
+                    .unwrap();
+                id += 1;
+            }
+        }
+        assert!(batch_verifier.batch_verify_all(rng).unwrap());
+
+        // Invalid circuits are rejected
+        let circuit = TestCircuit::create(ProvingSystem::CoboundaryMarlin);
+        assert!(matches!(
+            circuit.setup_and_prove(0, rng),
+            Err(ProvingSystemError::ProofCreationFailed(_))
+        ));
+        assert!(matches!(
+            TestCircuit::create(ProvingSystem::Undefined).setup_and_prove(1, rng),
+            Err(ProvingSystemError::UndefinedProvingSystem)
+        ));
+        assert!(matches!(
+            TestCircuit {
+                segment_size: TEST_CIRCUIT_SEGMENT_SIZE + 1,
+                ..circuit
+            }
+            .setup_and_prove(1, rng),
+            Err(ProvingSystemError::InvalidCircuitParameters(_))
+        ));
+        assert!(matches!(
+            TestCircuit {
+                segment_size: (get_g1_max_degree().unwrap() + 1) * 2,
+                ..circuit
+            }
+            .setup_and_prove(1, rng),
+            Err(ProvingSystemError::UnsupportedDegree { .. })
+        ));
+    }
+
+    // Compares the golden vectors with the checked-in ones, so that any change to them is noticed.
+    // Set UPDATE_GOLDEN_VECTORS to rewrite the file instead, whenever a change is intended.
+    #[test]
+    fn golden_vectors_test() {
+        check_golden_file(
+            GOLDEN_VECTORS_PATH,
+            &golden_vectors_to_string(&golden_vectors()),
+            "UPDATE_GOLDEN_VECTORS",
+        );
+    }
+}
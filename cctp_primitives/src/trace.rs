@@ -0,0 +1,25 @@
+//! Spans around the consensus-critical operations (e.g. building the commitment of a block,
+//! verifying a batch of proofs, generating the universal params and processing bit vectors),
+//! emitted through `tracing` if the `tracing` feature is enabled, so that slow operations can
+//! be correlated with the data (ids, sizes) they were performing on.
+
+/// Enter an info level span with the given name and fields, as `tracing::info_span!` takes them,
+/// returning a guard exiting it when dropped.
+#[cfg(feature = "tracing")]
+macro_rules! trace_span {
+    ($($args:tt)*) => {
+        ::tracing::info_span!($($args)*).entered()
+    };
+}
+
+/// Without the `tracing` feature no span is entered, and its fields aren't evaluated.
+#[cfg(not(feature = "tracing"))]
+macro_rules! trace_span {
+    ($($args:tt)*) => {
+        $crate::trace::DisabledSpan
+    };
+}
+
+/// Guard returned by `trace_span!` when the `tracing` feature is disabled.
+#[cfg(not(feature = "tracing"))]
+pub(crate) struct DisabledSpan;
@@ -1,8 +1,13 @@
-use crate::type_mapping::{Error, FieldElement, FieldHash, GingerMHT, FIELD_SIZE};
+use crate::type_mapping::{
+    Error, FieldElement, FieldHash, GingerMHT, FIELD_CAPACITY, FIELD_SIZE,
+    GINGER_MHT_POSEIDON_PARAMETERS,
+};
 use crate::utils::mht::{append_leaf_to_ginger_mht, new_ginger_mht};
 use algebra::{CanonicalSerialize, ToConstraintField, UniformRand};
 use primitives::FieldBasedHash;
 use rand::Rng;
+#[cfg(feature = "zeroize")]
+use zeroize::Zeroize;
 
 pub const fn pow2(power: usize) -> usize {
     1 << power
@@ -12,11 +17,63 @@ pub const fn pow2(power: usize) -> usize {
 // Merkle Tree utils
 //--------------------------------------------------------------------------------------------------
 
+/// Processing step used by default for the sidechain subtrees, which usually hold a handful of leaves
+pub const SC_SUBTREE_PROCESSING_STEP: usize = 1 << 4;
+
 /// Creates new FieldElement-based MT
 pub fn new_mt(height: usize) -> Result<GingerMHT, Error> {
     new_ginger_mht(height, 2usize.pow(height as u32))
 }
 
+/// Creates new FieldElement-based MT hashing the appended leaves in batches of `processing_step`,
+/// instead of waiting for all the 2^height leaves as `new_mt` does.
+/// Smaller steps trade some hashing efficiency for smaller buffers, which is convenient
+/// for trees that are expected to be mostly empty.
+/// `processing_step` must be a power of 2 not bigger than 2^height.
+pub fn new_mt_with_processing_step(
+    height: usize,
+    processing_step: usize,
+) -> Result<GingerMHT, Error> {
+    if !processing_step.is_power_of_two() || processing_step > 2usize.pow(height as u32) {
+        Err(format!(
+            "Invalid processing step {} for a MT of height {}",
+            processing_step, height
+        ))?
+    }
+    new_ginger_mht(height, processing_step)
+}
+
+/// Creates new FieldElement-based MT for a sidechain subtree, with `SC_SUBTREE_PROCESSING_STEP`
+/// or, if the tree is smaller, with a processing step of 2^height.
+pub fn new_sc_subtree_mt(height: usize) -> Result<GingerMHT, Error> {
+    new_mt_with_processing_step(height, SC_SUBTREE_PROCESSING_STEP.min(pow2(height)))
+}
+
+/// Maximum height of a FieldElement-based MT for which the empty root is known
+pub const MAX_EMPTY_ROOT_HEIGHT: usize = GINGER_MHT_POSEIDON_PARAMETERS.nodes.len() - 1;
+
+/// Returns the root of an empty FieldElement-based MT of the given height,
+/// or an error if `height` is bigger than `MAX_EMPTY_ROOT_HEIGHT`
+pub fn empty_root_at_height(height: usize) -> Result<FieldElement, Error> {
+    GINGER_MHT_POSEIDON_PARAMETERS
+        .nodes
+        .get(height)
+        .copied()
+        .ok_or_else(|| {
+            format!(
+                "Height {} is bigger than the max supported height {}",
+                height, MAX_EMPTY_ROOT_HEIGHT
+            )
+            .into()
+        })
+}
+
+/// Same as `empty_root_at_height`, for the definition of constants:
+/// an out of range `height` makes the build fail.
+pub const fn empty_root_at_height_const(height: usize) -> &'static FieldElement {
+    &GINGER_MHT_POSEIDON_PARAMETERS.nodes[height]
+}
+
 /// Sequentially inserts leafs into an MT by using a specified position which is incremented afterwards
 /// Returns false if there is no more place to insert a leaf
 pub fn add_leaf(tree: &mut GingerMHT, leaf: &FieldElement) -> bool {
@@ -73,20 +130,23 @@ impl DataAccumulator {
 
     /// Update this struct with data obtained by serializing the input instance `serializable`.
     pub fn update<T: CanonicalSerialize>(&mut self, serializable: T) -> Result<&mut Self, Error> {
-        // Serialize serializable without saving any additional info
-        let mut buffer = Vec::with_capacity(serializable.serialized_size());
-        serializable.serialize_without_metadata(&mut buffer)?;
-
-        let mut bits = primitives::bytes_to_bits(buffer.as_slice());
-        // byte serialization is in little endian, but bit serialization is in big endian: we need to reverse.
-        bits.reverse();
+        let mut bits = serialize_to_bits(serializable)?;
         self.bit_buffer.append(&mut bits);
+
+        // Wipe the intermediate copy of the data
+        #[cfg(feature = "zeroize")]
+        bits.zeroize();
+
         Ok(self)
     }
 
     /// Update this struct with 'bits', assumed to be in big endian bit order.
     pub fn update_with_bits(&mut self, mut bits: Vec<bool>) -> Result<&mut Self, Error> {
         self.bit_buffer.append(&mut bits);
+
+        #[cfg(feature = "zeroize")]
+        bits.zeroize();
+
         Ok(self)
     }
 
@@ -113,33 +173,467 @@ impl DataAccumulator {
     }
 }
 
+/// Serialize `serializable`, without saving any additional info, into bytes.
+fn serialize_to_bytes<T: CanonicalSerialize>(serializable: T) -> Result<Vec<u8>, Error> {
+    let mut buffer = Vec::with_capacity(serializable.serialized_size());
+    serializable.serialize_without_metadata(&mut buffer)?;
+    Ok(buffer)
+}
+
+/// Serialize `serializable`, without saving any additional info, into big endian bits.
+fn serialize_to_bits<T: CanonicalSerialize>(serializable: T) -> Result<Vec<bool>, Error> {
+    #[allow(unused_mut)]
+    let mut buffer = serialize_to_bytes(serializable)?;
+
+    let mut bits = primitives::bytes_to_bits(buffer.as_slice());
+    // byte serialization is in little endian, but bit serialization is in big endian: we need to reverse.
+    bits.reverse();
+
+    // Wipe the intermediate copy of the data
+    #[cfg(feature = "zeroize")]
+    buffer.zeroize();
+
+    Ok(bits)
+}
+
+/// Default number of field elements absorbed at once by a `BoundedDataAccumulator`.
+pub const DEFAULT_ACCUMULATOR_FLUSH_THRESHOLD: usize = 1 << 10;
+
+/// Accumulator of serializable data or bits, using a bounded amount of memory regardless of the
+/// size of the accumulated data: as soon as `flush_threshold` FieldElements can be deserialized
+/// out of the accumulated bits, they are absorbed by a running variable length FieldHash.
+/// The resulting hash is the same `DataAccumulator::compute_field_hash_variable_length(false)`
+/// returns for the same sequence of updates.
+/// Huge data (e.g. a multi-MB `custom_creation_data`) should be supplied in multiple updates,
+/// bearing in mind that, as for `DataAccumulator`, the hash depends on how data is split among them.
+pub struct BoundedDataAccumulator {
+    bit_buffer: Vec<bool>,
+    hasher: FieldHash,
+    flush_threshold: usize,
+}
+
+impl BoundedDataAccumulator {
+    /// Initialize an empty accumulator, absorbing FieldElements `flush_threshold` at a time.
+    /// Returns an error if `flush_threshold` is 0.
+    pub fn init(flush_threshold: usize) -> Result<Self, Error> {
+        if flush_threshold == 0 {
+            Err("Flush threshold must be greater than 0")?
+        }
+        Ok(Self {
+            bit_buffer: vec![],
+            hasher: FieldHash::init_variable_length(false, None),
+            flush_threshold,
+        })
+    }
+
+    /// Update this struct with data obtained by serializing the input instance `serializable`.
+    pub fn update<T: CanonicalSerialize>(&mut self, serializable: T) -> Result<&mut Self, Error> {
+        let bits = serialize_to_bits(serializable)?;
+        self.update_with_bits(bits)
+    }
+
+    /// Update this struct with 'bits', assumed to be in big endian bit order.
+    pub fn update_with_bits(&mut self, mut bits: Vec<bool>) -> Result<&mut Self, Error> {
+        self.bit_buffer.append(&mut bits);
+
+        #[cfg(feature = "zeroize")]
+        bits.zeroize();
+
+        self.flush()?;
+        Ok(self)
+    }
+
+    /// Absorb all the complete chunks of `flush_threshold` FieldElements accumulated so far.
+    fn flush(&mut self) -> Result<(), Error> {
+        let chunk_size = self.flush_threshold * FIELD_CAPACITY;
+        while self.bit_buffer.len() >= chunk_size {
+            self.absorb(chunk_size)?;
+        }
+        Ok(())
+    }
+
+    /// Deserialize the first `num_bits` accumulated bits into FieldElements and absorb them.
+    fn absorb(&mut self, num_bits: usize) -> Result<(), Error> {
+        let fes: Vec<FieldElement> = self.bit_buffer[..num_bits].to_field_elements()?;
+        fes.into_iter().for_each(|fe| {
+            self.hasher.update(fe);
+        });
+
+        #[cfg(feature = "zeroize")]
+        self.bit_buffer[..num_bits].zeroize();
+
+        self.bit_buffer.drain(..num_bits);
+        Ok(())
+    }
+
+    /// Absorb the remaining accumulated data and compute the FieldHash of all of it.
+    pub fn finalize(mut self) -> Result<FieldElement, Error> {
+        let num_bits = self.bit_buffer.len();
+        self.absorb(num_bits)?;
+        self.hasher.finalize()
+    }
+}
+
+/// The accumulator may be used with secret witnesses: wipe them when it goes out of scope.
+#[cfg(feature = "zeroize")]
+impl Drop for BoundedDataAccumulator {
+    fn drop(&mut self) {
+        self.bit_buffer.zeroize();
+    }
+}
+
+/// Number of bytes of serialized data converted into bits at once by a `StreamingDataAccumulator`.
+const STREAMING_CHUNK_SIZE: usize = 1 << 12;
+
+/// Accumulator of serializable data or bits which packs them into FieldElements on the fly, feeding
+/// them into a running constant length FieldHash, so that the memory used doesn't grow with the size
+/// of the accumulated data (e.g. multi-MB verification keys), apart from a copy of the serialization
+/// of the data of a single update.
+/// The total number of bits of the data to accumulate must be known in advance, since it determines
+/// the length of the FieldHash: the resulting hash is the same
+/// `DataAccumulator::compute_field_hash_constant_length()` returns for the same sequence of updates.
+pub struct StreamingDataAccumulator {
+    bit_buffer: Vec<bool>,
+    hasher: FieldHash,
+    num_bits: usize,
+    accumulated_bits: usize,
+}
+
+impl StreamingDataAccumulator {
+    /// Initialize an empty accumulator, expecting exactly `num_bits` bits of data
+    /// (e.g. 8 times the length of a byte array).
+    pub fn init(num_bits: usize) -> Self {
+        let num_fes = (num_bits + FIELD_CAPACITY - 1) / FIELD_CAPACITY;
+        Self {
+            bit_buffer: Vec::with_capacity(2 * FIELD_CAPACITY),
+            hasher: FieldHash::init_constant_length(num_fes, None),
+            num_bits,
+            accumulated_bits: 0,
+        }
+    }
+
+    /// Update this struct with data obtained by serializing the input instance `serializable`.
+    /// The serialized data is converted into bits and packed into FieldElements a chunk at a time.
+    pub fn update_streaming<T: CanonicalSerialize>(
+        &mut self,
+        serializable: T,
+    ) -> Result<&mut Self, Error> {
+        #[allow(unused_mut)]
+        let mut buffer = serialize_to_bytes(serializable)?;
+
+        // The serialization is in little endian byte order, while the bits are in big endian order:
+        // the bits of the whole data are reversed, i.e. chunks are processed starting from the last one
+        for chunk in buffer.rchunks(STREAMING_CHUNK_SIZE) {
+            let mut bits = primitives::bytes_to_bits(chunk);
+            bits.reverse();
+            self.update_streaming_with_bits(bits)?;
+        }
+
+        // Wipe the intermediate copy of the data
+        #[cfg(feature = "zeroize")]
+        buffer.zeroize();
+
+        Ok(self)
+    }
+
+    /// Update this struct with 'bits', assumed to be in big endian bit order.
+    pub fn update_streaming_with_bits(&mut self, mut bits: Vec<bool>) -> Result<&mut Self, Error> {
+        if self.accumulated_bits + bits.len() > self.num_bits {
+            Err(format!(
+                "Too much data for the accumulator: {} bits exceed the expected {}",
+                self.accumulated_bits + bits.len(),
+                self.num_bits
+            ))?
+        }
+        self.accumulated_bits += bits.len();
+        self.bit_buffer.append(&mut bits);
+
+        #[cfg(feature = "zeroize")]
+        bits.zeroize();
+
+        // Absorb all the complete FieldElements accumulated so far
+        let num_bits = self.bit_buffer.len() - self.bit_buffer.len() % FIELD_CAPACITY;
+        self.absorb(num_bits)?;
+        Ok(self)
+    }
+
+    /// Deserialize the first `num_bits` accumulated bits into FieldElements and absorb them.
+    fn absorb(&mut self, num_bits: usize) -> Result<(), Error> {
+        let fes: Vec<FieldElement> = self.bit_buffer[..num_bits].to_field_elements()?;
+        fes.into_iter().for_each(|fe| {
+            self.hasher.update(fe);
+        });
+
+        #[cfg(feature = "zeroize")]
+        self.bit_buffer[..num_bits].zeroize();
+
+        self.bit_buffer.drain(..num_bits);
+        Ok(())
+    }
+
+    /// Absorb the remaining accumulated data and compute the FieldHash of all of it.
+    /// Returns an error if less data than expected has been accumulated.
+    pub fn finalize(mut self) -> Result<FieldElement, Error> {
+        if self.accumulated_bits != self.num_bits {
+            Err(format!(
+                "Not enough data for the accumulator: {} bits instead of the expected {}",
+                self.accumulated_bits, self.num_bits
+            ))?
+        }
+        let num_bits = self.bit_buffer.len();
+        self.absorb(num_bits)?;
+        self.hasher.finalize()
+    }
+}
+
+/// The accumulator may be used with secret witnesses: wipe them when it goes out of scope.
+#[cfg(feature = "zeroize")]
+impl Drop for StreamingDataAccumulator {
+    fn drop(&mut self) {
+        self.bit_buffer.zeroize();
+    }
+}
+
+/// Wipe the accumulated data, leaving the accumulator empty.
+/// Note that copies left behind by previous reallocations of the internal buffer
+/// are not wiped: to avoid them, accumulate data of known size in a single `update`.
+#[cfg(feature = "zeroize")]
+impl Zeroize for DataAccumulator {
+    fn zeroize(&mut self) {
+        self.bit_buffer.zeroize();
+    }
+}
+
+/// The accumulator may be used with secret witnesses: wipe them when it goes out of scope.
+#[cfg(feature = "zeroize")]
+impl Drop for DataAccumulator {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
 //--------------------------------------------------------------------------------------------------
 // Serialization utils
 //--------------------------------------------------------------------------------------------------
 
+/// Generates vector of random bytes using the supplied `rng`
+pub fn rand_vec_with_rng<R: Rng + ?Sized>(len: usize, rng: &mut R) -> Vec<u8> {
+    (0..len).map(|_| rng.gen()).collect()
+}
+
+/// Get random (but valid) field element using the supplied `rng`
+pub fn rand_fe_with_rng<R: Rng + ?Sized>(rng: &mut R) -> FieldElement {
+    FieldElement::rand(rng)
+}
+
+/// Get random (but valid) field element bytes using the supplied `rng`
+pub fn rand_fe_bytes_with_rng<R: Rng + ?Sized>(rng: &mut R) -> [u8; FIELD_SIZE] {
+    let mut buffer = [0u8; FIELD_SIZE];
+    CanonicalSerialize::serialize(&FieldElement::rand(rng), &mut buffer[..]).unwrap();
+    buffer
+}
+
+/// Generate random (but valid) array of field elements using the supplied `rng`
+pub fn rand_fe_vec_with_rng<R: Rng + ?Sized>(len: usize, rng: &mut R) -> Vec<FieldElement> {
+    (0..len).map(|_| rand_fe_with_rng(rng)).collect::<Vec<_>>()
+}
+
 /// Generates vector of random bytes
+#[deprecated(note = "Use `rand_vec_with_rng` to be able to supply a (seedable) rng")]
 pub fn rand_vec(len: usize) -> Vec<u8> {
-    let mut rng = rand::thread_rng();
-    (0..len).map(|_| rng.gen()).collect()
+    rand_vec_with_rng(len, &mut rand::thread_rng())
 }
 
 /// Get random (but valid) field element
+#[deprecated(note = "Use `rand_fe_with_rng` to be able to supply a (seedable) rng")]
 pub fn rand_fe() -> FieldElement {
-    FieldElement::rand(&mut rand::thread_rng())
+    rand_fe_with_rng(&mut rand::thread_rng())
 }
 
 /// Get random (but valid) field element bytes
+#[deprecated(note = "Use `rand_fe_bytes_with_rng` to be able to supply a (seedable) rng")]
 pub fn rand_fe_bytes() -> [u8; FIELD_SIZE] {
-    let mut buffer = [0u8; FIELD_SIZE];
-    CanonicalSerialize::serialize(
-        &FieldElement::rand(&mut rand::thread_rng()),
-        &mut buffer[..],
-    )
-    .unwrap();
-    buffer
+    rand_fe_bytes_with_rng(&mut rand::thread_rng())
 }
 
 /// Generate random (but valid) array of field elements
+#[deprecated(note = "Use `rand_fe_vec_with_rng` to be able to supply a (seedable) rng")]
 pub fn rand_fe_vec(len: usize) -> Vec<FieldElement> {
-    (0..len).map(|_| rand_fe()).collect::<Vec<_>>()
+    rand_fe_vec_with_rng(len, &mut rand::thread_rng())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    #[cfg(feature = "zeroize")]
+    #[test]
+    fn test_data_accumulator_zeroize() {
+        let rng = &mut StdRng::seed_from_u64(42);
+        let mut acc = DataAccumulator::init();
+        acc.update(rand_fe_with_rng(rng))
+            .unwrap()
+            .update_with_bits(vec![true; 10])
+            .unwrap();
+        assert!(!acc.get_field_elements().unwrap().is_empty());
+
+        acc.zeroize();
+        assert!(acc.bit_buffer.is_empty());
+        assert!(acc.get_field_elements().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_bounded_data_accumulator() {
+        let rng = &mut StdRng::seed_from_u64(42);
+        let chunks = (0..10)
+            .map(|i| rand_vec_with_rng(i * 100 + 1, rng))
+            .collect::<Vec<_>>();
+        let fe = rand_fe_with_rng(rng);
+
+        let mut acc = DataAccumulator::init();
+        chunks.iter().for_each(|chunk| {
+            acc.update(chunk.as_slice()).unwrap();
+        });
+        acc.update(fe)
+            .unwrap()
+            .update_with_bits(vec![true; 10])
+            .unwrap();
+        let expected_hash = acc.compute_field_hash_variable_length(false).unwrap();
+
+        for &flush_threshold in [1, 2, 7, DEFAULT_ACCUMULATOR_FLUSH_THRESHOLD].iter() {
+            let mut bounded_acc = BoundedDataAccumulator::init(flush_threshold).unwrap();
+            for chunk in chunks.iter() {
+                bounded_acc.update(chunk.as_slice()).unwrap();
+                // Memory is bounded by the size of a chunk of FieldElements
+                assert!(bounded_acc.bit_buffer.len() < flush_threshold * FIELD_CAPACITY);
+            }
+            bounded_acc
+                .update(fe)
+                .unwrap()
+                .update_with_bits(vec![true; 10])
+                .unwrap();
+            assert_eq!(bounded_acc.finalize().unwrap(), expected_hash);
+        }
+
+        assert!(BoundedDataAccumulator::init(0).is_err());
+    }
+
+    #[test]
+    fn test_streaming_data_accumulator() {
+        let rng = &mut StdRng::seed_from_u64(42);
+        // Data spanning multiple chunks, and not a multiple of them
+        let big_data = rand_vec_with_rng(3 * STREAMING_CHUNK_SIZE + 17, rng);
+        let small_data = rand_vec_with_rng(5, rng);
+        let fe = rand_fe_with_rng(rng);
+        let bits = vec![true; 10];
+
+        let expected_hash = DataAccumulator::init()
+            .update(big_data.as_slice())
+            .unwrap()
+            .update(small_data.as_slice())
+            .unwrap()
+            .update(fe)
+            .unwrap()
+            .update_with_bits(bits.clone())
+            .unwrap()
+            .compute_field_hash_constant_length()
+            .unwrap();
+
+        let num_bits = 8 * (big_data.len() + small_data.len())
+            + serialize_to_bits(fe).unwrap().len()
+            + bits.len();
+        let mut acc = StreamingDataAccumulator::init(num_bits);
+        acc.update_streaming(big_data.as_slice())
+            .unwrap()
+            .update_streaming(small_data.as_slice())
+            .unwrap()
+            .update_streaming(fe)
+            .unwrap()
+            .update_streaming_with_bits(bits.clone())
+            .unwrap();
+        // Memory is bounded by the size of a FieldElement
+        assert!(acc.bit_buffer.len() < FIELD_CAPACITY);
+        assert_eq!(acc.finalize().unwrap(), expected_hash);
+
+        // Same hash as DataAccumulator for a single byte array, as in hash_scc
+        let expected_hash = DataAccumulator::init()
+            .update(big_data.as_slice())
+            .unwrap()
+            .compute_field_hash_constant_length()
+            .unwrap();
+        let mut acc = StreamingDataAccumulator::init(8 * big_data.len());
+        acc.update_streaming(big_data.as_slice()).unwrap();
+        assert_eq!(acc.finalize().unwrap(), expected_hash);
+
+        // More or less data than expected
+        let mut acc = StreamingDataAccumulator::init(8 * small_data.len());
+        assert!(acc.update_streaming(big_data.as_slice()).is_err());
+        let mut acc = StreamingDataAccumulator::init(8 * big_data.len());
+        acc.update_streaming(small_data.as_slice()).unwrap();
+        assert!(acc.finalize().is_err());
+    }
+
+    #[test]
+    fn test_new_mt_with_processing_step() {
+        use primitives::FieldBasedMerkleTree;
+
+        let rng = &mut StdRng::seed_from_u64(42);
+        let height = 6;
+        let leaves = rand_fe_vec_with_rng(10, rng);
+
+        let mut expected_mt = new_mt(height).unwrap();
+        leaves
+            .iter()
+            .for_each(|leaf| assert!(add_leaf(&mut expected_mt, leaf)));
+        expected_mt.finalize_in_place().unwrap();
+
+        // The root doesn't depend on the processing step
+        for &processing_step in [2, 4, SC_SUBTREE_PROCESSING_STEP, pow2(height)].iter() {
+            let mut mt = new_mt_with_processing_step(height, processing_step).unwrap();
+            leaves
+                .iter()
+                .for_each(|leaf| assert!(add_leaf(&mut mt, leaf)));
+            mt.finalize_in_place().unwrap();
+            assert_eq!(mt.root(), expected_mt.root());
+        }
+
+        // Invalid processing steps
+        for &processing_step in [0, 3, pow2(height + 1)].iter() {
+            assert!(new_mt_with_processing_step(height, processing_step).is_err());
+        }
+    }
+
+    #[test]
+    fn test_empty_root_at_height() {
+        use primitives::FieldBasedMerkleTree;
+
+        for height in 0..=MAX_EMPTY_ROOT_HEIGHT.min(12) {
+            let mut mt = new_mt(height).unwrap();
+            mt.finalize_in_place().unwrap();
+            assert_eq!(mt.root().unwrap(), empty_root_at_height(height).unwrap());
+            assert_eq!(
+                *empty_root_at_height_const(height),
+                empty_root_at_height(height).unwrap()
+            );
+        }
+
+        assert!(empty_root_at_height(MAX_EMPTY_ROOT_HEIGHT).is_ok());
+        assert!(empty_root_at_height(MAX_EMPTY_ROOT_HEIGHT + 1).is_err());
+    }
+
+    #[test]
+    fn test_rand_with_rng_is_reproducible() {
+        let seed = 42u64;
+        let rng_1 = &mut StdRng::seed_from_u64(seed);
+        let rng_2 = &mut StdRng::seed_from_u64(seed);
+
+        assert_eq!(rand_vec_with_rng(100, rng_1), rand_vec_with_rng(100, rng_2));
+        assert_eq!(rand_fe_with_rng(rng_1), rand_fe_with_rng(rng_2));
+        assert_eq!(rand_fe_bytes_with_rng(rng_1), rand_fe_bytes_with_rng(rng_2));
+        assert_eq!(
+            rand_fe_vec_with_rng(10, rng_1),
+            rand_fe_vec_with_rng(10, rng_2)
+        );
+    }
 }
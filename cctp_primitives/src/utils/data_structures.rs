@@ -1,4 +1,7 @@
-use crate::type_mapping::MC_PK_SIZE;
+use crate::{
+    type_mapping::{Error, FieldElement, MC_PK_SIZE},
+    utils::{commitment_tree::DataAccumulator, packing::SingleFieldElementPacker},
+};
 use algebra::serialize::*;
 
 #[derive(Clone, Debug, Eq, PartialEq, CanonicalSerialize, CanonicalDeserialize)]
@@ -55,10 +58,49 @@ impl CanonicalDeserialize for BackwardTransfer {
     }
 }
 
+/// Certificate fields which are packed together, as the circuit does, into the cert sysdata
+/// hash, alongside the sidechain id, the BT root and the end cumulative SC tx commitment root.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct CertSysData {
+    pub epoch_number: u32,
+    pub quality: u64,
+    pub btr_fee: u64,
+    pub ft_min_amount: u64,
+}
+
+impl CertSysData {
+    pub fn new(epoch_number: u32, quality: u64, btr_fee: u64, ft_min_amount: u64) -> Self {
+        Self {
+            epoch_number,
+            quality,
+            btr_fee,
+            ft_min_amount,
+        }
+    }
+
+    /// Pack the fields into the field elements [epoch_number, quality, fees]:
+    /// epoch_number and quality are kept in separate field elements (for simplicity of treatment
+    /// in the circuit), while btr_fee and ft_min_amount are packed together into a single one.
+    pub fn to_field_elements(&self) -> Result<Vec<FieldElement>, Error> {
+        // Width checked at compile time through CERT_FEES_BITS
+        let fees_field_element = SingleFieldElementPacker::init()
+            .pack(self.btr_fee)?
+            .pack(self.ft_min_amount)?
+            .get_field_element()?;
+
+        Ok(vec![
+            FieldElement::from(self.epoch_number),
+            FieldElement::from(self.quality),
+            fees_field_element,
+        ])
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
     use crate::utils::serialization::serialize_to_buffer;
+    use rand::Rng;
 
     #[test]
     fn test_serialized_size() {
@@ -83,4 +125,36 @@ mod test {
             test_canonical_serialize_deserialize(true, &test_bt);
         }
     }
+
+    #[test]
+    fn test_cert_sys_data_packing() {
+        let rng = &mut rand::thread_rng();
+        let sys_data = CertSysData::new(rng.gen(), rng.gen(), rng.gen(), rng.gen());
+        let fes = sys_data.to_field_elements().unwrap();
+
+        assert_eq!(fes.len(), 3);
+        assert_eq!(fes[0], FieldElement::from(sys_data.epoch_number));
+        assert_eq!(fes[1], FieldElement::from(sys_data.quality));
+        assert_eq!(
+            fes[2],
+            DataAccumulator::init()
+                .update(sys_data.btr_fee)
+                .unwrap()
+                .update(sys_data.ft_min_amount)
+                .unwrap()
+                .get_field_elements()
+                .unwrap()[0]
+        );
+
+        // Fees are position dependent
+        let swapped_fees = CertSysData::new(
+            sys_data.epoch_number,
+            sys_data.quality,
+            sys_data.ft_min_amount,
+            sys_data.btr_fee,
+        );
+        if sys_data.btr_fee != sys_data.ft_min_amount {
+            assert_ne!(swapped_fees.to_field_elements().unwrap()[2], fes[2]);
+        }
+    }
 }
@@ -0,0 +1,240 @@
+use crate::{
+    proving_system::{ZendooProof, ZendooVerifierKey},
+    type_mapping::{Error, FieldElement},
+    utils::serialization::{get_serialization_policy, serialize_to_buffer},
+};
+use algebra::CanonicalSerialize;
+use std::fmt::{Display, Formatter};
+
+/// Number of bytes in each line of `hex_dump()`, and of context around the
+/// first differing byte in a `SerializedDiff`.
+pub const HEX_DUMP_LINE_SIZE: usize = 16;
+
+/// Named part of a serialized artifact, e.g. the proving system byte of a proof.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SerializedComponent {
+    pub name: String,
+    /// Length of the component [bytes]
+    pub len: usize,
+}
+
+impl SerializedComponent {
+    pub fn new(name: &str, len: usize) -> Self {
+        Self {
+            name: name.to_owned(),
+            len,
+        }
+    }
+}
+
+/// Artifacts whose serialization can be split into components, allowing `diff_serialized()`
+/// to report which one of them differs.
+pub trait SerializedLayout {
+    /// Return the components of the (`compressed` or uncompressed) serialization of `self`, in order.
+    fn serialized_layout(&self, compressed: bool) -> Vec<SerializedComponent>;
+}
+
+fn serialized_len<T: CanonicalSerialize>(t: &T, compressed: bool) -> usize {
+    if compressed {
+        t.serialized_size()
+    } else {
+        t.uncompressed_size()
+    }
+}
+
+impl SerializedLayout for FieldElement {
+    fn serialized_layout(&self, compressed: bool) -> Vec<SerializedComponent> {
+        vec![SerializedComponent::new(
+            "field_element",
+            serialized_len(self, compressed),
+        )]
+    }
+}
+
+impl SerializedLayout for ZendooProof {
+    fn serialized_layout(&self, compressed: bool) -> Vec<SerializedComponent> {
+        vec![
+            SerializedComponent::new("proving_system", 1),
+            SerializedComponent::new("proof", serialized_len(self, compressed) - 1),
+        ]
+    }
+}
+
+impl SerializedLayout for ZendooVerifierKey {
+    fn serialized_layout(&self, compressed: bool) -> Vec<SerializedComponent> {
+        vec![
+            SerializedComponent::new("proving_system", 1),
+            SerializedComponent::new("vk", serialized_len(self, compressed) - 1),
+        ]
+    }
+}
+
+/// Format `bytes` as lines of `HEX_DUMP_LINE_SIZE` hex encoded bytes, each one prefixed
+/// by the offset of its first byte.
+pub fn hex_dump_bytes(bytes: &[u8]) -> String {
+    bytes
+        .chunks(HEX_DUMP_LINE_SIZE)
+        .enumerate()
+        .map(|(i, line)| {
+            format!(
+                "{:08x}  {}\n",
+                i * HEX_DUMP_LINE_SIZE,
+                line.iter()
+                    .map(|b| format!("{:02x}", b))
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            )
+        })
+        .collect()
+}
+
+/// Serialize `t`, according to the current `SerializationPolicy`, and return its `hex_dump_bytes()`.
+pub fn hex_dump<T: CanonicalSerialize>(t: &T) -> Result<String, Error> {
+    Ok(hex_dump_bytes(&serialize_to_buffer(t, None)?))
+}
+
+/// First difference between two serialized artifacts.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SerializedDiff {
+    /// Offset of the first differing byte (or the length of the shortest artifact,
+    /// if it's a prefix of the other one)
+    pub offset: usize,
+    pub len_a: usize,
+    pub len_b: usize,
+    /// Name of the component `offset` belongs to and offset within it, if known
+    pub component: Option<(String, usize)>,
+    /// Up to `HEX_DUMP_LINE_SIZE` hex encoded bytes of each artifact, starting at `offset`
+    pub context_a: String,
+    pub context_b: String,
+}
+
+impl Display for SerializedDiff {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Serialized artifacts differ at offset {}", self.offset)?;
+        if let Some((name, offset)) = &self.component {
+            write!(f, " ({} + {})", name, offset)?;
+        }
+        if self.len_a != self.len_b {
+            write!(f, ", lengths {} and {}", self.len_a, self.len_b)?;
+        }
+        write!(f, ": [{}] vs [{}]", self.context_a, self.context_b)
+    }
+}
+
+/// Compare `a` and `b` byte by byte, returning their first difference, if any,
+/// located within `layout`.
+pub fn diff_bytes(a: &[u8], b: &[u8], layout: &[SerializedComponent]) -> Option<SerializedDiff> {
+    let offset = match a
+        .iter()
+        .zip(b.iter())
+        .position(|(byte_a, byte_b)| byte_a != byte_b)
+    {
+        Some(offset) => offset,
+        None if a.len() == b.len() => return None,
+        None => a.len().min(b.len()),
+    };
+
+    let mut component_start = 0;
+    let mut component = None;
+    for c in layout.iter() {
+        if offset < component_start + c.len {
+            component = Some((c.name.clone(), offset - component_start));
+            break;
+        }
+        component_start += c.len;
+    }
+
+    let context = |bytes: &[u8]| {
+        bytes
+            .iter()
+            .skip(offset)
+            .take(HEX_DUMP_LINE_SIZE)
+            .map(|b| format!("{:02x}", b))
+            .collect::<Vec<_>>()
+            .join(" ")
+    };
+
+    Some(SerializedDiff {
+        offset,
+        len_a: a.len(),
+        len_b: b.len(),
+        component,
+        context_a: context(a),
+        context_b: context(b),
+    })
+}
+
+/// Serialize `a` and `b`, according to the current `SerializationPolicy`, and return their
+/// first difference, if any, reporting which component of `a` it belongs to.
+pub fn diff_serialized<T: CanonicalSerialize + SerializedLayout>(
+    a: &T,
+    b: &T,
+) -> Result<Option<SerializedDiff>, Error> {
+    let compressed = get_serialization_policy().compressed;
+    let bytes_a = serialize_to_buffer(a, Some(compressed))?;
+    let bytes_b = serialize_to_buffer(b, Some(compressed))?;
+    Ok(diff_bytes(
+        &bytes_a,
+        &bytes_b,
+        &a.serialized_layout(compressed),
+    ))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{type_mapping::FIELD_SIZE, utils::commitment_tree::rand_fe_vec_with_rng};
+    use algebra::Field;
+
+    #[test]
+    fn hex_dump_test() {
+        let bytes = (0u8..20).collect::<Vec<_>>();
+        assert_eq!(
+            hex_dump_bytes(&bytes),
+            "00000000  00 01 02 03 04 05 06 07 08 09 0a 0b 0c 0d 0e 0f\n\
+             00000010  10 11 12 13\n"
+        );
+        assert!(hex_dump_bytes(&[]).is_empty());
+
+        let dump = hex_dump(&FieldElement::one()).unwrap();
+        assert_eq!(
+            dump.lines().count(),
+            (FIELD_SIZE + HEX_DUMP_LINE_SIZE - 1) / HEX_DUMP_LINE_SIZE
+        );
+    }
+
+    #[test]
+    fn diff_serialized_test() {
+        let fes = rand_fe_vec_with_rng(2, &mut rand::thread_rng());
+        assert_eq!(diff_serialized(&fes[0], &fes[0]).unwrap(), None);
+
+        let diff = diff_serialized(&fes[0], &fes[1]).unwrap().unwrap();
+        assert_eq!(diff.len_a, FIELD_SIZE);
+        assert_eq!(diff.len_b, FIELD_SIZE);
+        assert_eq!(
+            diff.component,
+            Some(("field_element".to_owned(), diff.offset))
+        );
+        assert!(diff.to_string().contains("field_element"));
+
+        // Differences are located within the layout
+        let layout = vec![
+            SerializedComponent::new("header", 1),
+            SerializedComponent::new("body", 4),
+        ];
+        let diff = diff_bytes(&[1, 2, 3, 4, 5], &[1, 2, 3, 0, 5], &layout).unwrap();
+        assert_eq!(diff.offset, 3);
+        assert_eq!(diff.component, Some(("body".to_owned(), 2)));
+        assert_eq!(diff.context_a, "04 05");
+        assert_eq!(diff.context_b, "00 05");
+        let diff = diff_bytes(&[0, 2], &[1, 2], &layout).unwrap();
+        assert_eq!(diff.component, Some(("header".to_owned(), 0)));
+
+        // A prefix differs at the end of the shortest artifact
+        let diff = diff_bytes(&[1, 2, 3, 4, 5, 6], &[1, 2, 3, 4, 5], &layout).unwrap();
+        assert_eq!(diff.offset, 5);
+        assert_eq!((diff.len_a, diff.len_b), (6, 5));
+        assert_eq!(diff.component, None);
+        assert_eq!(diff.context_b, "");
+    }
+}
@@ -0,0 +1,74 @@
+use crate::{
+    type_mapping::{Error, FieldElement},
+    utils::commitment_tree::{hash_vec, DataAccumulator},
+};
+
+/// Size of the hash of a MC block [bytes]
+pub const MC_BLOCK_HASH_SIZE: usize = 32;
+
+/// Number of field elements a reference to a MC block is packed into:
+/// ceil((256 + 32) / 254) = 2 for block hash and height, plus the cumulative commitment tree root.
+pub const MC_BLOCK_REFERENCE_FES: usize = 3;
+
+/// Pack the reference to a MC block, i.e. its hash, its height and the root of the cumulative
+/// sidechain transactions commitment tree at such block, into the field elements hashed by
+/// `hash_mc_block_reference()`, in the same order, e.g. to be used as circuit inputs.
+pub fn pack_mc_block_reference(
+    block_hash: &[u8; MC_BLOCK_HASH_SIZE],
+    height: u32,
+    cum_root: &FieldElement,
+) -> Result<Vec<FieldElement>, Error> {
+    let mut fes = DataAccumulator::init()
+        .update(&block_hash[..])?
+        .update(height)?
+        .get_field_elements()?;
+    debug_assert!(fes.len() == MC_BLOCK_REFERENCE_FES - 1);
+    fes.push(*cum_root);
+    Ok(fes)
+}
+
+/// Compute the canonical hash of the reference to a MC block, i.e. of its hash, its height and
+/// the root of the cumulative sidechain transactions commitment tree at such block.
+pub fn hash_mc_block_reference(
+    block_hash: &[u8; MC_BLOCK_HASH_SIZE],
+    height: u32,
+    cum_root: &FieldElement,
+) -> Result<FieldElement, Error> {
+    hash_vec(pack_mc_block_reference(block_hash, height, cum_root)?)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::utils::commitment_tree::rand_fe_with_rng;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    #[test]
+    fn mc_block_reference_test() {
+        let rng = &mut StdRng::seed_from_u64(42);
+        let block_hash: [u8; MC_BLOCK_HASH_SIZE] = rng.gen();
+        let height: u32 = rng.gen();
+        let cum_root = rand_fe_with_rng(rng);
+
+        let fes = pack_mc_block_reference(&block_hash, height, &cum_root).unwrap();
+        assert_eq!(fes.len(), MC_BLOCK_REFERENCE_FES);
+        assert_eq!(fes[MC_BLOCK_REFERENCE_FES - 1], cum_root);
+
+        // The hash of a fixed reference is pinned by the golden vectors, see testing::golden_vectors()
+        let hash = hash_mc_block_reference(&block_hash, height, &cum_root).unwrap();
+        assert_eq!(hash, hash_vec(fes).unwrap());
+
+        // Any change of the reference changes the hash
+        let mut other_block_hash = block_hash;
+        other_block_hash[0] ^= 1;
+        for other_hash in [
+            hash_mc_block_reference(&other_block_hash, height, &cum_root),
+            hash_mc_block_reference(&block_hash, height.wrapping_add(1), &cum_root),
+            hash_mc_block_reference(&block_hash, height, &rand_fe_with_rng(rng)),
+        ]
+        .iter()
+        {
+            assert_ne!(other_hash.as_ref().unwrap(), &hash);
+        }
+    }
+}
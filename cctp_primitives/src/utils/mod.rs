@@ -1,13 +1,21 @@
-use crate::utils::data_structures::BackwardTransfer;
+use crate::utils::data_structures::{BackwardTransfer, CertSysData};
 use crate::{
-    type_mapping::{Error, FieldElement, GingerMHT, GINGER_MHT_POSEIDON_PARAMETERS},
-    utils::commitment_tree::{hash_vec, DataAccumulator},
+    type_mapping::{Error, FieldElement, GingerMHT},
+    utils::{
+        commitment_tree::{empty_root_at_height, hash_vec, DataAccumulator},
+        poseidon_hash::get_poseidon_hash_constant_length,
+    },
 };
-use primitives::FieldBasedMerkleTree;
+use primitives::{FieldBasedHash, FieldBasedMerkleTree};
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
 
 pub mod commitment_tree;
 pub mod data_structures;
+pub mod debug;
+pub mod mc_block_ref;
 pub mod mht;
+pub mod packing;
 pub mod poseidon_hash;
 pub mod serialization;
 
@@ -15,13 +23,8 @@ fn _get_root_from_field_vec(
     field_vec: Vec<FieldElement>,
     height: usize,
 ) -> Result<FieldElement, Error> {
-    if height > GINGER_MHT_POSEIDON_PARAMETERS.nodes.len() {
-        Err(format!(
-            "Height {} is bigger then GINGER_MHT_POSEIDON_PARAMETERS nodes len {}",
-            height,
-            GINGER_MHT_POSEIDON_PARAMETERS.nodes.len()
-        ))?
-    }
+    // Also checks that height is supported
+    let empty_root = empty_root_at_height(height)?;
 
     if !field_vec.is_empty() {
         let mut mt = GingerMHT::init(height, 2usize.pow(height as u32))?;
@@ -32,20 +35,58 @@ fn _get_root_from_field_vec(
         mt.root()
             .ok_or_else(|| Error::from("Failed to compute Merkle Tree root"))
     } else {
-        Ok(GINGER_MHT_POSEIDON_PARAMETERS.nodes[height])
+        Ok(empty_root)
+    }
+}
+
+/// Run `a` and `b`, concurrently if the `parallel` feature is enabled, returning both results.
+pub(crate) fn join_results<A, B, RA, RB>(a: A, b: B) -> (Result<RA, Error>, Result<RB, Error>)
+where
+    A: FnOnce() -> Result<RA, Error> + Send,
+    B: FnOnce() -> Result<RB, Error> + Send,
+    RA: Send,
+    RB: Send,
+{
+    #[cfg(feature = "parallel")]
+    {
+        // Error isn't Send: errors are sent back as their messages
+        let (result_a, result_b) = rayon::join(
+            || a().map_err(|e| e.to_string()),
+            || b().map_err(|e| e.to_string()),
+        );
+        (result_a.map_err(Error::from), result_b.map_err(Error::from))
+    }
+
+    #[cfg(not(feature = "parallel"))]
+    {
+        (a(), b())
     }
 }
 
-/// Get the Merkle Root of a Binary Merkle Tree of height 12 built from the Backward Transfer list
+fn _get_bt_leaf(bt: &BackwardTransfer) -> Result<FieldElement, Error> {
+    let bt_fes = DataAccumulator::init().update(bt)?.get_field_elements()?;
+    assert_eq!(bt_fes.len(), 1);
+    Ok(bt_fes[0])
+}
+
+#[cfg(feature = "parallel")]
+fn _get_bt_leaves(bt_list: &[BackwardTransfer]) -> Result<Vec<FieldElement>, Error> {
+    Ok(bt_list
+        .par_iter()
+        .map(|bt| _get_bt_leaf(bt).map_err(|e| e.to_string()))
+        .collect::<Result<Vec<_>, _>>()?)
+}
+
+#[cfg(not(feature = "parallel"))]
+fn _get_bt_leaves(bt_list: &[BackwardTransfer]) -> Result<Vec<FieldElement>, Error> {
+    bt_list.iter().map(_get_bt_leaf).collect()
+}
+
+/// Get the Merkle Root of a Binary Merkle Tree of height 12 built from the Backward Transfer list.
+/// If the `parallel` feature is enabled, the leaves are computed concurrently.
 pub fn get_bt_merkle_root(bt_list: Option<&[BackwardTransfer]>) -> Result<FieldElement, Error> {
     let leaves = if let Some(bt_list) = bt_list {
-        let mut leaves = Vec::with_capacity(bt_list.len());
-        for bt in bt_list.iter() {
-            let bt_fes = DataAccumulator::init().update(bt)?.get_field_elements()?;
-            assert_eq!(bt_fes.len(), 1);
-            leaves.push(bt_fes[0]);
-        }
-        leaves
+        _get_bt_leaves(bt_list)?
     } else {
         vec![]
     };
@@ -62,6 +103,23 @@ pub fn get_cert_data_hash(
     end_cumulative_sc_tx_commitment_tree_root: &FieldElement,
     btr_fee: u64,
     ft_min_amount: u64,
+) -> Result<FieldElement, Error> {
+    get_cert_data_hash_from_sys_data(
+        sc_id,
+        &CertSysData::new(epoch_number, quality, btr_fee, ft_min_amount),
+        bt_list,
+        custom_fields,
+        end_cumulative_sc_tx_commitment_tree_root,
+    )
+}
+
+/// Same as `get_cert_data_hash`, with epoch number, quality and fees taken from `sys_data`
+pub fn get_cert_data_hash_from_sys_data(
+    sc_id: &FieldElement,
+    sys_data: &CertSysData,
+    bt_list: Option<&[BackwardTransfer]>,
+    custom_fields: Option<Vec<&FieldElement>>, //aka proof_data - includes custom_field_elements and bit_vectors merkle roots
+    end_cumulative_sc_tx_commitment_tree_root: &FieldElement,
 ) -> Result<FieldElement, Error> {
     // Compute bt_list merkle root
     let bt_root = get_bt_merkle_root(bt_list)?;
@@ -74,15 +132,12 @@ pub fn get_cert_data_hash(
         custom_fields_hash = Some(hash_vec(custom_fes)?)
     }
 
-    get_cert_data_hash_from_bt_root_and_custom_fields_hash(
+    _get_cert_data_hash_from_parts(
         sc_id,
-        epoch_number,
-        quality,
+        sys_data,
         bt_root,
         custom_fields_hash,
         end_cumulative_sc_tx_commitment_tree_root,
-        btr_fee,
-        ft_min_amount,
     )
 }
 
@@ -96,27 +151,127 @@ pub fn get_cert_data_hash_from_bt_root_and_custom_fields_hash(
     btr_fee: u64,
     ft_min_amount: u64,
 ) -> Result<FieldElement, Error> {
-    // Pack btr_fee and ft_min_amount into a single field element
-    let fees_field_elements = DataAccumulator::init()
-        .update(btr_fee)?
-        .update(ft_min_amount)?
-        .get_field_elements()?;
+    _get_cert_data_hash_from_parts(
+        sc_id,
+        &CertSysData::new(epoch_number, quality, btr_fee, ft_min_amount),
+        bt_root,
+        custom_fields_hash,
+        end_cumulative_sc_tx_commitment_tree_root,
+    )
+}
 
-    assert_eq!(fees_field_elements.len(), 1);
+/// Builder computing the same hash as `get_cert_data_hash`, whose fields are set by name,
+/// so that they can be supplied in the order the SDK serializes them:
+/// sc_id, epoch_number, bt_list, quality, end_cumulative_sc_tx_commitment_tree_root,
+/// ft_min_amount, btr_fee, custom_fields.
+/// `sc_id` and `end_cumulative_sc_tx_commitment_tree_root` must always be set, while the
+/// other fields default to 0 (or to an empty/absent list) if not set.
+#[derive(Clone, Debug, Default)]
+pub struct CertificateDataHashBuilder {
+    sc_id: Option<FieldElement>,
+    epoch_number: u32,
+    bt_list: Vec<BackwardTransfer>,
+    quality: u64,
+    end_cumulative_sc_tx_commitment_tree_root: Option<FieldElement>,
+    ft_min_amount: u64,
+    btr_fee: u64,
+    custom_fields: Option<Vec<FieldElement>>,
+}
+
+impl CertificateDataHashBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
 
-    // Pack epoch_number and quality into separate field elements (for simplicity of treatment in
-    // the circuit)
-    let epoch_number_fe = FieldElement::from(epoch_number);
-    let quality_fe = FieldElement::from(quality);
+    pub fn sc_id(mut self, sc_id: FieldElement) -> Self {
+        self.sc_id = Some(sc_id);
+        self
+    }
+
+    pub fn epoch_number(mut self, epoch_number: u32) -> Self {
+        self.epoch_number = epoch_number;
+        self
+    }
+
+    pub fn bt_list(mut self, bt_list: Vec<BackwardTransfer>) -> Self {
+        self.bt_list = bt_list;
+        self
+    }
+
+    pub fn quality(mut self, quality: u64) -> Self {
+        self.quality = quality;
+        self
+    }
+
+    pub fn end_cumulative_sc_tx_commitment_tree_root(mut self, root: FieldElement) -> Self {
+        self.end_cumulative_sc_tx_commitment_tree_root = Some(root);
+        self
+    }
+
+    pub fn ft_min_amount(mut self, ft_min_amount: u64) -> Self {
+        self.ft_min_amount = ft_min_amount;
+        self
+    }
+
+    pub fn btr_fee(mut self, btr_fee: u64) -> Self {
+        self.btr_fee = btr_fee;
+        self
+    }
+
+    /// Set the custom fields (aka proof_data - includes custom_field_elements and
+    /// bit_vectors merkle roots). If never called, the certificate has no custom fields.
+    pub fn custom_fields(mut self, custom_fields: Vec<FieldElement>) -> Self {
+        self.custom_fields = Some(custom_fields);
+        self
+    }
+
+    /// Compute the certificate data hash, returning an error if `sc_id` or
+    /// `end_cumulative_sc_tx_commitment_tree_root` haven't been set.
+    pub fn build(&self) -> Result<FieldElement, Error> {
+        let sc_id = self
+            .sc_id
+            .as_ref()
+            .ok_or("Missing sc_id for certificate data hash")?;
+        let end_cumulative_sc_tx_commitment_tree_root = self
+            .end_cumulative_sc_tx_commitment_tree_root
+            .as_ref()
+            .ok_or("Missing end_cumulative_sc_tx_commitment_tree_root for certificate data hash")?;
+
+        get_cert_data_hash_from_sys_data(
+            sc_id,
+            &CertSysData::new(
+                self.epoch_number,
+                self.quality,
+                self.btr_fee,
+                self.ft_min_amount,
+            ),
+            Some(self.bt_list.as_slice()),
+            self.custom_fields
+                .as_ref()
+                .map(|custom_fields| custom_fields.iter().collect()),
+            end_cumulative_sc_tx_commitment_tree_root,
+        )
+    }
+}
+
+fn _get_cert_data_hash_from_parts(
+    sc_id: &FieldElement,
+    sys_data: &CertSysData,
+    bt_root: FieldElement,
+    custom_fields_hash: Option<FieldElement>,
+    end_cumulative_sc_tx_commitment_tree_root: &FieldElement,
+) -> Result<FieldElement, Error> {
+    // Pack epoch_number, quality and fees as the circuit does
+    let sys_data_fes = sys_data.to_field_elements()?;
 
     // Compute cert sysdata hash
     let cert_sysdata_hash = hash_vec(vec![
         *sc_id,
-        epoch_number_fe,
+        sys_data_fes[0],
         bt_root,
-        quality_fe,
+        sys_data_fes[1],
         *end_cumulative_sc_tx_commitment_tree_root,
-        fees_field_elements[0],
+        sys_data_fes[2],
     ])?;
 
     // Final field elements to hash
@@ -135,8 +290,192 @@ pub fn get_cert_data_hash_from_bt_root_and_custom_fields_hash(
 }
 
 pub fn compute_sc_id(tx_hash: &[u8; 32], pos: u32) -> Result<FieldElement, Error> {
-    DataAccumulator::init()
+    compute_sc_id_with_network_tag(tx_hash, pos, None)
+}
+
+/// Same as `compute_sc_id`, with the hash personalized with `network_tag`, if any, so that
+/// identical transactions on different networks (e.g. mainnet, testnet and regtest) yield
+/// different sidechain ids. Without `network_tag`, the id is the one `compute_sc_id` returns.
+pub fn compute_sc_id_with_network_tag(
+    tx_hash: &[u8; 32],
+    pos: u32,
+    network_tag: Option<&FieldElement>,
+) -> Result<FieldElement, Error> {
+    let fes = DataAccumulator::init()
         .update(&tx_hash[..])?
         .update(pos)?
-        .compute_field_hash_constant_length()
+        .get_field_elements()?;
+
+    let mut hasher = get_poseidon_hash_constant_length(fes.len(), network_tag.map(|tag| vec![tag]));
+    fes.into_iter().for_each(|fe| {
+        hasher.update(fe);
+    });
+    hasher.finalize()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::type_mapping::MC_PK_SIZE;
+    use crate::utils::commitment_tree::{rand_fe_vec_with_rng, rand_fe_with_rng};
+    use rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    fn compute_sc_id_with_network_tag_test() {
+        let rng = &mut StdRng::seed_from_u64(42);
+        let tx_hash = [7u8; 32];
+        let network_tags = rand_fe_vec_with_rng(2, rng);
+
+        let sc_id = compute_sc_id(&tx_hash, 1).unwrap();
+        assert_eq!(
+            compute_sc_id_with_network_tag(&tx_hash, 1, None).unwrap(),
+            sc_id
+        );
+        assert_eq!(
+            sc_id,
+            DataAccumulator::init()
+                .update(&tx_hash[..])
+                .unwrap()
+                .update(1u32)
+                .unwrap()
+                .compute_field_hash_constant_length()
+                .unwrap()
+        );
+
+        // Different networks, different ids
+        let tagged_sc_ids = network_tags
+            .iter()
+            .map(|tag| compute_sc_id_with_network_tag(&tx_hash, 1, Some(tag)).unwrap())
+            .collect::<Vec<_>>();
+        assert_ne!(tagged_sc_ids[0], sc_id);
+        assert_ne!(tagged_sc_ids[0], tagged_sc_ids[1]);
+        assert_eq!(
+            compute_sc_id_with_network_tag(&tx_hash, 1, Some(&network_tags[0])).unwrap(),
+            tagged_sc_ids[0]
+        );
+        assert_ne!(
+            compute_sc_id_with_network_tag(&tx_hash, 2, Some(&network_tags[0])).unwrap(),
+            tagged_sc_ids[0]
+        );
+    }
+
+    #[test]
+    fn get_bt_merkle_root_test() {
+        let bt_list = (0..10u8)
+            .map(|i| BackwardTransfer {
+                pk_dest: [i; MC_PK_SIZE],
+                amount: i as u64,
+            })
+            .collect::<Vec<_>>();
+
+        // Leaves are in the order of the Backward Transfers, whether computed concurrently or not
+        let mut mt = GingerMHT::init(12, 2usize.pow(12)).unwrap();
+        for bt in bt_list.iter() {
+            let bt_fes = DataAccumulator::init()
+                .update(bt)
+                .unwrap()
+                .get_field_elements()
+                .unwrap();
+            mt.append(bt_fes[0]).unwrap();
+        }
+        mt.finalize_in_place().unwrap();
+        assert_eq!(
+            get_bt_merkle_root(Some(bt_list.as_slice())).unwrap(),
+            mt.root().unwrap()
+        );
+
+        assert_eq!(
+            get_bt_merkle_root(None).unwrap(),
+            empty_root_at_height(12).unwrap()
+        );
+        assert_eq!(
+            get_bt_merkle_root(Some(&[])).unwrap(),
+            empty_root_at_height(12).unwrap()
+        );
+
+        // Results and errors of join_results are kept apart
+        let (a, b) = join_results(|| Ok(1), || -> Result<u8, Error> { Err("error")? });
+        assert_eq!(a.unwrap(), 1);
+        assert_eq!(b.unwrap_err().to_string(), "error");
+    }
+
+    #[test]
+    fn cert_data_hash_builder_test() {
+        let rng = &mut StdRng::seed_from_u64(42);
+        let sc_id = rand_fe_with_rng(rng);
+        let end_cum_comm_tree_root = rand_fe_with_rng(rng);
+        let custom_fields = rand_fe_vec_with_rng(3, rng);
+        let bt_list = vec![
+            BackwardTransfer {
+                pk_dest: [1u8; MC_PK_SIZE],
+                amount: 10,
+            },
+            BackwardTransfer {
+                pk_dest: [2u8; MC_PK_SIZE],
+                amount: 20,
+            },
+        ];
+
+        // Fields set in SDK order
+        let builder = CertificateDataHashBuilder::new()
+            .sc_id(sc_id)
+            .epoch_number(5)
+            .bt_list(bt_list.clone())
+            .quality(7)
+            .end_cumulative_sc_tx_commitment_tree_root(end_cum_comm_tree_root)
+            .ft_min_amount(11)
+            .btr_fee(13);
+        assert_eq!(
+            builder.build().unwrap(),
+            get_cert_data_hash(
+                &sc_id,
+                5,
+                7,
+                Some(bt_list.as_slice()),
+                None,
+                &end_cum_comm_tree_root,
+                13,
+                11
+            )
+            .unwrap()
+        );
+        assert_eq!(
+            builder
+                .clone()
+                .custom_fields(custom_fields.clone())
+                .build()
+                .unwrap(),
+            get_cert_data_hash(
+                &sc_id,
+                5,
+                7,
+                Some(bt_list.as_slice()),
+                Some(custom_fields.iter().collect()),
+                &end_cum_comm_tree_root,
+                13,
+                11
+            )
+            .unwrap()
+        );
+
+        // Unset fields are defaulted
+        assert_eq!(
+            CertificateDataHashBuilder::new()
+                .sc_id(sc_id)
+                .end_cumulative_sc_tx_commitment_tree_root(end_cum_comm_tree_root)
+                .build()
+                .unwrap(),
+            get_cert_data_hash(&sc_id, 0, 0, None, None, &end_cum_comm_tree_root, 0, 0).unwrap()
+        );
+
+        // Mandatory fields
+        assert!(CertificateDataHashBuilder::new()
+            .end_cumulative_sc_tx_commitment_tree_root(end_cum_comm_tree_root)
+            .build()
+            .is_err());
+        assert!(CertificateDataHashBuilder::new()
+            .sc_id(sc_id)
+            .build()
+            .is_err());
+    }
 }
@@ -0,0 +1,169 @@
+use crate::{
+    type_mapping::{Error, FieldElement, FIELD_CAPACITY, MC_PK_SIZE},
+    utils::commitment_tree::DataAccumulator,
+};
+use algebra::CanonicalSerialize;
+
+/// Data whose bit-length, once serialized to be packed into field elements, is fixed.
+pub trait PackedWidth {
+    /// Bit-length of the serialized data
+    const BITS: usize;
+}
+
+macro_rules! impl_packed_width_for_scalar {
+    ($($t:ty),*) => {
+        $(
+            impl PackedWidth for $t {
+                const BITS: usize = std::mem::size_of::<$t>() * 8;
+            }
+        )*
+    };
+}
+
+impl_packed_width_for_scalar!(bool, u8, u32, u64);
+
+impl<const N: usize> PackedWidth for [u8; N] {
+    const BITS: usize = N * 8;
+}
+
+/// Return true if data of `bits` bit-length can be packed into a single field element,
+/// i.e. if it doesn't exceed `FIELD_CAPACITY`.
+pub const fn fits_into_single_fe(bits: usize) -> bool {
+    bits <= FIELD_CAPACITY
+}
+
+/// Bit-length of btr_fee and ft_min_amount of a certificate, packed into a single field element
+pub const CERT_FEES_BITS: usize = 2 * <u64 as PackedWidth>::BITS;
+
+/// Bit-length of amount and public key hash of a CSW, packed into a single field element
+pub const CSW_AMOUNT_PK_HASH_BITS: usize =
+    <u64 as PackedWidth>::BITS + <[u8; MC_PK_SIZE] as PackedWidth>::BITS;
+
+// Compile-time checks of the widths of the data packed into single field elements
+const _: [(); 0] = [(); !fits_into_single_fe(CERT_FEES_BITS) as usize];
+const _: [(); 0] = [(); !fits_into_single_fe(CSW_AMOUNT_PK_HASH_BITS) as usize];
+
+/// Packer of data into a single field element, checking at each update that the overall
+/// bit-length of the packed data doesn't exceed `FIELD_CAPACITY`, instead of silently
+/// spilling it over multiple field elements as a `DataAccumulator` does.
+#[derive(Clone)]
+pub struct SingleFieldElementPacker {
+    accumulator: DataAccumulator,
+    bits: usize,
+}
+
+impl Default for SingleFieldElementPacker {
+    fn default() -> Self {
+        Self::init()
+    }
+}
+
+impl SingleFieldElementPacker {
+    /// Initialize an empty packer.
+    pub fn init() -> Self {
+        Self {
+            accumulator: DataAccumulator::init(),
+            bits: 0,
+        }
+    }
+
+    /// Return the bit-length of the data packed so far
+    pub fn bits(&self) -> usize {
+        self.bits
+    }
+
+    /// Account for `bits` more bits, returning an error if they don't fit anymore.
+    fn reserve(&mut self, bits: usize) -> Result<(), Error> {
+        let total_bits = self.bits + bits;
+        if !fits_into_single_fe(total_bits) {
+            Err(format!(
+                "Packed data is {} bits long, more than the field capacity of {} bits",
+                total_bits, FIELD_CAPACITY
+            ))?
+        }
+        self.bits = total_bits;
+        Ok(())
+    }
+
+    /// Pack `data`, whose bit-length is `T::BITS`.
+    pub fn pack<T: PackedWidth + CanonicalSerialize>(
+        &mut self,
+        data: T,
+    ) -> Result<&mut Self, Error> {
+        self.reserve(T::BITS)?;
+        self.accumulator.update(data)?;
+        Ok(self)
+    }
+
+    /// Pack `bytes`, whose bit-length is `<[u8; N]>::BITS`.
+    pub fn pack_array<const N: usize>(&mut self, bytes: &[u8; N]) -> Result<&mut Self, Error> {
+        self.reserve(<[u8; N] as PackedWidth>::BITS)?;
+        self.accumulator.update(&bytes[..])?;
+        Ok(self)
+    }
+
+    /// Pack `bytes`, whose bit-length is known only at runtime.
+    pub fn pack_bytes(&mut self, bytes: &[u8]) -> Result<&mut Self, Error> {
+        self.reserve(bytes.len() * 8)?;
+        self.accumulator.update(bytes)?;
+        Ok(self)
+    }
+
+    /// Return the field element the data has been packed into.
+    pub fn get_field_element(&self) -> Result<FieldElement, Error> {
+        let fes = self.accumulator.get_field_elements()?;
+        if fes.len() != 1 {
+            Err(format!(
+                "Data packed into {} field elements instead of 1",
+                fes.len()
+            ))?
+        }
+        Ok(fes[0])
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn single_fe_packer_test() {
+        assert_eq!(<bool as PackedWidth>::BITS, 8);
+        assert_eq!(<u64 as PackedWidth>::BITS, 64);
+        assert_eq!(<[u8; MC_PK_SIZE] as PackedWidth>::BITS, MC_PK_SIZE * 8);
+
+        // Same field element as the DataAccumulator one
+        let pk_hash = [7u8; MC_PK_SIZE];
+        let mut packer = SingleFieldElementPacker::init();
+        packer.pack(10u64).unwrap().pack_array(&pk_hash).unwrap();
+        assert_eq!(packer.bits(), CSW_AMOUNT_PK_HASH_BITS);
+        assert_eq!(
+            packer.get_field_element().unwrap(),
+            DataAccumulator::init()
+                .update(10u64)
+                .unwrap()
+                .update(&pk_hash[..])
+                .unwrap()
+                .get_field_elements()
+                .unwrap()[0]
+        );
+
+        // Data exceeding the field capacity is rejected, leaving the packer untouched
+        let max_bytes = vec![0xFFu8; FIELD_CAPACITY / 8 - 8];
+        let mut packer = SingleFieldElementPacker::init();
+        packer
+            .pack(u64::MAX)
+            .unwrap()
+            .pack_bytes(&max_bytes)
+            .unwrap();
+        assert!(packer.pack(true).is_err());
+        assert!(packer.pack_bytes(&[0u8]).is_err());
+        assert_eq!(packer.bits(), 64 + max_bytes.len() * 8);
+        assert!(packer.get_field_element().is_ok());
+
+        // Nothing packed
+        assert!(SingleFieldElementPacker::init()
+            .get_field_element()
+            .is_err());
+    }
+}
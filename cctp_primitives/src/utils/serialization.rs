@@ -1,17 +1,106 @@
+use crate::type_mapping::Error;
 use algebra::{serialize::*, SemanticallyValid};
+use lazy_static::lazy_static;
+#[cfg(feature = "parallel")]
+use rayon::{prelude::*, ThreadPool, ThreadPoolBuilder};
 use std::{
     fs::File,
     io::{BufReader, BufWriter, Cursor, Error as IoError, ErrorKind, Read},
-    path::Path,
+    panic::{catch_unwind, AssertUnwindSafe},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, RwLock,
+    },
+    thread::JoinHandle,
 };
 
+/// Process-wide defaults for the `semantic_checks` and `compressed` flags, used by the
+/// (de)serialization functions of this module whenever the corresponding flag is None,
+/// together with the strictness of the deserialization of data coming from outside.
+/// It's set by `cctp_init()` according to the `CctpConfig`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct SerializationPolicy {
+    pub semantic_checks: bool,
+    pub compressed: bool,
+    /// Whether data coming from outside (i.e. deserialized by `deserialize_from_buffer_bounded()`,
+    /// `deserialize_many()` or `ProvingSystem::deserialize_untrusted()`) must pass the strict checks too
+    pub strict_untrusted: bool,
+}
+
+impl Default for SerializationPolicy {
+    /// No semantic checks and uncompressed representation (the historical defaults), with strict
+    /// checks for data coming from outside.
+    fn default() -> Self {
+        Self {
+            semantic_checks: false,
+            compressed: false,
+            strict_untrusted: true,
+        }
+    }
+}
+
+lazy_static! {
+    static ref SERIALIZATION_POLICY: RwLock<Option<SerializationPolicy>> = RwLock::new(None);
+}
+
+fn _set_policy_inner(
+    slot: &RwLock<Option<SerializationPolicy>>,
+    policy: SerializationPolicy,
+) -> Result<(), Error> {
+    let mut policy_guard = slot
+        .write()
+        .map_err(|_| "Failed to acquire lock for SERIALIZATION_POLICY")?;
+    // Setting the same policy again is harmless
+    if matches!(*policy_guard, Some(current) if current != policy) {
+        Err("Serialization policy has already been set to a different value")?
+    }
+    policy_guard.replace(policy);
+    Ok(())
+}
+
+/// Set the process-wide `SerializationPolicy`. It's meant to be called once, at initialization,
+/// before any (de)serialization takes place: returns an error if a different policy has
+/// already been set.
+pub fn set_serialization_policy(policy: SerializationPolicy) -> Result<(), Error> {
+    _set_policy_inner(&SERIALIZATION_POLICY, policy)
+}
+
+/// Unset the process-wide `SerializationPolicy`, restoring the default one.
+pub(crate) fn unset_serialization_policy() -> Result<(), Error> {
+    SERIALIZATION_POLICY
+        .write()
+        .map_err(|_| "Failed to acquire lock for SERIALIZATION_POLICY")?
+        .take();
+    Ok(())
+}
+
+/// Return the process-wide `SerializationPolicy`, or the default one if it has not been set.
+pub fn get_serialization_policy() -> SerializationPolicy {
+    SERIALIZATION_POLICY
+        .read()
+        .ok()
+        .and_then(|policy_guard| *policy_guard)
+        .unwrap_or_default()
+}
+
+/// Checks to be passed by data coming from outside, on top of the semantic ones, unless strict checks
+/// for untrusted data have been disabled in the `SerializationPolicy` (e.g. `ProvingSystem::Undefined`,
+/// which no proof or vk can belong to, is rejected).
+pub trait StrictlyValid {
+    fn is_strictly_valid(&self) -> bool {
+        true
+    }
+}
+
 fn _deserialize_inner<R: Read, T: CanonicalDeserialize + SemanticallyValid>(
     reader: R,
     semantic_checks: Option<bool>,
     compressed: Option<bool>,
 ) -> Result<T, SerializationError> {
-    let semantic_checks = semantic_checks.unwrap_or(false);
-    let compressed = compressed.unwrap_or(false);
+    let policy = get_serialization_policy();
+    let semantic_checks = semantic_checks.unwrap_or(policy.semantic_checks);
+    let compressed = compressed.unwrap_or(policy.compressed);
 
     let t = if compressed {
         T::deserialize_unchecked(reader)
@@ -31,6 +120,7 @@ fn _deserialize_inner<R: Read, T: CanonicalDeserialize + SemanticallyValid>(
 /// `compressed` can be optional, due to some types being uncompressable;
 /// `semantic_checks` can be optional, due to some types having no checks to be performed,
 /// or trivial checks already performed a priori during serialization.
+/// When a flag is None, its value is taken from the `SerializationPolicy`.
 pub fn deserialize_from_buffer<T: CanonicalDeserialize + SemanticallyValid>(
     buffer: &[u8],
     semantic_checks: Option<bool>,
@@ -71,14 +161,215 @@ pub fn deserialize_from_buffer_strict<T: CanonicalDeserialize + SemanticallyVali
     Ok(t)
 }
 
+/// Same as `deserialize_from_buffer_strict`, followed by the strict checks for untrusted data,
+/// if enabled in the `SerializationPolicy`.
+fn _deserialize_untrusted_inner<T>(
+    buffer: &[u8],
+    semantic_checks: Option<bool>,
+    compressed: Option<bool>,
+) -> Result<T, SerializationError>
+where
+    T: CanonicalDeserialize + SemanticallyValid + StrictlyValid,
+{
+    let t: T = deserialize_from_buffer_strict(buffer, semantic_checks, compressed)?;
+
+    if get_serialization_policy().strict_untrusted && !t.is_strictly_valid() {
+        return Err(SerializationError::InvalidData);
+    }
+
+    Ok(t)
+}
+
+/// Maximum size [bytes] accepted for a serialized `ZendooProof` coming from the network.
+pub const MAX_ZENDOO_PROOF_SIZE: usize = 9 * 1024;
+
+/// Maximum size [bytes] accepted for a serialized `ZendooVerifierKey` coming from the network.
+pub const MAX_ZENDOO_VK_SIZE: usize = 9 * 1024;
+
+/// Maximum size [bytes] accepted for a serialized commitment tree proof (existence,
+/// absence or leaf proof) coming from the network.
+pub const MAX_COMMITMENT_PROOF_SIZE: usize = 4 * 1024;
+
+/// Stack size of the threads of the pools built by `build_deserialization_pool()`.
+/// As each level of nesting consumes at least one byte of input, bounding the length of the
+/// input bounds the recursion depth of the nested `CanonicalDeserialize` impls, too: this
+/// stack size is generous enough for the maximum sizes above.
+#[cfg(feature = "parallel")]
+const BOUNDED_DESERIALIZATION_STACK_SIZE: usize = 16 * 1024 * 1024;
+
+/// Check that `buffer` is not longer than `max_len`.
+fn _check_buffer_len(buffer: &[u8], max_len: usize) -> Result<(), SerializationError> {
+    if buffer.len() > max_len {
+        return Err(SerializationError::IoError(IoError::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "Oversized data. Buff len is {} but max allowed is {}",
+                buffer.len(),
+                max_len
+            ),
+        )));
+    }
+    Ok(())
+}
+
+/// Run `deserialize` on `buffer`, on the calling thread, after checking that `buffer` is not longer
+/// than `max_len`. A panic during deserialization is reported as an error.
+/// The types deserialized from the network aren't recursive, so the nesting depth of their
+/// `CanonicalDeserialize` impls is fixed by the types themselves, while the length check bounds
+/// the number (and the size) of the elements of their collections.
+fn _deserialize_bounded_inner<T, F>(
+    buffer: &[u8],
+    max_len: usize,
+    deserialize: F,
+) -> Result<T, SerializationError>
+where
+    F: FnOnce(&[u8]) -> Result<T, SerializationError>,
+{
+    _check_buffer_len(buffer, max_len)?;
+
+    catch_unwind(AssertUnwindSafe(|| deserialize(buffer)))
+        .map_err(|_| SerializationError::InvalidData)?
+}
+
+/// Same as `deserialize_from_buffer_strict`, but meant for data coming from the network:
+/// `buffer` is rejected if longer than `max_len`, panics during deserialization are
+/// reported as errors, and the strict checks for untrusted data are performed (see `StrictlyValid`).
+pub fn deserialize_from_buffer_bounded<T>(
+    buffer: &[u8],
+    max_len: usize,
+    semantic_checks: Option<bool>,
+    compressed: Option<bool>,
+) -> Result<T, SerializationError>
+where
+    T: CanonicalDeserialize + SemanticallyValid + StrictlyValid,
+{
+    _deserialize_bounded_inner(buffer, max_len, |buffer| {
+        _deserialize_untrusted_inner(buffer, semantic_checks, compressed)
+    })
+}
+
+/// Same as `deserialize_from_buffer_bounded`, for types without semantic checks
+/// (e.g. commitment tree proofs). Trailing bytes in `buffer` are rejected.
+pub fn deserialize_canonical_bounded<T>(
+    buffer: &[u8],
+    max_len: usize,
+) -> Result<T, SerializationError>
+where
+    T: CanonicalDeserialize,
+{
+    _deserialize_bounded_inner(buffer, max_len, |buffer| {
+        let buff_len = buffer.len() as u64;
+        let mut buffer = Cursor::new(buffer);
+        let t = T::deserialize(&mut buffer)?;
+
+        if buffer.position() != buff_len {
+            return Err(SerializationError::IoError(IoError::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "Oversized data. Read {} but buff len is {}",
+                    buffer.position(),
+                    buff_len
+                ),
+            )));
+        }
+
+        Ok(t)
+    })
+}
+
+/// Build a rayon pool suitable for `deserialize_many`, i.e. with `num_threads` threads (or as
+/// many as the available CPUs, if 0), each one with a stack of `BOUNDED_DESERIALIZATION_STACK_SIZE` bytes.
+/// Available with the `parallel` feature.
+#[cfg(feature = "parallel")]
+pub fn build_deserialization_pool(num_threads: usize) -> Result<ThreadPool, Error> {
+    Ok(ThreadPoolBuilder::new()
+        .num_threads(num_threads)
+        .stack_size(BOUNDED_DESERIALIZATION_STACK_SIZE)
+        .thread_name(|i| format!("cctp-deserialization-{}", i))
+        .build()?)
+}
+
+#[cfg(feature = "parallel")]
+lazy_static! {
+    static ref DESERIALIZATION_POOL: RwLock<Option<Arc<ThreadPool>>> = RwLock::new(None);
+}
+
+/// Set (or unset) the pool shared by the callers of `deserialize_many`, e.g. to bound its threads.
+#[cfg(feature = "parallel")]
+pub(crate) fn set_deserialization_pool(pool: Option<ThreadPool>) -> Result<(), Error> {
+    *DESERIALIZATION_POOL
+        .write()
+        .map_err(|_| "Failed to acquire lock for DESERIALIZATION_POOL")? = pool.map(Arc::new);
+    Ok(())
+}
+
+/// Return the pool shared by the callers of `deserialize_many`, building one with as many threads
+/// as the available CPUs if it has not been set.
+#[cfg(feature = "parallel")]
+fn get_deserialization_pool() -> Arc<ThreadPool> {
+    if let Some(pool) = DESERIALIZATION_POOL.read().unwrap().as_ref() {
+        return pool.clone();
+    }
+    DESERIALIZATION_POOL
+        .write()
+        .unwrap()
+        .get_or_insert_with(|| {
+            Arc::new(
+                build_deserialization_pool(0).expect("Unable to build the deserialization pool"),
+            )
+        })
+        .clone()
+}
+
+/// Deserialize, in parallel, independent elements (e.g. the proofs and the vks of the certificates
+/// of a block) each one out of the corresponding buffer of `buffers`, as `deserialize_from_buffer_bounded`
+/// does: each buffer is rejected if longer than `max_len`, trailing bytes are rejected, panics are
+/// reported as errors and the strict checks for untrusted data are performed. The result of each deserialization is returned in the same order of `buffers`,
+/// so that a failure doesn't prevent the other elements from being deserialized.
+/// Deserializations are performed on `pool`, which should be built through `build_deserialization_pool()`
+/// to bound the recursion depth as `deserialize_from_buffer_bounded` does, or on a pool shared by
+/// all the callers of this function if None (sized according to the thread budget, see `cctp_init()`).
+/// Available with the `parallel` feature.
+#[cfg(feature = "parallel")]
+pub fn deserialize_many<T>(
+    buffers: &[&[u8]],
+    max_len: usize,
+    semantic_checks: Option<bool>,
+    compressed: Option<bool>,
+    pool: Option<&ThreadPool>,
+) -> Vec<Result<T, SerializationError>>
+where
+    T: CanonicalDeserialize + SemanticallyValid + StrictlyValid + Send,
+{
+    let shared_pool;
+    let pool = match pool {
+        Some(pool) => pool,
+        None => {
+            shared_pool = get_deserialization_pool();
+            &shared_pool
+        }
+    };
+    pool.install(|| {
+        buffers
+            .par_iter()
+            .map(|buffer| {
+                _check_buffer_len(buffer, max_len)?;
+                catch_unwind(|| _deserialize_untrusted_inner(buffer, semantic_checks, compressed))
+                    .map_err(|_| SerializationError::InvalidData)?
+            })
+            .collect()
+    })
+}
+
 /// Serialize to buffer, choosing whether to use compressed representation or not,
 /// depending on the value of `compressed` flag.
 /// `compressed` can be optional, due to some types being uncompressable.
+/// When `compressed` is None, its value is taken from the `SerializationPolicy`.
 pub fn serialize_to_buffer<T: CanonicalSerialize>(
     to_write: &T,
     compressed: Option<bool>,
 ) -> Result<Vec<u8>, SerializationError> {
-    let compressed = compressed.unwrap_or(false);
+    let compressed = compressed.unwrap_or_else(|| get_serialization_policy().compressed);
 
     let mut buffer;
     if compressed {
@@ -110,6 +401,120 @@ pub fn read_from_file<T: CanonicalDeserialize + SemanticallyValid>(
     _deserialize_inner(reader, semantic_checks, compressed)
 }
 
+/// Reader wrapper invoking `callback` with the number of bytes read so far and the total
+/// number of bytes to read, each time new data is pulled from `inner`.
+struct ProgressReader<R: Read, F: FnMut(u64, u64)> {
+    inner: R,
+    bytes_read: u64,
+    total_bytes: u64,
+    callback: F,
+}
+
+impl<R: Read, F: FnMut(u64, u64)> Read for ProgressReader<R, F> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        if read > 0 {
+            self.bytes_read += read as u64;
+            (self.callback)(self.bytes_read, self.total_bytes);
+        }
+        Ok(read)
+    }
+}
+
+/// Same as `read_from_file`, but `progress_callback` is periodically invoked (roughly once
+/// every `DEFAULT_BUF_SIZE` bytes) with the number of bytes processed so far and the total
+/// size of the file, in bytes. Useful to report progress when loading huge artifacts,
+/// like prover keys.
+pub fn read_from_file_with_progress<T, F>(
+    file_path: &Path,
+    semantic_checks: Option<bool>,
+    compressed: Option<bool>,
+    progress_callback: F,
+) -> Result<T, SerializationError>
+where
+    T: CanonicalDeserialize + SemanticallyValid,
+    F: FnMut(u64, u64),
+{
+    let fs = File::open(file_path).map_err(SerializationError::IoError)?;
+    let total_bytes = fs.metadata().map_err(SerializationError::IoError)?.len();
+    let reader = BufReader::with_capacity(
+        DEFAULT_BUF_SIZE,
+        ProgressReader {
+            inner: fs,
+            bytes_read: 0,
+            total_bytes,
+            callback: progress_callback,
+        },
+    );
+
+    _deserialize_inner(reader, semantic_checks, compressed)
+}
+
+/// Handle to a deserialization from file running on a separate thread,
+/// as returned by `read_from_file_in_background`.
+pub struct FileLoaderHandle<T> {
+    bytes_read: Arc<AtomicU64>,
+    total_bytes: u64,
+    handle: JoinHandle<Result<T, SerializationError>>,
+}
+
+impl<T> FileLoaderHandle<T> {
+    /// Return the number of bytes processed so far.
+    pub fn bytes_read(&self) -> u64 {
+        self.bytes_read.load(Ordering::Relaxed)
+    }
+
+    /// Return the total size, in bytes, of the file being loaded.
+    pub fn total_bytes(&self) -> u64 {
+        self.total_bytes
+    }
+
+    /// Return true if the loading procedure has terminated (successfully or not).
+    pub fn is_finished(&self) -> bool {
+        self.handle.is_finished()
+    }
+
+    /// Block until the loading procedure terminates and return its result.
+    pub fn join(self) -> Result<T, SerializationError> {
+        self.handle.join().map_err(|_| {
+            SerializationError::IoError(IoError::new(
+                ErrorKind::Other,
+                "Thread loading the file panicked",
+            ))
+        })?
+    }
+}
+
+/// Same as `read_from_file`, but the deserialization is performed on a separate thread.
+/// The returned handle allows to poll the progress of the operation and to retrieve its result.
+pub fn read_from_file_in_background<T>(
+    file_path: &Path,
+    semantic_checks: Option<bool>,
+    compressed: Option<bool>,
+) -> Result<FileLoaderHandle<T>, SerializationError>
+where
+    T: CanonicalDeserialize + SemanticallyValid + Send + 'static,
+{
+    let total_bytes = std::fs::metadata(file_path)
+        .map_err(SerializationError::IoError)?
+        .len();
+    let bytes_read = Arc::new(AtomicU64::new(0));
+
+    let file_path: PathBuf = file_path.to_path_buf();
+    let thread_bytes_read = bytes_read.clone();
+    let handle = std::thread::spawn(move || {
+        read_from_file_with_progress(&file_path, semantic_checks, compressed, |read, _| {
+            thread_bytes_read.store(read, Ordering::Relaxed)
+        })
+    });
+
+    Ok(FileLoaderHandle {
+        bytes_read,
+        total_bytes,
+        handle,
+    })
+}
+
 /// Serialize to file, choosing whether to use compressed representation or not,
 /// depending on the value of `compressed` flag.
 /// `compressed` can be optional, due to some types being uncompressable.
@@ -118,7 +523,7 @@ pub fn write_to_file<T: CanonicalSerialize>(
     file_path: &Path,
     compressed: Option<bool>,
 ) -> Result<(), SerializationError> {
-    let compressed = compressed.unwrap_or(false);
+    let compressed = compressed.unwrap_or_else(|| get_serialization_policy().compressed);
 
     let fs = File::create(file_path).map_err(SerializationError::IoError)?;
     let mut writer = BufWriter::with_capacity(DEFAULT_BUF_SIZE, fs);
@@ -140,12 +545,44 @@ pub fn is_valid<T: SemanticallyValid>(to_check: &T) -> bool {
 #[cfg(test)]
 mod test {
     use super::*;
-    use crate::type_mapping::{DarlinProof, DarlinVerifierKey};
+    use crate::commitment_tree::{
+        proofs::{CswLeafProof, ScAbsenceProof, ScExistenceProof},
+        CommitmentTree,
+    };
+    use crate::proving_system::{ZendooProof, ZendooVerifierKey};
+    use crate::type_mapping::{DarlinProof, DarlinVerifierKey, FieldElement};
+    use crate::utils::commitment_tree::rand_vec_with_rng;
+    use algebra::UniformRand;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
     use std::{
         io::{Error as IoError, ErrorKind},
         path::Path,
     };
 
+    #[test]
+    fn test_serialization_policy() {
+        // Default policy preserves the historical defaults
+        let default_policy = SerializationPolicy::default();
+        assert!(!default_policy.semantic_checks);
+        assert!(!default_policy.compressed);
+        assert!(default_policy.strict_untrusted);
+
+        // The global policy is not modified here, not to interfere with the other tests
+        let slot = RwLock::new(None);
+        let policy = SerializationPolicy {
+            semantic_checks: true,
+            compressed: true,
+            strict_untrusted: false,
+        };
+        _set_policy_inner(&slot, policy).unwrap();
+        assert_eq!(*slot.read().unwrap(), Some(policy));
+
+        // Same policy can be set again, a different one can't
+        _set_policy_inner(&slot, policy).unwrap();
+        assert!(_set_policy_inner(&slot, default_policy).is_err());
+        assert_eq!(*slot.read().unwrap(), Some(policy));
+    }
+
     #[test]
     fn test_strict_deserialization() {
         let proof_path = Path::new("./test/strict_deser/sample_final_darlin_proof");
@@ -248,4 +685,272 @@ mod test {
         )
         .is_ok());
     }
+
+    #[test]
+    fn test_read_from_file_with_progress() {
+        let vk_path = Path::new("./test/strict_deser/sample_final_darlin_vk");
+        let vk_file_len = std::fs::metadata(vk_path).unwrap().len();
+        let vk = read_from_file::<DarlinVerifierKey>(&vk_path, Some(true), Some(true)).unwrap();
+
+        // Progress must be monotonic and reach the whole file size
+        let mut last_read = 0u64;
+        let vk_with_progress = read_from_file_with_progress::<DarlinVerifierKey, _>(
+            &vk_path,
+            Some(true),
+            Some(true),
+            |read, total| {
+                assert!(read > last_read);
+                assert_eq!(total, vk_file_len);
+                last_read = read;
+            },
+        )
+        .unwrap();
+        assert_eq!(vk, vk_with_progress);
+        assert_eq!(last_read, vk_file_len);
+
+        // Loading in background must give the same result
+        let handle =
+            read_from_file_in_background::<DarlinVerifierKey>(&vk_path, Some(true), Some(true))
+                .unwrap();
+        assert_eq!(handle.total_bytes(), vk_file_len);
+        let vk_in_background = handle.join().unwrap();
+        assert_eq!(vk, vk_in_background);
+
+        // Loading a non existing file must fail immediately
+        assert!(read_from_file_in_background::<DarlinVerifierKey>(
+            Path::new("./test/strict_deser/non_existing_file"),
+            Some(true),
+            Some(true)
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_bounded_deserialization() {
+        let proof_path = Path::new("./test/strict_deser/sample_final_darlin_proof");
+        let vk_path = Path::new("./test/strict_deser/sample_final_darlin_vk");
+        let proof = ZendooProof::Darlin(
+            read_from_file::<DarlinProof>(&proof_path, Some(true), Some(true)).unwrap(),
+        );
+        let vk = ZendooVerifierKey::Darlin(
+            read_from_file::<DarlinVerifierKey>(&vk_path, Some(true), Some(true)).unwrap(),
+        );
+        let proof_bytes = serialize_to_buffer(&proof, Some(true)).unwrap();
+        let vk_bytes = serialize_to_buffer(&vk, Some(true)).unwrap();
+
+        // Valid data within bounds (sample data has been generated with a big segment size,
+        // so it doesn't fit into MAX_ZENDOO_PROOF_SIZE and MAX_ZENDOO_VK_SIZE)
+        assert_eq!(
+            deserialize_from_buffer_bounded::<ZendooProof>(
+                &proof_bytes,
+                proof_bytes.len(),
+                Some(true),
+                Some(true)
+            )
+            .unwrap(),
+            proof
+        );
+        assert_eq!(
+            deserialize_from_buffer_bounded::<ZendooVerifierKey>(
+                &vk_bytes,
+                vk_bytes.len(),
+                Some(true),
+                Some(true)
+            )
+            .unwrap(),
+            vk
+        );
+
+        // Oversized data
+        assert!(deserialize_from_buffer_bounded::<ZendooProof>(
+            &proof_bytes,
+            proof_bytes.len() - 1,
+            Some(true),
+            Some(true)
+        )
+        .is_err());
+
+        // Trailing bytes
+        let mut trailing = proof_bytes.clone();
+        trailing.push(0u8);
+        assert!(deserialize_from_buffer_bounded::<ZendooProof>(
+            &trailing,
+            trailing.len(),
+            Some(true),
+            Some(true)
+        )
+        .is_err());
+
+        // Hostile length prefixes: a valid proving system byte followed by huge lengths
+        let mut hostile = vec![crate::proving_system::DARLIN_PS_BYTE];
+        hostile.extend_from_slice(&[0xFF; 64]);
+        assert!(deserialize_from_buffer_bounded::<ZendooProof>(
+            &hostile,
+            MAX_ZENDOO_PROOF_SIZE,
+            Some(true),
+            Some(true)
+        )
+        .is_err());
+        assert!(deserialize_from_buffer_bounded::<ZendooVerifierKey>(
+            &hostile,
+            MAX_ZENDOO_VK_SIZE,
+            Some(true),
+            Some(true)
+        )
+        .is_err());
+
+        // Truncated data
+        assert!(deserialize_from_buffer_bounded::<ZendooProof>(
+            &proof_bytes[..proof_bytes.len() / 2],
+            proof_bytes.len(),
+            Some(true),
+            Some(true)
+        )
+        .is_err());
+
+        // Random garbage must be rejected without crashing
+        let rng = &mut StdRng::seed_from_u64(42);
+        for _ in 0..100 {
+            let garbage = rand_vec_with_rng(rng.gen_range(100..2000), rng);
+            assert!(deserialize_from_buffer_bounded::<ZendooProof>(
+                &garbage,
+                MAX_ZENDOO_PROOF_SIZE,
+                Some(true),
+                Some(true)
+            )
+            .is_err());
+            assert!(deserialize_from_buffer_bounded::<ZendooVerifierKey>(
+                &garbage,
+                MAX_ZENDOO_VK_SIZE,
+                Some(true),
+                Some(true)
+            )
+            .is_err());
+            assert!(deserialize_canonical_bounded::<ScAbsenceProof>(
+                &garbage,
+                MAX_COMMITMENT_PROOF_SIZE
+            )
+            .is_err());
+            assert!(deserialize_canonical_bounded::<ScExistenceProof>(
+                &garbage,
+                MAX_COMMITMENT_PROOF_SIZE
+            )
+            .is_err());
+            assert!(deserialize_canonical_bounded::<CswLeafProof>(
+                &garbage,
+                MAX_COMMITMENT_PROOF_SIZE
+            )
+            .is_err());
+        }
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn test_deserialize_many() {
+        let proof_path = Path::new("./test/strict_deser/sample_final_darlin_proof");
+        let proof = ZendooProof::Darlin(
+            read_from_file::<DarlinProof>(&proof_path, Some(true), Some(true)).unwrap(),
+        );
+        let proof_bytes = serialize_to_buffer(&proof, Some(true)).unwrap();
+        let mut trailing = proof_bytes.clone();
+        trailing.push(0u8);
+        let garbage = rand_vec_with_rng(1000, &mut StdRng::seed_from_u64(42));
+
+        let buffers = vec![
+            proof_bytes.as_slice(),
+            &proof_bytes[..proof_bytes.len() / 2],
+            proof_bytes.as_slice(),
+            trailing.as_slice(),
+            garbage.as_slice(),
+            proof_bytes.as_slice(),
+        ];
+        let expected_ok = [true, false, true, false, false, true];
+
+        let pool = build_deserialization_pool(2).unwrap();
+        for pool in [None, Some(&pool)].iter() {
+            let results = deserialize_many::<ZendooProof>(
+                &buffers,
+                trailing.len(),
+                Some(true),
+                Some(true),
+                *pool,
+            );
+            assert_eq!(results.len(), buffers.len());
+            for (result, expected_ok) in results.iter().zip(expected_ok.iter()) {
+                assert_eq!(result.is_ok(), *expected_ok);
+                if let Ok(deserialized) = result {
+                    assert_eq!(deserialized, &proof);
+                }
+            }
+
+            // Oversized data is rejected
+            assert!(deserialize_many::<ZendooProof>(
+                &buffers,
+                proof_bytes.len() - 1,
+                Some(true),
+                Some(true),
+                *pool
+            )
+            .iter()
+            .all(|result| result.is_err()));
+        }
+        assert!(
+            deserialize_many::<ZendooProof>(&[], MAX_ZENDOO_PROOF_SIZE, None, None, None)
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_bounded_deserialization_commitment_proofs() {
+        let rng = &mut StdRng::seed_from_u64(42);
+        let mut cmt = CommitmentTree::create();
+        let sc_ids = (0..3).map(|_| FieldElement::rand(rng)).collect::<Vec<_>>();
+        assert!(cmt.add_cert_leaf(&sc_ids[0], &FieldElement::rand(rng)));
+        assert!(cmt.add_csw_leaf(&sc_ids[1], &FieldElement::rand(rng)));
+
+        let existence_proof = cmt.get_sc_existence_proof(&sc_ids[0]).unwrap();
+        let absence_proof = cmt.get_sc_absence_proof(&sc_ids[2]).unwrap();
+        let csw_leaf_proof = cmt.get_csw_leaf_proof(&sc_ids[1], 0).unwrap();
+
+        let existence_proof_bytes = serialize_to_buffer(&existence_proof, None).unwrap();
+        let absence_proof_bytes = serialize_to_buffer(&absence_proof, None).unwrap();
+        let csw_leaf_proof_bytes = serialize_to_buffer(&csw_leaf_proof, None).unwrap();
+
+        assert_eq!(
+            deserialize_canonical_bounded::<ScExistenceProof>(
+                &existence_proof_bytes,
+                MAX_COMMITMENT_PROOF_SIZE
+            )
+            .unwrap(),
+            existence_proof
+        );
+        assert_eq!(
+            deserialize_canonical_bounded::<ScAbsenceProof>(
+                &absence_proof_bytes,
+                MAX_COMMITMENT_PROOF_SIZE
+            )
+            .unwrap(),
+            absence_proof
+        );
+        assert_eq!(
+            deserialize_canonical_bounded::<CswLeafProof>(
+                &csw_leaf_proof_bytes,
+                MAX_COMMITMENT_PROOF_SIZE
+            )
+            .unwrap(),
+            csw_leaf_proof
+        );
+
+        // Oversized and truncated data
+        assert!(deserialize_canonical_bounded::<ScAbsenceProof>(
+            &absence_proof_bytes,
+            absence_proof_bytes.len() - 1
+        )
+        .is_err());
+        assert!(deserialize_canonical_bounded::<ScAbsenceProof>(
+            &absence_proof_bytes[..absence_proof_bytes.len() - 1],
+            MAX_COMMITMENT_PROOF_SIZE
+        )
+        .is_err());
+    }
 }